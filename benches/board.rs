@@ -0,0 +1,317 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use swap_and_match_engine::{Board, BoardState, MatchPattern, PatternAnchor, Piece, Pos, ALL_DIRECTIONS};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const TYPES: [char; 5] = ['r', 'g', 'b', 'y', 'p'];
+const SIZES: [u8; 2] = [8, 64];
+
+/// Counts every allocation made through the system allocator, so that a
+/// benchmark can check that its hot path does not allocate.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Counts the allocations made while running an action.
+fn allocations_during<T>(action: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let result = action();
+    (result, ALLOCATIONS.load(Ordering::Relaxed) - before)
+}
+
+/// Creates horizontal and vertical three-in-a-row patterns for every type.
+fn patterns() -> Vec<MatchPattern> {
+    let mut patterns = Vec::new();
+
+    for &piece_type in TYPES.iter() {
+        let horizontal: HashSet<Pos> = (0..3).map(|x| Pos::new(x, 0)).collect();
+        let vertical: HashSet<Pos> = (0..3).map(|y| Pos::new(0, y)).collect();
+        patterns.push(MatchPattern::new(piece_type, horizontal, 1));
+        patterns.push(MatchPattern::new(piece_type, vertical, 1));
+    }
+
+    patterns
+}
+
+/// Gets a piece type for a position so that no three pieces in a row or column
+/// share a type.
+fn type_without_matches(x: u8, y: u8) -> char {
+    TYPES[(usize::from(x) + 2 * usize::from(y)) % TYPES.len()]
+}
+
+/// Creates a board filled with regular pieces that contains no matches and has
+/// no positions marked for a match check.
+fn full_board(size: u8) -> Board {
+    let mut board = Board::new(BoardState::new(size, size), patterns(), Vec::new());
+
+    for y in 0..size {
+        for x in 0..size {
+            board.set_piece(Pos::new(x, y), Piece::Regular(type_without_matches(x, y), ALL_DIRECTIONS));
+        }
+    }
+
+    while board.next_match().is_some() {}
+
+    board
+}
+
+/// Creates a board filled with pieces of the same type, so every position is part of a match.
+fn dense_board(size: u8) -> Board {
+    let mut board = Board::new(BoardState::new(size, size), patterns(), Vec::new());
+
+    for y in 0..size {
+        for x in 0..size {
+            board.set_piece(Pos::new(x, y), Piece::Regular(TYPES[0], ALL_DIRECTIONS));
+        }
+    }
+
+    board
+}
+
+/// Creates a board filled with pieces of the same type whose patterns only match
+/// in the top row, so most pattern variants fail their anchor check.
+fn anchored_board(size: u8) -> Board {
+    let patterns = patterns().into_iter()
+        .map(|pattern| pattern.with_anchor(PatternAnchor::Row(size - 1)))
+        .collect();
+    let mut board = Board::new(BoardState::new(size, size), patterns, Vec::new());
+
+    for y in 0..size {
+        for x in 0..size {
+            board.set_piece(Pos::new(x, y), Piece::Regular(TYPES[0], ALL_DIRECTIONS));
+        }
+    }
+
+    board
+}
+
+/// Creates a board filled with pieces of many types, with horizontal and vertical
+/// three-in-a-row patterns for every type, and no positions marked for a match check.
+fn many_types_board(size: u8, type_count: u32) -> Board {
+    let types: Vec<char> = (0..type_count).map(|index| std::char::from_u32(0x100 + index).unwrap()).collect();
+
+    let mut patterns = Vec::new();
+    for &piece_type in types.iter() {
+        let horizontal: HashSet<Pos> = (0..3).map(|x| Pos::new(x, 0)).collect();
+        let vertical: HashSet<Pos> = (0..3).map(|y| Pos::new(0, y)).collect();
+        patterns.push(MatchPattern::new(piece_type, horizontal, 1));
+        patterns.push(MatchPattern::new(piece_type, vertical, 1));
+    }
+
+    let mut board = Board::new(BoardState::new(size, size), patterns, Vec::new());
+    for y in 0..size {
+        for x in 0..size {
+            let index = (usize::from(x) + 3 * usize::from(y)) % types.len();
+            board.set_piece(Pos::new(x, y), Piece::Regular(types[index], ALL_DIRECTIONS));
+        }
+    }
+
+    while board.next_match().is_some() {}
+
+    board
+}
+
+/// Creates a board whose bottom half is empty and whose top half is full of pieces.
+fn half_empty_board(size: u8) -> Board {
+    let mut board = full_board(size);
+
+    for y in 0..size / 2 {
+        for x in 0..size {
+            board.set_piece(Pos::new(x, y), Piece::Empty);
+        }
+    }
+
+    while board.next_match().is_some() {}
+
+    board
+}
+
+/// Clears every match on the board, trickles the remaining pieces, and refills
+/// the board from the top until no matches remain. Returns the number of matches.
+fn resolve_cascade(board: &mut Board, size: u8, next_type: &mut usize) -> usize {
+    let mut matches = 0;
+
+    loop {
+        let mut cleared = HashSet::new();
+        while let Some(next_match) = board.next_match() {
+            cleared.extend(next_match.board_pos().iter().copied());
+            matches += 1;
+        }
+
+        if cleared.is_empty() {
+            return matches;
+        }
+
+        for &pos in cleared.iter() {
+            board.set_piece(pos, Piece::Empty);
+        }
+        board.trickle();
+
+        for x in 0..size {
+            let top = Pos::new(x, size - 1);
+            while board.piece(top) == Piece::Empty {
+                *next_type = (*next_type + 1) % TYPES.len();
+                board.add_and_trickle(top, Piece::Regular(TYPES[*next_type], ALL_DIRECTIONS));
+            }
+        }
+    }
+}
+
+fn swap_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("swap_throughput");
+
+    for &size in SIZES.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}x{}", size, size)), &size, |b, &size| {
+            let mut board = full_board(size);
+            b.iter(|| {
+                for y in 0..size {
+                    for x in 0..size - 1 {
+                        black_box(board.swap_pieces(Pos::new(x, y), Pos::new(x + 1, y)));
+                    }
+                }
+                while board.next_match().is_some() {}
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn match_detection_dense(c: &mut Criterion) {
+    let mut group = c.benchmark_group("match_detection_dense");
+
+    for &size in SIZES.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}x{}", size, size)), &size, |b, &size| {
+            b.iter_batched(
+                || dense_board(size),
+                |mut board| {
+                    let mut matches = 0;
+                    while board.next_match().is_some() {
+                        matches += 1;
+                    }
+                    black_box(matches)
+                },
+                BatchSize::SmallInput
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn match_detection_anchored(c: &mut Criterion) {
+    let mut group = c.benchmark_group("match_detection_anchored");
+
+    for &size in SIZES.iter() {
+        // Every variant fails its anchor check below the top row, so swapping there should not allocate
+        let mut board = anchored_board(size);
+        while board.next_match().is_some() {}
+        let (is_matched, allocations) = allocations_during(|| {
+            assert!(board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+            board.next_match().is_some()
+        });
+        assert!(!is_matched);
+        assert_eq!(0, allocations, "Swap without a match allocated on {}x{} board", size, size);
+
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}x{}", size, size)), &size, |b, &size| {
+            b.iter_batched(
+                || anchored_board(size),
+                |mut board| {
+                    let mut matches = 0;
+                    while board.next_match().is_some() {
+                        matches += 1;
+                    }
+                    black_box(matches)
+                },
+                BatchSize::SmallInput
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn many_types(c: &mut Criterion) {
+    let mut group = c.benchmark_group("many_types");
+
+    for &type_count in [8, 32, 128].iter() {
+        group.bench_with_input(BenchmarkId::new("swap_and_check", type_count), &type_count, |b, &type_count| {
+            let mut board = many_types_board(64, type_count);
+            b.iter(|| {
+                for y in 0..64 {
+                    for x in 0..63 {
+                        black_box(board.swap_pieces(Pos::new(x, y), Pos::new(x + 1, y)));
+                    }
+                }
+                while board.next_match().is_some() {}
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("scan_all_matches", type_count), &type_count, |b, &type_count| {
+            let board = many_types_board(64, type_count);
+            b.iter(|| black_box(board.scan_all_matches().len()))
+        });
+    }
+
+    group.finish();
+}
+
+fn trickle_full_columns(c: &mut Criterion) {
+    let mut group = c.benchmark_group("trickle_full_columns");
+
+    for &size in SIZES.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}x{}", size, size)), &size, |b, &size| {
+            b.iter_batched(
+                || half_empty_board(size),
+                |mut board| black_box(board.trickle()),
+                BatchSize::SmallInput
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn cascade_resolution(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cascade_resolution");
+
+    for &size in SIZES.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}x{}", size, size)), &size, |b, &size| {
+            b.iter_batched(
+                || dense_board(size),
+                |mut board| {
+                    let mut next_type = 0;
+                    black_box(resolve_cascade(&mut board, size, &mut next_type))
+                },
+                BatchSize::SmallInput
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, swap_throughput, match_detection_dense, match_detection_anchored, many_types,
+                 trickle_full_columns, cascade_resolution);
+criterion_main!(benches);
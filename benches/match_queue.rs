@@ -0,0 +1,67 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use swap_and_match_engine::{Board, BoardState, MatchOrder, MatchPattern, Piece, Pos, ALL_DIRECTIONS};
+
+use std::collections::HashSet;
+
+const TYPES: [char; 4] = ['r', 'g', 'b', 'y'];
+
+/// Creates a full board whose bottom half is empty, so that trickling moves
+/// every piece in the top half and marks its positions several times.
+fn cascade_board(size: u8, match_order: MatchOrder) -> Board {
+    let mut patterns = Vec::new();
+    for &piece_type in TYPES.iter() {
+        let horizontal: HashSet<Pos> = (0..3).map(|x| Pos::new(x, 0)).collect();
+        let vertical: HashSet<Pos> = (0..3).map(|y| Pos::new(0, y)).collect();
+        patterns.push(MatchPattern::new(piece_type, horizontal, 1));
+        patterns.push(MatchPattern::new(piece_type, vertical, 1));
+    }
+
+    let mut board = Board::new(BoardState::new(size, size), patterns, Vec::new());
+    board.set_match_order(match_order);
+
+    for y in 0..size {
+        for x in 0..size {
+            let piece = match y < size / 2 {
+                true => Piece::Empty,
+                false => Piece::Regular(TYPES[usize::from((x / 2 + y) % 4)], ALL_DIRECTIONS)
+            };
+            board.set_piece(Pos::new(x, y), piece);
+        }
+    }
+
+    while board.next_match().is_some() {}
+
+    board
+}
+
+fn trickle_then_drain_matches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("trickle_then_drain_matches");
+
+    for &size in [8u8, 64].iter() {
+        for &match_order in [MatchOrder::Fifo, MatchOrder::ByRow, MatchOrder::ByRank].iter() {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?}", match_order), format!("{}x{}", size, size)),
+                &size,
+                |b, &size| b.iter_batched(
+                    || cascade_board(size, match_order),
+                    |mut board| {
+                        board.trickle();
+
+                        let mut matches = 0;
+                        while board.next_match().is_some() {
+                            matches += 1;
+                        }
+
+                        black_box(matches)
+                    },
+                    BatchSize::SmallInput
+                )
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, trickle_then_drain_matches);
+criterion_main!(benches);
@@ -0,0 +1,179 @@
+//! A headless playground for trying out levels from the terminal.
+//!
+//! Run it with a level file in the format read by `Level::from_json()`:
+//!
+//! ```text
+//! cargo run --example cli --features json -- level.json [seed]
+//! ```
+//!
+//! The board is drawn with the top row first, and each command is read from
+//! standard input:
+//!
+//! * `swap x1 y1 x2 y2` - swaps two pieces and resolves the cascade
+//! * `show` - draws the board again
+//! * `goals` - prints the progress of each goal
+//! * `help` - lists the commands
+//! * `quit` - exits
+
+use swap_and_match_engine::{Board, BoardEvent, BoardObserver, Goals, Level, Piece, Pos, SessionLimit, Spawner,
+                            WeightedSpawner};
+
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process;
+
+const HELP: &str = "commands: swap x1 y1 x2 y2 | show | goals | help | quit";
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!("usage: {} <level.json> [seed]", args[0]);
+        process::exit(2);
+    }
+
+    let level = fs::read_to_string(&args[1])
+        .map_err(|err| err.to_string())
+        .and_then(|text| Level::from_json(&text).map_err(|err| err.to_string()))
+        .unwrap_or_else(|err| {
+            eprintln!("could not load {}: {}", args[1], err);
+            process::exit(1);
+        });
+    let seed = match args.get(2).map(|seed| seed.parse::<u64>()) {
+        Some(Ok(seed)) => seed,
+        Some(Err(_)) => {
+            eprintln!("seed must be a non-negative integer: {}", args[2]);
+            process::exit(2);
+        },
+        None => 0
+    };
+
+    let mut board = level.board();
+    board.set_seed(seed);
+    let mut spawner = WeightedSpawner::new(level.spawn_weights().to_vec(), seed);
+    let mut goals = Goals::new(level.goals().to_vec());
+    let mut moves_left = match level.limit() {
+        SessionLimit::Moves(moves) => Some(moves),
+        SessionLimit::Turns(_) => None
+    };
+
+    println!("{}", render(&board));
+    println!("{}", HELP);
+
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().expect("Could not write to standard output");
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).expect("Could not read from standard input") == 0 {
+            break;
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => {},
+            ["swap", coords @ ..] => match parse_swap(coords) {
+                Some((first, second)) => if swap(&mut board, &mut spawner, &mut goals, first, second, &mut moves_left) {
+                    println!("{}", render(&board));
+                },
+                None => println!("usage: swap x1 y1 x2 y2")
+            },
+            ["show"] => println!("{}", render(&board)),
+            ["goals"] => print_goals(&goals),
+            ["help"] => println!("{}", HELP),
+            ["quit"] | ["exit"] => break,
+            _ => println!("unknown command. {}", HELP)
+        }
+    }
+}
+
+/// Parses the four coordinates of a swap command.
+fn parse_swap(coords: &[&str]) -> Option<(Pos, Pos)> {
+    let values = coords.iter().map(|coord| coord.parse::<u8>().ok()).collect::<Option<Vec<u8>>>()?;
+    match values.as_slice() {
+        &[x1, y1, x2, y2] => Some((Pos::new(x1, y1), Pos::new(x2, y2))),
+        _ => None
+    }
+}
+
+/// Swaps two pieces, resolves the cascade, and prints what happened.
+/// Returns whether the pieces were swapped.
+fn swap(board: &mut Board, spawner: &mut dyn Spawner, goals: &mut Goals, first: Pos, second: Pos,
+        moves_left: &mut Option<u32>) -> bool {
+    if *moves_left == Some(0) {
+        println!("no moves left");
+        return false;
+    }
+
+    if let Err(err) = board.try_swap_pieces(first, second) {
+        println!("swap rejected: {}", err);
+        return false;
+    }
+
+    let matches = board.resolve_cascade(spawner, &mut |event: &BoardEvent| {
+        goals.on_event(event);
+        print_event(event);
+    });
+    println!("{} match(es) cleared", matches);
+
+    if let Some(moves) = moves_left.as_mut() {
+        *moves -= 1;
+        println!("{} move(s) left", moves);
+    }
+    if goals.are_all_complete() {
+        println!("all goals complete!");
+    }
+
+    true
+}
+
+/// Prints the events that are useful when following a cascade. Moved and
+/// spawned pieces are left out because they are visible on the next render.
+fn print_event(event: &BoardEvent) {
+    match event {
+        BoardEvent::Matched { piece_type, rank, changed_pos, positions, chain_index, .. } => println!(
+            "  chain {}: matched {} '{}' (rank {}) at {}",
+            chain_index, positions.len(), piece_type, rank, changed_pos
+        ),
+        BoardEvent::Delivered { pos, piece } => println!("  delivered {} at {}", piece, pos),
+        BoardEvent::ObstacleDamaged { pos, .. } | BoardEvent::WallDamaged { pos, .. } => {
+            println!("  damaged obstacle at {}", pos)
+        },
+        BoardEvent::ObstacleDestroyed { pos, .. } | BoardEvent::WallDestroyed { pos, .. } => {
+            println!("  destroyed obstacle at {}", pos)
+        },
+        _ => {}
+    }
+}
+
+/// Prints the progress of each goal.
+fn print_goals(goals: &Goals) {
+    for (index, goal) in goals.goals().iter().enumerate() {
+        println!("  {:?}: {}/{}", goal, goals.progress(index), goal.target());
+    }
+}
+
+/// Draws the board with the top row first, using the same characters as
+/// level layouts, with the row and column numbers along the edges.
+fn render(board: &Board) -> String {
+    let grid = board.export_grid();
+    let mut text = String::new();
+
+    for (y, row) in grid.iter().enumerate().rev() {
+        text.push_str(&format!("{:>3} ", y));
+        for cell in row {
+            text.push(match cell.piece() {
+                Piece::Regular(piece_type, _) => piece_type,
+                Piece::Empty => '.',
+                Piece::Wall => '#'
+            });
+        }
+        text.push('\n');
+    }
+
+    let width = grid.first().map_or(0, |row| row.len());
+    text.push_str("    ");
+    text.extend((0..width).map(|x| std::char::from_digit((x % 10) as u32, 10).unwrap()));
+    text
+}
@@ -0,0 +1,434 @@
+//! A [Godot](https://godotengine.org) class, built with
+//! [gdext](https://godot-rust.github.io), that runs a swap-and-match board
+//! inside a Godot game. Load this crate's library as a GDExtension with the
+//! entry symbol `swap_and_match_init`, create a [SwapAndMatchBoard] resource,
+//! call `configure()` on it, and call `swap()` whenever the player swaps two
+//! pieces. The matches, moves, spawns, and clears in the cascade that follows
+//! are emitted as signals so that scripts can animate and score them.
+//!
+//! Positions use the board's coordinates, so `(0, 0)` is the bottom-left
+//! space. Pieces are passed as one-character strings: the piece's type for
+//! a regular piece, `" "` for an empty space, and `"#"` for a wall.
+//!
+//! gdext needs a much newer Rust compiler than the engine supports, so the
+//! bindings live in this separate crate instead of behind an engine feature.
+
+use swap_and_match_engine::{Board, BoardEvent, BoardState, MatchPattern, PatternParseError, Piece, Pos,
+                            TypeId, WeightedSpawner, ALL_DIRECTIONS};
+
+use std::convert::TryFrom;
+
+use godot::builtin::{Array, GString, PackedInt64Array, PackedStringArray, Vector2i};
+use godot::classes::{IResource, Resource};
+use godot::init::{gdextension, ExtensionLibrary};
+use godot::obj::{Base, WithUserSignals};
+use godot::register::{godot_api, GodotClass};
+
+struct SwapAndMatchExtension;
+
+#[gdextension(entry_symbol = swap_and_match_init)]
+unsafe impl ExtensionLibrary for SwapAndMatchExtension {}
+
+/// A board that Godot scripts can swap pieces on, with the spawner that fills
+/// it during cascades. The board is empty until `configure()` is called.
+#[derive(GodotClass)]
+#[class(base = Resource)]
+pub struct SwapAndMatchBoard {
+    board: Option<Board>,
+    spawner: Option<WeightedSpawner>,
+    base: Base<Resource>
+}
+
+#[godot_api]
+impl IResource for SwapAndMatchBoard {
+
+    fn init(base: Base<Resource>) -> SwapAndMatchBoard {
+        SwapAndMatchBoard { board: None, spawner: None, base }
+    }
+
+}
+
+#[godot_api]
+impl SwapAndMatchBoard {
+
+    /// Emitted when a match is found during a cascade.
+    #[signal]
+    fn matched(piece_type: GString, positions: Array<Vector2i>, chain_index: i64);
+
+    /// Emitted when a piece is removed because it was part of a match.
+    #[signal]
+    fn piece_cleared(pos: Vector2i, piece_type: GString);
+
+    /// Emitted when a piece falls from one position to another.
+    #[signal]
+    fn piece_moved(from: Vector2i, to: Vector2i);
+
+    /// Emitted when a new piece is placed to fill an empty space.
+    #[signal]
+    fn piece_spawned(pos: Vector2i, piece_type: GString);
+
+    /// Replaces the board with an empty one. Returns false and leaves the
+    /// current board unchanged if the size is not between 0 and 255, a
+    /// pattern cannot be parsed, or there are no types to spawn.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - the width of the new board
+    /// * `height` - the height of the new board
+    /// * `patterns` - the match patterns, drawn as text in the format that
+    ///   [MatchPattern] parses
+    /// * `spawn_types` - the types of the pieces that fill the board, each
+    ///   spawned equally often
+    /// * `seed` - the seed for the spawner's random choices
+    #[func]
+    fn configure(&mut self, width: i64, height: i64, patterns: PackedStringArray,
+                 spawn_types: GString, seed: i64) -> bool {
+        let (width, height) = match (u8::try_from(width), u8::try_from(height)) {
+            (Ok(width), Ok(height)) => (width, height),
+            _ => return false
+        };
+
+        let pattern_texts: Vec<String> = patterns.as_slice().iter().map(GString::to_string).collect();
+        let patterns = match parse_patterns(&pattern_texts) {
+            Ok(patterns) => patterns,
+            Err(_) => return false
+        };
+
+        let weights = spawn_weights(&spawn_types.to_string());
+        if weights.is_empty() {
+            return false;
+        }
+
+        self.board = Some(Board::new(BoardState::new(width, height), patterns, Vec::new()));
+        self.spawner = Some(WeightedSpawner::new(weights, seed as u64));
+        true
+    }
+
+    /// Gets the width of the board, or zero if it is not configured.
+    #[func]
+    fn width(&self) -> i64 {
+        self.board.as_ref().map_or(0, |board| i64::from(board.view().width()))
+    }
+
+    /// Gets the height of the board, or zero if it is not configured.
+    #[func]
+    fn height(&self) -> i64 {
+        self.board.as_ref().map_or(0, |board| i64::from(board.view().height()))
+    }
+
+    /// Gets the piece at a position, or an empty string if the position is
+    /// outside the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to get
+    #[func]
+    fn get_piece(&self, pos: Vector2i) -> GString {
+        match self.board.as_ref().and_then(|board| board_pos(board, pos).map(|pos| board.piece(pos))) {
+            Some(piece) => GString::from(&piece.to_string()),
+            None => GString::new()
+        }
+    }
+
+    /// Places a piece on the board without triggering matches, such as to set
+    /// up a level. Returns false and leaves the board unchanged if the
+    /// position is outside the board, the piece is not one character, or the
+    /// board does not allow the piece there.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to place the piece at
+    /// * `piece` - the piece to place
+    #[func]
+    fn set_piece(&mut self, pos: Vector2i, piece: GString) -> bool {
+        let board = match self.board.as_mut() {
+            Some(board) => board,
+            None => return false
+        };
+
+        place_piece(board, pos, &piece.to_string())
+    }
+
+    /// Gets every space on the board in one flat, row-major list, starting
+    /// with the bottom row, in the [encoded](swap_and_match_engine::CellExport::encoded)
+    /// format.
+    #[func]
+    fn cells(&self) -> PackedInt64Array {
+        self.board.as_ref()
+            .map(|board| board.export_flat().iter().map(|cell| i64::from(cell.encoded())).collect())
+            .unwrap_or_default()
+    }
+
+    /// Swaps two pieces and resolves the cascade that follows, emitting a
+    /// signal for each change. Returns false and leaves the board unchanged
+    /// if either position is outside the board or the swap is not allowed.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the position of the first piece to swap
+    /// * `second` - the position of the second piece to swap
+    #[func]
+    fn swap(&mut self, first: Vector2i, second: Vector2i) -> bool {
+        let (board, spawner) = match (self.board.as_mut(), self.spawner.as_mut()) {
+            (Some(board), Some(spawner)) => (board, spawner),
+            _ => return false
+        };
+
+        let (first, second) = match (board_pos(board, first), board_pos(board, second)) {
+            (Some(first), Some(second)) => (first, second),
+            _ => return false
+        };
+
+        if board.try_swap_pieces(first, second).is_err() {
+            return false;
+        }
+
+        let mut events = Vec::new();
+        board.resolve_cascade(spawner, &mut |event: &BoardEvent| events.push(event.clone()));
+
+        for event in events {
+            self.emit_event(event);
+        }
+
+        true
+    }
+
+    /// Emits the signal for an event, if it has one.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - the event that happened on the board
+    fn emit_event(&mut self, event: BoardEvent) {
+        match event {
+            BoardEvent::Matched { piece_type, positions, chain_index, .. } => {
+                let positions: Array<Vector2i> = positions.iter().map(|&pos| vector_from_pos(pos)).collect();
+                self.signals().matched().emit(&GString::from(&piece_type.to_string()), &positions,
+                                              chain_index as i64);
+            },
+            BoardEvent::Cleared { pos, piece } => {
+                self.signals().piece_cleared().emit(vector_from_pos(pos), &GString::from(&piece.to_string()));
+            },
+            BoardEvent::Moved { from, to } => {
+                self.signals().piece_moved().emit(vector_from_pos(from), vector_from_pos(to));
+            },
+            BoardEvent::Spawned { pos, piece } => {
+                self.signals().piece_spawned().emit(vector_from_pos(pos), &GString::from(&piece.to_string()));
+            },
+            _ => {}
+        }
+    }
+
+}
+
+/// Places a piece without marking its space for a match check. Returns false
+/// and leaves the board unchanged if the position is outside the board, the
+/// text is not one character, or the piece would be one piece type too many.
+///
+/// # Arguments
+///
+/// * `board` - the board to place the piece on
+/// * `pos` - the position to place the piece at
+/// * `text` - the piece's character
+fn place_piece(board: &mut Board, pos: Vector2i, text: &str) -> bool {
+    let (pos, piece) = match (board_pos(board, pos), piece_from_text(text)) {
+        (Some(pos), Some(piece)) => (pos, piece),
+        _ => return false
+    };
+
+    if let Piece::Regular(piece_type, _) = piece {
+        let is_new_type = board.type_id(piece_type).is_none();
+        if is_new_type && board.piece_types().len() >= usize::from(TypeId::MAX) {
+            return false;
+        }
+    }
+
+    board.set_piece_silent(pos, piece);
+    true
+}
+
+/// Parses match patterns drawn as text.
+///
+/// # Arguments
+///
+/// * `texts` - the patterns to parse
+fn parse_patterns(texts: &[String]) -> Result<Vec<MatchPattern>, PatternParseError> {
+    texts.iter().map(|text| text.parse()).collect()
+}
+
+/// Gives every piece type in a string the same spawn weight.
+///
+/// # Arguments
+///
+/// * `types` - the types to spawn, one character each
+fn spawn_weights(types: &str) -> Vec<(char, u32)> {
+    types.chars().map(|piece_type| (piece_type, 1)).collect()
+}
+
+/// Converts a one-character string to a piece, or returns None if the string
+/// is not exactly one character. Regular pieces can move in every direction.
+///
+/// # Arguments
+///
+/// * `text` - the piece's character
+fn piece_from_text(text: &str) -> Option<Piece> {
+    let mut chars = text.chars();
+    let piece = match (chars.next(), chars.next()) {
+        (Some(' '), None) => Piece::Empty,
+        (Some('#'), None) => Piece::Wall,
+        (Some(piece_type), None) => Piece::Regular(piece_type, ALL_DIRECTIONS),
+        _ => return None
+    };
+
+    Some(piece)
+}
+
+/// Converts a Godot vector to a position, or returns None if the position is
+/// outside the board.
+///
+/// # Arguments
+///
+/// * `board` - the board the position should be on
+/// * `pos` - the vector to convert
+fn board_pos(board: &Board, pos: Vector2i) -> Option<Pos> {
+    let pos = pos_from_vector(pos)?;
+    if pos.x() < board.view().width() && pos.y() < board.view().height() {
+        Some(pos)
+    } else {
+        None
+    }
+}
+
+/// Converts a Godot vector to a position, or returns None if either coordinate
+/// does not fit in a position.
+///
+/// # Arguments
+///
+/// * `pos` - the vector to convert
+fn pos_from_vector(pos: Vector2i) -> Option<Pos> {
+    match (u8::try_from(pos.x), u8::try_from(pos.y)) {
+        (Ok(x), Ok(y)) => Some(Pos::new(x, y)),
+        _ => None
+    }
+}
+
+/// Converts a position to a Godot vector.
+///
+/// # Arguments
+///
+/// * `pos` - the position to convert
+fn vector_from_pos(pos: Pos) -> Vector2i {
+    Vector2i::new(i32::from(pos.x()), i32::from(pos.y()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_patterns, piece_from_text, place_piece, pos_from_vector, spawn_weights, vector_from_pos};
+    use godot::builtin::Vector2i;
+    use swap_and_match_engine::{Board, BoardState, Piece, Pos, ALL_DIRECTIONS};
+
+    fn board_with_pair() -> Board {
+        let mut board = Board::new(BoardState::new(3, 1), vec!["aaa".parse().unwrap()], Vec::new());
+        board.set_piece_silent(Pos::new(0, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        board.set_piece_silent(Pos::new(1, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        board
+    }
+
+    #[test]
+    fn place_piece_completes_pattern_no_match_queued() {
+        let mut board = board_with_pair();
+
+        assert!(place_piece(&mut board, Vector2i::new(2, 0), "a"));
+
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(2, 0)));
+        assert_eq!(0, board.pending_match_checks().count());
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    fn place_piece_outside_board_false() {
+        let mut board = board_with_pair();
+
+        assert!(!place_piece(&mut board, Vector2i::new(3, 0), "a"));
+
+        assert_eq!(Piece::Wall, board.piece(Pos::new(2, 0)));
+    }
+
+    #[test]
+    fn place_piece_multiple_chars_false() {
+        let mut board = board_with_pair();
+
+        assert!(!place_piece(&mut board, Vector2i::new(2, 0), "aa"));
+
+        assert_eq!(Piece::Wall, board.piece(Pos::new(2, 0)));
+    }
+
+    #[test]
+    fn piece_from_text_regular_movable_in_all_directions() {
+        assert_eq!(Some(Piece::Regular('f', ALL_DIRECTIONS)), piece_from_text("f"));
+    }
+
+    #[test]
+    fn piece_from_text_space_is_empty() {
+        assert_eq!(Some(Piece::Empty), piece_from_text(" "));
+    }
+
+    #[test]
+    fn piece_from_text_hash_is_wall() {
+        assert_eq!(Some(Piece::Wall), piece_from_text("#"));
+    }
+
+    #[test]
+    fn piece_from_text_empty_string_none() {
+        assert_eq!(None, piece_from_text(""));
+    }
+
+    #[test]
+    fn piece_from_text_multiple_chars_none() {
+        assert_eq!(None, piece_from_text("fg"));
+    }
+
+    #[test]
+    fn piece_from_text_display_round_trip() {
+        for piece in [Piece::Regular('f', ALL_DIRECTIONS), Piece::Empty, Piece::Wall] {
+            assert_eq!(Some(piece), piece_from_text(&piece.to_string()));
+        }
+    }
+
+    #[test]
+    fn pos_from_vector_in_range_converted() {
+        assert_eq!(Some(Pos::new(3, 255)), pos_from_vector(Vector2i::new(3, 255)));
+    }
+
+    #[test]
+    fn pos_from_vector_negative_none() {
+        assert_eq!(None, pos_from_vector(Vector2i::new(-1, 0)));
+    }
+
+    #[test]
+    fn pos_from_vector_too_large_none() {
+        assert_eq!(None, pos_from_vector(Vector2i::new(0, 256)));
+    }
+
+    #[test]
+    fn vector_from_pos_round_trip() {
+        assert_eq!(Some(Pos::new(4, 7)), pos_from_vector(vector_from_pos(Pos::new(4, 7))));
+    }
+
+    #[test]
+    fn spawn_weights_each_type_weighted_equally() {
+        assert_eq!(vec![('f', 1), ('g', 1)], spawn_weights("fg"));
+    }
+
+    #[test]
+    fn parse_patterns_valid_patterns_parsed() {
+        let patterns = parse_patterns(&["fff".to_string(), "g\ng\ng".to_string()]).unwrap();
+        assert_eq!(2, patterns.len());
+        assert_eq!('f', patterns[0].piece_type());
+        assert_eq!('g', patterns[1].piece_type());
+    }
+
+    #[test]
+    fn parse_patterns_invalid_pattern_error() {
+        assert!(parse_patterns(&["fff".to_string(), String::new()]).is_err());
+    }
+}
@@ -0,0 +1,452 @@
+//! Telemetry for live games. Attach an [AnalyticsSink] to a board with
+//! [Board::set_analytics_sink()], and every swap and every [BoardEvent] the
+//! board reports is streamed to the sink as an [AnalyticsRecord], without
+//! wrapping the calls that cause them. Records are serializable with serde,
+//! so the sink can write them straight to a log or a network connection.
+//! The `analytics` feature turns on the `serde` feature for this reason.
+//!
+//! Records from a [SwapTransaction](crate::SwapTransaction), a
+//! [transaction()](Board::transaction), or a
+//! [move_piece_along()](Board::move_piece_along) are held until the change is
+//! kept, so a change that is rolled back never reaches the sink.
+
+use crate::board::Board;
+use crate::events::{BoardEvent, BoardObserver};
+use crate::position::Pos;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Something that happened on a board, as reported to an [AnalyticsSink].
+#[derive(Clone, Eq, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub enum AnalyticsEvent {
+
+    /// Two pieces were swapped, which starts a new turn.
+    Swapped {
+        first: Pos,
+        second: Pos
+    },
+
+    /// A swap was asked for but not allowed.
+    SwapRejected {
+        first: Pos,
+        second: Pos
+    },
+
+    /// An event was sent to an observer while matches were resolved or while
+    /// the board was ticked.
+    Board(BoardEvent)
+
+}
+
+/// One event in the analytics stream, with where it happened in the game.
+#[derive(Clone, Eq, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct AnalyticsRecord {
+    sequence: u64,
+    turn: u64,
+    timestamp_ms: u64,
+    event: AnalyticsEvent
+}
+
+impl AnalyticsRecord {
+
+    /// Gets the number of records sent to the sink before this one.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Gets the number of swaps made since the sink was attached, including
+    /// the swap this record reports, if any. Every event caused by a swap's
+    /// cascade has the same turn as the swap.
+    pub fn turn(&self) -> u64 {
+        self.turn
+    }
+
+    /// Gets the time the event happened, in milliseconds since the Unix epoch.
+    pub fn timestamp_ms(&self) -> u64 {
+        self.timestamp_ms
+    }
+
+    /// Gets the event that happened.
+    pub fn event(&self) -> &AnalyticsEvent {
+        &self.event
+    }
+
+}
+
+/// Receives every [AnalyticsRecord] from a board as it happens. Any closure
+/// that accepts a record is a sink.
+pub trait AnalyticsSink: Send + Sync {
+
+    /// Handles a record that was just created.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - the record to handle
+    fn record(&mut self, record: &AnalyticsRecord);
+
+}
+
+impl<F: FnMut(&AnalyticsRecord) + Send + Sync> AnalyticsSink for F {
+    fn record(&mut self, record: &AnalyticsRecord) {
+        self(record)
+    }
+}
+
+/// A sink attached to a board and the position it has reached in the stream.
+/// Records are held back instead of sent while a change that can still be
+/// undone is in progress.
+pub(crate) struct AnalyticsStream {
+    sink: Box<dyn AnalyticsSink>,
+    sequence: u64,
+    turn: u64,
+    held: Vec<AnalyticsRecord>,
+    hold_depth: usize
+}
+
+/// Where an [AnalyticsStream] was when it started holding records, so that
+/// the records held since then can be dropped if the change is undone.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) struct AnalyticsCheckpoint {
+    held_count: usize,
+    sequence: u64,
+    turn: u64
+}
+
+impl AnalyticsStream {
+
+    /// Sends an event to the sink. A swap starts a new turn before it is sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - the event to send
+    fn send(&mut self, event: AnalyticsEvent) {
+        if let AnalyticsEvent::Swapped { .. } = event {
+            self.turn += 1;
+        }
+
+        let timestamp_ms = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        let record = AnalyticsRecord { sequence: self.sequence, turn: self.turn, timestamp_ms, event };
+        self.sequence += 1;
+
+        if self.hold_depth > 0 {
+            self.held.push(record);
+        } else {
+            self.sink.record(&record);
+        }
+    }
+
+}
+
+impl Board {
+
+    /// Attaches a sink that receives every swap and every [BoardEvent] on the
+    /// board, replacing any sink that was attached before. Sequence numbers and
+    /// turns start again from zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - receives a record for each event
+    pub fn set_analytics_sink(&mut self, sink: Box<dyn AnalyticsSink>) {
+        *self.analytics_mut() = Some(AnalyticsStream { sink, sequence: 0, turn: 0, held: Vec::new(), hold_depth: 0 });
+    }
+
+    /// Detaches the board's analytics sink and returns it, if it had one.
+    pub fn remove_analytics_sink(&mut self) -> Option<Box<dyn AnalyticsSink>> {
+        self.analytics_mut().take().map(|stream| stream.sink)
+    }
+
+    /// Sends an event to the board's analytics sink, if it has one.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - the event to send
+    pub(crate) fn send_analytics(&mut self, event: AnalyticsEvent) {
+        if let Some(stream) = self.analytics_mut().as_mut() {
+            stream.send(event);
+        }
+    }
+
+    /// Holds back the records sent to the board's analytics sink until
+    /// [release_analytics()](Board::release_analytics) is called, so that
+    /// the events of a change that is undone are never sent. Holds can be
+    /// nested, and records are only sent once the outermost hold is released.
+    /// Returns None if the board has no sink.
+    pub(crate) fn hold_analytics(&mut self) -> Option<AnalyticsCheckpoint> {
+        self.analytics_mut().as_mut().map(|stream| {
+            stream.hold_depth += 1;
+            AnalyticsCheckpoint { held_count: stream.held.len(), sequence: stream.sequence, turn: stream.turn }
+        })
+    }
+
+    /// Ends a hold started by [hold_analytics()](Board::hold_analytics). If
+    /// the change is not kept, the records held since the checkpoint are
+    /// dropped, and the sequence numbers and turns continue as if they had
+    /// never been sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `checkpoint` - where the stream was when the hold started
+    /// * `is_kept` - whether the change made during the hold was kept
+    pub(crate) fn release_analytics(&mut self, checkpoint: Option<AnalyticsCheckpoint>, is_kept: bool) {
+
+        // A sink attached during the hold starts a new stream with nothing held
+        let (stream, checkpoint) = match (self.analytics_mut().as_mut(), checkpoint) {
+            (Some(stream), Some(checkpoint)) if stream.hold_depth > 0 => (stream, checkpoint),
+            _ => return
+        };
+
+        stream.hold_depth -= 1;
+        if !is_kept {
+            stream.held.truncate(checkpoint.held_count);
+            stream.sequence = checkpoint.sequence;
+            stream.turn = checkpoint.turn;
+        }
+
+        if stream.hold_depth == 0 {
+            for record in stream.held.drain(..) {
+                stream.sink.record(&record);
+            }
+        }
+    }
+
+    /// Runs an operation that reports events to an observer, also sending
+    /// each event to the board's analytics sink, if it has one.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer` - receives the events that the operation reports
+    /// * `operation` - changes the board and reports events to the given observer
+    pub(crate) fn with_analytics<T>(&mut self, observer: &mut dyn BoardObserver,
+                                    operation: impl FnOnce(&mut Board, &mut dyn BoardObserver) -> T) -> T {
+
+        // Take the stream so that the board can be changed while events are sent
+        let mut stream = match self.analytics_mut().take() {
+            Some(stream) => stream,
+            None => return operation(self, observer)
+        };
+
+        let result = operation(self, &mut |event: &BoardEvent| {
+            observer.on_event(event);
+            stream.send(AnalyticsEvent::Board(event.clone()));
+        });
+
+        // Keep a sink that the operation attached instead
+        let analytics = self.analytics_mut();
+        if analytics.is_none() {
+            *analytics = Some(stream);
+        }
+
+        result
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analytics::{AnalyticsEvent, AnalyticsRecord};
+    use crate::board::{Board, SwapError};
+    use crate::events::BoardEvent;
+    use crate::piece::Piece;
+    use crate::position::Pos;
+    use crate::test_support::board_from_rows;
+    use crate::transaction::TransactionError;
+    use crate::view::BoardView;
+
+    use enumset::EnumSet;
+
+    use std::sync::{Arc, Mutex};
+
+    fn attach(board: &mut Board) -> Arc<Mutex<Vec<AnalyticsRecord>>> {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink_records = Arc::clone(&records);
+        board.set_analytics_sink(Box::new(move |record: &AnalyticsRecord| {
+            sink_records.lock().unwrap().push(record.clone());
+        }));
+        records
+    }
+
+    #[test]
+    fn swap_pieces_accepted_swap_recorded() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        let records = attach(&mut board);
+
+        assert!(board.swap_pieces(Pos::new(1, 0), Pos::new(1, 1)));
+
+        let records = records.lock().unwrap();
+        assert_eq!(1, records.len());
+        assert_eq!(&AnalyticsEvent::Swapped { first: Pos::new(1, 0), second: Pos::new(1, 1) }, records[0].event());
+        assert_eq!(1, records[0].turn());
+        assert_eq!(0, records[0].sequence());
+        assert!(records[0].timestamp_ms() > 0);
+    }
+
+    #[test]
+    fn swap_pieces_rejected_swap_recorded_same_turn() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        board.set_piece_silent(Pos::new(2, 1), Piece::Regular('c', EnumSet::new()));
+        let records = attach(&mut board);
+
+        assert!(!board.swap_pieces(Pos::new(1, 1), Pos::new(2, 1)));
+
+        let records = records.lock().unwrap();
+        assert_eq!(&AnalyticsEvent::SwapRejected { first: Pos::new(1, 1), second: Pos::new(2, 1) }, records[0].event());
+        assert_eq!(0, records[0].turn());
+    }
+
+    #[test]
+    fn swap_pieces_with_context_rejected_swap_recorded() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        let records = attach(&mut board);
+        let never = |_: &BoardView, _: &(), _: Pos, _: Pos| false;
+
+        assert!(!board.swap_pieces_with(Pos::new(1, 0), Pos::new(1, 1), &(), &[&never]));
+
+        let records = records.lock().unwrap();
+        assert_eq!(1, records.len());
+        assert_eq!(&AnalyticsEvent::SwapRejected { first: Pos::new(1, 0), second: Pos::new(1, 1) }, records[0].event());
+    }
+
+    #[test]
+    fn resolve_cascade_events_recorded_with_swap_turn() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        let records = attach(&mut board);
+        let mut observed = Vec::new();
+
+        assert!(board.swap_pieces(Pos::new(1, 0), Pos::new(1, 1)));
+        board.resolve_cascade(&mut |_: &Board, _| Piece::Empty, &mut |event: &BoardEvent| observed.push(event.clone()));
+
+        let records = records.lock().unwrap();
+        let recorded: Vec<BoardEvent> = records.iter().filter_map(|record| match record.event() {
+            AnalyticsEvent::Board(event) => Some(event.clone()),
+            _ => None
+        }).collect();
+        assert!(!observed.is_empty());
+        assert_eq!(observed, recorded);
+        assert!(records.iter().all(|record| record.turn() == 1));
+        assert!(records.iter().enumerate().all(|(index, record)| record.sequence() == index as u64));
+    }
+
+    #[test]
+    fn begin_swap_committed_swap_recorded() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        let records = attach(&mut board);
+
+        board.begin_swap(Pos::new(1, 0), Pos::new(1, 1)).unwrap().commit();
+
+        let records = records.lock().unwrap();
+        assert_eq!(1, records.len());
+        assert_eq!(&AnalyticsEvent::Swapped { first: Pos::new(1, 0), second: Pos::new(1, 1) }, records[0].event());
+        assert_eq!(1, records[0].turn());
+    }
+
+    #[test]
+    fn begin_swap_rolled_back_nothing_recorded() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        let records = attach(&mut board);
+
+        board.begin_swap(Pos::new(1, 0), Pos::new(1, 1)).unwrap().rollback();
+        assert!(records.lock().unwrap().is_empty());
+
+        assert!(board.swap_pieces(Pos::new(1, 1), Pos::new(2, 1)));
+        let records = records.lock().unwrap();
+        assert_eq!(1, records.len());
+        assert_eq!(0, records[0].sequence());
+        assert_eq!(1, records[0].turn());
+    }
+
+    #[test]
+    fn move_piece_along_rejected_nothing_recorded() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        board.set_piece_silent(Pos::new(2, 1), Piece::Regular('c', EnumSet::new()));
+        let records = attach(&mut board);
+
+        assert_eq!(
+            Err(SwapError::Rejected),
+            board.move_piece_along(&[Pos::new(0, 1), Pos::new(1, 1), Pos::new(2, 1)])
+        );
+
+        assert!(records.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn move_piece_along_accepted_every_step_recorded() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        let records = attach(&mut board);
+
+        assert_eq!(Ok(()), board.move_piece_along(&[Pos::new(0, 1), Pos::new(1, 1), Pos::new(2, 1)]));
+
+        let records = records.lock().unwrap();
+        assert_eq!(2, records.len());
+        assert_eq!(&AnalyticsEvent::Swapped { first: Pos::new(1, 1), second: Pos::new(2, 1) }, records[1].event());
+        assert_eq!(2, records[1].turn());
+    }
+
+    #[test]
+    fn transaction_failed_nothing_recorded() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        let records = attach(&mut board);
+
+        let result: Result<(), TransactionError> = board.transaction(|transaction| {
+            transaction.swap_pieces(Pos::new(1, 1), Pos::new(2, 1))?;
+            transaction.swap_pieces(Pos::new(2, 1), Pos::new(3, 1))?;
+            Ok(())
+        });
+
+        assert!(result.is_err());
+        assert!(records.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn tick_with_events_recorded() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        board.freeze(Pos::new(0, 0), 1);
+        let records = attach(&mut board);
+
+        board.tick();
+
+        let records = records.lock().unwrap();
+        assert_eq!(&AnalyticsEvent::Board(BoardEvent::Thawed { pos: Pos::new(0, 0) }), records[0].event());
+    }
+
+    #[test]
+    fn set_analytics_sink_replaced_counts_restart() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        attach(&mut board);
+        assert!(board.swap_pieces(Pos::new(1, 1), Pos::new(2, 1)));
+
+        let records = attach(&mut board);
+        assert!(board.swap_pieces(Pos::new(1, 1), Pos::new(2, 1)));
+
+        let records = records.lock().unwrap();
+        assert_eq!(1, records[0].turn());
+        assert_eq!(0, records[0].sequence());
+    }
+
+    #[test]
+    fn remove_analytics_sink_nothing_recorded() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        let records = attach(&mut board);
+
+        assert!(board.remove_analytics_sink().is_some());
+        assert!(board.swap_pieces(Pos::new(1, 1), Pos::new(2, 1)));
+
+        assert!(records.lock().unwrap().is_empty());
+        assert!(board.remove_analytics_sink().is_none());
+    }
+
+    #[test]
+    fn analytics_record_serialized_round_trip_equal() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        let records = attach(&mut board);
+        assert!(board.swap_pieces(Pos::new(1, 0), Pos::new(1, 1)));
+        board.resolve_cascade(&mut |_: &Board, _| Piece::Empty, &mut |_: &BoardEvent| {});
+
+        let records = records.lock().unwrap().clone();
+        let json = serde_json::to_string(&records).unwrap();
+        let deserialized: Vec<AnalyticsRecord> = serde_json::from_str(&json).unwrap();
+
+        assert!(records.len() > 1);
+        assert_eq!(records, deserialized);
+    }
+}
@@ -0,0 +1,242 @@
+//! A [Bevy](https://bevyengine.org) plugin that runs a board inside a Bevy
+//! app. Insert a [BoardResource] with the board and its spawner, add the
+//! [SwapAndMatchPlugin], and send a [SwapRequest] whenever the player swaps
+//! two pieces. Every [BoardEvent] that happens on the board is sent as an
+//! [EngineEvent] so that other systems can animate and score it.
+//!
+//! Cascades are resolved one link per update instead of all at once, so the
+//! events for each link in a chain arrive on separate frames.
+
+use crate::board::Board;
+use crate::cascade::{MatchAction, Spawner};
+use crate::events::BoardEvent;
+use crate::position::Pos;
+
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::event::{Event, EventReader, EventWriter};
+use bevy::ecs::schedule::IntoSystemConfigs;
+use bevy::ecs::system::{ResMut, Resource};
+
+/// The board that the plugin's systems update, with the spawner that fills
+/// it during cascades.
+#[derive(Resource)]
+pub struct BoardResource {
+    board: Board,
+    spawner: Box<dyn Spawner>,
+    next_chain_index: Option<usize>
+}
+
+impl BoardResource {
+
+    /// Creates a resource for a board.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - the board to update
+    /// * `spawner` - chooses the new pieces that fill the board
+    pub fn new(board: Board, spawner: Box<dyn Spawner>) -> BoardResource {
+        BoardResource { board, spawner, next_chain_index: None }
+    }
+
+    /// Gets the board.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Gets the board to change it directly, such as to place pieces when a
+    /// level starts.
+    pub fn board_mut(&mut self) -> &mut Board {
+        &mut self.board
+    }
+
+    /// Checks whether a cascade is still being resolved. Swaps requested
+    /// during a cascade are rejected.
+    pub fn is_cascading(&self) -> bool {
+        self.next_chain_index.is_some()
+    }
+
+}
+
+/// Asks the plugin to swap two pieces, usually in response to player input.
+#[derive(Event, Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SwapRequest {
+    pub first: Pos,
+    pub second: Pos
+}
+
+/// Sent after a [SwapRequest] is handled. A rejected swap did not change the
+/// board, either because the board's swap rules refused it, a position was
+/// outside the board, or a cascade was still being resolved.
+#[derive(Event, Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SwapFinished {
+    pub first: Pos,
+    pub second: Pos,
+    pub is_accepted: bool
+}
+
+/// An event that happened on the board, sent in the order the board
+/// reported it.
+#[derive(Event, Clone, Eq, PartialEq, Debug)]
+pub struct EngineEvent(pub BoardEvent);
+
+/// Adds the swap and match events and the systems that swap pieces and
+/// step cascades. The app must have a [BoardResource] for the systems to
+/// run; they do nothing without one.
+pub struct SwapAndMatchPlugin;
+
+impl Plugin for SwapAndMatchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SwapRequest>()
+            .add_event::<SwapFinished>()
+            .add_event::<EngineEvent>()
+            .add_systems(Update, (apply_swap_requests, step_cascade).chain());
+    }
+}
+
+/// Swaps the pieces for each [SwapRequest] and starts a cascade after the
+/// first accepted swap. Requests after an accepted swap in the same update
+/// are rejected until the cascade is resolved.
+pub fn apply_swap_requests(resource: Option<ResMut<BoardResource>>, mut requests: EventReader<SwapRequest>,
+                           mut finished: EventWriter<SwapFinished>) {
+    let mut resource = match resource {
+        Some(resource) => resource,
+        None => return
+    };
+
+    for request in requests.read() {
+        let is_accepted = !resource.is_cascading()
+            && resource.board.try_swap_pieces(request.first, request.second).is_ok();
+        if is_accepted {
+            resource.next_chain_index = Some(0);
+        }
+
+        finished.send(SwapFinished { first: request.first, second: request.second, is_accepted });
+    }
+}
+
+/// Resolves the next link of the current cascade, if there is one, and sends
+/// an [EngineEvent] for everything that happened.
+pub fn step_cascade(resource: Option<ResMut<BoardResource>>, mut engine_events: EventWriter<EngineEvent>) {
+    let mut resource = match resource {
+        Some(resource) => resource,
+        None => return
+    };
+    let chain_index = match resource.next_chain_index {
+        Some(chain_index) => chain_index,
+        None => return
+    };
+
+    let resource = &mut *resource;
+    let mut events = Vec::new();
+    let link = resource.board.resolve_chain_link(
+        chain_index,
+        resource.spawner.as_mut(),
+        &mut |event: &BoardEvent| events.push(EngineEvent(event.clone())),
+        &mut |_: &Board, _: &BoardEvent| MatchAction::Clear
+    );
+    resource.next_chain_index = link.map(|_| chain_index + 1);
+
+    engine_events.send_batch(events);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bevy_plugin::{BoardResource, EngineEvent, SwapAndMatchPlugin, SwapFinished, SwapRequest};
+    use crate::board::{Board, BoardState};
+    use crate::events::BoardEvent;
+    use crate::matching::MatchPattern;
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+
+    use bevy::app::App;
+    use bevy::ecs::event::Events;
+
+    use std::collections::HashSet;
+
+    fn app() -> App {
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(0, 0));
+        spaces.insert(Pos::new(1, 0));
+        spaces.insert(Pos::new(2, 0));
+        let mut board = Board::new(BoardState::new(3, 2), vec![MatchPattern::new('a', spaces, 1)], Vec::new());
+        let types = [['a', 'b', 'a'], ['b', 'a', 'b']];
+        for (y, row) in types.iter().enumerate() {
+            for (x, &piece_type) in row.iter().enumerate() {
+                board.set_piece(Pos::new(x as u8, y as u8), Piece::Regular(piece_type, ALL_DIRECTIONS));
+            }
+        }
+        while board.next_match().is_some() {}
+
+        let mut app = App::new();
+        app.add_plugins(SwapAndMatchPlugin);
+        app.insert_resource(BoardResource::new(board, Box::new(|_: &Board, _| Piece::Regular('c', ALL_DIRECTIONS))));
+        app
+    }
+
+    fn drain<T: bevy::ecs::event::Event>(app: &mut App) -> Vec<T> {
+        app.world_mut().resource_mut::<Events<T>>().drain().collect()
+    }
+
+    #[test]
+    fn update_accepted_swap_cascade_events_sent() {
+        let mut app = app();
+        app.world_mut().send_event(SwapRequest { first: Pos::new(1, 0), second: Pos::new(1, 1) });
+
+        app.update();
+
+        assert_eq!(
+            vec![SwapFinished { first: Pos::new(1, 0), second: Pos::new(1, 1), is_accepted: true }],
+            drain::<SwapFinished>(&mut app)
+        );
+        let events = drain::<EngineEvent>(&mut app);
+        assert!(matches!(events[0], EngineEvent(BoardEvent::Matched { piece_type: 'a', chain_index: 0, .. })));
+        assert!(events.iter().any(|event| matches!(event, EngineEvent(BoardEvent::Spawned { .. }))));
+    }
+
+    #[test]
+    fn update_cascade_finished_after_last_link() {
+        let mut app = app();
+        app.world_mut().send_event(SwapRequest { first: Pos::new(1, 0), second: Pos::new(1, 1) });
+
+        app.update();
+        app.update();
+
+        assert!(!app.world().resource::<BoardResource>().is_cascading());
+    }
+
+    #[test]
+    fn update_rejected_swap_board_unchanged() {
+        let mut app = app();
+        app.world_mut().send_event(SwapRequest { first: Pos::new(0, 0), second: Pos::new(5, 5) });
+
+        app.update();
+
+        assert!(!drain::<SwapFinished>(&mut app)[0].is_accepted);
+        assert!(drain::<EngineEvent>(&mut app).is_empty());
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), app.world().resource::<BoardResource>().board().piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn update_swap_during_cascade_rejected() {
+        let mut app = app();
+        app.world_mut().send_event(SwapRequest { first: Pos::new(1, 0), second: Pos::new(1, 1) });
+        app.world_mut().send_event(SwapRequest { first: Pos::new(0, 0), second: Pos::new(0, 1) });
+
+        app.update();
+
+        let finished = drain::<SwapFinished>(&mut app);
+        assert!(finished[0].is_accepted);
+        assert!(!finished[1].is_accepted);
+    }
+
+    #[test]
+    fn update_without_board_resource_does_nothing() {
+        let mut app = App::new();
+        app.add_plugins(SwapAndMatchPlugin);
+        app.world_mut().send_event(SwapRequest { first: Pos::new(0, 0), second: Pos::new(0, 1) });
+
+        app.update();
+
+        assert!(drain::<SwapFinished>(&mut app).is_empty());
+    }
+}
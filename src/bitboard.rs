@@ -1,21 +1,31 @@
+use crate::piece::Direction;
 use crate::position::Pos;
 
+use std::cmp::min;
 use std::fmt::{Display, Formatter};
 
 use bitvec::bitvec;
+use bitvec::field::BitField;
 use bitvec::vec::BitVec;
 
-/// Efficiently maintains the state of a board with bits.
+/// Efficiently maintains the state of a board with bits. Each coordinate on
+/// the board is either set or unset.
+///
+/// The board uses bitboards to track the location of each piece type, the
+/// empty spaces, and the directions in which pieces can move. Games can use
+/// bitboards to implement their own rules, such as "at most five red pieces per
+/// column", with whole-board operations instead of checking each position.
+///
+/// Like the board, coordinates with larger y values are higher on the bitboard,
+/// and coordinates with larger x values are further right.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub(crate) struct BitBoard {
+pub struct BitBoard {
     board: BitVec,
     width: u8,
     height: u8
 }
 
-/// A bitboard represents game state in binary. Board operations copy the board
-/// with the new state.
 impl BitBoard {
 
     /// Creates a new bitboard with a given size.
@@ -32,6 +42,16 @@ impl BitBoard {
         }
     }
 
+    /// Gets the horizontal size of this bitboard.
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    /// Gets the vertical size of this bitboard.
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
     /// Checks if a coordinate is set in this bitboard.
     ///
     /// # Arguments
@@ -73,6 +93,161 @@ impl BitBoard {
         self.board.set(bit_pos, false)
     }
 
+    /// Counts the coordinates that are set in this bitboard.
+    pub fn count(&self) -> usize {
+        self.board.count_ones()
+    }
+
+    /// Checks if no coordinates are set in this bitboard.
+    pub fn is_empty(&self) -> bool {
+        self.board.not_any()
+    }
+
+    /// Gets an iterator over the coordinates that are set in this bitboard in
+    /// the same order as [first_set()](BitBoard::first_set). Each word of the
+    /// bitboard is scanned with trailing-zero counts, so unset coordinates are
+    /// skipped in bulk and iterating a sparse bitboard is fast.
+    pub fn iter_set(&self) -> impl Iterator<Item = Pos> + '_ {
+        let width = usize::from(self.width);
+        let len = self.board.len();
+
+        let bit_positions = self.board.as_raw_slice().iter().enumerate().flat_map(|(word_index, &word)| {
+            let mut bits = word;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(word_index * usize::BITS as usize + bit)
+            })
+        });
+
+        // The last word may have bits past the end of the bitboard
+        bit_positions
+            .take_while(move |&bit_pos| bit_pos < len)
+            .map(move |bit_pos| Pos::new((bit_pos % width) as u8, (bit_pos / width) as u8))
+    }
+
+    /// Moves every set coordinate in a direction. Coordinates that would be moved
+    /// outside the bitboard are discarded, and coordinates that nothing moves
+    /// into are unset.
+    ///
+    /// # Arguments
+    ///
+    /// * `direction` - the direction to move the coordinates in
+    /// * `amount` - the number of spaces to move each coordinate
+    pub fn shift(&mut self, direction: Direction, amount: u8) {
+        if amount == 0 {
+            return;
+        }
+
+        let width = usize::from(self.width);
+        let distance = match direction {
+            Direction::North | Direction::South => usize::from(amount) * width,
+            Direction::East | Direction::West => usize::from(amount)
+        };
+
+        let is_horizontal = direction == Direction::East || direction == Direction::West;
+        if distance >= self.board.len() || (is_horizontal && amount >= self.width) {
+            self.board.set_all(false);
+            return;
+        }
+
+        match direction {
+            Direction::North | Direction::East => self.board.shift_right(distance),
+            Direction::South | Direction::West => self.board.shift_left(distance)
+        }
+
+        // Clear the coordinates that wrapped around from the neighboring row
+        let cleared_columns = match direction {
+            Direction::East => 0..usize::from(amount),
+            Direction::West => width - usize::from(amount)..width,
+            _ => return
+        };
+
+        for y in 0..usize::from(self.height) {
+            let row_start = y * width;
+            self.board[row_start + cleared_columns.start..row_start + cleared_columns.end].set_all(false);
+        }
+    }
+
+    /// Sets every coordinate that is horizontally or vertically adjacent to a
+    /// set coordinate, such as to find the spaces next to a match or the area
+    /// hit by a blast. Set coordinates stay set.
+    pub fn dilate(&mut self) {
+        let original = self.clone();
+
+        for &direction in [Direction::North, Direction::South, Direction::East, Direction::West].iter() {
+            let mut shifted = original.clone();
+            shifted.shift(direction, 1);
+            self.union(&shifted);
+        }
+    }
+
+    /// Unsets every coordinate that has a horizontally or vertically adjacent
+    /// coordinate that is unset, such as to find the pieces surrounded on all
+    /// sides by pieces of the same type. Coordinates on the edges of the
+    /// bitboard are always unset because the spaces past the edges count as unset.
+    pub fn erode(&mut self) {
+        let original = self.clone();
+
+        for &direction in [Direction::North, Direction::South, Direction::East, Direction::West].iter() {
+            let mut shifted = original.clone();
+            shifted.shift(direction, 1);
+            self.intersect(&shifted);
+        }
+    }
+
+    /// Unsets every coordinate that is not set in another bitboard.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - the bitboard to intersect with
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bitboards are different sizes.
+    pub fn intersect(&mut self, other: &BitBoard) {
+        self.combine(other, |word, other_word| word & other_word);
+    }
+
+    /// Sets every coordinate that is set in another bitboard.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - the bitboard to combine with
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bitboards are different sizes.
+    pub fn union(&mut self, other: &BitBoard) {
+        self.combine(other, |word, other_word| word | other_word);
+    }
+
+    /// Unsets every coordinate that is set in another bitboard.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - the bitboard whose coordinates to remove
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bitboards are different sizes.
+    pub fn difference(&mut self, other: &BitBoard) {
+        self.combine(other, |word, other_word| word & !other_word);
+    }
+
+    /// Gets the first coordinate that is set in this bitboard, searching each row
+    /// from left to right, starting with the bottom row. Returns None if no
+    /// coordinates are set.
+    pub fn first_set(&self) -> Option<Pos> {
+        let bit_pos = self.board.first_one()?;
+        let width = usize::from(self.width);
+        Some(Pos::new((bit_pos % width) as u8, (bit_pos / width) as u8))
+    }
+
     /// Swaps two coordinates in this bitboard.
     ///
     /// # Arguments
@@ -88,6 +263,72 @@ impl BitBoard {
         self.board.swap(first_bit_pos, second_bit_pos)
     }
 
+    /// Checks if every coordinate set in a smaller mask is also set in this
+    /// bitboard when the mask's origin is moved to a given coordinate. Each
+    /// row of the mask is shifted into place and compared with the bitboard
+    /// in chunks of up to 64 bits, so no memory is allocated.
+    ///
+    /// Returns false if any part of the moved mask would be outside this bitboard.
+    ///
+    /// # Arguments
+    ///
+    /// * `mask` - the coordinates that must be set
+    /// * `origin` - the coordinate in this bitboard where the mask's origin is placed
+    pub fn contains_at(&self, mask: &BitBoard, origin: Pos) -> bool {
+        let fits_horizontally = usize::from(origin.x()) + usize::from(mask.width)
+            <= usize::from(self.width);
+        let fits_vertically = usize::from(origin.y()) + usize::from(mask.height)
+            <= usize::from(self.height);
+        if !fits_horizontally || !fits_vertically {
+            return false;
+        }
+
+        let mask_width = usize::from(mask.width);
+        for y in 0..mask.height {
+            let mask_start = usize::from(y) * mask_width;
+            let board_start = self.bit_pos(Pos::new(origin.x(), origin.y() + y));
+
+            let mut x = 0;
+            while x < mask_width {
+                let len = min(u64::BITS as usize, mask_width - x);
+                let mask_bits: u64 = mask.board[mask_start + x..mask_start + x + len].load_le();
+                let board_bits: u64 = self.board[board_start + x..board_start + x + len].load_le();
+
+                if board_bits & mask_bits != mask_bits {
+                    return false;
+                }
+
+                x += len;
+            }
+        }
+
+        true
+    }
+
+    /// Combines this bitboard with another, one machine word at a time. The unused
+    /// bits at the end of the last word are unset in both bitboards, so any
+    /// operation that maps two unset bits to an unset bit keeps them unset.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - the bitboard to combine with
+    /// * `operation` - the function that combines a word from each bitboard
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bitboards are different sizes.
+    fn combine(&mut self, other: &BitBoard, operation: impl Fn(usize, usize) -> usize) {
+        if self.width != other.width || self.height != other.height {
+            panic!("Tried to combine a {}x{} bitboard with a {}x{} bitboard",
+                   self.width, self.height, other.width, other.height);
+        }
+
+        let words = self.board.as_mut_raw_slice();
+        for (word, &other_word) in words.iter_mut().zip(other.board.as_raw_slice()) {
+            *word = operation(*word, other_word);
+        }
+    }
+
     /// Converts a coordinate into the position of the corresponding bit.
     ///
     /// # Arguments
@@ -128,8 +369,15 @@ impl Display for BitBoard {
 #[cfg(test)]
 mod tests {
     use crate::bitboard::BitBoard;
+    use crate::piece::Direction;
     use crate::position::Pos;
 
+    fn board_with(width: u8, height: u8, positions: &[(u8, u8)]) -> BitBoard {
+        let mut board = BitBoard::new(width, height);
+        positions.iter().for_each(|&(x, y)| board.set(Pos::new(x, y)));
+        board
+    }
+
     #[test]
     fn bitboard_new_no_size_does_not_panic() {
         BitBoard::new(0, 0);
@@ -249,6 +497,183 @@ mod tests {
         BitBoard::new(15, 17).unset(pos);
     }
 
+    #[test]
+    fn bitboard_width_height_match_new() {
+        let board = BitBoard::new(15, 17);
+        assert_eq!(15, board.width());
+        assert_eq!(17, board.height());
+    }
+
+    #[test]
+    fn bitboard_count_none_set_zero() {
+        assert_eq!(0, BitBoard::new(16, 16).count());
+        assert!(BitBoard::new(16, 16).is_empty());
+    }
+
+    #[test]
+    fn bitboard_count_some_set_counted() {
+        let board = board_with(255, 255, &[(0, 0), (254, 254), (3, 100)]);
+        assert_eq!(3, board.count());
+        assert!(!board.is_empty());
+    }
+
+    #[test]
+    fn bitboard_iter_set_row_major_order() {
+        let board = board_with(15, 17, &[(14, 16), (10, 4), (3, 4), (2, 7)]);
+        assert_eq!(
+            vec![Pos::new(3, 4), Pos::new(10, 4), Pos::new(2, 7), Pos::new(14, 16)],
+            board.iter_set().collect::<Vec<Pos>>()
+        );
+    }
+
+    #[test]
+    fn bitboard_iter_set_last_coordinate_found() {
+        let board = board_with(13, 11, &[(0, 0), (12, 10)]);
+        assert_eq!(vec![Pos::new(0, 0), Pos::new(12, 10)], board.iter_set().collect::<Vec<Pos>>());
+    }
+
+    #[test]
+    fn bitboard_iter_set_shifted_out_coordinates_skipped() {
+        let mut board = board_with(9, 9, &[(4, 8), (4, 7), (0, 0)]);
+        board.shift(Direction::North, 1);
+        assert_eq!(vec![Pos::new(0, 1), Pos::new(4, 8)], board.iter_set().collect::<Vec<Pos>>());
+    }
+
+    #[test]
+    fn bitboard_iter_set_matches_is_set() {
+        let positions: Vec<(u8, u8)> = (0..200).map(|i| ((i * 7 % 70) as u8, (i * 13 % 70) as u8)).collect();
+        let board = board_with(70, 70, &positions);
+
+        let mut expected = Vec::new();
+        for y in 0..70 {
+            for x in 0..70 {
+                if board.is_set(Pos::new(x, y)) {
+                    expected.push(Pos::new(x, y));
+                }
+            }
+        }
+
+        assert_eq!(expected, board.iter_set().collect::<Vec<Pos>>());
+    }
+
+    #[test]
+    fn bitboard_dilate_orthogonal_neighbors_set() {
+        let mut board = board_with(5, 5, &[(2, 2), (0, 0)]);
+        board.dilate();
+        assert_eq!(board_with(5, 5, &[(0, 0), (1, 0), (0, 1), (2, 2), (1, 2), (3, 2), (2, 1), (2, 3)]), board);
+    }
+
+    #[test]
+    fn bitboard_dilate_does_not_wrap_rows() {
+        let mut board = board_with(4, 3, &[(3, 1)]);
+        board.dilate();
+        assert_eq!(board_with(4, 3, &[(3, 1), (2, 1), (3, 0), (3, 2)]), board);
+    }
+
+    #[test]
+    fn bitboard_erode_only_surrounded_kept() {
+        let positions: Vec<(u8, u8)> = (0..3).flat_map(|y| (0..3).map(move |x| (x + 1, y + 1))).collect();
+        let mut board = board_with(5, 5, &positions);
+        board.erode();
+        assert_eq!(board_with(5, 5, &[(2, 2)]), board);
+    }
+
+    #[test]
+    fn bitboard_erode_edges_unset() {
+        let positions: Vec<(u8, u8)> = (0..3).flat_map(|y| (0..3).map(move |x| (x, y))).collect();
+        let mut board = board_with(3, 3, &positions);
+        board.erode();
+        assert_eq!(board_with(3, 3, &[(1, 1)]), board);
+    }
+
+    #[test]
+    fn bitboard_shift_north_moves_up() {
+        let mut board = board_with(4, 4, &[(1, 0), (2, 3)]);
+        board.shift(Direction::North, 2);
+        assert_eq!(board_with(4, 4, &[(1, 2)]), board);
+    }
+
+    #[test]
+    fn bitboard_shift_south_moves_down() {
+        let mut board = board_with(4, 4, &[(1, 0), (2, 3)]);
+        board.shift(Direction::South, 1);
+        assert_eq!(board_with(4, 4, &[(2, 2)]), board);
+    }
+
+    #[test]
+    fn bitboard_shift_east_does_not_wrap() {
+        let mut board = board_with(4, 4, &[(0, 1), (3, 1), (2, 2)]);
+        board.shift(Direction::East, 1);
+        assert_eq!(board_with(4, 4, &[(1, 1), (3, 2)]), board);
+    }
+
+    #[test]
+    fn bitboard_shift_west_does_not_wrap() {
+        let mut board = board_with(4, 4, &[(0, 1), (3, 1), (2, 2)]);
+        board.shift(Direction::West, 2);
+        assert_eq!(board_with(4, 4, &[(1, 1), (0, 2)]), board);
+    }
+
+    #[test]
+    fn bitboard_shift_zero_unchanged() {
+        let mut board = board_with(4, 4, &[(0, 1), (3, 1)]);
+        board.shift(Direction::West, 0);
+        assert_eq!(board_with(4, 4, &[(0, 1), (3, 1)]), board);
+    }
+
+    #[test]
+    fn bitboard_shift_past_edge_cleared() {
+        let mut board = board_with(4, 6, &[(0, 1), (3, 5)]);
+        board.shift(Direction::East, 4);
+        assert!(board.is_empty());
+
+        let mut board = board_with(4, 6, &[(0, 1), (3, 5)]);
+        board.shift(Direction::North, 6);
+        assert!(board.is_empty());
+    }
+
+    #[test]
+    fn bitboard_intersect_keeps_common() {
+        let mut board = board_with(100, 3, &[(0, 0), (99, 2), (50, 1)]);
+        board.intersect(&board_with(100, 3, &[(99, 2), (50, 1), (4, 0)]));
+        assert_eq!(board_with(100, 3, &[(99, 2), (50, 1)]), board);
+    }
+
+    #[test]
+    fn bitboard_union_keeps_all() {
+        let mut board = board_with(100, 3, &[(0, 0), (99, 2)]);
+        board.union(&board_with(100, 3, &[(99, 2), (4, 0)]));
+        assert_eq!(board_with(100, 3, &[(0, 0), (99, 2), (4, 0)]), board);
+    }
+
+    #[test]
+    fn bitboard_difference_removes_other() {
+        let mut board = board_with(100, 3, &[(0, 0), (99, 2), (50, 1)]);
+        board.difference(&board_with(100, 3, &[(99, 2), (4, 0)]));
+        assert_eq!(board_with(100, 3, &[(0, 0), (50, 1)]), board);
+    }
+
+    #[test]
+    #[should_panic]
+    fn bitboard_intersect_different_sizes_panics() {
+        BitBoard::new(4, 5).intersect(&BitBoard::new(5, 4));
+    }
+
+    #[test]
+    fn bitboard_first_set_none_set_none() {
+        assert_eq!(None, BitBoard::new(16, 16).first_set());
+    }
+
+    #[test]
+    fn bitboard_first_set_lowest_row_first() {
+        let mut board = BitBoard::new(15, 17);
+        board.set(Pos::new(14, 16));
+        board.set(Pos::new(10, 4));
+        board.set(Pos::new(3, 4));
+        board.set(Pos::new(2, 7));
+        assert_eq!(Some(Pos::new(3, 4)), board.first_set());
+    }
+
     #[test]
     fn bitboard_swap_both_unset() {
         let pos1 = Pos::new(1, 3);
@@ -336,6 +761,88 @@ mod tests {
         BitBoard::new(15, 17).swap(pos1, pos2);
     }
 
+    #[test]
+    fn bitboard_contains_at_all_mask_set_true() {
+        let mut mask = BitBoard::new(3, 2);
+        mask.set(Pos::new(0, 0));
+        mask.set(Pos::new(2, 1));
+
+        let mut board = BitBoard::new(16, 16);
+        board.set(Pos::new(4, 5));
+        board.set(Pos::new(6, 6));
+
+        assert!(board.contains_at(&mask, Pos::new(4, 5)));
+    }
+
+    #[test]
+    fn bitboard_contains_at_extra_bits_set_true() {
+        let mut mask = BitBoard::new(3, 1);
+        mask.set(Pos::new(0, 0));
+        mask.set(Pos::new(2, 0));
+
+        let mut board = BitBoard::new(16, 16);
+        board.set(Pos::new(4, 5));
+        board.set(Pos::new(5, 5));
+        board.set(Pos::new(6, 5));
+
+        assert!(board.contains_at(&mask, Pos::new(4, 5)));
+    }
+
+    #[test]
+    fn bitboard_contains_at_mask_bit_unset_false() {
+        let mut mask = BitBoard::new(3, 2);
+        mask.set(Pos::new(0, 0));
+        mask.set(Pos::new(2, 1));
+
+        let mut board = BitBoard::new(16, 16);
+        board.set(Pos::new(4, 5));
+
+        assert!(!board.contains_at(&mask, Pos::new(4, 5)));
+    }
+
+    #[test]
+    fn bitboard_contains_at_wraps_row_false() {
+        let mut mask = BitBoard::new(2, 1);
+        mask.set(Pos::new(0, 0));
+        mask.set(Pos::new(1, 0));
+
+        let mut board = BitBoard::new(16, 16);
+        board.set(Pos::new(15, 5));
+        board.set(Pos::new(0, 6));
+
+        assert!(!board.contains_at(&mask, Pos::new(15, 5)));
+    }
+
+    #[test]
+    fn bitboard_contains_at_outside_top_false() {
+        let mut mask = BitBoard::new(1, 2);
+        mask.set(Pos::new(0, 0));
+
+        let mut board = BitBoard::new(16, 16);
+        board.set(Pos::new(3, 15));
+
+        assert!(!board.contains_at(&mask, Pos::new(3, 15)));
+    }
+
+    #[test]
+    fn bitboard_contains_at_wide_mask_true() {
+        let mut mask = BitBoard::new(200, 1);
+        let mut board = BitBoard::new(255, 3);
+        for x in (0..200).step_by(3) {
+            mask.set(Pos::new(x, 0));
+            board.set(Pos::new(x + 50, 2));
+        }
+
+        assert!(board.contains_at(&mask, Pos::new(50, 2)));
+        assert!(!board.contains_at(&mask, Pos::new(51, 2)));
+    }
+
+    #[test]
+    fn bitboard_contains_at_empty_mask_true() {
+        let board = BitBoard::new(16, 16);
+        assert!(board.contains_at(&BitBoard::new(0, 0), Pos::new(3, 4)));
+    }
+
     #[test]
     fn bitboard_display_shows_state() {
         let mut board = BitBoard::new(15, 17);
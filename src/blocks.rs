@@ -0,0 +1,514 @@
+use crate::board::{Board, PosSet};
+use crate::matching::{Match, MatchSource};
+use crate::piece::{Piece, PieceType, ALL_DIRECTIONS};
+use crate::position::Pos;
+
+use enumset::EnumSet;
+
+/// A rectangular piece that covers several spaces on the board, such as a
+/// 2x1 log or a 2x2 block. Each covered space holds a regular piece of the
+/// block's type, but the block swaps, matches, and falls as a unit.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Block {
+    anchor: Pos,
+    width: u8,
+    height: u8
+}
+
+impl Block {
+
+    /// Gets the bottom-left space the block covers.
+    pub fn anchor(&self) -> Pos {
+        self.anchor
+    }
+
+    /// Gets the number of columns the block covers.
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    /// Gets the number of rows the block covers.
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    /// Checks if the block covers a position.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to check
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.x() >= self.anchor.x() && pos.x() - self.anchor.x() < self.width
+            && pos.y() >= self.anchor.y() && pos.y() - self.anchor.y() < self.height
+    }
+
+    /// Gets all the positions the block covers, from the bottom row to the
+    /// top row and from left to right within each row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let anchor = self.anchor;
+        let width = self.width;
+        (0..self.height).flat_map(
+            move |y| (0..width).map(move |x| Pos::new(anchor.x() + x, anchor.y() + y))
+        )
+    }
+
+    /// Creates a new block without checking it against the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `anchor` - the bottom-left space the block covers
+    /// * `width` - the number of columns the block covers
+    /// * `height` - the number of rows the block covers
+    pub(crate) fn new(anchor: Pos, width: u8, height: u8) -> Block {
+        Block { anchor, width, height }
+    }
+
+}
+
+impl Board {
+
+    /// Places a block of one piece type that covers several spaces. The pieces
+    /// it replaces are removed, including any blocks they were part of.
+    ///
+    /// Swapping any space of the block with a piece next to it moves the whole
+    /// block one space in that direction. The pieces in the way move to the
+    /// spaces the block leaves behind. Blocks cannot be swapped into walls,
+    /// other blocks, or the edge of the board.
+    ///
+    /// When any space of a block is part of a match, the match includes every
+    /// space of the block. A block only falls when every space below it is
+    /// empty, and it never moves diagonally or through portals.
+    ///
+    /// Changing any space of the block with [set_piece()](Board::set_piece),
+    /// or moving it with a row shift or a rotation, splits the block into
+    /// ordinary pieces.
+    ///
+    /// Every space of the block is marked for a match check.
+    ///
+    /// # Arguments
+    ///
+    /// * `anchor` - the bottom-left space the block will cover
+    /// * `width` - the number of columns the block will cover
+    /// * `height` - the number of rows the block will cover
+    /// * `piece_type` - the type of the block's pieces
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block has no spaces or does not fit inside the board.
+    pub fn place_block(&mut self, anchor: Pos, width: u8, height: u8, piece_type: PieceType) {
+        let fits_width = u16::from(anchor.x()) + u16::from(width) <= u16::from(self.state().width);
+        let fits_height = u16::from(anchor.y()) + u16::from(height) <= u16::from(self.state().height);
+        if width == 0 || height == 0 || !fits_width || !fits_height {
+            panic!("Tried to place {}x{} block outside board at {}", width, height, anchor);
+        }
+
+        let block = Block::new(anchor, width, height);
+        for pos in block.positions() {
+            self.set_piece(pos, Piece::Regular(piece_type, ALL_DIRECTIONS));
+            self.set_gravity_movable(pos, EnumSet::new());
+        }
+
+        self.state_mut().blocks.push(block);
+    }
+
+    /// Gets the block that covers a position, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to check
+    pub fn block_at(&self, pos: Pos) -> Option<Block> {
+        self.state().blocks.iter().find(|block| block.contains(pos)).copied()
+    }
+
+    /// Gets all the blocks on the board.
+    pub fn blocks(&self) -> &[Block] {
+        &self.state().blocks
+    }
+
+    /// Splits the block that covers a position into ordinary pieces, which
+    /// stay where they are. Returns the removed block, or None if no block
+    /// covers the position.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - any position covered by the block
+    pub fn remove_block(&mut self, pos: Pos) -> Option<Block> {
+        let index = self.state().blocks.iter().position(|block| block.contains(pos))?;
+        let block = self.state_mut().blocks.remove(index);
+
+        for pos in block.positions() {
+            if let Piece::Regular(_, directions) = self.piece(pos) {
+                self.set_gravity_movable(pos, directions);
+            }
+        }
+
+        Some(block)
+    }
+
+    /// Splits every block that covers any of the given positions.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - the positions whose blocks to remove
+    pub(crate) fn remove_blocks_in(&mut self, positions: &[Pos]) {
+        if self.state().blocks.is_empty() {
+            return;
+        }
+
+        for &pos in positions {
+            self.remove_block(pos);
+        }
+    }
+
+    /// Moves a block one space toward a position next to it, if one of the
+    /// positions is covered by a block. Returns None if neither position is
+    /// covered by a block, or whether the block moved otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of the swap
+    /// * `second` - the second position of the swap
+    pub(crate) fn swap_block(&mut self, first: Pos, second: Pos) -> Option<bool> {
+        let (index, target) = match (self.block_index(first), self.block_index(second)) {
+            (None, None) => return None,
+            (Some(index), None) => (index, second),
+            (None, Some(index)) => (index, first),
+            (Some(_), Some(_)) => return Some(false)
+        };
+        let from = if target == second { first } else { second };
+
+        let x_offset = i16::from(target.x()) - i16::from(from.x());
+        let y_offset = i16::from(target.y()) - i16::from(from.y());
+        if x_offset.abs() + y_offset.abs() != 1 {
+            return Some(false);
+        }
+
+        let block = self.state().blocks[index];
+        let lines: Vec<Vec<Pos>> = match x_offset != 0 {
+            true => (0..block.height).map(|y| {
+                (0..=block.width).map(|x| match x_offset > 0 {
+                    true => Pos::new(block.anchor.x() + block.width - x, block.anchor.y() + y),
+                    false => Pos::new(block.anchor.x() + x - 1, block.anchor.y() + y)
+                }).collect()
+            }).collect(),
+            false => (0..block.width).map(|x| {
+                (0..=block.height).map(|y| match y_offset > 0 {
+                    true => Pos::new(block.anchor.x() + x, block.anchor.y() + block.height - y),
+                    false => Pos::new(block.anchor.x() + x, block.anchor.y() + y - 1)
+                }).collect()
+            }).collect()
+        };
+
+        // Each line starts with the space in front of the block, which the block's pieces move into
+        let is_blocked = lines.iter().map(|line| line[0]).any(
+            |pos| self.piece(pos) == Piece::Wall || self.block_index(pos).is_some()
+        ) || lines.iter().flatten().any(|&pos| self.is_locked(pos));
+        if is_blocked {
+            return Some(false);
+        }
+
+        for line in lines {
+            for pair in line.windows(2) {
+                self.swap_always(pair[0], pair[1], MatchSource::PlayerSwap);
+            }
+        }
+
+        let anchor = &mut self.state_mut().blocks[index].anchor;
+        *anchor = Pos::new((i16::from(anchor.x()) + x_offset) as u8, (i16::from(anchor.y()) + y_offset) as u8);
+        Some(true)
+    }
+
+    /// Moves every block down as far as it can fall. Returns the single-space
+    /// (from position, to position) steps made by the blocks' pieces.
+    pub(crate) fn drop_blocks(&mut self) -> Vec<(Pos, Pos)> {
+        let mut steps = Vec::new();
+        let mut order: Vec<usize> = (0..self.state().blocks.len()).collect();
+        order.sort_by_key(|&index| self.state().blocks[index].anchor.y());
+
+        for index in order {
+            while self.fall_block(index, &mut steps) {}
+        }
+
+        steps
+    }
+
+    /// Moves the anchor of each block whose anchor piece was moved by a step,
+    /// after the block's pieces were already moved.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - the (from position, to position) steps that were made
+    pub(crate) fn move_block_anchors(&mut self, steps: &[(Pos, Pos)]) {
+        for block in self.state_mut().blocks.iter_mut() {
+            if let Some(&(_, to)) = steps.iter().find(|&&(from, _)| from == block.anchor) {
+                block.anchor = to;
+            }
+        }
+    }
+
+    /// Adds every space of the blocks touched by a match to the match.
+    ///
+    /// # Arguments
+    ///
+    /// * `blocks` - the blocks on the board
+    /// * `found` - the match to expand
+    pub(crate) fn include_blocks<'a>(blocks: &[Block], mut found: Match<'a>) -> Match<'a> {
+        let touched: PosSet = blocks.iter()
+            .filter(|block| found.board_pos().iter().any(|&pos| block.contains(pos)))
+            .flat_map(|block| block.positions())
+            .collect();
+        found.extend_board_pos(touched);
+        found
+    }
+
+    /// Moves a block one space down if every space below it is empty. Returns
+    /// true if the block moved.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - the index of the block in the board state
+    /// * `steps` - the steps made so far, which the block's steps are added to
+    fn fall_block(&mut self, index: usize, steps: &mut Vec<(Pos, Pos)>) -> bool {
+        let block = self.state().blocks[index];
+        if block.anchor.y() == 0 {
+            return false;
+        }
+
+        let below = block.anchor.y() - 1;
+        let columns = block.anchor.x()..block.anchor.x() + block.width;
+        let is_locked = block.positions().any(|pos| self.is_locked(pos));
+        if is_locked || !columns.clone().all(|x| self.state().empties.is_set(Pos::new(x, below))) {
+            return false;
+        }
+
+        for x in columns {
+            for y in block.anchor.y()..block.anchor.y() + block.height {
+                self.swap_unmarked(Pos::new(x, y), Pos::new(x, y - 1));
+                steps.push((Pos::new(x, y), Pos::new(x, y - 1)));
+            }
+        }
+
+        self.state_mut().blocks[index].anchor = Pos::new(block.anchor.x(), below);
+        true
+    }
+
+    /// Gets the index of the block that covers a position.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to check
+    fn block_index(&self, pos: Pos) -> Option<usize> {
+        self.state().blocks.iter().position(|block| block.contains(pos))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocks::Block;
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::test_support::board_from_rows;
+
+    #[test]
+    fn contains_inside_and_outside() {
+        let block = Block::new(Pos::new(1, 1), 2, 1);
+
+        assert!(block.contains(Pos::new(1, 1)));
+        assert!(block.contains(Pos::new(2, 1)));
+        assert!(!block.contains(Pos::new(3, 1)));
+        assert!(!block.contains(Pos::new(1, 2)));
+        assert!(!block.contains(Pos::new(0, 1)));
+    }
+
+    #[test]
+    fn positions_all_covered_spaces() {
+        let positions: Vec<Pos> = Block::new(Pos::new(1, 2), 2, 2).positions().collect();
+
+        assert_eq!(vec![Pos::new(1, 2), Pos::new(2, 2), Pos::new(1, 3), Pos::new(2, 3)], positions);
+    }
+
+    #[test]
+    #[should_panic]
+    fn place_block_outside_board_panics() {
+        board_from_rows(&["bcdbc", "dbcdb", "cdbcd", "bcdbc"], &["aaa"]).place_block(Pos::new(4, 0), 2, 1, 'a');
+    }
+
+    #[test]
+    #[should_panic]
+    fn place_block_no_spaces_panics() {
+        board_from_rows(&["bcdbc", "dbcdb", "cdbcd", "bcdbc"], &["aaa"]).place_block(Pos::new(0, 0), 0, 1, 'a');
+    }
+
+    #[test]
+    fn place_block_covered_spaces_have_type() {
+        let mut board = board_from_rows(&["bcdbc", "dbcdb", "cdbcd", "bcdbc"], &["aaa"]);
+
+        board.place_block(Pos::new(1, 1), 2, 2, 'l');
+
+        assert_eq!(Some(Block::new(Pos::new(1, 1), 2, 2)), board.block_at(Pos::new(2, 2)));
+        assert_eq!(Piece::Regular('l', ALL_DIRECTIONS), board.piece(Pos::new(2, 2)));
+        assert_eq!(None, board.block_at(Pos::new(3, 2)));
+        assert_eq!(1, board.blocks().len());
+    }
+
+    #[test]
+    fn set_piece_inside_block_splits_block() {
+        let mut board = board_from_rows(&["bcdbc", "dbcdb", "cdbcd", "bcdbc"], &["aaa"]);
+        board.place_block(Pos::new(1, 1), 2, 1, 'l');
+
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+
+        assert!(board.blocks().is_empty());
+        assert_eq!(Piece::Regular('l', ALL_DIRECTIONS), board.piece(Pos::new(2, 1)));
+    }
+
+    #[test]
+    fn remove_block_pieces_stay() {
+        let mut board = board_from_rows(&["bcdbc", "dbcdb", "cdbcd", "bcdbc"], &["aaa"]);
+        board.place_block(Pos::new(1, 1), 2, 1, 'l');
+
+        assert_eq!(Some(Block::new(Pos::new(1, 1), 2, 1)), board.remove_block(Pos::new(2, 1)));
+        assert_eq!(None, board.remove_block(Pos::new(2, 1)));
+        assert_eq!(Piece::Regular('l', ALL_DIRECTIONS), board.piece(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn swap_pieces_block_east_moves_whole_block() {
+        let mut board = board_from_rows(&["bcdbc", "dbcdb", "cdbcd", "bcdbc"], &["aaa"]);
+        board.place_block(Pos::new(1, 1), 2, 2, 'l');
+        let displaced1 = board.piece(Pos::new(3, 1));
+        let displaced2 = board.piece(Pos::new(3, 2));
+
+        assert!(board.swap_pieces(Pos::new(2, 2), Pos::new(3, 2)));
+
+        assert_eq!(Some(Block::new(Pos::new(2, 1), 2, 2)), board.block_at(Pos::new(3, 1)));
+        for pos in [Pos::new(2, 1), Pos::new(3, 1), Pos::new(2, 2), Pos::new(3, 2)].iter() {
+            assert_eq!(Piece::Regular('l', ALL_DIRECTIONS), board.piece(*pos));
+        }
+        assert_eq!(displaced1, board.piece(Pos::new(1, 1)));
+        assert_eq!(displaced2, board.piece(Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn swap_pieces_block_south_moves_whole_block() {
+        let mut board = board_from_rows(&["bcdbc", "dbcdb", "cdbcd", "bcdbc"], &["aaa"]);
+        board.place_block(Pos::new(1, 1), 2, 1, 'l');
+        let displaced1 = board.piece(Pos::new(1, 0));
+        let displaced2 = board.piece(Pos::new(2, 0));
+
+        assert!(board.swap_pieces(Pos::new(1, 0), Pos::new(1, 1)));
+
+        assert_eq!(Some(Block::new(Pos::new(1, 0), 2, 1)), board.block_at(Pos::new(1, 0)));
+        assert_eq!(displaced1, board.piece(Pos::new(1, 1)));
+        assert_eq!(displaced2, board.piece(Pos::new(2, 1)));
+    }
+
+    #[test]
+    fn swap_pieces_block_into_wall_rejected() {
+        let mut board = board_from_rows(&["bcdbc", "dbcdb", "cdbcd", "bcdbc"], &["aaa"]);
+        board.place_block(Pos::new(1, 1), 2, 2, 'l');
+        board.set_piece(Pos::new(3, 1), Piece::Wall);
+
+        assert!(!board.swap_pieces(Pos::new(2, 2), Pos::new(3, 2)));
+        assert_eq!(Some(Block::new(Pos::new(1, 1), 2, 2)), board.block_at(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn swap_pieces_block_not_adjacent_rejected() {
+        let mut board = board_from_rows(&["bcdbc", "dbcdb", "cdbcd", "bcdbc"], &["aaa"]);
+        board.place_block(Pos::new(1, 1), 2, 1, 'l');
+
+        assert!(!board.swap_pieces(Pos::new(1, 1), Pos::new(4, 1)));
+        assert!(!board.swap_pieces(Pos::new(1, 1), Pos::new(2, 1)));
+    }
+
+    #[test]
+    fn swap_pieces_block_into_block_rejected() {
+        let mut board = board_from_rows(&["bcdbc", "dbcdb", "cdbcd", "bcdbc"], &["aaa"]);
+        board.place_block(Pos::new(0, 1), 2, 1, 'l');
+        board.place_block(Pos::new(2, 1), 2, 1, 'm');
+
+        assert!(!board.swap_pieces(Pos::new(1, 1), Pos::new(2, 1)));
+    }
+
+    #[test]
+    fn next_match_touching_block_includes_whole_block() {
+        let mut board = board_from_rows(&["bcdbc", "dbcdb", "cdbcd", "bcdbc"], &["aaa"]);
+        board.place_block(Pos::new(2, 0), 2, 2, 'a');
+        board.set_piece(Pos::new(0, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('a', ALL_DIRECTIONS));
+
+        let positions = board.next_match().unwrap().board_pos().clone();
+
+        assert!(Block::new(Pos::new(2, 0), 2, 2).positions().all(|pos| positions.contains(&pos)));
+    }
+
+    #[test]
+    fn trickle_block_falls_as_unit() {
+        let mut board = board_from_rows(&["#cd#c", "#bc#b", "#db#d", "#cd#c"], &["aaa"]);
+        board.place_block(Pos::new(1, 2), 2, 2, 'l');
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+
+        let moves = board.trickle();
+
+        assert_eq!(Some(Block::new(Pos::new(1, 0), 2, 2)), board.block_at(Pos::new(1, 0)));
+        assert_eq!(4, moves.len());
+        assert!(moves.iter().all(|piece_move| piece_move.path().len() == 3));
+    }
+
+    #[test]
+    fn trickle_block_partly_supported_stays() {
+        let mut board = board_from_rows(&["#cd#c", "#bc#b", "#db#d", "#cd#c"], &["aaa"]);
+        board.place_block(Pos::new(1, 2), 2, 1, 'l');
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(Some(Block::new(Pos::new(1, 2), 2, 1)), board.block_at(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn trickle_pieces_above_falling_block_follow() {
+        let mut board = board_from_rows(&["#cd#c", "#bc#b", "#db#d", "#cd#c"], &["aaa"]);
+        board.place_block(Pos::new(1, 1), 2, 1, 'l');
+        let above = board.piece(Pos::new(1, 2));
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(Some(Block::new(Pos::new(1, 0), 2, 1)), board.block_at(Pos::new(1, 0)));
+        assert_eq!(above, board.piece(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn step_block_falls_one_space_per_step() {
+        let mut board = board_from_rows(&["#cd#c", "#bc#b", "#db#d", "#cd#c"], &["aaa"]);
+        board.place_block(Pos::new(1, 2), 2, 1, 'l');
+        for x in 1..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Empty);
+            board.set_piece(Pos::new(x, 1), Piece::Empty);
+        }
+
+        board.step(1);
+
+        assert_eq!(Some(Block::new(Pos::new(1, 1), 2, 1)), board.block_at(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn shuffle_block_stays() {
+        let mut board = board_from_rows(&["bcdbc", "dbcdb", "cdbcd", "bcdbc"], &["aaa"]);
+        board.place_block(Pos::new(1, 1), 2, 2, 'l');
+
+        board.shuffle();
+
+        assert_eq!(Some(Block::new(Pos::new(1, 1), 2, 2)), board.block_at(Pos::new(1, 1)));
+        assert_eq!(Piece::Regular('l', ALL_DIRECTIONS), board.piece(Pos::new(2, 2)));
+    }
+}
@@ -1,10 +1,25 @@
+#[cfg(feature = "analytics")]
+use crate::analytics::{AnalyticsEvent, AnalyticsStream};
 use crate::bitboard::BitBoard;
-use crate::matching::{MatchPattern, Match};
+use crate::blocks::Block;
+use crate::cascade::Spawner;
+use crate::events::{BoardEvent, BoardObserver, ClearCause, Effect};
+use crate::matching::{MatchPattern, Match, MatchOrder, MatchSource, CompiledPattern};
 use crate::piece::{Piece, Direction, PieceType, ALL_DIRECTIONS};
 use crate::position::Pos;
-
+use crate::posset::flood;
+use crate::random::Rng;
+use crate::report::TurnReport;
+use crate::tick::TickBehavior;
+use crate::types::{TypeId, TypeTable};
+use crate::view::BoardView;
+use crate::walls::{WallKind, WallType};
+use crate::zobrist::{compute_zobrist, zobrist_key};
+
+use std::cmp::{max, min, Reverse};
 use std::collections::{VecDeque, HashSet, HashMap};
 use std::fmt::{Debug, Formatter, Display};
+use std::mem;
 
 use enumset::EnumSet;
 
@@ -17,10 +32,22 @@ use enumset::EnumSet;
 pub struct BoardState {
     pub(crate) width: u8,
     pub(crate) height: u8,
-    pub(crate) pieces: HashMap<PieceType, BitBoard>,
+    pub(crate) pieces: TypeTable,
+    pub(crate) type_grid: Vec<TypeId>,
     pub(crate) empties: BitBoard,
     pub(crate) movable_directions: [BitBoard; 4],
-    pub(crate) last_changed: VecDeque<Pos>
+    pub(crate) gravity_directions: [BitBoard; 4],
+    pub(crate) last_changed: VecDeque<Pos>,
+    pub(crate) changed: BitBoard,
+    pub(crate) change_sources: HashMap<Pos, MatchSource>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) change_order: ChangeOrder,
+    pub(crate) counters: HashMap<Pos, u32>,
+    pub(crate) frozen: HashMap<Pos, u32>,
+    pub(crate) wall_kinds: HashMap<WallType, WallKind>,
+    pub(crate) walls: HashMap<Pos, (WallType, u32)>,
+    pub(crate) blocks: Vec<Block>,
+    pub(crate) rng: Rng
 }
 
 impl BoardState {
@@ -38,7 +65,8 @@ impl BoardState {
         BoardState {
             width,
             height,
-            pieces: HashMap::new(),
+            pieces: TypeTable::new(),
+            type_grid: vec![0; usize::from(width) * usize::from(height)],
             empties: BitBoard::new(width, height),
             movable_directions: [
                 BitBoard::new(width, height),
@@ -46,17 +74,347 @@ impl BoardState {
                 BitBoard::new(width, height),
                 BitBoard::new(width, height)
             ],
-            last_changed: VecDeque::new()
+            gravity_directions: [
+                BitBoard::new(width, height),
+                BitBoard::new(width, height),
+                BitBoard::new(width, height),
+                BitBoard::new(width, height)
+            ],
+            last_changed: VecDeque::new(),
+            changed: BitBoard::new(width, height),
+            change_sources: HashMap::new(),
+            change_order: ChangeOrder::default(),
+            counters: HashMap::new(),
+            frozen: HashMap::new(),
+            wall_kinds: HashMap::new(),
+            walls: HashMap::new(),
+            blocks: Vec::new(),
+            rng: Rng::default()
+        }
+    }
+
+    /// Marks a position for a match check. A position that is already marked
+    /// keeps its place in the queue and is not added again, but its source is
+    /// replaced with the most recent one.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to mark
+    /// * `source` - what changed the position
+    pub(crate) fn mark_changed(&mut self, pos: Pos, source: MatchSource) {
+        if !self.changed.is_set(pos) {
+            self.changed.set(pos);
+            self.last_changed.push_back(pos);
+            self.change_order.push(pos);
+        }
+        self.change_sources.insert(pos, source);
+    }
+
+    /// Finds the index of a marked position in the queue of positions marked
+    /// for a match check. Returns None if the position is not marked.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to find
+    pub(crate) fn changed_index(&self, pos: Pos) -> Option<usize> {
+        if !self.changed.is_set(pos) {
+            return None;
+        }
+
+        self.change_order.index_of(&self.last_changed, pos)
+            .or_else(|| self.last_changed.iter().position(|&changed_pos| changed_pos == pos))
+    }
+
+    /// Removes a position from the queue of positions marked for a match check
+    /// and unmarks it. Returns the position and what changed it, or None if
+    /// there is no position at that index.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - the index of the position in the queue
+    pub(crate) fn take_changed(&mut self, index: usize) -> Option<(Pos, MatchSource)> {
+        let pos = self.last_changed.remove(index)?;
+        self.changed.unset(pos);
+        Some((pos, self.unmark_source(pos)))
+    }
+
+    /// Forgets what changed a position that is no longer marked for a match
+    /// check and returns it. Positions marked without a recorded source, such
+    /// as in a state saved by an older version, are treated as [MatchSource::Scripted].
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position that was unmarked
+    pub(crate) fn unmark_source(&mut self, pos: Pos) -> MatchSource {
+        self.change_order.remove(pos);
+        self.change_sources.remove(&pos).unwrap_or(MatchSource::Scripted)
+    }
+
+}
+
+/// Remembers the order that positions were marked for a match check, so a
+/// position's index in the queue can be found with a binary search instead of
+/// scanning the queue. Each position gets a sequence number when it is marked,
+/// and the queue is always ordered by sequence number.
+///
+/// The order only speeds up lookups, so it is not part of a state's equality
+/// and is not saved. Lookups in a queue that was replaced without updating the
+/// order fall back to a scan.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct ChangeOrder {
+    sequence_numbers: HashMap<Pos, u64>,
+    next_sequence_number: u64
+}
+
+impl ChangeOrder {
+
+    /// Gives a newly-marked position the next sequence number.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position that was marked
+    fn push(&mut self, pos: Pos) {
+        self.sequence_numbers.insert(pos, self.next_sequence_number);
+        self.next_sequence_number += 1;
+    }
+
+    /// Forgets the sequence number of a position that was unmarked.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position that was unmarked
+    fn remove(&mut self, pos: Pos) {
+        self.sequence_numbers.remove(&pos);
+    }
+
+    /// Forgets the sequence numbers of every position.
+    fn clear(&mut self) {
+        self.sequence_numbers.clear();
+    }
+
+    /// Finds the index of a position in a queue ordered by sequence number.
+    /// Returns None if the position has no sequence number or is not at the
+    /// index its sequence number points to.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - the positions marked for a match check
+    /// * `pos` - the position to find
+    fn index_of(&self, queue: &VecDeque<Pos>, pos: Pos) -> Option<usize> {
+        let sequence_number = *self.sequence_numbers.get(&pos)?;
+        let index = queue.binary_search_by_key(
+            &sequence_number,
+            |queued_pos| self.sequence_numbers.get(queued_pos).copied().unwrap_or(u64::MAX)
+        ).ok()?;
+
+        match queue[index] == pos {
+            true => Some(index),
+            false => None
         }
     }
 
 }
 
+impl PartialEq for ChangeOrder {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for ChangeOrder {}
+
+/// Everything that decides where a board's pieces fall, so that a planned
+/// fall can be dropped if any of it changes.
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct FallSetup {
+    type_grid: Vec<TypeId>,
+    empties: BitBoard,
+    gravity_directions: [BitBoard; 4],
+    blocks: Vec<Block>,
+    locked: BitBoard,
+    portals: HashMap<Pos, Pos>,
+    weights: HashMap<PieceType, u32>,
+    floating: HashSet<PieceType>,
+    diagonal_fill: DiagonalFill
+}
+
+/// The rounds of a fall that [step()](Board::step) has not made yet.
+#[derive(Clone, Debug)]
+struct FallPlan {
+    setup: FallSetup,
+    rounds: VecDeque<Vec<(Pos, Pos)>>
+}
+
 /// A group of positions on the board.
 pub type PosSet = HashSet<Pos>;
 
-/// A function that returns true if two pieces can be swapped.
-pub type SwapRule = Box<dyn Fn(&Board, Pos, Pos) -> bool>;
+/// A function that returns true if two pieces can be swapped. Rules receive
+/// a read-only view of the board, so they cannot change it.
+pub type SwapRule = Box<dyn Fn(&BoardView, Pos, Pos) -> bool + Send + Sync>;
+
+/// What a [TypeSwapRule] decides about swapping a piece of its type.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum TypeSwap {
+
+    /// Swap the pieces without running the board's swap rules, such as for
+    /// bombs that can swap with anything anywhere. Locked, frozen, and staged
+    /// spaces and walls still cannot be swapped.
+    Always,
+
+    /// Never swap the pieces, such as for stones the player cannot move.
+    Never,
+
+    /// Let the board's swap rules decide.
+    Default
+
+}
+
+/// A swap rule for one piece type, registered with
+/// [set_type_swap_rule()](Board::set_type_swap_rule). The rule receives a
+/// read-only view of the board, the position of the piece of its type, the
+/// position of the other piece, and the other piece, so it does not need to
+/// look up either piece.
+pub type TypeSwapRule = Box<dyn Fn(&BoardView, Pos, Pos, Piece) -> TypeSwap + Send + Sync>;
+
+/// Which positions gravity marks for a match check when pieces fall.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GravityMarking {
+
+    /// Every position a piece passes through is marked, including the
+    /// positions the pieces fell from.
+    #[default]
+    All,
+
+    /// Only the positions where pieces land are marked.
+    Final,
+
+    /// Falling pieces are not marked.
+    Off
+
+}
+
+/// Which empty spaces falling pieces can slip into diagonally.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DiagonalFill {
+
+    /// Pieces slip diagonally into any empty space that a piece directly
+    /// above will not fill.
+    #[default]
+    Always,
+
+    /// Pieces only slip diagonally into shadowed spaces, which no piece
+    /// can reach by falling straight down because a wall or a piece that
+    /// cannot fall is somewhere above them. Other empty spaces are left
+    /// to be filled from the top of the board.
+    ShadowedOnly
+
+}
+
+/// Why [try_set_piece()](Board::try_set_piece) could not replace a piece.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SetPieceError {
+
+    /// The position is outside the board.
+    OutOfBounds(Pos),
+
+    /// The piece would be the 65,536th distinct piece type on the board.
+    TooManyPieceTypes
+
+}
+
+impl Display for SetPieceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SetPieceError::OutOfBounds(pos) => write!(f, "Position is outside the board: {}", pos),
+            SetPieceError::TooManyPieceTypes => write!(f, "Board already has {} piece types", TypeId::MAX)
+        }
+    }
+}
+
+impl std::error::Error for SetPieceError {}
+
+/// Why [try_swap_pieces()](Board::try_swap_pieces) could not swap two pieces
+/// or [move_piece_along()](Board::move_piece_along) could not move a piece.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SwapError {
+
+    /// The position is outside the board.
+    OutOfBounds(Pos),
+
+    /// A swap rule did not allow the swap, or a block could not move.
+    Rejected,
+
+    /// The piece at the position is a wall, and the piece it would be swapped
+    /// with cannot [swap with walls](Board::set_wall_swapper).
+    TargetIsWall(Pos),
+
+    /// Two consecutive positions in a path are not horizontally or vertically
+    /// adjacent.
+    NotAdjacent(Pos, Pos)
+
+}
+
+impl Display for SwapError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SwapError::OutOfBounds(pos) => write!(f, "Position is outside the board: {}", pos),
+            SwapError::Rejected => write!(f, "Swap is not allowed"),
+            SwapError::TargetIsWall(pos) => write!(f, "Cannot swap with the wall at {}", pos),
+            SwapError::NotAdjacent(first, second) => write!(f, "Positions are not adjacent: {} and {}", first, second)
+        }
+    }
+}
+
+impl std::error::Error for SwapError {}
+
+/// The full path of a piece that fell when the board was trickled.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PieceMove {
+    path: Vec<Pos>
+}
+
+impl PieceMove {
+
+    /// Creates a new move along a path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - every position the piece occupied, in order, from where it
+    ///   started to where it landed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path is empty.
+    pub fn new(path: Vec<Pos>) -> PieceMove {
+        if path.is_empty() {
+            panic!("Tried to create a move with an empty path");
+        }
+
+        PieceMove { path }
+    }
+
+    /// Gets the position the piece started in.
+    pub fn from(&self) -> Pos {
+        self.path[0]
+    }
+
+    /// Gets the position the piece landed in.
+    pub fn to(&self) -> Pos {
+        self.path[self.path.len() - 1]
+    }
+
+    /// Gets every position the piece occupied, in order, from where it
+    /// started to where it landed. Consecutive positions are one space
+    /// apart, either directly below or diagonally below, except when
+    /// the piece moved from a portal's exit to its entry.
+    pub fn path(&self) -> &[Pos] {
+        &self.path
+    }
+
+}
 
 /// Contains zero or many pieces and represents the current state
 /// of the game.
@@ -93,9 +451,40 @@ pub type SwapRule = Box<dyn Fn(&Board, Pos, Pos) -> bool>;
 ///
 /// The board's lack of default restrictions allows games to implement
 /// their own unique or non-standard rules.
+///
+/// A board is [Send] and [Sync], so it can be moved and shared across
+/// threads. Everything that a board holds, including its swap rules,
+/// spawners, tick behaviors, and analytics sink, must be [Send] and
+/// [Sync] as well.
 pub struct Board {
     patterns: Vec<MatchPattern>,
+    compiled_patterns: Vec<CompiledPattern>,
     swap_rules: Vec<SwapRule>,
+    type_swap_rules: HashMap<PieceType, TypeSwapRule>,
+    match_order: MatchOrder,
+    gravity_marking: GravityMarking,
+    diagonal_fill: DiagonalFill,
+    ingredients: HashSet<PieceType>,
+    weights: HashMap<PieceType, u32>,
+    floating: HashSet<PieceType>,
+    wall_swappers: HashSet<PieceType>,
+    obstacles: HashMap<PieceType, Option<PieceType>>,
+    exits: BitBoard,
+    locked: BitBoard,
+    match_prevented: BitBoard,
+    staging_rows: u8,
+    portals: HashMap<Pos, Pos>,
+    tags: HashMap<String, HashSet<PieceType>>,
+    tick_behaviors: Vec<Box<dyn TickBehavior>>,
+    column_spawners: HashMap<u8, Box<dyn Spawner>>,
+    cascade_region: Option<PosSet>,
+    zobrist: u64,
+    effects: Option<Vec<Effect>>,
+    clear_cause: ClearCause,
+    turn_report: TurnReport,
+    #[cfg(feature = "analytics")]
+    analytics: Option<AnalyticsStream>,
+    fall_plan: Option<FallPlan>,
     state: BoardState
 }
 
@@ -106,1671 +495,9072 @@ impl Board {
     /// # Arguments
     ///
     /// * `initial_state` - the initial state of the board. Create a state with a 
-    ///                     size for brand new games. Otherwise, use a state 
-    ///                     deserialized from your save format.
+    ///   size for brand new games. Otherwise, use a state 
+    ///   deserialized from your save format.
     /// * `patterns` - the match patterns the board should use to detect matches. If
-    ///                two patterns have the same rank, no order is guaranteed.
+    ///   two patterns have the same rank, no order is guaranteed. Each
+    ///   pattern's [ID](MatchPattern::id) becomes its index in this list.
     /// * `swap_rules` - the swap rules that define whether two pieces can be swapped.
-    ///                  If any rule returns false for two positions, the pieces are
-    ///                  not swapped, and the swap method returns false. These rules
-    ///                  are executed in the order provided after the default rule,
-    ///                  so less expensive calculations should be done in earlier rules.
+    ///   If any rule returns false for two positions, the pieces are
+    ///   not swapped, and the swap method returns false. These rules
+    ///   are executed in the order provided after the default rule,
+    ///   so less expensive calculations should be done in earlier rules.
+    ///   Swap rules only apply to swaps that a player or game asks
+    ///   for. Falling, rising, and refilling pieces never check them.
     pub fn new(initial_state: BoardState, mut patterns: Vec<MatchPattern>,
                mut swap_rules: Vec<SwapRule>) -> Board {
-        patterns.sort_by(|a, b| b.rank().cmp(&a.rank()));
-        swap_rules.insert(0, Box::from(Board::are_pieces_movable));
+        patterns.iter_mut().enumerate().for_each(|(id, pattern)| pattern.set_id(id));
+        patterns.sort_by_key(|pattern| Reverse(pattern.rank()));
+        let compiled_patterns = CompiledPattern::compile_all(&patterns, initial_state.width, initial_state.height);
+        swap_rules.insert(0, Box::new(
+            |view: &BoardView, first, second| view.board().are_pieces_movable(first, second)
+        ));
+
+        let mut exits = BitBoard::new(initial_state.width, initial_state.height);
+        for x in 0..initial_state.width {
+            exits.set(Pos::new(x, 0));
+        }
+        let locked = BitBoard::new(initial_state.width, initial_state.height);
+        let match_prevented = BitBoard::new(initial_state.width, initial_state.height);
 
         Board {
             patterns,
+            compiled_patterns,
             swap_rules,
+            type_swap_rules: HashMap::new(),
+            match_order: MatchOrder::Fifo,
+            gravity_marking: GravityMarking::All,
+            diagonal_fill: DiagonalFill::Always,
+            ingredients: HashSet::new(),
+            weights: HashMap::new(),
+            floating: HashSet::new(),
+            wall_swappers: HashSet::new(),
+            obstacles: HashMap::new(),
+            exits,
+            locked,
+            match_prevented,
+            staging_rows: 0,
+            portals: HashMap::new(),
+            tags: HashMap::new(),
+            tick_behaviors: Vec::new(),
+            column_spawners: HashMap::new(),
+            cascade_region: None,
+            zobrist: compute_zobrist(&initial_state),
+            effects: None,
+            clear_cause: ClearCause::Direct,
+            turn_report: TurnReport::default(),
+            #[cfg(feature = "analytics")]
+            analytics: None,
+            fall_plan: None,
             state: initial_state
         }
     }
 
-    /// Gets the current state of the board, which is (de)serializable and is
-    /// useful for saving the board. Use other board methods to mutate the
-    /// board's state.
-    pub fn state(&self) -> &BoardState {
-        &self.state
-    }
-
-    /// Gets a piece at the given position on the board. By default,
-    /// all pieces on the board are walls.
+    /// Creates a copy of the board's pieces, patterns, tags, and settings that
+    /// can be changed freely to try out moves. The copy has no swap rules other
+    /// than the default movability rule, no type swap rules, no column spawners,
+    /// no tick behaviors, and no effect queue.
+    pub(crate) fn scratch_copy(&self) -> Board {
+        let mut board = Board::new(self.state.clone(), Vec::new(), Vec::new());
+        board.patterns = self.patterns.clone();
+        board.compiled_patterns = self.compiled_patterns.clone();
+        board.match_order = self.match_order;
+        board.gravity_marking = self.gravity_marking;
+        board.diagonal_fill = self.diagonal_fill;
+        board.ingredients = self.ingredients.clone();
+        board.weights = self.weights.clone();
+        board.floating = self.floating.clone();
+        board.wall_swappers = self.wall_swappers.clone();
+        board.obstacles = self.obstacles.clone();
+        board.exits = self.exits.clone();
+        board.locked = self.locked.clone();
+        board.match_prevented = self.match_prevented.clone();
+        board.staging_rows = self.staging_rows;
+        board.portals = self.portals.clone();
+        board.tags = self.tags.clone();
+        board.cascade_region = self.cascade_region.clone();
+        board
+    }
+
+    /// Trades swap rules, type swap rules, and column spawners with another
+    /// board. Lending a board's rules to a [scratch copy](Board::scratch_copy)
+    /// lets the copy play by the same rules, and trading again gives them back.
     ///
     /// # Arguments
     ///
-    /// * `pos` - position of the piece to get
-    ///
-    /// # Panics
-    ///
-    /// Panics if the provided position is outside the board.
-    pub fn piece(&self, pos: Pos) -> Piece {
-        if !self.is_within_board(pos) {
-            panic!("Tried to get piece outside board: {}", pos);
-        }
+    /// * `other` - the board to trade rules with
+    pub(crate) fn trade_rules(&mut self, other: &mut Board) {
+        mem::swap(&mut self.swap_rules, &mut other.swap_rules);
+        mem::swap(&mut self.type_swap_rules, &mut other.type_swap_rules);
+        mem::swap(&mut self.column_spawners, &mut other.column_spawners);
+    }
 
-        if self.state.empties.is_set(pos) {
-            return Piece::Empty;
-        }
+    /// Gets the match patterns the board uses to detect matches, from the
+    /// highest rank to the lowest rank.
+    pub fn patterns(&self) -> &[MatchPattern] {
+        &self.patterns
+    }
 
-        let possible_type = self.piece_type(pos);
-        match possible_type {
-            None => Piece::Wall,
-            Some(piece_type) => Piece::Regular(piece_type, self.movable_directions(pos))
-        }
+    /// Gets the order in which positions marked for a match check are checked.
+    pub fn match_order(&self) -> MatchOrder {
+        self.match_order
     }
 
-    /// Attempts to swap two pieces on the board. If any swap rule is broken (i.e. it
-    /// results false), then the pieces will not be swapped, and this method will
-    /// return false.
+    /// Sets the order in which positions marked for a match check are checked.
+    /// The order applies to all marked positions, including those marked before
+    /// the order was changed.
     ///
-    /// If the swap is successful, both swapped positions will be marked for a match check.
+    /// # Arguments
     ///
-    /// Swapping a piece in a direction in which it is marked unmovable is automatically
-    /// a violation of the swap rules.
+    /// * `match_order` - the new order to check positions in
+    pub fn set_match_order(&mut self, match_order: MatchOrder) {
+        self.match_order = match_order;
+    }
+
+    /// Gets which positions are marked for a match check when pieces fall.
+    pub fn gravity_marking(&self) -> GravityMarking {
+        self.gravity_marking
+    }
+
+    /// Sets which positions are marked for a match check when pieces fall
+    /// in [trickle()](Board::trickle), [add_and_trickle()](Board::add_and_trickle),
+    /// and [step()](Board::step). Gravity never runs the swap rules.
     ///
-    /// Swapping with a piece that is empty is considered valid by default. The existing
-    /// piece moves into the empty space while the other space is cleared. It is also valid
-    /// to swap a piece with itself, though this has no effect on the board and does not
-    /// mark the piece for a match check.
+    /// # Arguments
     ///
-    /// The order of two positions provided does not matter.
+    /// * `gravity_marking` - which positions falling pieces mark
+    pub fn set_gravity_marking(&mut self, gravity_marking: GravityMarking) {
+        self.gravity_marking = gravity_marking;
+    }
+
+    /// Gets which empty spaces falling pieces can slip into diagonally.
+    pub fn diagonal_fill(&self) -> DiagonalFill {
+        self.diagonal_fill
+    }
+
+    /// Sets which empty spaces falling pieces can slip into diagonally in
+    /// [trickle()](Board::trickle), [add_and_trickle()](Board::add_and_trickle),
+    /// and [step()](Board::step).
     ///
     /// # Arguments
     ///
-    /// * `first` - the first position of a piece to swap
-    /// * `second` - the second position of a piece to swap
+    /// * `diagonal_fill` - which spaces pieces can slip into diagonally
+    pub fn set_diagonal_fill(&mut self, diagonal_fill: DiagonalFill) {
+        self.diagonal_fill = diagonal_fill;
+    }
+
+    /// Checks whether engine operations queue [Effect]s. The queue is
+    /// disabled by default.
+    pub fn effects_enabled(&self) -> bool {
+        self.effects.is_some()
+    }
+
+    /// Enables or disables the effect queue. Disabling the queue discards
+    /// any effects that have not been drained yet.
     ///
-    /// # Panics
+    /// # Arguments
     ///
-    /// Panics if either position is outside the board.
-    #[must_use]
-    pub fn swap_pieces(&mut self, first: Pos, second: Pos) -> bool {
-        if !self.is_within_board(first) || !self.is_within_board(second) {
-            panic!("Tried to swap piece outside board: {} with {}", first, second);
+    /// * `enabled` - whether engine operations should queue effects
+    pub fn set_effects_enabled(&mut self, enabled: bool) {
+        if !enabled {
+            self.effects = None;
+        } else if self.effects.is_none() {
+            self.effects = Some(Vec::new());
         }
+    }
 
-        if !self.swap_rules.iter().all(|rule| rule(self, first, second)) {
-            return false;
-        }
+    /// Gets the queued effects in the order they happened without removing them.
+    pub fn effects(&self) -> &[Effect] {
+        self.effects.as_deref().unwrap_or(&[])
+    }
 
-        self.swap_always(first, second);
-        true
+    /// Gets the number of queued effects, or None if effects are disabled.
+    pub(crate) fn effect_count(&self) -> Option<usize> {
+        self.effects.as_ref().map(Vec::len)
     }
 
-    /// Replaces a piece at the given position and returns the previous piece.
-    /// The space is marked as needing a match check. Swap rules do not apply
-    /// and the replacement is always successful.
+    /// Removes the effects queued after a count taken with
+    /// [effect_count()](Board::effect_count), such as when undoing changes.
     ///
     /// # Arguments
     ///
-    /// * `pos` - the position of the piece to replace
-    /// * `piece` - the piece to put at the given position
-    ///
-    /// # Panics
-    ///
-    /// Panics if the provided position is outside the board.
-    pub fn set_piece(&mut self, pos: Pos, piece: Piece) -> Piece {
-        if !self.is_within_board(pos) {
-            panic!("Tried to set piece out of bounds: {}", pos);
+    /// * `count` - the number of effects to keep, or None to keep them all
+    pub(crate) fn truncate_effects(&mut self, count: Option<usize>) {
+        if let (Some(effects), Some(count)) = (self.effects.as_mut(), count) {
+            effects.truncate(count);
         }
+    }
 
-        self.state.last_changed.push_back(pos);
-        let old_piece = self.piece(pos);
-
-        if let Some(piece_type) = self.piece_type(pos) {
-            self.state.pieces.entry(piece_type).and_modify(
-                |board| board.unset(pos)
-            );
+    /// Removes and returns all queued effects in the order they happened.
+    /// Returns an empty list if the queue is disabled.
+    pub fn drain_effects(&mut self) -> Vec<Effect> {
+        match self.effects.as_mut() {
+            Some(effects) => mem::take(effects),
+            None => Vec::new()
         }
-
-        match piece {
-            Piece::Regular(piece_type, directions) => {
-                let width = self.state.width;
-                let height = self.state.height;
-                self.state.pieces.entry(piece_type).and_modify(
-                    |board| board.set(pos)
-                ).or_insert_with(|| {
-                    let mut board = BitBoard::new(width, height);
-                    board.set(pos);
-                    board
-                });
-                self.state.empties.unset(pos);
-                self.set_movable_directions(pos, directions);
-            },
-            Piece::Empty => {
-                self.state.empties.set(pos);
-                self.set_movable_directions(pos, ALL_DIRECTIONS);
-            },
-            Piece::Wall => {
-                self.state.empties.unset(pos);
-                self.set_movable_directions(pos, EnumSet::new());
-            }
-        };
-
-        old_piece
     }
 
-    /// Gets the next match on the board. Matches from pieces that were changed
-    /// earlier are returned first. Matches are always based on the current board
-    /// state, not the board state when the match occurred.
+    /// Sets the cause recorded for pieces cleared by [set_piece()](Board::set_piece)
+    /// and returns the previous cause.
     ///
-    /// Pieces that were changed but did not create a match are skipped.
+    /// # Arguments
     ///
-    /// Regardless of whether a match is found, each piece is unmarked for a
-    /// match check, unless it has been marked multiple times.
-    pub fn next_match(&mut self) -> Option<Match> {
-        let mut next_pos;
-        let mut next_match = None;
-
-        while next_match.is_none() {
-            next_pos = self.state.last_changed.pop_front()?;
-
-            let boards = &self.state.pieces;
+    /// * `cause` - the cause of the clears that follow
+    pub(crate) fn set_clear_cause(&mut self, cause: ClearCause) -> ClearCause {
+        mem::replace(&mut self.clear_cause, cause)
+    }
 
-            next_match = self.patterns.iter().find_map(|pattern| {
-                if let Some(board) = boards.get(&pattern.piece_type()) {
-                    let positions = Board::check_pattern(
-                        board,
-                        pattern.spaces(),
-                        next_pos
-                    )?;
+    /// Adds an effect to the queue if the queue is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `effect` - the effect to queue
+    fn record_effect(&mut self, effect: Effect) {
+        if let Some(effects) = self.effects.as_mut() {
+            effects.push(effect);
+        }
+    }
 
-                    return Some(Match::new(pattern, next_pos, positions));
-                }
+    /// Queues the effect for a space that changed from one piece to another.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the space that changed
+    /// * `old_piece` - the piece that was in the space
+    /// * `new_piece` - the piece that is now in the space
+    fn record_piece_change(&mut self, pos: Pos, old_piece: Piece, new_piece: Piece) {
+        let effect = match (old_piece, new_piece) {
+            (Piece::Regular(..), Piece::Empty) => Effect::Cleared { pos, cause: self.clear_cause },
+            (Piece::Empty, Piece::Regular(..)) => Effect::Spawned { pos },
+            (Piece::Empty, _) | (_, Piece::Empty) => return,
+            (from, to) if from != to => Effect::Converted { pos, from, to },
+            _ => return
+        };
 
-                None
-            });
-        }
+        self.record_effect(effect);
+    }
 
-        next_match
+    /// Checks whether pieces of a type are ingredients. Ingredients are removed
+    /// and delivered when they reach an exit during [resolve_cascade()](Board::resolve_cascade).
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of piece to check
+    pub fn is_ingredient(&self, piece_type: PieceType) -> bool {
+        self.ingredients.contains(&piece_type)
     }
 
-    /// Moves all pieces down to fill the empty spaces below them.
+    /// Makes pieces of a type ingredients or regular pieces. Ingredients are
+    /// removed and delivered when they reach an exit during
+    /// [resolve_cascade()](Board::resolve_cascade). No types are ingredients by default.
     ///
-    /// Pieces will move diagonally and down if there is an empty space there
-    /// and if there is no piece in the column next to them that will fill
-    /// that space. The piece will continue falling diagonally and then down
-    /// until there are no more empty spaces to fill.
-    ///
-    /// A piece may fall diagonally left or right, but if both spaces are open,
-    /// left is preferred.
-    ///
-    /// Pieces will not move past walls or other pieces that are unmovable and
-    /// directly adjacent. However, pieces will move past walls that are diagonally
-    /// adjacent.
-    ///
-    /// Does not fill empty spaces with new pieces.
-    ///
-    /// Marks all the spaces that change for a match check.
+    /// # Arguments
     ///
-    /// Generates a sequence of moves in (from position, to position) format that
-    /// makes the pieces fall naturally.
-    pub fn trickle(&mut self) -> Vec<(Pos, Pos)> {
-        let mut moves = Vec::new();
-
-        for x in 0..self.state.width {
-            moves.append(&mut self.trickle_column(x));
+    /// * `piece_type` - the type of piece to change
+    /// * `is_ingredient` - whether pieces of the type are ingredients
+    pub fn set_ingredient(&mut self, piece_type: PieceType, is_ingredient: bool) {
+        if is_ingredient {
+            self.ingredients.insert(piece_type);
+        } else {
+            self.ingredients.remove(&piece_type);
         }
-        moves.append(&mut self.trickle_diagonally());
+    }
 
-        moves
+    /// Gets the ingredient types on this board.
+    pub fn ingredients(&self) -> &HashSet<PieceType> {
+        &self.ingredients
     }
 
-    /// Replaces a space with a piece and moves it down to fill the empty
-    /// spaces below it.
+    /// Gets the weight of a piece type. See [set_weight()](Board::set_weight).
     ///
-    /// If the piece provided is an empty piece or a wall, this method performs
-    /// identically to [set_piece()].
+    /// # Arguments
     ///
-    /// A regular piece will move diagonally and down if there is an empty space
-    /// there. The piece will continue falling diagonally and then down until
-    /// there are no more empty spaces to fill.
+    /// * `piece_type` - the type of piece to check
+    pub fn weight(&self, piece_type: PieceType) -> u32 {
+        self.weights.get(&piece_type).copied().unwrap_or(0)
+    }
+
+    /// Sets the weight of a piece type. When the board is trickled, a piece
+    /// sinks through the lighter pieces directly beneath it, like a boulder
+    /// sinking through gems, and the lighter pieces rise to take its place.
+    /// Pieces only trade places with pieces that can fall, and pieces with
+    /// the same weight stay in order. Weights also apply to pieces added with
+    /// [add_and_trickle()](Board::add_and_trickle) and, one space at a time,
+    /// to [step()](Board::step). Every type weighs zero by default.
     ///
-    /// The piece may fall diagonally left or right, but if both spaces are open,
-    /// left is preferred.
+    /// # Arguments
     ///
-    /// The piece will not move past walls or other pieces that are unmovable and
-    /// directly adjacent. However, it will move past walls that are diagonally
-    /// adjacent.
+    /// * `piece_type` - the type of piece to change
+    /// * `weight` - the new weight of the type
+    pub fn set_weight(&mut self, piece_type: PieceType, weight: u32) {
+        match weight {
+            0 => self.weights.remove(&piece_type),
+            _ => self.weights.insert(piece_type, weight)
+        };
+    }
+
+    /// Checks whether pieces of a type float. See [set_floating()](Board::set_floating).
     ///
-    /// Does not fill empty spaces with new pieces.
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of piece to check
+    pub fn is_floating(&self, piece_type: PieceType) -> bool {
+        self.floating.contains(&piece_type)
+    }
+
+    /// Makes pieces of a type float, like bubbles, or fall like other pieces.
+    /// When the board is trickled, floating pieces rise straight up into the
+    /// empty spaces above them instead of falling, and other pieces fall into
+    /// the spaces they leave behind. A floating piece only rises if it can
+    /// move north with gravity. Floating pieces never trade places with
+    /// heavier or lighter pieces. [step()](Board::step) raises floating pieces
+    /// one space at a time. No types float by default.
     ///
-    /// Marks all the spaces that change for a match check.
+    /// # Arguments
     ///
-    /// Generates a sequence of moves in (from position, to position) format that
-    /// makes the piece fall naturally.
-    pub fn add_and_trickle(&mut self, pos: Pos, piece: Piece) -> Vec<(Pos, Pos)> {
-        self.set_piece(pos, piece);
-        self.trickle_piece(pos, false)
+    /// * `piece_type` - the type of piece to change
+    /// * `is_floating` - whether pieces of the type float
+    pub fn set_floating(&mut self, piece_type: PieceType, is_floating: bool) {
+        if is_floating {
+            self.floating.insert(piece_type);
+        } else {
+            self.floating.remove(&piece_type);
+        }
     }
 
-    /// Gets the type of a piece at a certain position. If there is no regular piece
-    /// at that position (i.e. it is empty or a wall), Option::None is returned.
+    /// Checks whether pieces of a type can be swapped with walls. See
+    /// [set_wall_swapper()](Board::set_wall_swapper).
     ///
     /// # Arguments
     ///
-    /// * `pos` - the position of the piece whose type to find
-    fn piece_type(&self, pos: Pos) -> Option<PieceType> {
-        self.state.pieces.iter().find_map(|(&piece_type, board)|
-            match board.is_set(pos) {
-                true => Some(piece_type),
-                false => None
-            }
-        )
+    /// * `piece_type` - the type of piece to check
+    pub fn is_wall_swapper(&self, piece_type: PieceType) -> bool {
+        self.wall_swappers.contains(&piece_type)
     }
 
-    /// Gets all of the movable directions for a piece at a given position.
-    /// Empty pieces are always movable in all directions, while walls are
-    /// movable in no directions.
+    /// Lets pieces of a type, such as a drill power-up, be swapped with walls.
+    /// The wall moves into the piece's space along with its
+    /// [type](Board::wall_type) and hit points. The piece must still be able
+    /// to move toward the wall, and locked or frozen spaces still cannot be
+    /// swapped. No types can swap with walls by default, so swapping with a
+    /// wall fails with [SwapError::TargetIsWall].
     ///
     /// # Arguments
     ///
-    /// * `pos` - the position of the piece whose movable directions to find
-    fn movable_directions(&self, pos: Pos) -> EnumSet<Direction> {
-        let mut directions = EnumSet::new();
-
-        for direction in ALL_DIRECTIONS {
-            if self.state.movable_directions[direction as usize].is_set(pos) {
-                directions.insert(direction);
-            }
+    /// * `piece_type` - the type of piece to change
+    /// * `can_swap_walls` - whether pieces of the type can be swapped with walls
+    pub fn set_wall_swapper(&mut self, piece_type: PieceType, can_swap_walls: bool) {
+        if can_swap_walls {
+            self.wall_swappers.insert(piece_type);
+        } else {
+            self.wall_swappers.remove(&piece_type);
         }
-
-        directions
     }
 
-    /// Sets the movable directions for a piece at a given position.
+    /// Makes pieces of a type obstacles, such as crates, that are damaged when
+    /// a match is cleared orthogonally next to them during
+    /// [resolve_cascade()](Board::resolve_cascade). A damaged obstacle becomes
+    /// a piece of the damaged type with the same movable directions, or is
+    /// destroyed if there is no damaged type. Making the damaged type an
+    /// obstacle too creates obstacles that take several hits. No types are
+    /// obstacles by default.
     ///
     /// # Arguments
     ///
-    /// * `pos` - the position of the piece whose movable directions to set
-    /// * `directions` the new movable directions of the piece
-    fn set_movable_directions(&mut self, pos: Pos, directions: EnumSet<Direction>) {
-        for direction in ALL_DIRECTIONS {
-            let ordinal = direction as usize;
-            if directions.contains(direction) {
-                self.state.movable_directions[ordinal].set(pos);
-            } else {
-                self.state.movable_directions[ordinal].unset(pos);
-            }
-        }
+    /// * `piece_type` - the type of piece to change
+    /// * `damaged_type` - the type the obstacle becomes when damaged, or None
+    ///   to destroy it
+    pub fn set_obstacle(&mut self, piece_type: PieceType, damaged_type: Option<PieceType>) {
+        self.obstacles.insert(piece_type, damaged_type);
     }
 
-    /// Checks if the pieces at two positions on the board are both movable in the
-    /// direction in which they would be swapped.
+    /// Makes pieces of a type ordinary pieces instead of obstacles. Returns
+    /// the type's damaged type if it was an obstacle.
     ///
     /// # Arguments
     ///
-    /// * `first` - the position of the first piece to check
-    /// * `second` - the position of the second piece to check
-    fn are_pieces_movable(&self, first: Pos, second: Pos) -> bool {
-        let is_first_movable = self.is_movable(first, second);
-        let is_second_movable = self.is_movable(second, first);
+    /// * `piece_type` - the type of piece to change
+    pub fn remove_obstacle(&mut self, piece_type: PieceType) -> Option<Option<PieceType>> {
+        self.obstacles.remove(&piece_type)
+    }
 
-        is_first_movable && is_second_movable
+    /// Gets the obstacle types on this board and the type each one becomes
+    /// when damaged. See [set_obstacle()](Board::set_obstacle).
+    pub fn obstacles(&self) -> &HashMap<PieceType, Option<PieceType>> {
+        &self.obstacles
     }
 
-    /// Checks if a piece is movable vertically and horizontally.
+    /// Gets the positions where ingredients leave the board. By default,
+    /// every position in the bottom row is an exit.
+    pub fn exits(&self) -> &BitBoard {
+        &self.exits
+    }
+
+    /// Marks or unmarks a position as an exit where ingredients leave the board.
     ///
     /// # Arguments
     ///
-    /// * `from` - the current position of the piece
-    /// * `to` - the position where the piece will be moved
-    fn is_movable(&self, from: Pos, to: Pos) -> bool {
-        self.is_vertically_movable(from, to) && self.is_horizontally_movable(from, to)
+    /// * `pos` - the position to change
+    /// * `is_exit` - whether the position is an exit
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board.
+    pub fn set_exit(&mut self, pos: Pos, is_exit: bool) {
+        if is_exit {
+            self.exits.set(pos);
+        } else {
+            self.exits.unset(pos);
+        }
     }
 
-    /// Checks if a piece is vertically movable from one position to another.
-    /// If there is no vertical change between the two positions, the piece
-    /// is considered movable.
+    /// Gets the entry position linked to a portal's exit position, if there
+    /// is a portal at that position.
     ///
     /// # Arguments
     ///
-    /// * `from` - the current position of the piece
-    /// * `to` - the position where the piece will be moved
-    fn is_vertically_movable(&self, from: Pos, to: Pos) -> bool {
-        if to.y() > from.y() {
-            return self.state.movable_directions[Direction::North as usize].is_set(from);
-        } else if to.y() < from.y() {
-            return self.state.movable_directions[Direction::South as usize].is_set(from);
-        }
-
-        true
+    /// * `exit` - the exit position of the portal
+    pub fn portal(&self, exit: Pos) -> Option<Pos> {
+        self.portals.get(&exit).copied()
     }
 
-    /// Checks if a piece is horizontally movable from one position to another.
-    /// If there is no horizontal change between the two positions, the piece
-    /// is considered movable.
+    /// Links a portal's exit position to an entry position. When a piece
+    /// trickles down to the exit and cannot fall any further, it moves to
+    /// the entry if the entry is empty and continues falling from there.
+    /// Replaces any portal that already uses the same exit.
     ///
     /// # Arguments
     ///
-    /// * `from` - the current position of the piece
-    /// * `to` - the position where the piece will be moved
-    fn is_horizontally_movable(&self, from: Pos, to: Pos) -> bool {
-        if to.x() > from.x() {
-            return self.state.movable_directions[Direction::East as usize].is_set(from);
-        } else if to.x() < from.x() {
-            return self.state.movable_directions[Direction::West as usize].is_set(from);
+    /// * `exit` - the position where pieces leave through the portal
+    /// * `entry` - the position where pieces reappear
+    ///
+    /// # Panics
+    ///
+    /// Panics if either position is outside the board.
+    pub fn add_portal(&mut self, exit: Pos, entry: Pos) {
+        if !self.is_within_board(exit) || !self.is_within_board(entry) {
+            panic!("Tried to add portal outside board: {} to {}", exit, entry);
         }
 
-        true
+        self.portals.insert(exit, entry);
     }
 
-    /// Checks for a pattern that includes a specific position on the board. Looks
-    /// for all variants of a pattern (all possible patterns that include the required
-    /// position). Returns the positions on the board that correspond to that pattern
-    /// if there is a match.
+    /// Removes a portal and returns its entry position, if there was a portal
+    /// at the given exit position.
     ///
     /// # Arguments
     ///
-    /// * `board` - the board to check for a pattern
-    /// * `pattern` - the set of relative positions that represent a pattern
-    /// * `pos` - the position that must be included in a match
-    fn check_pattern(board: &BitBoard, pattern: &PosSet, pos: Pos) -> Option<PosSet> {
-        pattern.iter().find_map(|&original| {
-
-            // Don't check variants outside the board
-            if original.x() > pos.x() || original.y() > pos.y() {
-                return None;
-            }
-
-            Board::check_variant(board, pattern, pos - original)
-        })
+    /// * `exit` - the exit position of the portal to remove
+    pub fn remove_portal(&mut self, exit: Pos) -> Option<Pos> {
+        self.portals.remove(&exit)
     }
 
-    /// Checks for a single variant of a pattern and returns the corresponding positions
-    /// on the board if found.
+    /// Locks every space in a rectangle, including the spaces on its edges, such
+    /// as while an animation plays or while a timed obstacle covers the region.
+    /// Pieces in locked spaces cannot be swapped or shuffled and do not fall,
+    /// and falling pieces treat locked spaces like walls. The corners may be
+    /// given in any order, and any part of the rectangle outside the board is
+    /// ignored.
     ///
     /// # Arguments
     ///
-    /// * `board` - the board to check for a variant
-    /// * `pattern` - the set of relative positions that represent a variant
-    /// * `new_origin` - the origin to use for the pattern positions so that they
-    ///                  correspond to actual positions on the board
-    fn check_variant(board: &BitBoard, pattern: &PosSet, new_origin: Pos) -> Option<PosSet> {
-        let grid_pos = Board::change_origin(pattern, new_origin);
-        match grid_pos.iter().all(|&pos| board.is_set(pos)) {
-            true => Some(grid_pos),
-            false => None
-        }
+    /// * `from` - one corner of the rectangle
+    /// * `to` - the opposite corner of the rectangle
+    pub fn lock_region(&mut self, from: Pos, to: Pos) {
+        let positions: Vec<Pos> = self.pieces_in_rect(from, to).map(|(pos, _)| pos).collect();
+        positions.into_iter().for_each(|pos| self.locked.set(pos));
     }
 
-    /// Changes the origin of a set of points.
+    /// Unlocks every space in a rectangle, including the spaces on its edges.
+    /// The corners may be given in any order, and any part of the rectangle
+    /// outside the board is ignored. Unlocked pieces do not fall until the
+    /// board is trickled again.
     ///
     /// # Arguments
     ///
-    /// * `positions` - the positions to change the origin of
-    /// * `origin` - the new origin to use for the positions
-    fn change_origin(positions: &PosSet, origin: Pos) -> PosSet {
-        positions.iter().map(|&original| original + origin).collect()
+    /// * `from` - one corner of the rectangle
+    /// * `to` - the opposite corner of the rectangle
+    pub fn unlock_region(&mut self, from: Pos, to: Pos) {
+        let positions: Vec<Pos> = self.pieces_in_rect(from, to).map(|(pos, _)| pos).collect();
+        positions.into_iter().for_each(|pos| self.locked.unset(pos));
     }
 
-    /// Moves all the pieces in a column down to fill empty spaces directly beneath them.
+    /// Checks whether a space is locked. See [lock_region()](Board::lock_region).
     ///
     /// # Arguments
     ///
-    /// * `x` - the x coordinate of the column to trickle
-    fn trickle_column(&mut self, x: u8) -> Vec<(Pos, Pos)> {
-        let mut empty_spaces = VecDeque::new();
-        let mut moves = Vec::new();
-
-        for y in 0..self.state.height {
-            let current_pos = Pos::new(x, y);
-            if self.state.empties.is_set(current_pos) {
-                empty_spaces.push_back(y);
-            } else if self.state.movable_directions[Direction::South as usize].is_set(current_pos) {
-                if let Some(space_to_fill) = empty_spaces.pop_front() {
-                    self.swap_always(current_pos, Pos::new(x, space_to_fill));
-                    empty_spaces.push_back(y);
-                    moves.push((Pos::new(x, y), Pos::new(x, space_to_fill)));
-                }
-            } else {
-                empty_spaces.clear();
-            }
+    /// * `pos` - the position to check
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board.
+    pub fn is_locked(&self, pos: Pos) -> bool {
+        if !self.is_within_board(pos) {
+            panic!("Tried to check lock outside board: {}", pos);
         }
 
-        moves
+        self.locked.is_set(pos)
     }
 
-    /// Moves all pieces in the board diagonally and down until they can no longer be moved.
-    /// Should be called after [trickle_column()](Board::trickle_column) is run on all columns.
-    fn trickle_diagonally(&mut self) -> Vec<(Pos, Pos)> {
-        let mut moves = Vec::new();
+    /// Prevents matches in every space in a rectangle, including the spaces on
+    /// its edges, such as a dispenser lane or staging rows above the visible
+    /// board. Pieces in these spaces still fall, swap, and hold their place,
+    /// but no match can include them, and falling pieces do not mark them for
+    /// a match check. The corners may be given in any order, and any part of
+    /// the rectangle outside the board is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - one corner of the rectangle
+    /// * `to` - the opposite corner of the rectangle
+    pub fn prevent_matches_in_region(&mut self, from: Pos, to: Pos) {
+        let positions: Vec<Pos> = self.pieces_in_rect(from, to).map(|(pos, _)| pos).collect();
+        positions.into_iter().for_each(|pos| self.match_prevented.set(pos));
+    }
 
-        for y in 0..self.state.height {
-            for x in 0..self.state.width {
-                moves.append(&mut self.trickle_piece(Pos::new(x, y), true));
-            }
+    /// Allows matches again in every space in a rectangle, including the spaces
+    /// on its edges. The corners may be given in any order, and any part of the
+    /// rectangle outside the board is ignored. Pieces in the rectangle are not
+    /// marked for a match check.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - one corner of the rectangle
+    /// * `to` - the opposite corner of the rectangle
+    pub fn allow_matches_in_region(&mut self, from: Pos, to: Pos) {
+        let positions: Vec<Pos> = self.pieces_in_rect(from, to).map(|(pos, _)| pos).collect();
+        positions.into_iter().for_each(|pos| self.match_prevented.unset(pos));
+    }
+
+    /// Checks whether matches are prevented in a space. See
+    /// [prevent_matches_in_region()](Board::prevent_matches_in_region).
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to check
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board.
+    pub fn is_match_prevented(&self, pos: Pos) -> bool {
+        if !self.is_within_board(pos) {
+            panic!("Tried to check match prevention outside board: {}", pos);
         }
 
-        moves
+        self.match_prevented.is_set(pos)
     }
 
-    /// Moves a piece down and diagonally until it can no longer be moved.
-    /// Returns the list of swaps that makes the piece fall naturally. See
-    /// [trickle()] for a complete list of rules on how the piece is
-    /// trickled.
+    /// Makes the top rows of the board hidden staging rows that hold the pieces
+    /// waiting to fall into the visible board. The spawner fills the staging
+    /// rows during a [cascade](Board::resolve_cascade), and pieces trickle from
+    /// them into the visible rows below, so new pieces can be shown falling in
+    /// from above.
+    ///
+    /// Matches are prevented in staging rows (see
+    /// [prevent_matches_in_region()](Board::prevent_matches_in_region)), and
+    /// their pieces cannot be swapped, rotated, or shuffled. Matches are allowed
+    /// again in rows that are no longer staging rows. Rotating or flipping the
+    /// board removes the staging rows.
     ///
     /// # Arguments
     ///
-    /// * `piece_pos` - the position of the piece to trickle
-    /// * `check_adj` - whether to check if the horizontally adjacent piece
-    ///                 will fall to fill the spot when all pieces in the row
-    ///                 are trickled
-    fn trickle_piece(&mut self, piece_pos: Pos, check_adj: bool) -> Vec<(Pos, Pos)> {
-        let mut moves = Vec::new();
+    /// * `rows` - the number of rows at the top of the board to use for staging
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more staging rows than rows on the board.
+    pub fn set_staging_rows(&mut self, rows: u8) {
+        let height = self.state.height;
+        if rows > height {
+            panic!("Tried to use {} staging rows on a board with {} rows", rows, height);
+        }
 
-        if self.state.empties.is_set(piece_pos) {
-            return moves;
+        let right = self.state.width.saturating_sub(1);
+        let top = height.saturating_sub(1);
+        if self.staging_rows > 0 {
+            self.allow_matches_in_region(Pos::new(0, height - self.staging_rows), Pos::new(right, top));
+        }
+        if rows > 0 {
+            self.prevent_matches_in_region(Pos::new(0, height - rows), Pos::new(right, top));
         }
 
-        let mut previous_trickled_pos;
-        let mut current_trickled_pos = piece_pos;
+        self.staging_rows = rows;
+    }
 
-        loop {
-            previous_trickled_pos = current_trickled_pos;
-            current_trickled_pos = self.trickle_piece_down(previous_trickled_pos);
-            if previous_trickled_pos != current_trickled_pos {
-                moves.push((previous_trickled_pos, current_trickled_pos));
-            }
+    /// Gets the number of hidden staging rows at the top of the board. See
+    /// [set_staging_rows()](Board::set_staging_rows).
+    pub fn staging_rows(&self) -> u8 {
+        self.staging_rows
+    }
 
-            previous_trickled_pos = current_trickled_pos;
-            current_trickled_pos = self.trickle_piece_diagonally(
-                previous_trickled_pos,
-                check_adj
-            );
+    /// Gets the number of rows that are not staging rows, which are the rows
+    /// that players can see.
+    pub fn visible_height(&self) -> u8 {
+        self.state.height - self.staging_rows
+    }
 
-            if previous_trickled_pos == current_trickled_pos {
-                break;
-            } else {
-                moves.push((previous_trickled_pos, current_trickled_pos));
-            }
+    /// Checks whether a space is in a hidden staging row. See
+    /// [set_staging_rows()](Board::set_staging_rows).
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to check
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board.
+    pub fn is_staging(&self, pos: Pos) -> bool {
+        if !self.is_within_board(pos) {
+            panic!("Tried to check staging row outside board: {}", pos);
         }
 
-        moves
+        pos.y() >= self.visible_height()
     }
 
-    /// Moves a piece diagonally (down and horizontally). If the space to the left
-    /// is open, then the piece moves down and to the left. Otherwise, it moves down
-    /// and to the right if that space is open. Returns the new position of the piece.
+    /// Adds a tag to a piece type, such as a color or a category. Tags let games
+    /// refer to groups of piece types without listing every type.
     ///
     /// # Arguments
     ///
-    /// * `piece_pos` - the current position of the piece
-    /// * `check_adj` - whether to check if the horizontally adjacent piece
-    ///                 will fall to fill the spot when all pieces in the row
-    ///                 are trickled
-    fn trickle_piece_diagonally(&mut self, piece_pos: Pos, check_adj: bool) -> Pos {
-        let mut diagonally_trickled_pos = self.trickle_piece_to_side(piece_pos, true, check_adj);
-        if diagonally_trickled_pos == piece_pos {
-            diagonally_trickled_pos = self.trickle_piece_to_side(piece_pos, false, check_adj);
-        }
+    /// * `piece_type` - the type of piece to tag
+    /// * `tag` - the tag to add
+    pub fn add_tag(&mut self, piece_type: PieceType, tag: &str) {
+        self.tags.entry(tag.to_string()).or_default().insert(piece_type);
+    }
 
-        diagonally_trickled_pos
+    /// Removes a tag from a piece type. Returns true if the type had the tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of piece to remove the tag from
+    /// * `tag` - the tag to remove
+    pub fn remove_tag(&mut self, piece_type: PieceType, tag: &str) -> bool {
+        self.tags.get_mut(tag).is_some_and(|types| types.remove(&piece_type))
     }
 
-    /// Moves a piece one space down and one space horizontally if there is an
-    /// empty space there. Returns the new position of the piece.
+    /// Checks whether a piece type has a tag.
     ///
     /// # Arguments
     ///
-    /// * `current_pos` - the current position of the piece to move
-    /// * `to_west` - whether to move the piece west (or east if false)
-    /// * `check_adj` - whether to check if the horizontally adjacent piece
-    ///                 will fall to fill the spot when all pieces in the row
-    ///                 are trickled
-    fn trickle_piece_to_side(&mut self, current_pos: Pos, to_west: bool, check_adj: bool) -> Pos {
-        if !self.can_move_pos_down_diagonally(current_pos, to_west) {
-            return current_pos;
-        }
-
-        let empty_pos = Board::move_pos_down_diagonally(current_pos, to_west);
-        let is_empty_pos = self.state.empties.is_set(empty_pos);
+    /// * `piece_type` - the type of piece to check
+    /// * `tag` - the tag to look for
+    pub fn has_tag(&self, piece_type: PieceType, tag: &str) -> bool {
+        self.tags.get(tag).is_some_and(|types| types.contains(&piece_type))
+    }
 
-        let horizontal_dir_board = match to_west {
-            true => &self.state.movable_directions[Direction::West as usize],
-            false => &self.state.movable_directions[Direction::East as usize]
-        };
-        let vertical_dir_board = &self.state.movable_directions[Direction::South as usize];
-        let is_movable = horizontal_dir_board.is_set(current_pos) &&
-            vertical_dir_board.is_set(current_pos);
+    /// Gets the positions of all pieces whose type has a tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - the tag of the pieces to find
+    pub fn pieces_with_tag(&self, tag: &str) -> BitBoard {
+        self.tags.get(tag)
+            .and_then(|types| Board::union_boards(types.iter(), &self.state.pieces))
+            .unwrap_or_else(|| BitBoard::new(self.state.width, self.state.height))
+    }
 
-        let adjacent_pos = Pos::new(empty_pos.x(), current_pos.y());
-        let will_adj_fill_space = check_adj && vertical_dir_board.is_set(adjacent_pos)
-            && !self.state.empties.is_set(adjacent_pos);
+    /// Gets the counter for the piece at a position, such as the number of turns
+    /// until a bomb explodes. Returns None if the piece has no counter.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece
+    pub fn counter(&self, pos: Pos) -> Option<u32> {
+        self.state.counters.get(&pos).copied()
+    }
 
-        if !is_empty_pos || !is_movable || will_adj_fill_space {
-            return current_pos;
+    /// Sets the counter for the piece at a position. The counter moves with the
+    /// piece when it is swapped or trickled and is removed when the piece is
+    /// replaced. A counter of zero is removed on the next [tick()](Board::tick)
+    /// without being decremented.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece
+    /// * `value` - the new value of the counter
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board.
+    pub fn set_counter(&mut self, pos: Pos, value: u32) {
+        if !self.is_within_board(pos) {
+            panic!("Tried to set counter outside board: {}", pos);
         }
 
-        self.swap_always(current_pos, empty_pos);
-
-        empty_pos
+        self.state.counters.insert(pos, value);
     }
 
-    /// Moves a piece down until it is moved into the lowest empty space directly
-    /// below it. Returns the new position of the piece
+    /// Removes the counter for the piece at a position and returns its value,
+    /// if the piece had a counter.
     ///
     /// # Arguments
     ///
-    /// * `piece_pos` - the current position of the piece to move
-    fn trickle_piece_down(&mut self, piece_pos: Pos) -> Pos {
-        let vertical_dir_board = &self.state.movable_directions[Direction::South as usize];
-        if !vertical_dir_board.is_set(piece_pos){
-            return piece_pos;
-        }
+    /// * `pos` - the position of the piece
+    pub fn remove_counter(&mut self, pos: Pos) -> Option<u32> {
+        self.state.counters.remove(&pos)
+    }
 
-        let mut next_y = piece_pos.y();
-        while next_y > 0 && self.state.empties.is_set(Pos::new(piece_pos.x(), next_y - 1)) {
-            next_y -= 1;
+    /// Gets the number of turns that the piece at a position stays frozen,
+    /// or None if the piece is not frozen.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece
+    pub fn frozen_turns(&self, pos: Pos) -> Option<u32> {
+        self.state.frozen.get(&pos).copied()
+    }
+
+    /// Freezes the piece at a position for a number of turns, like a piece
+    /// covered in ice that melts over time. A frozen piece cannot be swapped,
+    /// rotated, or shuffled, but it still falls and matches. Each
+    /// [tick()](Board::tick) takes one turn off the timer, and the piece thaws
+    /// when the timer reaches zero. The timer moves with the piece and is
+    /// removed when the piece is replaced. Freezing a piece for zero turns
+    /// thaws it.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece
+    /// * `turns` - the number of ticks until the piece thaws
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board.
+    pub fn freeze(&mut self, pos: Pos, turns: u32) {
+        if !self.is_within_board(pos) {
+            panic!("Tried to freeze piece outside board: {}", pos);
         }
-        self.swap_always(piece_pos, Pos::new(piece_pos.x(), next_y));
 
-        Pos::new(piece_pos.x(), next_y)
+        match turns {
+            0 => self.state.frozen.remove(&pos),
+            _ => self.state.frozen.insert(pos, turns)
+        };
     }
 
-    /// Swaps two pieces regardless of the swap rules. Pieces more than one
-    /// space apart can be swapped. Always successful. Marks both spaces
-    /// for a match check if they are different.
+    /// Thaws the piece at a position immediately and returns the number of
+    /// turns it had left, if it was frozen.
     ///
     /// # Arguments
     ///
-    /// * `first` - the position of a piece to swap
-    /// * `second` - the position of another piece to swap
-    fn swap_always(&mut self, first: Pos, second: Pos) {
-        if first == second {
-            return;
-        }
+    /// * `pos` - the position of the piece
+    pub fn thaw(&mut self, pos: Pos) -> Option<u32> {
+        self.state.frozen.remove(&pos)
+    }
 
-        self.state.last_changed.push_back(first);
-        self.state.last_changed.push_back(second);
+    /// Adds a behavior that runs on every [tick()](Board::tick), after all the
+    /// behaviors that were already added.
+    ///
+    /// # Arguments
+    ///
+    /// * `behavior` - the behavior to add
+    pub fn add_tick_behavior(&mut self, behavior: Box<dyn TickBehavior>) {
+        self.tick_behaviors.push(behavior);
+    }
 
-        self.state.empties.swap(first, second);
-        self.state.movable_directions[0].swap(first, second);
-        self.state.movable_directions[1].swap(first, second);
-        self.state.movable_directions[2].swap(first, second);
-        self.state.movable_directions[3].swap(first, second);
+    /// Advances the board by one turn. Every counter is decremented, and the
+    /// counters that reach zero are removed. Then each tick behavior runs in
+    /// the order they were added with the positions of the removed counters.
+    /// Finally, every [frozen](Board::freeze) piece loses one turn, and the
+    /// pieces whose timers reach zero thaw.
+    ///
+    /// Returns the positions whose counters reached zero, row by row from the
+    /// bottom row.
+    pub fn tick(&mut self) -> Vec<Pos> {
+        self.tick_with(&mut |_: &BoardEvent| {})
+    }
 
-        let possible_first_type = self.piece_type(first);
-        let possible_second_type = self.piece_type(second);
+    /// Advances the board by one turn like [tick()](Board::tick) and sends the
+    /// events caused by the tick behaviors, such as cleared or consumed pieces,
+    /// to an observer. A [BoardEvent::Thawed] event is sent for each piece that
+    /// thaws.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer` - receives each event caused by the tick behaviors
+    pub fn tick_with(&mut self, observer: &mut dyn BoardObserver) -> Vec<Pos> {
+        self.observed(observer, Board::tick_observed)
+    }
 
-        // We don't want to undo the swap if both pieces are of the same type
-        if possible_first_type != possible_second_type {
-            if let Some(first_type) = possible_first_type {
-                self.state.pieces.entry(first_type).and_modify(
-                    |board| board.swap(first, second)
-                );
+    /// Advances the board by one turn. See [tick_with()](Board::tick_with).
+    ///
+    /// # Arguments
+    ///
+    /// * `observer` - receives each event caused by the tick behaviors
+    fn tick_observed(&mut self, observer: &mut dyn BoardObserver) -> Vec<Pos> {
+        let mut expired = Vec::new();
+        for (&pos, counter) in self.state.counters.iter_mut() {
+            *counter = counter.saturating_sub(1);
+            if *counter == 0 {
+                expired.push(pos);
             }
+        }
+        expired.sort_by_key(|pos| (pos.y(), pos.x()));
+        expired.iter().for_each(|pos| { self.state.counters.remove(pos); });
+
+        let previous_cause = self.set_clear_cause(ClearCause::Tick);
+        let mut behaviors = mem::take(&mut self.tick_behaviors);
+        for behavior in behaviors.iter_mut() {
+            behavior.on_tick(self, &expired, observer);
+        }
+        self.set_clear_cause(previous_cause);
+
+        // Keep any behaviors that were added while ticking
+        behaviors.append(&mut self.tick_behaviors);
+        self.tick_behaviors = behaviors;
 
-            if let Some(second_type) = possible_second_type {
-                self.state.pieces.entry(second_type).and_modify(
-                    |board| board.swap(first, second)
-                );
+        let mut thawed = Vec::new();
+        for (&pos, turns) in self.state.frozen.iter_mut() {
+            *turns = turns.saturating_sub(1);
+            if *turns == 0 {
+                thawed.push(pos);
             }
         }
+        thawed.sort_by_key(|pos| (pos.y(), pos.x()));
+        for pos in thawed {
+            self.state.frozen.remove(&pos);
+            observer.on_event(&BoardEvent::Thawed { pos });
+        }
+
+        self.assert_invariants();
+        expired
     }
 
-    /// Checks if a position can move one space down and one space horizontally
-    /// and still be inside the board.
+    /// Sets the spawner that fills a column when matches are resolved, instead
+    /// of the spawner passed to [resolve_cascade()](Board::resolve_cascade).
+    /// For example, a column could only drop ingredients. A spawner that always
+    /// returns [Piece::Empty] makes a dead column that never refills.
+    ///
+    /// Column spawners stay with their column index when the board is rotated
+    /// or mirrored.
     ///
     /// # Arguments
     ///
-    /// * `pos` - the position to move
-    /// * `to_west` - whether to move the position west (or east if false)
-    fn can_move_pos_down_diagonally(&self, pos: Pos, to_west: bool) -> bool {
-        match to_west {
-            true => pos.x() > 0 && pos.y() > 0,
-            false => pos.x() < self.state.width - 1 && pos.y() > 0
+    /// * `x` - the x coordinate of the column
+    /// * `spawner` - chooses the new pieces that fill the column
+    ///
+    /// # Panics
+    ///
+    /// Panics if the column is outside the board.
+    pub fn set_spawner_for_column(&mut self, x: u8, spawner: Box<dyn Spawner>) {
+        if x >= self.state.width {
+            panic!("Tried to set spawner for column outside board: {}", x);
         }
+
+        self.column_spawners.insert(x, spawner);
     }
 
-    /// Moves a position one space down and one space horizontally.
+    /// Removes the spawner for a column and returns it, if the column had
+    /// its own spawner. The column is filled by the default spawner again.
     ///
     /// # Arguments
     ///
-    /// * `pos` - the position to move
-    /// * `to_west` - whether to move the position west (or east if false)
-    fn move_pos_down_diagonally(pos: Pos, to_west: bool) -> Pos {
-        match to_west {
-            true => Pos::new(pos.x() - 1, pos.y() - 1),
-            false => Pos::new(pos.x() + 1, pos.y() - 1)
-        }
+    /// * `x` - the x coordinate of the column
+    pub fn remove_spawner_for_column(&mut self, x: u8) -> Option<Box<dyn Spawner>> {
+        self.column_spawners.remove(&x)
     }
 
-    /// Checks if a given position is inside the board.
+    /// Sets the region where [resolve_cascade()](Board::resolve_cascade) finds
+    /// matches. Only matches whose pieces are all inside the region are cleared,
+    /// as with [next_match_in()](Board::next_match_in). Pieces outside the region
+    /// still fall and refill. By default, the whole board is used.
     ///
     /// # Arguments
     ///
-    /// * `pos` - the position to check
-    fn is_within_board(&self, pos: Pos) -> bool {
-        pos.x() < self.state.width && pos.y() < self.state.height
+    /// * `region` - the positions that a match must be inside, or None to use
+    ///   the whole board
+    pub fn set_cascade_region(&mut self, region: Option<PosSet>) {
+        self.cascade_region = region;
     }
 
-}
-
-impl Debug for Board {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("")
-            .field(&self.patterns)
-            .field(&self.state)
-            .finish()
+    /// Gets the region where [resolve_cascade()](Board::resolve_cascade) finds
+    /// matches, or None if the whole board is used.
+    pub fn cascade_region(&self) -> Option<&PosSet> {
+        self.cascade_region.as_ref()
     }
-}
-
-impl Display for Board {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut str = String::new();
-
-        for y in (0..self.state.height).rev() {
-            for x in 0..self.state.width {
-                str.push_str(&self.piece(Pos::new(x, y)).to_string());
-            }
 
-            str.push('\n');
-        }
+    /// Gets the spawners that replace the default spawner for some columns.
+    pub(crate) fn column_spawners_mut(&mut self) -> &mut HashMap<u8, Box<dyn Spawner>> {
+        &mut self.column_spawners
+    }
 
-        write!(f, "{}", str)
+    /// Restarts the board's random number generator from a seed. Boards with
+    /// the same seed make the same random choices, such as which piece a
+    /// spreading blocker consumes.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - the new seed for the generator
+    pub fn set_seed(&mut self, seed: u64) {
+        self.state.rng = Rng::new(seed);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::board::{Board, BoardState};
-    use crate::position::Pos;
-    use crate::piece::{Piece, Direction, ALL_DIRECTIONS};
-    use std::collections::{HashSet};
-    use crate::matching::MatchPattern;
-    use enumset::{enum_set};
-    use std::panic;
+    /// Gets the board's random number generator. The generator is saved with
+    /// the board state.
+    pub fn rng_mut(&mut self) -> &mut Rng {
+        &mut self.state.rng
+    }
 
-    #[test]
-    #[should_panic]
-    fn get_piece_out_of_bounds_panics() {
-        let board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
-        board.piece(Pos::new(16, 16));
+    /// Gets a read-only view of the board.
+    pub fn view(&self) -> BoardView<'_> {
+        BoardView::new(self)
     }
 
-    #[test]
-    fn swap_adjacent_all_rules_passed_swapped() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
-        let type1 = 'f';
-        let type2 = 's';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+    /// Gets the current state of the board, which is (de)serializable and is
+    /// useful for saving the board. Use other board methods to mutate the
+    /// board's state.
+    pub fn state(&self) -> &BoardState {
+        &self.state
+    }
 
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), piece2);
+    /// Gets everything that happened since the last call to
+    /// [end_turn()](Board::end_turn) without ending the turn.
+    pub fn turn_report(&self) -> &TurnReport {
+        &self.turn_report
+    }
 
-        assert!(board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
-        match board.piece(Pos::new(1, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
-            _ => panic!("Wrong piece")
-        };
-        match board.piece(Pos::new(1, 3)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
+    /// Gets the report for the current turn to record what happens on the board.
+    pub(crate) fn turn_report_mut(&mut self) -> &mut TurnReport {
+        &mut self.turn_report
     }
 
-    #[test]
-    fn swap_non_adjacent_all_rules_passed_swapped() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
-        let type1 = 'f';
-        let type2 = 's';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+    /// Runs an operation that reports events to an observer, also adding each
+    /// event to the [turn report](Board::turn_report) and sending it to the
+    /// board's analytics sink, if it has one.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer` - receives the events that the operation reports
+    /// * `operation` - changes the board and reports events to the given observer
+    pub(crate) fn observed<T>(&mut self, observer: &mut dyn BoardObserver,
+                              operation: impl FnOnce(&mut Board, &mut dyn BoardObserver) -> T) -> T {
+        let mut report = TurnReport::default();
+        let result = self.with_analytics(&mut |event: &BoardEvent| {
+            observer.on_event(event);
+            report.on_event(event);
+        }, operation);
 
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(14, 15), piece2);
+        self.turn_report.merge(report);
+        result
+    }
 
-        assert!(board.swap_pieces(Pos::new(1, 2), Pos::new(14, 15)));
-        match board.piece(Pos::new(1, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
-            _ => panic!("Wrong piece")
-        };
-        match board.piece(Pos::new(14, 15)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
+    /// Gets the board's analytics stream to attach, detach, or send to it.
+    #[cfg(feature = "analytics")]
+    pub(crate) fn analytics_mut(&mut self) -> &mut Option<AnalyticsStream> {
+        &mut self.analytics
     }
 
-    #[test]
-    fn swap_rules_violated_not_swapped() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| false)
-        ]);
-        let type1 = 'f';
-        let type2 = 's';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+    /// Runs an operation that reports events to an observer. Without the
+    /// `analytics` feature, there is no sink to also send the events to.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer` - receives the events that the operation reports
+    /// * `operation` - changes the board and reports events to the given observer
+    #[cfg(not(feature = "analytics"))]
+    pub(crate) fn with_analytics<T>(&mut self, observer: &mut dyn BoardObserver,
+                                    operation: impl FnOnce(&mut Board, &mut dyn BoardObserver) -> T) -> T {
+        operation(self, observer)
+    }
 
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), piece2);
+    /// Replaces the board's state with a state that was previously taken from
+    /// this board, such as to undo simulated moves.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - the state to restore
+    pub(crate) fn restore_state(&mut self, state: BoardState) {
+        let is_resized = state.width != self.state.width || state.height != self.state.height;
+        self.state = state;
+        if is_resized {
+            self.compiled_patterns = CompiledPattern::compile_all(&self.patterns, self.state.width, self.state.height);
+        }
+        self.refresh_zobrist();
+    }
 
-        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
-        match board.piece(Pos::new(1, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
-        match board.piece(Pos::new(1, 3)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
-            _ => panic!("Wrong piece")
-        };
+    /// Gets the Zobrist hash of the board's pieces, which is kept up to date as
+    /// the board changes, so getting it takes constant time. Boards with the
+    /// same piece type, empty space, or wall at every position have the same
+    /// hash, so the hash can be used as a key in transposition tables for
+    /// searches or to detect repeated states in replays. Different states may
+    /// rarely have the same hash.
+    ///
+    /// Movable directions, counters, blocks, the positions marked for a match
+    /// check, and the random number generator are not included.
+    pub fn zobrist(&self) -> u64 {
+        self.zobrist
     }
 
-    #[test]
-    fn swap_rules_violated_short_circuits() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| false),
-            Box::new(|_, _, _| { panic!("Should short circuit before this") })
-        ]);
-        let type1 = 'f';
-        let type2 = 's';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+    /// Recomputes the board's [Zobrist hash](Board::zobrist) from scratch after
+    /// its state was replaced.
+    pub(crate) fn refresh_zobrist(&mut self) {
+        self.zobrist = compute_zobrist(&self.state);
+    }
 
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), piece2);
+    /// Updates the board's [Zobrist hash](Board::zobrist) after the piece at a
+    /// position changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position that changed
+    /// * `old_piece` - the piece that was at the position
+    /// * `new_piece` - the piece that is now at the position
+    fn update_zobrist(&mut self, pos: Pos, old_piece: Piece, new_piece: Piece) {
+        self.zobrist ^= zobrist_key(pos, old_piece) ^ zobrist_key(pos, new_piece);
+    }
 
-        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
+    /// Gets the board's state for changes that other modules in the crate make
+    /// directly, which must keep the state's bitboards consistent.
+    pub(crate) fn state_mut(&mut self) -> &mut BoardState {
+        &mut self.state
+    }
+
+    /// Gets a piece at the given position on the board. By default,
+    /// all pieces on the board are walls.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - position of the piece to get
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board.
+    pub fn piece(&self, pos: Pos) -> Piece {
+        if !self.is_within_board(pos) {
+            panic!("Tried to get piece outside board: {}", pos);
+        }
+
+        if self.state.empties.is_set(pos) {
+            return Piece::Empty;
+        }
+
+        let possible_type = self.piece_type(pos);
+        match possible_type {
+            None => Piece::Wall,
+            Some(piece_type) => Piece::Regular(piece_type, self.movable_directions(pos))
+        }
+    }
+
+    /// Gets the bitboard that contains the positions of all pieces that are movable
+    /// in a direction. Empty pieces are always movable, and walls never are.
+    ///
+    /// # Arguments
+    ///
+    /// * `direction` - the direction to get the bitboard for
+    pub fn movable_in(&self, direction: Direction) -> &BitBoard {
+        &self.state.movable_directions[direction as usize]
+    }
+
+    /// Changes the directions in which a regular piece is movable without changing
+    /// its type, such as to freeze a row of pieces in place. Empty pieces and walls
+    /// are not affected. Returns true if the piece is a regular piece.
+    ///
+    /// The position is not marked for a match check because the piece's type
+    /// does not change.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to change
+    /// * `directions` - all the directions in which the piece will be movable
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board.
+    pub fn set_movable(&mut self, pos: Pos, directions: EnumSet<Direction>) -> bool {
+        if !self.is_within_board(pos) {
+            panic!("Tried to set movable directions outside board: {}", pos);
+        }
+
+        if self.piece_type(pos).is_none() {
+            return false;
+        }
+
+        self.set_movable_directions(pos, directions);
+        self.set_gravity_directions(pos, directions);
+        true
+    }
+
+    /// Makes a regular piece movable in one more direction, keeping its type
+    /// and its other directions, like [set_movable()](Board::set_movable).
+    /// Gravity can also move the piece in that direction. Empty pieces and
+    /// walls are not affected. Returns true if the piece is a regular piece.
+    ///
+    /// The position is not marked for a match check.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to change
+    /// * `direction` - the direction in which the piece will be movable
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board.
+    pub fn make_movable(&mut self, pos: Pos, direction: Direction) -> bool {
+        self.change_direction(pos, direction, true)
+    }
+
+    /// Makes a regular piece unmovable in one direction, keeping its type and
+    /// its other directions, like [set_movable()](Board::set_movable). Gravity
+    /// can no longer move the piece in that direction either. Empty pieces and
+    /// walls are not affected. Returns true if the piece is a regular piece.
+    ///
+    /// The position is not marked for a match check.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to change
+    /// * `direction` - the direction in which the piece will not be movable
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board.
+    pub fn make_unmovable(&mut self, pos: Pos, direction: Direction) -> bool {
+        self.change_direction(pos, direction, false)
+    }
+
+    /// Gets the bitboard that contains the positions of all pieces that gravity
+    /// can move in a direction. Pieces fall if they can move south, and slip
+    /// diagonally if they can also move east or west. Empty pieces are always
+    /// movable, and walls never are.
+    ///
+    /// # Arguments
+    ///
+    /// * `direction` - the direction to get the bitboard for
+    pub fn gravity_movable_in(&self, direction: Direction) -> &BitBoard {
+        &self.state.gravity_directions[direction as usize]
+    }
+
+    /// Changes the directions in which gravity can move a regular piece without
+    /// changing the directions in which it can be swapped. A sticky piece that
+    /// can be swapped but never falls has no gravity directions, while a piece
+    /// that cannot be swapped but still falls keeps its gravity directions.
+    /// Empty pieces and walls are not affected. Returns true if the piece is
+    /// a regular piece.
+    ///
+    /// When a regular piece is placed with [set_piece()](Board::set_piece)
+    /// or changed with [set_movable()](Board::set_movable), gravity can move
+    /// it in the same directions it can be swapped in.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to change
+    /// * `directions` - all the directions in which gravity can move the piece
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board.
+    pub fn set_gravity_movable(&mut self, pos: Pos, directions: EnumSet<Direction>) -> bool {
+        if !self.is_within_board(pos) {
+            panic!("Tried to set gravity directions outside board: {}", pos);
+        }
+
+        if self.piece_type(pos).is_none() {
+            return false;
+        }
+
+        self.set_gravity_directions(pos, directions);
+        true
+    }
+
+    /// Gets the bitboard that contains the positions of all pieces of a given type.
+    /// Returns None if no piece of that type has ever been placed on the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of pieces to get the bitboard for
+    pub fn type_bitboard(&self, piece_type: PieceType) -> Option<&BitBoard> {
+        self.state.pieces.get(&piece_type)
+    }
+
+    /// Gets the compact ID of a piece type, which games can use to index their
+    /// own per-type tables. Returns None if no piece of that type has ever been
+    /// placed on the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type to get the ID of
+    pub fn type_id(&self, piece_type: PieceType) -> Option<TypeId> {
+        self.state.pieces.id(piece_type)
+    }
+
+    /// Gets the piece type with a compact ID, or None if no type has that ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - the ID of the type
+    pub fn type_with_id(&self, id: TypeId) -> Option<PieceType> {
+        self.state.pieces.piece_type(id)
+    }
+
+    /// Gets every piece type that has ever been placed on the board, ordered by ID.
+    pub fn piece_types(&self) -> &[PieceType] {
+        self.state.pieces.types()
+    }
+
+    /// Gets the bitboard that contains the positions of all empty pieces.
+    pub fn empty_bitboard(&self) -> &BitBoard {
+        &self.state.empties
+    }
+
+    /// Counts the pieces of a given type on the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of pieces to count
+    pub fn count(&self, piece_type: PieceType) -> usize {
+        self.state.pieces.get(&piece_type).map_or(0, |board| board.count())
+    }
+
+    /// Counts the empty pieces on the board.
+    pub fn count_empty(&self) -> usize {
+        self.state.empties.count()
+    }
+
+    /// Counts the pieces of every type on the board. Types with no pieces
+    /// on the board are not included.
+    pub fn counts(&self) -> HashMap<PieceType, usize> {
+        self.state.pieces.iter()
+            .map(|(&piece_type, board)| (piece_type, board.count()))
+            .filter(|&(_, count)| count > 0)
+            .collect()
+    }
+
+    /// Gets all the pieces in a rectangle, including the pieces on its edges. The
+    /// corners may be given in any order, and any part of the rectangle outside
+    /// the board is ignored. Pieces are returned row by row, from the bottom row
+    /// to the top row and from left to right within each row.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - one corner of the rectangle
+    /// * `to` - the opposite corner of the rectangle
+    pub fn pieces_in_rect(&self, from: Pos, to: Pos) -> impl Iterator<Item = (Pos, Piece)> + '_ {
+        let min_x = min(from.x(), to.x());
+        let min_y = min(from.y(), to.y());
+        let max_x = min(max(from.x(), to.x()), self.state.width.saturating_sub(1));
+        let max_y = min(max(from.y(), to.y()), self.state.height.saturating_sub(1));
+        let is_on_board = self.state.width > 0 && self.state.height > 0;
+
+        (min_y..=max_y)
+            .filter(move |_| is_on_board)
+            .flat_map(move |y| (min_x..=max_x).map(move |x| Pos::new(x, y)))
+            .map(move |pos| (pos, self.piece(pos)))
+    }
+
+    /// Gets all the pieces whose distance from a position is at most a given
+    /// radius, including the piece at that position. Distance is measured in a
+    /// straight line between the centers of two spaces, so the pieces form a
+    /// circle. Pieces are returned in the same order as
+    /// [pieces_in_rect()](Board::pieces_in_rect).
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the center of the circle
+    /// * `radius` - the maximum distance from the center
+    pub fn pieces_within(&self, pos: Pos, radius: u8) -> impl Iterator<Item = (Pos, Piece)> + '_ {
+        let from = Pos::new(pos.x().saturating_sub(radius), pos.y().saturating_sub(radius));
+        let to = Pos::new(pos.x().saturating_add(radius), pos.y().saturating_add(radius));
+        let max_distance = u32::from(radius) * u32::from(radius);
+
+        self.pieces_in_rect(from, to).filter(move |&(other, _)| {
+            let dx = u32::from(max(pos.x(), other.x()) - min(pos.x(), other.x()));
+            let dy = u32::from(max(pos.y(), other.y()) - min(pos.y(), other.y()));
+            dx * dx + dy * dy <= max_distance
+        })
+    }
+
+    /// Attempts to swap two pieces on the board. If any swap rule is broken (i.e. it
+    /// results false), then the pieces will not be swapped, and this method will
+    /// return false.
+    ///
+    /// If the swap is successful, both swapped positions will be marked for a match check.
+    ///
+    /// Swapping a piece in a direction in which it is marked unmovable is automatically
+    /// a violation of the swap rules.
+    ///
+    /// The [type swap rules](Board::set_type_swap_rule) of both pieces run before
+    /// the board's swap rules and can allow or forbid the swap on their own.
+    ///
+    /// Swapping with a piece that is empty is considered valid by default. The existing
+    /// piece moves into the empty space while the other space is cleared. It is also valid
+    /// to swap a piece with itself, though this has no effect on the board and does not
+    /// mark the piece for a match check.
+    ///
+    /// The order of two positions provided does not matter.
+    ///
+    /// If either position is covered by a [Block], the whole block moves one
+    /// space toward the other position. See [place_block()](Board::place_block).
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    ///
+    /// # Panics
+    ///
+    /// Panics if either position is outside the board. Use
+    /// [try_swap_pieces()](Board::try_swap_pieces) to get an error instead.
+    #[must_use]
+    pub fn swap_pieces(&mut self, first: Pos, second: Pos) -> bool {
+        if !self.is_within_board(first) || !self.is_within_board(second) {
+            panic!("Tried to swap piece outside board: {} with {}", first, second);
+        }
+
+        self.swap_if_allowed(first, second, true)
+    }
+
+    /// Swaps two pieces like [swap_pieces()](Board::swap_pieces) if the caller
+    /// and every swap rule allow it, recording the swap or its rejection either way.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap, which must be on the board
+    /// * `second` - the second position of a piece to swap, which must be on the board
+    /// * `is_allowed` - whether the caller's own checks allow the swap
+    pub(crate) fn swap_if_allowed(&mut self, first: Pos, second: Pos, is_allowed: bool) -> bool {
+        let is_moved = is_allowed && self.passes_swap_rules(first, second) && self.swap_without_rules(first, second);
+
+        self.turn_report.record_swap(is_moved);
+        #[cfg(feature = "analytics")]
+        self.send_analytics(match is_moved {
+            true => AnalyticsEvent::Swapped { first, second },
+            false => AnalyticsEvent::SwapRejected { first, second }
+        });
+
+        self.assert_invariants();
+        is_moved
+    }
+
+    /// Swaps two pieces, or moves the block covering either of them, as a
+    /// player swap without checking any swap rules. Returns false if a block
+    /// could not move.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap, which must be on the board
+    /// * `second` - the second position of a piece to swap, which must be on the board
+    pub(crate) fn swap_without_rules(&mut self, first: Pos, second: Pos) -> bool {
+        match self.swap_block(first, second) {
+            Some(is_moved) => is_moved,
+            None => {
+                self.swap_always(first, second, MatchSource::PlayerSwap);
+                true
+            }
+        }
+    }
+
+    /// Checks whether every swap rule allows two pieces to be swapped.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    pub(crate) fn passes_swap_rules(&self, first: Pos, second: Pos) -> bool {
+        let view = self.view();
+        let mut is_always = false;
+
+        if !self.type_swap_rules.is_empty() {
+            let first_piece = self.piece(first);
+            let second_piece = self.piece(second);
+            let decisions = [(first, second, first_piece, second_piece), (second, first, second_piece, first_piece)];
+
+            for &(pos, other, piece, other_piece) in decisions.iter() {
+                let rule = match piece {
+                    Piece::Regular(piece_type, _) => self.type_swap_rules.get(&piece_type),
+                    _ => None
+                };
+
+                match rule.map_or(TypeSwap::Default, |rule| rule(&view, pos, other, other_piece)) {
+                    TypeSwap::Never => return false,
+                    TypeSwap::Always => is_always = true,
+                    TypeSwap::Default => {}
+                }
+            }
+        }
+
+        match is_always {
+            true => !self.is_held(first) && !self.is_held(second) && self.blocking_wall(first, second).is_none(),
+            false => self.swap_rules.iter().all(|rule| rule(&view, first, second))
+        }
+    }
+
+    /// Sets the swap rule for pieces of a type, replacing the type's previous
+    /// rule. The rule runs whenever a piece of the type would be swapped, before
+    /// the board's swap rules. If either piece's rule returns [TypeSwap::Never],
+    /// the swap fails. Otherwise, if either returns [TypeSwap::Always], the
+    /// board's swap rules are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of piece the rule applies to
+    /// * `rule` - decides whether a piece of the type can be swapped
+    pub fn set_type_swap_rule(&mut self, piece_type: PieceType, rule: TypeSwapRule) {
+        self.type_swap_rules.insert(piece_type, rule);
+    }
+
+    /// Removes the swap rule for pieces of a type and returns it, if the type
+    /// had one.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of piece whose rule to remove
+    pub fn remove_type_swap_rule(&mut self, piece_type: PieceType) -> Option<TypeSwapRule> {
+        self.type_swap_rules.remove(&piece_type)
+    }
+
+    /// Swaps two pieces like [swap_pieces()](Board::swap_pieces), but returns
+    /// an error instead of panicking when either position is outside the board.
+    /// The board is unchanged if an error is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    pub fn try_swap_pieces(&mut self, first: Pos, second: Pos) -> Result<(), SwapError> {
+        self.try_swap_pieces_with(first, second, &(), &[])
+    }
+
+    /// Drags a piece through several spaces, one step at a time. Each step swaps
+    /// the dragged piece with the piece in the next space, so every displaced
+    /// piece shifts one space backwards along the path. Every step must follow
+    /// the swap rules, which see the board as it is after the previous steps.
+    ///
+    /// If any step fails, the board and its [turn report](Board::turn_report)
+    /// are left as they were before the move. A path with fewer than two
+    /// positions does not change the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - every space the piece passes through, starting from the
+    ///   piece's current position
+    pub fn move_piece_along(&mut self, path: &[Pos]) -> Result<(), SwapError> {
+        if let Some(&pos) = path.iter().find(|&&pos| !self.is_within_board(pos)) {
+            return Err(SwapError::OutOfBounds(pos));
+        }
+
+        if let Some(step) = path.windows(2).find(|step| !Board::are_adjacent(step[0], step[1])) {
+            return Err(SwapError::NotAdjacent(step[0], step[1]));
+        }
+
+        let previous_state = self.state.clone();
+        let previous_effect_count = self.effect_count();
+        let previous_report = self.turn_report.clone();
+        #[cfg(feature = "analytics")]
+        let analytics = self.hold_analytics();
+
+        for step in path.windows(2) {
+            let error = match self.blocking_wall(step[0], step[1]) {
+                Some(pos) => SwapError::TargetIsWall(pos),
+                None if !self.swap_pieces(step[0], step[1]) => SwapError::Rejected,
+                None => continue
+            };
+
+            self.restore_state(previous_state);
+            self.truncate_effects(previous_effect_count);
+            self.turn_report = previous_report;
+            #[cfg(feature = "analytics")]
+            self.release_analytics(analytics, false);
+            return Err(error);
+        }
+
+        #[cfg(feature = "analytics")]
+        self.release_analytics(analytics, true);
+        Ok(())
+    }
+
+    /// Rotates three pieces in a cycle, such as for games where players turn a
+    /// group of pieces instead of swapping two. Rotating clockwise moves each
+    /// piece to the next position in the array and the last piece to the first
+    /// position, which turns the pieces clockwise when the positions are listed
+    /// in clockwise order. Rotating counterclockwise moves the pieces the
+    /// opposite way. All three positions are marked for a match check.
+    ///
+    /// The pieces are not rotated and this method returns false if the
+    /// positions are not all different, any piece is not movable in the
+    /// direction it would move, or any position is locked, frozen, or covered by
+    /// a [Block]. Swap rules do not apply, as they only compare two pieces.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - the positions of the pieces to rotate
+    /// * `clockwise` - whether to rotate the pieces clockwise
+    ///
+    /// # Panics
+    ///
+    /// Panics if any position is outside the board.
+    #[must_use]
+    pub fn rotate_pieces(&mut self, positions: [Pos; 3], clockwise: bool) -> bool {
+        if let Some(pos) = positions.iter().find(|&&pos| !self.is_within_board(pos)) {
+            panic!("Tried to rotate piece outside board: {}", pos);
+        }
+
+        let [first, second, third] = positions;
+        let cycle = match clockwise {
+            true => [first, second, third],
+            false => [first, third, second]
+        };
+
+        if first == second || second == third || first == third {
+            return false;
+        }
+
+        let is_stuck = |pos: Pos| self.is_held(pos) || self.block_at(pos).is_some();
+        if positions.iter().any(|&pos| is_stuck(pos)) {
+            return false;
+        }
+
+        let is_cycle_movable = (0..cycle.len()).all(|index| {
+            self.is_movable(cycle[index], cycle[(index + 1) % cycle.len()])
+        });
+        if !is_cycle_movable {
+            return false;
+        }
+
+        self.swap_unmarked(cycle[0], cycle[1]);
+        self.swap_unmarked(cycle[0], cycle[2]);
+
+        for index in 0..cycle.len() {
+            let from = cycle[index];
+            let to = cycle[(index + 1) % cycle.len()];
+            self.state.mark_changed(to, MatchSource::PlayerSwap);
+            if let Piece::Regular(..) = self.piece(to) {
+                self.record_effect(Effect::Moved { from, to });
+            }
+        }
+
+        true
+    }
+
+    /// Randomly rearranges the regular pieces that are movable in all directions,
+    /// using the board's random number generator. Empty pieces, walls, pieces in
+    /// locked spaces, frozen pieces, and pieces that are unmovable in any
+    /// direction stay in place. Pieces keep their counters, and every moved
+    /// piece is marked for a match check.
+    ///
+    /// Boards with the same seed and the same pieces are shuffled identically.
+    pub fn shuffle(&mut self) {
+        let regular = self.regular_bitboard();
+        let positions: Vec<Pos> = regular.iter_set().filter(|&pos| {
+            let is_movable = matches!(self.piece(pos), Piece::Regular(_, directions) if directions == ALL_DIRECTIONS);
+            is_movable && self.block_at(pos).is_none() && !self.is_held(pos)
+        }).collect();
+
+        for index in (1..positions.len()).rev() {
+            let other_index = self.state.rng.next_below(index + 1);
+            self.swap_always(positions[index], positions[other_index], MatchSource::Scripted);
+        }
+    }
+
+    /// Fills the empty spaces in a region with pieces of random types, such as
+    /// to set up a level, without completing any of the board's patterns. Spaces
+    /// are filled from the bottom row up. When a type would complete a pattern,
+    /// another type that has not been tried yet is chosen instead. The new
+    /// pieces are movable in all directions and are not marked for a match
+    /// check.
+    ///
+    /// Returns the number of spaces where every type completed a pattern. Those
+    /// spaces keep the last type tried, so the board has a match unless the
+    /// count is zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - the only positions to fill, or None to fill every empty space
+    /// * `types` - the types to choose from
+    /// * `rng` - the random number generator that chooses the types
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no types to choose from or if a chosen type would be
+    /// the 65,536th distinct piece type on the board.
+    pub fn fill_without_matches(&mut self, region: Option<&PosSet>, types: &[PieceType], rng: &mut Rng) -> usize {
+        if types.is_empty() {
+            panic!("Tried to fill board without any piece types");
+        }
+
+        let mut unavoidable = 0;
+        let empties = self.state.empties.clone();
+        for pos in empties.iter_set() {
+            let is_in_region = region.map_or(true, |region| region.contains(&pos));
+            if !is_in_region {
+                continue;
+            }
+
+            let mut candidates = types.to_vec();
+            loop {
+                let piece_type = candidates.swap_remove(rng.next_below(candidates.len()));
+                self.set_piece_silent(pos, Piece::Regular(piece_type, ALL_DIRECTIONS));
+
+                let tags = &self.tags;
+                let prevented = &self.match_prevented;
+                if Board::find_match(&self.patterns, &self.compiled_patterns, &self.state.pieces, tags, prevented, pos).is_none() {
+                    break;
+                }
+                if candidates.is_empty() {
+                    unavoidable += 1;
+                    break;
+                }
+            }
+        }
+
+        unavoidable
+    }
+
+    /// Finds the matches that swapping two pieces would cause without changing
+    /// the board or the positions marked for a match check. Returns None if any
+    /// swap rule is broken.
+    ///
+    /// The matches are identical to those that [next_match()](Board::next_match)
+    /// would return for the two swapped positions if the pieces were swapped with
+    /// [swap_pieces()](Board::swap_pieces). Matches from positions that were already
+    /// marked for a match check are not included. Only the bitboards of the two
+    /// swapped piece types are copied, so previewing a swap is inexpensive.
+    ///
+    /// Swaps that would move a [Block] cannot be previewed and return None.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    ///
+    /// # Panics
+    ///
+    /// Panics if either position is outside the board.
+    pub fn preview_swap(&self, first: Pos, second: Pos) -> Option<Vec<Match<'_>>> {
+        if !self.is_within_board(first) || !self.is_within_board(second) {
+            panic!("Tried to preview swap outside board: {} with {}", first, second);
+        }
+
+        if !self.passes_swap_rules(first, second) {
+            return None;
+        }
+
+        if self.block_at(first).is_some() || self.block_at(second).is_some() {
+            return None;
+        }
+
+        if first == second {
+            return Some(Vec::new());
+        }
+
+        let possible_first_type = self.piece_type(first);
+        let possible_second_type = self.piece_type(second);
+
+        // Swapping pieces of the same type does not change any bitboards
+        let mut swapped_boards = TypeTable::new();
+        let boards = match possible_first_type == possible_second_type {
+            true => &self.state.pieces,
+            false => {
+
+                // Tagged patterns may need the bitboards of types that were not swapped
+                if self.patterns.iter().any(|pattern| pattern.tag().is_some()) {
+                    swapped_boards = self.state.pieces.clone();
+                }
+
+                for piece_type in [possible_first_type, possible_second_type].iter().flatten() {
+                    let mut board = self.state.pieces[piece_type].clone();
+                    board.swap(first, second);
+                    swapped_boards.insert(*piece_type, board);
+                }
+                &swapped_boards
+            }
+        };
+
+        Some(vec![first, second].into_iter()
+            .filter_map(|pos| Board::find_match(&self.patterns, &self.compiled_patterns, boards, &self.tags, &self.match_prevented, pos))
+            .map(|found| found.with_source(MatchSource::PlayerSwap))
+            .collect())
+    }
+
+    /// Replaces a piece at the given position and returns the previous piece.
+    /// The space is marked as needing a match check. Swap rules do not apply
+    /// and the replacement is always successful. If the space is covered by a
+    /// [Block], the block is split into ordinary pieces first.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to replace
+    /// * `piece` - the piece to put at the given position
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board or if the piece
+    /// would be the 65,536th distinct piece type on the board. Use
+    /// [try_set_piece()](Board::try_set_piece) to get an error instead.
+    pub fn set_piece(&mut self, pos: Pos, piece: Piece) -> Piece {
+        self.replace_piece(pos, piece, Some(MatchSource::Scripted))
+    }
+
+    /// Replaces a piece like [set_piece()](Board::set_piece), but marks the
+    /// space for a match check as changed by the given source.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to replace
+    /// * `piece` - the piece to put at the given position
+    /// * `source` - what changed the space
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board or if the piece
+    /// would be the 65,536th distinct piece type on the board.
+    pub(crate) fn set_piece_from(&mut self, pos: Pos, piece: Piece, source: MatchSource) -> Piece {
+        self.replace_piece(pos, piece, Some(source))
+    }
+
+    /// Replaces a piece like [set_piece()](Board::set_piece), but does not mark
+    /// the space for a match check, such as while setting up a board or for a
+    /// cosmetic change that should not cause an immediate match. A space that
+    /// was already marked stays marked.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to replace
+    /// * `piece` - the piece to put at the given position
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board or if the piece
+    /// would be the 65,536th distinct piece type on the board.
+    pub fn set_piece_silent(&mut self, pos: Pos, piece: Piece) -> Piece {
+        self.replace_piece(pos, piece, None)
+    }
+
+    /// Replaces a piece at the given position and returns the previous piece.
+    /// See [set_piece()](Board::set_piece).
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to replace
+    /// * `piece` - the piece to put at the given position
+    /// * `mark` - what changed the space, if it should be marked for a match check
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board or if the piece
+    /// would be the 65,536th distinct piece type on the board.
+    fn replace_piece(&mut self, pos: Pos, piece: Piece, mark: Option<MatchSource>) -> Piece {
+        if !self.is_within_board(pos) {
+            panic!("Tried to set piece out of bounds: {}", pos);
+        }
+
+        self.remove_blocks_in(&[pos]);
+        if let Some(source) = mark {
+            self.state.mark_changed(pos, source);
+        }
+        self.state.counters.remove(&pos);
+        self.state.frozen.remove(&pos);
+        self.state.walls.remove(&pos);
+        let old_piece = self.piece(pos);
+
+        let grid_index = self.grid_index(pos);
+        let old_id = mem::replace(&mut self.state.type_grid[grid_index], 0);
+        if old_id != 0 {
+            self.state.pieces.board_mut(old_id).unset(pos);
+        }
+
+        match piece {
+            Piece::Regular(piece_type, directions) => {
+                let id = self.type_index(piece_type);
+                self.state.type_grid[grid_index] = id;
+                self.state.pieces.board_mut(id).set(pos);
+                self.state.empties.unset(pos);
+                self.set_movable_directions(pos, directions);
+                self.set_gravity_directions(pos, directions);
+            },
+            Piece::Empty => {
+                self.state.empties.set(pos);
+                self.set_movable_directions(pos, ALL_DIRECTIONS);
+                self.set_gravity_directions(pos, ALL_DIRECTIONS);
+            },
+            Piece::Wall => {
+                self.state.empties.unset(pos);
+                self.set_movable_directions(pos, EnumSet::new());
+                self.set_gravity_directions(pos, EnumSet::new());
+            }
+        };
+
+        self.update_zobrist(pos, old_piece, piece);
+        self.record_piece_change(pos, old_piece, piece);
+        old_piece
+    }
+
+    /// Replaces a piece like [set_piece()](Board::set_piece), but returns an
+    /// error instead of panicking. The board is unchanged if an error is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to replace
+    /// * `piece` - the piece to put at the given position
+    pub fn try_set_piece(&mut self, pos: Pos, piece: Piece) -> Result<Piece, SetPieceError> {
+        if !self.is_within_board(pos) {
+            return Err(SetPieceError::OutOfBounds(pos));
+        }
+
+        if let Piece::Regular(piece_type, _) = piece {
+            let is_new_type = self.state.pieces.id(piece_type).is_none();
+            if is_new_type && self.state.pieces.len() >= usize::from(TypeId::MAX) {
+                return Err(SetPieceError::TooManyPieceTypes);
+            }
+        }
+
+        Ok(self.set_piece(pos, piece))
+    }
+
+    /// Gets the positions marked for a match check, in the order they were
+    /// marked. Each position appears at most once.
+    pub fn pending_match_checks(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.state.last_changed.iter().copied()
+    }
+
+    /// Marks a position for a match check without changing the piece there,
+    /// such as after a game changes the board in a way the engine does not
+    /// know about. A position that is already marked keeps its place.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to mark
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is outside the board.
+    pub fn mark_for_match_check(&mut self, pos: Pos) {
+        if !self.is_within_board(pos) {
+            panic!("Tried to mark position outside board: {}", pos);
+        }
+
+        self.state.mark_changed(pos, MatchSource::Scripted);
+    }
+
+    /// Unmarks every position marked for a match check, so no matches are
+    /// found until pieces change again.
+    pub fn clear_pending(&mut self) {
+        for pos in self.state.last_changed.drain(..) {
+            self.state.changed.unset(pos);
+        }
+        self.state.change_sources.clear();
+        self.state.change_order.clear();
+    }
+
+    /// Changes every piece of one type into a piece of another type, such as
+    /// for power-ups that paint part of the board. Converted pieces keep their
+    /// movable directions and counters and are marked for a match check.
+    /// A [Block] that is only partly converted is split into ordinary pieces.
+    ///
+    /// Returns the number of pieces that were converted. Nothing is converted
+    /// if both types are the same.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_type` - the type of pieces to convert
+    /// * `to_type` - the type that the pieces become
+    /// * `region` - the only positions to convert, or None to convert pieces
+    ///   anywhere on the board
+    ///
+    /// # Panics
+    ///
+    /// Panics if the board already has the maximum number of piece types and
+    /// the new type is not one of them.
+    pub fn convert_pieces(&mut self, from_type: PieceType, to_type: PieceType, region: Option<&PosSet>) -> usize {
+        let mut converted = match self.state.pieces.get(&from_type) {
+            Some(board) if from_type != to_type => board.clone(),
+            _ => return 0
+        };
+        if let Some(region) = region {
+            converted.intersect(&self.region_board(region));
+        }
+
+        let positions: Vec<Pos> = converted.iter_set().collect();
+        if positions.is_empty() {
+            return 0;
+        }
+
+        let partial_blocks: Vec<Pos> = self.state.blocks.iter()
+            .filter(|block| !block.positions().all(|pos| converted.is_set(pos)))
+            .filter(|block| block.positions().any(|pos| converted.is_set(pos)))
+            .map(|block| block.anchor())
+            .collect();
+        self.remove_blocks_in(&partial_blocks);
+
+        let from_index = self.type_index(from_type);
+        let to_index = self.type_index(to_type);
+        self.state.pieces.board_mut(from_index).difference(&converted);
+        self.state.pieces.board_mut(to_index).union(&converted);
+
+        for &pos in positions.iter() {
+            let grid_index = self.grid_index(pos);
+            self.state.type_grid[grid_index] = to_index;
+            self.state.mark_changed(pos, MatchSource::Scripted);
+
+            let new_piece = self.piece(pos);
+            if let Piece::Regular(_, directions) = new_piece {
+                let old_piece = Piece::Regular(from_type, directions);
+                self.update_zobrist(pos, old_piece, new_piece);
+                self.record_piece_change(pos, old_piece, new_piece);
+            }
+        }
+
+        positions.len()
+    }
+
+    /// Gets the next match on the board. The board's [MatchOrder] determines which
+    /// changed pieces are checked first. By default, matches from pieces that were
+    /// changed earlier are returned first. Matches are always based on the current
+    /// board state, not the board state when the match occurred.
+    ///
+    /// Pieces that were changed but did not create a match are skipped.
+    ///
+    /// Regardless of whether a match is found, each checked piece is unmarked for
+    /// a match check. A piece that was marked several times before it was checked
+    /// is only checked once.
+    pub fn next_match(&mut self) -> Option<Match<'_>> {
+        self.next_match_within(None)
+    }
+
+    /// Gets the next match like [next_match()](Board::next_match), but only
+    /// finds matches whose pieces are all inside a region of the board, such
+    /// as when part of the board is frozen or holds a separate mini-game.
+    /// Only marked positions inside the region are checked and unmarked.
+    /// Positions outside the region stay marked for a later check.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - the positions that a match must be inside
+    pub fn next_match_in(&mut self, region: &PosSet) -> Option<Match<'_>> {
+        self.next_match_within(Some(region))
+    }
+
+    /// Gets the next match, optionally only checking positions and matching
+    /// pieces inside a region.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - the positions that a match must be inside, or None to
+    ///   search the whole board
+    pub(crate) fn next_match_within(&mut self, region: Option<&PosSet>) -> Option<Match<'_>> {
+        let region_boards = region.map(|region| self.pieces_in_region(region));
+        if self.match_order == MatchOrder::ByRank {
+            return self.next_match_by_rank(region, region_boards.as_ref());
+        }
+
+        let is_in_region = |pos: &Pos| region.map_or(true, |region| region.contains(pos));
+        loop {
+            let index = match (self.match_order, region) {
+                (MatchOrder::ByRow, None) => {
+                    let lowest_pos = self.state.changed.first_set()?;
+                    self.state.changed_index(lowest_pos)?
+                },
+                (MatchOrder::ByRow, Some(_)) => {
+                    let lowest_pos = self.state.changed.iter_set().find(is_in_region)?;
+                    self.state.changed_index(lowest_pos)?
+                },
+                (MatchOrder::Lifo, _) => self.state.last_changed.iter().rposition(is_in_region)?,
+                _ => self.state.last_changed.iter().position(is_in_region)?
+            };
+
+            let (next_pos, source) = self.state.take_changed(index)?;
+            let boards = region_boards.as_ref().unwrap_or(&self.state.pieces);
+            let next_match = Board::find_match(&self.patterns, &self.compiled_patterns, boards, &self.tags, &self.match_prevented, next_pos);
+            if let Some(next_match) = next_match {
+                return Some(Board::include_blocks(&self.state.blocks, next_match.with_source(source)));
+            }
+        }
+    }
+
+    /// Creates a bitboard with every position in a region set. Positions
+    /// outside the board are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - the positions to set
+    pub(crate) fn region_board(&self, region: &PosSet) -> BitBoard {
+        let mut region_board = BitBoard::new(self.state.width, self.state.height);
+        region.iter().filter(|&&pos| self.is_within_board(pos)).for_each(|&pos| region_board.set(pos));
+        region_board
+    }
+
+    /// Gets the bitboards for each piece type with every position outside a
+    /// region unset.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - the positions to keep
+    fn pieces_in_region(&self, region: &PosSet) -> TypeTable {
+        let region_board = self.region_board(region);
+
+        self.state.pieces.map_boards(|board| {
+            let mut board = board.clone();
+            board.intersect(&region_board);
+            board
+        })
+    }
+
+    /// Checks whether any of the board's patterns could still be formed with
+    /// the pieces already on the board, assuming no new pieces are added. This
+    /// is useful for detecting a failed level early in modes without refills.
+    ///
+    /// A pattern can still be formed if it fits somewhere on the board where
+    /// none of its spaces is a wall or an unmovable piece that the pattern
+    /// does not match, and there are enough movable matching pieces elsewhere
+    /// to fill the spaces that do not already hold a matching piece. The
+    /// check is conservative: it returns false only if no pattern can possibly
+    /// be formed, but true does not guarantee that a sequence of swaps forms one.
+    pub fn can_any_pattern_still_match(&self) -> bool {
+        let mut movable = BitBoard::new(self.state.width, self.state.height);
+        for direction_board in self.state.movable_directions.iter() {
+            movable.union(direction_board);
+        }
+        movable.difference(&self.state.empties);
+
+        self.patterns.iter().any(|pattern| self.can_pattern_still_match(pattern, &movable))
+    }
+
+    /// Finds all matches for the pieces marked for a match check, splitting the
+    /// marked positions across threads. This is useful for large boards with
+    /// many changed positions.
+    ///
+    /// The matches are identical to those returned by calling [next_match()](Board::next_match)
+    /// until it returns None without changing the board in between. They are
+    /// returned in the order that the positions were changed, regardless of
+    /// the order in which the threads finish.
+    ///
+    /// All pieces are unmarked for a match check. The board's [MatchOrder] is ignored.
+    #[cfg(feature = "parallel")]
+    pub fn find_matches_parallel(&mut self) -> Vec<Match<'_>> {
+        use rayon::prelude::*;
+
+        let changed: Vec<(Pos, MatchSource)> = mem::take(&mut self.state.last_changed).into_iter()
+            .map(|pos| {
+                self.state.changed.unset(pos);
+                (pos, self.state.unmark_source(pos))
+            })
+            .collect();
+        let patterns = &self.patterns;
+        let compiled_patterns = &self.compiled_patterns;
+        let boards = &self.state.pieces;
+        let tags = &self.tags;
+        let prevented = &self.match_prevented;
+        let blocks = &self.state.blocks;
+
+        changed.into_par_iter()
+            .filter_map(|(pos, source)| Board::find_match(patterns, compiled_patterns, boards, tags, prevented, pos)
+                .map(|found| found.with_source(source)))
+            .map(|found| Board::include_blocks(blocks, found))
+            .collect()
+    }
+
+    /// Finds every match on the whole board, ignoring which positions are marked
+    /// for a match check. This is useful after loading a level or changing the
+    /// board outside the engine, when the marked positions do not reflect the
+    /// pieces on the board. The marked positions are not changed.
+    ///
+    /// Patterns are checked from highest to lowest rank, and each piece is part
+    /// of at most one match, as if every match were cleared as soon as it was
+    /// found. Each pattern is located with whole-board bitboard shifts instead
+    /// of position-by-position checks, so scanning is fast even on large boards.
+    /// The changed position of each match is one of the match's own positions.
+    pub fn scan_all_matches(&self) -> Vec<Match<'_>> {
+        let mut claimed = self.match_prevented.clone();
+        let mut matches = Vec::new();
+
+        for (pattern, compiled) in self.patterns.iter().zip(self.compiled_patterns.iter()) {
+            let found = match pattern.cluster_size() {
+                Some(min_size) => self.scan_clusters(pattern, min_size, &claimed),
+                None => self.scan_pattern(pattern, compiled, &claimed)
+            };
+
+            for next_match in found {
+                let is_claimed = next_match.board_pos().iter().any(|&pos| claimed.is_set(pos));
+                if is_claimed {
+                    continue;
+                }
+
+                let next_match = Board::include_blocks(&self.state.blocks, next_match);
+                next_match.board_pos().iter().for_each(|&pos| claimed.set(pos));
+                matches.push(next_match);
+            }
+        }
+
+        matches
+    }
+
+    /// Finds the places where a pattern that is not a cluster pattern fits on
+    /// the whole board. The matches may overlap each other.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - the pattern to find
+    /// * `compiled` - the pattern prepared for the board's size
+    /// * `claimed` - the spaces that cannot be part of a match
+    fn scan_pattern<'a>(&self, pattern: &'a MatchPattern, compiled: &CompiledPattern,
+                        claimed: &BitBoard) -> Vec<Match<'a>> {
+        let pattern_type = pattern.piece_type();
+        let board = match pattern.tag().and_then(|tag| self.tags.get(tag)) {
+            Some(types) => Board::union_boards(types.iter().chain(Some(&pattern_type)), &self.state.pieces),
+            None => self.state.pieces.get(&pattern_type).cloned()
+        };
+        let mut board = match board {
+            Some(board) if !pattern.offsets().is_empty() => board,
+            _ => return Vec::new()
+        };
+        board.difference(claimed);
+
+        // A pattern fits at an origin if the board is set at every offset from it
+        let mut origins = compiled.origins().clone();
+        for &offset in pattern.offsets() {
+            let mut shifted = board.clone();
+            shifted.shift(Direction::South, offset.y());
+            shifted.shift(Direction::West, offset.x());
+            origins.intersect(&shifted);
+        }
+
+        origins.iter_set()
+            .map(|origin| Match::new(pattern, pattern.offsets()[0] + origin, Board::change_origin(pattern.offsets(), origin)))
+            .collect()
+    }
+
+    /// Finds the groups on the whole board that are large enough to match a
+    /// cluster pattern. The groups do not overlap each other.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - the cluster pattern to find
+    /// * `min_size` - the fewest pieces a group needs to match
+    /// * `claimed` - the spaces that cannot be part of a match
+    fn scan_clusters<'a>(&self, pattern: &'a MatchPattern, min_size: usize, claimed: &BitBoard) -> Vec<Match<'a>> {
+        let mut visited = claimed.clone();
+        let mut matches = Vec::new();
+
+        for pos in self.regular_bitboard().iter_set() {
+            if visited.is_set(pos) {
+                continue;
+            }
+
+            let found = Board::find_cluster(pattern, min_size, &self.state.pieces, &self.tags, &visited, pos);
+            if let Some(found) = found {
+                found.board_pos().iter().for_each(|&pos| visited.set(pos));
+                matches.push(found);
+            }
+        }
+
+        matches
+    }
+
+    /// Moves all pieces down to fill the empty spaces below them.
+    ///
+    /// Pieces will move diagonally and down if there is an empty space there
+    /// and if there is no piece in the column next to them that will fill
+    /// that space. The piece will continue falling diagonally and then down
+    /// until there are no more empty spaces to fill.
+    ///
+    /// A piece may fall diagonally left or right, but if both spaces are open,
+    /// left is preferred.
+    ///
+    /// Pieces will not move past walls or other pieces that are unmovable and
+    /// directly adjacent. However, pieces will move past walls that are diagonally
+    /// adjacent.
+    ///
+    /// A piece that cannot fall past a portal's exit moves to the portal's entry
+    /// if it is empty and continues falling. Each move through a portal is
+    /// included in the sequence of moves, so the sequence contains the piece's
+    /// full path.
+    ///
+    /// [Floating](Board::set_floating) pieces rise into the empty spaces above
+    /// them instead of falling, and other pieces fall into the spaces they
+    /// leave. Once no more pieces can move, heavier pieces sink through the
+    /// lighter pieces directly beneath them. See [set_weight()](Board::set_weight).
+    ///
+    /// Does not fill empty spaces with new pieces. Swap rules never stop
+    /// pieces from falling, because gravity does not swap pieces.
+    ///
+    /// Marks the spaces that change for a match check according to the
+    /// board's [gravity marking](Board::set_gravity_marking).
+    ///
+    /// Returns one move for each piece that fell, in the order the pieces
+    /// started moving, so the pieces' falls can be replayed exactly.
+    pub fn trickle(&mut self) -> Vec<PieceMove> {
+        let steps = self.settle();
+        self.mark_gravity_moves(&steps)
+    }
+
+    /// Moves pieces like [trickle()](Board::trickle) without marking any
+    /// spaces. Returns the steps made.
+    fn settle(&mut self) -> Vec<(Pos, Pos)> {
+        self.with_locked_frozen(|board| {
+            let mut steps = Vec::new();
+
+            // Pieces can fall into the spaces that floating pieces rise out of
+            loop {
+                steps.append(&mut board.with_floating_held(Board::fall_all));
+
+                let mut rise_steps = Vec::new();
+                for x in 0..board.state.width {
+                    rise_steps.append(&mut board.raise_floating_pieces(x));
+                }
+                if rise_steps.is_empty() {
+                    break;
+                }
+                steps.append(&mut rise_steps);
+            }
+
+            for x in 0..board.state.width {
+                steps.append(&mut board.sink_heavy_pieces(x));
+            }
+
+            steps
+        })
+    }
+
+    /// Replaces a space with a piece and moves it down to fill the empty
+    /// spaces below it.
+    ///
+    /// If the piece provided is an empty piece or a wall, this method performs
+    /// identically to [set_piece()].
+    ///
+    /// A regular piece will move diagonally and down if there is an empty space
+    /// there. The piece will continue falling diagonally and then down until
+    /// there are no more empty spaces to fill.
+    ///
+    /// The piece may fall diagonally left or right, but if both spaces are open,
+    /// left is preferred.
+    ///
+    /// The piece will not move past walls or other pieces that are unmovable and
+    /// directly adjacent. However, it will move past walls that are diagonally
+    /// adjacent. The piece falls through portals like it would in [trickle()].
+    ///
+    /// Does not fill empty spaces with new pieces.
+    ///
+    /// A [floating](Board::set_floating) piece rises instead of falling. Once
+    /// a piece lands, it sinks through the lighter pieces directly beneath it.
+    /// See [set_weight()](Board::set_weight).
+    ///
+    /// Marks the space where the piece is added for a match check. The spaces
+    /// that change as the piece falls are marked according to the board's
+    /// [gravity marking](Board::set_gravity_marking).
+    ///
+    /// Returns the piece's move if it fell, including its full path.
+    pub fn add_and_trickle(&mut self, pos: Pos, piece: Piece) -> Option<PieceMove> {
+        self.set_piece_from(pos, piece, MatchSource::Gravity);
+        let steps = self.with_locked_frozen(|board| match board.is_floating_at(pos) {
+            true => board.raise_piece(pos),
+            false => {
+                let mut steps = board.trickle_piece(pos, false);
+                let landed_pos = steps.last().map_or(pos, |&(_, to)| to);
+                steps.append(&mut board.sink_piece(landed_pos));
+                steps
+            }
+        });
+        self.mark_gravity_moves(&steps).into_iter().find(|piece_move| piece_move.from() == pos)
+    }
+
+    /// Advances gravity by a number of steps, moving each falling piece at most
+    /// one space per step instead of all the way down. Real-time games can call
+    /// this once per frame to animate pieces as they fall. These steps are
+    /// unrelated to the counters updated by [tick()](Board::tick).
+    ///
+    /// Pieces move along the same paths that [trickle()] would move them
+    /// along, one space per step, so once the fall is complete, the board is
+    /// the same as if [trickle()] had been called. Each piece starts moving
+    /// as soon as the spaces it moves into are empty, so pieces stacked above
+    /// an empty space fall together. Moving through a portal and a heavier
+    /// piece trading places with a lighter piece beneath it each take one
+    /// step. See [set_weight()](Board::set_weight).
+    ///
+    /// The rest of the fall is remembered between calls, so calling this
+    /// method once per frame moves pieces along the same paths as one call
+    /// with more steps. If a piece, a piece's gravity, a block, a locked
+    /// space, a portal, or a gravity setting changes between calls, the fall
+    /// starts over from the pieces' current positions.
+    ///
+    /// Does not fill empty spaces with new pieces. Swap rules never stop
+    /// pieces from falling.
+    ///
+    /// Marks the spaces that change for a match check according to the
+    /// board's [gravity marking](Board::set_gravity_marking). With
+    /// [GravityMarking::Final], the positions pieces occupy after the last
+    /// step are marked, even if the pieces are still falling.
+    ///
+    /// Returns the (from position, to position) moves made in each step.
+    /// Stops early once the fall is complete, so there may be fewer entries
+    /// than steps requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - the maximum number of steps to advance
+    pub fn step(&mut self, steps: u32) -> Vec<Vec<(Pos, Pos)>> {
+        let mut rounds = match self.fall_plan.take() {
+            Some(plan) if plan.setup == self.fall_setup() => plan.rounds,
+            _ => self.plan_fall()
+        };
+
+        let mut all_moves = Vec::new();
+        for _ in 0..steps {
+            let moves = match rounds.pop_front() {
+                Some(moves) => moves,
+                None => break
+            };
+
+            self.play_round(&moves);
+            all_moves.push(moves);
+        }
+
+        let steps: Vec<(Pos, Pos)> = all_moves.iter().flatten().copied().collect();
+        self.mark_gravity_moves(&steps);
+
+        if !rounds.is_empty() {
+            self.fall_plan = Some(FallPlan { setup: self.fall_setup(), rounds });
+        }
+
+        all_moves
+    }
+
+    /// Moves every piece in a row horizontally by an offset. Positive offsets
+    /// move pieces east and negative offsets move pieces west. Walls move
+    /// like any other piece, and swap rules do not apply.
+    ///
+    /// If wrapping is enabled, pieces that move past one end of the row
+    /// reappear at the other end. Otherwise, those pieces are removed and
+    /// the spaces left behind are empty.
+    ///
+    /// Every position in the row is marked for a match check. Counters move
+    /// with their pieces.
+    ///
+    /// # Arguments
+    ///
+    /// * `y` - the y coordinate of the row to shift
+    /// * `offset` - the number of spaces to move each piece
+    /// * `wrap` - whether pieces that move off the board reappear on the other side
+    ///
+    /// # Panics
+    ///
+    /// Panics if the row is outside the board.
+    pub fn shift_row(&mut self, y: u8, offset: i16, wrap: bool) {
+        if y >= self.state.height {
+            panic!("Tried to shift row outside board: {}", y);
+        }
+
+        let positions: Vec<Pos> = (0..self.state.width).map(|x| Pos::new(x, y)).collect();
+        self.shift_line(&positions, offset, wrap);
+    }
+
+    /// Moves every piece in a column vertically by an offset. Positive offsets
+    /// move pieces north and negative offsets move pieces south. Walls move
+    /// like any other piece, and swap rules do not apply.
+    ///
+    /// If wrapping is enabled, pieces that move past one end of the column
+    /// reappear at the other end. Otherwise, those pieces are removed and
+    /// the spaces left behind are empty.
+    ///
+    /// Every position in the column is marked for a match check. Counters move
+    /// with their pieces.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - the x coordinate of the column to shift
+    /// * `offset` - the number of spaces to move each piece
+    /// * `wrap` - whether pieces that move off the board reappear on the other side
+    ///
+    /// # Panics
+    ///
+    /// Panics if the column is outside the board.
+    pub fn shift_column(&mut self, x: u8, offset: i16, wrap: bool) {
+        if x >= self.state.width {
+            panic!("Tried to shift column outside board: {}", x);
+        }
+
+        let positions: Vec<Pos> = (0..self.state.height).map(|y| Pos::new(x, y)).collect();
+        self.shift_line(&positions, offset, wrap);
+    }
+
+    /// Pushes every column up by a number of rows and fills the rows at the
+    /// bottom of the board with a piece, such as attack rows sent by an
+    /// opponent. Walls and empty spaces move like any other piece, and swap
+    /// rules do not apply. Pieces pushed past the top of the board are removed.
+    /// Returns true if any regular piece was pushed off the board.
+    ///
+    /// Every position on the board is marked for a match check, and counters
+    /// move with their pieces. Exits and portals stay in place, and any [Block]
+    /// is split into ordinary pieces.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - the number of rows to insert at the bottom of the board.
+    ///   Inserting more rows than the board's height fills the
+    ///   whole board.
+    /// * `piece` - the piece placed in each space of the inserted rows
+    ///
+    /// # Panics
+    ///
+    /// Panics if the piece would be the 65,536th distinct piece type on the board.
+    pub fn inject_garbage(&mut self, rows: u8, piece: Piece) -> bool {
+        let rows = min(rows, self.state.height);
+        let first_lost_row = self.state.height - rows;
+        let overflowed = (first_lost_row..self.state.height).any(|y| (0..self.state.width).any(
+            |x| matches!(self.piece(Pos::new(x, y)), Piece::Regular(..))
+        ));
+
+        for x in 0..self.state.width {
+            self.shift_column(x, i16::from(rows), false);
+            for y in 0..rows {
+                self.set_piece(Pos::new(x, y), piece);
+            }
+        }
+
+        overflowed
+    }
+
+    /// Rotates the entire board 90 degrees clockwise. The board's width and
+    /// height are swapped. Each piece's movable directions and counter, the
+    /// exits, and the portals are rotated with the board.
+    ///
+    /// Every [block](Board::place_block) is dissolved into separate pieces, and
+    /// the number of [staging rows](Board::set_staging_rows) is reset to zero.
+    ///
+    /// Positions that were marked for a match check stay marked at their new
+    /// positions, and every other position is marked after them. Every mark
+    /// is reset to the [Scripted](MatchSource::Scripted) source, so the source
+    /// of each earlier change is lost.
+    pub fn rotate_90(&mut self) {
+        let old_width = self.state.width;
+        self.transform(
+            self.state.height,
+            old_width,
+            |pos| Pos::new(pos.y(), old_width - 1 - pos.x()),
+            |direction| match direction {
+                Direction::North => Direction::East,
+                Direction::East => Direction::South,
+                Direction::South => Direction::West,
+                Direction::West => Direction::North
+            }
+        );
+    }
+
+    /// Rotates the entire board 180 degrees. Each piece's movable directions and
+    /// counter, the exits, and the portals are rotated with the board.
+    ///
+    /// Every [block](Board::place_block) is dissolved into separate pieces, and
+    /// the number of [staging rows](Board::set_staging_rows) is reset to zero.
+    ///
+    /// Positions that were marked for a match check stay marked at their new
+    /// positions, and every other position is marked after them. Every mark
+    /// is reset to the [Scripted](MatchSource::Scripted) source, so the source
+    /// of each earlier change is lost.
+    pub fn rotate_180(&mut self) {
+        let width = self.state.width;
+        let height = self.state.height;
+        self.transform(
+            width,
+            height,
+            |pos| Pos::new(width - 1 - pos.x(), height - 1 - pos.y()),
+            |direction| match direction {
+                Direction::North => Direction::South,
+                Direction::South => Direction::North,
+                Direction::East => Direction::West,
+                Direction::West => Direction::East
+            }
+        );
+    }
+
+    /// Mirrors the entire board so that the west side becomes the east side.
+    /// Each piece's movable directions and counter, the exits, and the portals
+    /// are mirrored with the board.
+    ///
+    /// Every [block](Board::place_block) is dissolved into separate pieces, and
+    /// the number of [staging rows](Board::set_staging_rows) is reset to zero.
+    ///
+    /// Positions that were marked for a match check stay marked at their new
+    /// positions, and every other position is marked after them. Every mark
+    /// is reset to the [Scripted](MatchSource::Scripted) source, so the source
+    /// of each earlier change is lost.
+    pub fn mirror_horizontal(&mut self) {
+        let width = self.state.width;
+        self.transform(
+            width,
+            self.state.height,
+            |pos| Pos::new(width - 1 - pos.x(), pos.y()),
+            |direction| match direction {
+                Direction::East => Direction::West,
+                Direction::West => Direction::East,
+                direction => direction
+            }
+        );
+    }
+
+    /// Checks every position marked for a match check and returns the match with
+    /// the highest rank. If several positions have matches with the same rank, the
+    /// position that was marked first is used. All checked positions without a match
+    /// are unmarked along with the position of the returned match.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - the only positions to check, or None to check every position
+    /// * `region_boards` - the bitboards for each piece type inside the region,
+    ///   if there is a region
+    fn next_match_by_rank(&mut self, region: Option<&PosSet>,
+                          region_boards: Option<&TypeTable>) -> Option<Match<'_>> {
+        let patterns = &self.patterns;
+        let compiled_patterns = &self.compiled_patterns;
+        let boards = region_boards.unwrap_or(&self.state.pieces);
+        let tags = &self.tags;
+        let prevented = &self.match_prevented;
+        let is_in_region = |pos: &Pos| region.map_or(true, |region| region.contains(pos));
+
+        let ranks: Vec<Option<u32>> = self.state.last_changed.iter().map(|pos| match is_in_region(pos) {
+            true => Board::find_match(patterns, compiled_patterns, boards, tags, prevented, *pos).map(|found| found.pattern().rank()),
+            false => None
+        }).collect();
+
+        let best_pos = ranks.iter()
+            .enumerate()
+            .filter_map(|(index, rank)| rank.map(|rank| (index, rank)))
+            .max_by_key(|&(index, rank)| (rank, Reverse(index)))
+            .map(|(index, _)| self.state.last_changed[index]);
+
+        let mut best_source = MatchSource::Scripted;
+        let checked = mem::take(&mut self.state.last_changed);
+        for (pos, rank) in checked.into_iter().zip(ranks) {
+            if (rank.is_some() && Some(pos) != best_pos) || !is_in_region(&pos) {
+                self.state.last_changed.push_back(pos);
+            } else {
+                self.state.changed.unset(pos);
+                let source = self.state.unmark_source(pos);
+                if Some(pos) == best_pos {
+                    best_source = source;
+                }
+            }
+        }
+
+        let boards = region_boards.unwrap_or(&self.state.pieces);
+        Board::find_match(&self.patterns, &self.compiled_patterns, boards, &self.tags, &self.match_prevented, best_pos?)
+            .map(|found| Board::include_blocks(&self.state.blocks, found.with_source(best_source)))
+    }
+
+    /// Moves every piece in a line of positions toward the end of the line by
+    /// an offset, or toward the start if the offset is negative.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - the positions in the line, in order
+    /// * `offset` - the number of spaces to move each piece
+    /// * `wrap` - whether pieces that move past one end reappear at the other end
+    fn shift_line(&mut self, positions: &[Pos], offset: i16, wrap: bool) {
+        let len = positions.len() as i32;
+        let pieces: Vec<Piece> = positions.iter().map(|&pos| self.piece(pos)).collect();
+        let counters: Vec<Option<u32>> = positions.iter().map(|&pos| self.counter(pos)).collect();
+        let frozen: Vec<Option<u32>> = positions.iter().map(|&pos| self.frozen_turns(pos)).collect();
+        let walls: Vec<Option<(WallType, u32)>> = positions.iter().map(|pos| self.state.walls.get(pos).copied()).collect();
+
+        for (index, &pos) in positions.iter().enumerate() {
+            let source = index as i32 - i32::from(offset);
+            let source = if wrap {
+                Some(source.rem_euclid(len) as usize)
+            } else if (0..len).contains(&source) {
+                Some(source as usize)
+            } else {
+                None
+            };
+
+            self.set_piece(pos, source.map_or(Piece::Empty, |source| pieces[source]));
+            if let Some(counter) = source.and_then(|source| counters[source]) {
+                self.state.counters.insert(pos, counter);
+            }
+            if let Some(turns) = source.and_then(|source| frozen[source]) {
+                self.state.frozen.insert(pos, turns);
+            }
+            if let Some(wall) = source.and_then(|source| walls[source]) {
+                self.state.walls.insert(pos, wall);
+            }
+        }
+    }
+
+    /// Moves every position on the board to a new position, possibly changing the
+    /// size of the board. Blocks are dissolved into separate pieces and the staging
+    /// rows are reset to zero. Then the positions that were marked for a match check
+    /// are marked again at their new positions, followed by every other position,
+    /// all with the [Scripted](MatchSource::Scripted) source.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - the width of the board after the transformation
+    /// * `height` - the height of the board after the transformation
+    /// * `map_pos` - gets the new position of each position on the board
+    /// * `map_direction` - gets the new direction of each movable direction
+    fn transform(&mut self, width: u8, height: u8, map_pos: impl Fn(Pos) -> Pos,
+                 map_direction: impl Fn(Direction) -> Direction) {
+        let block_anchors: Vec<Pos> = self.state.blocks.iter().map(|block| block.anchor()).collect();
+        self.remove_blocks_in(&block_anchors);
+
+        let map_board = |board: &BitBoard| {
+            let mut new_board = BitBoard::new(width, height);
+            board.iter_set().for_each(|pos| new_board.set(map_pos(pos)));
+            new_board
+        };
+
+        let old_width = self.state.width;
+        let old_height = self.state.height;
+        let mut type_grid = vec![0; usize::from(width) * usize::from(height)];
+        for y in 0..old_height {
+            for x in 0..old_width {
+                let new_pos = map_pos(Pos::new(x, y));
+                let new_index = usize::from(new_pos.y()) * usize::from(width) + usize::from(new_pos.x());
+                type_grid[new_index] = self.state.type_grid[self.grid_index(Pos::new(x, y))];
+            }
+        }
+
+        let mut movable_directions = [
+            BitBoard::new(width, height),
+            BitBoard::new(width, height),
+            BitBoard::new(width, height),
+            BitBoard::new(width, height)
+        ];
+        let mut gravity_directions = movable_directions.clone();
+        for direction in ALL_DIRECTIONS {
+            movable_directions[map_direction(direction) as usize] =
+                map_board(&self.state.movable_directions[direction as usize]);
+            gravity_directions[map_direction(direction) as usize] =
+                map_board(&self.state.gravity_directions[direction as usize]);
+        }
+
+        let pieces = self.state.pieces.map_boards(&map_board);
+        let empties = map_board(&self.state.empties);
+        let last_changed: VecDeque<Pos> = self.state.last_changed.iter().map(|&pos| map_pos(pos)).collect();
+        let counters = self.state.counters.iter().map(|(&pos, &counter)| (map_pos(pos), counter)).collect();
+        let frozen = self.state.frozen.iter().map(|(&pos, &turns)| (map_pos(pos), turns)).collect();
+        let walls = self.state.walls.iter().map(|(&pos, &wall)| (map_pos(pos), wall)).collect();
+        let exits = map_board(&self.exits);
+        let locked = map_board(&self.locked);
+        let match_prevented = map_board(&self.match_prevented);
+        let portals = self.portals.iter().map(|(&exit, &entry)| (map_pos(exit), map_pos(entry))).collect();
+        let cascade_region = self.cascade_region.as_ref()
+            .map(|region| region.iter().map(|&pos| map_pos(pos)).collect());
+
+        self.state.width = width;
+        self.state.height = height;
+        self.compiled_patterns = CompiledPattern::compile_all(&self.patterns, width, height);
+        self.state.pieces = pieces;
+        self.state.type_grid = type_grid;
+        self.state.empties = empties;
+        self.state.movable_directions = movable_directions;
+        self.state.gravity_directions = gravity_directions;
+        self.state.changed = BitBoard::new(width, height);
+        self.state.last_changed = VecDeque::new();
+        self.state.change_sources = HashMap::new();
+        self.state.change_order = ChangeOrder::default();
+        self.state.counters = counters;
+        self.state.frozen = frozen;
+        self.state.walls = walls;
+        self.exits = exits;
+        self.locked = locked;
+        self.match_prevented = match_prevented;
+        self.staging_rows = 0;
+        self.portals = portals;
+        self.cascade_region = cascade_region;
+        self.refresh_zobrist();
+
+        for pos in last_changed {
+            self.state.mark_changed(pos, MatchSource::Scripted);
+        }
+        for y in 0..height {
+            for x in 0..width {
+                self.state.mark_changed(Pos::new(x, y), MatchSource::Scripted);
+            }
+        }
+    }
+
+    /// Finds the highest-ranked match that includes the given position on the
+    /// board as it is now, without changing the positions marked for a match check.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position that must be included in a match
+    pub(crate) fn match_at(&self, pos: Pos) -> Option<Match<'_>> {
+        Board::find_match(&self.patterns, &self.compiled_patterns, &self.state.pieces, &self.tags, &self.match_prevented, pos)
+    }
+
+    /// Finds the highest-ranked match that includes the given position.
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - the patterns to check, sorted from highest to lowest rank
+    /// * `compiled_patterns` - the patterns prepared for the board's size, in the same order
+    /// * `boards` - the bitboards for each piece type
+    /// * `tags` - the piece types that have each tag
+    /// * `prevented` - the spaces that cannot be part of a match
+    /// * `pos` - the position that must be included in a match
+    fn find_match<'a>(patterns: &'a [MatchPattern], compiled_patterns: &[CompiledPattern], boards: &TypeTable,
+                      tags: &HashMap<String, HashSet<PieceType>>, prevented: &BitBoard,
+                      pos: Pos) -> Option<Match<'a>> {
+        patterns.iter().zip(compiled_patterns).find_map(|(pattern, compiled)| {
+            if let Some(min_size) = pattern.cluster_size() {
+                return Board::find_cluster(pattern, min_size, boards, tags, prevented, pos);
+            }
+
+            let positions = match pattern.tag().and_then(|tag| tags.get(tag)) {
+                Some(types) => {
+                    let pattern_type = pattern.piece_type();
+                    let board = Board::union_boards(types.iter().chain(Some(&pattern_type)), boards)?;
+                    Board::check_pattern(&board, pattern, compiled, prevented, pos)?
+                },
+                None => Board::check_pattern(boards.get(&pattern.piece_type())?, pattern, compiled, prevented, pos)?
+            };
+
+            Some(Match::new(pattern, pos, positions))
+        })
+    }
+
+    /// Finds the group of connected pieces that includes the given position if
+    /// the group is large enough to match a cluster pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - the cluster pattern to check
+    /// * `min_size` - the fewest pieces a group needs to match
+    /// * `boards` - the bitboards for each piece type
+    /// * `tags` - the piece types that have each tag
+    /// * `prevented` - the spaces that cannot be part of a match
+    /// * `pos` - the position that must be included in a match
+    fn find_cluster<'a>(pattern: &'a MatchPattern, min_size: usize, boards: &TypeTable,
+                        tags: &HashMap<String, HashSet<PieceType>>, prevented: &BitBoard,
+                        pos: Pos) -> Option<Match<'a>> {
+        let (piece_type, board) = match pattern.is_any_type() {
+            true => boards.iter()
+                .find(|(_, board)| board.is_set(pos))
+                .map(|(&piece_type, board)| (piece_type, board.clone()))?,
+            false => {
+                let pattern_type = pattern.piece_type();
+                let tagged_types = pattern.tag().and_then(|tag| tags.get(tag));
+                let types = tagged_types.into_iter().flatten().chain(Some(&pattern_type));
+                (pattern_type, Board::union_boards(types, boards)?)
+            }
+        };
+
+        if !board.is_set(pos) || prevented.is_set(pos) {
+            return None;
+        }
+
+        let group = flood(pos, |neighbor| {
+            neighbor.x() < board.width() && neighbor.y() < board.height() && board.is_set(neighbor)
+                && !prevented.is_set(neighbor)
+        });
+        if group.len() < min_size || !pattern.is_anchored(&group) {
+            return None;
+        }
+
+        Some(Match::new(pattern, pos, group).with_piece_type(piece_type))
+    }
+
+    /// Checks whether a pattern could still be formed with the pieces already on
+    /// the board. See [can_any_pattern_still_match()](Board::can_any_pattern_still_match).
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - the pattern to check
+    /// * `movable` - the non-empty spaces that are movable in at least one direction
+    fn can_pattern_still_match(&self, pattern: &MatchPattern, movable: &BitBoard) -> bool {
+        if pattern.is_any_type() {
+            return self.state.pieces.values().any(|board| board.count() >= pattern.min_pieces());
+        }
+
+        let pattern_type = pattern.piece_type();
+        let tagged_types = pattern.tag().and_then(|tag| self.tags.get(tag));
+        let types = tagged_types.into_iter().flatten().chain(Some(&pattern_type));
+        let matching = match Board::union_boards(types, &self.state.pieces) {
+            Some(matching) => matching,
+            None => return false
+        };
+
+        let mut movable_matching = matching.clone();
+        movable_matching.intersect(movable);
+        let total_movable = movable_matching.count();
+        if matching.count() < pattern.min_pieces() {
+            return false;
+        }
+
+        // Any group of pieces could be moved together into a cluster
+        if pattern.cluster_size().is_some() {
+            return true;
+        }
+
+        let mask = pattern.mask();
+        if mask.width() > self.state.width || mask.height() > self.state.height {
+            return false;
+        }
+
+        (0..=(self.state.height - mask.height())).any(|y| (0..=(self.state.width - mask.width())).any(|x| {
+            let mut in_place = 0;
+            let mut movable_in_place = 0;
+
+            for &offset in pattern.offsets() {
+                let pos = Pos::new(x + offset.x(), y + offset.y());
+                if matching.is_set(pos) {
+                    in_place += 1;
+                    if movable.is_set(pos) {
+                        movable_in_place += 1;
+                    }
+                } else if !self.state.empties.is_set(pos) && !movable.is_set(pos) {
+                    return false;
+                }
+            }
+
+            total_movable - movable_in_place >= pattern.offsets().len() - in_place
+        }))
+    }
+
+    /// Combines the bitboards of several piece types. Returns None if none of
+    /// the types has a bitboard.
+    ///
+    /// # Arguments
+    ///
+    /// * `types` - the piece types whose bitboards to combine
+    /// * `boards` - the bitboards for each piece type
+    fn union_boards<'a>(types: impl Iterator<Item = &'a PieceType>,
+                        boards: &TypeTable) -> Option<BitBoard> {
+        types.filter_map(|piece_type| boards.get(piece_type)).fold(None, |combined, board| {
+            match combined {
+                Some(mut combined) => {
+                    combined.union(board);
+                    Some(combined)
+                },
+                None => Some(board.clone())
+            }
+        })
+    }
+
+    /// Creates a bitboard with every position that holds a regular piece set.
+    fn regular_bitboard(&self) -> BitBoard {
+        Board::union_boards(self.state.pieces.keys(), &self.state.pieces)
+            .unwrap_or_else(|| BitBoard::new(self.state.width, self.state.height))
+    }
+
+    /// Gets the type of a piece at a certain position. If there is no regular piece
+    /// at that position (i.e. it is empty or a wall), Option::None is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece whose type to find
+    fn piece_type(&self, pos: Pos) -> Option<PieceType> {
+        self.state.pieces.piece_type(self.state.type_grid[self.grid_index(pos)])
+    }
+
+    /// Gets the ID of a piece type, which is its value in the type grid, adding
+    /// the type to the board's type table if it has not been seen before. Zero
+    /// is reserved for spaces without a regular piece.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type to find the ID of
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type is new and the type table already contains 65,535 types.
+    fn type_index(&mut self, piece_type: PieceType) -> TypeId {
+        self.state.pieces.id_or_insert(piece_type, self.state.width, self.state.height)
+    }
+
+    /// Converts a position into its index in the type grid.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to convert
+    fn grid_index(&self, pos: Pos) -> usize {
+        usize::from(pos.y()) * usize::from(self.state.width) + usize::from(pos.x())
+    }
+
+    /// Gets all of the movable directions for a piece at a given position.
+    /// Empty pieces are always movable in all directions, while walls are
+    /// movable in no directions.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece whose movable directions to find
+    fn movable_directions(&self, pos: Pos) -> EnumSet<Direction> {
+        let mut directions = EnumSet::new();
+
+        for direction in ALL_DIRECTIONS {
+            if self.state.movable_directions[direction as usize].is_set(pos) {
+                directions.insert(direction);
+            }
+        }
+
+        directions
+    }
+
+    /// Makes a regular piece movable or unmovable in one direction, both for
+    /// swaps and for gravity. Returns true if the piece is a regular piece.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to change
+    /// * `direction` - the direction to change
+    /// * `is_movable` - whether the piece will be movable in the direction
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board.
+    fn change_direction(&mut self, pos: Pos, direction: Direction, is_movable: bool) -> bool {
+        if !self.is_within_board(pos) {
+            panic!("Tried to change movable direction outside board: {}", pos);
+        }
+
+        if self.piece_type(pos).is_none() {
+            return false;
+        }
+
+        for board in [&mut self.state.movable_directions, &mut self.state.gravity_directions] {
+            match is_movable {
+                true => board[direction as usize].set(pos),
+                false => board[direction as usize].unset(pos)
+            }
+        }
+        true
+    }
+
+    /// Sets the movable directions for a piece at a given position.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece whose movable directions to set
+    /// * `directions` the new movable directions of the piece
+    fn set_movable_directions(&mut self, pos: Pos, directions: EnumSet<Direction>) {
+        for direction in ALL_DIRECTIONS {
+            let ordinal = direction as usize;
+            if directions.contains(direction) {
+                self.state.movable_directions[ordinal].set(pos);
+            } else {
+                self.state.movable_directions[ordinal].unset(pos);
+            }
+        }
+    }
+
+    /// Sets the directions in which gravity can move a piece at a given position.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece whose gravity directions to set
+    /// * `directions` the new gravity directions of the piece
+    fn set_gravity_directions(&mut self, pos: Pos, directions: EnumSet<Direction>) {
+        for direction in ALL_DIRECTIONS {
+            let ordinal = direction as usize;
+            if directions.contains(direction) {
+                self.state.gravity_directions[ordinal].set(pos);
+            } else {
+                self.state.gravity_directions[ordinal].unset(pos);
+            }
+        }
+    }
+
+    /// Checks if two positions are horizontally or vertically adjacent.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position to check
+    /// * `second` - the second position to check
+    fn are_adjacent(first: Pos, second: Pos) -> bool {
+        let dx = max(first.x(), second.x()) - min(first.x(), second.x());
+        let dy = max(first.y(), second.y()) - min(first.y(), second.y());
+        dx + dy == 1
+    }
+
+    /// Checks if the pieces at two positions on the board are both movable in the
+    /// direction in which they would be swapped and neither space is locked,
+    /// holds a frozen piece, or is in a staging row.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the position of the first piece to check
+    /// * `second` - the position of the second piece to check
+    fn are_pieces_movable(&self, first: Pos, second: Pos) -> bool {
+        if self.is_held(first) || self.is_held(second) {
+            return false;
+        }
+
+        let is_first_movable = self.is_movable(first, second) || self.is_swappable_wall(first, second);
+        let is_second_movable = self.is_movable(second, first) || self.is_swappable_wall(second, first);
+
+        is_first_movable && is_second_movable
+    }
+
+    /// Checks if a position holds a wall that can be swapped because the
+    /// piece at the other position can swap with walls.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position that may hold a wall
+    /// * `other` - the position of the piece swapped with the wall
+    fn is_swappable_wall(&self, pos: Pos, other: Pos) -> bool {
+        self.piece(pos) == Piece::Wall && match self.piece(other) {
+            Piece::Regular(piece_type, _) => self.wall_swappers.contains(&piece_type),
+            _ => false
+        }
+    }
+
+    /// Gets the position of a wall that prevents two different positions from
+    /// being swapped, if there is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    pub(crate) fn blocking_wall(&self, first: Pos, second: Pos) -> Option<Pos> {
+        if first == second {
+            return None;
+        }
+
+        [(first, second), (second, first)].iter()
+            .find(|&&(pos, other)| self.piece(pos) == Piece::Wall && !self.is_swappable_wall(pos, other))
+            .map(|&(pos, _)| pos)
+    }
+
+    /// Checks if a space cannot be swapped because it is locked, its piece
+    /// is frozen, or it is in a staging row.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to check
+    fn is_held(&self, pos: Pos) -> bool {
+        self.locked.is_set(pos) || self.state.frozen.contains_key(&pos) || pos.y() >= self.visible_height()
+    }
+
+    /// Checks if a piece is movable vertically and horizontally.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - the current position of the piece
+    /// * `to` - the position where the piece will be moved
+    fn is_movable(&self, from: Pos, to: Pos) -> bool {
+        self.is_vertically_movable(from, to) && self.is_horizontally_movable(from, to)
+    }
+
+    /// Checks if a piece is vertically movable from one position to another.
+    /// If there is no vertical change between the two positions, the piece
+    /// is considered movable.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - the current position of the piece
+    /// * `to` - the position where the piece will be moved
+    fn is_vertically_movable(&self, from: Pos, to: Pos) -> bool {
+        if to.y() > from.y() {
+            return self.state.movable_directions[Direction::North as usize].is_set(from);
+        } else if to.y() < from.y() {
+            return self.state.movable_directions[Direction::South as usize].is_set(from);
+        }
+
+        true
+    }
+
+    /// Checks if a piece is horizontally movable from one position to another.
+    /// If there is no horizontal change between the two positions, the piece
+    /// is considered movable.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - the current position of the piece
+    /// * `to` - the position where the piece will be moved
+    fn is_horizontally_movable(&self, from: Pos, to: Pos) -> bool {
+        if to.x() > from.x() {
+            return self.state.movable_directions[Direction::East as usize].is_set(from);
+        } else if to.x() < from.x() {
+            return self.state.movable_directions[Direction::West as usize].is_set(from);
+        }
+
+        true
+    }
+
+    /// Checks for a pattern that includes a specific position on the board. Looks
+    /// for all variants of a pattern (all possible patterns that include the required
+    /// position). Returns the positions on the board that correspond to that pattern
+    /// if there is a match.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - the board to check for a pattern
+    /// * `pattern` - the pattern to check for
+    /// * `compiled` - the pattern prepared for the board's size
+    /// * `prevented` - the spaces that cannot be part of a match
+    /// * `pos` - the position that must be included in a match
+    fn check_pattern(board: &BitBoard, pattern: &MatchPattern, compiled: &CompiledPattern,
+                     prevented: &BitBoard, pos: Pos) -> Option<PosSet> {
+
+        // Every variant includes the position, so none can match if it has another type
+        if !board.is_set(pos) {
+            return None;
+        }
+
+        // The cached pattern is always for the board's size unless the board was resized without recompiling
+        let resized;
+        let compiled = match compiled.is_for_size(board.width(), board.height()) {
+            true => compiled,
+            false => {
+                resized = CompiledPattern::new(pattern, board.width(), board.height());
+                &resized
+            }
+        };
+
+        pattern.offsets().iter().find_map(|&original| {
+
+            // Don't check variants outside the board
+            if original.x() > pos.x() || original.y() > pos.y() {
+                return None;
+            }
+
+            let new_origin = pos - original;
+            match compiled.origins().is_set(new_origin) {
+                true => Board::check_variant(board, pattern, prevented, new_origin),
+                false => None
+            }
+        })
+    }
+
+    /// Checks for a single variant of a pattern and returns the corresponding positions
+    /// on the board if found. The pattern's mask is compared with the board directly,
+    /// so the positions are only allocated if the variant matches. A variant that
+    /// includes a space where matches are prevented does not match, which is
+    /// checked with the pattern's offsets before any positions are collected.
+    ///
+    /// The origin must be one of the [compiled](CompiledPattern) pattern's
+    /// origins, so the variant is entirely on the board and meets the pattern's
+    /// anchor.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - the board to check for a variant
+    /// * `pattern` - the pattern to check for
+    /// * `prevented` - the spaces that cannot be part of a match
+    /// * `new_origin` - the origin to use for the pattern positions so that they
+    ///   correspond to actual positions on the board
+    fn check_variant(board: &BitBoard, pattern: &MatchPattern, prevented: &BitBoard,
+                     new_origin: Pos) -> Option<PosSet> {
+        if !board.contains_at(pattern.mask(), new_origin) {
+            return None;
+        }
+
+        let offsets = pattern.offsets();
+        let is_prevented = !prevented.is_empty() && offsets.iter().any(|&offset| prevented.is_set(offset + new_origin));
+        if is_prevented {
+            return None;
+        }
+
+        Some(Board::change_origin(offsets, new_origin))
+    }
+
+    /// Changes the origin of a list of points.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - the positions to change the origin of
+    /// * `origin` - the new origin to use for the positions
+    fn change_origin(positions: &[Pos], origin: Pos) -> PosSet {
+        positions.iter().map(|&original| original + origin).collect()
+    }
+
+    /// Moves all pieces down until none can fall, including falling blocks.
+    /// Returns the steps made. See [trickle()](Board::trickle).
+    fn fall_all(&mut self) -> Vec<(Pos, Pos)> {
+        let mut steps = Vec::new();
+
+        // Pieces above a falling block can fall again once it lands
+        loop {
+            for x in 0..self.state.width {
+                steps.append(&mut self.trickle_column(x));
+            }
+            steps.append(&mut self.trickle_diagonally());
+
+            let mut block_steps = self.drop_blocks();
+            if block_steps.is_empty() {
+                break;
+            }
+            steps.append(&mut block_steps);
+        }
+
+        steps
+    }
+
+    /// Runs a gravity operation while floating pieces cannot fall. Floating
+    /// pieces are temporarily made unable to move south, then restored, so
+    /// the operation must not move them.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - the gravity operation to run
+    fn with_floating_held<T>(&mut self, operation: impl FnOnce(&mut Board) -> T) -> T {
+        let floating = match Board::union_boards(self.floating.iter(), &self.state.pieces) {
+            Some(mut floating) => {
+                floating.intersect(&self.state.gravity_directions[Direction::South as usize]);
+                floating
+            },
+            None => return operation(self)
+        };
+
+        self.state.gravity_directions[Direction::South as usize].difference(&floating);
+        let result = operation(self);
+        self.state.gravity_directions[Direction::South as usize].union(&floating);
+
+        result
+    }
+
+    /// Checks whether the piece at a position is a floating piece.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to check
+    fn is_floating_at(&self, pos: Pos) -> bool {
+        match self.piece(pos) {
+            Piece::Regular(piece_type, _) => self.floating.contains(&piece_type),
+            _ => false
+        }
+    }
+
+    /// Moves every floating piece in a column up into the empty spaces above
+    /// it, from the top of the column down. Returns the steps made.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - the x coordinate of the column
+    fn raise_floating_pieces(&mut self, x: u8) -> Vec<(Pos, Pos)> {
+        let mut steps = Vec::new();
+        if self.floating.is_empty() {
+            return steps;
+        }
+
+        for y in (0..self.state.height).rev() {
+            let pos = Pos::new(x, y);
+            if self.is_floating_at(pos) {
+                steps.append(&mut self.raise_piece(pos));
+            }
+        }
+
+        steps
+    }
+
+    /// Moves a piece straight up until the space above it is not empty or it
+    /// reaches the top of the board. Returns the steps made.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_pos` - the current position of the piece to move
+    fn raise_piece(&mut self, piece_pos: Pos) -> Vec<(Pos, Pos)> {
+        let mut steps = Vec::new();
+        let can_rise = self.state.gravity_directions[Direction::North as usize].is_set(piece_pos)
+            && self.block_at(piece_pos).is_none();
+        if !can_rise {
+            return steps;
+        }
+
+        let mut next_y = piece_pos.y();
+        while next_y + 1 < self.state.height && self.state.empties.is_set(Pos::new(piece_pos.x(), next_y + 1)) {
+            next_y += 1;
+        }
+
+        let landed_pos = Pos::new(piece_pos.x(), next_y);
+        if landed_pos != piece_pos {
+            self.swap_unmarked(piece_pos, landed_pos);
+            for y in piece_pos.y()..next_y {
+                steps.push((Pos::new(piece_pos.x(), y), Pos::new(piece_pos.x(), y + 1)));
+            }
+        }
+
+        steps
+    }
+
+    /// Runs a gravity operation while locked spaces act like walls. Locked spaces
+    /// are temporarily made not empty and unable to fall, then restored.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - the gravity operation to run
+    fn with_locked_frozen<T>(&mut self, operation: impl FnOnce(&mut Board) -> T) -> T {
+        if self.locked.is_empty() {
+            return operation(self);
+        }
+
+        let mut locked_empties = self.state.empties.clone();
+        locked_empties.intersect(&self.locked);
+        self.state.empties.difference(&self.locked);
+
+        let mut locked_gravity = self.state.gravity_directions.clone();
+        for (saved, gravity_board) in locked_gravity.iter_mut().zip(self.state.gravity_directions.iter_mut()) {
+            saved.intersect(&self.locked);
+            gravity_board.difference(&self.locked);
+        }
+
+        let result = operation(self);
+
+        self.state.empties.union(&locked_empties);
+        for (saved, gravity_board) in locked_gravity.iter().zip(self.state.gravity_directions.iter_mut()) {
+            gravity_board.union(saved);
+        }
+
+        result
+    }
+
+    /// Gets everything that decides where the board's pieces fall.
+    fn fall_setup(&self) -> FallSetup {
+        FallSetup {
+            type_grid: self.state.type_grid.clone(),
+            empties: self.state.empties.clone(),
+            gravity_directions: self.state.gravity_directions.clone(),
+            blocks: self.state.blocks.clone(),
+            locked: self.locked.clone(),
+            portals: self.portals.clone(),
+            weights: self.weights.clone(),
+            floating: self.floating.clone(),
+            diagonal_fill: self.diagonal_fill
+        }
+    }
+
+    /// Plans the steps that [trickle()](Board::trickle) would make from the
+    /// board's current state, split into rounds. See [schedule_fall()](Board::schedule_fall).
+    fn plan_fall(&self) -> VecDeque<Vec<(Pos, Pos)>> {
+        let steps = self.scratch_copy().settle();
+        let blocks: Vec<(Pos, usize)> = self.state.blocks.iter()
+            .map(|block| (block.anchor(), usize::from(block.width()) * usize::from(block.height())))
+            .collect();
+        Board::schedule_fall(&steps, blocks)
+    }
+
+    /// Splits the steps of a fall into rounds in which each piece moves at
+    /// most one space. Each step happens in the round after the piece's
+    /// previous step, but no earlier than the round in which the space it
+    /// moves into was emptied. Playing the rounds in order makes the same
+    /// steps in the same order for each space, so the pieces end where the
+    /// fall left them.
+    ///
+    /// A block's steps for falling one space and the two steps of a trade
+    /// always happen in the same round.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - (from position, to position) steps in the order they were made
+    /// * `blocks` - the anchor and number of spaces of each block before the fall
+    fn schedule_fall(steps: &[(Pos, Pos)], mut blocks: Vec<(Pos, usize)>) -> VecDeque<Vec<(Pos, Pos)>> {
+        let mut rounds: VecDeque<Vec<(Pos, Pos)>> = VecDeque::new();
+        let mut piece_at: HashMap<Pos, usize> = HashMap::new();
+        let mut next_rounds: Vec<usize> = Vec::new();
+        let mut emptied_rounds: HashMap<Pos, usize> = HashMap::new();
+
+        let mut index = 0;
+        while index < steps.len() {
+            let (from, to) = steps[index];
+            let group_len = match blocks.iter_mut().find(|(anchor, _)| *anchor == from) {
+                Some((anchor, size)) => {
+                    *anchor = to;
+                    *size
+                },
+                None if steps.get(index + 1) == Some(&(to, from)) => 2,
+                None => 1
+            };
+            let group = &steps[index..min(index + group_len, steps.len())];
+            let is_entered = |pos: Pos| group.iter().any(|&(_, to)| to == pos);
+            let is_left = |pos: Pos| group.iter().any(|&(from, _)| from == pos);
+
+            let round = group.iter().map(|&(from, to)| {
+                let piece_round = piece_at.get(&from).map_or(0, |&piece| next_rounds[piece]);
+                let space_round = match is_left(to) {
+                    true => 0,
+                    false => emptied_rounds.get(&to).copied().unwrap_or(0)
+                };
+                max(piece_round, space_round)
+            }).max().unwrap_or(0);
+
+            let pieces: Vec<usize> = group.iter().map(|(from, _)| piece_at.remove(from).unwrap_or_else(|| {
+                next_rounds.push(0);
+                next_rounds.len() - 1
+            })).collect();
+            for (&(from, to), piece) in group.iter().zip(pieces) {
+                next_rounds[piece] = round + 1;
+                piece_at.insert(to, piece);
+                if !is_entered(from) {
+                    emptied_rounds.insert(from, round);
+                }
+            }
+
+            if rounds.len() <= round {
+                rounds.resize_with(round + 1, Vec::new);
+            }
+            rounds[round].extend_from_slice(group);
+            index += group.len();
+        }
+
+        rounds
+    }
+
+    /// Makes one round of a planned fall. See [schedule_fall()](Board::schedule_fall).
+    ///
+    /// # Arguments
+    ///
+    /// * `round` - the (from position, to position) steps to make
+    fn play_round(&mut self, round: &[(Pos, Pos)]) {
+        let mut index = 0;
+        while index < round.len() {
+            let (from, to) = round[index];
+            self.swap_unmarked(from, to);
+
+            // Both steps of a trade are made by one swap
+            if round.get(index + 1) == Some(&(to, from)) {
+                index += 1;
+            }
+            index += 1;
+        }
+
+        self.move_block_anchors(round);
+    }
+
+    /// Moves all the pieces in a column down to fill empty spaces directly beneath them.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - the x coordinate of the column to trickle
+    fn trickle_column(&mut self, x: u8) -> Vec<(Pos, Pos)> {
+        let mut empty_spaces = VecDeque::new();
+        let mut moves = Vec::new();
+
+        for y in 0..self.state.height {
+            let current_pos = Pos::new(x, y);
+            if self.state.empties.is_set(current_pos) {
+                empty_spaces.push_back(y);
+            } else if self.state.gravity_directions[Direction::South as usize].is_set(current_pos) {
+                let mut landed_pos = current_pos;
+                if let Some(space_to_fill) = empty_spaces.pop_front() {
+                    landed_pos = Pos::new(x, space_to_fill);
+                    self.swap_unmarked(current_pos, landed_pos);
+                    empty_spaces.push_back(y);
+                    Board::push_fall_steps(current_pos, landed_pos, &mut moves);
+                }
+
+                if self.trickle_through_portals(landed_pos, &mut moves) != landed_pos {
+                    empty_spaces.push_front(landed_pos.y());
+                }
+            } else {
+                empty_spaces.clear();
+            }
+        }
+
+        moves
+    }
+
+    /// Moves heavier pieces in a column down through the lighter pieces directly
+    /// beneath them until every piece rests on a piece at least as heavy, a
+    /// piece that cannot fall, or the bottom of the board. Each trade is added
+    /// as a pair of steps in opposite directions.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - the x coordinate of the column to sort by weight
+    fn sink_heavy_pieces(&mut self, x: u8) -> Vec<(Pos, Pos)> {
+        let mut steps = Vec::new();
+        if self.weights.is_empty() {
+            return steps;
+        }
+
+        let mut is_sorted = false;
+        while !is_sorted {
+            is_sorted = true;
+
+            for y in 1..self.state.height {
+                let lower = Pos::new(x, y - 1);
+                let upper = Pos::new(x, y);
+                if let (Some(lower_weight), Some(upper_weight)) = (self.sinking_weight(lower), self.sinking_weight(upper)) {
+                    if upper_weight > lower_weight {
+                        self.swap_unmarked(upper, lower);
+                        steps.push((upper, lower));
+                        steps.push((lower, upper));
+                        is_sorted = false;
+                    }
+                }
+            }
+        }
+
+        steps
+    }
+
+    /// Moves a piece down through the lighter pieces directly beneath it until
+    /// it rests on a piece at least as heavy, a piece that cannot fall, or the
+    /// bottom of the board. Each trade is added as a pair of steps in opposite
+    /// directions.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_pos` - the current position of the piece to sink
+    fn sink_piece(&mut self, piece_pos: Pos) -> Vec<(Pos, Pos)> {
+        let mut steps = Vec::new();
+        let weight = match self.sinking_weight(piece_pos) {
+            Some(weight) if weight > 0 => weight,
+            _ => return steps
+        };
+
+        let mut current_pos = piece_pos;
+        while current_pos.y() > 0 {
+            let lower = Pos::new(current_pos.x(), current_pos.y() - 1);
+            match self.sinking_weight(lower) {
+                Some(lower_weight) if lower_weight < weight => {
+                    self.swap_unmarked(current_pos, lower);
+                    steps.push((current_pos, lower));
+                    steps.push((lower, current_pos));
+                    current_pos = lower;
+                },
+                _ => break
+            }
+        }
+
+        steps
+    }
+
+    /// Gets the weight of the piece at a position if it can trade places with
+    /// other pieces by weight, or None if it is not a regular piece that can
+    /// fall, is floating, or is part of a [Block].
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece
+    fn sinking_weight(&self, pos: Pos) -> Option<u32> {
+        match self.piece(pos) {
+            Piece::Regular(piece_type, _) if self.state.gravity_directions[Direction::South as usize].is_set(pos)
+                && !self.floating.contains(&piece_type)
+                && self.block_at(pos).is_none() => Some(self.weight(piece_type)),
+            _ => None
+        }
+    }
+
+    /// Adds a step for each space a piece passed through while falling straight down.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - the position the piece fell from
+    /// * `to` - the position the piece landed in, directly below `from`
+    /// * `steps` - the steps made so far, which the piece's steps are added to
+    fn push_fall_steps(from: Pos, to: Pos, steps: &mut Vec<(Pos, Pos)>) {
+        for y in (to.y()..from.y()).rev() {
+            steps.push((Pos::new(from.x(), y + 1), Pos::new(from.x(), y)));
+        }
+    }
+
+    /// Combines single steps into one move for each piece. A step starting
+    /// where an earlier step ended belongs to the same piece, since no other
+    /// piece can move into a space that is still occupied. The only exception
+    /// is two pieces trading places, which is a pair of consecutive steps in
+    /// opposite directions.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - (from position, to position) steps in the order they were made
+    fn collect_piece_moves(steps: &[(Pos, Pos)]) -> Vec<PieceMove> {
+        let mut moves: Vec<PieceMove> = Vec::new();
+        let mut move_at: HashMap<Pos, usize> = HashMap::new();
+
+        let mut index = 0;
+        while index < steps.len() {
+            let (from, to) = steps[index];
+            let is_trade = steps.get(index + 1) == Some(&(to, from));
+            let traded_index = match is_trade {
+                true => move_at.remove(&to),
+                false => None
+            };
+
+            Board::push_piece_step(&mut moves, &mut move_at, from, to);
+            if is_trade {
+                match traded_index {
+                    Some(traded_index) => {
+                        moves[traded_index].path.push(from);
+                        move_at.insert(from, traded_index);
+                    },
+                    None => {
+                        move_at.insert(from, moves.len());
+                        moves.push(PieceMove::new(vec![to, from]));
+                    }
+                }
+                index += 1;
+            }
+
+            index += 1;
+        }
+
+        moves
+    }
+
+    /// Adds a single step to the move of the piece that ended at the step's
+    /// starting position, or starts a new move if no piece ended there.
+    ///
+    /// # Arguments
+    ///
+    /// * `moves` - the moves made so far
+    /// * `move_at` - the index of the move that ended at each position
+    /// * `from` - the position the piece moved from
+    /// * `to` - the position the piece moved to
+    fn push_piece_step(moves: &mut Vec<PieceMove>, move_at: &mut HashMap<Pos, usize>, from: Pos, to: Pos) {
+        match move_at.remove(&from) {
+            Some(index) => {
+                moves[index].path.push(to);
+                move_at.insert(to, index);
+            },
+            None => {
+                move_at.insert(to, moves.len());
+                moves.push(PieceMove::new(vec![from, to]));
+            }
+        }
+    }
+
+    /// Marks the positions changed by gravity for a match check according to
+    /// the board's gravity marking. Returns the moves made by each piece.
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - (from position, to position) steps in the order they were made
+    fn mark_gravity_moves(&mut self, steps: &[(Pos, Pos)]) -> Vec<PieceMove> {
+        let moves = Board::collect_piece_moves(steps);
+
+        let marked: Vec<Pos> = match self.gravity_marking {
+            GravityMarking::All => steps.iter().flat_map(|&(from, to)| vec![from, to]).collect(),
+            GravityMarking::Final => moves.iter().map(PieceMove::to).collect(),
+            GravityMarking::Off => Vec::new()
+        };
+        let prevented = &self.match_prevented;
+        for pos in marked.into_iter().filter(|&pos| !prevented.is_set(pos)) {
+            self.state.mark_changed(pos, MatchSource::Gravity);
+        }
+
+        for piece_move in moves.iter() {
+            self.record_effect(Effect::Moved { from: piece_move.from(), to: piece_move.to() });
+        }
+
+        moves
+    }
+
+    /// Moves all pieces in the board diagonally and down until they can no longer be moved.
+    /// Should be called after [trickle_column()](Board::trickle_column) is run on all columns.
+    fn trickle_diagonally(&mut self) -> Vec<(Pos, Pos)> {
+        let mut moves = Vec::new();
+
+        for y in 0..self.state.height {
+            for x in 0..self.state.width {
+                moves.append(&mut self.trickle_piece(Pos::new(x, y), true));
+            }
+        }
+
+        moves
+    }
+
+    /// Moves a piece down and diagonally until it can no longer be moved.
+    /// Returns the list of swaps that makes the piece fall naturally. See
+    /// [trickle()] for a complete list of rules on how the piece is
+    /// trickled.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_pos` - the position of the piece to trickle
+    /// * `check_adj` - whether to check if the horizontally adjacent piece
+    ///   will fall to fill the spot when all pieces in the row
+    ///   are trickled
+    fn trickle_piece(&mut self, piece_pos: Pos, check_adj: bool) -> Vec<(Pos, Pos)> {
+        let mut moves = Vec::new();
+
+        if self.state.empties.is_set(piece_pos) {
+            return moves;
+        }
+
+        let mut previous_trickled_pos;
+        let mut current_trickled_pos = piece_pos;
+
+        loop {
+            current_trickled_pos = self.trickle_piece_down(current_trickled_pos, &mut moves);
+
+            previous_trickled_pos = current_trickled_pos;
+            current_trickled_pos = self.trickle_piece_diagonally(
+                previous_trickled_pos,
+                check_adj
+            );
+
+            if previous_trickled_pos == current_trickled_pos {
+                break;
+            } else {
+                moves.push((previous_trickled_pos, current_trickled_pos));
+            }
+        }
+
+        moves
+    }
+
+    /// Moves a piece diagonally (down and horizontally). If the space to the left
+    /// is open, then the piece moves down and to the left. Otherwise, it moves down
+    /// and to the right if that space is open. Returns the new position of the piece.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_pos` - the current position of the piece
+    /// * `check_adj` - whether to check if the horizontally adjacent piece
+    ///   will fall to fill the spot when all pieces in the row
+    ///   are trickled
+    fn trickle_piece_diagonally(&mut self, piece_pos: Pos, check_adj: bool) -> Pos {
+        let mut diagonally_trickled_pos = self.trickle_piece_to_side(piece_pos, true, check_adj);
+        if diagonally_trickled_pos == piece_pos {
+            diagonally_trickled_pos = self.trickle_piece_to_side(piece_pos, false, check_adj);
+        }
+
+        diagonally_trickled_pos
+    }
+
+    /// Moves a piece one space down and one space horizontally if there is an
+    /// empty space there. Returns the new position of the piece.
+    ///
+    /// # Arguments
+    ///
+    /// * `current_pos` - the current position of the piece to move
+    /// * `to_west` - whether to move the piece west (or east if false)
+    /// * `check_adj` - whether to check if the horizontally adjacent piece
+    ///   will fall to fill the spot when all pieces in the row
+    ///   are trickled
+    fn trickle_piece_to_side(&mut self, current_pos: Pos, to_west: bool, check_adj: bool) -> Pos {
+        if !self.can_move_pos_down_diagonally(current_pos, to_west) {
+            return current_pos;
+        }
+
+        let empty_pos = Board::move_pos_down_diagonally(current_pos, to_west);
+        let is_empty_pos = self.state.empties.is_set(empty_pos);
+
+        let horizontal_dir_board = match to_west {
+            true => &self.state.gravity_directions[Direction::West as usize],
+            false => &self.state.gravity_directions[Direction::East as usize]
+        };
+        let vertical_dir_board = &self.state.gravity_directions[Direction::South as usize];
+        let is_movable = horizontal_dir_board.is_set(current_pos) &&
+            vertical_dir_board.is_set(current_pos);
+
+        let adjacent_pos = Pos::new(empty_pos.x(), current_pos.y());
+        let will_adj_fill_space = check_adj && vertical_dir_board.is_set(adjacent_pos)
+            && !self.state.empties.is_set(adjacent_pos);
+        let is_allowed_fill = match self.diagonal_fill {
+            DiagonalFill::Always => true,
+            DiagonalFill::ShadowedOnly => self.is_shadowed(empty_pos)
+        };
+
+        if !is_empty_pos || !is_movable || will_adj_fill_space || !is_allowed_fill {
+            return current_pos;
+        }
+
+        self.swap_unmarked(current_pos, empty_pos);
+
+        empty_pos
+    }
+
+    /// Checks if no piece can reach an empty space by falling straight down.
+    /// The space is shadowed if the first space above it that is not empty
+    /// holds a wall or a piece that cannot fall, and neither that space nor
+    /// any empty space in between is a portal's entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the empty position to check
+    fn is_shadowed(&self, pos: Pos) -> bool {
+        for y in pos.y()..self.state.height {
+            let current_pos = Pos::new(pos.x(), y);
+            if self.portals.values().any(|&entry| entry == current_pos) {
+                return false;
+            }
+
+            if !self.state.empties.is_set(current_pos) {
+                return !self.state.gravity_directions[Direction::South as usize].is_set(current_pos);
+            }
+        }
+
+        false
+    }
+
+    /// Moves a piece down until it is moved into the lowest empty space directly
+    /// below it, including through portals. Returns the new position of the piece.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_pos` - the current position of the piece to move
+    /// * `moves` - the moves made so far, which the piece's moves are added to
+    fn trickle_piece_down(&mut self, piece_pos: Pos, moves: &mut Vec<(Pos, Pos)>) -> Pos {
+        let landed_pos = self.fall_piece_down(piece_pos, moves);
+        self.trickle_through_portals(landed_pos, moves)
+    }
+
+    /// Moves a piece down until it is moved into the lowest empty space directly
+    /// below it, ignoring portals. Returns the new position of the piece.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_pos` - the current position of the piece to move
+    /// * `moves` - the moves made so far, which the piece's move is added to
+    fn fall_piece_down(&mut self, piece_pos: Pos, moves: &mut Vec<(Pos, Pos)>) -> Pos {
+        let vertical_dir_board = &self.state.gravity_directions[Direction::South as usize];
+        if !vertical_dir_board.is_set(piece_pos){
+            return piece_pos;
+        }
+
+        let mut next_y = piece_pos.y();
+        while next_y > 0 && self.state.empties.is_set(Pos::new(piece_pos.x(), next_y - 1)) {
+            next_y -= 1;
+        }
+
+        let landed_pos = Pos::new(piece_pos.x(), next_y);
+        if landed_pos != piece_pos {
+            self.swap_unmarked(piece_pos, landed_pos);
+            Board::push_fall_steps(piece_pos, landed_pos, moves);
+        }
+
+        landed_pos
+    }
+
+    /// Moves a piece through the portal at its position to the portal's entry
+    /// and down from there, repeating while the piece lands on another portal's
+    /// exit. Each portal is used at most once per fall so that pieces cannot loop
+    /// forever. Returns the new position of the piece.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_pos` - the current position of the piece to move
+    /// * `moves` - the moves made so far, which the piece's moves are added to
+    fn trickle_through_portals(&mut self, piece_pos: Pos, moves: &mut Vec<(Pos, Pos)>) -> Pos {
+        let mut current_pos = piece_pos;
+
+        for _ in 0..self.portals.len() {
+            let entry = match self.portals.get(&current_pos) {
+                Some(&entry) => entry,
+                None => break
+            };
+
+            let is_movable = self.state.gravity_directions[Direction::South as usize].is_set(current_pos);
+            if !is_movable || self.state.empties.is_set(current_pos) || !self.state.empties.is_set(entry) {
+                break;
+            }
+
+            self.swap_unmarked(current_pos, entry);
+            moves.push((current_pos, entry));
+            current_pos = self.fall_piece_down(entry, moves);
+        }
+
+        current_pos
+    }
+
+    /// Swaps two pieces regardless of the swap rules. Pieces more than one
+    /// space apart can be swapped. Always successful. Marks both spaces
+    /// for a match check if they are different.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the position of a piece to swap
+    /// * `second` - the position of another piece to swap
+    /// * `source` - what caused the swap
+    pub(crate) fn swap_always(&mut self, first: Pos, second: Pos, source: MatchSource) {
+        if first == second {
+            return;
+        }
+
+        self.state.mark_changed(first, source);
+        self.state.mark_changed(second, source);
+        self.swap_unmarked(first, second);
+
+        if let Piece::Regular(..) = self.piece(second) {
+            self.record_effect(Effect::Moved { from: first, to: second });
+        }
+        if let Piece::Regular(..) = self.piece(first) {
+            self.record_effect(Effect::Moved { from: second, to: first });
+        }
+    }
+
+    /// Swaps two pieces regardless of the swap rules without marking either
+    /// space for a match check. Pieces more than one space apart can be swapped.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the position of a piece to swap
+    /// * `second` - the position of another piece to swap
+    pub(crate) fn swap_unmarked(&mut self, first: Pos, second: Pos) {
+        if first == second {
+            return;
+        }
+
+        let first_piece = self.piece(first);
+        let second_piece = self.piece(second);
+        self.update_zobrist(first, first_piece, second_piece);
+        self.update_zobrist(second, second_piece, first_piece);
+
+        for values in [&mut self.state.counters, &mut self.state.frozen] {
+            let first_value = values.remove(&first);
+            if let Some(value) = values.remove(&second) {
+                values.insert(first, value);
+            }
+            if let Some(value) = first_value {
+                values.insert(second, value);
+            }
+        }
+        let first_wall = self.state.walls.remove(&first);
+        if let Some(wall) = self.state.walls.remove(&second) {
+            self.state.walls.insert(first, wall);
+        }
+        if let Some(wall) = first_wall {
+            self.state.walls.insert(second, wall);
+        }
+
+        self.state.empties.swap(first, second);
+        self.state.movable_directions[0].swap(first, second);
+        self.state.movable_directions[1].swap(first, second);
+        self.state.movable_directions[2].swap(first, second);
+        self.state.movable_directions[3].swap(first, second);
+        for gravity_board in self.state.gravity_directions.iter_mut() {
+            gravity_board.swap(first, second);
+        }
+
+        let first_grid_index = self.grid_index(first);
+        let second_grid_index = self.grid_index(second);
+        let first_id = self.state.type_grid[first_grid_index];
+        let second_id = self.state.type_grid[second_grid_index];
+        self.state.type_grid.swap(first_grid_index, second_grid_index);
+
+        // We don't want to undo the swap if both pieces are of the same type
+        if first_id != second_id {
+            for &id in [first_id, second_id].iter().filter(|&&id| id != 0) {
+                self.state.pieces.board_mut(id).swap(first, second);
+            }
+        }
+    }
+
+    /// Checks if a position can move one space down and one space horizontally
+    /// and still be inside the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to move
+    /// * `to_west` - whether to move the position west (or east if false)
+    fn can_move_pos_down_diagonally(&self, pos: Pos, to_west: bool) -> bool {
+        match to_west {
+            true => pos.x() > 0 && pos.y() > 0,
+            false => pos.x() < self.state.width - 1 && pos.y() > 0
+        }
+    }
+
+    /// Moves a position one space down and one space horizontally.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to move
+    /// * `to_west` - whether to move the position west (or east if false)
+    fn move_pos_down_diagonally(pos: Pos, to_west: bool) -> Pos {
+        match to_west {
+            true => Pos::new(pos.x() - 1, pos.y() - 1),
+            false => Pos::new(pos.x() + 1, pos.y() - 1)
+        }
+    }
+
+    /// Checks if a given position is inside the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to check
+    pub(crate) fn is_within_board(&self, pos: Pos) -> bool {
+        pos.x() < self.state.width && pos.y() < self.state.height
+    }
+
+}
+
+impl Debug for Board {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("")
+            .field(&self.patterns)
+            .field(&self.state)
+            .finish()
+    }
+}
+
+impl Display for Board {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut str = String::new();
+
+        for y in (0..self.state.height).rev() {
+            for x in 0..self.state.width {
+                str.push_str(&self.piece(Pos::new(x, y)).to_string());
+            }
+
+            str.push('\n');
+        }
+
+        write!(f, "{}", str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, BoardState, DiagonalFill, GravityMarking, PieceMove, PosSet, SetPieceError, SwapError, TypeSwap};
+    use crate::position::Pos;
+    use crate::piece::{Piece, Direction, ALL_DIRECTIONS};
+    use std::collections::{HashSet, HashMap};
+    use crate::matching::{MatchPattern, MatchOrder, MatchSource, PatternAnchor};
+    use crate::events::{ClearCause, Effect};
+    use crate::random::Rng;
+    use crate::walls::WallKind;
+    use enumset::{enum_set, EnumSet};
+    use std::panic;
+
+    #[test]
+    #[should_panic]
+    fn get_piece_out_of_bounds_panics() {
+        let board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        board.piece(Pos::new(16, 16));
+    }
+
+    #[test]
+    fn exits_new_board_bottom_row() {
+        let board = Board::new(BoardState::new(3, 2), Vec::new(), Vec::new());
+        let exits: Vec<Pos> = board.exits().iter_set().collect();
+        assert_eq!(vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)], exits);
+    }
+
+    #[test]
+    fn set_exit_marked_and_unmarked() {
+        let mut board = Board::new(BoardState::new(3, 2), Vec::new(), Vec::new());
+        board.set_exit(Pos::new(1, 1), true);
+        board.set_exit(Pos::new(0, 0), false);
+        let exits: Vec<Pos> = board.exits().iter_set().collect();
+        assert_eq!(vec![Pos::new(1, 0), Pos::new(2, 0), Pos::new(1, 1)], exits);
+    }
+
+    #[test]
+    fn set_ingredient_added_and_removed() {
+        let mut board = Board::new(BoardState::new(3, 2), Vec::new(), Vec::new());
+        assert!(!board.is_ingredient('i'));
+        board.set_ingredient('i', true);
+        assert!(board.is_ingredient('i'));
+        board.set_ingredient('i', false);
+        assert!(!board.is_ingredient('i'));
+    }
+
+    #[test]
+    fn add_tag_type_has_tag() {
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        board.add_tag('f', "red");
+
+        assert!(board.has_tag('f', "red"));
+        assert!(!board.has_tag('f', "blue"));
+        assert!(!board.has_tag('s', "red"));
+    }
+
+    #[test]
+    fn remove_tag_type_no_longer_has_tag() {
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        board.add_tag('f', "red");
+
+        assert!(board.remove_tag('f', "red"));
+        assert!(!board.remove_tag('f', "red"));
+        assert!(!board.has_tag('f', "red"));
+    }
+
+    #[test]
+    fn pieces_with_tag_unknown_tag_empty() {
+        let board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        assert!(board.pieces_with_tag("red").is_empty());
+    }
+
+    #[test]
+    fn pieces_with_tag_all_tagged_types_included() {
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 1), Piece::Regular('g', ALL_DIRECTIONS));
+        board.add_tag('f', "red");
+        board.add_tag('g', "red");
+        board.add_tag('h', "red");
+
+        let positions: Vec<Pos> = board.pieces_with_tag("red").iter_set().collect();
+        assert_eq!(vec![Pos::new(0, 0), Pos::new(2, 1)], positions);
+    }
+
+    #[test]
+    fn next_match_tagged_pattern_mixed_types_match() {
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(0, 0));
+        spaces.insert(Pos::new(1, 0));
+        spaces.insert(Pos::new(2, 0));
+        let pattern = MatchPattern::new('f', spaces, 1).with_tag("red");
+
+        let mut board = Board::new(BoardState::new(4, 2), vec![pattern], Vec::new());
+        board.add_tag('g', "red");
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('f', ALL_DIRECTIONS));
+
+        let next_match = board.next_match().unwrap();
+        assert_eq!(3, next_match.board_pos().len());
+        assert_eq!('f', next_match.pattern().piece_type());
+    }
+
+    #[test]
+    fn next_match_untagged_pattern_mixed_types_no_match() {
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(0, 0));
+        spaces.insert(Pos::new(1, 0));
+        spaces.insert(Pos::new(2, 0));
+        let pattern = MatchPattern::new('f', spaces, 1);
+
+        let mut board = Board::new(BoardState::new(4, 2), vec![pattern], Vec::new());
+        board.add_tag('g', "red");
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('f', ALL_DIRECTIONS));
+
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    fn preview_swap_tagged_pattern_uses_unswapped_types() {
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(0, 0));
+        spaces.insert(Pos::new(1, 0));
+        spaces.insert(Pos::new(2, 0));
+        let pattern = MatchPattern::new('f', spaces, 1).with_tag("red");
+
+        let mut board = Board::new(BoardState::new(3, 2), vec![pattern], Vec::new());
+        board.add_tag('g', "red");
+        board.set_piece(Pos::new(0, 0), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 1), Piece::Regular('f', ALL_DIRECTIONS));
+        while board.next_match().is_some() {}
+
+        let matches = board.preview_swap(Pos::new(1, 0), Pos::new(1, 1)).unwrap();
+        assert_eq!(1, matches.len());
+        assert_eq!(Pos::new(1, 0), matches[0].changed_pos());
+    }
+
+    #[test]
+    fn movable_in_includes_empty_and_movable_pieces() {
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', enum_set!(Direction::North)));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('f', enum_set!(Direction::South)));
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+
+        let positions: Vec<Pos> = board.movable_in(Direction::North).iter_set().collect();
+        assert_eq!(vec![Pos::new(0, 0), Pos::new(2, 0)], positions);
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_movable_out_of_bounds_panics() {
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        board.set_movable(Pos::new(4, 0), ALL_DIRECTIONS);
+    }
+
+    #[test]
+    fn set_movable_regular_piece_type_kept() {
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+
+        assert!(board.set_movable(Pos::new(0, 0), enum_set!(Direction::East)));
+
+        assert_eq!(Piece::Regular('f', enum_set!(Direction::East)), board.piece(Pos::new(0, 0)));
+        assert!(!board.movable_in(Direction::North).is_set(Pos::new(0, 0)));
+        assert!(board.movable_in(Direction::East).is_set(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn set_movable_frozen_piece_cannot_swap() {
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
+
+        board.set_movable(Pos::new(0, 0), EnumSet::new());
+
+        assert!(!board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn set_movable_empty_and_wall_not_changed() {
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+
+        assert!(!board.set_movable(Pos::new(0, 0), EnumSet::new()));
+        assert!(!board.set_movable(Pos::new(1, 0), ALL_DIRECTIONS));
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
+        assert!(board.movable_in(Direction::North).is_set(Pos::new(0, 0)));
+        assert!(!board.movable_in(Direction::North).is_set(Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn type_bitboard_type_never_placed_none() {
+        let board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        assert!(board.type_bitboard('f').is_none());
+    }
+
+    #[test]
+    fn type_bitboard_type_placed_has_positions() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(1, 2), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(3, 4), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(5, 6), Piece::Regular('s', ALL_DIRECTIONS));
+
+        let positions: Vec<Pos> = board.type_bitboard('f').unwrap().iter_set().collect();
+        assert_eq!(vec![Pos::new(1, 2), Pos::new(3, 4)], positions);
+    }
+
+    #[test]
+    fn empty_bitboard_has_empty_positions() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Regular('f', ALL_DIRECTIONS));
+
+        let positions: Vec<Pos> = board.empty_bitboard().iter_set().collect();
+        assert_eq!(vec![Pos::new(1, 2)], positions);
+    }
+
+    #[test]
+    fn count_type_never_placed_zero() {
+        let board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        assert_eq!(0, board.count('f'));
+    }
+
+    #[test]
+    fn count_type_placed_counted() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(1, 2), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(3, 4), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(5, 6), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(3, 4), Piece::Regular('s', ALL_DIRECTIONS));
+
+        assert_eq!(1, board.count('f'));
+        assert_eq!(2, board.count('s'));
+    }
+
+    #[test]
+    fn count_empty_empties_counted() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(5, 6), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(3, 4), Piece::Wall);
+
+        assert_eq!(1, board.count_empty());
+    }
+
+    #[test]
+    fn counts_all_present_types_counted() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(1, 2), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(3, 4), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(5, 6), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(7, 8), Piece::Regular('t', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(7, 8), Piece::Empty);
+
+        let mut expected = HashMap::new();
+        expected.insert('f', 2);
+        expected.insert('s', 1);
+        assert_eq!(expected, board.counts());
+    }
+
+    #[test]
+    fn pieces_in_rect_inside_board_all_pieces() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(2, 3), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+
+        let pieces: Vec<(Pos, Piece)> = board.pieces_in_rect(Pos::new(3, 4), Pos::new(2, 3)).collect();
+        assert_eq!(vec![
+            (Pos::new(2, 3), Piece::Regular('f', ALL_DIRECTIONS)),
+            (Pos::new(3, 3), Piece::Wall),
+            (Pos::new(2, 4), Piece::Wall),
+            (Pos::new(3, 4), Piece::Empty)
+        ], pieces);
+    }
+
+    #[test]
+    fn pieces_in_rect_single_space_one_piece() {
+        let board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        let pieces: Vec<(Pos, Piece)> = board.pieces_in_rect(Pos::new(5, 5), Pos::new(5, 5)).collect();
+        assert_eq!(vec![(Pos::new(5, 5), Piece::Wall)], pieces);
+    }
+
+    #[test]
+    fn pieces_in_rect_partly_outside_board_clamped() {
+        let board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        let positions: Vec<Pos> = board.pieces_in_rect(Pos::new(14, 15), Pos::new(u8::MAX, u8::MAX))
+            .map(|(pos, _)| pos)
+            .collect();
+        assert_eq!(vec![Pos::new(14, 15), Pos::new(15, 15)], positions);
+    }
+
+    #[test]
+    fn pieces_in_rect_outside_board_none() {
+        let board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        assert_eq!(0, board.pieces_in_rect(Pos::new(20, 20), Pos::new(30, 30)).count());
+    }
+
+    #[test]
+    fn pieces_in_rect_empty_board_none() {
+        let board = Board::new(BoardState::new(0, 0), Vec::new(), Vec::new());
+        assert_eq!(0, board.pieces_in_rect(Pos::new(0, 0), Pos::new(3, 3)).count());
+    }
+
+    #[test]
+    fn pieces_within_radius_zero_center_only() {
+        let board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        let positions: Vec<Pos> = board.pieces_within(Pos::new(4, 4), 0).map(|(pos, _)| pos).collect();
+        assert_eq!(vec![Pos::new(4, 4)], positions);
+    }
+
+    #[test]
+    fn pieces_within_radius_one_plus_shape() {
+        let board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        let positions: Vec<Pos> = board.pieces_within(Pos::new(4, 4), 1).map(|(pos, _)| pos).collect();
+        assert_eq!(vec![
+            Pos::new(4, 3),
+            Pos::new(3, 4),
+            Pos::new(4, 4),
+            Pos::new(5, 4),
+            Pos::new(4, 5)
+        ], positions);
+    }
+
+    #[test]
+    fn pieces_within_radius_two_circle() {
+        let board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        let positions: HashSet<Pos> = board.pieces_within(Pos::new(4, 4), 2).map(|(pos, _)| pos).collect();
+        assert_eq!(13, positions.len());
+        assert!(positions.contains(&Pos::new(3, 3)));
+        assert!(positions.contains(&Pos::new(6, 4)));
+        assert!(!positions.contains(&Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn pieces_within_near_corner_clamped() {
+        let board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        let positions: Vec<Pos> = board.pieces_within(Pos::new(0, 0), 1).map(|(pos, _)| pos).collect();
+        assert_eq!(vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(0, 1)], positions);
+    }
+
+    fn preview_board() -> Board {
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(0, 0));
+        spaces.insert(Pos::new(1, 0));
+        spaces.insert(Pos::new(2, 0));
+        let pattern1 = MatchPattern::new('f', spaces.clone(), 1);
+        let pattern2 = MatchPattern::new('s', spaces, 1);
+
+        let mut board = Board::new(BoardState::new(4, 2), vec![pattern1, pattern2], Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(3, 0), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 1), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 1), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 1), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(3, 1), Piece::Regular('g', ALL_DIRECTIONS));
+        while board.next_match().is_some() {}
+        board
+    }
+
+    #[test]
+    #[should_panic]
+    fn preview_swap_outside_board_panics() {
+        let board = preview_board();
+        let _ = board.preview_swap(Pos::new(0, 0), Pos::new(4, 0));
+    }
+
+    #[test]
+    fn preview_swap_rules_violated_none() {
+        let mut board = preview_board();
+        board.set_piece(Pos::new(1, 1), Piece::Wall);
+        assert!(board.preview_swap(Pos::new(1, 0), Pos::new(1, 1)).is_none());
+    }
+
+    #[test]
+    fn preview_swap_no_match_empty() {
+        let board = preview_board();
+        assert_eq!(Some(Vec::new()), board.preview_swap(Pos::new(0, 0), Pos::new(0, 1)));
+    }
+
+    #[test]
+    fn preview_swap_self_empty() {
+        let board = preview_board();
+        assert_eq!(Some(Vec::new()), board.preview_swap(Pos::new(1, 0), Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn preview_swap_both_pieces_match_both_returned() {
+        let board = preview_board();
+
+        let matches = board.preview_swap(Pos::new(1, 0), Pos::new(1, 1)).unwrap();
+
+        assert_eq!(2, matches.len());
+        assert_eq!('f', matches[0].pattern().piece_type());
+        assert_eq!(Pos::new(1, 0), matches[0].changed_pos());
+        assert_eq!('s', matches[1].pattern().piece_type());
+        assert_eq!(Pos::new(1, 1), matches[1].changed_pos());
+    }
+
+    #[test]
+    fn preview_swap_board_and_queue_unchanged() {
+        let board = preview_board();
+        let expected = format!("{}", board);
+
+        let _ = board.preview_swap(Pos::new(1, 0), Pos::new(1, 1));
+
+        assert_eq!(expected, format!("{}", board));
+        assert!(board.state().last_changed.is_empty());
+        assert_eq!(Piece::Regular('s', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn preview_swap_same_as_real_swap() {
+        let mut board = preview_board();
+        let preview: Vec<(Pos, PosSet)> = board.preview_swap(Pos::new(1, 0), Pos::new(1, 1)).unwrap()
+            .into_iter()
+            .map(|found| (found.changed_pos(), found.board_pos().clone()))
+            .collect();
+
+        assert!(board.swap_pieces(Pos::new(1, 0), Pos::new(1, 1)));
+        let mut actual = Vec::new();
+        while let Some(found) = board.next_match() {
+            actual.push((found.changed_pos(), found.board_pos().clone()));
+        }
+
+        assert_eq!(actual, preview);
+    }
+
+    #[test]
+    fn swap_adjacent_all_rules_passed_swapped() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let type2 = 's';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), piece2);
+
+        assert!(board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
+        match board.piece(Pos::new(1, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
+            _ => panic!("Wrong piece")
+        };
+        match board.piece(Pos::new(1, 3)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    fn swap_non_adjacent_all_rules_passed_swapped() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let type2 = 's';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(14, 15), piece2);
+
+        assert!(board.swap_pieces(Pos::new(1, 2), Pos::new(14, 15)));
+        match board.piece(Pos::new(1, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
+            _ => panic!("Wrong piece")
+        };
+        match board.piece(Pos::new(14, 15)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    fn swap_rules_violated_not_swapped() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| false)
+        ]);
+        let type1 = 'f';
+        let type2 = 's';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), piece2);
+
+        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
+        match board.piece(Pos::new(1, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+        match board.piece(Pos::new(1, 3)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    fn swap_rules_violated_short_circuits() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| false),
+            Box::new(|_, _, _| { panic!("Should short circuit before this") })
+        ]);
+        let type1 = 'f';
+        let type2 = 's';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), piece2);
+
+        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
+    }
+
+    #[test]
+    fn swap_empty_all_rules_passed_swapped() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+
+        assert!(board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        match board.piece(Pos::new(1, 3)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    fn swap_wall_all_rules_passed_not_swapped() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+
+        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 3)));
+        match board.piece(Pos::new(1, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    #[should_panic]
+    #[allow(unused_must_use)]
+    fn swap_first_pos_outside_board_panics() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+
+        board.swap_pieces(Pos::new(16, 16), Pos::new(1, 2));
+    }
+
+    #[test]
+    #[should_panic]
+    #[allow(unused_must_use)]
+    fn swap_first_pos_very_large_panics() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+
+        board.swap_pieces(Pos::new(u8::MAX, u8::MAX), Pos::new(1, 2));
+    }
+
+    #[test]
+    #[should_panic]
+    #[allow(unused_must_use)]
+    fn swap_second_pos_outside_board_panics() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+
+        board.swap_pieces(Pos::new(1, 2), Pos::new(16, 16));
+    }
+
+    #[test]
+    #[should_panic]
+    #[allow(unused_must_use)]
+    fn swap_second_pos_very_large_panics() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+
+        board.swap_pieces(Pos::new(1, 2), Pos::new(u8::MAX, u8::MAX));
+    }
+
+    #[test]
+    fn swap_self_all_rules_passed_swapped() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+
+        assert!(board.swap_pieces(Pos::new(1, 2), Pos::new(1, 2)));
+        match board.piece(Pos::new(1, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    fn swap_same_vertical_not_vertically_movable_swapped() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let type2 = 's';
+        let piece1 = Piece::Regular(type1, enum_set!(Direction::West | Direction::East));
+
+        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(2, 2), piece2);
+
+        assert!(board.swap_pieces(Pos::new(1, 2), Pos::new(2, 2)));
+        match board.piece(Pos::new(1, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
+            _ => panic!("Wrong piece")
+        };
+        match board.piece(Pos::new(2, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    fn swap_same_horizontal_not_horizontally_movable_swapped() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let type2 = 's';
+        let piece1 = Piece::Regular(type1, enum_set!(Direction::North | Direction::South));
+
+        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), piece2);
+
+        assert!(board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
+        match board.piece(Pos::new(1, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
+            _ => panic!("Wrong piece")
+        };
+        match board.piece(Pos::new(1, 3)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    fn swap_north_not_movable_north_not_swapped() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let type2 = 's';
+        let piece1 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::West
+        ));
+
+        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), piece2);
+
+        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
+
+        match board.piece(Pos::new(1, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+        match board.piece(Pos::new(1, 3)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    fn swap_south_not_movable_south_not_swapped() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let type2 = 's';
+        let piece1 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::East | Direction::West
+        ));
+
+        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 0), piece2);
+
+        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(1, 5)));
+        match board.piece(Pos::new(1, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+        match board.piece(Pos::new(1, 0)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    fn swap_east_not_movable_east_not_swapped() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let type2 = 's';
+        let piece1 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::South | Direction::West
+        ));
+
+        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(2, 3), piece2);
+
+        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(2, 3)));
+        match board.piece(Pos::new(1, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+        match board.piece(Pos::new(2, 3)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    fn swap_west_not_movable_west_not_swapped() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let type2 = 's';
+        let piece1 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::South | Direction::East
+        ));
+
+        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(0, 2), piece2);
+
+        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(4, 3)));
+
+        match board.piece(Pos::new(1, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+        match board.piece(Pos::new(0, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    fn set_piece_not_present_wall_returned() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        assert_eq!(Piece::Wall, board.set_piece(Pos::new(1, 2), piece1));
+
+        match board.piece(Pos::new(1, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    fn set_piece_wall_old_returned() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+
+        match board.set_piece(Pos::new(1, 2), Piece::Wall) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn set_piece_empty_old_returned() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+
+        match board.set_piece(Pos::new(1, 2), Piece::Empty) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn set_piece_duplicate_old_returned() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+
+        assert_eq!(piece1, board.set_piece(Pos::new(1, 2), piece1));
+        match board.piece(Pos::new(1, 2)) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    fn set_piece_present_old_piece_returned() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let type2 = 's';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        match board.set_piece(Pos::new(1, 2), piece2) {
+            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
+            _ => panic!("Wrong piece")
+        };
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_piece_out_of_bounds_panics() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(16, 16), piece1);
+    }
+
+    #[test]
+    fn set_piece_many_types_all_types_kept() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+
+        for index in 0..255u32 {
+            let piece_type = char::from_u32(u32::from('A') + index).unwrap();
+            let pos = Pos::new((index % 16) as u8, (index / 16) as u8);
+            board.set_piece(pos, Piece::Regular(piece_type, ALL_DIRECTIONS));
+        }
+
+        for index in 0..255u32 {
+            let piece_type = char::from_u32(u32::from('A') + index).unwrap();
+            let pos = Pos::new((index % 16) as u8, (index / 16) as u8);
+            assert_eq!(Piece::Regular(piece_type, ALL_DIRECTIONS), board.piece(pos));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_piece_too_many_types_panics() {
+        let mut board = Board::new(BoardState::new(1, 1), Vec::new(), Vec::new());
+
+        for index in 0..=u32::from(u16::MAX) {
+            let piece_type = char::from_u32(0x10000 + index).unwrap();
+            board.set_piece(Pos::new(0, 0), Piece::Regular(piece_type, ALL_DIRECTIONS));
+        }
+    }
+
+    #[test]
+    fn set_piece_replaced_type_no_longer_present() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+        let piece2 = Piece::Regular('s', ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 2), piece2);
+        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
+
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+
+        board.set_piece(Pos::new(1, 2), Piece::Wall);
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn next_match_no_patterns_none() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
+            Box::new(|_, _, _| true),
+            Box::new(|_, _, _| true)
+        ]);
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(2, 3), piece2);
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    fn next_match_set_pieces_match_found() {
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(2, 3));
+        pattern_pos.insert(Pos::new(3, 3));
+        pattern_pos.insert(Pos::new(6, 8));
+
+        let type1 = 'f';
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(type1, pattern_pos, 1)],
+            Vec::new()
+        );
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 1), piece1);
+        board.set_piece(Pos::new(1, 1), piece2);
+        board.set_piece(Pos::new(4, 6), piece3);
+
+        let next_match = board.next_match().unwrap();
+        assert_eq!(Pos::new(0, 1), next_match.changed_pos());
+        assert!(next_match.board_pos().contains(&Pos::new(0, 1)));
+        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
+        assert!(next_match.board_pos().contains(&Pos::new(4, 6)));
+    }
+
+    #[test]
+    fn next_match_swap_pieces_match_found() {
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(2, 3));
+        pattern_pos.insert(Pos::new(3, 3));
+        pattern_pos.insert(Pos::new(8, 8));
+
+        let type1 = 'f';
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(type1, pattern_pos, 1)],
+            Vec::new()
+        );
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 1), piece1);
+        board.set_piece(Pos::new(1, 1), piece2);
+        board.set_piece(Pos::new(8, 8), piece3);
+        board.set_piece(Pos::new(6, 6), Piece::Empty);
+        board.next_match();
+        board.next_match();
+        board.next_match();
+        board.next_match();
+
+        assert!(board.swap_pieces(Pos::new(6, 6), Pos::new(8, 8)));
+
+        let next_match = board.next_match().unwrap();
+        assert_eq!(Pos::new(6, 6), next_match.changed_pos());
+        assert!(next_match.board_pos().contains(&Pos::new(0, 1)));
+        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
+        assert!(next_match.board_pos().contains(&Pos::new(6, 6)));
+    }
+
+    #[test]
+    fn next_match_swap_self_no_match_found() {
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(2, 3));
+        pattern_pos.insert(Pos::new(3, 3));
+        pattern_pos.insert(Pos::new(8, 8));
+
+        let type1 = 'f';
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(type1, pattern_pos, 1)],
+            Vec::new()
+        );
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 1), piece1);
+        board.set_piece(Pos::new(1, 1), piece2);
+        board.set_piece(Pos::new(6, 6), piece3);
+        board.next_match();
+        board.next_match();
+        board.next_match();
+
+        assert!(board.swap_pieces(Pos::new(6, 6), Pos::new(6, 6)));
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    fn next_match_trickle_match_found() {
+        let type1 = 'f';
+
+        let mut pattern_pos1 = HashSet::new();
+        pattern_pos1.insert(Pos::new(0, 0));
+        pattern_pos1.insert(Pos::new(0, 1));
+        pattern_pos1.insert(Pos::new(1, 0));
+
+        let mut board = Board::new(BoardState::new(16, 16), vec![
+            MatchPattern::new(type1, pattern_pos1, 1)
+        ], Vec::new());
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), piece1);
+        board.set_piece(Pos::new(0, 2), piece1);
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+
+        for _ in 0..6 {
+            board.next_match();
+        }
+
+        board.trickle();
+
+        let next_match = board.next_match().unwrap();
+        assert_eq!(Pos::new(0, 1), next_match.changed_pos());
+        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
+        assert!(next_match.board_pos().contains(&Pos::new(1, 0)));
+        assert!(next_match.board_pos().contains(&Pos::new(0, 1)));
+    }
+
+    #[test]
+    fn next_match_add_trickle_match_found() {
+        let type1 = 'f';
+
+        let mut pattern_pos1 = HashSet::new();
+        pattern_pos1.insert(Pos::new(0, 0));
+        pattern_pos1.insert(Pos::new(0, 1));
+        pattern_pos1.insert(Pos::new(1, 0));
+
+        let mut board = Board::new(BoardState::new(16, 16), vec![
+            MatchPattern::new(type1, pattern_pos1, 1)
+        ], Vec::new());
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), piece1);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+
+        for _ in 0..6 {
+            board.next_match();
+        }
+
+        board.add_and_trickle(Pos::new(0, 2), piece1);
+
+        let next_match = board.next_match().unwrap();
+        assert_eq!(Pos::new(1, 0), next_match.changed_pos());
+        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
+        assert!(next_match.board_pos().contains(&Pos::new(1, 0)));
+        assert!(next_match.board_pos().contains(&Pos::new(0, 1)));
+    }
+
+    #[test]
+    fn next_match_matches_all_variants() {
+        let piece_type = 'f';
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(2, 2));
+        pattern_pos.insert(Pos::new(3, 3));
+        pattern_pos.insert(Pos::new(4, 4));
+
+        let type1 = 'f';
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(type1, pattern_pos, 1)],
+            Vec::new()
+        );
+        let piece1 = Piece::Regular(piece_type, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(piece_type, ALL_DIRECTIONS);
+        let piece3 = Piece::Regular(piece_type, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece2);
+        board.set_piece(Pos::new(2, 2), piece3);
+
+        let next_match1 = board.next_match().unwrap();
+        assert_eq!(Pos::new(0, 0), next_match1.changed_pos());
+        assert!(next_match1.board_pos().contains(&Pos::new(0, 0)));
+        assert!(next_match1.board_pos().contains(&Pos::new(1, 1)));
+        assert!(next_match1.board_pos().contains(&Pos::new(2, 2)));
+
+        let next_match2 = board.next_match().unwrap();
+        assert_eq!(Pos::new(1, 1), next_match2.changed_pos());
+        assert!(next_match2.board_pos().contains(&Pos::new(0, 0)));
+        assert!(next_match2.board_pos().contains(&Pos::new(1, 1)));
+        assert!(next_match2.board_pos().contains(&Pos::new(2, 2)));
+
+        let next_match3 = board.next_match().unwrap();
+        assert_eq!(Pos::new(2, 2), next_match3.changed_pos());
+        assert!(next_match3.board_pos().contains(&Pos::new(0, 0)));
+        assert!(next_match3.board_pos().contains(&Pos::new(1, 1)));
+        assert!(next_match3.board_pos().contains(&Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn next_match_does_not_match_wrong_types() {
+        let type1 = 'f';
+        let type2 = 's';
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(2, 2));
+        pattern_pos.insert(Pos::new(3, 3));
+        pattern_pos.insert(Pos::new(4, 4));
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(type2, pattern_pos, 1)],
+            Vec::new()
+        );
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece2);
+        board.set_piece(Pos::new(2, 2), piece3);
+
+        assert!(board.next_match().is_none());
+        assert!(board.next_match().is_none());
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    fn next_match_matches_when_not_all_in_queue() {
+        let type1 = 'f';
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(2, 2));
+        pattern_pos.insert(Pos::new(3, 3));
+        pattern_pos.insert(Pos::new(4, 4));
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(type1, pattern_pos, 1)],
+            Vec::new()
+        );
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece2);
+
+        board.next_match();
+        board.next_match();
+
+        board.set_piece(Pos::new(2, 2), piece3);
+
+        let next_match = board.next_match().unwrap();
+        assert_eq!(Pos::new(2, 2), next_match.changed_pos());
+        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
+        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
+        assert!(next_match.board_pos().contains(&Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn next_match_board_state_changed_after_match_still_matches() {
+        let type1 = 'f';
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(2, 2));
+        pattern_pos.insert(Pos::new(3, 3));
+        pattern_pos.insert(Pos::new(4, 4));
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(type1, pattern_pos, 1)],
+            Vec::new()
+        );
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece4 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece2);
+
+        board.next_match();
+        board.next_match();
+
+        board.set_piece(Pos::new(2, 2), piece3);
+        board.set_piece(Pos::new(2, 3), piece4);
+
+        let next_match = board.next_match().unwrap();
+        assert_eq!(Pos::new(2, 2), next_match.changed_pos());
+        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
+        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
+        assert!(next_match.board_pos().contains(&Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn next_match_match_overwritten_does_not_match() {
+        let type1 = 'f';
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(2, 2));
+        pattern_pos.insert(Pos::new(3, 3));
+        pattern_pos.insert(Pos::new(4, 4));
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(type1, pattern_pos, 1)],
+            Vec::new()
+        );
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece2);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+
+        board.next_match();
+        board.next_match();
+        board.next_match();
+
+        board.set_piece(Pos::new(2, 2), piece3);
+        assert!(board.swap_pieces(Pos::new(2, 2), Pos::new(2, 3)));
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    fn next_match_position_set_twice_matches_once() {
+        let piece_type = 'f';
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(2, 2));
+        pattern_pos.insert(Pos::new(3, 3));
+        pattern_pos.insert(Pos::new(4, 4));
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(piece_type, pattern_pos, 1)],
+            Vec::new()
+        );
+        let piece1 = Piece::Regular(piece_type, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(piece_type, ALL_DIRECTIONS);
+        let piece3 = Piece::Regular(piece_type, ALL_DIRECTIONS);
+        let piece4 = Piece::Regular(piece_type, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece2);
+
+        board.next_match();
+        board.next_match();
+
+        board.set_piece(Pos::new(2, 2), piece3);
+        board.set_piece(Pos::new(2, 2), piece4);
+
+        let next_match = board.next_match().unwrap();
+        assert_eq!(Pos::new(2, 2), next_match.changed_pos());
+        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
+        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
+        assert!(next_match.board_pos().contains(&Pos::new(2, 2)));
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    fn next_match_two_patterns_same_rank_matching_picked() {
+        let type1 = 'f';
+        let type2 = 's';
+
+        let mut pattern_pos1 = HashSet::new();
+        pattern_pos1.insert(Pos::new(2, 2));
+        pattern_pos1.insert(Pos::new(3, 3));
+        pattern_pos1.insert(Pos::new(4, 4));
+
+        let mut pattern_pos2 = HashSet::new();
+        pattern_pos2.insert(Pos::new(2, 2));
+        pattern_pos2.insert(Pos::new(3, 3));
+        pattern_pos2.insert(Pos::new(4, 4));
+
+        let mut board = Board::new(BoardState::new(16, 16), vec![
+            MatchPattern::new(type2, pattern_pos1, 1),
+            MatchPattern::new(type1, pattern_pos2, 1)
+        ], Vec::new());
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece2);
+
+        board.next_match();
+        board.next_match();
+
+        board.set_piece(Pos::new(2, 2), piece3);
+
+        let next_match = board.next_match().unwrap();
+        assert_eq!(Pos::new(2, 2), next_match.changed_pos());
+        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
+        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
+        assert!(next_match.board_pos().contains(&Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn next_match_two_patterns_different_rank_higher_picked() {
+        let type1 = 'f';
+
+        let mut pattern_pos1 = HashSet::new();
+        pattern_pos1.insert(Pos::new(2, 2));
+        pattern_pos1.insert(Pos::new(3, 3));
+        pattern_pos1.insert(Pos::new(4, 4));
+
+        let mut pattern_pos2 = HashSet::new();
+        pattern_pos2.insert(Pos::new(1, 1));
+        pattern_pos2.insert(Pos::new(2, 2));
+        pattern_pos2.insert(Pos::new(3, 3));
+        pattern_pos2.insert(Pos::new(4, 4));
+
+        let mut board = Board::new(BoardState::new(16, 16), vec![
+            MatchPattern::new(type1, pattern_pos1, 1),
+            MatchPattern::new(type1, pattern_pos2, 2)
+        ], Vec::new());
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece4 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece2);
+        board.set_piece(Pos::new(2, 2), piece3);
+
+        board.next_match();
+        board.next_match();
+        board.next_match();
+
+        board.set_piece(Pos::new(3, 3), piece4);
+
+        let next_match = board.next_match().unwrap();
+        assert_eq!(Pos::new(3, 3), next_match.changed_pos());
+        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
+        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
+        assert!(next_match.board_pos().contains(&Pos::new(2, 2)));
+        assert!(next_match.board_pos().contains(&Pos::new(3, 3)));
+    }
+
+    #[test]
+    fn next_match_position_marked_twice_matches_once() {
+        let piece_type = 'f';
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(0, 0));
+        pattern_pos.insert(Pos::new(1, 0));
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(piece_type, pattern_pos, 1)],
+            Vec::new()
+        );
+        let piece = Piece::Regular(piece_type, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 0), piece);
+        board.next_match();
+
+        board.set_piece(Pos::new(1, 0), piece);
+        board.set_piece(Pos::new(1, 0), piece);
+        board.set_piece(Pos::new(1, 0), piece);
+
+        assert_eq!(Pos::new(1, 0), board.next_match().unwrap().changed_pos());
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    fn next_match_position_marked_again_after_check_matches_again() {
+        let piece_type = 'f';
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(0, 0));
+        pattern_pos.insert(Pos::new(1, 0));
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(piece_type, pattern_pos, 1)],
+            Vec::new()
+        );
+        let piece = Piece::Regular(piece_type, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(0, 0), piece);
+        board.set_piece(Pos::new(1, 0), piece);
+        assert_eq!(Pos::new(0, 0), board.next_match().unwrap().changed_pos());
+
+        board.set_piece(Pos::new(0, 0), piece);
+        assert_eq!(Pos::new(1, 0), board.next_match().unwrap().changed_pos());
+        assert_eq!(Pos::new(0, 0), board.next_match().unwrap().changed_pos());
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    fn next_match_swap_marks_each_position_once() {
+        let piece_type = 'f';
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(0, 0));
+        pattern_pos.insert(Pos::new(1, 0));
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(piece_type, pattern_pos, 1)],
+            Vec::new()
+        );
+
+        board.set_piece(Pos::new(0, 0), Piece::Regular(piece_type, ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular(piece_type, ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        assert!(board.swap_pieces(Pos::new(1, 0), Pos::new(2, 0)));
+        assert!(board.swap_pieces(Pos::new(1, 0), Pos::new(2, 0)));
+
+        let mut changed = Vec::new();
+        while let Some(next_match) = board.next_match() {
+            changed.push(next_match.changed_pos());
+        }
+
+        assert_eq!(vec![Pos::new(0, 0), Pos::new(1, 0)], changed);
+    }
+
+    #[test]
+    fn match_order_default_fifo() {
+        let board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        assert_eq!(MatchOrder::Fifo, board.match_order());
+    }
+
+    #[test]
+    fn next_match_by_row_lowest_row_first() {
+        let piece_type = 'f';
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(0, 0));
+        pattern_pos.insert(Pos::new(1, 0));
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(piece_type, pattern_pos, 1)],
+            Vec::new()
+        );
+        board.set_match_order(MatchOrder::ByRow);
+        let piece = Piece::Regular(piece_type, ALL_DIRECTIONS);
+
+        board.set_piece(Pos::new(4, 5), piece);
+        board.set_piece(Pos::new(5, 5), piece);
+        board.set_piece(Pos::new(3, 1), piece);
+        board.set_piece(Pos::new(2, 1), piece);
+        board.set_piece(Pos::new(8, 0), piece);
+
+        assert_eq!(MatchOrder::ByRow, board.match_order());
+        assert_eq!(Pos::new(2, 1), board.next_match().unwrap().changed_pos());
+        assert_eq!(Pos::new(3, 1), board.next_match().unwrap().changed_pos());
+        assert_eq!(Pos::new(4, 5), board.next_match().unwrap().changed_pos());
+        assert_eq!(Pos::new(5, 5), board.next_match().unwrap().changed_pos());
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    fn next_match_by_rank_highest_rank_first() {
+        let type1 = 'f';
+        let type2 = 's';
+
+        let mut pattern_pos1 = HashSet::new();
+        pattern_pos1.insert(Pos::new(0, 0));
+        pattern_pos1.insert(Pos::new(1, 0));
+
+        let mut pattern_pos2 = HashSet::new();
+        pattern_pos2.insert(Pos::new(0, 0));
+        pattern_pos2.insert(Pos::new(0, 1));
+
+        let mut board = Board::new(BoardState::new(16, 16), vec![
+            MatchPattern::new(type1, pattern_pos1, 1),
+            MatchPattern::new(type2, pattern_pos2, 2)
+        ], Vec::new());
+        board.set_match_order(MatchOrder::ByRank);
+
+        board.set_piece(Pos::new(0, 0), Piece::Regular(type1, ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular(type1, ALL_DIRECTIONS));
+        board.set_piece(Pos::new(9, 9), Piece::Regular(type1, ALL_DIRECTIONS));
+        board.set_piece(Pos::new(5, 5), Piece::Regular(type2, ALL_DIRECTIONS));
+        board.set_piece(Pos::new(5, 6), Piece::Regular(type2, ALL_DIRECTIONS));
+
+        let next_match = board.next_match().unwrap();
+        assert_eq!(Pos::new(5, 5), next_match.changed_pos());
+        assert_eq!(2, next_match.pattern().rank());
+
+        assert_eq!(Pos::new(5, 6), board.next_match().unwrap().changed_pos());
+        assert_eq!(Pos::new(0, 0), board.next_match().unwrap().changed_pos());
+        assert_eq!(Pos::new(1, 0), board.next_match().unwrap().changed_pos());
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    fn next_match_by_rank_no_matches_unmarks_all() {
+        let piece_type = 'f';
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(0, 0));
+        pattern_pos.insert(Pos::new(1, 0));
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(piece_type, pattern_pos, 1)],
+            Vec::new()
+        );
+        board.set_match_order(MatchOrder::ByRank);
+
+        board.set_piece(Pos::new(0, 0), Piece::Regular(piece_type, ALL_DIRECTIONS));
+        board.set_piece(Pos::new(5, 5), Piece::Regular(piece_type, ALL_DIRECTIONS));
+        assert!(board.next_match().is_none());
+
+        board.set_match_order(MatchOrder::Fifo);
+        board.set_piece(Pos::new(1, 0), Piece::Regular(piece_type, ALL_DIRECTIONS));
+        assert_eq!(Pos::new(1, 0), board.next_match().unwrap().changed_pos());
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn find_matches_parallel_same_as_sequential() {
+        let type1 = 'f';
+        let type2 = 's';
+
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(0, 0));
+        pattern_pos.insert(Pos::new(1, 0));
+        pattern_pos.insert(Pos::new(2, 0));
+
+        let patterns = vec![
+            MatchPattern::new(type1, pattern_pos.clone(), 1),
+            MatchPattern::new(type2, pattern_pos, 1)
+        ];
+
+        let mut sequential_board = Board::new(BoardState::new(64, 64), patterns.clone(), Vec::new());
+        let mut parallel_board = Board::new(BoardState::new(64, 64), patterns, Vec::new());
+
+        for y in 0..64 {
+            for x in 0..60 {
+                let piece_type = if (x / 3 + y) % 2 == 0 { type1 } else { type2 };
+                let piece = Piece::Regular(piece_type, ALL_DIRECTIONS);
+                sequential_board.set_piece(Pos::new(x, y), piece);
+                parallel_board.set_piece(Pos::new(x, y), piece);
+            }
+        }
+
+        let mut expected_matches = Vec::new();
+        while let Some(next_match) = sequential_board.next_match() {
+            expected_matches.push((next_match.changed_pos(), next_match.board_pos().clone()));
+        }
+
+        let actual_matches: Vec<_> = parallel_board.find_matches_parallel().iter()
+            .map(|next_match| (next_match.changed_pos(), next_match.board_pos().clone()))
+            .collect();
+
+        assert_eq!(60 * 64, expected_matches.len());
+        assert_eq!(expected_matches, actual_matches);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn find_matches_parallel_unmarks_all_pieces() {
+        let type1 = 'f';
+
+        let mut pattern_pos = HashSet::new();
+        pattern_pos.insert(Pos::new(0, 0));
+        pattern_pos.insert(Pos::new(1, 0));
+
+        let mut board = Board::new(
+            BoardState::new(16, 16),
+            vec![MatchPattern::new(type1, pattern_pos, 1)],
+            Vec::new()
+        );
+
+        board.set_piece(Pos::new(0, 0), Piece::Regular(type1, ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular(type1, ALL_DIRECTIONS));
+        board.set_piece(Pos::new(5, 5), Piece::Regular(type1, ALL_DIRECTIONS));
+
+        assert_eq!(2, board.find_matches_parallel().len());
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    fn trickle_no_diagonals_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+    }
+
+    #[test]
+    fn trickle_no_diagonals_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]),
+            PieceMove::new(vec![Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1), Pos::new(2, 0)]),
+            PieceMove::new(vec![
+                Pos::new(2, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1)
+            ]),
+            PieceMove::new(vec![
+                Pos::new(3, 4), Pos::new(3, 3), Pos::new(3, 2), Pos::new(3, 1), Pos::new(3, 0)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_no_diagonals_fills_prev_piece_space_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_fills_prev_piece_space_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]),
+            PieceMove::new(vec![
+                Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2), Pos::new(0, 1)
+            ]),
+            PieceMove::new(vec![Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1), Pos::new(2, 0)]),
+            PieceMove::new(vec![
+                Pos::new(2, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1)
+            ]),
+            PieceMove::new(vec![
+                Pos::new(3, 4), Pos::new(3, 3), Pos::new(3, 2), Pos::new(3, 1), Pos::new(3, 0)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_left_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_left_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![
+                Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2), Pos::new(0, 1)
+            ]),
+            PieceMove::new(vec![Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1), Pos::new(2, 0)]),
+            PieceMove::new(vec![
+                Pos::new(2, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1)
+            ]),
+            PieceMove::new(vec![
+                Pos::new(3, 4), Pos::new(3, 3), Pos::new(3, 2), Pos::new(3, 1), Pos::new(3, 0)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_right_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), piece1);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_right_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), piece1);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]),
+            PieceMove::new(vec![Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1), Pos::new(2, 0)]),
+            PieceMove::new(vec![Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1)]),
+            PieceMove::new(vec![
+                Pos::new(2, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(3, 1)
+            ]),
+            PieceMove::new(vec![
+                Pos::new(3, 4), Pos::new(3, 3), Pos::new(3, 2), Pos::new(3, 1), Pos::new(3, 0)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_ambiguous_sets_board_left_preferred() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), piece1);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_ambiguous_generates_moves_left_preferred() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), piece1);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1), Pos::new(2, 0)]),
+            PieceMove::new(vec![Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1)]),
+            PieceMove::new(vec![
+                Pos::new(2, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(1, 1)
+            ]),
+            PieceMove::new(vec![
+                Pos::new(3, 4), Pos::new(3, 3), Pos::new(3, 2), Pos::new(3, 1), Pos::new(3, 0)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_tall_tower_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), piece1);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), piece1);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_tall_tower_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), piece1);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), piece1);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(2, 2), Pos::new(2, 1), Pos::new(2, 0)]),
+            PieceMove::new(vec![Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1)]),
+            PieceMove::new(vec![Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(1, 1)]),
+            PieceMove::new(vec![
+                Pos::new(2, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(3, 1)
+            ]),
+            PieceMove::new(vec![
+                Pos::new(3, 4), Pos::new(3, 3), Pos::new(3, 2), Pos::new(3, 1), Pos::new(3, 0)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_blocking_wall_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Wall);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), piece1);
+
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Wall);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Wall);
+        board.set_piece(Pos::new(2, 4), piece1);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), piece1);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Wall);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 3)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 3)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(2, 3)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 3)));
+        assert_eq!(piece1, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_blocking_wall_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Wall);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), piece1);
+
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Wall);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Wall);
+        board.set_piece(Pos::new(2, 4), piece1);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), piece1);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Wall);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(0, 5), Pos::new(0, 4)]),
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1), Pos::new(1, 0)]),
+            PieceMove::new(vec![Pos::new(1, 5), Pos::new(1, 4)]),
+            PieceMove::new(vec![Pos::new(3, 1), Pos::new(3, 0)]),
+            PieceMove::new(vec![Pos::new(2, 5), Pos::new(3, 4)])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_through_hole_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Wall);
+        board.set_piece(Pos::new(0, 5), piece1);
+
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Wall);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Wall);
+        board.set_piece(Pos::new(3, 5), piece1);
+
+        board.trickle();
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 4)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_through_hole_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Wall);
+        board.set_piece(Pos::new(0, 5), piece1);
+
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Wall);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Wall);
+        board.set_piece(Pos::new(3, 5), piece1);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![
+                Pos::new(1, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1),
+                Pos::new(2, 0)
+            ]),
+            PieceMove::new(vec![
+                Pos::new(3, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1),
+                Pos::new(1, 0)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_changing_directions_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Wall);
+        board.set_piece(Pos::new(0, 5), piece1);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Wall);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Wall);
+        board.set_piece(Pos::new(3, 5), piece1);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 4)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_changing_directions_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Wall);
+        board.set_piece(Pos::new(0, 5), piece1);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Wall);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Wall);
+        board.set_piece(Pos::new(3, 5), piece1);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![
+                Pos::new(3, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1),
+                Pos::new(3, 0)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_west_wall_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Wall);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_west_wall_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Wall);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![
+                Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2), Pos::new(0, 1)
+            ]),
+            PieceMove::new(vec![Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1), Pos::new(2, 0)]),
+            PieceMove::new(vec![
+                Pos::new(2, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1)
+            ]),
+            PieceMove::new(vec![
+                Pos::new(3, 4), Pos::new(3, 3), Pos::new(3, 2), Pos::new(3, 1), Pos::new(3, 0)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_east_wall_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), piece1);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), piece1);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Wall);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_east_wall_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), piece1);
+        board.set_piece(Pos::new(2, 5), piece1);
+
+        board.set_piece(Pos::new(3, 0), piece1);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Wall);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]),
+            PieceMove::new(vec![Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1), Pos::new(2, 0)]),
+            PieceMove::new(vec![Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1)]),
+            PieceMove::new(vec![
+                Pos::new(2, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(3, 1)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_no_diagonals_unmovable_north_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    }
+
+    #[test]
+    fn trickle_no_diagonals_unmovable_north_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]),
+            PieceMove::new(vec![Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2)])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_no_diagonals_unmovable_south_sets_board_for_movable() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::East | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    }
+
+    #[test]
+    fn trickle_no_diagonals_unmovable_south_generates_moves_for_movable() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::East | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3)])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_no_diagonals_unmovable_east_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    }
+
+    #[test]
+    fn trickle_no_diagonals_unmovable_east_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]),
+            PieceMove::new(vec![Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2)])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_no_diagonals_unmovable_west_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::East
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    }
+
+    #[test]
+    fn trickle_no_diagonals_unmovable_west_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::East
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]),
+            PieceMove::new(vec![Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2)])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_left_unmovable_north_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece2, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_left_unmovable_north_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1), Pos::new(0, 0)]),
+            PieceMove::new(vec![
+                Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2), Pos::new(1, 1)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_left_unmovable_south_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::East | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_left_unmovable_south_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::East | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![
+                Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(0, 2), Pos::new(0, 1),
+                Pos::new(0, 0)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_left_unmovable_east_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.trickle();
+
+        assert_eq!(piece2, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_left_unmovable_east_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1), Pos::new(0, 0)]),
+            PieceMove::new(vec![
+                Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2), Pos::new(1, 1)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_left_unmovable_west_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::North
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_left_unmovable_west_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::North
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]),
+            PieceMove::new(vec![
+                Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2), Pos::new(0, 1),
+                Pos::new(0, 0)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_right_unmovable_north_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece2, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_right_unmovable_north_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1), Pos::new(2, 0)]),
+            PieceMove::new(vec![
+                Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2), Pos::new(1, 1)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_right_unmovable_south_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::East | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_right_unmovable_south_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::East | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![
+                Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(2, 2), Pos::new(2, 1),
+                Pos::new(2, 0)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_right_unmovable_east_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_right_unmovable_east_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]),
+            PieceMove::new(vec![
+                Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2), Pos::new(2, 1),
+                Pos::new(2, 0)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
     }
 
     #[test]
-    fn swap_empty_all_rules_passed_swapped() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn trickle_with_diagonals_right_unmovable_west_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::North
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece2, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_right_unmovable_west_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::North
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), piece1);
+
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1), Pos::new(2, 0)]),
+            PieceMove::new(vec![
+                Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2), Pos::new(1, 1)
+            ])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_right_border_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(15, 0), piece1);
+        board.set_piece(Pos::new(15, 1), Piece::Empty);
+        board.set_piece(Pos::new(15, 2), piece1);
+        board.set_piece(Pos::new(15, 3), Piece::Empty);
+        board.set_piece(Pos::new(15, 4), Piece::Empty);
+        board.set_piece(Pos::new(15, 5), piece1);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(15, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(15, 1)));
+        assert_eq!(piece1, board.piece(Pos::new(15, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 5)));
+    }
+
+    #[test]
+    fn trickle_right_border_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(15, 0), piece1);
+        board.set_piece(Pos::new(15, 1), Piece::Empty);
+        board.set_piece(Pos::new(15, 2), piece1);
+        board.set_piece(Pos::new(15, 3), Piece::Empty);
+        board.set_piece(Pos::new(15, 4), Piece::Empty);
+        board.set_piece(Pos::new(15, 5), piece1);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(15, 2), Pos::new(15, 1)]),
+            PieceMove::new(vec![Pos::new(15, 5), Pos::new(15, 4), Pos::new(15, 3), Pos::new(15, 2)])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_top_border_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 10), piece1);
+        board.set_piece(Pos::new(0, 11), Piece::Empty);
+        board.set_piece(Pos::new(0, 12), piece1);
+        board.set_piece(Pos::new(0, 13), Piece::Empty);
+        board.set_piece(Pos::new(0, 14), Piece::Empty);
+        board.set_piece(Pos::new(0, 15), piece1);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 10)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 11)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 12)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 13)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 14)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 15)));
+    }
+
+    #[test]
+    fn trickle_top_border_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 10), piece1);
+        board.set_piece(Pos::new(0, 11), Piece::Empty);
+        board.set_piece(Pos::new(0, 12), piece1);
+        board.set_piece(Pos::new(0, 13), Piece::Empty);
+        board.set_piece(Pos::new(0, 14), Piece::Empty);
+        board.set_piece(Pos::new(0, 15), piece1);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(0, 12), Pos::new(0, 11)]),
+            PieceMove::new(vec![Pos::new(0, 15), Pos::new(0, 14), Pos::new(0, 13), Pos::new(0, 12)])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_left_adjacent_even_towers_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), piece1);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    }
+
+    #[test]
+    fn trickle_with_diagonals_left_adjacent_even_towers_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), piece1);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 1), Pos::new(0, 0)]),
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_right_adjacent_even_towers_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
         board.set_piece(Pos::new(1, 2), piece1);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        assert!(board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        match board.piece(Pos::new(1, 3)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), piece1);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn swap_wall_all_rules_passed_not_swapped() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn trickle_with_diagonals_right_adjacent_even_towers_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
         board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 3)));
-        match board.piece(Pos::new(1, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), piece1);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(2, 1), Pos::new(3, 0)]),
+            PieceMove::new(vec![Pos::new(2, 2), Pos::new(2, 1)])
+        ];
+        assert_eq!(expected_moves, board.trickle());
     }
 
     #[test]
-    #[should_panic]
-    #[allow(unused_must_use)]
-    fn swap_first_pos_outside_board_panics() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn trickle_with_diagonals_left_adjacent_uneven_towers_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        board.set_piece(Pos::new(1, 2), piece1);
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
 
-        board.swap_pieces(Pos::new(16, 16), Pos::new(1, 2));
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), piece1);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
     }
 
     #[test]
-    #[should_panic]
-    #[allow(unused_must_use)]
-    fn swap_first_pos_very_large_panics() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn trickle_with_diagonals_left_adjacent_uneven_towers_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), piece1);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(1, 1), Pos::new(0, 0)]),
+            PieceMove::new(vec![Pos::new(2, 2), Pos::new(1, 1)]),
+            PieceMove::new(vec![Pos::new(2, 3), Pos::new(2, 2)])
+        ];
+        assert_eq!(expected_moves, board.trickle());
+    }
+
+    #[test]
+    fn trickle_with_diagonals_right_adjacent_uneven_towers_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
         board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), piece1);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.swap_pieces(Pos::new(u8::MAX, u8::MAX), Pos::new(1, 2));
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    #[should_panic]
-    #[allow(unused_must_use)]
-    fn swap_second_pos_outside_board_panics() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn trickle_with_diagonals_right_adjacent_uneven_towers_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
         board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), piece1);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
 
-        board.swap_pieces(Pos::new(1, 2), Pos::new(16, 16));
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(2, 1), Pos::new(3, 0)]),
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(2, 1)]),
+            PieceMove::new(vec![Pos::new(1, 3), Pos::new(1, 2)])
+        ];
+        assert_eq!(expected_moves, board.trickle());
     }
 
     #[test]
-    #[should_panic]
-    #[allow(unused_must_use)]
-    fn swap_second_pos_very_large_panics() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn trickle_with_diagonals_piece_replaced_with_more_movable_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
 
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
         board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
 
-        board.swap_pieces(Pos::new(1, 2), Pos::new(u8::MAX, u8::MAX));
-    }
+        board.set_piece(Pos::new(2, 0), piece1);
 
-    #[test]
-    fn swap_self_all_rules_passed_swapped() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        board.set_piece(Pos::new(2, 1), piece2);
+        board.set_piece(Pos::new(2, 1), piece1);
 
-        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
 
-        assert!(board.swap_pieces(Pos::new(1, 2), Pos::new(1, 2)));
-        match board.piece(Pos::new(1, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
-    }
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
 
-    #[test]
-    fn swap_same_vertical_not_vertically_movable_swapped() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
-        let type1 = 'f';
-        let type2 = 's';
-        let piece1 = Piece::Regular(type1, enum_set!(Direction::West | Direction::East));
+        board.trickle();
 
-        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
 
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(2, 2), piece2);
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
 
-        assert!(board.swap_pieces(Pos::new(1, 2), Pos::new(2, 2)));
-        match board.piece(Pos::new(1, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
-            _ => panic!("Wrong piece")
-        };
-        match board.piece(Pos::new(2, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
     }
 
     #[test]
-    fn swap_same_horizontal_not_horizontally_movable_swapped() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn trickle_with_diagonals_piece_replaced_with_more_movable_generates_moves() {
         let type1 = 'f';
-        let type2 = 's';
-        let piece1 = Piece::Regular(type1, enum_set!(Direction::North | Direction::South));
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
 
-        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
 
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
         board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
 
-        assert!(board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
-        match board.piece(Pos::new(1, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
-            _ => panic!("Wrong piece")
-        };
-        match board.piece(Pos::new(1, 3)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
+        board.set_piece(Pos::new(2, 0), piece1);
+
+        board.set_piece(Pos::new(2, 1), piece2);
+        board.set_piece(Pos::new(2, 1), piece1);
+
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+
+        let expected_moves = vec![
+            PieceMove::new(vec![Pos::new(2, 1), Pos::new(3, 0)]),
+            PieceMove::new(vec![Pos::new(1, 2), Pos::new(2, 1)])
+        ];
+        assert_eq!(expected_moves, board.trickle());
     }
 
     #[test]
-    fn swap_north_not_movable_north_not_swapped() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn trickle_with_diagonals_piece_replaced_with_less_movable_sets_board() {
         let type1 = 'f';
-        let type2 = 's';
-        let piece1 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::West
-        ));
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
 
-        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
 
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
         board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
 
-        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(1, 3)));
+        board.set_piece(Pos::new(2, 0), piece1);
 
-        match board.piece(Pos::new(1, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
-        match board.piece(Pos::new(1, 3)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
-            _ => panic!("Wrong piece")
-        };
-    }
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 1), piece2);
 
-    #[test]
-    fn swap_south_not_movable_south_not_swapped() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
-        let type1 = 'f';
-        let type2 = 's';
-        let piece1 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::East | Direction::West
-        ));
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
 
-        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
 
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 0), piece2);
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
 
-        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(1, 5)));
-        match board.piece(Pos::new(1, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
-        match board.piece(Pos::new(1, 0)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
-            _ => panic!("Wrong piece")
-        };
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
     }
 
     #[test]
-    fn swap_east_not_movable_east_not_swapped() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn trickle_with_diagonals_piece_replaced_with_less_movable_generates_moves() {
         let type1 = 'f';
-        let type2 = 's';
-        let piece1 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::South | Direction::West
-        ));
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
 
-        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+        let mut board = Board::new(BoardState::new(16, 16), 
+                                   Vec::new(), Vec::new());
 
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
         board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(2, 3), piece2);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
 
-        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(2, 3)));
-        match board.piece(Pos::new(1, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
-        match board.piece(Pos::new(2, 3)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
-            _ => panic!("Wrong piece")
-        };
+        board.set_piece(Pos::new(2, 0), piece1);
+
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 1), piece2);
+
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+
+        let expected_moves: Vec<PieceMove> = vec![];
+        assert_eq!(expected_moves, board.trickle());
     }
 
     #[test]
-    fn swap_west_not_movable_west_not_swapped() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn add_trickle_no_diagonals_sets_board() {
         let type1 = 'f';
-        let type2 = 's';
-        let piece1 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::South | Direction::East
-        ));
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(0, 2), piece2);
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
 
-        assert!(!board.swap_pieces(Pos::new(1, 2), Pos::new(4, 3)));
+        board.add_and_trickle(Pos::new(0, 4), piece1);
 
-        match board.piece(Pos::new(1, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
-        match board.piece(Pos::new(0, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type2, piece_type),
-            _ => panic!("Wrong piece")
-        };
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
     }
 
     #[test]
-    fn set_piece_not_present_wall_returned() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn add_trickle_no_diagonals_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        assert_eq!(Piece::Wall, board.set_piece(Pos::new(1, 2), piece1));
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
-        match board.piece(Pos::new(1, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        let expected_move = Some(PieceMove::new(vec![Pos::new(0, 4), Pos::new(0, 3), Pos::new(0, 2), Pos::new(0, 1)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(0, 4), piece1));
     }
 
     #[test]
-    fn set_piece_wall_old_returned() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn add_trickle_with_diagonals_left_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        board.set_piece(Pos::new(1, 2), piece1);
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
-        match board.set_piece(Pos::new(1, 2), Piece::Wall) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
-        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 2)));
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.add_and_trickle(Pos::new(1, 4), piece1);
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
     }
 
     #[test]
-    fn set_piece_empty_old_returned() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn add_trickle_with_diagonals_left_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        board.set_piece(Pos::new(1, 2), piece1);
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
-        match board.set_piece(Pos::new(1, 2), Piece::Empty) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        let expected_move = Some(PieceMove::new(vec![Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2), Pos::new(0, 1)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(1, 4), piece1));
     }
 
     #[test]
-    fn set_piece_duplicate_old_returned() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn add_trickle_with_diagonals_right_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        board.set_piece(Pos::new(1, 2), piece1);
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
-        assert_eq!(piece1, board.set_piece(Pos::new(1, 2), piece1));
-        match board.piece(Pos::new(1, 2)) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), piece1);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        board.add_and_trickle(Pos::new(2, 4), piece1);
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn set_piece_present_old_piece_returned() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn add_trickle_with_diagonals_right_generates_moves() {
         let type1 = 'f';
-        let type2 = 's';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type2, ALL_DIRECTIONS);
 
-        board.set_piece(Pos::new(1, 2), piece1);
-        match board.set_piece(Pos::new(1, 2), piece2) {
-            Piece::Regular(piece_type, _) => assert_eq!(type1, piece_type),
-            _ => panic!("Wrong piece")
-        };
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), piece1);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        let expected_move = Some(PieceMove::new(vec![Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(3, 1)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(2, 4), piece1));
     }
 
     #[test]
-    #[should_panic]
-    fn set_piece_out_of_bounds_panics() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
+    fn add_trickle_with_diagonals_ambiguous_sets_board_left_preferred() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        board.set_piece(Pos::new(16, 16), piece1);
-    }
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
-    #[test]
-    fn next_match_no_patterns_none() {
-        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), vec![
-            Box::new(|_, _, _| true),
-            Box::new(|_, _, _| true)
-        ]);
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(2, 3), piece2);
-        assert!(board.next_match().is_none());
-    }
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-    #[test]
-    fn next_match_set_pieces_match_found() {
-        let mut pattern_pos = HashSet::new();
-        pattern_pos.insert(Pos::new(2, 3));
-        pattern_pos.insert(Pos::new(3, 3));
-        pattern_pos.insert(Pos::new(6, 8));
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        let type1 = 'f';
+        board.set_piece(Pos::new(3, 0), piece1);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
 
-        let mut board = Board::new(
-            BoardState::new(16, 16),
-            vec![MatchPattern::new(type1, pattern_pos, 1)],
-            Vec::new()
-        );
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+        board.add_and_trickle(Pos::new(2, 4), piece1);
 
-        board.set_piece(Pos::new(0, 1), piece1);
-        board.set_piece(Pos::new(1, 1), piece2);
-        board.set_piece(Pos::new(4, 6), piece3);
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
 
-        let next_match = board.next_match().unwrap();
-        assert_eq!(Pos::new(0, 1), next_match.changed_pos());
-        assert!(next_match.board_pos().contains(&Pos::new(0, 1)));
-        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
-        assert!(next_match.board_pos().contains(&Pos::new(4, 6)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn next_match_swap_pieces_match_found() {
-        let mut pattern_pos = HashSet::new();
-        pattern_pos.insert(Pos::new(2, 3));
-        pattern_pos.insert(Pos::new(3, 3));
-        pattern_pos.insert(Pos::new(8, 8));
-
+    fn add_trickle_with_diagonals_ambiguous_generates_moves_left_preferred() {
         let type1 = 'f';
-
-        let mut board = Board::new(
-            BoardState::new(16, 16),
-            vec![MatchPattern::new(type1, pattern_pos, 1)],
-            Vec::new()
-        );
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
-
-        board.set_piece(Pos::new(0, 1), piece1);
-        board.set_piece(Pos::new(1, 1), piece2);
-        board.set_piece(Pos::new(8, 8), piece3);
-        board.set_piece(Pos::new(6, 6), Piece::Empty);
-        board.next_match();
-        board.next_match();
-        board.next_match();
-        board.next_match();
-
-        assert!(board.swap_pieces(Pos::new(6, 6), Pos::new(8, 8)));
 
-        let next_match = board.next_match().unwrap();
-        assert_eq!(Pos::new(6, 6), next_match.changed_pos());
-        assert!(next_match.board_pos().contains(&Pos::new(0, 1)));
-        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
-        assert!(next_match.board_pos().contains(&Pos::new(6, 6)));
-    }
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
-    #[test]
-    fn next_match_swap_self_no_match_found() {
-        let mut pattern_pos = HashSet::new();
-        pattern_pos.insert(Pos::new(2, 3));
-        pattern_pos.insert(Pos::new(3, 3));
-        pattern_pos.insert(Pos::new(8, 8));
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
 
-        let type1 = 'f';
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        let mut board = Board::new(
-            BoardState::new(16, 16),
-            vec![MatchPattern::new(type1, pattern_pos, 1)],
-            Vec::new()
-        );
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(0, 1), piece1);
-        board.set_piece(Pos::new(1, 1), piece2);
-        board.set_piece(Pos::new(6, 6), piece3);
-        board.next_match();
-        board.next_match();
-        board.next_match();
+        board.set_piece(Pos::new(3, 0), piece1);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
 
-        assert!(board.swap_pieces(Pos::new(6, 6), Pos::new(6, 6)));
-        assert!(board.next_match().is_none());
+        let expected_move = Some(PieceMove::new(vec![Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(1, 1)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(2, 4), piece1));
     }
 
     #[test]
-    fn next_match_trickle_match_found() {
+    fn add_trickle_with_diagonals_blocking_wall_sets_board() {
         let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        let mut pattern_pos1 = HashSet::new();
-        pattern_pos1.insert(Pos::new(0, 0));
-        pattern_pos1.insert(Pos::new(0, 1));
-        pattern_pos1.insert(Pos::new(1, 0));
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
-        let mut board = Board::new(BoardState::new(16, 16), vec![
-            MatchPattern::new(type1, pattern_pos1, 1)
-        ], Vec::new());
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Wall);
+        board.set_piece(Pos::new(0, 4), piece1);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), piece1);
-        board.set_piece(Pos::new(0, 2), piece1);
-        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 0), piece1);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
         board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Wall);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        for _ in 0..6 {
-            board.next_match();
-        }
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Wall);
+        board.set_piece(Pos::new(2, 4), piece1);
+        board.set_piece(Pos::new(2, 5), piece1);
 
-        board.trickle();
+        board.set_piece(Pos::new(3, 0), piece1);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Wall);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        board.add_and_trickle(Pos::new(1, 5), piece1);
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 3)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 3)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(2, 3)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 4)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 5)));
 
-        let next_match = board.next_match().unwrap();
-        assert_eq!(Pos::new(0, 1), next_match.changed_pos());
-        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
-        assert!(next_match.board_pos().contains(&Pos::new(1, 0)));
-        assert!(next_match.board_pos().contains(&Pos::new(0, 1)));
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn next_match_add_trickle_match_found() {
+    fn add_trickle_with_diagonals_blocking_wall_generates_moves() {
         let type1 = 'f';
-
-        let mut pattern_pos1 = HashSet::new();
-        pattern_pos1.insert(Pos::new(0, 0));
-        pattern_pos1.insert(Pos::new(0, 1));
-        pattern_pos1.insert(Pos::new(1, 0));
-
-        let mut board = Board::new(BoardState::new(16, 16), vec![
-            MatchPattern::new(type1, pattern_pos1, 1)
-        ], Vec::new());
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), piece1);
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
         board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Wall);
+        board.set_piece(Pos::new(0, 4), piece1);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
+
+        board.set_piece(Pos::new(1, 0), piece1);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
         board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Wall);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        for _ in 0..6 {
-            board.next_match();
-        }
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Wall);
+        board.set_piece(Pos::new(2, 4), piece1);
+        board.set_piece(Pos::new(2, 5), piece1);
 
-        board.add_and_trickle(Pos::new(0, 2), piece1);
+        board.set_piece(Pos::new(3, 0), piece1);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Wall);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
 
-        let next_match = board.next_match().unwrap();
-        assert_eq!(Pos::new(1, 0), next_match.changed_pos());
-        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
-        assert!(next_match.board_pos().contains(&Pos::new(1, 0)));
-        assert!(next_match.board_pos().contains(&Pos::new(0, 1)));
+        let expected_move = Some(PieceMove::new(vec![Pos::new(1, 5), Pos::new(1, 4)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(1, 5), piece1));
     }
 
     #[test]
-    fn next_match_matches_all_variants() {
-        let piece_type = 'f';
-        let mut pattern_pos = HashSet::new();
-        pattern_pos.insert(Pos::new(2, 2));
-        pattern_pos.insert(Pos::new(3, 3));
-        pattern_pos.insert(Pos::new(4, 4));
-
+    fn add_trickle_with_diagonals_through_hole_sets_board() {
         let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        let mut board = Board::new(
-            BoardState::new(16, 16),
-            vec![MatchPattern::new(type1, pattern_pos, 1)],
-            Vec::new()
-        );
-        let piece1 = Piece::Regular(piece_type, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(piece_type, ALL_DIRECTIONS);
-        let piece3 = Piece::Regular(piece_type, ALL_DIRECTIONS);
-
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece2);
-        board.set_piece(Pos::new(2, 2), piece3);
-
-        let next_match1 = board.next_match().unwrap();
-        assert_eq!(Pos::new(0, 0), next_match1.changed_pos());
-        assert!(next_match1.board_pos().contains(&Pos::new(0, 0)));
-        assert!(next_match1.board_pos().contains(&Pos::new(1, 1)));
-        assert!(next_match1.board_pos().contains(&Pos::new(2, 2)));
-
-        let next_match2 = board.next_match().unwrap();
-        assert_eq!(Pos::new(1, 1), next_match2.changed_pos());
-        assert!(next_match2.board_pos().contains(&Pos::new(0, 0)));
-        assert!(next_match2.board_pos().contains(&Pos::new(1, 1)));
-        assert!(next_match2.board_pos().contains(&Pos::new(2, 2)));
-
-        let next_match3 = board.next_match().unwrap();
-        assert_eq!(Pos::new(2, 2), next_match3.changed_pos());
-        assert!(next_match3.board_pos().contains(&Pos::new(0, 0)));
-        assert!(next_match3.board_pos().contains(&Pos::new(1, 1)));
-        assert!(next_match3.board_pos().contains(&Pos::new(2, 2)));
-    }
-
-    #[test]
-    fn next_match_does_not_match_wrong_types() {
-        let type1 = 'f';
-        let type2 = 's';
-        let mut pattern_pos = HashSet::new();
-        pattern_pos.insert(Pos::new(2, 2));
-        pattern_pos.insert(Pos::new(3, 3));
-        pattern_pos.insert(Pos::new(4, 4));
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
-        let mut board = Board::new(
-            BoardState::new(16, 16),
-            vec![MatchPattern::new(type2, pattern_pos, 1)],
-            Vec::new()
-        );
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Wall);
+        board.set_piece(Pos::new(0, 5), piece1);
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece2);
-        board.set_piece(Pos::new(2, 2), piece3);
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Wall);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        assert!(board.next_match().is_none());
-        assert!(board.next_match().is_none());
-        assert!(board.next_match().is_none());
-    }
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-    #[test]
-    fn next_match_matches_when_not_all_in_queue() {
-        let type1 = 'f';
-        let mut pattern_pos = HashSet::new();
-        pattern_pos.insert(Pos::new(2, 2));
-        pattern_pos.insert(Pos::new(3, 3));
-        pattern_pos.insert(Pos::new(4, 4));
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Wall);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
 
-        let mut board = Board::new(
-            BoardState::new(16, 16),
-            vec![MatchPattern::new(type1, pattern_pos, 1)],
-            Vec::new()
-        );
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+        board.add_and_trickle(Pos::new(1, 5), piece1);
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece2);
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 4)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 5)));
 
-        board.next_match();
-        board.next_match();
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
 
-        board.set_piece(Pos::new(2, 2), piece3);
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
 
-        let next_match = board.next_match().unwrap();
-        assert_eq!(Pos::new(2, 2), next_match.changed_pos());
-        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
-        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
-        assert!(next_match.board_pos().contains(&Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn next_match_board_state_changed_after_match_still_matches() {
+    fn add_trickle_with_diagonals_through_hole_generates_moves() {
         let type1 = 'f';
-        let mut pattern_pos = HashSet::new();
-        pattern_pos.insert(Pos::new(2, 2));
-        pattern_pos.insert(Pos::new(3, 3));
-        pattern_pos.insert(Pos::new(4, 4));
-
-        let mut board = Board::new(
-            BoardState::new(16, 16),
-            vec![MatchPattern::new(type1, pattern_pos, 1)],
-            Vec::new()
-        );
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece4 = Piece::Regular(type1, ALL_DIRECTIONS);
-
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece2);
-
-        board.next_match();
-        board.next_match();
 
-        board.set_piece(Pos::new(2, 2), piece3);
-        board.set_piece(Pos::new(2, 3), piece4);
-
-        let next_match = board.next_match().unwrap();
-        assert_eq!(Pos::new(2, 2), next_match.changed_pos());
-        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
-        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
-        assert!(next_match.board_pos().contains(&Pos::new(2, 2)));
-    }
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
-    #[test]
-    fn next_match_match_overwritten_does_not_match() {
-        let type1 = 'f';
-        let mut pattern_pos = HashSet::new();
-        pattern_pos.insert(Pos::new(2, 2));
-        pattern_pos.insert(Pos::new(3, 3));
-        pattern_pos.insert(Pos::new(4, 4));
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Wall);
+        board.set_piece(Pos::new(0, 5), piece1);
 
-        let mut board = Board::new(
-            BoardState::new(16, 16),
-            vec![MatchPattern::new(type1, pattern_pos, 1)],
-            Vec::new()
-        );
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Wall);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece2);
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
         board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        board.next_match();
-        board.next_match();
-        board.next_match();
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Wall);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 2), piece3);
-        assert!(board.swap_pieces(Pos::new(2, 2), Pos::new(2, 3)));
-        assert!(board.next_match().is_none());
+        let expected_move = Some(PieceMove::new(vec![Pos::new(1, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1), Pos::new(1, 0)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(1, 5), piece1));
     }
 
     #[test]
-    fn next_match_position_in_queue_twice_matches_twice() {
-        let piece_type = 'f';
-        let mut pattern_pos = HashSet::new();
-        pattern_pos.insert(Pos::new(2, 2));
-        pattern_pos.insert(Pos::new(3, 3));
-        pattern_pos.insert(Pos::new(4, 4));
+    fn add_trickle_with_diagonals_changing_directions_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        let mut board = Board::new(
-            BoardState::new(16, 16),
-            vec![MatchPattern::new(piece_type, pattern_pos, 1)],
-            Vec::new()
-        );
-        let piece1 = Piece::Regular(piece_type, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(piece_type, ALL_DIRECTIONS);
-        let piece3 = Piece::Regular(piece_type, ALL_DIRECTIONS);
-        let piece4 = Piece::Regular(piece_type, ALL_DIRECTIONS);
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece2);
-
-        board.next_match();
-        board.next_match();
-
-        board.set_piece(Pos::new(2, 2), piece3);
-        board.set_piece(Pos::new(2, 2), piece4);
-
-        let next_match = board.next_match().unwrap();
-        assert_eq!(Pos::new(2, 2), next_match.changed_pos());
-        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
-        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
-        assert!(next_match.board_pos().contains(&Pos::new(2, 2)));
-    }
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Wall);
+        board.set_piece(Pos::new(0, 5), piece1);
 
-    #[test]
-    fn next_match_two_patterns_same_rank_matching_picked() {
-        let type1 = 'f';
-        let type2 = 's';
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Wall);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        let mut pattern_pos1 = HashSet::new();
-        pattern_pos1.insert(Pos::new(2, 2));
-        pattern_pos1.insert(Pos::new(3, 3));
-        pattern_pos1.insert(Pos::new(4, 4));
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        let mut pattern_pos2 = HashSet::new();
-        pattern_pos2.insert(Pos::new(2, 2));
-        pattern_pos2.insert(Pos::new(3, 3));
-        pattern_pos2.insert(Pos::new(4, 4));
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Wall);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
 
-        let mut board = Board::new(BoardState::new(16, 16), vec![
-            MatchPattern::new(type2, pattern_pos1, 1),
-            MatchPattern::new(type1, pattern_pos2, 1)
-        ], Vec::new());
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
+        board.add_and_trickle(Pos::new(3, 5), piece1);
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece2);
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 4)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 5)));
 
-        board.next_match();
-        board.next_match();
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
 
-        board.set_piece(Pos::new(2, 2), piece3);
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
 
-        let next_match = board.next_match().unwrap();
-        assert_eq!(Pos::new(2, 2), next_match.changed_pos());
-        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
-        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
-        assert!(next_match.board_pos().contains(&Pos::new(2, 2)));
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn next_match_two_patterns_different_rank_higher_picked() {
+    fn add_trickle_with_diagonals_changing_directions_generates_moves() {
         let type1 = 'f';
-
-        let mut pattern_pos1 = HashSet::new();
-        pattern_pos1.insert(Pos::new(2, 2));
-        pattern_pos1.insert(Pos::new(3, 3));
-        pattern_pos1.insert(Pos::new(4, 4));
-
-        let mut pattern_pos2 = HashSet::new();
-        pattern_pos2.insert(Pos::new(1, 1));
-        pattern_pos2.insert(Pos::new(2, 2));
-        pattern_pos2.insert(Pos::new(3, 3));
-        pattern_pos2.insert(Pos::new(4, 4));
-
-        let mut board = Board::new(BoardState::new(16, 16), vec![
-            MatchPattern::new(type1, pattern_pos1, 1),
-            MatchPattern::new(type1, pattern_pos2, 2)
-        ], Vec::new());
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece3 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece4 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece2);
-        board.set_piece(Pos::new(2, 2), piece3);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Wall);
+        board.set_piece(Pos::new(0, 5), piece1);
 
-        board.next_match();
-        board.next_match();
-        board.next_match();
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Wall);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(3, 3), piece4);
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        let next_match = board.next_match().unwrap();
-        assert_eq!(Pos::new(3, 3), next_match.changed_pos());
-        assert!(next_match.board_pos().contains(&Pos::new(0, 0)));
-        assert!(next_match.board_pos().contains(&Pos::new(1, 1)));
-        assert!(next_match.board_pos().contains(&Pos::new(2, 2)));
-        assert!(next_match.board_pos().contains(&Pos::new(3, 3)));
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Wall);
+        board.set_piece(Pos::new(3, 5), Piece::Empty);
+
+        let expected_move = Some(PieceMove::new(vec![Pos::new(3, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1), Pos::new(3, 0)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(3, 5), piece1));
     }
 
     #[test]
-    fn trickle_no_diagonals_sets_board() {
+    fn add_trickle_with_diagonals_west_wall_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(0, 0), piece1);
         board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Wall);
         board.set_piece(Pos::new(0, 3), Piece::Empty);
         board.set_piece(Pos::new(0, 4), Piece::Empty);
         board.set_piece(Pos::new(0, 5), Piece::Empty);
 
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
         board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
         board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
         board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), piece1);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 0), piece1);
         board.set_piece(Pos::new(3, 1), Piece::Empty);
         board.set_piece(Pos::new(3, 2), Piece::Empty);
         board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
         board.set_piece(Pos::new(3, 5), Piece::Empty);
 
-        board.trickle();
+        board.add_and_trickle(Pos::new(1, 5), piece1);
 
         assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
@@ -1798,56 +9588,51 @@ mod tests {
     }
 
     #[test]
-    fn trickle_no_diagonals_generates_moves() {
+    fn add_trickle_with_diagonals_west_wall_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(0, 0), piece1);
         board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Wall);
         board.set_piece(Pos::new(0, 3), Piece::Empty);
         board.set_piece(Pos::new(0, 4), Piece::Empty);
         board.set_piece(Pos::new(0, 5), Piece::Empty);
 
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
         board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
         board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
         board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), piece1);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 0), piece1);
         board.set_piece(Pos::new(3, 1), Piece::Empty);
         board.set_piece(Pos::new(3, 2), Piece::Empty);
         board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
         board.set_piece(Pos::new(3, 5), Piece::Empty);
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(2, 3), Pos::new(2, 0)),
-            (Pos::new(2, 5), Pos::new(2, 1)),
-            (Pos::new(3, 4), Pos::new(3, 0))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        let expected_move = Some(PieceMove::new(vec![Pos::new(1, 5), Pos::new(1, 4), Pos::new(1, 3), Pos::new(1, 2), Pos::new(0, 1)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(1, 5), piece1));
     }
 
     #[test]
-    fn trickle_no_diagonals_fills_prev_piece_space_sets_board() {
+    fn add_trickle_with_diagonals_east_wall_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(0, 0), piece1);
@@ -1858,30 +9643,30 @@ mod tests {
         board.set_piece(Pos::new(0, 5), Piece::Empty);
 
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
         board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
         board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), piece1);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 0), piece1);
         board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Wall);
         board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
         board.set_piece(Pos::new(3, 5), Piece::Empty);
 
-        board.trickle();
+        board.add_and_trickle(Pos::new(2, 5), piece1);
 
         assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
@@ -1902,19 +9687,19 @@ mod tests {
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
 
         assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(piece1, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn trickle_with_diagonals_fills_prev_piece_space_generates_moves() {
+    fn add_trickle_with_diagonals_east_wall_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(0, 0), piece1);
@@ -1925,189 +9710,257 @@ mod tests {
         board.set_piece(Pos::new(0, 5), Piece::Empty);
 
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 1), piece1);
         board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
         board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), piece1);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 0), piece1);
         board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Wall);
         board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
         board.set_piece(Pos::new(3, 5), Piece::Empty);
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 5), Pos::new(1, 2)),
-            (Pos::new(2, 3), Pos::new(2, 0)),
-            (Pos::new(2, 5), Pos::new(2, 1)),
-            (Pos::new(3, 4), Pos::new(3, 0)),
-            (Pos::new(1, 2), Pos::new(0, 1))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        let expected_move = Some(PieceMove::new(vec![Pos::new(2, 5), Pos::new(2, 4), Pos::new(2, 3), Pos::new(2, 2), Pos::new(3, 1)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(2, 5), piece1));
     }
 
     #[test]
-    fn trickle_with_diagonals_left_sets_board() {
+    fn add_trickle_no_diagonals_unmovable_north_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::West
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.add_and_trickle(Pos::new(1, 2), piece2);
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    }
+
+    #[test]
+    fn add_trickle_no_diagonals_unmovable_north_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(1, 0), piece1);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), piece1);
+        let expected_move = Some(PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(1, 2), piece2));
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+    #[test]
+    fn add_trickle_no_diagonals_unmovable_south_sets_board_for_movable() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::East | Direction::West
+        ));
 
-        board.trickle();
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.add_and_trickle(Pos::new(1, 2), piece2);
 
         assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    #[test]
+    fn add_trickle_no_diagonals_unmovable_south_generates_moves_for_movable() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::East | Direction::West
+        ));
 
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        assert_eq!(None, board.add_and_trickle(Pos::new(1, 2), piece2));
     }
 
     #[test]
-    fn trickle_with_diagonals_left_generates_moves() {
+    fn add_trickle_no_diagonals_unmovable_east_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::West
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.add_and_trickle(Pos::new(1, 2), piece2);
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    }
+
+    #[test]
+    fn add_trickle_no_diagonals_unmovable_east_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
         board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), piece1);
+        let expected_move = Some(PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(1, 2), piece2));
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+    #[test]
+    fn add_trickle_no_diagonals_unmovable_west_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::East
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        board.add_and_trickle(Pos::new(1, 2), piece2);
+
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    }
+
+    #[test]
+    fn add_trickle_no_diagonals_unmovable_west_generates_moves() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::East
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
-        let expected_moves = vec![
-            (Pos::new(1, 5), Pos::new(1, 2)),
-            (Pos::new(2, 3), Pos::new(2, 0)),
-            (Pos::new(2, 5), Pos::new(2, 1)),
-            (Pos::new(3, 4), Pos::new(3, 0)),
-            (Pos::new(1, 2), Pos::new(0, 1))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
+
+        let expected_move = Some(PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(1, 2), piece2));
     }
 
     #[test]
-    fn trickle_with_diagonals_right_sets_board() {
+    fn add_trickle_with_diagonals_left_unmovable_north_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::West
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
-
         board.set_piece(Pos::new(1, 0), piece1);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
         board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
-        board.set_piece(Pos::new(2, 4), piece1);
-        board.set_piece(Pos::new(2, 5), piece1);
-
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        board.set_piece(Pos::new(0, 4), Piece::Empty);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
 
-        board.trickle();
+        board.add_and_trickle(Pos::new(1, 2), piece2);
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(0, 0)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
@@ -2115,36 +9968,25 @@ mod tests {
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
 
         assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
-
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
-
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn trickle_with_diagonals_right_generates_moves() {
+    fn add_trickle_with_diagonals_left_unmovable_north_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::West
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
         board.set_piece(Pos::new(0, 1), Piece::Empty);
         board.set_piece(Pos::new(0, 2), Piece::Empty);
         board.set_piece(Pos::new(0, 3), Piece::Empty);
@@ -2153,45 +9995,27 @@ mod tests {
 
         board.set_piece(Pos::new(1, 0), piece1);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
         board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
-        board.set_piece(Pos::new(2, 4), piece1);
-        board.set_piece(Pos::new(2, 5), piece1);
-
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
-
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(2, 3), Pos::new(2, 0)),
-            (Pos::new(2, 4), Pos::new(2, 1)),
-            (Pos::new(2, 5), Pos::new(2, 2)),
-            (Pos::new(3, 4), Pos::new(3, 0)),
-            (Pos::new(2, 2), Pos::new(3, 1))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        let expected_move = Some(PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1), Pos::new(0, 0)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(1, 2), piece2));
     }
 
     #[test]
-    fn trickle_with_diagonals_ambiguous_sets_board_left_preferred() {
+    fn add_trickle_with_diagonals_left_unmovable_south_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::East | Direction::West
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
         board.set_piece(Pos::new(0, 1), Piece::Empty);
         board.set_piece(Pos::new(0, 2), Piece::Empty);
         board.set_piece(Pos::new(0, 3), Piece::Empty);
@@ -2205,60 +10029,35 @@ mod tests {
         board.set_piece(Pos::new(1, 4), Piece::Empty);
         board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
-        board.set_piece(Pos::new(2, 4), piece1);
-        board.set_piece(Pos::new(2, 5), piece1);
-
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        board.add_and_trickle(Pos::new(1, 2), piece2);
 
-        board.trickle();
+        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
-
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
-
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
-
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn trickle_with_diagonals_ambiguous_generates_moves_left_preferred() {
+    fn add_trickle_with_diagonals_left_unmovable_south_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::East | Direction::West
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
         board.set_piece(Pos::new(0, 1), Piece::Empty);
         board.set_piece(Pos::new(0, 2), Piece::Empty);
         board.set_piece(Pos::new(0, 3), Piece::Empty);
@@ -2267,44 +10066,26 @@ mod tests {
 
         board.set_piece(Pos::new(1, 0), piece1);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), piece2);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
         board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
-        board.set_piece(Pos::new(2, 4), piece1);
-        board.set_piece(Pos::new(2, 5), piece1);
-
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
-
-        let expected_moves = vec![
-            (Pos::new(2, 3), Pos::new(2, 0)),
-            (Pos::new(2, 4), Pos::new(2, 1)),
-            (Pos::new(2, 5), Pos::new(2, 2)),
-            (Pos::new(3, 4), Pos::new(3, 0)),
-            (Pos::new(2, 2), Pos::new(1, 1))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        assert_eq!(None, board.add_and_trickle(Pos::new(1, 2), piece2));
     }
 
     #[test]
-    fn trickle_with_diagonals_tall_tower_sets_board() {
+    fn add_trickle_with_diagonals_left_unmovable_east_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::West
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
         board.set_piece(Pos::new(0, 1), Piece::Empty);
         board.set_piece(Pos::new(0, 2), Piece::Empty);
         board.set_piece(Pos::new(0, 3), Piece::Empty);
@@ -2318,23 +10099,9 @@ mod tests {
         board.set_piece(Pos::new(1, 4), Piece::Empty);
         board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), piece1);
-        board.set_piece(Pos::new(2, 3), piece1);
-        board.set_piece(Pos::new(2, 4), piece1);
-        board.set_piece(Pos::new(2, 5), piece1);
-
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
-
-        board.trickle();
+        board.add_and_trickle(Pos::new(1, 2), piece2);
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(0, 0)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
@@ -2342,36 +10109,24 @@ mod tests {
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
 
         assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
-
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
-
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn trickle_with_diagonals_tall_tower_generates_moves() {
+    fn add_trickle_with_diagonals_left_unmovable_east_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::West
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
-
-        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
         board.set_piece(Pos::new(0, 1), Piece::Empty);
         board.set_piece(Pos::new(0, 2), Piece::Empty);
         board.set_piece(Pos::new(0, 3), Piece::Empty);
@@ -2385,167 +10140,98 @@ mod tests {
         board.set_piece(Pos::new(1, 4), Piece::Empty);
         board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), piece1);
-        board.set_piece(Pos::new(2, 3), piece1);
-        board.set_piece(Pos::new(2, 4), piece1);
-        board.set_piece(Pos::new(2, 5), piece1);
-
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
-
-        let expected_moves = vec![
-            (Pos::new(2, 2), Pos::new(2, 0)),
-            (Pos::new(2, 3), Pos::new(2, 1)),
-            (Pos::new(2, 4), Pos::new(2, 2)),
-            (Pos::new(2, 5), Pos::new(2, 3)),
-            (Pos::new(3, 4), Pos::new(3, 0)),
-            (Pos::new(2, 2), Pos::new(1, 1)),
-            (Pos::new(2, 3), Pos::new(2, 2)),
-            (Pos::new(2, 2), Pos::new(3, 1))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        let expected_move = Some(PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1), Pos::new(0, 0)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(1, 2), piece2));
     }
 
     #[test]
-    fn trickle_with_diagonals_blocking_wall_sets_board() {
+    fn add_trickle_with_diagonals_left_unmovable_west_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::North
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(0, 0), Piece::Empty);
         board.set_piece(Pos::new(0, 1), Piece::Empty);
         board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Wall);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
         board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), piece1);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 0), piece1);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Wall);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
-
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Wall);
-        board.set_piece(Pos::new(2, 4), piece1);
-        board.set_piece(Pos::new(2, 5), piece1);
-
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), piece1);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Wall);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.trickle();
+        board.add_and_trickle(Pos::new(1, 2), piece2);
 
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 3)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
 
         assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 3)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
-
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(2, 3)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
-
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 3)));
-        assert_eq!(piece1, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn trickle_with_diagonals_blocking_wall_generates_moves() {
+    fn add_trickle_with_diagonals_left_unmovable_west_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::North
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(0, 0), Piece::Empty);
         board.set_piece(Pos::new(0, 1), Piece::Empty);
         board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Wall);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
         board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), piece1);
+        board.set_piece(Pos::new(0, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 0), piece1);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Wall);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
-
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Wall);
-        board.set_piece(Pos::new(2, 4), piece1);
-        board.set_piece(Pos::new(2, 5), piece1);
-
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), piece1);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Wall);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        let expected_moves = vec![
-            (Pos::new(0, 5), Pos::new(0, 4)),
-            (Pos::new(1, 2), Pos::new(1, 0)),
-            (Pos::new(1, 5), Pos::new(1, 4)),
-            (Pos::new(3, 1), Pos::new(3, 0)),
-            (Pos::new(2, 5), Pos::new(3, 4))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        let expected_move = Some(PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(1, 2), piece2));
     }
 
     #[test]
-    fn trickle_with_diagonals_through_hole_sets_board() {
+    fn add_trickle_with_diagonals_right_unmovable_north_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::West
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Wall);
-        board.set_piece(Pos::new(0, 5), piece1);
-
-        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 0), piece1);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
         board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Wall);
-        board.set_piece(Pos::new(1, 5), piece1);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
         board.set_piece(Pos::new(2, 0), Piece::Empty);
         board.set_piece(Pos::new(2, 1), Piece::Empty);
@@ -2554,65 +10240,40 @@ mod tests {
         board.set_piece(Pos::new(2, 4), Piece::Empty);
         board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Wall);
-        board.set_piece(Pos::new(3, 5), piece1);
-
-        board.trickle();
-
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 4)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 5)));
+        board.add_and_trickle(Pos::new(1, 2), piece2);
 
         assert_eq!(piece1, board.piece(Pos::new(1, 0)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(2, 0)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
-
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn trickle_with_diagonals_through_hole_generates_moves() {
+    fn add_trickle_with_diagonals_right_unmovable_north_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::West
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Wall);
-        board.set_piece(Pos::new(0, 5), piece1);
-
-        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 0), piece1);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
         board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Wall);
-        board.set_piece(Pos::new(1, 5), piece1);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
         board.set_piece(Pos::new(2, 0), Piece::Empty);
         board.set_piece(Pos::new(2, 1), Piece::Empty);
@@ -2621,265 +10282,165 @@ mod tests {
         board.set_piece(Pos::new(2, 4), Piece::Empty);
         board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Wall);
-        board.set_piece(Pos::new(3, 5), piece1);
-
-        let expected_moves = vec![
-            (Pos::new(1, 5), Pos::new(2, 4)),
-            (Pos::new(2, 4), Pos::new(2, 0)),
-            (Pos::new(3, 5), Pos::new(2, 4)),
-            (Pos::new(2, 4), Pos::new(2, 1)),
-            (Pos::new(2, 1), Pos::new(1, 0))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        let expected_move = Some(PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1), Pos::new(2, 0)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(1, 2), piece2));
     }
 
     #[test]
-    fn trickle_with_diagonals_changing_directions_sets_board() {
+    fn add_trickle_with_diagonals_right_unmovable_south_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::East | Direction::West
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Wall);
-        board.set_piece(Pos::new(0, 5), piece1);
-
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
         board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Wall);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
         board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
         board.set_piece(Pos::new(2, 1), Piece::Empty);
         board.set_piece(Pos::new(2, 2), Piece::Empty);
         board.set_piece(Pos::new(2, 3), Piece::Empty);
         board.set_piece(Pos::new(2, 4), Piece::Empty);
         board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Wall);
-        board.set_piece(Pos::new(3, 5), piece1);
-
-        board.trickle();
-
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 4)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 5)));
+        board.add_and_trickle(Pos::new(1, 2), piece2);
 
         assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 0)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
-
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn trickle_with_diagonals_changing_directions_generates_moves() {
+    fn add_trickle_with_diagonals_right_unmovable_south_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::North | Direction::East | Direction::West
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Wall);
-        board.set_piece(Pos::new(0, 5), piece1);
-
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
         board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Wall);
+        board.set_piece(Pos::new(1, 4), Piece::Empty);
         board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), piece1);
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
         board.set_piece(Pos::new(2, 1), Piece::Empty);
         board.set_piece(Pos::new(2, 2), Piece::Empty);
         board.set_piece(Pos::new(2, 3), Piece::Empty);
         board.set_piece(Pos::new(2, 4), Piece::Empty);
         board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Wall);
-        board.set_piece(Pos::new(3, 5), piece1);
-
-        let expected_moves = vec![
-            (Pos::new(3, 5), Pos::new(2, 4)),
-            (Pos::new(2, 4), Pos::new(2, 1)),
-            (Pos::new(2, 1), Pos::new(3, 0))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        assert_eq!(None, board.add_and_trickle(Pos::new(1, 2), piece2));
     }
 
     #[test]
-    fn trickle_with_diagonals_west_wall_sets_board() {
+    fn add_trickle_with_diagonals_right_unmovable_east_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
-
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Wall);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::West
+        ));
+
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(1, 0), piece1);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
         board.set_piece(Pos::new(2, 0), Piece::Empty);
         board.set_piece(Pos::new(2, 1), Piece::Empty);
         board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
         board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), piece1);
-
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
-
-        board.trickle();
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+        board.add_and_trickle(Pos::new(1, 2), piece2);
 
         assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
-
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn trickle_with_diagonals_west_wall_generates_moves() {
+    fn add_trickle_with_diagonals_right_unmovable_east_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::North | Direction::West
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Wall);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
-
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
         board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
         board.set_piece(Pos::new(2, 0), Piece::Empty);
         board.set_piece(Pos::new(2, 1), Piece::Empty);
         board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
         board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), piece1);
-
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece1);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        let expected_moves = vec![
-            (Pos::new(1, 5), Pos::new(1, 2)),
-            (Pos::new(2, 3), Pos::new(2, 0)),
-            (Pos::new(2, 5), Pos::new(2, 1)),
-            (Pos::new(3, 4), Pos::new(3, 0)),
-            (Pos::new(1, 2), Pos::new(0, 1))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        let expected_move = Some(PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(1, 2), piece2));
     }
 
     #[test]
-    fn trickle_with_diagonals_east_wall_sets_board() {
+    fn add_trickle_with_diagonals_right_unmovable_west_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::North
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
-
         board.set_piece(Pos::new(1, 0), piece1);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
         board.set_piece(Pos::new(1, 5), Piece::Empty);
@@ -2887,66 +10448,41 @@ mod tests {
         board.set_piece(Pos::new(2, 0), Piece::Empty);
         board.set_piece(Pos::new(2, 1), Piece::Empty);
         board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
-        board.set_piece(Pos::new(2, 4), piece1);
-        board.set_piece(Pos::new(2, 5), piece1);
-
-        board.set_piece(Pos::new(3, 0), piece1);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Wall);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
-
-        board.trickle();
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+        board.add_and_trickle(Pos::new(1, 2), piece2);
 
         assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
+        assert_eq!(piece2, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
-
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
     }
 
     #[test]
-    fn trickle_with_diagonals_east_wall_generates_moves() {
+    fn add_trickle_with_diagonals_right_unmovable_west_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+        let piece2 = Piece::Regular(type1, enum_set!(
+            Direction::South | Direction::East | Direction::North
+        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
-
         board.set_piece(Pos::new(1, 0), piece1);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece1);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
         board.set_piece(Pos::new(1, 5), Piece::Empty);
@@ -2954,3547 +10490,3338 @@ mod tests {
         board.set_piece(Pos::new(2, 0), Piece::Empty);
         board.set_piece(Pos::new(2, 1), Piece::Empty);
         board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), piece1);
-        board.set_piece(Pos::new(2, 4), piece1);
-        board.set_piece(Pos::new(2, 5), piece1);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(3, 0), piece1);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Wall);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        let expected_move = Some(PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1), Pos::new(2, 0)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(1, 2), piece2));
+    }
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(2, 3), Pos::new(2, 0)),
-            (Pos::new(2, 4), Pos::new(2, 1)),
-            (Pos::new(2, 5), Pos::new(2, 2)),
-            (Pos::new(2, 2), Pos::new(3, 1))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+    #[test]
+    fn add_trickle_right_border_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(15, 0), piece1);
+        board.set_piece(Pos::new(15, 1), Piece::Empty);
+        board.set_piece(Pos::new(15, 2), Piece::Empty);
+        board.set_piece(Pos::new(15, 3), Piece::Empty);
+        board.set_piece(Pos::new(15, 4), Piece::Empty);
+        board.set_piece(Pos::new(15, 5), Piece::Empty);
+
+        board.add_and_trickle(Pos::new(15, 2), piece1);
+
+        assert_eq!(piece1, board.piece(Pos::new(15, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(15, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 4)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 5)));
     }
 
     #[test]
-    fn trickle_no_diagonals_unmovable_north_sets_board() {
+    fn add_trickle_right_border_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::West
-        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        board.set_piece(Pos::new(15, 0), piece1);
+        board.set_piece(Pos::new(15, 1), Piece::Empty);
+        board.set_piece(Pos::new(15, 2), Piece::Empty);
+        board.set_piece(Pos::new(15, 3), Piece::Empty);
+        board.set_piece(Pos::new(15, 4), Piece::Empty);
+        board.set_piece(Pos::new(15, 5), Piece::Empty);
 
-        board.trickle();
+        let expected_move = Some(PieceMove::new(vec![Pos::new(15, 2), Pos::new(15, 1)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(15, 2), piece1));
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    #[test]
+    fn add_trickle_top_border_sets_board() {
+        let type1 = 'f';
+        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(16, 16),
+                                   Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(0, 10), piece1);
+        board.set_piece(Pos::new(0, 11), Piece::Empty);
+        board.set_piece(Pos::new(0, 12), Piece::Empty);
+        board.set_piece(Pos::new(0, 13), Piece::Empty);
+        board.set_piece(Pos::new(0, 14), Piece::Empty);
+        board.set_piece(Pos::new(0, 15), Piece::Empty);
+
+        board.add_and_trickle(Pos::new(0, 12), piece1);
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 10)));
+        assert_eq!(piece1, board.piece(Pos::new(0, 11)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 12)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 13)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 14)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 15)));
     }
 
     #[test]
-    fn trickle_no_diagonals_unmovable_north_generates_moves() {
+    fn add_trickle_top_border_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::West
-        ));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        board.set_piece(Pos::new(0, 10), piece1);
+        board.set_piece(Pos::new(0, 11), Piece::Empty);
+        board.set_piece(Pos::new(0, 12), Piece::Empty);
+        board.set_piece(Pos::new(0, 13), Piece::Empty);
+        board.set_piece(Pos::new(0, 14), Piece::Empty);
+        board.set_piece(Pos::new(0, 15), Piece::Empty);
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 5), Pos::new(1, 2))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        let expected_move = Some(PieceMove::new(vec![Pos::new(0, 12), Pos::new(0, 11)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(0, 12), piece1));
     }
 
     #[test]
-    fn trickle_no_diagonals_unmovable_south_sets_board_for_movable() {
+    fn add_trickle_with_diagonals_piece_replaced_with_more_movable_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::East | Direction::West
-        ));
+        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), piece1);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
 
-        board.trickle();
+        board.set_piece(Pos::new(2, 0), piece1);
+
+        board.set_piece(Pos::new(2, 1), piece2);
+
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+
+        board.add_and_trickle(Pos::new(2, 1), piece1);
 
         assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 3)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
     }
 
     #[test]
-    fn trickle_no_diagonals_unmovable_south_generates_moves_for_movable() {
+    fn add_trickle_with_diagonals_piece_replaced_with_more_movable_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::East | Direction::West
-        ));
+        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), piece1);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
 
-        let expected_moves = vec![
-            (Pos::new(1, 5), Pos::new(1, 3))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        board.set_piece(Pos::new(2, 0), piece1);
+
+        board.set_piece(Pos::new(2, 1), piece2);
+
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+
+        let expected_move = Some(PieceMove::new(vec![Pos::new(2, 1), Pos::new(3, 0)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(2, 1), piece1));
     }
 
     #[test]
-    fn trickle_no_diagonals_unmovable_east_sets_board() {
+    fn add_trickle_with_diagonals_piece_replaced_with_less_movable_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::West
-        ));
+        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), piece1);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
 
-        board.trickle();
+        board.set_piece(Pos::new(2, 0), piece1);
+
+        board.set_piece(Pos::new(2, 1), piece1);
+
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+
+        board.add_and_trickle(Pos::new(2, 1), piece2);
 
         assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
         assert_eq!(piece1, board.piece(Pos::new(1, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
     }
 
     #[test]
-    fn trickle_no_diagonals_unmovable_east_generates_moves() {
+    fn add_trickle_with_diagonals_piece_replaced_with_less_movable_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::West
-        ));
+        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), piece1);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 5), Pos::new(1, 2))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        board.set_piece(Pos::new(2, 0), piece1);
+
+        board.set_piece(Pos::new(2, 1), piece1);
+
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+
+        assert_eq!(None, board.add_and_trickle(Pos::new(2, 1), piece2));
     }
 
     #[test]
-    fn trickle_no_diagonals_unmovable_west_sets_board() {
+    fn add_trickle_with_diagonals_piece_replace_wall_sets_board() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::East
-        ));
+        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), piece1);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
 
-        board.trickle();
+        board.set_piece(Pos::new(2, 0), piece1);
+
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+
+        board.add_and_trickle(Pos::new(2, 1), piece2);
 
         assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
         assert_eq!(piece1, board.piece(Pos::new(1, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
     }
 
     #[test]
-    fn trickle_no_diagonals_unmovable_west_generates_moves() {
+    fn add_trickle_with_diagonals_piece_replace_wall_generates_moves() {
         let type1 = 'f';
         let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::East
-        ));
+        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(16, 16),
                                    Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
+        board.set_piece(Pos::new(1, 1), piece1);
+        board.set_piece(Pos::new(1, 2), piece1);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 5), Pos::new(1, 2))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        board.set_piece(Pos::new(2, 0), piece1);
+
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(2, 4), Piece::Empty);
+
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), Piece::Empty);
+
+        assert_eq!(None, board.add_and_trickle(Pos::new(2, 1), piece2));
     }
 
     #[test]
-    fn trickle_with_diagonals_left_unmovable_north_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::West
-        ));
+    #[should_panic]
+    fn add_portal_out_of_bounds_panics() {
+        let mut board = Board::new(BoardState::new(3, 4), Vec::new(), Vec::new());
+        board.add_portal(Pos::new(0, 1), Pos::new(3, 3));
+    }
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+    #[test]
+    fn remove_portal_portal_removed() {
+        let mut board = Board::new(BoardState::new(3, 4), Vec::new(), Vec::new());
+        board.add_portal(Pos::new(0, 1), Pos::new(2, 3));
+        assert_eq!(Some(Pos::new(2, 3)), board.portal(Pos::new(0, 1)));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        assert_eq!(Some(Pos::new(2, 3)), board.remove_portal(Pos::new(0, 1)));
+        assert_eq!(None, board.portal(Pos::new(0, 1)));
+    }
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
+    #[test]
+    fn trickle_through_portal_sets_board() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+        let piece2 = Piece::Regular('s', ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(3, 4), Vec::new(), Vec::new());
         board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), piece1);
+        board.set_piece(Pos::new(0, 3), piece2);
+        for y in 0..4 {
+            board.set_piece(Pos::new(2, y), Piece::Empty);
+        }
+        board.add_portal(Pos::new(0, 1), Pos::new(2, 3));
 
         board.trickle();
 
-        assert_eq!(piece2, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 0)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
-
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
     }
 
     #[test]
-    fn trickle_with_diagonals_left_unmovable_north_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::West
-        ));
-
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+    fn trickle_through_portal_generates_full_path() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+        let piece2 = Piece::Regular('s', ALL_DIRECTIONS);
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        let mut board = Board::new(BoardState::new(3, 4), Vec::new(), Vec::new());
         board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
-
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        board.set_piece(Pos::new(0, 2), piece1);
+        board.set_piece(Pos::new(0, 3), piece2);
+        for y in 0..4 {
+            board.set_piece(Pos::new(2, y), Piece::Empty);
+        }
+        board.add_portal(Pos::new(0, 1), Pos::new(2, 3));
 
         let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 5), Pos::new(1, 2)),
-            (Pos::new(1, 1), Pos::new(0, 0)),
-            (Pos::new(1, 2), Pos::new(1, 1))
+            PieceMove::new(vec![
+                Pos::new(0, 2), Pos::new(0, 1), Pos::new(2, 3), Pos::new(2, 2), Pos::new(2, 1),
+                Pos::new(2, 0)
+            ]),
+            PieceMove::new(vec![
+                Pos::new(0, 3), Pos::new(0, 2), Pos::new(0, 1), Pos::new(2, 3), Pos::new(2, 2),
+                Pos::new(2, 1)
+            ])
         ];
         assert_eq!(expected_moves, board.trickle());
     }
 
     #[test]
-    fn trickle_with_diagonals_left_unmovable_south_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::East | Direction::West
-        ));
+    fn trickle_portal_entry_occupied_piece_stays() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        let mut board = Board::new(BoardState::new(3, 4), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 1), piece1);
+        board.set_piece(Pos::new(2, 3), piece1);
+        board.add_portal(Pos::new(0, 1), Pos::new(2, 3));
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        assert!(board.trickle().is_empty());
+        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+    #[test]
+    fn trickle_portal_unmovable_south_piece_stays() {
+        let piece1 = Piece::Regular('f', enum_set!(Direction::North | Direction::East | Direction::West));
 
-        board.trickle();
+        let mut board = Board::new(BoardState::new(3, 4), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 1), piece1);
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.add_portal(Pos::new(0, 1), Pos::new(2, 3));
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert!(board.trickle().is_empty());
+        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
+    }
+
+    #[test]
+    fn trickle_portal_loop_stops() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+
+        let mut board = Board::new(BoardState::new(1, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), piece1);
+        board.add_portal(Pos::new(0, 0), Pos::new(0, 2));
 
+        let expected_moves = vec![PieceMove::new(vec![
+            Pos::new(0, 2), Pos::new(0, 1), Pos::new(0, 0),
+            Pos::new(0, 2), Pos::new(0, 1), Pos::new(0, 0),
+            Pos::new(0, 2), Pos::new(0, 1), Pos::new(0, 0)
+        ])];
+        assert_eq!(expected_moves, board.trickle());
         assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
     }
 
     #[test]
-    fn trickle_with_diagonals_left_unmovable_south_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::East | Direction::West
-        ));
-
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+    fn add_and_trickle_through_portal_generates_full_path() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        let mut board = Board::new(BoardState::new(3, 4), Vec::new(), Vec::new());
         board.set_piece(Pos::new(0, 1), Piece::Empty);
         board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Empty);
+        board.add_portal(Pos::new(0, 1), Pos::new(2, 1));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        let expected_move = Some(PieceMove::new(vec![Pos::new(0, 2), Pos::new(0, 1), Pos::new(2, 1), Pos::new(2, 0)]));
+        assert_eq!(expected_move, board.add_and_trickle(Pos::new(0, 2), piece1));
+        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
+    }
 
-        let expected_moves = vec![
-            (Pos::new(1, 5), Pos::new(1, 3)),
-            (Pos::new(1, 3), Pos::new(0, 2)),
-            (Pos::new(0, 2), Pos::new(0, 0))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+    #[test]
+    #[should_panic]
+    fn set_gravity_movable_out_of_bounds_panics() {
+        let mut board = Board::new(BoardState::new(2, 2), Vec::new(), Vec::new());
+        board.set_gravity_movable(Pos::new(2, 0), EnumSet::new());
     }
 
     #[test]
-    fn trickle_with_diagonals_left_unmovable_east_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::West
-        ));
+    fn set_gravity_movable_empty_and_wall_not_changed() {
+        let mut board = Board::new(BoardState::new(2, 1), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        assert!(!board.set_gravity_movable(Pos::new(0, 0), EnumSet::new()));
+        assert!(!board.set_gravity_movable(Pos::new(1, 0), ALL_DIRECTIONS));
+        assert!(board.gravity_movable_in(Direction::South).is_set(Pos::new(0, 0)));
+        assert!(!board.gravity_movable_in(Direction::South).is_set(Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn set_gravity_movable_sticky_piece_swaps_but_does_not_fall() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+        let piece2 = Piece::Regular('s', ALL_DIRECTIONS);
 
+        let mut board = Board::new(BoardState::new(2, 2), Vec::new(), Vec::new());
         board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), piece1);
+        board.set_piece(Pos::new(1, 1), piece2);
+        assert!(board.set_gravity_movable(Pos::new(0, 1), EnumSet::new()));
+        board.set_gravity_movable(Pos::new(1, 1), enum_set!(Direction::South));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        assert!(board.trickle().is_empty());
+        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
 
+        assert!(board.swap_pieces(Pos::new(0, 1), Pos::new(1, 1)));
         board.trickle();
-
-        assert_eq!(piece2, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
-
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
         assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert_eq!(piece2, board.piece(Pos::new(0, 0)));
     }
 
     #[test]
-    fn trickle_with_diagonals_left_unmovable_east_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::West
-        ));
+    fn set_gravity_movable_unswappable_piece_falls() {
+        let piece1 = Piece::Regular('f', EnumSet::new());
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        let mut board = Board::new(BoardState::new(1, 2), Vec::new(), Vec::new());
         board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), piece1);
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        assert!(board.trickle().is_empty());
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 5), Pos::new(1, 2)),
-            (Pos::new(1, 1), Pos::new(0, 0)),
-            (Pos::new(1, 2), Pos::new(1, 1))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        board.set_gravity_movable(Pos::new(0, 1), ALL_DIRECTIONS);
+        board.trickle();
+
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert!(board.movable_in(Direction::South).is_set(Pos::new(0, 1)));
+        assert!(!board.movable_in(Direction::South).is_set(Pos::new(0, 0)));
     }
 
     #[test]
-    fn trickle_with_diagonals_left_unmovable_west_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::North
-        ));
+    fn set_piece_gravity_directions_reset_to_swap_directions() {
+        let mut board = Board::new(BoardState::new(1, 1), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_gravity_movable(Pos::new(0, 0), EnumSet::new());
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('s', enum_set!(Direction::South)));
 
+        assert!(board.gravity_movable_in(Direction::South).is_set(Pos::new(0, 0)));
+        assert!(!board.gravity_movable_in(Direction::East).is_set(Pos::new(0, 0)));
+    }
+
+    fn falling_column_board() -> Board {
+        let mut board = Board::new(BoardState::new(1, 4), Vec::new(), Vec::new());
         board.set_piece(Pos::new(0, 0), Piece::Empty);
         board.set_piece(Pos::new(0, 1), Piece::Empty);
         board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Regular('f', ALL_DIRECTIONS));
+        while board.next_match().is_some() {}
+        board
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+    #[test]
+    fn gravity_marking_new_board_all() {
+        assert_eq!(GravityMarking::All, falling_column_board().gravity_marking());
+    }
+
+    #[test]
+    fn trickle_gravity_marking_all_whole_path_marked() {
+        let mut board = falling_column_board();
 
         board.trickle();
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+        let changed: HashSet<Pos> = board.state().last_changed.iter().copied().collect();
+        let expected: HashSet<Pos> = (0..4).map(|y| Pos::new(0, y)).collect();
+        assert_eq!(expected, changed);
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    #[test]
+    fn trickle_gravity_marking_final_landing_marked() {
+        let mut board = falling_column_board();
+        board.set_gravity_marking(GravityMarking::Final);
+
+        board.trickle();
+
+        assert_eq!(vec![Pos::new(0, 0)], Vec::from(board.state().last_changed.clone()));
     }
 
     #[test]
-    fn trickle_with_diagonals_left_unmovable_west_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::North
-        ));
+    fn trickle_gravity_marking_off_nothing_marked() {
+        let mut board = falling_column_board();
+        board.set_gravity_marking(GravityMarking::Off);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        board.trickle();
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        assert!(board.state().last_changed.is_empty());
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn add_and_trickle_gravity_marking_final_added_and_landing_marked() {
+        let mut board = falling_column_board();
         board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        while board.next_match().is_some() {}
+        board.set_gravity_marking(GravityMarking::Final);
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        board.add_and_trickle(Pos::new(0, 3), Piece::Regular('f', ALL_DIRECTIONS));
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 5), Pos::new(1, 2)),
-            (Pos::new(1, 2), Pos::new(0, 1)),
-            (Pos::new(0, 1), Pos::new(0, 0))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        assert_eq!(vec![Pos::new(0, 3), Pos::new(0, 0)], Vec::from(board.state().last_changed.clone()));
+    }
+
+    #[test]
+    fn step_gravity_marking_final_current_positions_marked() {
+        let mut board = falling_column_board();
+        board.set_gravity_marking(GravityMarking::Final);
+
+        board.step(1);
+
+        assert_eq!(vec![Pos::new(0, 2)], Vec::from(board.state().last_changed.clone()));
+    }
+
+    fn overhang_board(has_overhang: bool) -> Board {
+        let mut board = Board::new(BoardState::new(2, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        if !has_overhang {
+            board.set_piece(Pos::new(0, 2), Piece::Empty);
+        }
+        board.set_piece(Pos::new(1, 1), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board
+    }
+
+    #[test]
+    fn diagonal_fill_new_board_always() {
+        assert_eq!(DiagonalFill::Always, overhang_board(true).diagonal_fill());
     }
 
     #[test]
-    fn trickle_with_diagonals_right_unmovable_north_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::West
-        ));
+    fn trickle_diagonal_fill_always_slips_into_open_column() {
+        let mut board = overhang_board(false);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        board.trickle();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn trickle_diagonal_fill_shadowed_only_open_column_not_filled() {
+        let mut board = overhang_board(false);
+        board.set_diagonal_fill(DiagonalFill::ShadowedOnly);
+
+        assert!(board.trickle().is_empty());
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn trickle_diagonal_fill_shadowed_only_slips_under_overhang() {
+        let mut board = overhang_board(true);
+        board.set_diagonal_fill(DiagonalFill::ShadowedOnly);
 
         board.trickle();
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
 
-        assert_eq!(piece2, board.piece(Pos::new(2, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    #[test]
+    fn trickle_diagonal_fill_shadowed_only_unmovable_overhang_is_shadow() {
+        let mut board = overhang_board(false);
+        board.set_piece(Pos::new(0, 2), Piece::Regular('g', enum_set!(Direction::North)));
+        board.set_diagonal_fill(DiagonalFill::ShadowedOnly);
+
+        board.trickle();
+
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
     }
 
     #[test]
-    fn trickle_with_diagonals_right_unmovable_north_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::West
-        ));
+    fn trickle_diagonal_fill_shadowed_only_portal_entry_above_not_shadow() {
+        let mut board = overhang_board(true);
+        board.add_portal(Pos::new(1, 2), Pos::new(0, 1));
+        board.set_diagonal_fill(DiagonalFill::ShadowedOnly);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        assert!(board.trickle().is_empty());
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
+    #[test]
+    fn add_and_trickle_diagonal_fill_shadowed_only_open_column_not_filled() {
+        let mut board = overhang_board(false);
         board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        board.set_piece(Pos::new(1, 0), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_diagonal_fill(DiagonalFill::ShadowedOnly);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        assert_eq!(None, board.add_and_trickle(Pos::new(1, 1), Piece::Regular('f', ALL_DIRECTIONS)));
+    }
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 5), Pos::new(1, 2)),
-            (Pos::new(1, 1), Pos::new(2, 0)),
-            (Pos::new(1, 2), Pos::new(1, 1))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+    #[test]
+    #[should_panic]
+    fn piece_move_new_empty_path_panics() {
+        PieceMove::new(Vec::new());
     }
 
     #[test]
-    fn trickle_with_diagonals_right_unmovable_south_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::East | Direction::West
-        ));
+    fn piece_move_from_and_to_path_ends() {
+        let piece_move = PieceMove::new(vec![Pos::new(1, 2), Pos::new(1, 1), Pos::new(0, 0)]);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        assert_eq!(Pos::new(1, 2), piece_move.from());
+        assert_eq!(Pos::new(0, 0), piece_move.to());
+        assert_eq!(3, piece_move.path().len());
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+    #[test]
+    fn step_stack_falls_one_space_per_step() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+        let piece2 = Piece::Regular('s', ALL_DIRECTIONS);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        let mut board = Board::new(BoardState::new(1, 4), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), piece1);
+        board.set_piece(Pos::new(0, 3), piece2);
 
-        board.trickle();
+        let expected_moves = vec![
+            vec![(Pos::new(0, 2), Pos::new(0, 1)), (Pos::new(0, 3), Pos::new(0, 2))],
+            vec![(Pos::new(0, 1), Pos::new(0, 0)), (Pos::new(0, 2), Pos::new(0, 1))]
+        ];
+        assert_eq!(expected_moves, board.step(2));
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    #[test]
+    fn step_fewer_steps_than_fall_piece_midway() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+        let mut board = Board::new(BoardState::new(1, 4), Vec::new(), Vec::new());
+        for y in 0..3 {
+            board.set_piece(Pos::new(0, y), Piece::Empty);
+        }
+        board.set_piece(Pos::new(0, 3), piece1);
+
+        assert_eq!(1, board.step(1).len());
+        assert_eq!(piece1, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
     }
 
     #[test]
-    fn trickle_with_diagonals_right_unmovable_south_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::East | Direction::West
-        ));
+    fn step_settled_stops_early() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        let mut board = Board::new(BoardState::new(1, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), piece1);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        assert_eq!(1, board.step(10).len());
+        assert!(board.step(10).is_empty());
+    }
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn step_blocked_piece_moves_diagonally() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
 
-        let expected_moves = vec![
-            (Pos::new(1, 5), Pos::new(1, 3)),
-            (Pos::new(1, 3), Pos::new(2, 2)),
-            (Pos::new(2, 2), Pos::new(2, 0))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        let mut board = Board::new(BoardState::new(2, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 1), piece1);
+
+        assert_eq!(vec![vec![(Pos::new(1, 1), Pos::new(0, 0))]], board.step(5));
+        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
     }
 
     #[test]
-    fn trickle_with_diagonals_right_unmovable_east_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::West
-        ));
+    fn step_unmovable_south_piece_stays() {
+        let piece1 = Piece::Regular('f', enum_set!(Direction::North | Direction::East | Direction::West));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        let mut board = Board::new(BoardState::new(1, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), piece1);
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+        assert!(board.step(1).is_empty());
+        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
+    }
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    fn random_falling_board(seed: u64) -> Board {
+        let mut rng = Rng::new(seed);
+        let width = 2 + rng.next_below(6) as u8;
+        let height = 2 + rng.next_below(6) as u8;
+        let mut board = Board::new(BoardState::new(width, height), Vec::new(), Vec::new());
+        board.set_floating('o', true);
+        board.set_weight('h', 2);
+
+        for y in 0..height {
+            for x in 0..width {
+                let piece = match rng.next_below(12) {
+                    0..=4 => Piece::Empty,
+                    5 => Piece::Wall,
+                    6 => Piece::Regular('u', enum_set!(Direction::North | Direction::East | Direction::West)),
+                    7 => Piece::Regular('o', ALL_DIRECTIONS),
+                    8 => Piece::Regular('h', ALL_DIRECTIONS),
+                    roll => Piece::Regular((b'a' + roll as u8 - 9) as char, ALL_DIRECTIONS)
+                };
+                board.set_piece(Pos::new(x, y), piece);
+            }
+        }
 
-        board.trickle();
+        // Entries are below their exits so that pieces cannot loop through portals
+        for _ in 0..rng.next_below(3) {
+            let exit = Pos::new(rng.next_below(usize::from(width)) as u8, 1 + rng.next_below(usize::from(height) - 1) as u8);
+            let entry = Pos::new(rng.next_below(usize::from(width)) as u8, rng.next_below(usize::from(exit.y())) as u8);
+            board.add_portal(exit, entry);
+        }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        board
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    fn assert_same_pieces(expected: &Board, actual: &Board) {
+        for y in 0..expected.state.height {
+            for x in 0..expected.state.width {
+                assert_eq!(expected.piece(Pos::new(x, y)), actual.piece(Pos::new(x, y)));
+            }
+        }
     }
 
     #[test]
-    fn trickle_with_diagonals_right_unmovable_east_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::West
-        ));
+    fn step_until_settled_random_boards_same_as_trickle() {
+        for seed in 0..500 {
+            let mut stepped = random_falling_board(seed);
+            let mut trickled = random_falling_board(seed);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+            stepped.step(u32::MAX);
+            trickled.trickle();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+            assert_same_pieces(&trickled, &stepped);
+        }
+    }
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn step_one_at_a_time_random_boards_same_as_repeated_trickle() {
+        for seed in 0..500 {
+            let mut stepped = random_falling_board(seed);
+            let mut trickled = random_falling_board(seed);
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 5), Pos::new(1, 2)),
-            (Pos::new(1, 2), Pos::new(2, 1)),
-            (Pos::new(2, 1), Pos::new(2, 0))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+            while !stepped.step(1).is_empty() {}
+            while !trickled.trickle().is_empty() {}
+
+            assert_same_pieces(&trickled, &stepped);
+        }
     }
 
     #[test]
-    fn trickle_with_diagonals_right_unmovable_west_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::North
-        ));
-
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+    #[should_panic]
+    fn shift_row_out_of_bounds_panics() {
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        board.shift_row(2, 1, true);
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+    #[test]
+    fn shift_row_positive_wrap_moves_east() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+        let piece2 = Piece::Regular('s', ALL_DIRECTIONS);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 1), piece1);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(3, 1), piece2);
 
-        board.trickle();
+        board.shift_row(1, 1, true);
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece2, board.piece(Pos::new(0, 1)));
         assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
-
-        assert_eq!(piece2, board.piece(Pos::new(2, 0)));
         assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 1)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 0)));
     }
 
     #[test]
-    fn trickle_with_diagonals_right_unmovable_west_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::North
-        ));
-
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
-
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), piece1);
+    fn shift_row_negative_wrap_more_than_width_moves_west() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
 
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
         board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        board.set_piece(Pos::new(3, 0), Piece::Empty);
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 5), Pos::new(1, 2)),
-            (Pos::new(1, 1), Pos::new(2, 0)),
-            (Pos::new(1, 2), Pos::new(1, 1))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        board.shift_row(0, -5, true);
+
+        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
+        assert_eq!(1, board.count('f'));
     }
 
     #[test]
-    fn trickle_right_border_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn shift_row_no_wrap_pieces_removed() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+        let piece2 = Piece::Regular('s', ALL_DIRECTIONS);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(1, 0), piece2);
 
-        board.set_piece(Pos::new(15, 0), piece1);
-        board.set_piece(Pos::new(15, 1), Piece::Empty);
-        board.set_piece(Pos::new(15, 2), piece1);
-        board.set_piece(Pos::new(15, 3), Piece::Empty);
-        board.set_piece(Pos::new(15, 4), Piece::Empty);
-        board.set_piece(Pos::new(15, 5), piece1);
+        board.shift_row(0, -1, false);
 
-        board.trickle();
+        assert_eq!(piece2, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 0)));
+        assert_eq!(0, board.count('f'));
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(15, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(15, 1)));
-        assert_eq!(piece1, board.piece(Pos::new(15, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 5)));
+    #[test]
+    fn shift_row_marks_row_for_match_check() {
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+
+        board.shift_row(1, 1, true);
+
+        let changed: HashSet<Pos> = board.state().last_changed.iter().copied().collect();
+        let expected: HashSet<Pos> = (0..4).map(|x| Pos::new(x, 1)).collect();
+        assert_eq!(expected, changed);
     }
 
     #[test]
-    fn trickle_right_border_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn shift_row_creates_match() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(0, 0));
+        spaces.insert(Pos::new(0, 1));
+        let pattern = MatchPattern::new('f', spaces, 1);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        let mut board = Board::new(BoardState::new(4, 2), vec![pattern], Vec::new());
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(3, 1), piece1);
+        while board.next_match().is_some() {}
 
-        board.set_piece(Pos::new(15, 0), piece1);
-        board.set_piece(Pos::new(15, 1), Piece::Empty);
-        board.set_piece(Pos::new(15, 2), piece1);
-        board.set_piece(Pos::new(15, 3), Piece::Empty);
-        board.set_piece(Pos::new(15, 4), Piece::Empty);
-        board.set_piece(Pos::new(15, 5), piece1);
+        board.shift_row(1, 1, true);
 
-        let expected_moves = vec![
-            (Pos::new(15, 2), Pos::new(15, 1)),
-            (Pos::new(15, 5), Pos::new(15, 2))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        assert!(board.next_match().is_some());
     }
 
     #[test]
-    fn trickle_top_border_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    #[should_panic]
+    fn shift_column_out_of_bounds_panics() {
+        let mut board = Board::new(BoardState::new(4, 2), Vec::new(), Vec::new());
+        board.shift_column(4, 1, true);
+    }
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+    #[test]
+    fn shift_column_positive_wrap_moves_north() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+        let piece2 = Piece::Regular('s', ALL_DIRECTIONS);
 
-        board.set_piece(Pos::new(0, 10), piece1);
-        board.set_piece(Pos::new(0, 11), Piece::Empty);
-        board.set_piece(Pos::new(0, 12), piece1);
-        board.set_piece(Pos::new(0, 13), Piece::Empty);
-        board.set_piece(Pos::new(0, 14), Piece::Empty);
-        board.set_piece(Pos::new(0, 15), piece1);
+        let mut board = Board::new(BoardState::new(2, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(1, 0), piece1);
+        board.set_piece(Pos::new(1, 2), piece2);
 
-        board.trickle();
+        board.shift_column(1, 1, true);
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 10)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 11)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 12)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 13)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 14)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 15)));
+        assert_eq!(piece2, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 2)));
     }
 
     #[test]
-    fn trickle_top_border_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn shift_column_no_wrap_offset_past_height_all_empty() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        let mut board = Board::new(BoardState::new(2, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), piece1);
 
-        board.set_piece(Pos::new(0, 10), piece1);
-        board.set_piece(Pos::new(0, 11), Piece::Empty);
-        board.set_piece(Pos::new(0, 12), piece1);
-        board.set_piece(Pos::new(0, 13), Piece::Empty);
-        board.set_piece(Pos::new(0, 14), Piece::Empty);
-        board.set_piece(Pos::new(0, 15), piece1);
+        board.shift_column(0, 300, false);
 
-        let expected_moves = vec![
-            (Pos::new(0, 12), Pos::new(0, 11)),
-            (Pos::new(0, 15), Pos::new(0, 12))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
     }
 
     #[test]
-    fn trickle_with_diagonals_left_adjacent_even_towers_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn rotate_90_non_square_pieces_moved_clockwise() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+        let piece2 = Piece::Regular('s', ALL_DIRECTIONS);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        let mut board = Board::new(BoardState::new(3, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 1), piece1);
+        board.set_piece(Pos::new(2, 0), piece2);
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        board.rotate_90();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(2, board.state().width);
+        assert_eq!(3, board.state().height);
+        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
+        assert_eq!(piece2, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 1)));
+        assert_eq!(vec![Pos::new(1, 2)], board.type_bitboard('f').unwrap().iter_set().collect::<Vec<Pos>>());
+    }
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), piece1);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn rotate_90_directions_rotated_clockwise() {
+        let piece1 = Piece::Regular('f', enum_set!(Direction::North | Direction::East));
 
-        board.trickle();
+        let mut board = Board::new(BoardState::new(3, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), piece1);
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+        board.rotate_90();
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert_eq!(
+            Piece::Regular('f', enum_set!(Direction::East | Direction::South)),
+            board.piece(Pos::new(0, 2))
+        );
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    #[test]
+    fn rotate_90_four_times_original_board() {
+        let mut board = Board::new(BoardState::new(3, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 1), Piece::Regular('f', enum_set!(Direction::North | Direction::West)));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        let expected = format!("{}", board);
+
+        board.rotate_90();
+        board.rotate_90();
+        board.rotate_90();
+        board.rotate_90();
+
+        assert_eq!(expected, format!("{}", board));
+        assert_eq!(
+            Piece::Regular('f', enum_set!(Direction::North | Direction::West)),
+            board.piece(Pos::new(0, 1))
+        );
     }
 
     #[test]
-    fn trickle_with_diagonals_left_adjacent_even_towers_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn rotate_180_pieces_and_directions_rotated() {
+        let piece1 = Piece::Regular('f', enum_set!(Direction::North | Direction::East));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        let mut board = Board::new(BoardState::new(3, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), piece1);
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        board.rotate_180();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(
+            Piece::Regular('f', enum_set!(Direction::South | Direction::West)),
+            board.piece(Pos::new(2, 1))
+        );
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 0)));
+    }
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), piece1);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn rotate_180_exits_and_portals_rotated() {
+        let mut board = Board::new(BoardState::new(3, 2), Vec::new(), Vec::new());
+        board.add_portal(Pos::new(0, 0), Pos::new(1, 1));
 
-        let expected_moves = vec![
-            (Pos::new(1, 1), Pos::new(0, 0)),
-            (Pos::new(1, 2), Pos::new(1, 1))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        board.rotate_180();
+
+        let exits: Vec<Pos> = board.exits().iter_set().collect();
+        assert_eq!(vec![Pos::new(0, 1), Pos::new(1, 1), Pos::new(2, 1)], exits);
+        assert_eq!(Some(Pos::new(1, 0)), board.portal(Pos::new(2, 1)));
+        assert_eq!(None, board.portal(Pos::new(0, 0)));
     }
 
     #[test]
-    fn trickle_with_diagonals_right_adjacent_even_towers_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn mirror_horizontal_pieces_and_directions_mirrored() {
+        let piece1 = Piece::Regular('f', enum_set!(Direction::North | Direction::East));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        let mut board = Board::new(BoardState::new(3, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 1), piece1);
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        board.mirror_horizontal();
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), piece1);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        assert_eq!(
+            Piece::Regular('f', enum_set!(Direction::North | Direction::West)),
+            board.piece(Pos::new(2, 1))
+        );
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 1)));
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+    #[test]
+    fn mirror_horizontal_marks_all_positions_after_existing() {
+        let mut board = Board::new(BoardState::new(3, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+
+        board.mirror_horizontal();
 
-        board.trickle();
+        assert_eq!(6, board.state().last_changed.len());
+        assert_eq!(Some(&Pos::new(2, 1)), board.state().last_changed.front());
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    #[test]
+    fn mirror_horizontal_match_found_in_new_position() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(0, 0));
+        spaces.insert(Pos::new(1, 0));
+        let pattern = MatchPattern::new('f', spaces, 1);
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+        let mut board = Board::new(BoardState::new(3, 2), vec![pattern], Vec::new());
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(1, 0), piece1);
 
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+        board.mirror_horizontal();
+
+        let expected: HashSet<Pos> = vec![Pos::new(1, 0), Pos::new(2, 0)].into_iter().collect();
+        assert_eq!(&expected, board.next_match().unwrap().board_pos());
     }
 
     #[test]
-    fn trickle_with_diagonals_right_adjacent_even_towers_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn display_shows_all_pieces_with_type() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+        let piece2 = Piece::Regular('s', ALL_DIRECTIONS);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
+        let mut board = Board::new(BoardState::new(15, 17),
                                    Vec::new(), Vec::new());
 
         board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
+
+        board.set_piece(Pos::new(1, 1), piece2);
         board.set_piece(Pos::new(1, 2), piece1);
         board.set_piece(Pos::new(1, 3), Piece::Empty);
         board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), piece1);
+        board.set_piece(Pos::new(2, 0), piece2);
+        board.set_piece(Pos::new(2, 1), piece2);
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
         board.set_piece(Pos::new(2, 3), Piece::Empty);
         board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.set_piece(Pos::new(3, 0), piece2);
+        board.set_piece(Pos::new(3, 1), piece1);
+        board.set_piece(Pos::new(3, 2), piece1);
         board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        board.set_piece(Pos::new(3, 4), piece2);
 
-        let expected_moves = vec![
-            (Pos::new(2, 1), Pos::new(3, 0)),
-            (Pos::new(2, 2), Pos::new(2, 1))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        let expected = "\
+        ###############\
+        \n###############\
+        \n###############\
+        \n###############\
+        \n###############\
+        \n###############\
+        \n###############\
+        \n###############\
+        \n###############\
+        \n###############\
+        \n###############\
+        \n###############\
+        \n#  s###########\
+        \n#   ###########\
+        \n#f f###########\
+        \n#ssf###########\
+        \n#fss###########\
+        \n";
+
+        assert_eq!(expected, format!("{}", board));
     }
 
     #[test]
-    fn trickle_with_diagonals_left_adjacent_uneven_towers_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn effects_new_board_disabled() {
+        let mut board = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        assert!(!board.effects_enabled());
+        assert!(board.effects().is_empty());
+        assert!(board.drain_effects().is_empty());
+    }
 
+    #[test]
+    fn set_piece_effects_enabled_records_changes() {
+        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
+        let piece2 = Piece::Regular('s', ALL_DIRECTIONS);
+        let mut board = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
         board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
-
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        board.set_effects_enabled(true);
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), piece1);
-        board.set_piece(Pos::new(2, 3), piece1);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        board.set_piece(Pos::new(0, 0), piece1);
+        board.set_piece(Pos::new(0, 0), piece2);
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 0), Piece::Wall);
 
-        board.trickle();
+        let expected = vec![
+            Effect::Spawned { pos: Pos::new(0, 0) },
+            Effect::Converted { pos: Pos::new(0, 0), from: piece1, to: piece2 },
+            Effect::Cleared { pos: Pos::new(0, 0), cause: ClearCause::Direct }
+        ];
+        assert_eq!(expected, board.drain_effects());
+        assert!(board.effects().is_empty());
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+    #[test]
+    fn set_effects_enabled_disabled_discards_queue() {
+        let mut board = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_effects_enabled(true);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        board.set_effects_enabled(false);
+        board.set_effects_enabled(true);
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+        assert!(board.effects().is_empty());
     }
 
     #[test]
-    fn trickle_with_diagonals_left_adjacent_uneven_towers_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn swap_pieces_effects_enabled_records_both_moves() {
+        let mut board = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_effects_enabled(true);
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        assert!(board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+
+        let expected = vec![
+            Effect::Moved { from: Pos::new(0, 0), to: Pos::new(1, 0) },
+            Effect::Moved { from: Pos::new(1, 0), to: Pos::new(0, 0) }
+        ];
+        assert_eq!(expected, board.effects());
+    }
 
+    #[test]
+    fn trickle_effects_enabled_one_move_per_piece() {
+        let mut board = Board::new(BoardState::new(1, 4), Vec::new(), Vec::new());
         board.set_piece(Pos::new(0, 0), Piece::Empty);
         board.set_piece(Pos::new(0, 1), Piece::Empty);
         board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
-
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_effects_enabled(true);
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), piece1);
-        board.set_piece(Pos::new(2, 3), piece1);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        board.trickle();
 
-        let expected_moves = vec![
-            (Pos::new(1, 1), Pos::new(0, 0)),
-            (Pos::new(2, 2), Pos::new(1, 1)),
-            (Pos::new(2, 3), Pos::new(2, 2))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        let expected = vec![Effect::Moved { from: Pos::new(0, 3), to: Pos::new(0, 0) }];
+        assert_eq!(expected, board.drain_effects());
     }
 
     #[test]
-    fn trickle_with_diagonals_right_adjacent_uneven_towers_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+    fn try_set_piece_in_bounds_replaces_piece() {
+        let mut board = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
+        let piece = Piece::Regular('f', ALL_DIRECTIONS);
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), piece1);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(Ok(Piece::Wall), board.try_set_piece(Pos::new(2, 2), piece));
+        assert_eq!(piece, board.piece(Pos::new(2, 2)));
+    }
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn try_set_piece_out_of_bounds_error() {
+        let mut board = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        let result = board.try_set_piece(Pos::new(3, 0), Piece::Regular('f', ALL_DIRECTIONS));
 
-        board.trickle();
+        assert_eq!(Err(SetPieceError::OutOfBounds(Pos::new(3, 0))), result);
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    #[test]
+    fn try_set_piece_too_many_types_error() {
+        let mut board = Board::new(BoardState::new(2, 1), Vec::new(), Vec::new());
+        for index in 0..u32::from(u16::MAX) {
+            let piece_type = std::char::from_u32(0x10000 + index).unwrap();
+            board.set_piece(Pos::new(0, 0), Piece::Regular(piece_type, ALL_DIRECTIONS));
+        }
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+        let result = board.try_set_piece(Pos::new(1, 0), Piece::Regular('f', ALL_DIRECTIONS));
 
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+        assert_eq!(Err(SetPieceError::TooManyPieceTypes), result);
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 0)));
+        assert!(board.try_set_piece(Pos::new(1, 0), Piece::Regular('\u{10000}', ALL_DIRECTIONS)).is_ok());
     }
 
     #[test]
-    fn trickle_with_diagonals_right_adjacent_uneven_towers_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
-
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), piece1);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    fn type_id_ids_in_order_of_first_placement() {
+        let mut board = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('s', ALL_DIRECTIONS));
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        assert_eq!(Some(1), board.type_id('s'));
+        assert_eq!(Some(2), board.type_id('f'));
+        assert_eq!(None, board.type_id('t'));
+        assert_eq!(Some('f'), board.type_with_id(2));
+        assert_eq!(None, board.type_with_id(0));
+        assert_eq!(&['s', 'f'], board.piece_types());
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+    #[test]
+    fn set_piece_many_types_bitboards_kept() {
+        let mut board = Board::new(BoardState::new(20, 20), Vec::new(), Vec::new());
+        for index in 0..400u32 {
+            let pos = Pos::new((index % 20) as u8, (index / 20) as u8);
+            board.set_piece(pos, Piece::Regular(std::char::from_u32(0x100 + index).unwrap(), ALL_DIRECTIONS));
+        }
 
-        let expected_moves = vec![
-            (Pos::new(2, 1), Pos::new(3, 0)),
-            (Pos::new(1, 2), Pos::new(2, 1)),
-            (Pos::new(1, 3), Pos::new(1, 2))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        assert_eq!(400, board.piece_types().len());
+        assert_eq!(Some(&Piece::Regular('\u{28f}', ALL_DIRECTIONS)), Some(&board.piece(Pos::new(19, 19))));
+        assert_eq!(1, board.type_bitboard('\u{28f}').unwrap().count());
     }
 
     #[test]
-    fn trickle_with_diagonals_piece_replaced_with_more_movable_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
+    fn try_swap_pieces_out_of_bounds_error() {
+        let mut board = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        let result = board.try_swap_pieces(Pos::new(0, 0), Pos::new(0, 3));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        assert_eq!(Err(SwapError::OutOfBounds(Pos::new(0, 3))), result);
+    }
 
-        board.set_piece(Pos::new(2, 0), piece1);
+    #[test]
+    fn try_swap_pieces_rule_violated_rejected() {
+        let mut board = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('g', EnumSet::new()));
 
-        board.set_piece(Pos::new(2, 1), piece2);
-        board.set_piece(Pos::new(2, 1), piece1);
+        let result = board.try_swap_pieces(Pos::new(0, 0), Pos::new(1, 0));
 
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        assert_eq!(Err(SwapError::Rejected), result);
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
+    #[test]
+    fn try_swap_pieces_allowed_swaps() {
+        let mut board = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
 
-        board.trickle();
+        assert_eq!(Ok(()), board.try_swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+        assert_eq!(Piece::Regular('s', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+    fn horizontal_three(piece_type: char) -> MatchPattern {
+        let spaces = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        MatchPattern::new(piece_type, spaces.into_iter().collect(), 1)
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+    fn vertical_three(piece_type: char) -> MatchPattern {
+        let spaces = vec![Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2)];
+        MatchPattern::new(piece_type, spaces.into_iter().collect(), 1)
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+    #[test]
+    fn can_any_pattern_still_match_enough_movable_pieces_true() {
+        let mut board = Board::new(BoardState::new(4, 4), vec![horizontal_three('f')], Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(3, 2), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 3), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+
+        assert!(board.can_any_pattern_still_match());
     }
 
     #[test]
-    fn trickle_with_diagonals_piece_replaced_with_more_movable_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
+    fn can_any_pattern_still_match_too_few_pieces_false() {
+        let mut board = Board::new(BoardState::new(4, 4), vec![horizontal_three('f')], Vec::new());
+        for x in 0..4 {
+            for y in 0..4 {
+                board.set_piece(Pos::new(x, y), Piece::Regular('s', ALL_DIRECTIONS));
+            }
+        }
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(3, 3), Piece::Regular('f', ALL_DIRECTIONS));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        assert!(!board.can_any_pattern_still_match());
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
+    #[test]
+    fn can_any_pattern_still_match_no_patterns_false() {
+        let mut board = Board::new(BoardState::new(4, 4), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
 
-        board.set_piece(Pos::new(2, 0), piece1);
+        assert!(!board.can_any_pattern_still_match());
+    }
 
-        board.set_piece(Pos::new(2, 1), piece2);
-        board.set_piece(Pos::new(2, 1), piece1);
+    #[test]
+    fn can_any_pattern_still_match_walls_block_every_row_false() {
+        let mut board = Board::new(BoardState::new(3, 3), vec![horizontal_three('f')], Vec::new());
+        for y in 0..3 {
+            board.set_piece(Pos::new(0, y), Piece::Regular('f', ALL_DIRECTIONS));
+        }
 
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        assert!(!board.can_any_pattern_still_match());
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
+    #[test]
+    fn can_any_pattern_still_match_unmovable_pieces_cannot_fill_false() {
+        let mut board = Board::new(BoardState::new(3, 2), vec![horizontal_three('f')], Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Regular('f', EnumSet::new()));
+        board.set_piece(Pos::new(1, 1), Piece::Regular('f', EnumSet::new()));
 
-        let expected_moves: Vec<(Pos, Pos)> = vec![
-            (Pos::new(2, 1), Pos::new(3, 0)),
-            (Pos::new(1, 2), Pos::new(2, 1))
-        ];
-        assert_eq!(expected_moves, board.trickle());
+        assert!(!board.can_any_pattern_still_match());
     }
 
     #[test]
-    fn trickle_with_diagonals_piece_replaced_with_less_movable_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
+    fn can_any_pattern_still_match_tagged_types_count_true() {
+        let pattern = horizontal_three('f').with_tag("wild");
+        let mut board = Board::new(BoardState::new(3, 1), vec![pattern], Vec::new());
+        board.add_tag('w', "wild");
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('w', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('f', ALL_DIRECTIONS));
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        assert!(board.can_any_pattern_still_match());
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
+    #[test]
+    fn inject_garbage_pushes_pieces_up() {
+        let piece = Piece::Regular('f', ALL_DIRECTIONS);
+        let mut board = Board::new(BoardState::new(2, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), piece);
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
+        board.set_counter(Pos::new(0, 0), 4);
 
-        board.set_piece(Pos::new(2, 0), piece1);
+        assert!(!board.inject_garbage(1, Piece::Wall));
 
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 1), piece2);
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 0)));
+        assert_eq!(piece, board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(Some(4), board.counter(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+    }
 
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
+    #[test]
+    fn inject_garbage_pieces_at_top_overflow() {
+        let mut board = Board::new(BoardState::new(2, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(1, 1), Piece::Regular('f', ALL_DIRECTIONS));
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        assert!(board.inject_garbage(1, Piece::Regular('g', ALL_DIRECTIONS)));
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Regular('g', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+        assert_eq!(0, board.count('f'));
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece2, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+    #[test]
+    fn inject_garbage_more_rows_than_height_fills_board() {
+        let mut board = Board::new(BoardState::new(2, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
 
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert!(board.inject_garbage(5, Piece::Regular('g', ALL_DIRECTIONS)));
+
+        assert_eq!(4, board.count('g'));
     }
 
     #[test]
-    fn trickle_with_diagonals_piece_replaced_with_less_movable_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
+    fn inject_garbage_marks_every_position() {
+        let mut board = Board::new(BoardState::new(3, 1), vec![
+            MatchPattern::new('g', vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)].into_iter().collect(), 1)
+        ], Vec::new());
+        while board.next_match().is_some() {}
 
-        let mut board = Board::new(BoardState::new(16, 16), 
-                                   Vec::new(), Vec::new());
+        board.inject_garbage(1, Piece::Regular('g', ALL_DIRECTIONS));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        assert!(board.next_match().is_some());
+    }
 
-        board.set_piece(Pos::new(2, 0), piece1);
+    #[test]
+    fn inject_garbage_zero_rows_no_change() {
+        let mut board = Board::new(BoardState::new(2, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(1, 1), Piece::Regular('f', ALL_DIRECTIONS));
 
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 1), piece2);
+        assert!(!board.inject_garbage(0, Piece::Empty));
 
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(1, 1)));
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
+    fn open_board(width: u8, height: u8) -> Board {
+        open_board_with_patterns(width, height, Vec::new())
+    }
 
-        let expected_moves: Vec<(Pos, Pos)> = vec![];
-        assert_eq!(expected_moves, board.trickle());
+    fn open_board_with_patterns(width: u8, height: u8, patterns: Vec<MatchPattern>) -> Board {
+        let mut board = Board::new(BoardState::new(width, height), patterns, Vec::new());
+        for y in 0..height {
+            for x in 0..width {
+                board.set_piece(Pos::new(x, y), Piece::Empty);
+            }
+        }
+        board
     }
 
     #[test]
-    fn add_trickle_no_diagonals_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn lock_region_corners_any_order_locks_rect() {
+        let mut board = open_board(4, 4);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        board.lock_region(Pos::new(2, 2), Pos::new(1, 1));
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        let locked: Vec<Pos> = (0..4).flat_map(|y| (0..4).map(move |x| Pos::new(x, y)))
+            .filter(|&pos| board.is_locked(pos))
+            .collect();
+        assert_eq!(vec![Pos::new(1, 1), Pos::new(2, 1), Pos::new(1, 2), Pos::new(2, 2)], locked);
+    }
 
-        board.add_and_trickle(Pos::new(0, 4), piece1);
+    #[test]
+    fn lock_region_outside_board_ignored() {
+        let mut board = open_board(2, 2);
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+        board.lock_region(Pos::new(1, 1), Pos::new(9, 9));
+
+        assert!(board.is_locked(Pos::new(1, 1)));
+        assert!(!board.is_locked(Pos::new(0, 1)));
     }
 
     #[test]
-    fn add_trickle_no_diagonals_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn unlock_region_unlocks_rect() {
+        let mut board = open_board(3, 1);
+        board.lock_region(Pos::new(0, 0), Pos::new(2, 0));
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        board.unlock_region(Pos::new(1, 0), Pos::new(2, 0));
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        assert!(board.is_locked(Pos::new(0, 0)));
+        assert!(!board.is_locked(Pos::new(1, 0)));
+        assert!(!board.is_locked(Pos::new(2, 0)));
+    }
 
-        let expected_moves = vec![
-            (Pos::new(0, 4), Pos::new(0, 1))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(0, 4), piece1));
+    #[test]
+    #[should_panic]
+    fn is_locked_outside_board_panics() {
+        open_board(2, 2).is_locked(Pos::new(2, 0));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_left_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn swap_pieces_locked_space_not_swapped() {
+        let mut board = open_board(2, 1);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        board.lock_region(Pos::new(1, 0), Pos::new(1, 0));
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert!(!board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+        assert!(board.preview_swap(Pos::new(0, 0), Pos::new(1, 0)).is_none());
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+    #[test]
+    fn swap_pieces_frozen_piece_not_swapped() {
+        let mut board = open_board(2, 1);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        board.freeze(Pos::new(1, 0), 2);
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert!(!board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn freeze_zero_turns_thaws() {
+        let mut board = open_board(2, 1);
+        board.freeze(Pos::new(1, 0), 2);
 
-        board.add_and_trickle(Pos::new(1, 4), piece1);
+        board.freeze(Pos::new(1, 0), 0);
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+        assert_eq!(None, board.frozen_turns(Pos::new(1, 0)));
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    #[test]
+    #[should_panic]
+    fn freeze_outside_board_panics() {
+        open_board(2, 2).freeze(Pos::new(0, 2), 1);
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    #[test]
+    fn thaw_frozen_returns_turns_left() {
+        let mut board = open_board(2, 1);
+        board.freeze(Pos::new(1, 0), 2);
+
+        assert_eq!(Some(2), board.thaw(Pos::new(1, 0)));
+        assert_eq!(None, board.thaw(Pos::new(1, 0)));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_left_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn swap_pieces_other_frozen_piece_unaffected() {
+        let mut board = open_board(3, 1);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('t', ALL_DIRECTIONS));
+        board.freeze(Pos::new(2, 0), 2);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert!(board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        assert_eq!(Some(2), board.frozen_turns(Pos::new(2, 0)));
+        assert_eq!(None, board.frozen_turns(Pos::new(0, 0)));
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn trickle_locked_piece_does_not_fall() {
+        let mut board = open_board(1, 3);
+        board.set_piece(Pos::new(0, 2), Piece::Regular('f', ALL_DIRECTIONS));
+        board.lock_region(Pos::new(0, 2), Pos::new(0, 2));
+
+        assert!(board.trickle().is_empty());
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
+    }
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn trickle_locked_empty_space_not_filled() {
+        let mut board = open_board(1, 3);
+        board.set_piece(Pos::new(0, 2), Piece::Regular('f', ALL_DIRECTIONS));
+        board.lock_region(Pos::new(0, 0), Pos::new(0, 0));
 
-        let expected_moves = vec![
-            (Pos::new(1, 4), Pos::new(1, 2)),
-            (Pos::new(1, 2), Pos::new(0, 1)),
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 4), piece1));
+        board.trickle();
+
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
+        assert!(board.is_locked(Pos::new(0, 0)));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_right_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn trickle_after_unlock_piece_falls() {
+        let mut board = open_board(1, 3);
+        board.set_piece(Pos::new(0, 2), Piece::Regular('f', ALL_DIRECTIONS));
+        board.lock_region(Pos::new(0, 2), Pos::new(0, 2));
+        board.trickle();
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        board.unlock_region(Pos::new(0, 2), Pos::new(0, 2));
+        board.trickle();
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn step_locked_piece_does_not_fall() {
+        let mut board = open_board(1, 3);
+        board.set_piece(Pos::new(0, 2), Piece::Regular('f', ALL_DIRECTIONS));
+        board.lock_region(Pos::new(0, 1), Pos::new(0, 1));
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        assert!(board.step(3).is_empty());
+    }
 
-        board.set_piece(Pos::new(3, 0), piece1);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+    #[test]
+    fn shuffle_locked_pieces_stay() {
+        let mut board = open_board(4, 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                let piece_type = if (x + y) % 2 == 0 { 'f' } else { 's' };
+                board.set_piece(Pos::new(x, y), Piece::Regular(piece_type, ALL_DIRECTIONS));
+            }
+        }
+        board.lock_region(Pos::new(0, 0), Pos::new(3, 1));
 
-        board.add_and_trickle(Pos::new(2, 4), piece1);
+        board.shuffle();
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+        for x in 0..4 {
+            assert_eq!(Piece::Regular(if x % 2 == 0 { 'f' } else { 's' }, ALL_DIRECTIONS), board.piece(Pos::new(x, 0)));
+        }
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    #[test]
+    fn shuffle_frozen_pieces_stay() {
+        let mut board = open_board(4, 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                let piece_type = if (x + y) % 2 == 0 { 'f' } else { 's' };
+                board.set_piece(Pos::new(x, y), Piece::Regular(piece_type, ALL_DIRECTIONS));
+            }
+        }
+        for x in 0..4 {
+            board.freeze(Pos::new(x, 0), 1);
+        }
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+        board.shuffle();
 
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+        for x in 0..4 {
+            assert_eq!(Piece::Regular(if x % 2 == 0 { 'f' } else { 's' }, ALL_DIRECTIONS), board.piece(Pos::new(x, 0)));
+            assert_eq!(Some(1), board.frozen_turns(Pos::new(x, 0)));
+        }
     }
 
     #[test]
-    fn add_trickle_with_diagonals_right_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn rotate_90_locked_spaces_rotate() {
+        let mut board = open_board(2, 3);
+        board.lock_region(Pos::new(0, 0), Pos::new(0, 0));
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        board.rotate_90();
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        assert!(board.is_locked(Pos::new(0, 1)));
+        assert!(!board.is_locked(Pos::new(0, 0)));
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    fn cluster_board(patterns: Vec<MatchPattern>) -> Board {
+        let mut board = open_board_with_patterns(4, 3, patterns);
+        let rows = ["ffss", "fsst", "ttsf"];
+        for (row_index, row) in rows.iter().enumerate() {
+            for (x, piece_type) in row.chars().enumerate() {
+                board.set_piece(Pos::new(x as u8, 2 - row_index as u8), Piece::Regular(piece_type, ALL_DIRECTIONS));
+            }
+        }
+        board
+    }
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn next_match_cluster_pattern_finds_whole_group() {
+        let mut board = cluster_board(vec![MatchPattern::cluster('s', 4, 1)]);
 
-        board.set_piece(Pos::new(3, 0), piece1);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        let next_match = board.next_match().unwrap();
 
-        let expected_moves = vec![
-            (Pos::new(2, 4), Pos::new(2, 2)),
-            (Pos::new(2, 2), Pos::new(3, 1))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(2, 4), piece1));
+        let expected: HashSet<Pos> = vec![Pos::new(2, 0), Pos::new(1, 1), Pos::new(2, 1), Pos::new(2, 2), Pos::new(3, 2)]
+            .into_iter().collect();
+        assert_eq!(&expected, next_match.board_pos());
+        assert_eq!('s', next_match.piece_type());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_ambiguous_sets_board_left_preferred() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn next_match_cluster_too_small_no_match() {
+        let mut board = cluster_board(vec![MatchPattern::cluster('f', 4, 1)]);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert!(board.next_match().is_none());
+    }
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+    #[test]
+    fn next_match_any_cluster_reports_type() {
+        let mut board = cluster_board(vec![MatchPattern::any_cluster(3, 1)]);
+        board.set_piece(Pos::new(2, 1), Piece::Regular('g', ALL_DIRECTIONS));
+        while board.next_match().is_some() {}
+        board.set_piece(Pos::new(0, 1), Piece::Regular('f', ALL_DIRECTIONS));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        let next_match = board.next_match().unwrap();
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        assert_eq!('f', next_match.piece_type());
+        assert_eq!(3, next_match.board_pos().len());
+    }
 
-        board.set_piece(Pos::new(3, 0), piece1);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+    #[test]
+    fn next_match_tagged_cluster_includes_tagged_types() {
+        let mut board = cluster_board(vec![MatchPattern::cluster('t', 3, 1).with_tag("wild")]);
+        board.add_tag('f', "wild");
+        while board.next_match().is_some() {}
+        board.set_piece(Pos::new(0, 0), Piece::Regular('t', ALL_DIRECTIONS));
 
-        board.add_and_trickle(Pos::new(2, 4), piece1);
+        let next_match = board.next_match().unwrap();
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+        assert_eq!(5, next_match.board_pos().len());
+        assert_eq!('t', next_match.piece_type());
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    #[test]
+    fn preview_swap_cluster_pattern_finds_group() {
+        let mut board = cluster_board(vec![MatchPattern::cluster('t', 3, 1)]);
+        board.set_piece(Pos::new(2, 1), Piece::Regular('t', ALL_DIRECTIONS));
+        while board.next_match().is_some() {}
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+        let matches = board.preview_swap(Pos::new(2, 0), Pos::new(2, 1)).unwrap();
 
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+        assert_eq!(1, matches.len());
+        assert_eq!(3, matches[0].board_pos().len());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_ambiguous_generates_moves_left_preferred() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn can_any_pattern_still_match_cluster_enough_pieces_true() {
+        let board = cluster_board(vec![MatchPattern::cluster('t', 3, 1)]);
+        assert!(board.can_any_pattern_still_match());
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        let board = cluster_board(vec![MatchPattern::cluster('t', 4, 1)]);
+        assert!(!board.can_any_pattern_still_match());
+    }
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+    fn path_board() -> Board {
+        path_board_with_patterns(Vec::new())
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    fn path_board_with_patterns(patterns: Vec<MatchPattern>) -> Board {
+        let mut board = open_board_with_patterns(4, 2, patterns);
+        for (x, piece_type) in "abcd".chars().enumerate() {
+            board.set_piece(Pos::new(x as u8, 0), Piece::Regular(piece_type, ALL_DIRECTIONS));
+        }
+        while board.next_match().is_some() {}
+        board
+    }
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn move_piece_along_path_shifts_displaced_pieces_back() {
+        let mut board = path_board();
 
-        board.set_piece(Pos::new(3, 0), piece1);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        assert_eq!(Ok(()), board.move_piece_along(&[Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0), Pos::new(2, 1)]));
 
-        let expected_moves = vec![
-            (Pos::new(2, 4), Pos::new(2, 2)),
-            (Pos::new(2, 2), Pos::new(1, 1))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(2, 4), piece1));
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Regular('c', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Regular('d', ALL_DIRECTIONS), board.piece(Pos::new(3, 0)));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_blocking_wall_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn move_piece_along_short_path_no_change() {
+        let mut board = path_board();
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert_eq!(Ok(()), board.move_piece_along(&[Pos::new(0, 0)]));
+        assert_eq!(Ok(()), board.move_piece_along(&[]));
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert!(board.next_match().is_none());
+    }
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Wall);
-        board.set_piece(Pos::new(0, 4), piece1);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+    #[test]
+    fn move_piece_along_not_adjacent_error() {
+        let mut board = path_board();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Wall);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        let result = board.move_piece_along(&[Pos::new(0, 0), Pos::new(1, 1)]);
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Wall);
-        board.set_piece(Pos::new(2, 4), piece1);
-        board.set_piece(Pos::new(2, 5), piece1);
+        assert_eq!(Err(SwapError::NotAdjacent(Pos::new(0, 0), Pos::new(1, 1))), result);
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn move_piece_along_outside_board_error() {
+        let mut board = path_board();
+
+        let result = board.move_piece_along(&[Pos::new(3, 0), Pos::new(4, 0)]);
+
+        assert_eq!(Err(SwapError::OutOfBounds(Pos::new(4, 0))), result);
+    }
+
+    #[test]
+    fn move_piece_along_rejected_step_board_unchanged() {
+        let mut board = path_board();
+        board.set_piece(Pos::new(2, 0), Piece::Regular('c', EnumSet::new()));
+        while board.next_match().is_some() {}
+        board.set_effects_enabled(true);
+
+        let result = board.move_piece_along(&[Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)]);
+
+        assert_eq!(Err(SwapError::Rejected), result);
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+        assert!(board.effects().is_empty());
+        assert!(board.next_match().is_none());
+    }
 
-        board.set_piece(Pos::new(3, 0), piece1);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Wall);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+    #[test]
+    fn move_piece_along_rejected_step_turn_report_unchanged() {
+        let mut board = path_board();
+        board.set_piece(Pos::new(2, 0), Piece::Regular('c', EnumSet::new()));
+        while board.next_match().is_some() {}
+        assert!(board.swap_pieces(Pos::new(3, 0), Pos::new(3, 1)));
 
-        board.add_and_trickle(Pos::new(1, 5), piece1);
+        let result = board.move_piece_along(&[Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)]);
 
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 3)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+        assert_eq!(Err(SwapError::Rejected), result);
+        let report = board.end_turn();
+        assert_eq!(1, report.swaps());
+        assert_eq!(0, report.rejected_swaps());
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 3)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    #[test]
+    fn rotate_pieces_clockwise_moves_to_next_position() {
+        let mut board = path_board();
+        let positions = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(1, 1)];
+        board.set_piece(Pos::new(1, 1), Piece::Regular('e', ALL_DIRECTIONS));
+        while board.next_match().is_some() {}
 
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(2, 3)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 4)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 5)));
+        assert!(board.rotate_pieces(positions, true));
 
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+        assert_eq!(Piece::Regular('e', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(1, 1)));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_blocking_wall_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn rotate_pieces_counterclockwise_moves_to_previous_position() {
+        let mut board = path_board();
+        let positions = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(1, 1)];
+        board.set_piece(Pos::new(1, 1), Piece::Regular('e', ALL_DIRECTIONS));
+        while board.next_match().is_some() {}
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert!(board.rotate_pieces(positions, false));
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Wall);
-        board.set_piece(Pos::new(0, 4), piece1);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Regular('e', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(1, 1)));
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Wall);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn rotate_pieces_marks_all_positions() {
+        let mut board = path_board_with_patterns(vec![MatchPattern::any_cluster(1, 1)]);
+        board.set_piece(Pos::new(1, 1), Piece::Regular('e', ALL_DIRECTIONS));
+        while board.next_match().is_some() {}
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Wall);
-        board.set_piece(Pos::new(2, 4), piece1);
-        board.set_piece(Pos::new(2, 5), piece1);
+        assert!(board.rotate_pieces([Pos::new(0, 0), Pos::new(1, 0), Pos::new(1, 1)], true));
 
-        board.set_piece(Pos::new(3, 0), piece1);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Wall);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        let mut checked = HashSet::new();
+        while let Some(next_match) = board.next_match() {
+            checked.insert(next_match.changed_pos());
+        }
+        assert_eq!(vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(1, 1)].into_iter().collect::<HashSet<Pos>>(), checked);
+    }
 
-        let expected_moves = vec![
-            (Pos::new(1, 5), Pos::new(1, 4))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 5), piece1));
+    #[test]
+    fn rotate_pieces_records_moves() {
+        let mut board = path_board();
+        board.set_effects_enabled(true);
+
+        assert!(board.rotate_pieces([Pos::new(0, 0), Pos::new(1, 0), Pos::new(1, 1)], true));
+
+        assert_eq!(&[
+            Effect::Moved { from: Pos::new(0, 0), to: Pos::new(1, 0) },
+            Effect::Moved { from: Pos::new(1, 0), to: Pos::new(1, 1) }
+        ], board.effects());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_through_hole_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn rotate_pieces_unmovable_piece_not_rotated() {
+        let mut board = path_board();
+        board.set_piece(Pos::new(1, 0), Piece::Regular('b', enum_set!(Direction::West)));
+        let positions = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(1, 1)];
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert!(!board.rotate_pieces(positions, true));
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Wall);
-        board.set_piece(Pos::new(0, 5), piece1);
+        assert!(board.rotate_pieces(positions, false));
+        assert_eq!(Piece::Regular('b', enum_set!(Direction::West)), board.piece(Pos::new(0, 0)));
+    }
 
-        board.set_piece(Pos::new(1, 0), Piece::Empty);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Wall);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn rotate_pieces_locked_or_repeated_not_rotated() {
+        let mut board = path_board();
 
-        board.set_piece(Pos::new(2, 0), piece1);
+        assert!(!board.rotate_pieces([Pos::new(0, 0), Pos::new(1, 0), Pos::new(0, 0)], true));
+
+        board.lock_region(Pos::new(1, 1), Pos::new(1, 1));
+        assert!(!board.rotate_pieces([Pos::new(0, 0), Pos::new(1, 0), Pos::new(1, 1)], true));
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn rotate_pieces_outside_board_panics() {
+        let mut board = path_board();
+        let _ = board.rotate_pieces([Pos::new(0, 0), Pos::new(1, 0), Pos::new(1, 2)], true);
+    }
+
+    #[test]
+    fn pending_match_checks_in_marked_order() {
+        let mut board = open_board(3, 3);
+        board.set_piece(Pos::new(2, 1), Piece::Regular('a', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        while board.next_match().is_some() {}
         board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        board.mark_for_match_check(Pos::new(0, 0));
+        board.mark_for_match_check(Pos::new(2, 1));
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Wall);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        assert_eq!(vec![Pos::new(2, 1), Pos::new(0, 0)], board.pending_match_checks().collect::<Vec<Pos>>());
+    }
 
-        board.add_and_trickle(Pos::new(1, 5), piece1);
+    #[test]
+    fn mark_for_match_check_unchanged_piece_matches() {
+        let mut board = open_board_with_patterns(3, 1, vec![horizontal_three('a')]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        }
+        board.clear_pending();
+        assert!(board.next_match().is_none());
 
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 4)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 5)));
+        board.mark_for_match_check(Pos::new(1, 0));
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert_eq!(Pos::new(1, 0), board.next_match().unwrap().changed_pos());
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    #[test]
+    #[should_panic]
+    fn mark_for_match_check_outside_board_panics() {
+        let mut board = open_board(3, 1);
+        board.mark_for_match_check(Pos::new(0, 1));
+    }
 
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+    #[test]
+    fn clear_pending_unmarks_all_positions() {
+        let mut board = open_board(3, 1);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('a', ALL_DIRECTIONS));
+
+        board.clear_pending();
+
+        assert_eq!(0, board.pending_match_checks().count());
+        board.mark_for_match_check(Pos::new(0, 0));
+        assert_eq!(vec![Pos::new(0, 0)], board.pending_match_checks().collect::<Vec<Pos>>());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_through_hole_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn pending_match_checks_trickle_no_duplicates() {
+        let mut board = open_board(2, 5);
+        for y in 2..5 {
+            board.set_piece(Pos::new(0, y), Piece::Regular('a', ALL_DIRECTIONS));
+            board.set_piece(Pos::new(1, y), Piece::Regular('b', ALL_DIRECTIONS));
+        }
+        board.clear_pending();
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        board.trickle();
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Wall);
-        board.set_piece(Pos::new(0, 5), piece1);
+        let pending: Vec<Pos> = board.pending_match_checks().collect();
+        let unique: HashSet<Pos> = pending.iter().copied().collect();
+        assert_eq!(unique.len(), pending.len());
+        assert!(unique.contains(&Pos::new(0, 0)) && unique.contains(&Pos::new(1, 2)));
+    }
 
-        board.set_piece(Pos::new(1, 0), Piece::Empty);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Wall);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn next_match_variant_past_right_edge_does_not_wrap() {
+        let mut board = open_board_with_patterns(3, 2, vec![horizontal_three('a')]);
+        board.set_piece(Pos::new(1, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 1), Piece::Regular('a', ALL_DIRECTIONS));
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        assert!(board.next_match().is_none());
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Wall);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+    #[test]
+    fn next_match_variant_past_top_edge_no_match() {
+        let spaces = vec![Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2)];
+        let mut board = open_board_with_patterns(1, 2, vec![MatchPattern::new('a', spaces.into_iter().collect(), 1)]);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 1), Piece::Regular('a', ALL_DIRECTIONS));
 
-        let expected_moves = vec![
-            (Pos::new(1, 5), Pos::new(2, 4)),
-            (Pos::new(2, 4), Pos::new(2, 1)),
-            (Pos::new(2, 1), Pos::new(1, 0))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 5), piece1));
+        assert!(board.next_match().is_none());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_changing_directions_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn next_match_variant_touching_edges_matches_on_board() {
+        let mut board = open_board_with_patterns(4, 4, vec!["a.\naa".parse().unwrap()]);
+        board.set_piece(Pos::new(2, 2), Piece::Regular('a', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(3, 2), Piece::Regular('a', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 3), Piece::Regular('a', ALL_DIRECTIONS));
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        let next_match = board.next_match().unwrap();
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Wall);
-        board.set_piece(Pos::new(0, 5), piece1);
+        let expected: HashSet<Pos> = vec![Pos::new(2, 2), Pos::new(3, 2), Pos::new(2, 3)].into_iter().collect();
+        assert_eq!(&expected, next_match.board_pos());
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Wall);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn next_match_anchored_row_only_matches_in_row() {
+        let mut board = open_board_with_patterns(3, 2, vec![horizontal_three('a').with_anchor(PatternAnchor::Row(0))]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 1), Piece::Regular('a', ALL_DIRECTIONS));
+        }
+        assert!(board.next_match().is_none());
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        }
+        let next_match = board.next_match().unwrap();
+        assert!(next_match.board_pos().iter().all(|pos| pos.y() == 0));
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Wall);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+    #[test]
+    fn next_match_anchored_cell_picks_variant_with_cell() {
+        let mut board = open_board_with_patterns(4, 1, vec![horizontal_three('a').with_anchor(PatternAnchor::Cell(Pos::new(3, 0)))]);
+        for x in 0..4 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        }
 
-        board.add_and_trickle(Pos::new(3, 5), piece1);
+        let next_match = board.next_match().unwrap();
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 4)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 5)));
+        assert_eq!(Pos::new(1, 0), next_match.changed_pos());
+        let expected: HashSet<Pos> = vec![Pos::new(1, 0), Pos::new(2, 0), Pos::new(3, 0)].into_iter().collect();
+        assert_eq!(&expected, next_match.board_pos());
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    #[test]
+    fn next_match_anchored_cluster_requires_anchor() {
+        let mut board = cluster_board(vec![MatchPattern::cluster('s', 4, 1).with_anchor(PatternAnchor::Column(0))]);
+        assert!(board.next_match().is_none());
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+        let mut board = cluster_board(vec![MatchPattern::cluster('s', 4, 1).with_anchor(PatternAnchor::Column(3))]);
+        assert_eq!(5, board.next_match().unwrap().board_pos().len());
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+    fn two_row_board() -> Board {
+        two_row_board_with_patterns(vec![horizontal_three('a')])
+    }
+
+    fn two_row_board_with_patterns(patterns: Vec<MatchPattern>) -> Board {
+        let mut board = open_board_with_patterns(3, 2, patterns);
+        for y in (0..2).rev() {
+            for x in 0..3 {
+                board.set_piece(Pos::new(x, y), Piece::Regular('a', ALL_DIRECTIONS));
+            }
+        }
+        board
     }
 
     #[test]
-    fn add_trickle_with_diagonals_changing_directions_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn next_match_in_only_finds_matches_inside_region() {
+        let mut board = two_row_board();
+        let region: PosSet = (0..3).map(|x| Pos::new(x, 0)).collect();
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        while let Some(next_match) = board.next_match_in(&region) {
+            assert!(next_match.board_pos().iter().all(|pos| region.contains(pos)));
+        }
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Wall);
-        board.set_piece(Pos::new(0, 5), piece1);
+        assert_eq!(vec![Pos::new(0, 1), Pos::new(1, 1), Pos::new(2, 1)],
+                   board.pending_match_checks().collect::<Vec<Pos>>());
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Wall);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn next_match_in_match_partly_outside_region_not_found() {
+        let mut board = two_row_board();
+        let region: PosSet = vec![Pos::new(0, 1), Pos::new(1, 1)].into_iter().collect();
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        assert!(board.next_match_in(&region).is_none());
+        assert_eq!(vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0), Pos::new(2, 1)],
+                   board.pending_match_checks().collect::<Vec<Pos>>());
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Wall);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+    #[test]
+    fn next_match_in_by_row_lowest_row_in_region_first() {
+        let mut board = two_row_board();
+        board.set_match_order(MatchOrder::ByRow);
+        let region: PosSet = vec![Pos::new(2, 1), Pos::new(1, 1), Pos::new(0, 1)].into_iter().collect();
 
-        let expected_moves = vec![
-            (Pos::new(3, 5), Pos::new(2, 4)),
-            (Pos::new(2, 4), Pos::new(2, 1)),
-            (Pos::new(2, 1), Pos::new(3, 0))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(3, 5), piece1));
+        assert_eq!(Pos::new(0, 1), board.next_match_in(&region).unwrap().changed_pos());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_west_wall_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn next_match_in_by_rank_leaves_outside_positions_marked() {
+        let mut board = two_row_board();
+        board.set_match_order(MatchOrder::ByRank);
+        let region: PosSet = (0..3).map(|x| Pos::new(x, 0)).collect();
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert_eq!(Pos::new(0, 0), board.next_match_in(&region).unwrap().changed_pos());
+        assert_eq!(vec![Pos::new(1, 0), Pos::new(2, 0), Pos::new(0, 1), Pos::new(1, 1), Pos::new(2, 1)],
+                   board.pending_match_checks().collect::<Vec<Pos>>());
+    }
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Wall);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+    #[test]
+    fn convert_pieces_whole_board_converts_all_of_type() {
+        let mut board = two_row_board();
+        board.set_piece(Pos::new(1, 1), Piece::Regular('b', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('a', enum_set!(Direction::North)));
+        board.clear_pending();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(5, board.convert_pieces('a', 'c', None));
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Regular('c', enum_set!(Direction::North)), board.piece(Pos::new(2, 0)));
+        assert_eq!(Piece::Regular('c', ALL_DIRECTIONS), board.piece(Pos::new(0, 1)));
+        assert_eq!(5, board.pending_match_checks().count());
+    }
 
-        board.set_piece(Pos::new(3, 0), piece1);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+    #[test]
+    fn convert_pieces_region_only_converts_inside() {
+        let mut board = two_row_board();
+        let region: PosSet = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(5, 5)].into_iter().collect();
 
-        board.add_and_trickle(Pos::new(1, 5), piece1);
+        assert_eq!(2, board.convert_pieces('a', 'b', Some(&region)));
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(2, 0)));
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+    #[test]
+    fn convert_pieces_converted_pieces_match() {
+        let mut board = two_row_board_with_patterns(vec![horizontal_three('b')]);
+        board.clear_pending();
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+        board.convert_pieces('a', 'b', None);
 
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+        assert_eq!('b', board.next_match().unwrap().piece_type());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_west_wall_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
-
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Wall);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+    fn convert_pieces_same_or_missing_type_converts_nothing() {
+        let mut board = two_row_board();
+        board.clear_pending();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(0, board.convert_pieces('a', 'a', None));
+        assert_eq!(0, board.convert_pieces('z', 'a', None));
+        assert_eq!(0, board.pending_match_checks().count());
+    }
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn convert_pieces_records_conversions() {
+        let mut board = two_row_board();
+        board.set_effects_enabled(true);
+        let region: PosSet = vec![Pos::new(2, 1)].into_iter().collect();
 
-        board.set_piece(Pos::new(3, 0), piece1);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        board.convert_pieces('a', 'b', Some(&region));
 
-        let expected_moves = vec![
-            (Pos::new(1, 5), Pos::new(1, 2)),
-            (Pos::new(1, 2), Pos::new(0, 1))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 5), piece1));
+        assert_eq!(&[Effect::Converted {
+            pos: Pos::new(2, 1),
+            from: Piece::Regular('a', ALL_DIRECTIONS),
+            to: Piece::Regular('b', ALL_DIRECTIONS)
+        }], board.effects());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_east_wall_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn convert_pieces_partly_converted_block_split() {
+        let mut board = open_board(4, 1);
+        board.place_block(Pos::new(0, 0), 2, 1, 'a');
+        board.place_block(Pos::new(2, 0), 2, 1, 'a');
+        let region: PosSet = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)].into_iter().collect();
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert_eq!(3, board.convert_pieces('a', 'b', Some(&region)));
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        assert_eq!(1, board.blocks().len());
+        assert_eq!(Pos::new(0, 0), board.blocks()[0].anchor());
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn prevent_matches_in_region_corners_any_order_prevents_rect() {
+        let mut board = open_board(3, 3);
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        board.prevent_matches_in_region(Pos::new(2, 2), Pos::new(1, 2));
 
-        board.set_piece(Pos::new(3, 0), piece1);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Wall);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        let prevented: Vec<Pos> = (0..3).flat_map(|y| (0..3).map(move |x| Pos::new(x, y)))
+            .filter(|&pos| board.is_match_prevented(pos))
+            .collect();
+        assert_eq!(vec![Pos::new(1, 2), Pos::new(2, 2)], prevented);
+    }
 
-        board.add_and_trickle(Pos::new(2, 5), piece1);
+    #[test]
+    #[should_panic]
+    fn is_match_prevented_outside_board_panics() {
+        open_board(2, 2).is_match_prevented(Pos::new(0, 2));
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+    #[test]
+    fn next_match_includes_prevented_space_no_match() {
+        let mut board = open_board_with_patterns(3, 2, vec![horizontal_three('a')]);
+        board.prevent_matches_in_region(Pos::new(2, 0), Pos::new(2, 0));
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert!(board.next_match().is_none());
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(2, 0)));
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    #[test]
+    fn next_match_outside_prevented_region_matches() {
+        let mut board = open_board_with_patterns(3, 2, vec![horizontal_three('a')]);
+        board.prevent_matches_in_region(Pos::new(0, 1), Pos::new(2, 1));
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('a', ALL_DIRECTIONS));
+            board.set_piece(Pos::new(x, 1), Piece::Regular('a', ALL_DIRECTIONS));
+        }
 
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Wall, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 5)));
+        let expected: HashSet<Pos> = (0..3).map(|x| Pos::new(x, 0)).collect();
+        while let Some(next_match) = board.next_match() {
+            assert_eq!(&expected, next_match.board_pos());
+        }
     }
 
     #[test]
-    fn add_trickle_with_diagonals_east_wall_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn next_match_allowed_again_matches() {
+        let mut board = open_board_with_patterns(3, 1, vec![horizontal_three('a')]);
+        board.prevent_matches_in_region(Pos::new(0, 0), Pos::new(2, 0));
+        board.allow_matches_in_region(Pos::new(0, 0), Pos::new(2, 0));
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        }
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert!(board.next_match().is_some());
+    }
 
-        board.set_piece(Pos::new(0, 0), piece1);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+    #[test]
+    fn next_match_cluster_stops_at_prevented_spaces() {
+        let mut board = cluster_board(vec![MatchPattern::cluster('s', 4, 1)]);
+        board.prevent_matches_in_region(Pos::new(0, 2), Pos::new(3, 2));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert!(board.next_match().is_none());
+    }
 
-        board.set_piece(Pos::new(2, 0), piece1);
-        board.set_piece(Pos::new(2, 1), piece1);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn trickle_prevented_spaces_not_marked() {
+        let mut board = open_board(1, 4);
+        board.set_piece(Pos::new(0, 3), Piece::Regular('a', ALL_DIRECTIONS));
+        board.prevent_matches_in_region(Pos::new(0, 2), Pos::new(0, 3));
+        board.clear_pending();
 
-        board.set_piece(Pos::new(3, 0), piece1);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Wall);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
-        board.set_piece(Pos::new(3, 5), Piece::Empty);
+        board.trickle();
 
-        let expected_moves = vec![
-            (Pos::new(2, 5), Pos::new(2, 2)),
-            (Pos::new(2, 2), Pos::new(3, 1))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(2, 5), piece1));
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        let pending: Vec<Pos> = board.pending_match_checks().collect();
+        assert_eq!(vec![Pos::new(0, 1), Pos::new(0, 0)], pending);
     }
 
     #[test]
-    fn add_trickle_no_diagonals_unmovable_north_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::West
-        ));
+    fn set_staging_rows_top_rows_staging() {
+        let mut board = open_board(2, 5);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        board.set_staging_rows(2);
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(2, board.staging_rows());
+        assert_eq!(3, board.visible_height());
+        assert!(!board.is_staging(Pos::new(1, 2)));
+        assert!(board.is_staging(Pos::new(0, 3)));
+        assert!(board.is_match_prevented(Pos::new(1, 4)));
+        assert!(!board.is_match_prevented(Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn set_staging_rows_fewer_rows_matches_allowed_again() {
+        let mut board = open_board(2, 5);
+        board.set_staging_rows(2);
 
-        board.add_and_trickle(Pos::new(1, 2), piece2);
+        board.set_staging_rows(1);
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert!(!board.is_staging(Pos::new(0, 3)));
+        assert!(!board.is_match_prevented(Pos::new(0, 3)));
+        assert!(board.is_match_prevented(Pos::new(0, 4)));
     }
 
     #[test]
-    fn add_trickle_no_diagonals_unmovable_north_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::West
-        ));
+    #[should_panic]
+    fn set_staging_rows_more_than_height_panics() {
+        open_board(2, 2).set_staging_rows(3);
+    }
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+    #[test]
+    #[should_panic]
+    fn is_staging_outside_board_panics() {
+        open_board(2, 2).is_staging(Pos::new(2, 0));
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn swap_pieces_into_staging_row_not_swapped() {
+        let mut board = open_board(1, 3);
+        board.set_piece(Pos::new(0, 1), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 2), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_staging_rows(1);
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 2), piece2));
+        assert!(!board.swap_pieces(Pos::new(0, 1), Pos::new(0, 2)));
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 1)));
     }
 
     #[test]
-    fn add_trickle_no_diagonals_unmovable_south_sets_board_for_movable() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::East | Direction::West
-        ));
+    fn rotate_90_staging_rows_removed() {
+        let mut board = open_board(2, 3);
+        board.set_staging_rows(1);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        board.rotate_90();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(0, board.staging_rows());
+        assert_eq!(board.state().height, board.visible_height());
+    }
 
-        board.add_and_trickle(Pos::new(1, 2), piece2);
+    #[test]
+    fn weight_default_zero() {
+        let mut board = open_board(1, 1);
+        board.set_weight('b', 3);
+        board.set_weight('b', 0);
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert_eq!(0, board.weight('b'));
+        assert_eq!(0, board.weight('g'));
     }
 
     #[test]
-    fn add_trickle_no_diagonals_unmovable_south_generates_moves_for_movable() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::East | Direction::West
-        ));
+    fn trickle_heavy_piece_sinks_through_lighter_pieces() {
+        let mut board = open_board(1, 4);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 1), Piece::Regular('h', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 3), Piece::Regular('b', ALL_DIRECTIONS));
+        board.set_weight('b', 2);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        let moves = board.trickle();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Regular('g', ALL_DIRECTIONS), board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Regular('h', ALL_DIRECTIONS), board.piece(Pos::new(0, 2)));
+        assert_eq!(vec![
+            PieceMove::new(vec![Pos::new(0, 3), Pos::new(0, 2), Pos::new(0, 1), Pos::new(0, 0)]),
+            PieceMove::new(vec![Pos::new(0, 1), Pos::new(0, 2)]),
+            PieceMove::new(vec![Pos::new(0, 0), Pos::new(0, 1)])
+        ], moves);
+    }
 
-        let expected_moves: Vec<(Pos, Pos)> = vec![];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 2), piece2));
+    #[test]
+    fn trickle_same_weight_pieces_stay_in_order() {
+        let mut board = open_board(1, 3);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 1), Piece::Regular('b', ALL_DIRECTIONS));
+        board.set_weight('b', 2);
+        board.set_weight('g', 2);
+
+        assert!(board.trickle().is_empty());
+        assert_eq!(Piece::Regular('g', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
     }
 
     #[test]
-    fn add_trickle_no_diagonals_unmovable_east_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::West
-        ));
+    fn trickle_heavy_piece_stops_on_piece_that_cannot_fall() {
+        let mut board = open_board(1, 3);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 1), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 2), Piece::Regular('b', ALL_DIRECTIONS));
+        board.set_gravity_movable(Pos::new(0, 1), EnumSet::new());
+        board.set_weight('b', 2);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert!(board.trickle().is_empty());
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(0, 2)));
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn set_floating_false_not_floating() {
+        let mut board = open_board(1, 1);
+        board.set_floating('o', true);
+        assert!(board.is_floating('o'));
 
-        board.add_and_trickle(Pos::new(1, 2), piece2);
+        board.set_floating('o', false);
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert!(!board.is_floating('o'));
     }
 
     #[test]
-    fn add_trickle_no_diagonals_unmovable_east_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::West
-        ));
-
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+    fn trickle_floating_piece_rises_and_others_fall_under() {
+        let mut board = open_board(2, 4);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('o', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 1), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_floating('o', true);
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        let moves = board.trickle();
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 2), piece2));
+        assert_eq!(Piece::Regular('o', ALL_DIRECTIONS), board.piece(Pos::new(0, 3)));
+        assert_eq!(Piece::Regular('g', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+        assert!(moves.contains(&PieceMove::new(vec![
+            Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2), Pos::new(0, 3)
+        ])));
     }
 
     #[test]
-    fn add_trickle_no_diagonals_unmovable_west_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::East
-        ));
+    fn trickle_floating_piece_stops_under_piece() {
+        let mut board = open_board(1, 4);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('o', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 3), Piece::Wall);
+        board.set_floating('o', true);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        board.trickle();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(Piece::Regular('o', ALL_DIRECTIONS), board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
+    }
 
-        board.add_and_trickle(Pos::new(1, 2), piece2);
+    #[test]
+    fn trickle_floating_piece_not_movable_north_stays() {
+        let mut board = open_board(1, 3);
+        board.set_piece(Pos::new(0, 1), Piece::Regular('o', ALL_DIRECTIONS));
+        board.set_gravity_movable(Pos::new(0, 1), Direction::South.into());
+        board.set_floating('o', true);
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert!(board.trickle().is_empty());
+        assert_eq!(Piece::Regular('o', ALL_DIRECTIONS), board.piece(Pos::new(0, 1)));
     }
 
     #[test]
-    fn add_trickle_no_diagonals_unmovable_west_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::East
-        ));
-
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+    fn trickle_piece_falls_into_space_left_by_floating_piece() {
+        let mut board = open_board(2, 3);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('o', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 1), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Wall);
+        board.set_floating('o', true);
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        board.trickle();
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 2), piece2));
+        assert_eq!(Piece::Regular('o', ALL_DIRECTIONS), board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Regular('g', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_left_unmovable_north_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::West
-        ));
+    fn add_and_trickle_floating_piece_rises() {
+        let mut board = open_board(1, 3);
+        board.set_floating('o', true);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        let piece_move = board.add_and_trickle(Pos::new(0, 0), Piece::Regular('o', ALL_DIRECTIONS));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(Some(PieceMove::new(vec![Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2)])), piece_move);
+    }
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+    #[test]
+    fn step_floating_piece_does_not_fall() {
+        let mut board = open_board(1, 3);
+        board.set_piece(Pos::new(0, 2), Piece::Regular('o', ALL_DIRECTIONS));
+        board.set_floating('o', true);
 
-        board.add_and_trickle(Pos::new(1, 2), piece2);
+        assert!(board.step(2).is_empty());
+    }
 
-        assert_eq!(piece2, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+    #[test]
+    fn make_unmovable_one_direction_others_kept() {
+        let mut board = open_board(2, 2);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.clear_pending();
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert!(board.make_unmovable(Pos::new(0, 0), Direction::East));
+
+        let expected = Direction::North | Direction::South | Direction::West;
+        assert_eq!(Piece::Regular('f', expected), board.piece(Pos::new(0, 0)));
+        assert!(!board.gravity_movable_in(Direction::East).is_set(Pos::new(0, 0)));
+        assert_eq!(0, board.pending_match_checks().count());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_left_unmovable_north_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::West
-        ));
+    fn make_movable_one_direction_added() {
+        let mut board = open_board(2, 2);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', EnumSet::new()));
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert!(board.make_movable(Pos::new(0, 0), Direction::South));
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        assert_eq!(Piece::Regular('f', Direction::South.into()), board.piece(Pos::new(0, 0)));
+        assert!(board.gravity_movable_in(Direction::South).is_set(Pos::new(0, 0)));
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn make_movable_wall_not_changed() {
+        let mut board = open_board(2, 2);
+        board.set_piece(Pos::new(0, 0), Piece::Wall);
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 1), Pos::new(0, 0))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 2), piece2));
+        assert!(!board.make_movable(Pos::new(0, 0), Direction::South));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 0)));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_left_unmovable_south_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::East | Direction::West
-        ));
+    #[should_panic]
+    fn make_unmovable_outside_board_panics() {
+        open_board(2, 2).make_unmovable(Pos::new(2, 0), Direction::North);
+    }
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+    #[test]
+    fn set_piece_silent_not_marked() {
+        let mut board = open_board_with_patterns(3, 1, vec![horizontal_three('a')]);
+        board.clear_pending();
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        for x in 0..3 {
+            board.set_piece_silent(Pos::new(x, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+        assert_eq!(0, board.pending_match_checks().count());
+        assert!(board.next_match().is_none());
+    }
 
-        board.add_and_trickle(Pos::new(1, 2), piece2);
+    #[test]
+    fn set_piece_silent_already_marked_stays_marked() {
+        let mut board = open_board(2, 1);
+        board.clear_pending();
+        board.mark_for_match_check(Pos::new(1, 0));
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        let old_piece = board.set_piece_silent(Pos::new(1, 0), Piece::Regular('a', ALL_DIRECTIONS));
 
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+        assert_eq!(Piece::Empty, old_piece);
+        assert_eq!(vec![Pos::new(1, 0)], board.pending_match_checks().collect::<Vec<Pos>>());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_left_unmovable_south_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::East | Direction::West
-        ));
-
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+    #[should_panic]
+    fn set_piece_silent_outside_board_panics() {
+        open_board(2, 2).set_piece_silent(Pos::new(2, 0), Piece::Wall);
+    }
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+    #[test]
+    fn fill_without_matches_no_matches_and_nothing_marked() {
+        let mut board = open_board_with_patterns(8, 8, vec![horizontal_three('a'), horizontal_three('b'), vertical_three('a'), vertical_three('b')]);
+        board.clear_pending();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), piece2);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        let unavoidable = board.fill_without_matches(None, &['a', 'b', 'c'], &mut Rng::new(3));
 
-        let expected_moves: Vec<(Pos, Pos)> = vec![];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 2), piece2));
+        assert_eq!(0, unavoidable);
+        assert_eq!(0, board.count_empty());
+        assert_eq!(0, board.pending_match_checks().count());
+        for y in 0..8 {
+            for x in 0..8 {
+                board.mark_for_match_check(Pos::new(x, y));
+            }
+        }
+        assert!(board.next_match().is_none());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_left_unmovable_east_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::West
-        ));
+    fn fill_without_matches_only_region_filled() {
+        let mut board = open_board(3, 3);
+        board.set_piece(Pos::new(1, 0), Piece::Wall);
+        let region: PosSet = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 2)].into_iter().collect();
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        board.fill_without_matches(Some(&region), &['a'], &mut Rng::new(3));
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(2, 2)));
+        assert_eq!(6, board.count_empty());
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn fill_without_matches_one_type_unavoidable_counted() {
+        let mut board = open_board_with_patterns(4, 1, vec![horizontal_three('a')]);
 
-        board.add_and_trickle(Pos::new(1, 2), piece2);
+        assert_eq!(2, board.fill_without_matches(None, &['a'], &mut Rng::new(3)));
+    }
 
-        assert_eq!(piece2, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+    #[test]
+    fn fill_without_matches_same_seed_same_pieces() {
+        let fill = || {
+            let mut board = open_board_with_patterns(5, 5, vec![horizontal_three('a'), horizontal_three('b')]);
+            board.fill_without_matches(None, &['a', 'b', 'c'], &mut Rng::new(11));
+            board.state().clone()
+        };
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert_eq!(fill(), fill());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_left_unmovable_east_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::West
-        ));
+    #[should_panic]
+    fn fill_without_matches_no_types_panics() {
+        open_board(2, 2).fill_without_matches(None, &[], &mut Rng::new(3));
+    }
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+    #[test]
+    fn next_match_lifo_latest_change_first() {
+        let mut board = Board::new(BoardState::new(8, 8), vec![horizontal_three('f')], Vec::new());
+        board.set_match_order(MatchOrder::Lifo);
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(5, 5), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(6, 5), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(7, 5), Piece::Regular('f', ALL_DIRECTIONS));
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 1), Pos::new(0, 0))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 2), piece2));
+        assert_eq!(MatchOrder::Lifo, board.match_order());
+        assert_eq!(Pos::new(7, 5), board.next_match().unwrap().changed_pos());
+        assert_eq!(Pos::new(6, 5), board.next_match().unwrap().changed_pos());
+        assert_eq!(Pos::new(5, 5), board.next_match().unwrap().changed_pos());
+        assert_eq!(Pos::new(2, 0), board.next_match().unwrap().changed_pos());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_left_unmovable_west_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::North
-        ));
+    fn next_match_in_lifo_skips_latest_change_outside_region() {
+        let mut board = Board::new(BoardState::new(8, 8), vec![horizontal_three('f')], Vec::new());
+        board.set_match_order(MatchOrder::Lifo);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(5, 5), Piece::Regular('f', ALL_DIRECTIONS));
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+        let region: HashSet<Pos> = (0..3).map(|x| Pos::new(x, 0)).collect();
+        assert_eq!(Pos::new(2, 0), board.next_match_in(&region).unwrap().changed_pos());
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn new_pattern_ids_are_original_indices() {
+        let vertical = MatchPattern::new('f', vertical_three('f').spaces().clone(), 5);
+        let board = Board::new(BoardState::new(8, 8), vec![horizontal_three('f'), vertical], Vec::new());
 
-        board.add_and_trickle(Pos::new(1, 2), piece2);
+        assert_eq!(5, board.patterns()[0].rank());
+        assert_eq!(1, board.patterns()[0].id());
+        assert_eq!(0, board.patterns()[1].id());
+    }
 
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 5)));
+    #[test]
+    fn next_match_reports_rank_and_pattern_id() {
+        let vertical = MatchPattern::new('f', vertical_three('f').spaces().clone(), 5);
+        let mut board = Board::new(BoardState::new(8, 8), vec![horizontal_three('f'), vertical], Vec::new());
+        for y in 0..3 {
+            board.set_piece(Pos::new(0, y), Piece::Regular('f', ALL_DIRECTIONS));
+        }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        let next_match = board.next_match().unwrap();
+        assert_eq!(5, next_match.rank());
+        assert_eq!(1, next_match.pattern_id());
+        assert_eq!(Pos::new(0, 0), next_match.changed_pos());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_left_unmovable_west_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::North
-        ));
+    fn scratch_copy_keeps_pattern_ids() {
+        let vertical = MatchPattern::new('f', vertical_three('f').spaces().clone(), 5);
+        let board = Board::new(BoardState::new(8, 8), vec![horizontal_three('f'), vertical], Vec::new());
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        let ids: Vec<usize> = board.scratch_copy().patterns().iter().map(|pattern| pattern.id()).collect();
+        assert_eq!(vec![1, 0], ids);
+    }
 
-        board.set_piece(Pos::new(0, 0), Piece::Empty);
-        board.set_piece(Pos::new(0, 1), Piece::Empty);
-        board.set_piece(Pos::new(0, 2), Piece::Empty);
-        board.set_piece(Pos::new(0, 3), Piece::Empty);
-        board.set_piece(Pos::new(0, 4), Piece::Empty);
-        board.set_piece(Pos::new(0, 5), Piece::Empty);
+    #[test]
+    fn next_match_into_owned_board_can_be_changed() {
+        let mut board = Board::new(BoardState::new(8, 8), vec![horizontal_three('f')], Vec::new());
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        let next_match = board.next_match().unwrap().into_owned();
+        for &pos in next_match.board_pos() {
+            board.set_piece(pos, Piece::Empty);
+        }
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 2), piece2));
+        assert_eq!(3, next_match.board_pos().len());
+        assert!(next_match.board_pos().iter().all(|&pos| board.piece(pos) == Piece::Empty));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_right_unmovable_north_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::West
-        ));
+    fn board_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Board>();
+    }
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+    #[test]
+    fn board_moved_to_thread_keeps_swap_rules() {
+        let mut board = Board::new(BoardState::new(4, 4), Vec::new(), vec![Box::new(|_, _, _| false)]);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        let swapped = std::thread::spawn(move || board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)))
+            .join()
+            .unwrap();
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        assert!(!swapped);
+    }
 
-        board.add_and_trickle(Pos::new(1, 2), piece2);
+    #[test]
+    fn scan_all_matches_finds_unmarked_matches() {
+        let mut board = Board::new(BoardState::new(8, 8), vec![horizontal_three('f'), vertical_three('s')], Vec::new());
+        for x in 2..5 {
+            board.set_piece_silent(Pos::new(x, 3), Piece::Regular('f', ALL_DIRECTIONS));
+        }
+        for y in 5..8 {
+            board.set_piece_silent(Pos::new(7, y), Piece::Regular('s', ALL_DIRECTIONS));
+        }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        let matches = board.scan_all_matches();
 
-        assert_eq!(piece2, board.piece(Pos::new(2, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+        let expected_f: HashSet<Pos> = (2..5).map(|x| Pos::new(x, 3)).collect();
+        let expected_s: HashSet<Pos> = (5..8).map(|y| Pos::new(7, y)).collect();
+        assert_eq!(2, matches.len());
+        assert!(matches.iter().any(|found| *found.board_pos() == expected_f && found.changed_pos() == Pos::new(2, 3)));
+        assert!(matches.iter().any(|found| *found.board_pos() == expected_s && found.piece_type() == 's'));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_right_unmovable_north_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::West
-        ));
+    fn scan_all_matches_pieces_in_one_match_only() {
+        let mut board = Board::new(BoardState::new(8, 8), vec![horizontal_three('f')], Vec::new());
+        for x in 0..5 {
+            board.set_piece_silent(Pos::new(x, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        }
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        let matches = board.scan_all_matches();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(1, matches.len());
+        assert_eq!(3, matches[0].board_pos().len());
+    }
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn scan_all_matches_higher_rank_first() {
+        let long = MatchPattern::new('f', (0..4).map(|x| Pos::new(x, 0)).collect(), 2);
+        let mut board = Board::new(BoardState::new(8, 8), vec![horizontal_three('f'), long], Vec::new());
+        for x in 1..5 {
+            board.set_piece_silent(Pos::new(x, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        }
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 1), Pos::new(2, 0))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 2), piece2));
+        let matches = board.scan_all_matches();
+
+        assert_eq!(1, matches.len());
+        assert_eq!(2, matches[0].rank());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_right_unmovable_south_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::East | Direction::West
-        ));
+    fn scan_all_matches_changed_positions_kept() {
+        let mut board = Board::new(BoardState::new(8, 8), vec![horizontal_three('f')], Vec::new());
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        }
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert_eq!(1, board.scan_all_matches().len());
+        assert_eq!(3, board.state().last_changed.len());
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+    #[test]
+    fn scan_all_matches_prevented_region_skipped() {
+        let mut board = Board::new(BoardState::new(8, 8), vec![horizontal_three('f')], Vec::new());
+        for x in 0..3 {
+            board.set_piece_silent(Pos::new(x, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        }
+        board.prevent_matches_in_region(Pos::new(2, 0), Pos::new(2, 0));
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+        assert!(board.scan_all_matches().is_empty());
+    }
+
+    #[test]
+    fn scan_all_matches_clusters_found() {
+        let mut board = Board::new(BoardState::new(6, 6), vec![MatchPattern::cluster('f', 4, 1)], Vec::new());
+        for pos in [Pos::new(0, 0), Pos::new(1, 0), Pos::new(1, 1), Pos::new(1, 2), Pos::new(4, 4)].iter() {
+            board.set_piece_silent(*pos, Piece::Regular('f', ALL_DIRECTIONS));
+        }
+
+        let matches = board.scan_all_matches();
+
+        assert_eq!(1, matches.len());
+        assert_eq!(4, matches[0].board_pos().len());
+    }
+
+    #[test]
+    fn scan_all_matches_same_as_next_match_on_large_board() {
+        let patterns = vec![horizontal_three('a'), vertical_three('a'), horizontal_three('b'), vertical_three('b')];
+        let mut board = Board::new(BoardState::new(64, 64), patterns, Vec::new());
+        let mut rng = Rng::new(7);
+        for y in 0..64 {
+            for x in 0..64 {
+                let piece_type = ['a', 'b', 'c'][rng.next_below(3)];
+                board.set_piece(Pos::new(x, y), Piece::Regular(piece_type, ALL_DIRECTIONS));
+            }
+        }
 
-        board.add_and_trickle(Pos::new(1, 2), piece2);
+        let scanned: HashSet<Pos> = board.scan_all_matches().iter().flat_map(|found| found.board_pos().clone()).collect();
+        let mut checked = HashSet::new();
+        while let Some(found) = board.next_match() {
+            checked.extend(found.board_pos().iter().copied());
+        }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert!(scanned.iter().all(|pos| checked.contains(pos)));
+        assert!(!scanned.is_empty());
+    }
 
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    #[test]
+    fn set_piece_never_seen_type_bitboard_set() {
+        let mut board = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
+
+        board.set_piece(Pos::new(1, 1), Piece::Regular('z', ALL_DIRECTIONS));
+
+        assert!(board.type_bitboard('z').unwrap().is_set(Pos::new(1, 1)));
+        assert_eq!(Piece::Regular('z', ALL_DIRECTIONS), board.piece(Pos::new(1, 1)));
+        assert_eq!(Ok(()), board.check_invariants());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_right_unmovable_south_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::North | Direction::East | Direction::West
-        ));
+    fn set_piece_new_type_over_old_type_old_bitboard_unset() {
+        let mut board = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(1, 1), Piece::Regular('a', ALL_DIRECTIONS));
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        board.set_piece(Pos::new(1, 1), Piece::Regular('z', ALL_DIRECTIONS));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert!(!board.type_bitboard('a').unwrap().is_set(Pos::new(1, 1)));
+        assert!(board.type_bitboard('z').unwrap().is_set(Pos::new(1, 1)));
+        assert_eq!(Ok(()), board.check_invariants());
+    }
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn set_piece_many_new_types_each_bitboard_set() {
+        let mut board = Board::new(BoardState::new(4, 4), Vec::new(), Vec::new());
 
-        let expected_moves: Vec<(Pos, Pos)> = vec![];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 2), piece2));
+        for (index, piece_type) in ('a'..='p').enumerate() {
+            let pos = Pos::new((index % 4) as u8, (index / 4) as u8);
+            board.set_piece(pos, Piece::Regular(piece_type, ALL_DIRECTIONS));
+            board.set_piece(pos, Piece::Regular(piece_type.to_ascii_uppercase(), ALL_DIRECTIONS));
+        }
+
+        for (index, piece_type) in ('A'..='P').enumerate() {
+            let pos = Pos::new((index % 4) as u8, (index / 4) as u8);
+            assert_eq!(Some(1), board.type_bitboard(piece_type).map(|bitboard| bitboard.count()));
+            assert!(board.type_bitboard(piece_type).unwrap().is_set(pos));
+        }
+        assert_eq!(Ok(()), board.check_invariants());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_right_unmovable_east_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::West
-        ));
+    fn convert_pieces_never_seen_type_bitboards_moved() {
+        let mut board = Board::new(BoardState::new(3, 1), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('a', ALL_DIRECTIONS));
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert_eq!(2, board.convert_pieces('a', 'z', None));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(0, board.type_bitboard('a').unwrap().count());
+        assert_eq!(2, board.type_bitboard('z').unwrap().count());
+        assert_eq!(Ok(()), board.check_invariants());
+    }
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn swap_pieces_piece_with_wall_type_bitboard_moved() {
+        let mut board = Board::new(BoardState::new(2, 1), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
 
-        board.add_and_trickle(Pos::new(1, 2), piece2);
+        assert!(board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece2, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        assert!(board.type_bitboard('a').unwrap().is_set(Pos::new(1, 0)));
+        assert!(!board.type_bitboard('a').unwrap().is_set(Pos::new(0, 0)));
+        assert_eq!(Ok(()), board.check_invariants());
+    }
 
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+    fn falling_column_board_without_swaps() -> Board {
+        let mut board = Board::new(BoardState::new(1, 4), Vec::new(), vec![Box::new(|_, _, _| false)]);
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Regular('f', ALL_DIRECTIONS));
+        board
     }
 
     #[test]
-    fn add_trickle_with_diagonals_right_unmovable_east_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::North | Direction::West
-        ));
+    fn trickle_swap_rules_reject_all_piece_still_falls() {
+        let mut board = falling_column_board_without_swaps();
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        let moves = board.trickle();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(1, moves.len());
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert!(!board.swap_pieces(Pos::new(0, 0), Pos::new(0, 1)));
+    }
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn step_swap_rules_reject_all_piece_still_falls() {
+        let mut board = falling_column_board_without_swaps();
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 2), piece2));
+        let steps = board.step(3);
+
+        assert_eq!(3, steps.len());
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_right_unmovable_west_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::North
-        ));
+    fn add_and_trickle_swap_rules_reject_all_piece_still_falls() {
+        let mut board = falling_column_board_without_swaps();
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        board.add_and_trickle(Pos::new(0, 3), Piece::Regular('g', ALL_DIRECTIONS));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(Piece::Regular('g', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    fn wall_swap_board() -> Board {
+        let mut board = Board::new(BoardState::new(3, 1), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('d', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.register_wall_type('c', WallKind::Breakable { hit_points: 2 });
+        board.place_wall(Pos::new(1, 0), 'c');
+        board
+    }
 
-        board.add_and_trickle(Pos::new(1, 2), piece2);
+    #[test]
+    fn try_swap_pieces_second_is_wall_target_is_wall_error() {
+        let mut board = wall_swap_board();
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 5)));
+        let result = board.try_swap_pieces(Pos::new(2, 0), Pos::new(1, 0));
 
-        assert_eq!(piece2, board.piece(Pos::new(2, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 5)));
+        assert_eq!(Err(SwapError::TargetIsWall(Pos::new(1, 0))), result);
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 0)));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_right_unmovable_west_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(
-            Direction::South | Direction::East | Direction::North
-        ));
+    fn try_swap_pieces_first_is_wall_target_is_wall_error() {
+        let mut board = wall_swap_board();
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        let result = board.try_swap_pieces(Pos::new(1, 0), Pos::new(2, 0));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), Piece::Empty);
-        board.set_piece(Pos::new(1, 2), Piece::Empty);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
-        board.set_piece(Pos::new(1, 5), Piece::Empty);
+        assert_eq!(Err(SwapError::TargetIsWall(Pos::new(1, 0))), result);
+    }
 
-        board.set_piece(Pos::new(2, 0), Piece::Empty);
-        board.set_piece(Pos::new(2, 1), Piece::Empty);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
-        board.set_piece(Pos::new(2, 5), Piece::Empty);
+    #[test]
+    fn swap_pieces_wall_swapper_wall_moves_with_type() {
+        let mut board = wall_swap_board();
+        board.set_wall_swapper('d', true);
 
-        let expected_moves = vec![
-            (Pos::new(1, 2), Pos::new(1, 1)),
-            (Pos::new(1, 1), Pos::new(2, 0))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(1, 2), piece2));
+        assert!(board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+
+        assert_eq!(Piece::Regular('d', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 0)));
+        assert_eq!(Some('c'), board.wall_type(Pos::new(0, 0)));
+        assert_eq!(Some(2), board.wall_hit_points(Pos::new(0, 0)));
+        assert_eq!(None, board.wall_type(Pos::new(1, 0)));
     }
 
     #[test]
-    fn add_trickle_right_border_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+    fn try_swap_pieces_other_type_with_wall_swapper_registered_error() {
+        let mut board = wall_swap_board();
+        board.set_wall_swapper('d', true);
 
-        board.set_piece(Pos::new(15, 0), piece1);
-        board.set_piece(Pos::new(15, 1), Piece::Empty);
-        board.set_piece(Pos::new(15, 2), Piece::Empty);
-        board.set_piece(Pos::new(15, 3), Piece::Empty);
-        board.set_piece(Pos::new(15, 4), Piece::Empty);
-        board.set_piece(Pos::new(15, 5), Piece::Empty);
+        assert_eq!(Err(SwapError::TargetIsWall(Pos::new(1, 0))), board.try_swap_pieces(Pos::new(2, 0), Pos::new(1, 0)));
+    }
 
-        board.add_and_trickle(Pos::new(15, 2), piece1);
+    #[test]
+    fn swap_pieces_wall_swapper_not_movable_toward_wall_rejected() {
+        let mut board = wall_swap_board();
+        board.set_piece(Pos::new(0, 0), Piece::Regular('d', EnumSet::only(Direction::West)));
+        board.set_wall_swapper('d', true);
 
-        assert_eq!(piece1, board.piece(Pos::new(15, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(15, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 4)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(15, 5)));
+        assert_eq!(Err(SwapError::Rejected), board.try_swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
     }
 
     #[test]
-    fn add_trickle_right_border_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn swap_pieces_wall_swapper_locked_wall_rejected() {
+        let mut board = wall_swap_board();
+        board.set_wall_swapper('d', true);
+        board.lock_region(Pos::new(1, 0), Pos::new(1, 0));
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert!(!board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+    }
 
-        board.set_piece(Pos::new(15, 0), piece1);
-        board.set_piece(Pos::new(15, 1), Piece::Empty);
-        board.set_piece(Pos::new(15, 2), Piece::Empty);
-        board.set_piece(Pos::new(15, 3), Piece::Empty);
-        board.set_piece(Pos::new(15, 4), Piece::Empty);
-        board.set_piece(Pos::new(15, 5), Piece::Empty);
+    #[test]
+    fn set_wall_swapper_removed_type_cannot_swap_walls() {
+        let mut board = wall_swap_board();
+        board.set_wall_swapper('d', true);
 
-        let expected_moves = vec![
-            (Pos::new(15, 2), Pos::new(15, 1))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(15, 2), piece1));
+        board.set_wall_swapper('d', false);
+
+        assert!(!board.is_wall_swapper('d'));
+        assert_eq!(Err(SwapError::TargetIsWall(Pos::new(1, 0))), board.try_swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
     }
 
     #[test]
-    fn add_trickle_top_border_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn move_piece_along_through_wall_target_is_wall_error() {
+        let mut board = wall_swap_board();
+        let before = board.state().clone();
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        let result = board.move_piece_along(&[Pos::new(2, 0), Pos::new(1, 0), Pos::new(0, 0)]);
 
-        board.set_piece(Pos::new(0, 10), piece1);
-        board.set_piece(Pos::new(0, 11), Piece::Empty);
-        board.set_piece(Pos::new(0, 12), Piece::Empty);
-        board.set_piece(Pos::new(0, 13), Piece::Empty);
-        board.set_piece(Pos::new(0, 14), Piece::Empty);
-        board.set_piece(Pos::new(0, 15), Piece::Empty);
+        assert_eq!(Err(SwapError::TargetIsWall(Pos::new(1, 0))), result);
+        assert_eq!(&before, board.state());
+    }
 
-        board.add_and_trickle(Pos::new(0, 12), piece1);
+    fn type_swap_board() -> Board {
+        let mut board = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('x', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('g', EnumSet::new()));
+        board.set_piece(Pos::new(0, 1), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 1), Piece::Regular('f', ALL_DIRECTIONS));
+        board
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(0, 10)));
-        assert_eq!(piece1, board.piece(Pos::new(0, 11)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 12)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 13)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 14)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 15)));
+    #[test]
+    fn swap_pieces_type_rule_always_ignores_board_rules() {
+        let mut board = type_swap_board();
+        board.set_type_swap_rule('x', Box::new(|_, _, _, _| TypeSwap::Always));
+
+        assert!(board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+        assert_eq!(Piece::Regular('x', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+        assert_eq!(Piece::Regular('g', EnumSet::new()), board.piece(Pos::new(0, 0)));
     }
 
     #[test]
-    fn add_trickle_top_border_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
+    fn swap_pieces_type_rule_always_for_second_piece_swapped() {
+        let mut board = type_swap_board();
+        board.set_type_swap_rule('x', Box::new(|_, _, _, _| TypeSwap::Always));
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert!(board.swap_pieces(Pos::new(1, 0), Pos::new(0, 0)));
+        assert_eq!(Piece::Regular('x', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+    }
 
-        board.set_piece(Pos::new(0, 10), piece1);
-        board.set_piece(Pos::new(0, 11), Piece::Empty);
-        board.set_piece(Pos::new(0, 12), Piece::Empty);
-        board.set_piece(Pos::new(0, 13), Piece::Empty);
-        board.set_piece(Pos::new(0, 14), Piece::Empty);
-        board.set_piece(Pos::new(0, 15), Piece::Empty);
+    #[test]
+    fn swap_pieces_type_rule_never_rejected() {
+        let mut board = type_swap_board();
+        board.set_type_swap_rule('s', Box::new(|_, _, _, _| TypeSwap::Never));
 
-        let expected_moves = vec![
-            (Pos::new(0, 12), Pos::new(0, 11))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(0, 12), piece1));
+        assert!(!board.swap_pieces(Pos::new(1, 1), Pos::new(0, 1)));
+        assert_eq!(Piece::Regular('s', ALL_DIRECTIONS), board.piece(Pos::new(0, 1)));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_piece_replaced_with_more_movable_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
+    fn swap_pieces_type_rule_never_overrides_always() {
+        let mut board = type_swap_board();
+        board.set_type_swap_rule('x', Box::new(|_, _, _, _| TypeSwap::Always));
+        board.set_type_swap_rule('s', Box::new(|_, _, _, _| TypeSwap::Never));
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert!(!board.swap_pieces(Pos::new(0, 0), Pos::new(0, 1)));
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
+    #[test]
+    fn swap_pieces_type_rule_default_uses_board_rules() {
+        let mut board = type_swap_board();
+        board.set_type_swap_rule('x', Box::new(|_, _, _, _| TypeSwap::Default));
 
-        board.set_piece(Pos::new(2, 0), piece1);
+        assert!(!board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+        assert!(board.swap_pieces(Pos::new(0, 0), Pos::new(0, 1)));
+    }
 
-        board.set_piece(Pos::new(2, 1), piece2);
+    #[test]
+    fn swap_pieces_type_rule_always_locked_space_rejected() {
+        let mut board = type_swap_board();
+        board.set_type_swap_rule('x', Box::new(|_, _, _, _| TypeSwap::Always));
+        board.lock_region(Pos::new(1, 0), Pos::new(1, 0));
 
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        assert!(!board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
+    #[test]
+    fn try_swap_pieces_type_rule_always_wall_error() {
+        let mut board = type_swap_board();
+        board.set_type_swap_rule('x', Box::new(|_, _, _, _| TypeSwap::Always));
 
-        board.add_and_trickle(Pos::new(2, 1), piece1);
+        assert_eq!(Err(SwapError::TargetIsWall(Pos::new(0, 2))), board.try_swap_pieces(Pos::new(0, 0), Pos::new(0, 2)));
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+    #[test]
+    fn swap_pieces_type_rule_receives_own_pos_first() {
+        let mut board = type_swap_board();
+        board.set_type_swap_rule('f', Box::new(|view, pos, other, other_piece| {
+            assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), view.board().piece(pos));
+            assert_eq!(view.board().piece(other), other_piece);
+            match other_piece {
+                Piece::Regular('s', _) => TypeSwap::Never,
+                _ => TypeSwap::Default
+            }
+        }));
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        board.set_piece(Pos::new(1, 2), Piece::Empty);
 
-        assert_eq!(piece1, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert!(!board.swap_pieces(Pos::new(0, 1), Pos::new(1, 1)));
+        assert!(board.swap_pieces(Pos::new(1, 1), Pos::new(1, 2)));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_piece_replaced_with_more_movable_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
+    fn remove_type_swap_rule_board_rules_apply_again() {
+        let mut board = type_swap_board();
+        board.set_type_swap_rule('x', Box::new(|_, _, _, _| TypeSwap::Always));
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert!(board.remove_type_swap_rule('x').is_some());
+        assert!(board.remove_type_swap_rule('x').is_none());
+        assert!(!board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
+    #[test]
+    fn preview_swap_type_rule_never_none() {
+        let mut board = type_swap_board();
+        board.set_type_swap_rule('s', Box::new(|_, _, _, _| TypeSwap::Never));
 
-        board.set_piece(Pos::new(2, 0), piece1);
+        assert!(board.preview_swap(Pos::new(0, 1), Pos::new(1, 1)).is_none());
+    }
 
-        board.set_piece(Pos::new(2, 1), piece2);
+    fn source_board(pieces: &[(u8, u8, Piece)]) -> Board {
+        let spaces = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+        let mut board = open_board_with_patterns(3, 3, vec![MatchPattern::new('f', spaces.into_iter().collect(), 1)]);
+        for &(x, y, piece) in pieces {
+            board.set_piece(Pos::new(x, y), piece);
+        }
+        board.clear_pending();
+        board
+    }
 
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
+    #[test]
+    fn next_match_swap_pieces_source_player_swap() {
+        let piece = Piece::Regular('f', ALL_DIRECTIONS);
+        let mut board = source_board(&[(0, 0, piece), (1, 0, piece), (2, 0, Piece::Regular('s', ALL_DIRECTIONS)), (2, 1, piece)]);
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        assert!(board.swap_pieces(Pos::new(2, 0), Pos::new(2, 1)));
 
-        let expected_moves: Vec<(Pos, Pos)> = vec![
-            (Pos::new(2, 1), Pos::new(3, 0))
-        ];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(2, 1), piece1));
+        assert_eq!(MatchSource::PlayerSwap, board.next_match().unwrap().source());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_piece_replaced_with_less_movable_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
+    fn next_match_set_piece_source_scripted() {
+        let piece = Piece::Regular('f', ALL_DIRECTIONS);
+        let mut board = source_board(&[(0, 0, piece), (1, 0, piece)]);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        board.set_piece(Pos::new(2, 0), piece);
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        assert_eq!(MatchSource::Scripted, board.next_match().unwrap().source());
+    }
 
-        board.set_piece(Pos::new(2, 0), piece1);
+    #[test]
+    fn next_match_trickle_source_gravity() {
+        let piece = Piece::Regular('f', ALL_DIRECTIONS);
+        let mut board = source_board(&[(0, 0, piece), (1, 0, piece), (2, 1, piece)]);
 
-        board.set_piece(Pos::new(2, 1), piece1);
+        board.trickle();
 
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        assert_eq!(MatchSource::Gravity, board.next_match().unwrap().source());
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
+    #[test]
+    fn next_match_add_and_trickle_source_gravity() {
+        let piece = Piece::Regular('f', ALL_DIRECTIONS);
+        let mut board = source_board(&[(0, 0, piece), (1, 0, piece)]);
 
-        board.add_and_trickle(Pos::new(2, 1), piece2);
+        board.add_and_trickle(Pos::new(2, 2), piece);
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+        assert_eq!(MatchSource::Gravity, board.next_match().unwrap().source());
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece2, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+    #[test]
+    fn next_match_marked_again_latest_source() {
+        let piece = Piece::Regular('f', ALL_DIRECTIONS);
+        let mut board = source_board(&[(0, 0, piece), (1, 0, piece), (2, 1, piece)]);
 
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        board.trickle();
+        board.mark_for_match_check(Pos::new(2, 0));
+
+        let next_match = board.next_match().unwrap();
+        assert_eq!(Pos::new(2, 0), next_match.changed_pos());
+        assert_eq!(MatchSource::Scripted, next_match.source());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_piece_replaced_with_less_movable_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
+    fn next_match_by_rank_swap_source_player_swap() {
+        let piece = Piece::Regular('f', ALL_DIRECTIONS);
+        let mut board = source_board(&[(0, 0, piece), (1, 0, piece), (2, 0, Piece::Regular('s', ALL_DIRECTIONS)), (2, 1, piece)]);
+        board.set_match_order(MatchOrder::ByRank);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert!(board.swap_pieces(Pos::new(2, 0), Pos::new(2, 1)));
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        assert_eq!(MatchSource::PlayerSwap, board.next_match().unwrap().source());
+        assert!(board.state().change_sources.is_empty());
+    }
 
-        board.set_piece(Pos::new(2, 0), piece1);
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn find_matches_parallel_swap_source_player_swap() {
+        let piece = Piece::Regular('f', ALL_DIRECTIONS);
+        let mut board = source_board(&[(0, 0, piece), (1, 0, piece), (2, 0, Piece::Regular('s', ALL_DIRECTIONS)), (2, 1, piece)]);
 
-        board.set_piece(Pos::new(2, 1), piece1);
+        assert!(board.swap_pieces(Pos::new(2, 0), Pos::new(2, 1)));
 
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        let matches = board.find_matches_parallel();
+        assert_eq!(1, matches.len());
+        assert_eq!(MatchSource::PlayerSwap, matches[0].source());
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
+    #[test]
+    fn preview_swap_source_player_swap() {
+        let piece = Piece::Regular('f', ALL_DIRECTIONS);
+        let board = source_board(&[(0, 0, piece), (1, 0, piece), (2, 0, Piece::Regular('s', ALL_DIRECTIONS)), (2, 1, piece)]);
+
+        let matches = board.preview_swap(Pos::new(2, 0), Pos::new(2, 1)).unwrap();
 
-        let expected_moves: Vec<(Pos, Pos)> = vec![];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(2, 1), piece2));
+        assert_eq!(MatchSource::PlayerSwap, matches[0].source());
     }
 
     #[test]
-    fn add_trickle_with_diagonals_piece_replace_wall_sets_board() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
-
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+    fn into_owned_source_kept() {
+        let piece = Piece::Regular('f', ALL_DIRECTIONS);
+        let mut board = source_board(&[(0, 0, piece), (1, 0, piece), (2, 1, piece)]);
+        board.trickle();
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
+        let owned = board.next_match().unwrap().into_owned();
 
-        board.set_piece(Pos::new(2, 0), piece1);
+        assert_eq!(MatchSource::Gravity, owned.source());
+    }
 
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
+    #[test]
+    fn clear_pending_sources_removed() {
+        let piece = Piece::Regular('f', ALL_DIRECTIONS);
+        let mut board = source_board(&[(0, 0, piece), (1, 0, piece), (2, 1, piece)]);
+        board.trickle();
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
+        board.clear_pending();
 
-        board.add_and_trickle(Pos::new(2, 1), piece2);
+        assert!(board.state().change_sources.is_empty());
+    }
 
-        assert_eq!(piece1, board.piece(Pos::new(1, 0)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 1)));
-        assert_eq!(piece1, board.piece(Pos::new(1, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 4)));
+    #[test]
+    fn changed_index_after_taking_positions_index_in_queue() {
+        let mut state = BoardState::new(4, 4);
+        let positions = [Pos::new(3, 3), Pos::new(0, 1), Pos::new(2, 0), Pos::new(1, 2), Pos::new(0, 0)];
+        for &pos in positions.iter() {
+            state.mark_changed(pos, MatchSource::Scripted);
+        }
 
-        assert_eq!(piece1, board.piece(Pos::new(2, 0)));
-        assert_eq!(piece2, board.piece(Pos::new(2, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 4)));
+        state.take_changed(1);
+        state.take_changed(2);
 
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 0)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 1)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 3)));
-        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 4)));
+        assert_eq!(Some(0), state.changed_index(Pos::new(3, 3)));
+        assert_eq!(Some(1), state.changed_index(Pos::new(2, 0)));
+        assert_eq!(Some(2), state.changed_index(Pos::new(0, 0)));
+        assert_eq!(None, state.changed_index(Pos::new(0, 1)));
+        assert_eq!(None, state.changed_index(Pos::new(1, 2)));
     }
 
     #[test]
-    fn add_trickle_with_diagonals_piece_replace_wall_generates_moves() {
-        let type1 = 'f';
-        let piece1 = Piece::Regular(type1, ALL_DIRECTIONS);
-        let piece2 = Piece::Regular(type1, enum_set!(Direction::South));
+    fn changed_index_queue_replaced_index_in_queue() {
+        let mut state = BoardState::new(4, 4);
+        state.mark_changed(Pos::new(0, 0), MatchSource::Scripted);
+        state.mark_changed(Pos::new(1, 0), MatchSource::Scripted);
+        state.last_changed.swap(0, 1);
 
-        let mut board = Board::new(BoardState::new(16, 16),
-                                   Vec::new(), Vec::new());
+        assert_eq!(Some(1), state.changed_index(Pos::new(0, 0)));
+        assert_eq!(Some(0), state.changed_index(Pos::new(1, 0)));
+    }
 
-        board.set_piece(Pos::new(1, 0), piece1);
-        board.set_piece(Pos::new(1, 1), piece1);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
+    #[test]
+    fn step_floating_piece_rises_one_space_per_step() {
+        let mut board = open_board(1, 3);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('o', ALL_DIRECTIONS));
+        board.set_floating('o', true);
 
-        board.set_piece(Pos::new(2, 0), piece1);
+        let moves = board.step(3);
 
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        assert_eq!(vec![
+            vec![(Pos::new(0, 0), Pos::new(0, 1))],
+            vec![(Pos::new(0, 1), Pos::new(0, 2))]
+        ], moves);
+        assert_eq!(Piece::Regular('o', ALL_DIRECTIONS), board.piece(Pos::new(0, 2)));
+    }
 
-        board.set_piece(Pos::new(3, 0), Piece::Empty);
-        board.set_piece(Pos::new(3, 1), Piece::Empty);
-        board.set_piece(Pos::new(3, 2), Piece::Empty);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), Piece::Empty);
+    #[test]
+    fn step_heavy_piece_sinks_after_falling() {
+        let mut board = open_board(1, 4);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 2), Piece::Regular('b', ALL_DIRECTIONS));
+        board.set_weight('b', 2);
 
-        let expected_moves: Vec<(Pos, Pos)> = vec![];
-        assert_eq!(expected_moves, board.add_and_trickle(Pos::new(2, 1), piece2));
+        let moves = board.step(5);
+
+        assert_eq!(vec![
+            vec![(Pos::new(0, 2), Pos::new(0, 1))],
+            vec![(Pos::new(0, 1), Pos::new(0, 0)), (Pos::new(0, 0), Pos::new(0, 1))]
+        ], moves);
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Regular('g', ALL_DIRECTIONS), board.piece(Pos::new(0, 1)));
     }
 
     #[test]
-    fn display_shows_all_pieces_with_type() {
-        let piece1 = Piece::Regular('f', ALL_DIRECTIONS);
-        let piece2 = Piece::Regular('s', ALL_DIRECTIONS);
+    fn step_until_settled_same_as_trickle() {
+        let setup = || {
+            let mut board = open_board(2, 4);
+            board.set_piece(Pos::new(0, 0), Piece::Regular('g', ALL_DIRECTIONS));
+            board.set_piece(Pos::new(0, 1), Piece::Regular('h', ALL_DIRECTIONS));
+            board.set_piece(Pos::new(0, 3), Piece::Regular('b', ALL_DIRECTIONS));
+            board.set_piece(Pos::new(1, 0), Piece::Regular('o', ALL_DIRECTIONS));
+            board.set_piece(Pos::new(1, 2), Piece::Regular('g', ALL_DIRECTIONS));
+            board.set_weight('b', 2);
+            board.set_floating('o', true);
+            board
+        };
+        let mut stepped = setup();
+        let mut trickled = setup();
 
-        let mut board = Board::new(BoardState::new(15, 17),
-                                   Vec::new(), Vec::new());
+        stepped.step(u32::MAX);
+        trickled.trickle();
 
-        board.set_piece(Pos::new(1, 0), piece1);
+        for y in 0..4 {
+            for x in 0..2 {
+                assert_eq!(trickled.piece(Pos::new(x, y)), stepped.piece(Pos::new(x, y)));
+            }
+        }
+    }
 
-        board.set_piece(Pos::new(1, 1), piece2);
-        board.set_piece(Pos::new(1, 2), piece1);
-        board.set_piece(Pos::new(1, 3), Piece::Empty);
-        board.set_piece(Pos::new(1, 4), Piece::Empty);
+    #[test]
+    fn add_and_trickle_heavy_piece_sinks_through_lighter_pieces() {
+        let mut board = open_board(1, 4);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('g', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 1), Piece::Regular('h', ALL_DIRECTIONS));
+        board.set_weight('b', 2);
 
-        board.set_piece(Pos::new(2, 0), piece2);
-        board.set_piece(Pos::new(2, 1), piece2);
-        board.set_piece(Pos::new(2, 2), Piece::Empty);
-        board.set_piece(Pos::new(2, 3), Piece::Empty);
-        board.set_piece(Pos::new(2, 4), Piece::Empty);
+        let piece_move = board.add_and_trickle(Pos::new(0, 3), Piece::Regular('b', ALL_DIRECTIONS));
 
-        board.set_piece(Pos::new(3, 0), piece2);
-        board.set_piece(Pos::new(3, 1), piece1);
-        board.set_piece(Pos::new(3, 2), piece1);
-        board.set_piece(Pos::new(3, 3), Piece::Empty);
-        board.set_piece(Pos::new(3, 4), piece2);
+        assert_eq!(Some(PieceMove::new(vec![Pos::new(0, 3), Pos::new(0, 2), Pos::new(0, 1), Pos::new(0, 0)])), piece_move);
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Regular('g', ALL_DIRECTIONS), board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Regular('h', ALL_DIRECTIONS), board.piece(Pos::new(0, 2)));
+    }
 
-        let expected = "\
-        ###############\
-        \n###############\
-        \n###############\
-        \n###############\
-        \n###############\
-        \n###############\
-        \n###############\
-        \n###############\
-        \n###############\
-        \n###############\
-        \n###############\
-        \n###############\
-        \n#  s###########\
-        \n#   ###########\
-        \n#f f###########\
-        \n#ssf###########\
-        \n#fss###########\
-        \n";
+    #[test]
+    fn rotate_90_non_square_patterns_match_new_size() {
+        let mut board = open_board_with_patterns(3, 1, vec![vertical_three('a')]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        }
+        assert!(board.next_match().is_none());
 
-        assert_eq!(expected, format!("{}", board));
+        board.rotate_90();
+
+        let next_match = board.next_match().unwrap();
+        let expected: HashSet<Pos> = (0..3).map(|y| Pos::new(0, y)).collect();
+        assert_eq!(&expected, next_match.board_pos());
     }
 }
\ No newline at end of file
@@ -0,0 +1,839 @@
+use crate::board::{Board, PosSet};
+use crate::events::{BoardEvent, BoardObserver, ClearCause};
+use crate::matching::MatchSource;
+use crate::piece::Piece;
+use crate::position::Pos;
+
+/// Chooses new pieces to fill the empty spaces at the top of the board.
+/// Any closure that accepts the board and a position and returns a
+/// [Piece] is a spawner.
+pub trait Spawner: Send + Sync {
+
+    /// Gets the piece to place at an empty position at the top of the board.
+    /// Returning an empty piece leaves the column unfilled.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - the board that the piece will be placed on
+    /// * `pos` - the position where the piece will be placed
+    fn spawn(&mut self, board: &Board, pos: Pos) -> Piece;
+
+}
+
+impl<F: FnMut(&Board, Pos) -> Piece + Send + Sync> Spawner for F {
+    fn spawn(&mut self, board: &Board, pos: Pos) -> Piece {
+        self(board, pos)
+    }
+}
+
+/// Determines what happens to a match before it is cleared from the board.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum MatchAction {
+
+    /// Clear every piece in the match.
+    Clear,
+
+    /// Leave the match on the board. No match event is sent for it.
+    Cancel,
+
+    /// Clear some positions and put new pieces at others instead of
+    /// clearing the whole match.
+    Replace {
+        cleared: PosSet,
+        placed: Vec<(Pos, Piece)>
+    }
+
+}
+
+/// Inspects each match before it is cleared and may cancel or change it.
+/// Any closure that accepts the board and a [BoardEvent::Matched] event
+/// and returns a [MatchAction] is an interceptor.
+///
+/// A list of interceptors is also an interceptor. Interceptors earlier in the
+/// list have priority: the first action other than [MatchAction::Clear] is used.
+pub trait MatchInterceptor {
+
+    /// Decides what to do with a match that was just found.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - the board where the match was found, before it was cleared
+    /// * `matched` - the match that was found, which is always a [BoardEvent::Matched] event
+    fn intercept(&mut self, board: &Board, matched: &BoardEvent) -> MatchAction;
+
+}
+
+impl<F: FnMut(&Board, &BoardEvent) -> MatchAction> MatchInterceptor for F {
+    fn intercept(&mut self, board: &Board, matched: &BoardEvent) -> MatchAction {
+        self(board, matched)
+    }
+}
+
+impl MatchInterceptor for Vec<Box<dyn MatchInterceptor>> {
+    fn intercept(&mut self, board: &Board, matched: &BoardEvent) -> MatchAction {
+        self.iter_mut()
+            .map(|interceptor| interceptor.intercept(board, matched))
+            .find(|action| *action != MatchAction::Clear)
+            .unwrap_or(MatchAction::Clear)
+    }
+}
+
+impl Board {
+
+    /// Resolves all matches on the board until no more matches occur.
+    ///
+    /// Each match is cleared from the board as soon as it is found, so a piece
+    /// is never part of more than one match. Once there are no more matches,
+    /// ingredients that have reached an exit are removed from the board. Then
+    /// the remaining pieces trickle down and the spawner fills the empty spaces
+    /// in the top row of each column. Columns with their own spawner, set with
+    /// [set_spawner_for_column()](Board::set_spawner_for_column), use it instead. The new and moved pieces are checked for
+    /// matches, and the process repeats.
+    ///
+    /// The observer receives an event for every match, cleared piece, delivered
+    /// ingredient, moved piece, and spawned piece in the order that they happen.
+    /// Each match event includes its position in the chain of matches, so games
+    /// can show combos without tracking the depth of the cascade.
+    ///
+    /// Only matches inside the board's [cascade region](Board::set_cascade_region)
+    /// are cleared, if it has one. Each cleared match damages the
+    /// [breakable walls](crate::WallKind::Breakable) and
+    /// [obstacles](Board::set_obstacle) next to it.
+    ///
+    /// Returns the number of matches that were cleared.
+    ///
+    /// # Arguments
+    ///
+    /// * `spawner` - chooses the new pieces that fill the board
+    /// * `observer` - receives the events that happen while matches are resolved
+    pub fn resolve_cascade(&mut self, spawner: &mut dyn Spawner,
+                           observer: &mut dyn BoardObserver) -> usize {
+        self.resolve_cascade_with(spawner, observer, &mut |_: &Board, _: &BoardEvent| MatchAction::Clear)
+    }
+
+    /// Resolves all matches on the board like [resolve_cascade()](Board::resolve_cascade),
+    /// but lets an interceptor cancel or change each match before it is cleared.
+    ///
+    /// Canceled matches are not counted and do not send a match event. Replaced
+    /// matches send a match event, a cleared event for each cleared position,
+    /// and a spawned event for each placed piece. Placed pieces are marked for a
+    /// match check.
+    ///
+    /// Returns the number of matches that were cleared or replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `spawner` - chooses the new pieces that fill the board
+    /// * `observer` - receives the events that happen while matches are resolved
+    /// * `interceptor` - decides what to do with each match before it is cleared
+    pub fn resolve_cascade_with(&mut self, spawner: &mut dyn Spawner,
+                                observer: &mut dyn BoardObserver,
+                                interceptor: &mut dyn MatchInterceptor) -> usize {
+        let mut total_matches = 0;
+
+        for chain_index in 0.. {
+            match self.resolve_chain_link(chain_index, spawner, observer, interceptor) {
+                Some(matches) => total_matches += matches,
+                None => break
+            }
+        }
+
+        self.assert_invariants();
+        total_matches
+    }
+
+    /// Resolves one link in a chain of matches: clears the current matches,
+    /// delivers ingredients, lets the remaining pieces trickle down, and
+    /// refills the board. Returns the number of matches that were cleared or
+    /// replaced, or None if nothing was cleared or delivered, which means the
+    /// cascade is over and the board was not changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain_index` - the number of times the board was refilled before this link
+    /// * `spawner` - chooses the new pieces that fill the board
+    /// * `observer` - receives the events that happen while the link is resolved
+    /// * `interceptor` - decides what to do with each match before it is cleared
+    pub(crate) fn resolve_chain_link(&mut self, chain_index: usize, spawner: &mut dyn Spawner,
+                                     observer: &mut dyn BoardObserver,
+                                     interceptor: &mut dyn MatchInterceptor) -> Option<usize> {
+        let link = self.observed(observer, |board, observer| {
+            let matches = board.clear_matches(chain_index, observer, interceptor);
+            let delivered = board.deliver_ingredients(observer);
+            if matches == 0 && delivered == 0 {
+                return None;
+            }
+
+            board.trickle().into_iter().for_each(|piece_move| observer.on_event(
+                &BoardEvent::Moved { from: piece_move.from(), to: piece_move.to() }
+            ));
+            board.refill(spawner, observer);
+
+            Some(matches)
+        });
+
+        if chain_index > 0 && link.is_some() {
+            self.turn_report_mut().record_cascade();
+        }
+
+        link
+    }
+
+    /// Clears every match currently on the board and returns the number of
+    /// matches that were cleared or replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain_index` - the number of times the board was refilled before these matches
+    /// * `observer` - receives an event for each match and cleared piece
+    /// * `interceptor` - decides what to do with each match before it is cleared
+    fn clear_matches(&mut self, chain_index: usize, observer: &mut dyn BoardObserver,
+                     interceptor: &mut dyn MatchInterceptor) -> usize {
+        let mut matches = 0;
+        let region = self.cascade_region().cloned();
+
+        loop {
+            let event = match self.next_match_within(region.as_ref()) {
+                Some(next_match) => BoardEvent::Matched {
+                    piece_type: next_match.piece_type(),
+                    rank: next_match.pattern().rank(),
+                    pattern_id: next_match.pattern_id(),
+                    changed_pos: next_match.changed_pos(),
+                    source: next_match.source(),
+                    positions: next_match.board_pos().clone(),
+                    chain_index,
+                    simultaneous_index: matches
+                },
+                None => return matches
+            };
+
+            let (cleared, placed) = match interceptor.intercept(self, &event) {
+                MatchAction::Clear => match &event {
+                    BoardEvent::Matched { positions, .. } => (positions.clone(), Vec::new()),
+                    _ => unreachable!()
+                },
+                MatchAction::Cancel => continue,
+                MatchAction::Replace { cleared, placed } => (cleared, placed)
+            };
+
+            observer.on_event(&event);
+            matches += 1;
+
+            let mut sorted_cleared: Vec<Pos> = cleared.iter().copied().collect();
+            sorted_cleared.sort_by_key(|pos| (pos.y(), pos.x()));
+            let previous_cause = self.set_clear_cause(ClearCause::Match);
+            for pos in sorted_cleared {
+                let piece = self.set_piece_from(pos, Piece::Empty, MatchSource::Gravity);
+                observer.on_event(&BoardEvent::Cleared { pos, piece });
+            }
+            self.set_clear_cause(previous_cause);
+            self.damage_obstacles_next_to(&cleared, observer);
+
+            for (pos, piece) in placed {
+                self.set_piece_from(pos, piece, MatchSource::Gravity);
+                self.turn_report_mut().record_special_piece(pos, piece);
+                observer.on_event(&BoardEvent::Spawned { pos, piece });
+            }
+        }
+    }
+
+    /// Removes every ingredient that is at an exit and returns the number
+    /// of ingredients that were removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer` - receives an event for each delivered ingredient
+    fn deliver_ingredients(&mut self, observer: &mut dyn BoardObserver) -> usize {
+        let mut delivered = Vec::new();
+        for &piece_type in self.ingredients() {
+            if let Some(board) = self.type_bitboard(piece_type) {
+                let mut at_exits = board.clone();
+                at_exits.intersect(self.exits());
+                delivered.extend(at_exits.iter_set());
+            }
+        }
+        delivered.sort_by_key(|pos| (pos.y(), pos.x()));
+
+        let previous_cause = self.set_clear_cause(ClearCause::Delivered);
+        for &pos in delivered.iter() {
+            let piece = self.set_piece_from(pos, Piece::Empty, MatchSource::Gravity);
+            observer.on_event(&BoardEvent::Delivered { pos, piece });
+        }
+        self.set_clear_cause(previous_cause);
+
+        delivered.len()
+    }
+
+    /// Fills the empty space at the top of each column with new pieces until
+    /// the top space is no longer empty or the spawner returns an empty piece.
+    /// Columns with their own spawner are filled by that spawner instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `spawner` - chooses the new pieces that fill columns without their own spawner
+    /// * `observer` - receives an event for each spawned and moved piece
+    fn refill(&mut self, spawner: &mut dyn Spawner, observer: &mut dyn BoardObserver) {
+        if self.state().height == 0 {
+            return;
+        }
+
+        let top = self.state().height - 1;
+        for x in 0..self.state().width {
+            let pos = Pos::new(x, top);
+
+            // Take the column's spawner so that it can receive the board
+            let mut column_spawner = self.column_spawners_mut().remove(&x);
+            let current_spawner: &mut dyn Spawner = match column_spawner.as_mut() {
+                Some(column_spawner) => column_spawner.as_mut(),
+                None => spawner
+            };
+
+            while self.piece(pos) == Piece::Empty {
+                let piece = current_spawner.spawn(self, pos);
+                if piece == Piece::Empty {
+                    break;
+                }
+
+                observer.on_event(&BoardEvent::Spawned { pos, piece });
+                if let Some(piece_move) = self.add_and_trickle(pos, piece) {
+                    observer.on_event(&BoardEvent::Moved { from: piece_move.from(), to: piece_move.to() });
+                }
+            }
+
+            if let Some(column_spawner) = column_spawner {
+                self.column_spawners_mut().insert(x, column_spawner);
+            }
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, BoardState};
+    use crate::cascade::{MatchAction, MatchInterceptor};
+    use crate::events::{BoardEvent, ClearCause, Effect};
+    use crate::matching::{MatchPattern, MatchSource};
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::walls::WallKind;
+    use std::collections::HashSet;
+
+    fn horizontal_three(piece_type: char) -> MatchPattern {
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(0, 0));
+        spaces.insert(Pos::new(1, 0));
+        spaces.insert(Pos::new(2, 0));
+        MatchPattern::new(piece_type, spaces, 1)
+    }
+
+    fn filled_board(width: u8, height: u8, patterns: Vec<MatchPattern>) -> Board {
+        let mut board = Board::new(BoardState::new(width, height), patterns, Vec::new());
+        for x in 0..width {
+            for y in 0..height {
+                let piece_type = if (x + y) % 2 == 0 { 'a' } else { 'b' };
+                board.set_piece(Pos::new(x, y), Piece::Regular(piece_type, ALL_DIRECTIONS));
+            }
+        }
+        while board.next_match().is_some() {}
+        board
+    }
+
+    #[test]
+    fn resolve_cascade_no_matches_no_events() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        let mut events = Vec::new();
+
+        let matches = board.resolve_cascade(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |event: &BoardEvent| events.push(event.clone())
+        );
+
+        assert_eq!(0, matches);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn resolve_cascade_match_has_id_of_pattern_found() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('d'), horizontal_three('c')]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+        let mut events = Vec::new();
+
+        board.resolve_cascade(
+            &mut |_: &Board, _| Piece::Regular('e', ALL_DIRECTIONS),
+            &mut |event: &BoardEvent| events.push(event.clone())
+        );
+
+        assert!(matches!(events[0], BoardEvent::Matched { piece_type: 'c', pattern_id: 1, .. }));
+    }
+
+    #[test]
+    fn resolve_cascade_one_match_cleared_and_refilled() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+        let mut events = Vec::new();
+
+        let matches = board.resolve_cascade(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |event: &BoardEvent| events.push(event.clone())
+        );
+
+        assert_eq!(1, matches);
+        assert!(matches!(events[0], BoardEvent::Matched { piece_type: 'c', rank: 1, .. }));
+        let cleared = events.iter().filter(|event| matches!(event, BoardEvent::Cleared { .. })).count();
+        assert_eq!(3, cleared);
+        let spawned = events.iter().filter(|event| matches!(event, BoardEvent::Spawned { .. })).count();
+        assert_eq!(3, spawned);
+        for x in 0..3 {
+            assert_eq!(Piece::Regular('d', ALL_DIRECTIONS), board.piece(Pos::new(x, 2)));
+        }
+    }
+
+    #[test]
+    fn resolve_cascade_swap_then_cascade_sources_differ() {
+        let mut board = Board::new(BoardState::new(3, 3), vec![horizontal_three('a')], Vec::new());
+        let types = [['a', 'b', 'a'], ['b', 'a', 'c'], ['a', 'c', 'a']];
+        for (y, row) in types.iter().enumerate() {
+            for (x, &piece_type) in row.iter().enumerate() {
+                board.set_piece(Pos::new(x as u8, y as u8), Piece::Regular(piece_type, ALL_DIRECTIONS));
+            }
+        }
+        while board.next_match().is_some() {}
+        let mut spawned = 0;
+        let mut events = Vec::new();
+
+        assert!(board.swap_pieces(Pos::new(1, 0), Pos::new(1, 1)));
+        board.resolve_cascade(
+            &mut move |_: &Board, _| {
+                spawned += 1;
+                Piece::Regular(if spawned <= 3 { 'a' } else { 'b' }, ALL_DIRECTIONS)
+            },
+            &mut |event: &BoardEvent| events.push(event.clone())
+        );
+
+        let sources: Vec<(usize, MatchSource)> = events.iter().filter_map(|event| match event {
+            BoardEvent::Matched { chain_index, source, .. } => Some((*chain_index, *source)),
+            _ => None
+        }).collect();
+        assert_eq!(vec![(0, MatchSource::PlayerSwap), (1, MatchSource::Gravity)], sources);
+    }
+
+    #[test]
+    fn resolve_cascade_swap_rules_reject_all_pieces_fall_and_refill() {
+        let mut board = Board::new(BoardState::new(3, 2), vec![horizontal_three('c')], vec![Box::new(|_, _, _| false)]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+            board.set_piece(Pos::new(x, 1), Piece::Regular('a', ALL_DIRECTIONS));
+        }
+
+        let matches = board.resolve_cascade(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |_: &BoardEvent| {}
+        );
+
+        assert_eq!(1, matches);
+        for x in 0..3 {
+            assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(x, 0)));
+            assert_eq!(Piece::Regular('d', ALL_DIRECTIONS), board.piece(Pos::new(x, 1)));
+        }
+    }
+
+    #[test]
+    fn resolve_cascade_staging_rows_fall_in_and_refill() {
+        let mut board = filled_board(3, 4, vec![horizontal_three('c')]);
+        board.set_staging_rows(1);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+        let staged: Vec<Piece> = (0..3).map(|x| board.piece(Pos::new(x, 3))).collect();
+
+        let matches = board.resolve_cascade(
+            &mut |_: &Board, _| Piece::Regular('c', ALL_DIRECTIONS),
+            &mut |_: &BoardEvent| {}
+        );
+
+        assert_eq!(1, matches);
+        for x in 0..3 {
+            assert_eq!(staged[usize::from(x)], board.piece(Pos::new(x, 2)));
+            assert_eq!(Piece::Regular('c', ALL_DIRECTIONS), board.piece(Pos::new(x, 3)));
+        }
+    }
+
+    #[test]
+    fn resolve_cascade_match_next_to_breakable_wall_destroys_it() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        board.register_wall_type('x', WallKind::Breakable { hit_points: 1 });
+        board.place_wall(Pos::new(1, 1), 'x');
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+        let mut events = Vec::new();
+
+        board.resolve_cascade(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |event: &BoardEvent| events.push(event.clone())
+        );
+
+        assert!(events.contains(&BoardEvent::WallDestroyed { pos: Pos::new(1, 1), wall_type: 'x' }));
+        assert_eq!(None, board.wall_type(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn resolve_cascade_match_next_to_obstacle_damages_it_once() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        board.set_piece(Pos::new(1, 1), Piece::Regular('X', enumset::EnumSet::new()));
+        board.set_obstacle('X', Some('x'));
+        board.set_obstacle('x', None);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+
+        board.resolve_cascade(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |_: &BoardEvent| {}
+        );
+
+        assert_eq!(Piece::Regular('x', enumset::EnumSet::new()), board.piece(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn resolve_cascade_effects_enabled_clears_caused_by_match() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+        board.set_effects_enabled(true);
+
+        board.resolve_cascade(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |_: &BoardEvent| {}
+        );
+
+        let effects = board.drain_effects();
+        let cleared: Vec<Effect> = effects.iter().copied()
+            .filter(|effect| matches!(effect, Effect::Cleared { .. }))
+            .collect();
+        assert_eq!(3, cleared.len());
+        assert!(cleared.iter().all(|effect| matches!(effect, Effect::Cleared { cause: ClearCause::Match, .. })));
+        assert_eq!(3, effects.iter().filter(|effect| matches!(effect, Effect::Spawned { .. })).count());
+        assert_eq!(6, effects.iter().filter(|effect| matches!(effect, Effect::Moved { .. })).count());
+    }
+
+    #[test]
+    fn resolve_cascade_spawned_pieces_match_again() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+        let mut spawned = 0;
+
+        let matches = board.resolve_cascade(
+            &mut |_: &Board, _| {
+                spawned += 1;
+                if spawned <= 3 {
+                    Piece::Regular('c', ALL_DIRECTIONS)
+                } else {
+                    Piece::Regular('d', ALL_DIRECTIONS)
+                }
+            },
+            &mut |_: &BoardEvent| {}
+        );
+
+        assert_eq!(2, matches);
+        for x in 0..3 {
+            assert_eq!(Piece::Regular('d', ALL_DIRECTIONS), board.piece(Pos::new(x, 2)));
+        }
+    }
+
+    #[test]
+    fn resolve_cascade_spawner_returns_empty_column_left_empty() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+
+        board.resolve_cascade(
+            &mut |_: &Board, pos: Pos| if pos.x() == 1 {
+                Piece::Empty
+            } else {
+                Piece::Regular('d', ALL_DIRECTIONS)
+            },
+            &mut |_: &BoardEvent| {}
+        );
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Regular('d', ALL_DIRECTIONS), board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Regular('d', ALL_DIRECTIONS), board.piece(Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn resolve_cascade_column_spawner_used_for_column() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+        board.set_spawner_for_column(1, Box::new(|_: &Board, _| Piece::Regular('i', ALL_DIRECTIONS)));
+
+        board.resolve_cascade(&mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS), &mut |_: &BoardEvent| {});
+
+        assert_eq!(Piece::Regular('d', ALL_DIRECTIONS), board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Regular('i', ALL_DIRECTIONS), board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Regular('d', ALL_DIRECTIONS), board.piece(Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn resolve_cascade_column_spawner_kept_between_cascades() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        board.set_spawner_for_column(0, Box::new(|_: &Board, _| Piece::Regular('i', ALL_DIRECTIONS)));
+
+        for _ in 0..2 {
+            for x in 0..3 {
+                board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+            }
+            board.resolve_cascade(&mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS), &mut |_: &BoardEvent| {});
+        }
+
+        assert_eq!(Piece::Regular('i', ALL_DIRECTIONS), board.piece(Pos::new(0, 2)));
+        assert!(board.remove_spawner_for_column(0).is_some());
+    }
+
+    #[test]
+    fn resolve_cascade_removed_column_spawner_default_used() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+        board.set_spawner_for_column(0, Box::new(|_: &Board, _| Piece::Empty));
+        board.remove_spawner_for_column(0);
+
+        board.resolve_cascade(&mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS), &mut |_: &BoardEvent| {});
+
+        assert_eq!(Piece::Regular('d', ALL_DIRECTIONS), board.piece(Pos::new(0, 2)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_spawner_for_column_outside_board_panics() {
+        let mut board = filled_board(3, 3, Vec::new());
+        board.set_spawner_for_column(3, Box::new(|_: &Board, _| Piece::Empty));
+    }
+
+    #[test]
+    fn resolve_cascade_ingredient_at_bottom_delivered() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        board.set_ingredient('i', true);
+        board.set_piece(Pos::new(1, 0), Piece::Regular('i', ALL_DIRECTIONS));
+        let mut events = Vec::new();
+
+        board.resolve_cascade(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |event: &BoardEvent| events.push(event.clone())
+        );
+
+        assert_eq!(BoardEvent::Delivered {
+            pos: Pos::new(1, 0),
+            piece: Piece::Regular('i', ALL_DIRECTIONS)
+        }, events[0]);
+        assert_eq!(0, board.count('i'));
+        assert_eq!(Piece::Regular('d', ALL_DIRECTIONS), board.piece(Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn resolve_cascade_ingredient_falls_to_exit_delivered() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        board.set_ingredient('i', true);
+        board.set_piece(Pos::new(1, 1), Piece::Regular('i', ALL_DIRECTIONS));
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+        let mut delivered = Vec::new();
+
+        board.resolve_cascade(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |event: &BoardEvent| if let BoardEvent::Delivered { pos, .. } = event {
+                delivered.push(*pos);
+            }
+        );
+
+        assert_eq!(vec![Pos::new(1, 0)], delivered);
+        assert_eq!(0, board.count('i'));
+    }
+
+    #[test]
+    fn resolve_cascade_ingredient_not_at_exit_stays() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        board.set_ingredient('i', true);
+        board.set_exit(Pos::new(1, 0), false);
+        board.set_piece(Pos::new(1, 0), Piece::Regular('i', ALL_DIRECTIONS));
+
+        let matches = board.resolve_cascade(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |_: &BoardEvent| {}
+        );
+
+        assert_eq!(0, matches);
+        assert_eq!(1, board.count('i'));
+    }
+
+    #[test]
+    fn resolve_cascade_marked_exit_delivers_above_bottom() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        board.set_ingredient('i', true);
+        board.set_exit(Pos::new(2, 1), true);
+        board.set_piece(Pos::new(2, 1), Piece::Regular('i', ALL_DIRECTIONS));
+
+        board.resolve_cascade(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |_: &BoardEvent| {}
+        );
+
+        assert_eq!(0, board.count('i'));
+    }
+
+    #[test]
+    fn resolve_cascade_matches_have_chain_and_simultaneous_indices() {
+        let mut board = filled_board(3, 4, vec![horizontal_three('c')]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+            board.set_piece(Pos::new(x, 1), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+        let mut spawned = 0;
+        let mut indices = Vec::new();
+
+        board.resolve_cascade(
+            &mut |board: &Board, pos: Pos| {
+                if spawned < 3 && board.piece(Pos::new(pos.x(), 2)) == Piece::Empty {
+                    spawned += 1;
+                    Piece::Regular('c', ALL_DIRECTIONS)
+                } else {
+                    Piece::Wall
+                }
+            },
+            &mut |event: &BoardEvent| if let BoardEvent::Matched { chain_index, simultaneous_index, .. } = event {
+                indices.push((*chain_index, *simultaneous_index));
+            }
+        );
+
+        assert_eq!(vec![(0, 0), (0, 1), (1, 0)], indices);
+    }
+
+    fn board_with_bottom_match() -> Board {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+        board
+    }
+
+    #[test]
+    fn resolve_cascade_with_cancel_match_left_on_board() {
+        let mut board = board_with_bottom_match();
+        let mut events = Vec::new();
+
+        let matches = board.resolve_cascade_with(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |event: &BoardEvent| events.push(event.clone()),
+            &mut |_: &Board, _: &BoardEvent| MatchAction::Cancel
+        );
+
+        assert_eq!(0, matches);
+        assert!(events.is_empty());
+        assert_eq!(3, board.count('c'));
+    }
+
+    #[test]
+    fn resolve_cascade_with_replace_places_piece() {
+        let mut board = board_with_bottom_match();
+        let special = Piece::Regular('s', ALL_DIRECTIONS);
+        let mut events = Vec::new();
+
+        let matches = board.resolve_cascade_with(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |event: &BoardEvent| events.push(event.clone()),
+            &mut |_: &Board, matched: &BoardEvent| match matched {
+                BoardEvent::Matched { positions, .. } => MatchAction::Replace {
+                    cleared: positions.clone(),
+                    placed: vec![(Pos::new(1, 0), special)]
+                },
+                _ => MatchAction::Clear
+            }
+        );
+
+        assert_eq!(1, matches);
+        assert_eq!(special, board.piece(Pos::new(1, 0)));
+        assert_eq!(0, board.count('c'));
+        assert!(events.contains(&BoardEvent::Spawned { pos: Pos::new(1, 0), piece: special }));
+    }
+
+    #[test]
+    fn resolve_cascade_with_interceptor_sees_board_before_clear() {
+        let mut board = board_with_bottom_match();
+        let mut seen = 0;
+
+        board.resolve_cascade_with(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |_: &BoardEvent| {},
+            &mut |board: &Board, _: &BoardEvent| {
+                seen = board.count('c');
+                MatchAction::Clear
+            }
+        );
+
+        assert_eq!(3, seen);
+    }
+
+    #[test]
+    fn intercept_list_first_non_clear_action_used() {
+        let board = board_with_bottom_match();
+        let mut interceptors: Vec<Box<dyn MatchInterceptor>> = vec![
+            Box::new(|_: &Board, _: &BoardEvent| MatchAction::Clear),
+            Box::new(|_: &Board, _: &BoardEvent| MatchAction::Cancel),
+            Box::new(|_: &Board, _: &BoardEvent| MatchAction::Replace {
+                cleared: HashSet::new(),
+                placed: Vec::new()
+            })
+        ];
+
+        assert_eq!(MatchAction::Cancel, interceptors.intercept(&board, &BoardEvent::Moved {
+            from: Pos::new(0, 0),
+            to: Pos::new(0, 0)
+        }));
+    }
+
+    #[test]
+    fn intercept_empty_list_clear() {
+        let board = board_with_bottom_match();
+        let mut interceptors: Vec<Box<dyn MatchInterceptor>> = Vec::new();
+
+        assert_eq!(MatchAction::Clear, interceptors.intercept(&board, &BoardEvent::Moved {
+            from: Pos::new(0, 0),
+            to: Pos::new(0, 0)
+        }));
+    }
+
+    #[test]
+    fn resolve_cascade_region_only_clears_matches_inside() {
+        let mut board = filled_board(3, 3, vec![horizontal_three('c')]);
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('c', ALL_DIRECTIONS));
+            board.set_piece(Pos::new(x, 2), Piece::Regular('c', ALL_DIRECTIONS));
+        }
+        let top_row = (0..3).map(|x| Pos::new(x, 2)).collect();
+        board.set_cascade_region(Some(top_row));
+
+        let matches = board.resolve_cascade(
+            &mut |_: &Board, _| Piece::Regular('d', ALL_DIRECTIONS),
+            &mut |_: &BoardEvent| {}
+        );
+
+        assert_eq!(1, matches);
+        for x in 0..3 {
+            assert_eq!(Piece::Regular('c', ALL_DIRECTIONS), board.piece(Pos::new(x, 0)));
+            assert_eq!(Piece::Regular('d', ALL_DIRECTIONS), board.piece(Pos::new(x, 2)));
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,209 @@
+use crate::board::{Board, SwapError};
+use crate::position::Pos;
+use crate::view::BoardView;
+
+/// A swap rule that depends on game state kept outside the board, such as the
+/// current player, the power-ups left, or a level modifier. The state is
+/// passed in as a context with each swap through
+/// [swap_pieces_with()](Board::swap_pieces_with), so the rule can be created
+/// once and still see the latest state. Any closure that accepts a view of
+/// the board, the context, and both positions is a contextual swap rule.
+pub trait ContextSwapRule<C: ?Sized> {
+
+    /// Checks whether two pieces can be swapped.
+    ///
+    /// # Arguments
+    ///
+    /// * `view` - a read-only view of the board before the swap
+    /// * `context` - the game state passed with the swap
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    fn allows(&self, view: &BoardView, context: &C, first: Pos, second: Pos) -> bool;
+
+}
+
+impl<C: ?Sized, F: Fn(&BoardView, &C, Pos, Pos) -> bool> ContextSwapRule<C> for F {
+    fn allows(&self, view: &BoardView, context: &C, first: Pos, second: Pos) -> bool {
+        self(view, context, first, second)
+    }
+}
+
+impl Board {
+
+    /// Swaps two pieces like [swap_pieces()](Board::swap_pieces), but only if
+    /// every contextual rule also allows the swap. The contextual rules run
+    /// before the board's own swap rules, and the board is unchanged if any
+    /// rule fails. A rejection by a contextual rule is recorded in the turn
+    /// report and analytics like any other rejected swap.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    /// * `context` - the game state given to each contextual rule
+    /// * `rules` - the rules that must allow the swap in addition to the
+    ///   board's swap rules
+    ///
+    /// # Panics
+    ///
+    /// Panics if either position is outside the board. Use
+    /// [try_swap_pieces_with()](Board::try_swap_pieces_with) to get an error instead.
+    #[must_use]
+    pub fn swap_pieces_with<C: ?Sized>(&mut self, first: Pos, second: Pos, context: &C,
+                                       rules: &[&dyn ContextSwapRule<C>]) -> bool {
+        if !self.is_within_board(first) || !self.is_within_board(second) {
+            panic!("Tried to swap piece outside board: {} with {}", first, second);
+        }
+
+        let view = self.view();
+        let is_allowed = rules.iter().all(|rule| rule.allows(&view, context, first, second));
+        self.swap_if_allowed(first, second, is_allowed)
+    }
+
+    /// Swaps two pieces like [swap_pieces_with()](Board::swap_pieces_with),
+    /// but returns an error instead of panicking when either position is
+    /// outside the board. The board is unchanged if an error is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    /// * `context` - the game state given to each contextual rule
+    /// * `rules` - the rules that must allow the swap in addition to the
+    ///   board's swap rules
+    pub fn try_swap_pieces_with<C: ?Sized>(&mut self, first: Pos, second: Pos, context: &C,
+                                           rules: &[&dyn ContextSwapRule<C>]) -> Result<(), SwapError> {
+        if let Some(&pos) = [first, second].iter().find(|&&pos| !self.is_within_board(pos)) {
+            return Err(SwapError::OutOfBounds(pos));
+        }
+
+        if let Some(pos) = self.blocking_wall(first, second) {
+            return Err(SwapError::TargetIsWall(pos));
+        }
+
+        match self.swap_pieces_with(first, second, context, rules) {
+            true => Ok(()),
+            false => Err(SwapError::Rejected)
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::SwapError;
+    use crate::context::ContextSwapRule;
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::test_support::board_from_rows;
+    use crate::view::BoardView;
+
+    use enumset::EnumSet;
+
+    struct Turn {
+        player: usize,
+        swaps_left: u32
+    }
+
+    fn has_swaps_left(_: &BoardView, turn: &Turn, _: Pos, _: Pos) -> bool {
+        turn.swaps_left > 0
+    }
+
+    #[test]
+    fn swap_pieces_with_rule_allows_swapped() {
+        let mut board = board_from_rows(&["###", "abc"], &[]);
+        let turn = Turn { player: 0, swaps_left: 1 };
+
+        assert!(board.swap_pieces_with(Pos::new(0, 0), Pos::new(1, 0), &turn, &[&has_swaps_left]));
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn swap_pieces_with_rule_rejects_board_unchanged() {
+        let mut board = board_from_rows(&["###", "abc"], &[]);
+        let turn = Turn { player: 0, swaps_left: 0 };
+
+        assert!(!board.swap_pieces_with(Pos::new(0, 0), Pos::new(1, 0), &turn, &[&has_swaps_left]));
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn swap_pieces_with_rule_rejects_rejection_in_turn_report() {
+        let mut board = board_from_rows(&["###", "abc"], &[]);
+        let turn = Turn { player: 0, swaps_left: 0 };
+
+        assert!(!board.swap_pieces_with(Pos::new(0, 0), Pos::new(1, 0), &turn, &[&has_swaps_left]));
+        assert_eq!(0, board.turn_report().swaps());
+        assert_eq!(1, board.turn_report().rejected_swaps());
+    }
+
+    #[test]
+    fn swap_pieces_with_same_rule_new_context_result_changes() {
+        let mut board = board_from_rows(&["###", "abc"], &[]);
+        let only_own_side = |_: &BoardView, turn: &Turn, first: Pos, _: Pos| usize::from(first.x()) == turn.player;
+        let rules: [&dyn ContextSwapRule<Turn>; 2] = [&has_swaps_left, &only_own_side];
+
+        assert!(!board.swap_pieces_with(Pos::new(0, 0), Pos::new(1, 0), &Turn { player: 1, swaps_left: 1 }, &rules));
+        assert!(board.swap_pieces_with(Pos::new(0, 0), Pos::new(1, 0), &Turn { player: 0, swaps_left: 1 }, &rules));
+    }
+
+    #[test]
+    fn swap_pieces_with_board_rules_still_apply() {
+        let mut board = board_from_rows(&["###", "abc"], &[]);
+        board.set_piece(Pos::new(2, 0), Piece::Regular('c', EnumSet::new()));
+        let turn = Turn { player: 0, swaps_left: 1 };
+
+        assert!(!board.swap_pieces_with(Pos::new(1, 0), Pos::new(2, 0), &turn, &[&has_swaps_left]));
+    }
+
+    #[test]
+    fn swap_pieces_with_unsized_context_passed() {
+        let mut board = board_from_rows(&["###", "abc"], &[]);
+        let modifiers = ["frozen_left_column"];
+        let not_frozen = |_: &BoardView, modifiers: &[&str], first: Pos, second: Pos| {
+            !modifiers.contains(&"frozen_left_column") || (first.x() > 0 && second.x() > 0)
+        };
+
+        assert!(!board.swap_pieces_with(Pos::new(0, 0), Pos::new(1, 0), &modifiers[..], &[&not_frozen]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn swap_pieces_with_out_of_bounds_panics() {
+        let mut board = board_from_rows(&["###", "abc"], &[]);
+
+        let _ = board.swap_pieces_with(Pos::new(0, 0), Pos::new(0, 5), &(), &[]);
+    }
+
+    #[test]
+    fn try_swap_pieces_with_out_of_bounds_error() {
+        let mut board = board_from_rows(&["###", "abc"], &[]);
+
+        assert_eq!(
+            Err(SwapError::OutOfBounds(Pos::new(0, 5))),
+            board.try_swap_pieces_with(Pos::new(0, 0), Pos::new(0, 5), &(), &[])
+        );
+    }
+
+    #[test]
+    fn try_swap_pieces_with_rule_rejects_rejected_error() {
+        let mut board = board_from_rows(&["###", "abc"], &[]);
+        let turn = Turn { player: 0, swaps_left: 0 };
+
+        assert_eq!(
+            Err(SwapError::Rejected),
+            board.try_swap_pieces_with(Pos::new(0, 0), Pos::new(1, 0), &turn, &[&has_swaps_left])
+        );
+    }
+
+    #[test]
+    fn try_swap_pieces_with_wall_target_is_wall_error() {
+        let mut board = board_from_rows(&["###", "abc"], &[]);
+        let turn = Turn { player: 0, swaps_left: 1 };
+
+        assert_eq!(
+            Err(SwapError::TargetIsWall(Pos::new(0, 1))),
+            board.try_swap_pieces_with(Pos::new(0, 0), Pos::new(0, 1), &turn, &[&has_swaps_left])
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use crate::board::PosSet;
+use crate::matching::MatchSource;
+use crate::piece::{Piece, PieceType};
+use crate::position::Pos;
+use crate::walls::WallType;
+
+/// Something that happened on the board while matches were being resolved
+/// or while the board was ticked.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoardEvent {
+
+    /// A match was found on the board.
+    ///
+    /// The pattern ID is the [ID](crate::MatchPattern::id) of the pattern that
+    /// found the match, so matches can be told apart by pattern even when
+    /// several patterns share a rank.
+    ///
+    /// The chain index counts how many times pieces have fallen and refilled
+    /// the board since the cascade started, so the first matches have a chain
+    /// index of zero and matches caused by falling pieces have higher indices.
+    /// The simultaneous index counts the matches found before this one with
+    /// the same chain index. The source tells what changed the position that
+    /// triggered the match.
+    Matched {
+        piece_type: PieceType,
+        rank: u32,
+        pattern_id: usize,
+        changed_pos: Pos,
+        source: MatchSource,
+        positions: PosSet,
+        chain_index: usize,
+        simultaneous_index: usize
+    },
+
+    /// A piece was removed from the board because it was part of a match.
+    Cleared {
+        pos: Pos,
+        piece: Piece
+    },
+
+    /// A piece fell from one position to another.
+    Moved {
+        from: Pos,
+        to: Pos
+    },
+
+    /// A new piece was placed on the board to fill an empty space.
+    Spawned {
+        pos: Pos,
+        piece: Piece
+    },
+
+    /// A piece left the board through an exit and was removed.
+    Delivered {
+        pos: Pos,
+        piece: Piece
+    },
+
+    /// A regular piece was replaced by a copy of the spreading piece next to it.
+    Consumed {
+        pos: Pos,
+        piece: Piece,
+        from: Pos
+    },
+
+    /// A frozen piece's timer ran out, so the piece can be swapped again.
+    Thawed {
+        pos: Pos
+    },
+
+    /// An obstacle next to a match was damaged and replaced by a piece of its
+    /// damaged type.
+    ObstacleDamaged {
+        pos: Pos,
+        from: Piece,
+        to: Piece
+    },
+
+    /// An obstacle next to a match was destroyed, leaving an empty space.
+    ObstacleDestroyed {
+        pos: Pos,
+        piece: Piece
+    },
+
+    /// A breakable wall lost a hit point but is still on the board.
+    WallDamaged {
+        pos: Pos,
+        wall_type: WallType,
+        hit_points: u32
+    },
+
+    /// A breakable wall lost its last hit point and was removed, leaving an
+    /// empty space.
+    WallDestroyed {
+        pos: Pos,
+        wall_type: WallType
+    }
+
+}
+
+/// Receives events as they happen on the board. Any closure that accepts
+/// a [BoardEvent] is an observer.
+pub trait BoardObserver {
+
+    /// Handles an event that just happened on the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - the event that happened
+    fn on_event(&mut self, event: &BoardEvent);
+
+}
+
+impl<F: FnMut(&BoardEvent)> BoardObserver for F {
+    fn on_event(&mut self, event: &BoardEvent) {
+        self(event)
+    }
+}
+
+/// Why a piece was removed from the board.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClearCause {
+
+    /// The piece was part of a match.
+    Match,
+
+    /// The piece was an ingredient that left the board through an exit.
+    Delivered,
+
+    /// The piece was removed by a tick behavior.
+    Tick,
+
+    /// The piece was removed by a [PowerUp](crate::PowerUp).
+    PowerUp,
+
+    /// The piece was an obstacle destroyed by a match next to it. See
+    /// [set_obstacle()](crate::Board::set_obstacle).
+    AdjacentMatch,
+
+    /// The piece was removed directly with [set_piece()](crate::Board::set_piece).
+    Direct
+
+}
+
+/// A visual change to the board that a game may want to animate. Effects are
+/// queued on the board by every engine operation while the queue is enabled,
+/// and the game drains them with [drain_effects()](crate::Board::drain_effects),
+/// usually once per frame. Unlike a [BoardObserver], the queue does not need
+/// to be passed to each operation.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Effect {
+
+    /// A piece was removed, leaving the space empty.
+    Cleared {
+        pos: Pos,
+        cause: ClearCause
+    },
+
+    /// A piece was placed in an empty space.
+    Spawned {
+        pos: Pos
+    },
+
+    /// A piece moved from one position to another, either by a swap or by
+    /// gravity. A piece that fell several spaces only has one effect.
+    Moved {
+        from: Pos,
+        to: Pos
+    },
+
+    /// A non-empty space changed to a different piece without being emptied.
+    Converted {
+        pos: Pos,
+        from: Piece,
+        to: Piece
+    }
+
+}
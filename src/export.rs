@@ -0,0 +1,290 @@
+use crate::board::Board;
+use crate::piece::{Direction, Piece, PieceType};
+use crate::position::Pos;
+use crate::types::TypeId;
+use crate::walls::WallType;
+
+use enumset::EnumSet;
+
+/// The kind of piece in an exported cell. The discriminants are part of the
+/// [encoded](CellExport::encoded) cell format and never change.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum CellKind {
+    Empty = 0,
+    Wall = 1,
+    Regular = 2
+}
+
+/// Everything a renderer or scripting layer needs to draw one space on the
+/// board, created by [export_grid()](Board::export_grid) or
+/// [export_flat()](Board::export_flat).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CellExport {
+    piece: Piece,
+    type_id: TypeId,
+    counter: Option<u32>,
+    frozen_turns: Option<u32>,
+    wall_type: Option<WallType>,
+    is_locked: bool,
+    is_in_block: bool
+}
+
+impl CellExport {
+
+    /// Bit set in an encoded cell when the piece can move north.
+    pub const NORTH_BIT: u32 = 1 << 2;
+
+    /// Bit set in an encoded cell when the piece can move south.
+    pub const SOUTH_BIT: u32 = 1 << 3;
+
+    /// Bit set in an encoded cell when the piece can move east.
+    pub const EAST_BIT: u32 = 1 << 4;
+
+    /// Bit set in an encoded cell when the piece can move west.
+    pub const WEST_BIT: u32 = 1 << 5;
+
+    /// Bit set in an encoded cell when the space is locked.
+    pub const LOCKED_BIT: u32 = 1 << 6;
+
+    /// Bit set in an encoded cell when the space is frozen.
+    pub const FROZEN_BIT: u32 = 1 << 7;
+
+    /// Bit set in an encoded cell when the piece has a counter.
+    pub const COUNTER_BIT: u32 = 1 << 8;
+
+    /// Bit set in an encoded cell when the piece is part of a block.
+    pub const BLOCK_BIT: u32 = 1 << 9;
+
+    /// Gets the piece in the space.
+    pub fn piece(&self) -> Piece {
+        self.piece
+    }
+
+    /// Gets the kind of piece in the space.
+    pub fn kind(&self) -> CellKind {
+        match self.piece {
+            Piece::Regular(..) => CellKind::Regular,
+            Piece::Wall => CellKind::Wall,
+            Piece::Empty => CellKind::Empty
+        }
+    }
+
+    /// Gets the type of the regular piece in the space, if there is one.
+    pub fn piece_type(&self) -> Option<PieceType> {
+        match self.piece {
+            Piece::Regular(piece_type, _) => Some(piece_type),
+            _ => None
+        }
+    }
+
+    /// Gets the board's [ID](Board::type_id) for the type of the regular
+    /// piece in the space, or zero if the space does not hold a regular piece.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Gets the directions the piece in the space can move. Walls and empty
+    /// spaces cannot move in any direction.
+    pub fn directions(&self) -> EnumSet<Direction> {
+        match self.piece {
+            Piece::Regular(_, directions) => directions,
+            _ => EnumSet::new()
+        }
+    }
+
+    /// Gets the [counter](Board::counter) of the piece in the space, if any.
+    pub fn counter(&self) -> Option<u32> {
+        self.counter
+    }
+
+    /// Gets the number of turns the space stays [frozen](Board::frozen_turns),
+    /// if it is frozen.
+    pub fn frozen_turns(&self) -> Option<u32> {
+        self.frozen_turns
+    }
+
+    /// Gets the [registered type](Board::wall_type) of the wall in the space,
+    /// if any.
+    pub fn wall_type(&self) -> Option<WallType> {
+        self.wall_type
+    }
+
+    /// Checks whether the space is [locked](Board::is_locked).
+    pub fn is_locked(&self) -> bool {
+        self.is_locked
+    }
+
+    /// Checks whether the piece in the space is part of a [block](Board::block_at).
+    pub fn is_in_block(&self) -> bool {
+        self.is_in_block
+    }
+
+    /// Packs the cell into a single integer for bindings that move the whole
+    /// board across a language boundary at once. The layout is stable:
+    ///
+    /// * bits 0-1 - the [kind](CellKind) of piece
+    /// * bits 2-5 - the directions the piece can move: [north](Self::NORTH_BIT),
+    ///   [south](Self::SOUTH_BIT), [east](Self::EAST_BIT), and
+    ///   [west](Self::WEST_BIT)
+    /// * bit 6    - set if the space is locked
+    /// * bit 7    - set if the space is frozen
+    /// * bit 8    - set if the piece has a counter
+    /// * bit 9    - set if the piece is part of a block
+    /// * bits 10-15 - always zero
+    /// * bits 16-31 - the [type ID](Self::type_id), or zero if the space does
+    ///   not hold a regular piece
+    ///
+    /// Counter values, frozen turns, and wall types are not included.
+    pub fn encoded(&self) -> u32 {
+        let directions = self.directions();
+        let flags = [
+            (directions.contains(Direction::North), CellExport::NORTH_BIT),
+            (directions.contains(Direction::South), CellExport::SOUTH_BIT),
+            (directions.contains(Direction::East), CellExport::EAST_BIT),
+            (directions.contains(Direction::West), CellExport::WEST_BIT),
+            (self.is_locked, CellExport::LOCKED_BIT),
+            (self.frozen_turns.is_some(), CellExport::FROZEN_BIT),
+            (self.counter.is_some(), CellExport::COUNTER_BIT),
+            (self.is_in_block, CellExport::BLOCK_BIT)
+        ];
+
+        flags.iter()
+            .filter(|&&(is_set, _)| is_set)
+            .fold(self.kind() as u32 | (u32::from(self.type_id) << 16), |encoded, &(_, bit)| encoded | bit)
+    }
+
+}
+
+impl Board {
+
+    /// Exports every space on the board in one call. The outer list holds the
+    /// rows from the bottom row (y = 0) up, and each row holds its spaces from
+    /// west to east, so a cell is found at `grid[y][x]`.
+    pub fn export_grid(&self) -> Vec<Vec<CellExport>> {
+        (0..self.state().height).map(|y| {
+            (0..self.state().width).map(|x| self.export_cell(Pos::new(x, y))).collect()
+        }).collect()
+    }
+
+    /// Exports every space on the board in one flat, row-major list, starting
+    /// with the bottom row. The cell at a position is found at index
+    /// `y * width + x`.
+    pub fn export_flat(&self) -> Vec<CellExport> {
+        self.export_grid().into_iter().flatten().collect()
+    }
+
+    /// Exports one space on the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the space
+    fn export_cell(&self, pos: Pos) -> CellExport {
+        let piece = self.piece(pos);
+        let type_id = match piece {
+            Piece::Regular(piece_type, _) => self.type_id(piece_type).unwrap_or(0),
+            _ => 0
+        };
+
+        CellExport {
+            piece,
+            type_id,
+            counter: self.counter(pos),
+            frozen_turns: self.frozen_turns(pos),
+            wall_type: self.wall_type(pos),
+            is_locked: self.is_locked(pos),
+            is_in_block: self.block_at(pos).is_some()
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::{CellExport, CellKind};
+    use crate::piece::{Direction, Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::test_support::board_from_rows;
+    use crate::walls::WallKind;
+
+    use enumset::EnumSet;
+
+    #[test]
+    fn export_grid_rows_from_bottom_up() {
+        let grid = board_from_rows(&["  #", "ab "], &[]).export_grid();
+
+        assert_eq!(2, grid.len());
+        assert_eq!(3, grid[0].len());
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), grid[0][0].piece());
+        assert_eq!(CellKind::Wall, grid[1][2].kind());
+        assert_eq!(CellKind::Empty, grid[1][0].kind());
+    }
+
+    #[test]
+    fn export_flat_row_major() {
+        let board = board_from_rows(&["  #", "ab "], &[]);
+
+        let flat = board.export_flat();
+
+        assert_eq!(6, flat.len());
+        assert_eq!(Some('b'), flat[1].piece_type());
+        assert_eq!(CellKind::Wall, flat[3 + 2].kind());
+        assert_eq!(board.export_grid().concat(), flat);
+    }
+
+    #[test]
+    fn export_grid_type_ids_match_board() {
+        let board = board_from_rows(&["  #", "ab "], &[]);
+
+        let grid = board.export_grid();
+
+        assert_eq!(board.type_id('a'), Some(grid[0][0].type_id()));
+        assert_eq!(board.type_id('b'), Some(grid[0][1].type_id()));
+        assert_eq!(0, grid[1][2].type_id());
+    }
+
+    #[test]
+    fn export_grid_cell_state_included() {
+        let mut board = board_from_rows(&["  #", "ab "], &[]);
+        board.register_wall_type('c', WallKind::Breakable { hit_points: 2 });
+        board.place_wall(Pos::new(2, 0), 'c');
+        board.set_counter(Pos::new(0, 0), 3);
+        board.lock_region(Pos::new(1, 0), Pos::new(1, 0));
+        board.freeze(Pos::new(0, 0), 2);
+
+        let grid = board.export_grid();
+
+        assert_eq!(Some(3), grid[0][0].counter());
+        assert!(grid[0][1].is_locked());
+        assert!(!grid[0][0].is_locked());
+        assert_eq!(Some(2), grid[0][0].frozen_turns());
+        assert_eq!(Some('c'), grid[0][2].wall_type());
+        assert_eq!(None, grid[1][2].wall_type());
+    }
+
+    #[test]
+    fn encoded_regular_piece_layout() {
+        let mut board = board_from_rows(&["  #", "ab "], &[]);
+        board.set_piece(Pos::new(1, 0), Piece::Regular('b', EnumSet::only(Direction::South)));
+        board.set_counter(Pos::new(1, 0), 1);
+
+        let grid = board.export_grid();
+
+        let expected_first = CellKind::Regular as u32
+            | CellExport::NORTH_BIT | CellExport::SOUTH_BIT | CellExport::EAST_BIT | CellExport::WEST_BIT
+            | (1 << 16);
+        assert_eq!(expected_first, grid[0][0].encoded());
+        let expected_second = CellKind::Regular as u32 | CellExport::SOUTH_BIT | CellExport::COUNTER_BIT | (2 << 16);
+        assert_eq!(expected_second, grid[0][1].encoded());
+    }
+
+    #[test]
+    fn encoded_wall_and_empty_kind_only() {
+        let grid = board_from_rows(&["  #", "ab "], &[]).export_grid();
+
+        assert_eq!(1, grid[1][2].encoded());
+        assert_eq!(0, grid[1][0].encoded());
+    }
+}
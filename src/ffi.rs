@@ -0,0 +1,543 @@
+//! A C-compatible interface to the board so that the crate can be built as a
+//! shared library and used from other engines, such as Unity, Godot, or C++.
+//!
+//! All boards and matches returned by these functions are owned by the caller
+//! and must be released with [sam_board_free()] and [sam_match_free()].
+//! Positions outside the board and invalid pieces are rejected with a
+//! [SamStatus] instead of panicking, because a panic cannot unwind across the
+//! FFI boundary.
+
+use crate::board::{Board, BoardState, PosSet, SetPieceError};
+use crate::matching::MatchPattern;
+use crate::piece::{Direction, Piece};
+use crate::position::Pos;
+
+use std::convert::TryFrom;
+use std::ptr;
+use std::slice;
+
+use enumset::EnumSet;
+
+/// A position on the board.
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SamPos {
+    pub x: u8,
+    pub y: u8
+}
+
+/// The category of a piece, stored in [SamPiece::kind] as a `u8`. The kind
+/// crosses the FFI boundary as a plain integer so that an unknown value from
+/// the caller is rejected instead of becoming an invalid enum.
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SamPieceKind {
+    Regular = 0,
+    Empty = 1,
+    Wall = 2
+}
+
+impl TryFrom<u8> for SamPieceKind {
+    type Error = SamStatus;
+
+    fn try_from(kind: u8) -> Result<Self, Self::Error> {
+        match kind {
+            0 => Ok(SamPieceKind::Regular),
+            1 => Ok(SamPieceKind::Empty),
+            2 => Ok(SamPieceKind::Wall),
+            _ => Err(SamStatus::InvalidPiece)
+        }
+    }
+}
+
+/// The result of a function that changes the board.
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SamStatus {
+
+    /// The board was changed.
+    Ok = 0,
+
+    /// A position is outside the board.
+    OutOfBounds = 1,
+
+    /// The piece's kind is unknown or its type is not a Unicode scalar value.
+    InvalidPiece = 2,
+
+    /// The board already has as many distinct piece types as it can hold.
+    TooManyPieceTypes = 3
+
+}
+
+impl From<SetPieceError> for SamStatus {
+    fn from(err: SetPieceError) -> Self {
+        match err {
+            SetPieceError::OutOfBounds(_) => SamStatus::OutOfBounds,
+            SetPieceError::TooManyPieceTypes => SamStatus::TooManyPieceTypes
+        }
+    }
+}
+
+/// A piece on the board. The kind is one of the [SamPieceKind] values. The
+/// piece type is a Unicode scalar value, and the movable directions are a
+/// bitmask where north is bit 0, south is bit 1, east is bit 2, and west is
+/// bit 3. Both are ignored unless the piece is a regular piece.
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SamPiece {
+    pub kind: u8,
+    pub piece_type: u32,
+    pub directions: u8
+}
+
+/// A match pattern passed to [sam_board_new()].
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct SamPattern {
+    pub piece_type: u32,
+    pub spaces: *const SamPos,
+    pub spaces_len: usize,
+    pub rank: u32
+}
+
+/// A match found on the board. The positions are owned by the match and are
+/// released with [sam_match_free()].
+#[repr(C)]
+#[derive(Debug)]
+pub struct SamMatch {
+    pub piece_type: u32,
+    pub rank: u32,
+    pub changed_pos: SamPos,
+    pub positions: *mut SamPos,
+    pub positions_len: usize
+}
+
+impl From<Pos> for SamPos {
+    fn from(pos: Pos) -> Self {
+        SamPos { x: pos.x(), y: pos.y() }
+    }
+}
+
+impl From<SamPos> for Pos {
+    fn from(pos: SamPos) -> Self {
+        Pos::new(pos.x, pos.y)
+    }
+}
+
+impl From<Piece> for SamPiece {
+    fn from(piece: Piece) -> Self {
+        match piece {
+            Piece::Regular(piece_type, directions) => SamPiece {
+                kind: SamPieceKind::Regular as u8,
+                piece_type: u32::from(piece_type),
+                directions: directions.as_u8()
+            },
+            Piece::Empty => SamPiece { kind: SamPieceKind::Empty as u8, piece_type: 0, directions: 0 },
+            Piece::Wall => SamPiece { kind: SamPieceKind::Wall as u8, piece_type: 0, directions: 0 }
+        }
+    }
+}
+
+impl SamPiece {
+
+    /// Converts this piece into a board piece. Returns an error if the kind is
+    /// unknown or the piece is a regular piece whose type is not a valid
+    /// Unicode scalar value.
+    fn to_piece(self) -> Result<Piece, SamStatus> {
+        match SamPieceKind::try_from(self.kind)? {
+            SamPieceKind::Regular => Ok(Piece::Regular(
+                char::try_from(self.piece_type).map_err(|_| SamStatus::InvalidPiece)?,
+                EnumSet::<Direction>::from_u8_truncated(self.directions)
+            )),
+            SamPieceKind::Empty => Ok(Piece::Empty),
+            SamPieceKind::Wall => Ok(Piece::Wall)
+        }
+    }
+
+}
+
+/// Creates a new board filled with walls. Returns null if any pattern is invalid.
+///
+/// # Safety
+///
+/// `patterns` must point to `patterns_len` valid patterns, and each pattern's
+/// `spaces` must point to `spaces_len` valid positions. `patterns` may be null
+/// if `patterns_len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn sam_board_new(width: u8, height: u8, patterns: *const SamPattern,
+                                       patterns_len: usize) -> *mut Board {
+    let mut board_patterns = Vec::with_capacity(patterns_len);
+
+    for pattern in raw_slice(patterns, patterns_len) {
+        let piece_type = match char::try_from(pattern.piece_type) {
+            Ok(piece_type) => piece_type,
+            Err(_) => return ptr::null_mut()
+        };
+
+        let spaces: PosSet = raw_slice(pattern.spaces, pattern.spaces_len).iter()
+            .map(|&pos| Pos::from(pos))
+            .collect();
+
+        board_patterns.push(MatchPattern::new(piece_type, spaces, pattern.rank));
+    }
+
+    let board = Board::new(BoardState::new(width, height), board_patterns, Vec::new());
+    Box::into_raw(Box::new(board))
+}
+
+/// Destroys a board created by [sam_board_new()]. Does nothing if the board is null.
+///
+/// # Safety
+///
+/// `board` must be null or a board returned by [sam_board_new()] that has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sam_board_free(board: *mut Board) {
+    if !board.is_null() {
+        drop(Box::from_raw(board));
+    }
+}
+
+/// Gets the piece at a position. Returns false and leaves `out_piece` untouched
+/// if the position is outside the board.
+///
+/// # Safety
+///
+/// `board` must be a valid board, and `out_piece` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn sam_board_piece(board: *const Board, pos: SamPos,
+                                         out_piece: *mut SamPiece) -> bool {
+    let board = &*board;
+    if !is_within_board(board, pos) {
+        return false;
+    }
+
+    *out_piece = SamPiece::from(board.piece(Pos::from(pos)));
+    true
+}
+
+/// Replaces the piece at a position and writes the previous piece to `out_old_piece`
+/// if it is not null. Returns [SamStatus::Ok] if the piece was replaced. Otherwise,
+/// returns why the piece could not be replaced, and the board is not changed.
+///
+/// # Safety
+///
+/// `board` must be a valid board, and `out_old_piece` must be null or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn sam_board_set_piece(board: *mut Board, pos: SamPos, piece: SamPiece,
+                                             out_old_piece: *mut SamPiece) -> SamStatus {
+    let board = &mut *board;
+    if !is_within_board(board, pos) {
+        return SamStatus::OutOfBounds;
+    }
+
+    let piece = match piece.to_piece() {
+        Ok(piece) => piece,
+        Err(status) => return status
+    };
+
+    let old_piece = match board.try_set_piece(Pos::from(pos), piece) {
+        Ok(old_piece) => old_piece,
+        Err(err) => return SamStatus::from(err)
+    };
+    if !out_old_piece.is_null() {
+        *out_old_piece = SamPiece::from(old_piece);
+    }
+
+    SamStatus::Ok
+}
+
+/// Attempts to swap two pieces. Returns false if either position is outside the
+/// board or if the swap rules prevent the swap.
+///
+/// # Safety
+///
+/// `board` must be a valid board.
+#[no_mangle]
+pub unsafe extern "C" fn sam_board_swap_pieces(board: *mut Board, first: SamPos,
+                                               second: SamPos) -> bool {
+    let board = &mut *board;
+    if !is_within_board(board, first) || !is_within_board(board, second) {
+        return false;
+    }
+
+    board.swap_pieces(Pos::from(first), Pos::from(second))
+}
+
+/// Finds the next match on the board and writes it to `out_match`. Returns false
+/// if there are no more matches, in which case `out_match` is untouched. Every
+/// match written must later be released with [sam_match_free()].
+///
+/// # Safety
+///
+/// `board` must be a valid board, and `out_match` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn sam_board_next_match(board: *mut Board, out_match: *mut SamMatch) -> bool {
+    let board = &mut *board;
+    let next_match = match board.next_match() {
+        Some(next_match) => next_match,
+        None => return false
+    };
+
+    let mut positions: Vec<SamPos> = next_match.board_pos().iter()
+        .map(|&pos| SamPos::from(pos))
+        .collect();
+    positions.sort_by_key(|pos| (pos.y, pos.x));
+    let positions = positions.into_boxed_slice();
+    let positions_len = positions.len();
+
+    *out_match = SamMatch {
+        piece_type: u32::from(next_match.piece_type()),
+        rank: next_match.pattern().rank(),
+        changed_pos: SamPos::from(next_match.changed_pos()),
+        positions: Box::into_raw(positions) as *mut SamPos,
+        positions_len
+    };
+
+    true
+}
+
+/// Moves all pieces down to fill empty spaces. Returns the number of pieces that moved.
+///
+/// # Safety
+///
+/// `board` must be a valid board.
+#[no_mangle]
+pub unsafe extern "C" fn sam_board_trickle(board: *mut Board) -> usize {
+    (*board).trickle().len()
+}
+
+/// Releases the positions owned by a match written by [sam_board_next_match()].
+/// The match itself is not freed, as it is owned by the caller. Does nothing if
+/// the match is null.
+///
+/// # Safety
+///
+/// `found_match` must be null or a match written by [sam_board_next_match()]
+/// that has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn sam_match_free(found_match: *mut SamMatch) {
+    if found_match.is_null() {
+        return;
+    }
+
+    let found_match = &mut *found_match;
+    if !found_match.positions.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            found_match.positions,
+            found_match.positions_len
+        )));
+    }
+
+    found_match.positions = ptr::null_mut();
+    found_match.positions_len = 0;
+}
+
+/// Checks if a position is inside the board.
+///
+/// # Arguments
+///
+/// * `board` - the board to check
+/// * `pos` - the position to check
+fn is_within_board(board: &Board, pos: SamPos) -> bool {
+    pos.x < board.state().width && pos.y < board.state().height
+}
+
+/// Creates a slice from a raw pointer, allowing null pointers for empty slices.
+///
+/// # Safety
+///
+/// `data` must point to `len` valid elements if `len` is not zero.
+unsafe fn raw_slice<'a, T>(data: *const T, len: usize) -> &'a [T] {
+    match len {
+        0 => &[],
+        _ => slice::from_raw_parts(data, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ffi::*;
+    use std::mem::MaybeUninit;
+    use std::ptr;
+
+    fn regular(piece_type: char) -> SamPiece {
+        SamPiece { kind: SamPieceKind::Regular as u8, piece_type: u32::from(piece_type), directions: 0b1111 }
+    }
+
+    fn new_board_with_row_pattern() -> *mut Board {
+        let spaces = [SamPos { x: 0, y: 0 }, SamPos { x: 1, y: 0 }, SamPos { x: 2, y: 0 }];
+        let pattern = SamPattern {
+            piece_type: u32::from('f'),
+            spaces: spaces.as_ptr(),
+            spaces_len: spaces.len(),
+            rank: 3
+        };
+
+        unsafe { sam_board_new(8, 8, &pattern, 1) }
+    }
+
+    #[test]
+    fn new_board_no_patterns_filled_with_walls() {
+        unsafe {
+            let board = sam_board_new(8, 8, ptr::null(), 0);
+            let mut piece = MaybeUninit::uninit();
+            assert!(sam_board_piece(board, SamPos { x: 3, y: 4 }, piece.as_mut_ptr()));
+            assert_eq!(SamPieceKind::Wall as u8, piece.assume_init().kind);
+            sam_board_free(board);
+        }
+    }
+
+    #[test]
+    fn new_board_invalid_piece_type_null() {
+        let pattern = SamPattern { piece_type: 0xD800, spaces: ptr::null(), spaces_len: 0, rank: 1 };
+        unsafe {
+            assert!(sam_board_new(8, 8, &pattern, 1).is_null());
+        }
+    }
+
+    #[test]
+    fn free_board_null_does_nothing() {
+        unsafe {
+            sam_board_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn set_piece_in_bounds_old_piece_returned() {
+        unsafe {
+            let board = sam_board_new(8, 8, ptr::null(), 0);
+            let mut old_piece = MaybeUninit::uninit();
+            assert_eq!(SamStatus::Ok, sam_board_set_piece(board, SamPos { x: 1, y: 2 }, regular('f'), old_piece.as_mut_ptr()));
+            assert_eq!(SamPieceKind::Wall as u8, old_piece.assume_init().kind);
+
+            let mut piece = MaybeUninit::uninit();
+            assert!(sam_board_piece(board, SamPos { x: 1, y: 2 }, piece.as_mut_ptr()));
+            assert_eq!(regular('f'), piece.assume_init());
+            sam_board_free(board);
+        }
+    }
+
+    #[test]
+    fn set_piece_out_of_bounds_out_of_bounds_status() {
+        unsafe {
+            let board = sam_board_new(8, 8, ptr::null(), 0);
+            assert_eq!(
+                SamStatus::OutOfBounds,
+                sam_board_set_piece(board, SamPos { x: 8, y: 2 }, regular('f'), ptr::null_mut())
+            );
+            sam_board_free(board);
+        }
+    }
+
+    #[test]
+    fn set_piece_invalid_type_invalid_piece_status() {
+        let piece = SamPiece { kind: SamPieceKind::Regular as u8, piece_type: 0xD800, directions: 0 };
+        unsafe {
+            let board = sam_board_new(8, 8, ptr::null(), 0);
+            assert_eq!(SamStatus::InvalidPiece, sam_board_set_piece(board, SamPos { x: 1, y: 2 }, piece, ptr::null_mut()));
+            sam_board_free(board);
+        }
+    }
+
+    #[test]
+    fn set_piece_unknown_kind_invalid_piece_status() {
+        let piece = SamPiece { kind: 3, piece_type: u32::from('f'), directions: 0 };
+        unsafe {
+            let board = sam_board_new(8, 8, ptr::null(), 0);
+            assert_eq!(SamStatus::InvalidPiece, sam_board_set_piece(board, SamPos { x: 1, y: 2 }, piece, ptr::null_mut()));
+
+            let mut old_piece = MaybeUninit::uninit();
+            assert!(sam_board_piece(board, SamPos { x: 1, y: 2 }, old_piece.as_mut_ptr()));
+            assert_eq!(SamPieceKind::Wall as u8, old_piece.assume_init().kind);
+            sam_board_free(board);
+        }
+    }
+
+    #[test]
+    fn get_piece_out_of_bounds_false() {
+        unsafe {
+            let board = sam_board_new(8, 8, ptr::null(), 0);
+            let mut piece = MaybeUninit::uninit();
+            assert!(!sam_board_piece(board, SamPos { x: 1, y: 8 }, piece.as_mut_ptr()));
+            sam_board_free(board);
+        }
+    }
+
+    #[test]
+    fn swap_pieces_movable_swapped() {
+        unsafe {
+            let board = sam_board_new(8, 8, ptr::null(), 0);
+            sam_board_set_piece(board, SamPos { x: 1, y: 2 }, regular('f'), ptr::null_mut());
+            sam_board_set_piece(board, SamPos { x: 1, y: 3 }, regular('s'), ptr::null_mut());
+            assert!(sam_board_swap_pieces(board, SamPos { x: 1, y: 2 }, SamPos { x: 1, y: 3 }));
+
+            let mut piece = MaybeUninit::uninit();
+            sam_board_piece(board, SamPos { x: 1, y: 2 }, piece.as_mut_ptr());
+            assert_eq!(regular('s'), piece.assume_init());
+            sam_board_free(board);
+        }
+    }
+
+    #[test]
+    fn swap_pieces_out_of_bounds_false() {
+        unsafe {
+            let board = sam_board_new(8, 8, ptr::null(), 0);
+            assert!(!sam_board_swap_pieces(board, SamPos { x: 1, y: 2 }, SamPos { x: u8::MAX, y: 3 }));
+            sam_board_free(board);
+        }
+    }
+
+    #[test]
+    fn next_match_match_present_match_written() {
+        unsafe {
+            let board = new_board_with_row_pattern();
+            sam_board_set_piece(board, SamPos { x: 2, y: 1 }, regular('f'), ptr::null_mut());
+            sam_board_set_piece(board, SamPos { x: 3, y: 1 }, regular('f'), ptr::null_mut());
+            sam_board_set_piece(board, SamPos { x: 4, y: 1 }, regular('f'), ptr::null_mut());
+
+            let mut found_match = MaybeUninit::uninit();
+            assert!(sam_board_next_match(board, found_match.as_mut_ptr()));
+            let mut found_match = found_match.assume_init();
+
+            assert_eq!(u32::from('f'), found_match.piece_type);
+            assert_eq!(3, found_match.rank);
+            assert_eq!(SamPos { x: 2, y: 1 }, found_match.changed_pos);
+            assert_eq!(
+                &[SamPos { x: 2, y: 1 }, SamPos { x: 3, y: 1 }, SamPos { x: 4, y: 1 }],
+                slice::from_raw_parts(found_match.positions, found_match.positions_len)
+            );
+
+            sam_match_free(&mut found_match);
+            assert!(found_match.positions.is_null());
+            sam_board_free(board);
+        }
+    }
+
+    #[test]
+    fn next_match_no_match_false() {
+        unsafe {
+            let board = new_board_with_row_pattern();
+            sam_board_set_piece(board, SamPos { x: 2, y: 1 }, regular('f'), ptr::null_mut());
+
+            let mut found_match = MaybeUninit::uninit();
+            assert!(!sam_board_next_match(board, found_match.as_mut_ptr()));
+            sam_board_free(board);
+        }
+    }
+
+    #[test]
+    fn trickle_empty_below_moves_counted() {
+        unsafe {
+            let board = sam_board_new(8, 8, ptr::null(), 0);
+            sam_board_set_piece(board, SamPos { x: 1, y: 0 }, SamPiece {
+                kind: SamPieceKind::Empty as u8,
+                piece_type: 0,
+                directions: 0
+            }, ptr::null_mut());
+            sam_board_set_piece(board, SamPos { x: 1, y: 1 }, regular('f'), ptr::null_mut());
+            assert_eq!(1, sam_board_trickle(board));
+            sam_board_free(board);
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use crate::board::PosSet;
+use crate::events::{BoardEvent, BoardObserver};
+use crate::piece::{Piece, PieceType};
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+
+/// An objective that a player must complete to finish a level.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Goal {
+
+    /// Clear a number of pieces of one type through matches.
+    Clear {
+        piece_type: PieceType,
+        count: u64
+    },
+
+    /// Clear a piece from every one of a group of cells at least once,
+    /// such as the cells of a layer underneath the pieces.
+    ClearCells(PosSet),
+
+    /// Reach a minimum score.
+    Score(u64),
+
+    /// Bring a number of pieces of one type out of the board through an exit,
+    /// such as the bottom row.
+    Deliver {
+        piece_type: PieceType,
+        count: u64
+    }
+
+}
+
+impl Goal {
+
+    /// Gets the amount of progress required to complete this goal.
+    pub fn target(&self) -> u64 {
+        match self {
+            Goal::Clear { count, .. } => *count,
+            Goal::ClearCells(cells) => u64::try_from(cells.len()).unwrap_or(u64::MAX),
+            Goal::Score(score) => *score,
+            Goal::Deliver { count, .. } => *count
+        }
+    }
+
+}
+
+/// A function that determines how many points an event is worth.
+pub type Scorer = Box<dyn Fn(&BoardEvent) -> u64>;
+
+/// Tracks progress toward a level's goals as events happen on the board.
+///
+/// Goals is a [BoardObserver], so progress updates automatically when it is
+/// passed to [resolve_cascade()](crate::Board::resolve_cascade). Each goal is
+/// identified by its index in the list of goals the tracker was created with.
+///
+/// By default, each match is worth its pattern's rank multiplied by the
+/// number of pieces in the match. A custom [Scorer] can replace this.
+pub struct Goals {
+    goals: Vec<Goal>,
+    progress: Vec<u64>,
+    cleared_cells: Vec<PosSet>,
+    score: u64,
+    scorer: Scorer,
+    completed: VecDeque<usize>
+}
+
+impl Goals {
+
+    /// Creates a new goal tracker with no progress toward any goal.
+    ///
+    /// # Arguments
+    ///
+    /// * `goals` - the goals to track
+    pub fn new(goals: Vec<Goal>) -> Goals {
+        Goals::with_scorer(goals, Box::new(Goals::default_score))
+    }
+
+    /// Creates a new goal tracker that scores events with a custom function.
+    ///
+    /// # Arguments
+    ///
+    /// * `goals` - the goals to track
+    /// * `scorer` - determines how many points each event is worth
+    pub fn with_scorer(goals: Vec<Goal>, scorer: Scorer) -> Goals {
+        let mut tracker = Goals {
+            progress: vec![0; goals.len()],
+            cleared_cells: vec![PosSet::new(); goals.len()],
+            goals,
+            score: 0,
+            scorer,
+            completed: VecDeque::new()
+        };
+
+        for index in 0..tracker.goals.len() {
+            if tracker.is_complete(index) {
+                tracker.completed.push_back(index);
+            }
+        }
+
+        tracker
+    }
+
+    /// Gets all the goals being tracked.
+    pub fn goals(&self) -> &[Goal] {
+        &self.goals
+    }
+
+    /// Gets the current progress toward a goal. Progress may exceed
+    /// the goal's [target()](Goal::target).
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - the index of the goal
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no goal at the given index.
+    pub fn progress(&self, index: usize) -> u64 {
+        self.progress[index]
+    }
+
+    /// Checks whether a goal has been completed.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - the index of the goal
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no goal at the given index.
+    pub fn is_complete(&self, index: usize) -> bool {
+        self.progress[index] >= self.goals[index].target()
+    }
+
+    /// Checks whether every goal has been completed.
+    pub fn are_all_complete(&self) -> bool {
+        (0..self.goals.len()).all(|index| self.is_complete(index))
+    }
+
+    /// Gets the total score from all the events so far.
+    pub fn score(&self) -> u64 {
+        self.score
+    }
+
+    /// Gets the index of the next goal that was completed. Each goal is
+    /// returned once, in the order that the goals were completed.
+    pub fn next_completed(&mut self) -> Option<usize> {
+        self.completed.pop_front()
+    }
+
+    /// Scores a match by multiplying its rank by the number of pieces in it.
+    /// All other events are worth nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - the event to score
+    pub(crate) fn default_score(event: &BoardEvent) -> u64 {
+        match event {
+            BoardEvent::Matched { rank, positions, .. } =>
+                u64::from(*rank).saturating_mul(u64::try_from(positions.len()).unwrap_or(u64::MAX)),
+            _ => 0
+        }
+    }
+
+    /// Calculates how much an event advances a goal.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - the index of the goal
+    /// * `event` - the event that happened
+    fn advance(&mut self, index: usize, event: &BoardEvent) -> u64 {
+        match (&self.goals[index], event) {
+            (Goal::Clear { piece_type, .. }, BoardEvent::Cleared { piece: Piece::Regular(cleared_type, _), .. })
+                if piece_type == cleared_type => 1,
+            (Goal::ClearCells(cells), BoardEvent::Cleared { pos, .. })
+                if cells.contains(pos) && self.cleared_cells[index].insert(*pos) => 1,
+            (Goal::Deliver { piece_type, .. }, BoardEvent::Delivered { piece: Piece::Regular(delivered_type, _), .. })
+                if piece_type == delivered_type => 1,
+            _ => 0
+        }
+    }
+
+}
+
+impl BoardObserver for Goals {
+    fn on_event(&mut self, event: &BoardEvent) {
+        self.score = self.score.saturating_add((self.scorer)(event));
+
+        for index in 0..self.goals.len() {
+            let was_complete = self.is_complete(index);
+
+            let amount = match self.goals[index] {
+                Goal::Score(_) => self.score - self.progress[index],
+                _ => self.advance(index, event)
+            };
+            self.progress[index] = self.progress[index].saturating_add(amount);
+
+            if !was_complete && self.is_complete(index) {
+                self.completed.push_back(index);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, BoardState, PosSet};
+    use crate::events::{BoardEvent, BoardObserver};
+    use crate::goals::{Goal, Goals};
+    use crate::matching::{MatchPattern, MatchSource};
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+
+    fn cleared(x: u8, y: u8, piece_type: char) -> BoardEvent {
+        BoardEvent::Cleared { pos: Pos::new(x, y), piece: Piece::Regular(piece_type, ALL_DIRECTIONS) }
+    }
+
+    fn matched(rank: u32, size: u8) -> BoardEvent {
+        BoardEvent::Matched {
+            piece_type: 'f',
+            rank,
+            pattern_id: 0,
+            changed_pos: Pos::new(0, 0),
+            source: MatchSource::PlayerSwap,
+            positions: (0..size).map(|x| Pos::new(x, 0)).collect(),
+            chain_index: 0,
+            simultaneous_index: 0
+        }
+    }
+
+    #[test]
+    fn new_no_goals_all_complete() {
+        let goals = Goals::new(Vec::new());
+        assert!(goals.are_all_complete());
+    }
+
+    #[test]
+    fn new_zero_target_complete_immediately() {
+        let mut goals = Goals::new(vec![Goal::Score(0), Goal::Score(5)]);
+        assert!(goals.is_complete(0));
+        assert!(!goals.is_complete(1));
+        assert_eq!(Some(0), goals.next_completed());
+        assert_eq!(None, goals.next_completed());
+    }
+
+    #[test]
+    fn on_event_clear_matching_type_progresses() {
+        let mut goals = Goals::new(vec![Goal::Clear { piece_type: 'f', count: 2 }]);
+        goals.on_event(&cleared(0, 0, 'f'));
+        goals.on_event(&cleared(1, 0, 's'));
+
+        assert_eq!(1, goals.progress(0));
+        assert!(!goals.is_complete(0));
+        assert_eq!(None, goals.next_completed());
+    }
+
+    #[test]
+    fn on_event_clear_reaches_count_completed_once() {
+        let mut goals = Goals::new(vec![Goal::Clear { piece_type: 'f', count: 2 }]);
+        goals.on_event(&cleared(0, 0, 'f'));
+        goals.on_event(&cleared(1, 0, 'f'));
+        goals.on_event(&cleared(2, 0, 'f'));
+
+        assert_eq!(3, goals.progress(0));
+        assert!(goals.is_complete(0));
+        assert_eq!(Some(0), goals.next_completed());
+        assert_eq!(None, goals.next_completed());
+    }
+
+    #[test]
+    fn on_event_clear_cells_same_cell_counted_once() {
+        let cells: PosSet = vec![Pos::new(0, 0), Pos::new(1, 0)].into_iter().collect();
+        let mut goals = Goals::new(vec![Goal::ClearCells(cells)]);
+        goals.on_event(&cleared(0, 0, 'f'));
+        goals.on_event(&cleared(0, 0, 's'));
+        goals.on_event(&cleared(5, 5, 's'));
+
+        assert_eq!(1, goals.progress(0));
+        assert!(!goals.is_complete(0));
+
+        goals.on_event(&cleared(1, 0, 'f'));
+        assert!(goals.is_complete(0));
+    }
+
+    #[test]
+    fn on_event_match_default_score_rank_times_size() {
+        let mut goals = Goals::new(vec![Goal::Score(20)]);
+        goals.on_event(&matched(2, 3));
+        assert_eq!(6, goals.score());
+        assert_eq!(6, goals.progress(0));
+
+        goals.on_event(&matched(3, 5));
+        assert_eq!(21, goals.score());
+        assert_eq!(Some(0), goals.next_completed());
+    }
+
+    #[test]
+    fn on_event_custom_scorer_used() {
+        let mut goals = Goals::with_scorer(vec![Goal::Score(2)], Box::new(
+            |event| if let BoardEvent::Cleared { .. } = event { 1 } else { 0 }
+        ));
+        goals.on_event(&matched(5, 3));
+        goals.on_event(&cleared(0, 0, 'f'));
+
+        assert_eq!(1, goals.score());
+    }
+
+    #[test]
+    fn on_event_deliver_matching_type_progresses() {
+        let mut goals = Goals::new(vec![Goal::Deliver { piece_type: 'i', count: 1 }]);
+        goals.on_event(&BoardEvent::Delivered { pos: Pos::new(0, 0), piece: Piece::Regular('f', ALL_DIRECTIONS) });
+        assert!(!goals.is_complete(0));
+
+        goals.on_event(&BoardEvent::Delivered { pos: Pos::new(0, 0), piece: Piece::Regular('i', ALL_DIRECTIONS) });
+        assert!(goals.is_complete(0));
+    }
+
+    #[test]
+    fn on_event_multiple_goals_completed_in_order() {
+        let mut goals = Goals::new(vec![
+            Goal::Clear { piece_type: 'f', count: 2 },
+            Goal::Clear { piece_type: 'f', count: 1 }
+        ]);
+        goals.on_event(&cleared(0, 0, 'f'));
+        goals.on_event(&cleared(1, 0, 'f'));
+
+        assert_eq!(Some(1), goals.next_completed());
+        assert_eq!(Some(0), goals.next_completed());
+        assert!(goals.are_all_complete());
+    }
+
+    #[test]
+    fn resolve_cascade_goals_updated_automatically() {
+        let spaces: PosSet = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)].into_iter().collect();
+        let mut board = Board::new(BoardState::new(3, 2), vec![MatchPattern::new('f', spaces, 1)], Vec::new());
+        for x in 0..3 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('f', ALL_DIRECTIONS));
+            board.set_piece(Pos::new(x, 1), Piece::Regular(if x == 1 { 's' } else { 'g' }, ALL_DIRECTIONS));
+        }
+        let mut goals = Goals::new(vec![Goal::Clear { piece_type: 'f', count: 3 }, Goal::Score(3)]);
+
+        board.resolve_cascade(&mut |_: &Board, _| Piece::Regular('s', ALL_DIRECTIONS), &mut goals);
+
+        assert!(goals.are_all_complete());
+        assert_eq!(3, goals.score());
+    }
+}
@@ -0,0 +1,226 @@
+use crate::bitboard::BitBoard;
+use crate::board::Board;
+use crate::piece::{Direction, PieceType, ALL_DIRECTIONS};
+use crate::position::Pos;
+
+use std::fmt::{Display, Formatter};
+
+/// A way that the internal bookkeeping of a board disagrees with itself,
+/// found by [check_invariants()](Board::check_invariants). A board changed only
+/// through its public methods never has a violation, so any violation is a
+/// bug in the engine.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum InvariantViolation {
+
+    /// Two piece types both claim the same space.
+    SharedSpace {
+        pos: Pos,
+        first: PieceType,
+        second: PieceType
+    },
+
+    /// A space is marked as empty but also holds a regular piece.
+    EmptyWithType {
+        pos: Pos,
+        piece_type: PieceType
+    },
+
+    /// The type recorded for a space does not match the type whose bitboard
+    /// contains the space.
+    TypeMismatch(Pos),
+
+    /// A wall is movable in at least one direction.
+    MovableWall(Pos),
+
+    /// An empty space is not movable in every direction.
+    ImmovableEmpty(Pos)
+
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvariantViolation::SharedSpace { pos, first, second } =>
+                write!(f, "Types {} and {} share space {}", first, second, pos),
+            InvariantViolation::EmptyWithType { pos, piece_type } =>
+                write!(f, "Empty space {} holds type {}", pos, piece_type),
+            InvariantViolation::TypeMismatch(pos) => write!(f, "Recorded type does not match at {}", pos),
+            InvariantViolation::MovableWall(pos) => write!(f, "Wall is movable at {}", pos),
+            InvariantViolation::ImmovableEmpty(pos) => write!(f, "Empty space is not movable at {}", pos)
+        }
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
+impl Board {
+
+    /// Checks that the board's internal bookkeeping is consistent: no space
+    /// belongs to two piece types, empty spaces hold no pieces, the type
+    /// recorded for each space matches the type bitboards, walls cannot move,
+    /// and empty spaces can move in every direction. Returns the first
+    /// violation found, checking types in the order of their IDs.
+    ///
+    /// Games and downstream tests can call this after their own rules change
+    /// the board to catch corrupted state early.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        let state = self.state();
+        let grid_type = |pos: Pos| state.type_grid[usize::from(pos.y()) * usize::from(state.width) + usize::from(pos.x())];
+        let mut claimed = BitBoard::new(state.width, state.height);
+
+        for (id, (&piece_type, board)) in (1..).zip(state.pieces.iter()) {
+            let mut shared = board.clone();
+            shared.intersect(&claimed);
+            if let Some(pos) = shared.first_set() {
+                let first = state.pieces.iter()
+                    .find(|(_, other)| other.is_set(pos))
+                    .map(|(&first, _)| first)
+                    .expect("Shared space must be in an earlier type's bitboard");
+                return Err(InvariantViolation::SharedSpace { pos, first, second: piece_type });
+            }
+
+            let mut empty = board.clone();
+            empty.intersect(&state.empties);
+            if let Some(pos) = empty.first_set() {
+                return Err(InvariantViolation::EmptyWithType { pos, piece_type });
+            }
+
+            if let Some(pos) = board.iter_set().find(|&pos| grid_type(pos) != id) {
+                return Err(InvariantViolation::TypeMismatch(pos));
+            }
+
+            claimed.union(board);
+        }
+
+        for pos in (0..state.height).flat_map(|y| (0..state.width).map(move |x| Pos::new(x, y))) {
+            if grid_type(pos) != 0 && !claimed.is_set(pos) {
+                return Err(InvariantViolation::TypeMismatch(pos));
+            }
+
+            let movable = |direction: Direction| state.movable_directions[direction as usize].is_set(pos);
+            if state.empties.is_set(pos) {
+                if !ALL_DIRECTIONS.iter().all(movable) {
+                    return Err(InvariantViolation::ImmovableEmpty(pos));
+                }
+            } else if !claimed.is_set(pos) && ALL_DIRECTIONS.iter().any(movable) {
+                return Err(InvariantViolation::MovableWall(pos));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Panics if [check_invariants()](Board::check_invariants) finds a violation.
+    /// The check only runs in debug builds or with the `invariants` feature, so
+    /// release builds do not pay for it. The board calls this itself after each
+    /// swap, cascade, and tick.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the board's internal bookkeeping is inconsistent.
+    pub fn assert_invariants(&self) {
+        if cfg!(any(debug_assertions, feature = "invariants")) {
+            if let Err(violation) = self.check_invariants() {
+                panic!("Board invariant violated: {}", violation);
+            }
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::invariants::InvariantViolation;
+    use crate::piece::{Direction, Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::test_support::board_from_rows;
+
+    #[test]
+    fn check_invariants_public_changes_ok() {
+        let mut board = board_from_rows(&["###", "###", "ab "], &[]);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('b', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Wall);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+
+        assert_eq!(Ok(()), board.check_invariants());
+    }
+
+    #[test]
+    fn check_invariants_two_types_one_space_error() {
+        let mut board = board_from_rows(&["###", "###", "ab "], &[]);
+        board.state_mut().pieces.get_mut(&'b').unwrap().set(Pos::new(0, 0));
+
+        assert_eq!(
+            Err(InvariantViolation::SharedSpace { pos: Pos::new(0, 0), first: 'a', second: 'b' }),
+            board.check_invariants()
+        );
+    }
+
+    #[test]
+    fn check_invariants_empty_with_type_error() {
+        let mut board = board_from_rows(&["###", "###", "ab "], &[]);
+        board.state_mut().empties.set(Pos::new(0, 0));
+
+        assert_eq!(
+            Err(InvariantViolation::EmptyWithType { pos: Pos::new(0, 0), piece_type: 'a' }),
+            board.check_invariants()
+        );
+    }
+
+    #[test]
+    fn check_invariants_type_grid_disagrees_error() {
+        let mut board = board_from_rows(&["###", "###", "ab "], &[]);
+        board.state_mut().type_grid[0] = 2;
+
+        assert_eq!(Err(InvariantViolation::TypeMismatch(Pos::new(0, 0))), board.check_invariants());
+    }
+
+    #[test]
+    fn check_invariants_type_grid_without_bitboard_error() {
+        let mut board = board_from_rows(&["###", "###", "ab "], &[]);
+        board.state_mut().type_grid[4] = 1;
+
+        assert_eq!(Err(InvariantViolation::TypeMismatch(Pos::new(1, 1))), board.check_invariants());
+    }
+
+    #[test]
+    fn check_invariants_movable_wall_error() {
+        let mut board = board_from_rows(&["###", "###", "ab "], &[]);
+        board.state_mut().movable_directions[Direction::East as usize].set(Pos::new(2, 2));
+
+        assert_eq!(Err(InvariantViolation::MovableWall(Pos::new(2, 2))), board.check_invariants());
+    }
+
+    #[test]
+    fn check_invariants_immovable_empty_error() {
+        let mut board = board_from_rows(&["###", "###", "ab "], &[]);
+        board.state_mut().movable_directions[Direction::North as usize].unset(Pos::new(2, 0));
+
+        assert_eq!(Err(InvariantViolation::ImmovableEmpty(Pos::new(2, 0))), board.check_invariants());
+    }
+
+    #[test]
+    fn assert_invariants_consistent_board_no_panic() {
+        board_from_rows(&["###", "###", "ab "], &[]).assert_invariants();
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(any(debug_assertions, feature = "invariants"))]
+    fn assert_invariants_corrupted_board_panics() {
+        let mut board = board_from_rows(&["###", "###", "ab "], &[]);
+        board.state_mut().empties.set(Pos::new(0, 0));
+
+        board.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(any(debug_assertions, feature = "invariants"))]
+    fn swap_pieces_corrupted_board_panics() {
+        let mut board = board_from_rows(&["###", "###", "ab "], &[]);
+        board.state_mut().movable_directions[Direction::East as usize].set(Pos::new(2, 2));
+
+        let _ = board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0));
+    }
+}
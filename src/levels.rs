@@ -0,0 +1,820 @@
+use crate::board::{Board, BoardState};
+#[cfg(feature = "json")]
+use crate::board::PosSet;
+use crate::goals::{Goal, Goals};
+use crate::matching::MatchPattern;
+use crate::piece::{Piece, PieceType, ALL_DIRECTIONS};
+use crate::position::Pos;
+use crate::random::Rng;
+use crate::session::{GameSession, SessionLimit, SessionStatus};
+use crate::spawners::WeightedSpawner;
+
+use std::ops::Range;
+#[cfg(feature = "json")]
+use std::convert::TryFrom;
+#[cfg(feature = "json")]
+use std::fmt::{Display, Formatter};
+#[cfg(feature = "json")]
+use serde_json::{Map, Value};
+
+/// Everything needed to play a level: the starting board, the match patterns,
+/// how likely each type is to fill the board, the goals, and the move or
+/// turn limit.
+///
+/// With the `json` feature, levels can be loaded from a JSON description
+/// with [from_json()](Level::from_json), which keeps level data out of code.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Level {
+    state: BoardState,
+    patterns: Vec<MatchPattern>,
+    spawn_weights: Vec<(PieceType, u32)>,
+    goals: Vec<Goal>,
+    limit: SessionLimit
+}
+
+impl Level {
+
+    /// Creates a new level.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - the starting state of the board
+    /// * `patterns` - the match patterns used by the level
+    /// * `spawn_weights` - each type that can fill the board and its weight.
+    ///   See [WeightedSpawner].
+    /// * `goals` - the goals that must be completed
+    /// * `limit` - the number of moves or turns available to complete the goals
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total of all the spawn weights is zero.
+    pub fn new(state: BoardState, patterns: Vec<MatchPattern>, spawn_weights: Vec<(PieceType, u32)>,
+               goals: Vec<Goal>, limit: SessionLimit) -> Level {
+        if spawn_weights.iter().all(|&(_, weight)| weight == 0) {
+            panic!("Tried to create level with no spawn weight");
+        }
+
+        Level { state, patterns, spawn_weights, goals, limit }
+    }
+
+    /// Loads a level from a JSON description like the following:
+    ///
+    /// ```json
+    /// {
+    ///     "layout": ["#sf", "fsf", "sfs"],
+    ///     "patterns": [{ "piece": "f", "rank": 1, "spaces": [[0, 0], [1, 0], [2, 0]] }],
+    ///     "spawn_weights": { "f": 2, "s": 1 },
+    ///     "goals": [
+    ///         { "type": "clear", "piece": "f", "count": 10 },
+    ///         { "type": "clear_cells", "cells": [[0, 0]] },
+    ///         { "type": "score", "target": 100 },
+    ///         { "type": "deliver", "piece": "i", "count": 1 }
+    ///     ],
+    ///     "moves": 20
+    /// }
+    /// ```
+    ///
+    /// The layout lists the rows of the board from the top row to the bottom
+    /// row. `#` is a wall, `.` is an empty space, and any other character is a
+    /// regular piece of that type that is movable in all directions. Patterns
+    /// may also have a `"tag"`. Either `"moves"` or `"turns"` sets the limit.
+    ///
+    /// No positions are marked for a match check in the loaded board.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - the JSON description of the level
+    #[cfg(feature = "json")]
+    pub fn from_json(text: &str) -> Result<Level, LevelError> {
+        let root: Value = serde_json::from_str(text).map_err(|err| LevelError::Syntax(err.to_string()))?;
+        let root = root.as_object().ok_or(LevelError::InvalidField("level"))?;
+
+        let state = parse_layout(field(root, "layout")?)?;
+
+        let patterns = field(root, "patterns")?.as_array()
+            .ok_or(LevelError::InvalidField("patterns"))?
+            .iter()
+            .map(parse_pattern)
+            .collect::<Result<Vec<MatchPattern>, LevelError>>()?;
+
+        let spawn_weights = field(root, "spawn_weights")?.as_object()
+            .ok_or(LevelError::InvalidField("spawn_weights"))?
+            .iter()
+            .map(|(piece_type, weight)| Ok((
+                parse_type(&Value::String(piece_type.clone()), "spawn_weights")?,
+                parse_u32(weight, "spawn_weights")?
+            )))
+            .collect::<Result<Vec<(PieceType, u32)>, LevelError>>()?;
+        if spawn_weights.iter().all(|&(_, weight)| weight == 0) {
+            return Err(LevelError::InvalidField("spawn_weights"));
+        }
+
+        let goals = field(root, "goals")?.as_array()
+            .ok_or(LevelError::InvalidField("goals"))?
+            .iter()
+            .map(parse_goal)
+            .collect::<Result<Vec<Goal>, LevelError>>()?;
+
+        let limit = match (root.get("moves"), root.get("turns")) {
+            (Some(moves), None) => SessionLimit::Moves(parse_u32(moves, "moves")?),
+            (None, Some(turns)) => SessionLimit::Turns(parse_u32(turns, "turns")?),
+            (None, None) => return Err(LevelError::MissingField("moves")),
+            (Some(_), Some(_)) => return Err(LevelError::InvalidField("turns"))
+        };
+
+        Ok(Level::new(state, patterns, spawn_weights, goals, limit))
+    }
+
+    /// Gets the starting state of the board.
+    pub fn state(&self) -> &BoardState {
+        &self.state
+    }
+
+    /// Gets the match patterns used by the level.
+    pub fn patterns(&self) -> &[MatchPattern] {
+        &self.patterns
+    }
+
+    /// Gets each type that can fill the board and its weight.
+    pub fn spawn_weights(&self) -> &[(PieceType, u32)] {
+        &self.spawn_weights
+    }
+
+    /// Gets the goals that must be completed.
+    pub fn goals(&self) -> &[Goal] {
+        &self.goals
+    }
+
+    /// Gets the number of moves or turns available to complete the goals.
+    pub fn limit(&self) -> SessionLimit {
+        self.limit
+    }
+
+    /// Creates a board in the level's starting state with the level's patterns.
+    pub fn board(&self) -> Board {
+        Board::new(self.state.clone(), self.patterns.clone(), Vec::new())
+    }
+
+    /// Creates a session to play the level. Sessions with the same seed
+    /// spawn the same pieces for the same moves.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - the seed for the board's and the spawner's random number generators
+    pub fn session(&self, seed: u64) -> GameSession {
+        let mut board = self.board();
+        board.set_seed(seed);
+
+        GameSession::new(
+            board,
+            Box::new(WeightedSpawner::new(self.spawn_weights.clone(), seed)),
+            Goals::new(self.goals.clone()),
+            self.limit
+        )
+    }
+
+}
+
+/// The reason that a level description could not be loaded.
+#[cfg(feature = "json")]
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum LevelError {
+
+    /// The description is not valid JSON.
+    Syntax(String),
+
+    /// A required field is missing.
+    MissingField(&'static str),
+
+    /// A field has the wrong type or an invalid value.
+    InvalidField(&'static str)
+
+}
+
+#[cfg(feature = "json")]
+impl Display for LevelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LevelError::Syntax(message) => write!(f, "Invalid level JSON: {}", message),
+            LevelError::MissingField(name) => write!(f, "Missing level field: {}", name),
+            LevelError::InvalidField(name) => write!(f, "Invalid level field: {}", name)
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl std::error::Error for LevelError {}
+
+/// Describes what a level must allow a player to do, and how thoroughly
+/// [validate_level()] should check it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LevelConstraints {
+    goals: Vec<Goal>,
+    moves: u32,
+    spawn_types: Vec<PieceType>,
+    seeds: Range<u64>,
+    min_success_rate: f64
+}
+
+impl LevelConstraints {
+
+    /// Creates new constraints that are checked with seeds 0 to 99 and require
+    /// every playout to succeed.
+    ///
+    /// # Arguments
+    ///
+    /// * `goals` - the goals that must be completed
+    /// * `moves` - the number of moves available to complete the goals
+    /// * `spawn_types` - the types of the pieces that fill the board, which are
+    ///   chosen with equal probability. Spawned pieces are
+    ///   movable in all directions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no spawn types.
+    pub fn new(goals: Vec<Goal>, moves: u32, spawn_types: Vec<PieceType>) -> LevelConstraints {
+        if spawn_types.is_empty() {
+            panic!("Tried to create level constraints without spawn types");
+        }
+
+        LevelConstraints {
+            goals,
+            moves,
+            spawn_types,
+            seeds: 0..100,
+            min_success_rate: 1.0
+        }
+    }
+
+    /// Sets the seeds to play the level with. Each seed is played once.
+    ///
+    /// # Arguments
+    ///
+    /// * `seeds` - the seeds for the spawned pieces and the player's choices
+    pub fn with_seeds(mut self, seeds: Range<u64>) -> LevelConstraints {
+        self.seeds = seeds;
+        self
+    }
+
+    /// Sets the fraction of playouts that must complete the goals for the level
+    /// to be valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_success_rate` - the required fraction of successful playouts,
+    ///   from 0.0 to 1.0
+    pub fn with_min_success_rate(mut self, min_success_rate: f64) -> LevelConstraints {
+        self.min_success_rate = min_success_rate;
+        self
+    }
+
+    /// Gets the goals that must be completed.
+    pub fn goals(&self) -> &[Goal] {
+        &self.goals
+    }
+
+    /// Gets the number of moves available to complete the goals.
+    pub fn moves(&self) -> u32 {
+        self.moves
+    }
+
+    /// Gets the types of the pieces that fill the board.
+    pub fn spawn_types(&self) -> &[PieceType] {
+        &self.spawn_types
+    }
+
+}
+
+/// The results of playing a level with many seeds.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LevelReport {
+    playouts: usize,
+    wins: usize,
+    winning_moves: u64,
+    min_success_rate: f64
+}
+
+impl LevelReport {
+
+    /// Gets the number of times the level was played.
+    pub fn playouts(&self) -> usize {
+        self.playouts
+    }
+
+    /// Gets the number of playouts that completed all the goals.
+    pub fn wins(&self) -> usize {
+        self.wins
+    }
+
+    /// Gets the fraction of playouts that completed all the goals. Returns
+    /// 0.0 if the level was not played.
+    pub fn success_rate(&self) -> f64 {
+        if self.playouts == 0 {
+            return 0.0;
+        }
+
+        self.wins as f64 / self.playouts as f64
+    }
+
+    /// Gets the average number of moves used by the playouts that completed
+    /// all the goals. Returns None if no playout completed the goals.
+    pub fn average_moves_to_win(&self) -> Option<f64> {
+        if self.wins == 0 {
+            return None;
+        }
+
+        Some(self.winning_moves as f64 / self.wins as f64)
+    }
+
+    /// Checks whether enough playouts completed the goals to meet the
+    /// constraints' minimum success rate.
+    pub fn is_valid(&self) -> bool {
+        self.playouts > 0 && self.success_rate() >= self.min_success_rate
+    }
+
+}
+
+/// Checks whether a level can be completed within its move limit by playing
+/// it once for each seed in the constraints.
+///
+/// Each playout starts from the same state. The player always makes the swap
+/// of two adjacent pieces that clears the most pieces, choosing randomly
+/// between equally good swaps, and every match is resolved before the next
+/// swap. A playout fails if the moves run out or no swap causes a match.
+///
+/// Since the player only looks one move ahead, a level that fails validation
+/// may still be possible for a skilled player to complete.
+///
+/// # Arguments
+///
+/// * `state` - the starting state of the level
+/// * `patterns` - the match patterns used by the level
+/// * `constraints` - the goals and move limit of the level and the seeds to test
+pub fn validate_level(state: &BoardState, patterns: &[MatchPattern],
+                      constraints: &LevelConstraints) -> LevelReport {
+    let mut report = LevelReport {
+        playouts: 0,
+        wins: 0,
+        winning_moves: 0,
+        min_success_rate: constraints.min_success_rate
+    };
+
+    for seed in constraints.seeds.clone() {
+        let mut rng = Rng::new(seed);
+        let mut board = Board::new(state.clone(), patterns.to_vec(), Vec::new());
+        board.set_seed(rng.next_u64());
+
+        let mut spawn_rng = Rng::new(rng.next_u64());
+        let spawn_types = constraints.spawn_types.clone();
+        let mut session = GameSession::new(
+            board,
+            Box::new(move |_: &Board, _| Piece::Regular(
+                spawn_types[spawn_rng.next_below(spawn_types.len())],
+                ALL_DIRECTIONS
+            )),
+            Goals::new(constraints.goals.clone()),
+            SessionLimit::Moves(constraints.moves)
+        );
+
+        report.playouts += 1;
+        if play_greedy(&mut session, &mut rng) == SessionStatus::Won {
+            report.wins += 1;
+            report.winning_moves += u64::from(constraints.moves - session.remaining());
+        }
+    }
+
+    report
+}
+
+/// Plays a session until it ends or no swap causes a match, always making
+/// the swap that clears the most pieces. Returns the status of the session
+/// when the player stopped.
+///
+/// # Arguments
+///
+/// * `session` - the session to play
+/// * `rng` - chooses between swaps that clear the same number of pieces
+pub(crate) fn play_greedy(session: &mut GameSession, rng: &mut Rng) -> SessionStatus {
+    while session.status() == SessionStatus::InProgress {
+        match best_swap(session.board(), rng) {
+            Some((first, second)) => { session.swap_pieces(first, second); },
+            None => break
+        }
+    }
+
+    session.status()
+}
+
+/// Finds the swap of two adjacent pieces that immediately clears the most
+/// pieces. Returns None if no swap causes a match.
+///
+/// # Arguments
+///
+/// * `board` - the board to search for swaps
+/// * `rng` - chooses between swaps that clear the same number of pieces
+pub(crate) fn best_swap(board: &Board, rng: &mut Rng) -> Option<(Pos, Pos)> {
+    let mut best_swaps = Vec::new();
+    let mut best_size = 0;
+
+    for (first, second) in adjacent_pairs(board) {
+        let size: usize = match board.preview_swap(first, second) {
+            Some(matches) => matches.iter().map(|next_match| next_match.board_pos().len()).sum(),
+            None => 0
+        };
+
+        if size > 0 && size >= best_size {
+            if size > best_size {
+                best_swaps.clear();
+                best_size = size;
+            }
+            best_swaps.push((first, second));
+        }
+    }
+
+    if best_swaps.is_empty() {
+        return None;
+    }
+
+    Some(best_swaps[rng.next_below(best_swaps.len())])
+}
+
+/// Gets every pair of horizontally or vertically adjacent positions on a board,
+/// row by row from the bottom row.
+///
+/// # Arguments
+///
+/// * `board` - the board to get positions from
+pub(crate) fn adjacent_pairs(board: &Board) -> Vec<(Pos, Pos)> {
+    let width = board.state().width;
+    let height = board.state().height;
+    let mut pairs = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            let pos = Pos::new(x, y);
+            if x + 1 < width {
+                pairs.push((pos, Pos::new(x + 1, y)));
+            }
+            if y + 1 < height {
+                pairs.push((pos, Pos::new(x, y + 1)));
+            }
+        }
+    }
+
+    pairs
+}
+
+/// Checks whether swapping any two adjacent pieces on a board causes a match.
+///
+/// # Arguments
+///
+/// * `board` - the board to search for swaps
+pub(crate) fn has_matching_swap(board: &Board) -> bool {
+    adjacent_pairs(board).into_iter().any(
+        |(first, second)| board.preview_swap(first, second).is_some_and(|matches| !matches.is_empty())
+    )
+}
+
+/// Gets a required field of a JSON object.
+///
+/// # Arguments
+///
+/// * `object` - the object that contains the field
+/// * `name` - the name of the field
+#[cfg(feature = "json")]
+fn field<'a>(object: &'a Map<String, Value>, name: &'static str) -> Result<&'a Value, LevelError> {
+    object.get(name).ok_or(LevelError::MissingField(name))
+}
+
+/// Converts a JSON number to a u32.
+///
+/// # Arguments
+///
+/// * `value` - the JSON number
+/// * `name` - the name of the field that contains the number
+#[cfg(feature = "json")]
+fn parse_u32(value: &Value, name: &'static str) -> Result<u32, LevelError> {
+    value.as_u64()
+        .and_then(|number| u32::try_from(number).ok())
+        .ok_or(LevelError::InvalidField(name))
+}
+
+/// Converts a JSON string with exactly one character to a piece type.
+///
+/// # Arguments
+///
+/// * `value` - the JSON string
+/// * `name` - the name of the field that contains the string
+#[cfg(feature = "json")]
+fn parse_type(value: &Value, name: &'static str) -> Result<PieceType, LevelError> {
+    let text = value.as_str().ok_or(LevelError::InvalidField(name))?;
+    let mut chars = text.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(piece_type), None) => Ok(piece_type),
+        _ => Err(LevelError::InvalidField(name))
+    }
+}
+
+/// Converts a JSON array of [x, y] pairs to a set of positions.
+///
+/// # Arguments
+///
+/// * `value` - the JSON array
+/// * `name` - the name of the field that contains the array
+#[cfg(feature = "json")]
+fn parse_positions(value: &Value, name: &'static str) -> Result<PosSet, LevelError> {
+    value.as_array().ok_or(LevelError::InvalidField(name))?.iter().map(|pair| {
+        let coordinates = pair.as_array().filter(|pair| pair.len() == 2).ok_or(LevelError::InvalidField(name))?;
+        let coordinate = |index: usize| coordinates[index].as_u64()
+            .and_then(|number| u8::try_from(number).ok())
+            .ok_or(LevelError::InvalidField(name));
+        Ok(Pos::new(coordinate(0)?, coordinate(1)?))
+    }).collect()
+}
+
+/// Converts a JSON array of rows, from the top row to the bottom row,
+/// to a board state.
+///
+/// # Arguments
+///
+/// * `value` - the JSON array of rows
+#[cfg(feature = "json")]
+fn parse_layout(value: &Value) -> Result<BoardState, LevelError> {
+    let rows = value.as_array()
+        .ok_or(LevelError::InvalidField("layout"))?
+        .iter()
+        .map(|row| row.as_str().map(|row| row.chars().collect::<Vec<char>>()))
+        .collect::<Option<Vec<Vec<char>>>>()
+        .ok_or(LevelError::InvalidField("layout"))?;
+
+    let height = u8::try_from(rows.len()).map_err(|_| LevelError::InvalidField("layout"))?;
+    let width = u8::try_from(rows.first().map_or(0, |row| row.len()))
+        .map_err(|_| LevelError::InvalidField("layout"))?;
+    if rows.iter().any(|row| row.len() != usize::from(width)) {
+        return Err(LevelError::InvalidField("layout"));
+    }
+
+    let mut board = Board::new(BoardState::new(width, height), Vec::new(), Vec::new());
+    for (row_index, row) in rows.iter().enumerate() {
+        let y = height - 1 - row_index as u8;
+        for (x, &symbol) in row.iter().enumerate() {
+            let piece = match symbol {
+                '#' => Piece::Wall,
+                '.' => Piece::Empty,
+                piece_type => Piece::Regular(piece_type, ALL_DIRECTIONS)
+            };
+            board.set_piece(Pos::new(x as u8, y), piece);
+        }
+    }
+
+    let mut state = board.state().clone();
+    while state.take_changed(0).is_some() {}
+    Ok(state)
+}
+
+/// Converts a JSON object to a match pattern.
+///
+/// # Arguments
+///
+/// * `value` - the JSON object
+#[cfg(feature = "json")]
+fn parse_pattern(value: &Value) -> Result<MatchPattern, LevelError> {
+    let object = value.as_object().ok_or(LevelError::InvalidField("patterns"))?;
+    let pattern = MatchPattern::new(
+        parse_type(field(object, "piece")?, "piece")?,
+        parse_positions(field(object, "spaces")?, "spaces")?,
+        parse_u32(field(object, "rank")?, "rank")?
+    );
+
+    match object.get("tag") {
+        Some(tag) => Ok(pattern.with_tag(tag.as_str().ok_or(LevelError::InvalidField("tag"))?)),
+        None => Ok(pattern)
+    }
+}
+
+/// Converts a JSON object to a goal.
+///
+/// # Arguments
+///
+/// * `value` - the JSON object
+#[cfg(feature = "json")]
+fn parse_goal(value: &Value) -> Result<Goal, LevelError> {
+    let object = value.as_object().ok_or(LevelError::InvalidField("goals"))?;
+    let count = || field(object, "count")?.as_u64().ok_or(LevelError::InvalidField("count"));
+
+    match field(object, "type")?.as_str() {
+        Some("clear") => Ok(Goal::Clear { piece_type: parse_type(field(object, "piece")?, "piece")?, count: count()? }),
+        Some("clear_cells") => Ok(Goal::ClearCells(parse_positions(field(object, "cells")?, "cells")?)),
+        Some("score") => Ok(Goal::Score(
+            field(object, "target")?.as_u64().ok_or(LevelError::InvalidField("target"))?
+        )),
+        Some("deliver") => Ok(Goal::Deliver { piece_type: parse_type(field(object, "piece")?, "piece")?, count: count()? }),
+        _ => Err(LevelError::InvalidField("type"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, BoardState};
+    use crate::goals::Goal;
+    use crate::levels::{validate_level, Level, LevelConstraints};
+    #[cfg(feature = "json")]
+    use crate::levels::LevelError;
+    use crate::matching::MatchPattern;
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::session::SessionLimit;
+    use std::collections::HashSet;
+
+    fn level() -> (BoardState, Vec<MatchPattern>) {
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(0, 0));
+        spaces.insert(Pos::new(1, 0));
+        spaces.insert(Pos::new(2, 0));
+        let patterns = vec![MatchPattern::new('f', spaces, 1)];
+        let mut board = Board::new(BoardState::new(3, 2), patterns.clone(), Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 1), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 1), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 1), Piece::Regular('s', ALL_DIRECTIONS));
+        while board.next_match().is_some() {}
+
+        (board.state().clone(), patterns)
+    }
+
+    #[test]
+    fn validate_level_completable_all_seeds_valid() {
+        let (state, patterns) = level();
+        let constraints = LevelConstraints::new(
+            vec![Goal::Clear { piece_type: 'f', count: 3 }],
+            1,
+            vec!['g']
+        ).with_seeds(0..10);
+
+        let report = validate_level(&state, &patterns, &constraints);
+
+        assert_eq!(10, report.playouts());
+        assert_eq!(10, report.wins());
+        assert_eq!(1.0, report.success_rate());
+        assert_eq!(Some(1.0), report.average_moves_to_win());
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn validate_level_goal_too_large_invalid() {
+        let (state, patterns) = level();
+        let constraints = LevelConstraints::new(
+            vec![Goal::Clear { piece_type: 'f', count: 100 }],
+            3,
+            vec!['g']
+        ).with_seeds(0..10);
+
+        let report = validate_level(&state, &patterns, &constraints);
+
+        assert_eq!(0, report.wins());
+        assert_eq!(None, report.average_moves_to_win());
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn validate_level_min_success_rate_met_valid() {
+        let (state, patterns) = level();
+        let constraints = LevelConstraints::new(
+            vec![Goal::Clear { piece_type: 'f', count: 100 }],
+            3,
+            vec!['g']
+        ).with_min_success_rate(0.0);
+
+        assert!(validate_level(&state, &patterns, &constraints).is_valid());
+    }
+
+    #[test]
+    fn validate_level_no_seeds_invalid() {
+        let (state, patterns) = level();
+        let constraints = LevelConstraints::new(
+            vec![Goal::Clear { piece_type: 'f', count: 3 }],
+            1,
+            vec!['g']
+        ).with_seeds(0..0);
+
+        let report = validate_level(&state, &patterns, &constraints);
+
+        assert_eq!(0, report.playouts());
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_no_spawn_types_panics() {
+        LevelConstraints::new(Vec::new(), 1, Vec::new());
+    }
+
+    #[test]
+    fn session_same_seed_same_spawns() {
+        let (state, patterns) = level();
+        let level = Level::new(
+            state,
+            patterns,
+            vec![('f', 1), ('s', 1), ('t', 1)],
+            vec![Goal::Score(1000)],
+            SessionLimit::Moves(5)
+        );
+        let mut first = level.session(4);
+        let mut second = level.session(4);
+
+        first.swap_pieces(Pos::new(1, 0), Pos::new(1, 1));
+        second.swap_pieces(Pos::new(1, 0), Pos::new(1, 1));
+
+        assert_eq!(first.board().state(), second.board().state());
+        assert_eq!(SessionLimit::Moves(5), first.limit());
+    }
+
+    #[test]
+    #[should_panic]
+    fn level_new_no_spawn_weight_panics() {
+        let (state, patterns) = level();
+        Level::new(state, patterns, vec![('f', 0)], Vec::new(), SessionLimit::Moves(1));
+    }
+
+    #[cfg(feature = "json")]
+    const LEVEL_JSON: &str = r##"{
+        "layout": ["#s.", "sfs", "fsf"],
+        "patterns": [{ "piece": "f", "rank": 1, "spaces": [[0, 0], [1, 0], [2, 0]], "tag": "fruit" }],
+        "spawn_weights": { "f": 2, "s": 1 },
+        "goals": [
+            { "type": "clear", "piece": "f", "count": 3 },
+            { "type": "clear_cells", "cells": [[0, 0], [1, 0]] },
+            { "type": "score", "target": 100 },
+            { "type": "deliver", "piece": "i", "count": 1 }
+        ],
+        "moves": 20
+    }"##;
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn from_json_valid_level_loaded() {
+        let level = Level::from_json(LEVEL_JSON).unwrap();
+        let board = level.board();
+
+        assert_eq!(3, board.state().width);
+        assert_eq!(3, board.state().height);
+        assert_eq!(Piece::Wall, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(1, 1)));
+        assert_eq!(Some("fruit"), level.patterns()[0].tag());
+        assert_eq!(&[('f', 2), ('s', 1)], level.spawn_weights());
+        assert_eq!(4, level.goals().len());
+        assert_eq!(Goal::Score(100), level.goals()[2]);
+        assert_eq!(SessionLimit::Moves(20), level.limit());
+        assert!(board.state().last_changed.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn from_json_session_plays_level() {
+        let level = Level::from_json(LEVEL_JSON).unwrap();
+        let mut session = level.session(0);
+
+        assert_eq!(Some(1), session.swap_pieces(Pos::new(1, 0), Pos::new(1, 1)));
+        assert_eq!(3, session.goals().progress(0));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn from_json_turn_limit_loaded() {
+        let text = LEVEL_JSON.replace("\"moves\"", "\"turns\"");
+        assert_eq!(SessionLimit::Turns(20), Level::from_json(&text).unwrap().limit());
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn from_json_invalid_json_syntax_error() {
+        assert!(matches!(Level::from_json("{"), Err(LevelError::Syntax(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn from_json_missing_limit_error() {
+        let text = LEVEL_JSON.replace(",\n        \"moves\": 20", "");
+        assert_eq!(Err(LevelError::MissingField("moves")), Level::from_json(&text));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn from_json_uneven_rows_error() {
+        let text = LEVEL_JSON.replace("\"#s.\"", "\"#s\"");
+        assert_eq!(Err(LevelError::InvalidField("layout")), Level::from_json(&text));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn from_json_unknown_goal_error() {
+        let text = LEVEL_JSON.replace("\"score\"", "\"points\"");
+        assert_eq!(Err(LevelError::InvalidField("type")), Level::from_json(&text));
+    }
+}
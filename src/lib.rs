@@ -1,10 +1,75 @@
+
+#[cfg(feature = "analytics")]
+pub mod analytics;
+#[cfg(feature = "bevy")]
+pub mod bevy_plugin;
 mod bitboard;
+mod blocks;
 mod board;
+mod cascade;
+mod context;
+mod events;
+mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod goals;
+mod invariants;
+mod levels;
+mod linked;
 mod matching;
+mod net;
 mod piece;
 mod position;
+mod posset;
+mod powerups;
+mod random;
+mod report;
+mod session;
+mod snapshot;
+mod solver;
+mod spawners;
+mod special;
+mod stats;
+#[cfg(test)]
+mod test_support;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod tick;
+mod transaction;
+mod types;
+mod versus;
+mod view;
+mod walls;
+mod zobrist;
 
+pub use bitboard::*;
+pub use blocks::*;
 pub use board::*;
+pub use cascade::*;
+pub use context::*;
+pub use events::*;
+pub use export::*;
+pub use goals::*;
+pub use invariants::*;
+pub use levels::*;
+pub use linked::*;
 pub use matching::*;
+pub use net::*;
 pub use piece::*;
-pub use position::*;
\ No newline at end of file
+pub use position::*;
+pub use posset::*;
+pub use powerups::*;
+pub use random::*;
+pub use report::*;
+pub use session::*;
+pub use snapshot::*;
+pub use solver::*;
+pub use spawners::*;
+pub use special::*;
+pub use stats::*;
+pub use tick::*;
+pub use transaction::*;
+pub use types::*;
+pub use versus::*;
+pub use view::*;
+pub use walls::*;
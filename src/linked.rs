@@ -0,0 +1,408 @@
+use crate::board::Board;
+use crate::cascade::Spawner;
+use crate::events::BoardEvent;
+use crate::piece::Piece;
+use crate::position::Pos;
+use crate::random::Rng;
+
+use std::convert::TryFrom;
+
+/// What happens on a linked board when pieces are cleared on another board.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LinkEffect {
+
+    /// Inserts a row of a piece at the bottom of the target board for every
+    /// `cleared_per_row` pieces cleared on the source board. Cleared pieces
+    /// that do not complete a row carry over to the next clear. See
+    /// [inject_garbage()](Board::inject_garbage).
+    SendGarbage {
+        piece: Piece,
+        cleared_per_row: usize
+    },
+
+    /// Unlocks a region of the target board once `after_cleared` pieces in
+    /// total have been cleared on the source board. The target board is then
+    /// resolved so that the unlocked pieces fall. See [unlock_region()](Board::unlock_region).
+    UnlockRegion {
+        from: Pos,
+        to: Pos,
+        after_cleared: usize
+    }
+
+}
+
+/// Several boards played together, such as in split-screen co-op, where
+/// clearing pieces on one board causes effects on another. Effects only
+/// travel along the links that are added. A single seed determines every
+/// board's random numbers, so linked games replay identically.
+pub struct LinkedBoards {
+    boards: Vec<Board>,
+    spawners: Vec<Box<dyn Spawner>>,
+    links: Vec<(usize, usize, LinkEffect)>,
+    link_progress: Vec<usize>,
+    cleared: Vec<usize>,
+    rng: Rng
+}
+
+impl LinkedBoards {
+
+    /// Creates a new set of linked boards without any links. Each board is
+    /// seeded from one random number generator created with the given seed.
+    ///
+    /// # Arguments
+    ///
+    /// * `boards` - the boards to link
+    /// * `spawners` - chooses the new pieces that fill each board
+    /// * `seed` - the seed that determines every board's random numbers
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no boards or if the number of spawners is not
+    /// the same as the number of boards.
+    pub fn new(mut boards: Vec<Board>, spawners: Vec<Box<dyn Spawner>>, seed: u64) -> LinkedBoards {
+        if boards.is_empty() || boards.len() != spawners.len() {
+            panic!("Tried to link {} boards with {} spawners", boards.len(), spawners.len());
+        }
+
+        let mut rng = Rng::new(seed);
+        for board in boards.iter_mut() {
+            board.set_seed(rng.next_u64());
+        }
+
+        let board_count = boards.len();
+        LinkedBoards {
+            boards,
+            spawners,
+            links: Vec::new(),
+            link_progress: Vec::new(),
+            cleared: vec![0; board_count],
+            rng
+        }
+    }
+
+    /// Adds a link that causes an effect on one board when pieces are
+    /// cleared on another. A board may be linked to itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - the index of the board whose clears cause the effect
+    /// * `target` - the index of the board that the effect happens on
+    /// * `effect` - what happens on the target board
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is not the index of a board or if the effect
+    /// sends garbage for every zero pieces.
+    pub fn add_link(&mut self, source: usize, target: usize, effect: LinkEffect) {
+        if source >= self.boards.len() || target >= self.boards.len() {
+            panic!("Tried to link board {} to board {} with {} boards", source, target, self.boards.len());
+        }
+
+        if let LinkEffect::SendGarbage { cleared_per_row: 0, .. } = effect {
+            panic!("Tried to send garbage for every zero cleared pieces");
+        }
+
+        self.links.push((source, target, effect));
+        self.link_progress.push(0);
+    }
+
+    /// Gets the number of linked boards.
+    pub fn len(&self) -> usize {
+        self.boards.len()
+    }
+
+    /// Checks whether there are no linked boards, which is never true.
+    pub fn is_empty(&self) -> bool {
+        self.boards.is_empty()
+    }
+
+    /// Gets a board.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - the index of the board
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is not the index of a board.
+    pub fn board(&self, index: usize) -> &Board {
+        &self.boards[index]
+    }
+
+    /// Gets a board that can be changed directly. Changes made directly do
+    /// not cause any linked effects.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - the index of the board
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is not the index of a board.
+    pub fn board_mut(&mut self, index: usize) -> &mut Board {
+        &mut self.boards[index]
+    }
+
+    /// Gets the total number of pieces cleared on a board, including pieces
+    /// cleared by linked effects.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - the index of the board
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is not the index of a board.
+    pub fn cleared(&self, index: usize) -> usize {
+        self.cleared[index]
+    }
+
+    /// Gets the random number generator shared by the linked boards, such as
+    /// for seeding spawners.
+    pub fn rng_mut(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    /// Swaps two pieces on a board, resolves the matches that the swap causes,
+    /// and applies the linked effects of the cleared pieces. Effects that
+    /// cause more clears, such as unlocking pieces that then match, apply
+    /// their own linked effects. Returns the number of matches cleared on
+    /// the swapped board, or None if the swap was rejected.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - the index of the board to swap pieces on
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is not the index of a board or if either position
+    /// is outside the board.
+    pub fn swap_pieces(&mut self, index: usize, first: Pos, second: Pos) -> Option<usize> {
+        if !self.boards[index].swap_pieces(first, second) {
+            return None;
+        }
+
+        let (matches, cleared) = self.resolve(index);
+        let mut pending = vec![(index, cleared)];
+
+        while let Some((source, cleared)) = pending.pop() {
+            for link_index in 0..self.links.len() {
+                let (link_source, target, effect) = self.links[link_index];
+                if link_source != source || cleared == 0 {
+                    continue;
+                }
+
+                if let Some(target_cleared) = self.apply_link(link_index, target, effect, cleared) {
+                    pending.push((target, target_cleared));
+                }
+            }
+        }
+
+        Some(matches)
+    }
+
+    /// Resolves the matches on a board and counts the pieces cleared. Returns
+    /// the number of matches and the number of cleared pieces.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - the index of the board to resolve
+    fn resolve(&mut self, index: usize) -> (usize, usize) {
+        let mut cleared = 0;
+        let matches = self.boards[index].resolve_cascade(
+            self.spawners[index].as_mut(),
+            &mut |event: &BoardEvent| if let BoardEvent::Cleared { .. } = event {
+                cleared += 1;
+            }
+        );
+
+        self.cleared[index] += cleared;
+        (matches, cleared)
+    }
+
+    /// Applies a link's effect for pieces cleared on its source board. Returns
+    /// the number of pieces cleared on the target board if the target board
+    /// was resolved.
+    ///
+    /// # Arguments
+    ///
+    /// * `link_index` - the index of the link
+    /// * `target` - the index of the board that the effect happens on
+    /// * `effect` - what happens on the target board
+    /// * `cleared` - the number of pieces just cleared on the source board
+    fn apply_link(&mut self, link_index: usize, target: usize, effect: LinkEffect,
+                  cleared: usize) -> Option<usize> {
+        let progress = &mut self.link_progress[link_index];
+
+        match effect {
+            LinkEffect::SendGarbage { piece, cleared_per_row } => {
+                *progress += cleared;
+                let rows = *progress / cleared_per_row;
+                *progress %= cleared_per_row;
+
+                if rows > 0 {
+                    let rows = u8::try_from(rows).unwrap_or(u8::MAX);
+                    self.boards[target].inject_garbage(rows, piece);
+                }
+
+                None
+            },
+            LinkEffect::UnlockRegion { from, to, after_cleared } => {
+                let was_unlocked = *progress >= after_cleared;
+                *progress = progress.saturating_add(cleared);
+                if was_unlocked || *progress < after_cleared {
+                    return None;
+                }
+
+                self.boards[target].unlock_region(from, to);
+                self.boards[target].trickle();
+                let (_, target_cleared) = self.resolve(target);
+                Some(target_cleared)
+            }
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::cascade::Spawner;
+    use crate::linked::{LinkEffect, LinkedBoards};
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::test_support::board_from_rows;
+
+    fn spawner() -> Box<dyn Spawner> {
+        Box::new(|_: &Board, _| Piece::Regular('e', ALL_DIRECTIONS))
+    }
+
+    /// Links two 3x3 boards where swapping (1, 0) and (1, 1) makes three 'a'
+    /// pieces in a row at the bottom.
+    fn linked() -> LinkedBoards {
+        let boards = vec![
+            board_from_rows(&["cdc", "dad", "aba"], &["aaa"]),
+            board_from_rows(&["cdc", "dad", "aba"], &["aaa"])
+        ];
+        LinkedBoards::new(boards, vec![spawner(), spawner()], 3)
+    }
+
+    #[test]
+    fn swap_pieces_match_counts_cleared() {
+        let mut linked = linked();
+
+        assert_eq!(Some(1), linked.swap_pieces(0, Pos::new(1, 0), Pos::new(1, 1)));
+
+        assert_eq!(3, linked.cleared(0));
+        assert_eq!(0, linked.cleared(1));
+    }
+
+    #[test]
+    fn swap_pieces_rejected_none() {
+        let mut linked = linked();
+        linked.board_mut(0).set_piece(Pos::new(1, 1), Piece::Wall);
+
+        assert_eq!(None, linked.swap_pieces(0, Pos::new(1, 0), Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn swap_pieces_send_garbage_link_injects_rows() {
+        let mut linked = linked();
+        linked.add_link(0, 1, LinkEffect::SendGarbage { piece: Piece::Wall, cleared_per_row: 2 });
+
+        linked.swap_pieces(0, Pos::new(1, 0), Pos::new(1, 1));
+
+        assert_eq!(Piece::Wall, linked.board(1).piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), linked.board(1).piece(Pos::new(0, 1)));
+    }
+
+    #[test]
+    fn swap_pieces_send_garbage_remainder_carries_over() {
+        let mut linked = linked();
+        linked.add_link(0, 1, LinkEffect::SendGarbage { piece: Piece::Wall, cleared_per_row: 4 });
+
+        linked.swap_pieces(0, Pos::new(1, 0), Pos::new(1, 1));
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), linked.board(1).piece(Pos::new(0, 0)));
+
+        *linked.board_mut(0) = board_from_rows(&["cdc", "dad", "aba"], &["aaa"]);
+        linked.swap_pieces(0, Pos::new(1, 0), Pos::new(1, 1));
+        assert_eq!(Piece::Wall, linked.board(1).piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn swap_pieces_unlock_link_unlocks_and_resolves_target() {
+        let mut linked = linked();
+        linked.board_mut(1).lock_region(Pos::new(1, 0), Pos::new(1, 1));
+        linked.add_link(0, 1, LinkEffect::UnlockRegion { from: Pos::new(1, 0), to: Pos::new(1, 1), after_cleared: 3 });
+
+        linked.swap_pieces(0, Pos::new(1, 0), Pos::new(1, 1));
+
+        assert!(!linked.board(1).is_locked(Pos::new(1, 0)));
+        assert_eq!(0, linked.cleared(1));
+    }
+
+    #[test]
+    fn swap_pieces_unlock_link_chains_clears() {
+        let mut linked = linked();
+        let target = linked.board_mut(1);
+        target.set_piece(Pos::new(1, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        target.lock_region(Pos::new(0, 0), Pos::new(2, 0));
+        linked.add_link(0, 1, LinkEffect::UnlockRegion { from: Pos::new(0, 0), to: Pos::new(2, 0), after_cleared: 3 });
+        linked.add_link(1, 0, LinkEffect::SendGarbage { piece: Piece::Wall, cleared_per_row: 3 });
+
+        linked.swap_pieces(0, Pos::new(1, 0), Pos::new(1, 1));
+
+        assert_eq!(3, linked.cleared(1));
+        assert_eq!(Piece::Wall, linked.board(0).piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn swap_pieces_unlock_link_only_once() {
+        let mut linked = linked();
+        linked.board_mut(1).lock_region(Pos::new(0, 0), Pos::new(0, 0));
+        linked.add_link(0, 1, LinkEffect::UnlockRegion { from: Pos::new(0, 0), to: Pos::new(0, 0), after_cleared: 3 });
+        linked.swap_pieces(0, Pos::new(1, 0), Pos::new(1, 1));
+        linked.board_mut(1).lock_region(Pos::new(0, 0), Pos::new(0, 0));
+
+        *linked.board_mut(0) = board_from_rows(&["cdc", "dad", "aba"], &["aaa"]);
+        linked.swap_pieces(0, Pos::new(1, 0), Pos::new(1, 1));
+
+        assert!(linked.board(1).is_locked(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn new_same_seed_boards_shuffle_identically() {
+        let mut first = linked();
+        let mut second = linked();
+
+        first.board_mut(1).shuffle();
+        second.board_mut(1).shuffle();
+
+        assert_eq!(first.board(1).state(), second.board(1).state());
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_spawner_count_mismatch_panics() {
+        let boards = vec![
+            board_from_rows(&["cdc", "dad", "aba"], &["aaa"]),
+            board_from_rows(&["cdc", "dad", "aba"], &["aaa"])
+        ];
+        LinkedBoards::new(boards, vec![spawner()], 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_link_unknown_board_panics() {
+        linked().add_link(0, 2, LinkEffect::SendGarbage { piece: Piece::Wall, cleared_per_row: 3 });
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_link_zero_garbage_rate_panics() {
+        linked().add_link(0, 1, LinkEffect::SendGarbage { piece: Piece::Wall, cleared_per_row: 0 });
+    }
+}
@@ -1,8 +1,11 @@
+use crate::bitboard::BitBoard;
 use crate::board::PosSet;
 use crate::piece::PieceType;
 use crate::position::Pos;
 
+use std::convert::TryFrom;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 /// A pattern of piece positions that represents a valid match on a board.
 #[derive(Clone, Eq, PartialEq, Debug, Default)]
@@ -10,7 +13,15 @@ use std::fmt::{Display, Formatter};
 pub struct MatchPattern {
     piece_type: PieceType,
     spaces: PosSet,
-    rank: u32
+    rank: u32,
+    tag: Option<String>,
+    offsets: Vec<Pos>,
+    mask: BitBoard,
+    cluster_size: Option<usize>,
+    is_any_type: bool,
+    anchor: Option<PatternAnchor>,
+    group: Option<String>,
+    id: usize
 }
 
 impl MatchPattern {
@@ -20,19 +31,128 @@ impl MatchPattern {
     /// # Arguments
     ///
     /// * `spaces` - a set of unique positions that represents a pattern.
-    ///              The values of the positions do not matter: only their
-    ///              relative positions matter.
+    ///   The values of the positions do not matter: only their
+    ///   relative positions matter.
     /// * `rank`    - the rank of a match. A higher ranked match takes precedence over
-    ///               a lower ranked one.
+    ///   a lower ranked one.
     pub fn new(piece_type: PieceType, spaces: PosSet, rank: u32) -> MatchPattern {
         let min_x = spaces.iter().map(|space | space.x()).min().unwrap_or(0);
         let min_y = spaces.iter().map(|space | space.y()).min().unwrap_or(0);
 
-        let spaces_around_origin = spaces.iter().map(
+        let spaces_around_origin: PosSet = spaces.iter().map(
             |space| Pos::new(space.x() - min_x, space.y() - min_y)
         ).collect();
 
-        MatchPattern { piece_type, spaces: spaces_around_origin, rank }
+        let max_x = spaces_around_origin.iter().map(|space| space.x()).max();
+        let max_y = spaces_around_origin.iter().map(|space| space.y()).max();
+
+        /* A pattern that spans 256 spaces cannot fit on any board, so it has no
+           offsets to check and never matches. */
+        let (offsets, mask) = match (max_x, max_y) {
+            (Some(max_x), Some(max_y)) if max_x < u8::MAX && max_y < u8::MAX => {
+                let mut offsets: Vec<Pos> = spaces_around_origin.iter().copied().collect();
+                offsets.sort();
+
+                let mut mask = BitBoard::new(max_x + 1, max_y + 1);
+                offsets.iter().for_each(|&offset| mask.set(offset));
+
+                (offsets, mask)
+            },
+            _ => (Vec::new(), BitBoard::new(0, 0))
+        };
+
+        MatchPattern {
+            piece_type,
+            spaces: spaces_around_origin,
+            rank,
+            tag: None,
+            offsets,
+            mask,
+            cluster_size: None,
+            is_any_type: false,
+            anchor: None,
+            group: None,
+            id: 0
+        }
+    }
+
+    /// Creates a pattern that matches any group of at least a minimum number of
+    /// pieces of one type that are connected horizontally or vertically, as in
+    /// "collapse" games. The match contains every piece in the group. A cluster
+    /// pattern has no spaces.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of pieces in the group
+    /// * `min_size` - the fewest pieces a group needs to match
+    /// * `rank` - the rank of a match. A higher ranked match takes precedence over
+    ///   a lower ranked one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the minimum size is zero.
+    pub fn cluster(piece_type: PieceType, min_size: usize, rank: u32) -> MatchPattern {
+        if min_size == 0 {
+            panic!("Tried to create cluster pattern with no minimum size");
+        }
+
+        MatchPattern { cluster_size: Some(min_size), ..MatchPattern::new(piece_type, PosSet::new(), rank) }
+    }
+
+    /// Creates a cluster pattern like [cluster()](MatchPattern::cluster) that
+    /// matches groups of every piece type, which makes cluster matching the
+    /// board's matching mode for all types. Each group still contains only one
+    /// type. Use [Match::piece_type()] to find the type of a match. The
+    /// pattern's own type is `'*'`, and tags are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_size` - the fewest pieces a group needs to match
+    /// * `rank` - the rank of a match. A higher ranked match takes precedence over
+    ///   a lower ranked one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the minimum size is zero.
+    pub fn any_cluster(min_size: usize, rank: u32) -> MatchPattern {
+        MatchPattern { is_any_type: true, ..MatchPattern::cluster('*', min_size, rank) }
+    }
+
+    /// Makes this pattern also match pieces of any type that has a tag. The
+    /// pattern matches any combination of pieces with its own type and pieces
+    /// with the tag, so a match may not contain any pieces of the pattern's type.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - the tag of the piece types that this pattern also matches
+    pub fn with_tag(mut self, tag: &str) -> MatchPattern {
+        self.tag = Some(tag.to_string());
+        self
+    }
+
+    /// Makes this pattern only match when at least one of the matched
+    /// positions is in a fixed place on the board, such as for objectives
+    /// that require a match in the bottom row or on a specific space.
+    ///
+    /// # Arguments
+    ///
+    /// * `anchor` - the places on the board that a match must include
+    pub fn with_anchor(mut self, anchor: PatternAnchor) -> MatchPattern {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    /// Places this pattern in a group of related patterns, such as every
+    /// rotation of one shape. Matches report the group so that scoring and
+    /// special-piece rules can refer to the shape instead of each pattern.
+    /// [PatternSet] groups patterns automatically.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - the identifier of the group
+    pub fn with_group(mut self, group: &str) -> MatchPattern {
+        self.group = Some(group.to_string());
+        self
     }
 
     /// Gets the type of pieces in this pattern.
@@ -50,10 +170,162 @@ impl MatchPattern {
         self.rank
     }
 
+    /// Gets the tag of the other piece types that this pattern matches, if any.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// Gets the fewest pieces in a group that this pattern matches, or None if
+    /// this is not a cluster pattern.
+    pub fn cluster_size(&self) -> Option<usize> {
+        self.cluster_size
+    }
+
+    /// Checks whether this pattern matches clusters of every piece type.
+    pub fn is_any_type(&self) -> bool {
+        self.is_any_type
+    }
+
+    /// Gets the places on the board that a match of this pattern must include,
+    /// if any.
+    pub fn anchor(&self) -> Option<PatternAnchor> {
+        self.anchor
+    }
+
+    /// Gets the identifier of the group that this pattern belongs to, if any.
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    /// Gets the index of this pattern in the list given to the [Board](crate::Board).
+    /// The ID does not change when the board sorts its patterns by rank. A
+    /// pattern that has not been given to a board has an ID of zero.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Gets the fewest pieces that a match of this pattern contains.
+    pub fn min_pieces(&self) -> usize {
+        self.cluster_size.unwrap_or(self.spaces.len())
+    }
+
+    /// Checks whether a group of positions on the board meets this pattern's
+    /// anchor. A pattern without an anchor accepts any positions.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - the positions of a possible match on the board
+    pub(crate) fn is_anchored(&self, positions: &PosSet) -> bool {
+        self.anchor.map_or(true, |anchor| positions.iter().any(|&pos| anchor.includes(pos)))
+    }
+
+    /// Changes the ID of this pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - the index of this pattern in the board's original pattern list
+    pub(crate) fn set_id(&mut self, id: usize) {
+        self.id = id;
+    }
+
+    /// Checks whether this pattern meets its anchor when its origin is moved to
+    /// a position on the board, without collecting the pattern's positions.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - the position on the board of the pattern's origin
+    pub(crate) fn is_anchored_at(&self, origin: Pos) -> bool {
+        self.anchor.map_or(true, |anchor| self.offsets.iter().any(|&offset| anchor.includes(offset + origin)))
+    }
+
+    /// Gets the relative positions in this pattern in a consistent order.
+    pub(crate) fn offsets(&self) -> &[Pos] {
+        &self.offsets
+    }
+
+    /// Gets the pattern as a bitboard whose size is the smallest rectangle
+    /// containing every space in the pattern.
+    pub(crate) fn mask(&self) -> &BitBoard {
+        &self.mask
+    }
+
+}
+
+/// A pattern prepared for one board size. The origins where the whole pattern
+/// fits on the board and meets its anchor are found once, so checking a
+/// position for a match only compares the pattern's mask with the board at
+/// those origins.
+#[derive(Clone, Debug)]
+pub(crate) struct CompiledPattern {
+    origins: BitBoard
+}
+
+impl CompiledPattern {
+
+    /// Prepares a pattern for a board size.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - the pattern to prepare
+    /// * `width` - the width of the board
+    /// * `height` - the height of the board
+    pub(crate) fn new(pattern: &MatchPattern, width: u8, height: u8) -> CompiledPattern {
+        let mut origins = BitBoard::new(width, height);
+        let mask = pattern.mask();
+        let fits = !pattern.offsets().is_empty() && mask.width() <= width && mask.height() <= height;
+
+        if fits {
+            for y in 0..=(height - mask.height()) {
+                for x in 0..=(width - mask.width()) {
+                    let origin = Pos::new(x, y);
+                    if pattern.is_anchored_at(origin) {
+                        origins.set(origin);
+                    }
+                }
+            }
+        }
+
+        CompiledPattern { origins }
+    }
+
+    /// Prepares every pattern in a list for a board size, in the same order.
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - the patterns to prepare
+    /// * `width` - the width of the board
+    /// * `height` - the height of the board
+    pub(crate) fn compile_all(patterns: &[MatchPattern], width: u8, height: u8) -> Vec<CompiledPattern> {
+        patterns.iter().map(|pattern| CompiledPattern::new(pattern, width, height)).collect()
+    }
+
+    /// Checks whether this pattern was prepared for a board size.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - the width of the board
+    /// * `height` - the height of the board
+    pub(crate) fn is_for_size(&self, width: u8, height: u8) -> bool {
+        self.origins.width() == width && self.origins.height() == height
+    }
+
+    /// Gets the origins where the whole pattern is on the board and meets its
+    /// anchor. Spaces where the pattern cannot be placed are unset.
+    pub(crate) fn origins(&self) -> &BitBoard {
+        &self.origins
+    }
+
 }
 
 impl Display for MatchPattern {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(min_size) = self.cluster_size {
+            return match self.is_any_type {
+                true => write!(f, "cluster of {}+ of any type\nr = {}", min_size, self.rank),
+                false => write!(f, "cluster of {}+ {}\nr = {}", min_size, self.piece_type, self.rank)
+            };
+        }
+
         let mut str = String::new();
 
         let type_abbreviation = self.piece_type;
@@ -79,12 +351,278 @@ impl Display for MatchPattern {
     }
 }
 
+/// Related patterns that share an identifier and a rank, such as every
+/// rotation of a T shape. A match of any pattern in the set reports the set's
+/// identifier as its [group](Match::group).
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct PatternSet {
+    id: String,
+    rank: u32,
+    patterns: Vec<MatchPattern>
+}
+
+impl PatternSet {
+
+    /// Creates a new set without any patterns.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - the identifier reported by matches of patterns in this set
+    /// * `rank` - the rank of every pattern in this set
+    pub fn new(id: &str, rank: u32) -> PatternSet {
+        PatternSet { id: id.to_string(), rank, patterns: Vec::new() }
+    }
+
+    /// Adds one shape to this set.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of pieces in the shape
+    /// * `spaces` - the relative positions of the shape
+    pub fn with_shape(mut self, piece_type: PieceType, spaces: PosSet) -> PatternSet {
+        self.push(MatchPattern::new(piece_type, spaces, self.rank));
+        self
+    }
+
+    /// Adds a shape and its rotations by 90, 180, and 270 degrees to this set.
+    /// Rotations that are the same as a shape already in the set are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of pieces in the shape
+    /// * `spaces` - the relative positions of the shape
+    pub fn with_rotations(mut self, piece_type: PieceType, spaces: PosSet) -> PatternSet {
+        let mut pattern = MatchPattern::new(piece_type, spaces, self.rank);
+
+        for _ in 0..4 {
+            let max_x = pattern.spaces.iter().map(|space| space.x()).max().unwrap_or(0);
+            let rotated = pattern.spaces.iter().map(|space| Pos::new(space.y(), max_x - space.x())).collect();
+
+            let next = MatchPattern::new(piece_type, rotated, self.rank);
+            self.push(pattern);
+            pattern = next;
+        }
+
+        self
+    }
+
+    /// Gets the identifier of this set.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Gets the rank of every pattern in this set.
+    pub fn rank(&self) -> u32 {
+        self.rank
+    }
+
+    /// Gets the patterns in this set.
+    pub fn patterns(&self) -> &[MatchPattern] {
+        &self.patterns
+    }
+
+    /// Converts this set into its patterns, each of which is in the set's group.
+    pub fn into_patterns(self) -> Vec<MatchPattern> {
+        self.patterns
+    }
+
+    /// Adds a pattern to this set unless the set already has one with the
+    /// same type and spaces.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - the pattern to add
+    fn push(&mut self, pattern: MatchPattern) {
+        let is_duplicate = self.patterns.iter().any(
+            |other| other.piece_type == pattern.piece_type && other.spaces == pattern.spaces
+        );
+
+        if !is_duplicate {
+            self.patterns.push(pattern.with_group(&self.id));
+        }
+    }
+
+}
+
+impl IntoIterator for PatternSet {
+    type Item = MatchPattern;
+    type IntoIter = std::vec::IntoIter<MatchPattern>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.patterns.into_iter()
+    }
+}
+
+/// The places on the board where a [MatchPattern] with an anchor must have
+/// at least one matched piece.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PatternAnchor {
+
+    /// A single space on the board.
+    Cell(Pos),
+
+    /// Any space in the row with this y coordinate. The bottom row is 0.
+    Row(u8),
+
+    /// Any space in the column with this x coordinate.
+    Column(u8)
+
+}
+
+impl PatternAnchor {
+
+    /// Checks whether a position on the board is one of the anchor's places.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to check
+    pub fn includes(&self, pos: Pos) -> bool {
+        match *self {
+            PatternAnchor::Cell(cell) => pos == cell,
+            PatternAnchor::Row(y) => pos.y() == y,
+            PatternAnchor::Column(x) => pos.x() == x
+        }
+    }
+
+}
+
+/// Why a [MatchPattern] could not be parsed from text.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum PatternParseError {
+
+    /// The text does not contain any spaces of the pattern.
+    Empty,
+
+    /// The pattern contains spaces for more than one piece type.
+    MixedTypes(PieceType, PieceType),
+
+    /// The pattern is wider or taller than the largest possible board.
+    TooLarge,
+
+    /// The rank line is not a valid rank.
+    InvalidRank(String)
+
+}
+
+impl Display for PatternParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatternParseError::Empty => write!(f, "Pattern has no spaces"),
+            PatternParseError::MixedTypes(first, second) =>
+                write!(f, "Pattern has more than one piece type: {} and {}", first, second),
+            PatternParseError::TooLarge => write!(f, "Pattern is larger than a board"),
+            PatternParseError::InvalidRank(line) => write!(f, "Invalid pattern rank: {}", line)
+        }
+    }
+}
+
+impl std::error::Error for PatternParseError {}
+
+impl FromStr for MatchPattern {
+    type Err = PatternParseError;
+
+    /// Parses a pattern drawn as text, in the same format that the pattern is
+    /// displayed in. Each line is a row, with the top row first. The character
+    /// for the pattern's piece type marks the spaces in the pattern, and `.`
+    /// marks the spaces that are not. An optional last line of the form
+    /// `r = 10` sets the rank, which is 1 by default. Whitespace around each
+    /// line is ignored, and blank lines are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - the pattern drawn as text, like `"XX.\n.XX"`
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut rows: Vec<&str> = text.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+
+        let mut rank = 1;
+        let rank_line = rows.last().copied();
+        let rank_value = rank_line.and_then(|line| line.strip_prefix('r'))
+            .and_then(|rest| rest.trim_start().strip_prefix('='));
+        if let (Some(line), Some(rank_value)) = (rank_line, rank_value) {
+            rank = rank_value.trim().parse().map_err(|_| PatternParseError::InvalidRank(line.to_string()))?;
+            rows.pop();
+        }
+
+        let height = u8::try_from(rows.len()).map_err(|_| PatternParseError::TooLarge)?;
+        let mut piece_type = None;
+        let mut spaces = PosSet::new();
+
+        for (row_index, row) in rows.iter().enumerate() {
+            let y = height - 1 - row_index as u8;
+            for (x, space) in row.chars().enumerate() {
+                if space == '.' {
+                    continue;
+                }
+
+                match piece_type {
+                    Some(existing) if existing != space => return Err(PatternParseError::MixedTypes(existing, space)),
+                    _ => piece_type = Some(space)
+                }
+
+                let x = u8::try_from(x).map_err(|_| PatternParseError::TooLarge)?;
+                spaces.insert(Pos::new(x, y));
+            }
+        }
+
+        match piece_type {
+            Some(piece_type) => Ok(MatchPattern::new(piece_type, spaces, rank)),
+            None => Err(PatternParseError::Empty)
+        }
+    }
+}
+
+/// The order in which the board checks positions that are marked for a match check.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatchOrder {
+
+    /// Positions that were changed earlier are checked first.
+    #[default]
+    Fifo,
+
+    /// Positions that were changed more recently are checked first. A position
+    /// that is changed again before it is checked keeps its original place.
+    Lifo,
+
+    /// Positions in lower rows are checked first, and positions in the same
+    /// row are checked from left to right. Cascades resolve from the bottom
+    /// of the board upward.
+    ByRow,
+
+    /// The match with the highest-ranked pattern among all marked positions
+    /// is found first. Every marked position is checked for each match.
+    ByRank
+
+}
+
+/// What changed the position that triggered a match, so that rules can treat
+/// matches made by the player differently from matches made by the board.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatchSource {
+
+    /// The position was changed by a player's swap or rotation.
+    PlayerSwap,
+
+    /// The position was changed by the board while it resolved a cascade,
+    /// such as when pieces fell, were spawned, or were cleared by a match.
+    Gravity,
+
+    /// The position was changed directly by the game, such as by setting a
+    /// piece, converting pieces, or shuffling the board.
+    Scripted
+
+}
+
 // A match found in a board.
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Match<'a> {
     pattern: &'a MatchPattern,
+    piece_type: PieceType,
     changed_pos: Pos,
-    board_pos: PosSet
+    board_pos: PosSet,
+    source: MatchSource
 }
 
 impl Match<'_> {
@@ -96,8 +634,29 @@ impl Match<'_> {
     /// * `pattern` - the pattern of the found match
     /// * `changed_pos` - the position that was changed and triggered the match
     /// * `board_pos` - actual positions on the board
-    pub(crate) fn new(pattern: &MatchPattern, changed_pos: Pos, board_pos: PosSet) -> Match {
-        Match { pattern, changed_pos, board_pos }
+    pub(crate) fn new(pattern: &MatchPattern, changed_pos: Pos, board_pos: PosSet) -> Match<'_> {
+        Match { pattern, piece_type: pattern.piece_type(), changed_pos, board_pos, source: MatchSource::Scripted }
+    }
+
+    /// Changes what caused the change that triggered the match.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - what changed the position that triggered the match
+    pub(crate) fn with_source(mut self, source: MatchSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Changes the type of the pieces in the match, for patterns that match
+    /// any type.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of the matched pieces
+    pub(crate) fn with_piece_type(mut self, piece_type: PieceType) -> Self {
+        self.piece_type = piece_type;
+        self
     }
 
     /// Gets the pattern associated with this match.
@@ -105,23 +664,73 @@ impl Match<'_> {
         self.pattern
     }
 
+    /// Gets the type of the matched pieces. This is the pattern's type unless
+    /// the pattern [matches any type](MatchPattern::is_any_type).
+    pub fn piece_type(&self) -> PieceType {
+        self.piece_type
+    }
+
+    /// Gets the identifier of the [group](MatchPattern::group) of the pattern
+    /// that found this match, if any.
+    pub fn group(&self) -> Option<&str> {
+        self.pattern.group()
+    }
+
+    /// Gets the rank of the pattern that found this match.
+    pub fn rank(&self) -> u32 {
+        self.pattern.rank()
+    }
+
+    /// Gets the [ID](MatchPattern::id) of the pattern that found this match.
+    pub fn pattern_id(&self) -> usize {
+        self.pattern.id()
+    }
+
     /// Gets the changed position that triggered this match.
     pub fn changed_pos(&self) -> Pos {
         self.changed_pos
     }
 
+    /// Gets what changed the position that triggered this match.
+    pub fn source(&self) -> MatchSource {
+        self.source
+    }
+
     /// Gets all of the board positions where this pattern is located.
     pub fn board_pos(&self) -> &PosSet {
         &self.board_pos
     }
 
+    /// Adds positions to the match.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - the board positions to add
+    pub(crate) fn extend_board_pos(&mut self, positions: impl IntoIterator<Item = Pos>) {
+        self.board_pos.extend(positions);
+    }
+
+    /// Copies the details of this match into a match that does not borrow the
+    /// board's patterns, so that the board can be changed while the match is held.
+    pub fn into_owned(self) -> OwnedMatch {
+        OwnedMatch {
+            pattern_id: self.pattern_id(),
+            rank: self.rank(),
+            group: self.group().map(str::to_string),
+            piece_type: self.piece_type,
+            changed_pos: self.changed_pos,
+            board_pos: self.board_pos,
+            source: self.source
+        }
+    }
+
 }
 
 impl Display for Match<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let mut str = String::new();
 
-        let type_abbreviation = self.pattern().piece_type();
+        let type_abbreviation = self.piece_type();
 
         let max_x = self.board_pos.iter().map(|pos| pos.x()).max().unwrap_or(0);
         let max_y = self.board_pos.iter().map(|pos| pos.y()).max().unwrap_or(0);
@@ -148,9 +757,69 @@ impl Display for Match<'_> {
     }
 }
 
+/// A match found in a board that does not borrow its pattern. The pattern can
+/// be found with its [ID](OwnedMatch::pattern_id) if it is needed.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedMatch {
+    pattern_id: usize,
+    rank: u32,
+    group: Option<String>,
+    piece_type: PieceType,
+    changed_pos: Pos,
+    board_pos: PosSet,
+    source: MatchSource
+}
+
+impl OwnedMatch {
+
+    /// Gets the [ID](MatchPattern::id) of the pattern that found this match.
+    pub fn pattern_id(&self) -> usize {
+        self.pattern_id
+    }
+
+    /// Gets the rank of the pattern that found this match.
+    pub fn rank(&self) -> u32 {
+        self.rank
+    }
+
+    /// Gets the identifier of the [group](MatchPattern::group) of the pattern
+    /// that found this match, if any.
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    /// Gets the type of the matched pieces.
+    pub fn piece_type(&self) -> PieceType {
+        self.piece_type
+    }
+
+    /// Gets the changed position that triggered this match.
+    pub fn changed_pos(&self) -> Pos {
+        self.changed_pos
+    }
+
+    /// Gets all of the board positions where the pattern is located.
+    pub fn board_pos(&self) -> &PosSet {
+        &self.board_pos
+    }
+
+    /// Gets what changed the position that triggered this match.
+    pub fn source(&self) -> MatchSource {
+        self.source
+    }
+
+}
+
+impl From<Match<'_>> for OwnedMatch {
+    fn from(found: Match<'_>) -> Self {
+        found.into_owned()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::matching::{MatchPattern, Match};
+    use crate::matching::{CompiledPattern, MatchPattern, Match, PatternAnchor, PatternParseError, PatternSet};
     use std::collections::{HashSet};
     use crate::position::Pos;
 
@@ -246,6 +915,36 @@ mod tests {
         assert_eq!(expected_spaces, *pattern.spaces());
     }
 
+    #[test]
+    fn new_pattern_filled_set_mask_has_spaces() {
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(4, 6));
+        spaces.insert(Pos::new(5, 5));
+        spaces.insert(Pos::new(9, 10));
+
+        let pattern = MatchPattern::new('t', spaces, 10);
+
+        assert_eq!(vec![Pos::new(0, 1), Pos::new(1, 0), Pos::new(5, 5)], pattern.offsets());
+        assert_eq!("\
+        000001\
+        \n000000\
+        \n000000\
+        \n000000\
+        \n100000\
+        \n010000\
+        \n", format!("{}", pattern.mask()));
+    }
+
+    #[test]
+    fn new_pattern_wider_than_any_board_has_no_offsets() {
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(0, 0));
+        spaces.insert(Pos::new(u8::MAX, 0));
+
+        let pattern = MatchPattern::new('t', spaces, 10);
+        assert!(pattern.offsets().is_empty());
+    }
+
     #[test]
     fn new_pattern_created_with_rank_has_rank() {
         let mut spaces = HashSet::new();
@@ -257,6 +956,18 @@ mod tests {
         assert_eq!(10, pattern.rank());
     }
 
+    #[test]
+    fn new_pattern_no_tag() {
+        let pattern = MatchPattern::new('f', HashSet::new(), 1);
+        assert_eq!(None, pattern.tag());
+    }
+
+    #[test]
+    fn with_tag_pattern_has_tag() {
+        let pattern = MatchPattern::new('f', HashSet::new(), 1).with_tag("red");
+        assert_eq!(Some("red"), pattern.tag());
+    }
+
     #[test]
     fn new_pattern_created_with_type_has_type() {
         let mut spaces = HashSet::new();
@@ -288,6 +999,126 @@ mod tests {
         assert_eq!(expected, format!("{}", pattern));
     }
 
+    #[test]
+    fn from_str_rows_top_first() {
+        let pattern: MatchPattern = "XX.\n.XX".parse().unwrap();
+
+        let expected: HashSet<Pos> = vec![Pos::new(0, 1), Pos::new(1, 1), Pos::new(1, 0), Pos::new(2, 0)]
+            .into_iter().collect();
+        assert_eq!('X', pattern.piece_type());
+        assert_eq!(&expected, pattern.spaces());
+        assert_eq!(1, pattern.rank());
+    }
+
+    #[test]
+    fn from_str_rank_line_sets_rank() {
+        let pattern: MatchPattern = "
+            ttt
+            r = 7
+        ".parse().unwrap();
+
+        assert_eq!(7, pattern.rank());
+        assert_eq!(3, pattern.spaces().len());
+    }
+
+    #[test]
+    fn from_str_display_round_trip() {
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(3, 4));
+        spaces.insert(Pos::new(4, 2));
+        spaces.insert(Pos::new(5, 5));
+        let pattern = MatchPattern::new('t', spaces, 10);
+
+        let parsed: MatchPattern = pattern.to_string().parse().unwrap();
+
+        assert_eq!(pattern, parsed);
+    }
+
+    #[test]
+    fn from_str_r_type_not_rank() {
+        let pattern: MatchPattern = "rrr".parse().unwrap();
+
+        assert_eq!('r', pattern.piece_type());
+        assert_eq!(3, pattern.spaces().len());
+    }
+
+    #[test]
+    fn from_str_no_spaces_error() {
+        assert_eq!(Err(PatternParseError::Empty), "...\n...".parse::<MatchPattern>());
+    }
+
+    #[test]
+    fn from_str_mixed_types_error() {
+        assert_eq!(Err(PatternParseError::MixedTypes('a', 'b')), "ab".parse::<MatchPattern>());
+    }
+
+    #[test]
+    fn from_str_invalid_rank_error() {
+        assert_eq!(Err(PatternParseError::InvalidRank("r = high".to_string())), "aa\nr = high".parse::<MatchPattern>());
+    }
+
+    #[test]
+    fn from_str_too_wide_error() {
+        let row = "a".repeat(300);
+        assert_eq!(Err(PatternParseError::TooLarge), row.parse::<MatchPattern>());
+    }
+
+    #[test]
+    fn cluster_pattern_no_spaces_min_pieces_is_size() {
+        let pattern = MatchPattern::cluster('t', 4, 2);
+
+        assert!(pattern.spaces().is_empty());
+        assert_eq!(Some(4), pattern.cluster_size());
+        assert_eq!(4, pattern.min_pieces());
+        assert!(!pattern.is_any_type());
+    }
+
+    #[test]
+    fn any_cluster_pattern_matches_any_type() {
+        let pattern = MatchPattern::any_cluster(3, 1);
+
+        assert!(pattern.is_any_type());
+        assert_eq!(Some(3), pattern.cluster_size());
+    }
+
+    #[test]
+    #[should_panic]
+    fn cluster_pattern_zero_size_panics() {
+        MatchPattern::cluster('t', 0, 1);
+    }
+
+    #[test]
+    fn display_cluster_pattern_shows_size() {
+        assert_eq!("cluster of 4+ t\nr = 2", format!("{}", MatchPattern::cluster('t', 4, 2)));
+        assert_eq!("cluster of 3+ of any type\nr = 1", format!("{}", MatchPattern::any_cluster(3, 1)));
+    }
+
+    #[test]
+    fn new_pattern_min_pieces_is_space_count() {
+        let pattern: MatchPattern = "tt\n.t".parse().unwrap();
+
+        assert_eq!(None, pattern.cluster_size());
+        assert_eq!(3, pattern.min_pieces());
+    }
+
+    #[test]
+    fn with_anchor_pattern_has_anchor() {
+        let pattern = MatchPattern::cluster('t', 3, 1).with_anchor(PatternAnchor::Row(0));
+
+        assert_eq!(Some(PatternAnchor::Row(0)), pattern.anchor());
+        assert_eq!(None, MatchPattern::cluster('t', 3, 1).anchor());
+    }
+
+    #[test]
+    fn pattern_anchor_includes_matching_positions() {
+        assert!(PatternAnchor::Cell(Pos::new(4, 4)).includes(Pos::new(4, 4)));
+        assert!(!PatternAnchor::Cell(Pos::new(4, 4)).includes(Pos::new(4, 3)));
+        assert!(PatternAnchor::Row(2).includes(Pos::new(7, 2)));
+        assert!(!PatternAnchor::Row(2).includes(Pos::new(2, 7)));
+        assert!(PatternAnchor::Column(2).includes(Pos::new(2, 7)));
+        assert!(!PatternAnchor::Column(2).includes(Pos::new(7, 2)));
+    }
+
     #[test]
     fn new_match_created_with_pattern_has_pattern() {
         let mut spaces = HashSet::new();
@@ -375,4 +1206,104 @@ mod tests {
 
         assert_eq!(expected, format!("{}", match1));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn with_rotations_line_has_two_patterns() {
+        let spaces = (0..3).map(|x| Pos::new(x, 0)).collect();
+        let set = PatternSet::new("line", 1).with_rotations('t', spaces);
+
+        let vertical: HashSet<Pos> = (0..3).map(|y| Pos::new(0, y)).collect();
+        assert_eq!(2, set.patterns().len());
+        assert!(set.patterns().iter().any(|pattern| *pattern.spaces() == vertical));
+    }
+
+    #[test]
+    fn with_rotations_t_shape_has_four_patterns() {
+        let spaces = [Pos::new(0, 1), Pos::new(1, 1), Pos::new(2, 1), Pos::new(1, 0)].iter().copied().collect();
+        let set = PatternSet::new("t", 3).with_rotations('t', spaces);
+
+        let rotated: HashSet<Pos> = [Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2), Pos::new(1, 1)].iter().copied().collect();
+        assert_eq!(4, set.patterns().len());
+        assert!(set.patterns().iter().any(|pattern| *pattern.spaces() == rotated));
+    }
+
+    #[test]
+    fn with_shape_patterns_share_group_and_rank() {
+        let set = PatternSet::new("pair", 7)
+            .with_shape('t', [Pos::new(0, 0), Pos::new(1, 0)].iter().copied().collect())
+            .with_shape('s', [Pos::new(0, 0), Pos::new(1, 0)].iter().copied().collect());
+
+        assert_eq!(2, set.patterns().len());
+        for pattern in set.into_patterns() {
+            assert_eq!(Some("pair"), pattern.group());
+            assert_eq!(7, pattern.rank());
+        }
+    }
+
+    #[test]
+    fn group_match_of_grouped_pattern_reports_group() {
+        let spaces: HashSet<Pos> = (0..3).map(|x| Pos::new(x, 0)).collect();
+        let pattern = MatchPattern::new('t', spaces.clone(), 1).with_group("line");
+
+        let found = Match::new(&pattern, Pos::new(0, 0), spaces);
+
+        assert_eq!(Some("line"), found.group());
+    }
+
+    #[test]
+    fn group_ungrouped_pattern_none() {
+        let pattern = MatchPattern::new('t', HashSet::new(), 1);
+        assert_eq!(None, pattern.group());
+    }
+
+    #[test]
+    fn into_owned_keeps_match_details() {
+        let spaces: HashSet<Pos> = (0..3).map(|x| Pos::new(x, 0)).collect();
+        let mut pattern = MatchPattern::new('t', spaces.clone(), 4).with_group("line");
+        pattern.set_id(2);
+
+        let owned = Match::new(&pattern, Pos::new(1, 0), spaces.clone()).into_owned();
+        drop(pattern);
+
+        assert_eq!(2, owned.pattern_id());
+        assert_eq!(4, owned.rank());
+        assert_eq!(Some("line"), owned.group());
+        assert_eq!('t', owned.piece_type());
+        assert_eq!(Pos::new(1, 0), owned.changed_pos());
+        assert_eq!(&spaces, owned.board_pos());
+    }
+
+    #[test]
+    fn compiled_pattern_new_origins_keep_pattern_on_board() {
+        let spaces: HashSet<Pos> = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)].into_iter().collect();
+        let pattern = MatchPattern::new('f', spaces, 1);
+
+        let compiled = CompiledPattern::new(&pattern, 4, 2);
+
+        let origins: Vec<Pos> = compiled.origins().iter_set().collect();
+        assert_eq!(vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(0, 1), Pos::new(1, 1)], origins);
+        assert!(compiled.is_for_size(4, 2));
+        assert!(!compiled.is_for_size(2, 4));
+    }
+
+    #[test]
+    fn compiled_pattern_new_anchored_origins_meet_anchor() {
+        let spaces: HashSet<Pos> = vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)].into_iter().collect();
+        let pattern = MatchPattern::new('f', spaces, 1).with_anchor(PatternAnchor::Row(1));
+
+        let compiled = CompiledPattern::new(&pattern, 4, 2);
+
+        let origins: Vec<Pos> = compiled.origins().iter_set().collect();
+        assert_eq!(vec![Pos::new(0, 1), Pos::new(1, 1)], origins);
+    }
+
+    #[test]
+    fn compiled_pattern_new_larger_than_board_no_origins() {
+        let spaces: HashSet<Pos> = vec![Pos::new(0, 0), Pos::new(0, 1), Pos::new(0, 2)].into_iter().collect();
+        let pattern = MatchPattern::new('f', spaces, 1);
+
+        let compiled = CompiledPattern::new(&pattern, 4, 2);
+
+        assert!(compiled.origins().is_empty());
+    }
+}
@@ -0,0 +1,452 @@
+use crate::board::Board;
+use crate::piece::{Direction, Piece, ALL_DIRECTIONS};
+use crate::position::Pos;
+use crate::powerups::{PowerUp, PowerUpError};
+
+use enumset::EnumSet;
+
+/// A move that a player asks to make. Clients send commands to a server,
+/// which validates them with [Board::apply_command()] and sends the accepted
+/// commands to every client. When all boards start with the same state and
+/// random seed, applying the same commands in the same order keeps them in
+/// lockstep.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveCommand {
+
+    /// Swap two pieces if the board's swap rules allow it.
+    Swap {
+        first: Pos,
+        second: Pos
+    },
+
+    /// Use a [Hammer](PowerUp::Hammer) power-up that clears the regular piece
+    /// at a position.
+    UsePowerUp {
+        target: Pos
+    },
+
+    /// Randomly rearrange the movable pieces. See [Board::shuffle()].
+    Shuffle
+
+}
+
+impl MoveCommand {
+
+    /// Encodes the command in a few bytes for sending over a network.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match *self {
+            MoveCommand::Swap { first, second } => vec![0, first.x(), first.y(), second.x(), second.y()],
+            MoveCommand::UsePowerUp { target } => vec![1, target.x(), target.y()],
+            MoveCommand::Shuffle => vec![2]
+        }
+    }
+
+    /// Decodes a command from bytes created by [to_bytes()](MoveCommand::to_bytes).
+    /// Returns None if the bytes are not a valid command.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - the encoded command
+    pub fn from_bytes(bytes: &[u8]) -> Option<MoveCommand> {
+        match *bytes {
+            [0, first_x, first_y, second_x, second_y] => Some(MoveCommand::Swap {
+                first: Pos::new(first_x, first_y),
+                second: Pos::new(second_x, second_y)
+            }),
+            [1, target_x, target_y] => Some(MoveCommand::UsePowerUp { target: Pos::new(target_x, target_y) }),
+            [2] => Some(MoveCommand::Shuffle),
+            _ => None
+        }
+    }
+
+}
+
+/// Whether a [MoveCommand] was applied to the board.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CommandResult {
+
+    /// The command changed the board.
+    Applied,
+
+    /// The command refers to a position outside the board.
+    OutOfBounds,
+
+    /// The board's swap rules do not allow the swap.
+    NotAllowed,
+
+    /// The power-up's target is not a regular piece.
+    InvalidTarget
+
+}
+
+impl Board {
+
+    /// Computes a fast, stable hash of the board's pieces, counters, freeze
+    /// timers, walls, and random number generator. Clients can exchange checksums each turn to detect when
+    /// their boards have fallen out of sync. The checksum is the same on every
+    /// platform and does not depend on the order in which piece types were
+    /// first added to the board.
+    ///
+    /// Positions marked for a match check are not included.
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        hasher.write(&[self.state().width, self.state().height]);
+
+        for y in 0..self.state().height {
+            for x in 0..self.state().width {
+                let pos = Pos::new(x, y);
+                match self.piece(pos) {
+                    Piece::Regular(piece_type, directions) => {
+                        let gravity_directions: EnumSet<Direction> = ALL_DIRECTIONS.iter()
+                            .filter(|&direction| self.gravity_movable_in(direction).is_set(pos))
+                            .collect();
+                        hasher.write(&[2, directions.as_u8(), gravity_directions.as_u8()]);
+                        hasher.write(&u32::from(piece_type).to_le_bytes());
+                    },
+                    Piece::Empty => hasher.write(&[1]),
+                    Piece::Wall => hasher.write(&[0])
+                }
+
+                if let Some(counter) = self.counter(pos) {
+                    hasher.write(&[x, y]);
+                    hasher.write(&counter.to_le_bytes());
+                }
+
+                if let Some(turns) = self.frozen_turns(pos) {
+                    hasher.write(&[3]);
+                    hasher.write(&turns.to_le_bytes());
+                }
+
+                if let Some(&(wall_type, hit_points)) = self.state().walls.get(&pos) {
+                    hasher.write(&[4]);
+                    hasher.write(&u32::from(wall_type).to_le_bytes());
+                    hasher.write(&hit_points.to_le_bytes());
+                }
+            }
+        }
+
+        for block in self.blocks() {
+            hasher.write(&[block.anchor().x(), block.anchor().y(), block.width(), block.height()]);
+        }
+
+        hasher.write(&self.state().rng.state.to_le_bytes());
+        hasher.finish()
+    }
+
+    /// Validates a command from a player and applies it if it is valid. Unlike
+    /// the methods that the command uses, invalid commands never panic, so
+    /// commands from untrusted clients can be applied directly.
+    ///
+    /// Matches caused by the command are not resolved. The changed positions
+    /// are marked for a match check as usual.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - the command to apply
+    pub fn apply_command(&mut self, command: MoveCommand) -> CommandResult {
+        let is_within_board = |pos: Pos| pos.x() < self.state().width && pos.y() < self.state().height;
+
+        match command {
+            MoveCommand::Swap { first, second } => {
+                if !is_within_board(first) || !is_within_board(second) {
+                    return CommandResult::OutOfBounds;
+                }
+
+                if self.swap_pieces(first, second) {
+                    CommandResult::Applied
+                } else {
+                    CommandResult::NotAllowed
+                }
+            },
+            MoveCommand::UsePowerUp { target } => {
+                match self.apply_powerup(PowerUp::Hammer, target) {
+                    Ok(()) => CommandResult::Applied,
+                    Err(PowerUpError::OutOfBounds(_)) => CommandResult::OutOfBounds,
+                    Err(PowerUpError::InvalidTarget(_)) => CommandResult::InvalidTarget
+                }
+            },
+            MoveCommand::Shuffle => {
+                self.shuffle();
+                CommandResult::Applied
+            }
+        }
+    }
+
+}
+
+/// The 64-bit FNV-1a hash, which is simple, fast for small inputs, and stable
+/// across platforms and Rust versions, unlike the standard library's hasher.
+struct Fnv1a {
+    hash: u64
+}
+
+impl Fnv1a {
+
+    /// Creates a new hasher with no input.
+    fn new() -> Fnv1a {
+        Fnv1a { hash: 0xCBF2_9CE4_8422_2325 }
+    }
+
+    /// Adds bytes to the hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - the bytes to add
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hash ^= u64::from(byte);
+            self.hash = self.hash.wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    }
+
+    /// Gets the hash of all the bytes added so far.
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, BoardState};
+    use crate::net::{CommandResult, MoveCommand};
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::test_support::board_from_rows;
+    use crate::walls::WallKind;
+    use enumset::EnumSet;
+
+    #[test]
+    fn to_bytes_from_bytes_same_command() {
+        let commands = [
+            MoveCommand::Swap { first: Pos::new(1, 2), second: Pos::new(3, 4) },
+            MoveCommand::UsePowerUp { target: Pos::new(5, 6) },
+            MoveCommand::Shuffle
+        ];
+
+        for command in commands.iter() {
+            assert_eq!(Some(*command), MoveCommand::from_bytes(&command.to_bytes()));
+        }
+    }
+
+    #[test]
+    fn from_bytes_invalid_none() {
+        assert_eq!(None, MoveCommand::from_bytes(&[]));
+        assert_eq!(None, MoveCommand::from_bytes(&[0, 1, 2]));
+        assert_eq!(None, MoveCommand::from_bytes(&[3]));
+    }
+
+    #[test]
+    fn apply_command_swap_applied() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+
+        let result = board.apply_command(MoveCommand::Swap { first: Pos::new(0, 0), second: Pos::new(1, 0) });
+
+        assert_eq!(CommandResult::Applied, result);
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn apply_command_swap_outside_board_out_of_bounds() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+
+        let result = board.apply_command(MoveCommand::Swap { first: Pos::new(0, 0), second: Pos::new(4, 0) });
+
+        assert_eq!(CommandResult::OutOfBounds, result);
+    }
+
+    #[test]
+    fn apply_command_swap_unmovable_not_allowed() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('a', EnumSet::new()));
+
+        let result = board.apply_command(MoveCommand::Swap { first: Pos::new(0, 0), second: Pos::new(1, 0) });
+
+        assert_eq!(CommandResult::NotAllowed, result);
+    }
+
+    #[test]
+    fn apply_command_power_up_clears_piece() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+
+        let result = board.apply_command(MoveCommand::UsePowerUp { target: Pos::new(2, 3) });
+
+        assert_eq!(CommandResult::Applied, result);
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 3)));
+    }
+
+    #[test]
+    fn apply_command_power_up_on_wall_invalid_target() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        board.set_piece(Pos::new(2, 3), Piece::Wall);
+
+        let result = board.apply_command(MoveCommand::UsePowerUp { target: Pos::new(2, 3) });
+
+        assert_eq!(CommandResult::InvalidTarget, result);
+        assert_eq!(Piece::Wall, board.piece(Pos::new(2, 3)));
+    }
+
+    #[test]
+    fn apply_command_shuffle_same_seed_lockstep() {
+        let original = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        let mut server = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        let mut client = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+
+        server.apply_command(MoveCommand::Shuffle);
+        client.apply_command(MoveCommand::Shuffle);
+
+        assert_eq!(server.state(), client.state());
+        assert_ne!(original.state().type_grid, server.state().type_grid);
+    }
+
+    #[test]
+    fn shuffle_keeps_piece_counts() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        let counts = board.counts();
+
+        board.shuffle();
+
+        assert_eq!(counts, board.counts());
+    }
+
+    #[test]
+    fn shuffle_unmovable_pieces_stay() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        board.set_piece(Pos::new(1, 1), Piece::Regular('z', EnumSet::new()));
+        board.set_piece(Pos::new(2, 2), Piece::Wall);
+
+        board.shuffle();
+
+        assert_eq!(Piece::Regular('z', EnumSet::new()), board.piece(Pos::new(1, 1)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn checksum_same_board_same_checksum() {
+        let first = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        let second = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+
+        assert_eq!(first.checksum(), second.checksum());
+    }
+
+    #[test]
+    fn checksum_different_piece_different_checksum() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        let checksum = board.checksum();
+
+        board.set_piece(Pos::new(3, 3), Piece::Regular('z', ALL_DIRECTIONS));
+
+        assert_ne!(checksum, board.checksum());
+    }
+
+    #[test]
+    fn checksum_different_movability_different_checksum() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        let checksum = board.checksum();
+
+        board.set_piece(Pos::new(0, 0), Piece::Regular('a', EnumSet::new()));
+
+        assert_ne!(checksum, board.checksum());
+    }
+
+    #[test]
+    fn checksum_different_gravity_different_checksum() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        let checksum = board.checksum();
+
+        board.set_gravity_movable(Pos::new(0, 0), EnumSet::new());
+
+        assert_ne!(checksum, board.checksum());
+    }
+
+    #[test]
+    fn checksum_block_different_checksum() {
+        let mut pieces = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        board.place_block(Pos::new(0, 0), 2, 1, 'a');
+        pieces.set_piece(Pos::new(0, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        pieces.set_piece(Pos::new(1, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        pieces.set_gravity_movable(Pos::new(0, 0), EnumSet::new());
+        pieces.set_gravity_movable(Pos::new(1, 0), EnumSet::new());
+
+        assert_ne!(pieces.checksum(), board.checksum());
+    }
+
+    #[test]
+    fn checksum_different_counter_different_checksum() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        let checksum = board.checksum();
+
+        board.set_counter(Pos::new(0, 0), 3);
+
+        assert_ne!(checksum, board.checksum());
+    }
+
+    #[test]
+    fn checksum_different_freeze_timer_different_checksum() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        board.freeze(Pos::new(0, 0), 3);
+        let checksum = board.checksum();
+
+        board.freeze(Pos::new(0, 0), 2);
+
+        assert_ne!(checksum, board.checksum());
+    }
+
+    #[test]
+    fn checksum_different_wall_hit_points_different_checksum() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abc#"], &[]);
+        board.register_wall_type('w', WallKind::Breakable { hit_points: 3 });
+        board.place_wall(Pos::new(3, 0), 'w');
+        let checksum = board.checksum();
+
+        board.state_mut().walls.insert(Pos::new(3, 0), ('w', 2));
+
+        assert_ne!(checksum, board.checksum());
+    }
+
+    #[test]
+    fn checksum_different_wall_type_different_checksum() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abc#"], &[]);
+        let checksum = board.checksum();
+
+        board.register_wall_type('w', WallKind::Decorative);
+        board.place_wall(Pos::new(3, 0), 'w');
+
+        assert_ne!(checksum, board.checksum());
+    }
+
+    #[test]
+    fn checksum_different_seed_different_checksum() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        let checksum = board.checksum();
+
+        board.set_seed(9);
+
+        assert_ne!(checksum, board.checksum());
+    }
+
+    #[test]
+    fn checksum_different_type_order_same_checksum() {
+        let mut first = Board::new(BoardState::new(2, 1), Vec::new(), Vec::new());
+        first.set_piece(Pos::new(0, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        first.set_piece(Pos::new(1, 0), Piece::Regular('b', ALL_DIRECTIONS));
+        let mut second = Board::new(BoardState::new(2, 1), Vec::new(), Vec::new());
+        second.set_piece(Pos::new(1, 0), Piece::Regular('b', ALL_DIRECTIONS));
+        second.set_piece(Pos::new(0, 0), Piece::Regular('a', ALL_DIRECTIONS));
+
+        assert_eq!(first.checksum(), second.checksum());
+    }
+
+    #[test]
+    fn checksum_match_checks_ignored() {
+        let mut board = board_from_rows(&["dabc", "cdab", "bcda", "abcd"], &[]);
+        let checksum = board.checksum();
+
+        while board.next_match().is_some() {}
+
+        assert_eq!(checksum, board.checksum());
+    }
+}
@@ -0,0 +1,234 @@
+use crate::board::{Board, PosSet};
+use crate::piece::Piece;
+use crate::position::Pos;
+
+use std::collections::VecDeque;
+
+/// Splits a set of positions into groups of positions that are connected
+/// horizontally or vertically. Diagonal neighbors are not connected. The
+/// groups are ordered by their lowest position, row by row from the bottom
+/// row and from left to right within each row.
+///
+/// # Arguments
+///
+/// * `positions` - the positions to group
+pub fn connected_components(positions: &PosSet) -> Vec<PosSet> {
+    let mut sorted: Vec<Pos> = positions.iter().copied().collect();
+    sorted.sort_by_key(|pos| (pos.y(), pos.x()));
+
+    let mut visited = PosSet::new();
+    let mut components = Vec::new();
+    for start in sorted {
+        if visited.contains(&start) {
+            continue;
+        }
+
+        let component = flood(start, |pos| positions.contains(&pos));
+        visited.extend(component.iter().copied());
+        components.push(component);
+    }
+
+    components
+}
+
+/// Gets the smallest rectangle that contains every position in a set, as the
+/// (bottom-left corner, top-right corner) of the rectangle. Returns None if
+/// the set is empty.
+///
+/// # Arguments
+///
+/// * `positions` - the positions the rectangle must contain
+pub fn bounding_box(positions: &PosSet) -> Option<(Pos, Pos)> {
+    let min_x = positions.iter().map(|pos| pos.x()).min()?;
+    let min_y = positions.iter().map(|pos| pos.y()).min()?;
+    let max_x = positions.iter().map(|pos| pos.x()).max()?;
+    let max_y = positions.iter().map(|pos| pos.y()).max()?;
+
+    Some((Pos::new(min_x, min_y), Pos::new(max_x, max_y)))
+}
+
+/// Adds the horizontal and vertical neighbors of every position to a set of
+/// positions, such as to find the spaces hit by a blast around a match.
+/// Neighbors outside a board of the given size are not added.
+///
+/// # Arguments
+///
+/// * `positions` - the positions to expand
+/// * `width` - the width of the board
+/// * `height` - the height of the board
+pub fn expand_adjacent(positions: &PosSet, width: u8, height: u8) -> PosSet {
+    let mut expanded = positions.clone();
+
+    for &pos in positions {
+        expanded.extend(neighbors(pos).filter(|neighbor| neighbor.x() < width && neighbor.y() < height));
+    }
+
+    expanded
+}
+
+impl Board {
+
+    /// Gets every position connected horizontally or vertically to a position
+    /// through spaces of the same kind, including the position itself. Regular
+    /// pieces are connected to pieces of the same type regardless of their
+    /// movable directions, empty spaces to empty spaces, and walls to walls.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - the position to fill from
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board.
+    pub fn flood_fill(&self, start: Pos) -> PosSet {
+        let width = self.state().width;
+        let height = self.state().height;
+        let start_piece = self.piece(start);
+
+        flood(start, |pos| pos.x() < width && pos.y() < height && match (start_piece, self.piece(pos)) {
+            (Piece::Regular(start_type, _), Piece::Regular(piece_type, _)) => start_type == piece_type,
+            (start_piece, piece) => start_piece == piece
+        })
+    }
+
+}
+
+/// Gets every position connected horizontally or vertically to a position
+/// through positions that are included, including the position itself.
+///
+/// # Arguments
+///
+/// * `start` - the position to fill from, which must be included
+/// * `is_included` - checks whether a position can be part of the group
+pub(crate) fn flood(start: Pos, is_included: impl Fn(Pos) -> bool) -> PosSet {
+    let mut group = PosSet::new();
+    let mut queue = VecDeque::new();
+    group.insert(start);
+    queue.push_back(start);
+
+    while let Some(pos) = queue.pop_front() {
+        for neighbor in neighbors(pos) {
+            if !group.contains(&neighbor) && is_included(neighbor) {
+                group.insert(neighbor);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    group
+}
+
+/// Gets the horizontal and vertical neighbors of a position that can be
+/// represented by a position.
+///
+/// # Arguments
+///
+/// * `pos` - the position whose neighbors to get
+fn neighbors(pos: Pos) -> impl Iterator<Item = Pos> {
+    let x = pos.x();
+    let y = pos.y();
+
+    vec![
+        x.checked_sub(1).map(|x| Pos::new(x, y)),
+        x.checked_add(1).map(|x| Pos::new(x, y)),
+        y.checked_sub(1).map(|y| Pos::new(x, y)),
+        y.checked_add(1).map(|y| Pos::new(x, y))
+    ].into_iter().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, BoardState, PosSet};
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::posset::{bounding_box, connected_components, expand_adjacent};
+    use enumset::EnumSet;
+
+    fn set(positions: &[(u8, u8)]) -> PosSet {
+        positions.iter().map(|&(x, y)| Pos::new(x, y)).collect()
+    }
+
+    #[test]
+    fn connected_components_separate_groups_split() {
+        let positions = set(&[(0, 0), (1, 0), (1, 1), (3, 0), (3, 1), (5, 5)]);
+
+        let components = connected_components(&positions);
+
+        assert_eq!(vec![set(&[(0, 0), (1, 0), (1, 1)]), set(&[(3, 0), (3, 1)]), set(&[(5, 5)])], components);
+    }
+
+    #[test]
+    fn connected_components_diagonal_not_connected() {
+        let positions = set(&[(0, 0), (1, 1)]);
+
+        assert_eq!(2, connected_components(&positions).len());
+    }
+
+    #[test]
+    fn connected_components_empty_set_no_groups() {
+        assert!(connected_components(&PosSet::new()).is_empty());
+    }
+
+    #[test]
+    fn connected_components_edge_of_range_no_overflow() {
+        let positions = set(&[(255, 255), (255, 254), (0, 0)]);
+
+        let components = connected_components(&positions);
+
+        assert_eq!(vec![set(&[(0, 0)]), set(&[(255, 254), (255, 255)])], components);
+    }
+
+    #[test]
+    fn bounding_box_several_positions_corners() {
+        let positions = set(&[(3, 1), (1, 4), (2, 2)]);
+
+        assert_eq!(Some((Pos::new(1, 1), Pos::new(3, 4))), bounding_box(&positions));
+    }
+
+    #[test]
+    fn bounding_box_empty_set_none() {
+        assert_eq!(None, bounding_box(&PosSet::new()));
+    }
+
+    #[test]
+    fn expand_adjacent_adds_neighbors_inside_board() {
+        let positions = set(&[(0, 0)]);
+
+        assert_eq!(set(&[(0, 0), (1, 0), (0, 1)]), expand_adjacent(&positions, 3, 3));
+    }
+
+    #[test]
+    fn expand_adjacent_neighbors_outside_board_ignored() {
+        let positions = set(&[(2, 2)]);
+
+        assert_eq!(set(&[(2, 2), (1, 2), (2, 1)]), expand_adjacent(&positions, 3, 3));
+    }
+
+    #[test]
+    fn flood_fill_same_type_ignores_directions() {
+        let mut board = Board::new(BoardState::new(3, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('f', EnumSet::new()));
+        board.set_piece(Pos::new(1, 1), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 1), Piece::Regular('f', ALL_DIRECTIONS));
+
+        assert_eq!(set(&[(0, 0), (1, 0), (1, 1), (2, 1)]), board.flood_fill(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn flood_fill_empty_spaces_connected() {
+        let mut board = Board::new(BoardState::new(3, 1), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+
+        assert_eq!(set(&[(0, 0), (1, 0)]), board.flood_fill(Pos::new(1, 0)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn flood_fill_outside_board_panics() {
+        let board = Board::new(BoardState::new(3, 1), Vec::new(), Vec::new());
+        board.flood_fill(Pos::new(3, 0));
+    }
+}
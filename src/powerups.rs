@@ -0,0 +1,243 @@
+use crate::board::Board;
+use crate::events::ClearCause;
+use crate::matching::MatchSource;
+use crate::piece::Piece;
+use crate::position::Pos;
+
+use std::fmt::{Display, Formatter};
+
+/// A consumable booster that a player selects and then uses on the board,
+/// outside the usual swap rules.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PowerUp {
+
+    /// Clears the regular piece at the target.
+    Hammer,
+
+    /// Swaps the piece at the target with the piece at another position,
+    /// ignoring the swap rules and the pieces' movable directions. The two
+    /// positions do not need to be adjacent.
+    Swapper {
+        other: Pos
+    },
+
+    /// Randomly rearranges the movable pieces. The target can be any position
+    /// on the board. See [Board::shuffle()].
+    Shuffle,
+
+    /// Clears every regular piece in the target's row.
+    RowClear,
+
+    /// Clears every regular piece in the target's column.
+    ColumnClear
+
+}
+
+/// Why [apply_powerup()](Board::apply_powerup) could not use a power-up.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum PowerUpError {
+
+    /// The position is outside the board.
+    OutOfBounds(Pos),
+
+    /// The power-up cannot be used on the piece at this position.
+    InvalidTarget(Pos)
+
+}
+
+impl Display for PowerUpError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PowerUpError::OutOfBounds(pos) => write!(f, "Position is outside the board: {}", pos),
+            PowerUpError::InvalidTarget(pos) => write!(f, "Power-up cannot be used at: {}", pos)
+        }
+    }
+}
+
+impl std::error::Error for PowerUpError {}
+
+impl Board {
+
+    /// Uses a power-up on the board. Pieces cleared by the power-up are
+    /// queued as [Cleared](crate::Effect::Cleared) effects with the
+    /// [PowerUp](ClearCause::PowerUp) cause, and every changed space is
+    /// marked for a match check. Matches are not resolved. The board is
+    /// unchanged if an error is returned.
+    ///
+    /// A [Hammer](PowerUp::Hammer) must target a regular piece, and neither
+    /// position of a [Swapper](PowerUp::Swapper) may be a wall, locked,
+    /// frozen, or covered by a [Block](crate::Block).
+    ///
+    /// # Arguments
+    ///
+    /// * `power_up` - the power-up to use
+    /// * `target` - the position the player selected
+    pub fn apply_powerup(&mut self, power_up: PowerUp, target: Pos) -> Result<(), PowerUpError> {
+        self.check_within_board(target)?;
+
+        match power_up {
+            PowerUp::Hammer => {
+                if !matches!(self.piece(target), Piece::Regular(..)) {
+                    return Err(PowerUpError::InvalidTarget(target));
+                }
+
+                self.clear_for_powerup(vec![target]);
+            },
+            PowerUp::Swapper { other } => {
+                self.check_within_board(other)?;
+
+                let is_stuck = |pos: Pos| self.piece(pos) == Piece::Wall || self.is_locked(pos)
+                    || self.frozen_turns(pos).is_some() || self.block_at(pos).is_some();
+                if let Some(&pos) = [target, other].iter().find(|&&pos| is_stuck(pos)) {
+                    return Err(PowerUpError::InvalidTarget(pos));
+                }
+
+                self.swap_always(target, other, MatchSource::Scripted);
+            },
+            PowerUp::Shuffle => self.shuffle(),
+            PowerUp::RowClear => {
+                let row = (0..self.state().width).map(|x| Pos::new(x, target.y())).collect();
+                self.clear_for_powerup(row);
+            },
+            PowerUp::ColumnClear => {
+                let column = (0..self.state().height).map(|y| Pos::new(target.x(), y)).collect();
+                self.clear_for_powerup(column);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if a position is outside the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position to check
+    fn check_within_board(&self, pos: Pos) -> Result<(), PowerUpError> {
+        match pos.x() < self.state().width && pos.y() < self.state().height {
+            true => Ok(()),
+            false => Err(PowerUpError::OutOfBounds(pos))
+        }
+    }
+
+    /// Clears the regular pieces at the given positions, skipping empty
+    /// spaces and walls.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - the positions to clear
+    fn clear_for_powerup(&mut self, positions: Vec<Pos>) {
+        let previous_cause = self.set_clear_cause(ClearCause::PowerUp);
+        for pos in positions {
+            if let Piece::Regular(..) = self.piece(pos) {
+                self.set_piece(pos, Piece::Empty);
+            }
+        }
+        self.set_clear_cause(previous_cause);
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::{ClearCause, Effect};
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::powerups::{PowerUp, PowerUpError};
+    use crate::test_support::board_from_rows;
+
+    #[test]
+    fn apply_powerup_hammer_clears_piece() {
+        let mut board = board_from_rows(&["gh#", "def", "abc"], &[]);
+        board.set_effects_enabled(true);
+
+        assert_eq!(Ok(()), board.apply_powerup(PowerUp::Hammer, Pos::new(1, 1)));
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(&[Effect::Cleared { pos: Pos::new(1, 1), cause: ClearCause::PowerUp }], board.effects());
+    }
+
+    #[test]
+    fn apply_powerup_hammer_on_wall_invalid_target() {
+        let mut board = board_from_rows(&["gh#", "def", "abc"], &[]);
+
+        let result = board.apply_powerup(PowerUp::Hammer, Pos::new(2, 2));
+
+        assert_eq!(Err(PowerUpError::InvalidTarget(Pos::new(2, 2))), result);
+        assert_eq!(Piece::Wall, board.piece(Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn apply_powerup_outside_board_error() {
+        let mut board = board_from_rows(&["gh#", "def", "abc"], &[]);
+
+        assert_eq!(Err(PowerUpError::OutOfBounds(Pos::new(3, 0))), board.apply_powerup(PowerUp::Shuffle, Pos::new(3, 0)));
+        assert_eq!(
+            Err(PowerUpError::OutOfBounds(Pos::new(0, 5))),
+            board.apply_powerup(PowerUp::Swapper { other: Pos::new(0, 5) }, Pos::new(0, 0))
+        );
+    }
+
+    #[test]
+    fn apply_powerup_swapper_ignores_directions_and_distance() {
+        let mut board = board_from_rows(&["gh#", "def", "abc"], &[]);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('a', enumset::EnumSet::new()));
+
+        assert_eq!(Ok(()), board.apply_powerup(PowerUp::Swapper { other: Pos::new(2, 1) }, Pos::new(0, 0)));
+
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Regular('a', enumset::EnumSet::new()), board.piece(Pos::new(2, 1)));
+    }
+
+    #[test]
+    fn apply_powerup_swapper_locked_invalid_target() {
+        let mut board = board_from_rows(&["gh#", "def", "abc"], &[]);
+        board.lock_region(Pos::new(1, 0), Pos::new(1, 0));
+
+        let result = board.apply_powerup(PowerUp::Swapper { other: Pos::new(1, 0) }, Pos::new(0, 0));
+
+        assert_eq!(Err(PowerUpError::InvalidTarget(Pos::new(1, 0))), result);
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn apply_powerup_row_clear_clears_only_row() {
+        let mut board = board_from_rows(&["gh#", "def", "abc"], &[]);
+
+        assert_eq!(Ok(()), board.apply_powerup(PowerUp::RowClear, Pos::new(0, 2)));
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(0, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Regular('d', ALL_DIRECTIONS), board.piece(Pos::new(0, 1)));
+    }
+
+    #[test]
+    fn apply_powerup_column_clear_clears_only_column() {
+        let mut board = board_from_rows(&["gh#", "def", "abc"], &[]);
+
+        assert_eq!(Ok(()), board.apply_powerup(PowerUp::ColumnClear, Pos::new(1, 0)));
+
+        for y in 0..3 {
+            assert_eq!(Piece::Empty, board.piece(Pos::new(1, y)));
+        }
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn apply_powerup_shuffle_keeps_pieces() {
+        let mut board = board_from_rows(&["gh#", "def", "abc"], &[]);
+
+        assert_eq!(Ok(()), board.apply_powerup(PowerUp::Shuffle, Pos::new(0, 0)));
+
+        let mut types: Vec<char> = (0..3).flat_map(|y| (0..3).map(move |x| Pos::new(x, y)))
+            .filter_map(|pos| match board.piece(pos) {
+                Piece::Regular(piece_type, _) => Some(piece_type),
+                _ => None
+            })
+            .collect();
+        types.sort_unstable();
+        assert_eq!(vec!['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'], types);
+    }
+}
@@ -0,0 +1,150 @@
+/// A small, fast pseudorandom number generator (SplitMix64) that produces the
+/// same numbers on every platform for the same seed. The generator is part of
+/// the [BoardState](crate::BoardState), so a saved game continues with the
+/// same random choices after it is loaded.
+///
+/// This generator is not suitable for cryptography.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rng {
+    pub(crate) state: u64
+}
+
+impl Rng {
+
+    /// Creates a new generator from a seed.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - the seed for the generator. Generators with the same seed
+    ///   produce the same numbers.
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// Gets the next random 64-bit number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut value = self.state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        value ^ (value >> 31)
+    }
+
+    /// Gets a random number from zero (inclusive) to a bound (exclusive).
+    ///
+    /// # Arguments
+    ///
+    /// * `bound` - one more than the largest number that can be returned
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bound is zero.
+    pub fn next_below(&mut self, bound: usize) -> usize {
+        self.next_below_u64(bound as u64) as usize
+    }
+
+    /// Gets a random number from zero (inclusive) to a 64-bit bound (exclusive).
+    /// Unlike [next_below()](Rng::next_below), the bound is not limited by the
+    /// size of `usize` on the target platform.
+    ///
+    /// # Arguments
+    ///
+    /// * `bound` - one more than the largest number that can be returned
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bound is zero.
+    pub fn next_below_u64(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            panic!("Tried to get a random number below zero");
+        }
+
+        // Widening multiplication avoids most of the bias of a remainder
+        ((u128::from(self.next_u64()) * u128::from(bound)) >> 64) as u64
+    }
+
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::Rng;
+
+    #[test]
+    fn next_u64_same_seed_same_numbers() {
+        let mut first = Rng::new(42);
+        let mut second = Rng::new(42);
+
+        for _ in 0..10 {
+            assert_eq!(first.next_u64(), second.next_u64());
+        }
+    }
+
+    #[test]
+    fn next_u64_different_seed_different_numbers() {
+        let mut first = Rng::new(1);
+        let mut second = Rng::new(2);
+
+        assert_ne!(first.next_u64(), second.next_u64());
+    }
+
+    #[test]
+    fn next_below_always_below_bound() {
+        let mut rng = Rng::new(7);
+
+        for _ in 0..1000 {
+            assert!(rng.next_below(5) < 5);
+        }
+    }
+
+    #[test]
+    fn next_below_all_values_reached() {
+        let mut rng = Rng::new(7);
+        let mut seen = [false; 5];
+
+        for _ in 0..1000 {
+            seen[rng.next_below(5)] = true;
+        }
+
+        assert!(seen.iter().all(|&value| value));
+    }
+
+    #[test]
+    #[should_panic]
+    fn next_below_zero_panics() {
+        Rng::new(7).next_below(0);
+    }
+
+    #[test]
+    fn next_below_u64_large_bound_upper_values_reached() {
+        let mut rng = Rng::new(7);
+        let bound = u64::from(u32::MAX) << 8;
+
+        let values: Vec<u64> = (0..1000).map(|_| rng.next_below_u64(bound)).collect();
+
+        assert!(values.iter().all(|&value| value < bound));
+        assert!(values.iter().any(|&value| value > u64::from(u32::MAX)));
+    }
+
+    #[test]
+    fn next_below_u64_same_as_next_below() {
+        let mut first = Rng::new(7);
+        let mut second = Rng::new(7);
+
+        for _ in 0..100 {
+            assert_eq!(first.next_below(1000) as u64, second.next_below_u64(1000));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn next_below_u64_zero_panics() {
+        Rng::new(7).next_below_u64(0);
+    }
+}
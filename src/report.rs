@@ -0,0 +1,278 @@
+use crate::board::Board;
+use crate::events::{BoardEvent, BoardObserver};
+use crate::goals::Goals;
+use crate::piece::{Piece, PieceType};
+use crate::position::Pos;
+
+use std::collections::HashMap;
+use std::mem;
+
+/// Everything that happened on a board during one turn, returned by
+/// [end_turn()](Board::end_turn). The board fills in the report by itself as
+/// pieces are swapped, matches are resolved, and the board is ticked, so a
+/// game can pass the whole report to its interface and goal tracker at once.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TurnReport {
+    swaps: u32,
+    rejected_swaps: u32,
+    matches: u32,
+    cascades: u32,
+    cleared_by_type: HashMap<PieceType, u32>,
+    score_delta: u64,
+    special_pieces: Vec<(Pos, Piece)>
+}
+
+impl TurnReport {
+
+    /// Gets the number of swaps that the board allowed.
+    pub fn swaps(&self) -> u32 {
+        self.swaps
+    }
+
+    /// Gets the number of swaps that the board rejected.
+    pub fn rejected_swaps(&self) -> u32 {
+        self.rejected_swaps
+    }
+
+    /// Gets the number of matches that were cleared or replaced.
+    pub fn matches(&self) -> u32 {
+        self.matches
+    }
+
+    /// Gets the number of chain links after the first, which are the rounds of
+    /// matches caused by falling and spawned pieces instead of by a swap.
+    pub fn cascades(&self) -> u32 {
+        self.cascades
+    }
+
+    /// Gets the number of regular pieces of a type that were cleared, whether
+    /// by a match or by a tick behavior.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of the cleared pieces
+    pub fn cleared(&self, piece_type: PieceType) -> u32 {
+        self.cleared_by_type.get(&piece_type).copied().unwrap_or(0)
+    }
+
+    /// Gets the number of regular pieces of each type that were cleared.
+    pub fn cleared_by_type(&self) -> &HashMap<PieceType, u32> {
+        &self.cleared_by_type
+    }
+
+    /// Gets the number of regular pieces of every type that were cleared.
+    pub fn total_cleared(&self) -> u32 {
+        self.cleared_by_type.values().sum()
+    }
+
+    /// Gets the points scored, using the same scoring as a [Goals] tracker
+    /// created with [Goals::new()].
+    pub fn score_delta(&self) -> u64 {
+        self.score_delta
+    }
+
+    /// Gets the special pieces that matches left behind, such as with
+    /// [SpecialPieceRules](crate::SpecialPieceRules), and where they were
+    /// placed, in the order they were created.
+    pub fn special_pieces(&self) -> &[(Pos, Piece)] {
+        &self.special_pieces
+    }
+
+    /// Checks whether nothing happened during the turn.
+    pub fn is_empty(&self) -> bool {
+        *self == TurnReport::default()
+    }
+
+    /// Counts a swap that the board was asked to make.
+    ///
+    /// # Arguments
+    ///
+    /// * `is_accepted` - whether the board allowed the swap
+    pub(crate) fn record_swap(&mut self, is_accepted: bool) {
+        match is_accepted {
+            true => self.swaps += 1,
+            false => self.rejected_swaps += 1
+        }
+    }
+
+    /// Counts a chain link after the first.
+    pub(crate) fn record_cascade(&mut self) {
+        self.cascades += 1;
+    }
+
+    /// Records a special piece that a match left behind.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - where the piece was placed
+    /// * `piece` - the piece that was placed
+    pub(crate) fn record_special_piece(&mut self, pos: Pos, piece: Piece) {
+        self.special_pieces.push((pos, piece));
+    }
+
+    /// Adds everything in another report to this one.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - the report to add
+    pub(crate) fn merge(&mut self, other: TurnReport) {
+        self.swaps += other.swaps;
+        self.rejected_swaps += other.rejected_swaps;
+        self.matches += other.matches;
+        self.cascades += other.cascades;
+        for (piece_type, cleared) in other.cleared_by_type {
+            *self.cleared_by_type.entry(piece_type).or_insert(0) += cleared;
+        }
+        self.score_delta = self.score_delta.saturating_add(other.score_delta);
+        self.special_pieces.extend(other.special_pieces);
+    }
+
+}
+
+impl BoardObserver for TurnReport {
+    fn on_event(&mut self, event: &BoardEvent) {
+        self.score_delta = self.score_delta.saturating_add(Goals::default_score(event));
+
+        match event {
+            BoardEvent::Matched { .. } => self.matches += 1,
+            BoardEvent::Cleared { piece: Piece::Regular(piece_type, _), .. } => {
+                *self.cleared_by_type.entry(*piece_type).or_insert(0) += 1;
+            },
+            _ => {}
+        }
+    }
+}
+
+impl Board {
+
+    /// Ends the current turn and returns everything that happened since the
+    /// previous call, or since the board was created. Swaps are counted
+    /// however they are made. Matches, cleared pieces, and scores are counted
+    /// for every event the board sends to an observer while resolving matches
+    /// or ticking, so matches found with [next_match()](Board::next_match)
+    /// and cleared by the game are not included.
+    pub fn end_turn(&mut self) -> TurnReport {
+        mem::take(self.turn_report_mut())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::events::{BoardEvent, BoardObserver};
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::special::{SpecialCondition, SpecialPieceRules};
+    use crate::test_support::board_from_rows;
+    use crate::transaction::TransactionError;
+
+    use enumset::EnumSet;
+
+    fn spawn_b(_: &Board, _: Pos) -> Piece {
+        Piece::Regular('b', ALL_DIRECTIONS)
+    }
+
+    #[test]
+    fn end_turn_new_board_empty() {
+        let mut board = board_from_rows(&["aca", "bac", "aba"], &["aaa"]);
+
+        assert!(board.end_turn().is_empty());
+    }
+
+    #[test]
+    fn end_turn_swap_and_cascade_counted() {
+        let mut board = board_from_rows(&["aca", "bac", "aba"], &["aaa"]);
+
+        let mut spawned = 0;
+        let mut spawn_a_then_b = move |_: &Board, _: Pos| {
+            spawned += 1;
+            Piece::Regular(if spawned <= 3 { 'a' } else { 'b' }, ALL_DIRECTIONS)
+        };
+
+        assert!(board.swap_pieces(Pos::new(1, 0), Pos::new(1, 1)));
+        board.resolve_cascade(&mut spawn_a_then_b, &mut |_: &BoardEvent| {});
+        let report = board.end_turn();
+
+        assert_eq!(1, report.swaps());
+        assert_eq!(2, report.matches());
+        assert_eq!(1, report.cascades());
+        assert_eq!(6, report.cleared('a'));
+        assert_eq!(6, report.total_cleared());
+        assert_eq!(6, report.score_delta());
+        assert!(report.special_pieces().is_empty());
+    }
+
+    #[test]
+    fn end_turn_report_reset() {
+        let mut board = board_from_rows(&["aca", "bac", "aba"], &["aaa"]);
+        assert!(board.swap_pieces(Pos::new(1, 1), Pos::new(2, 1)));
+
+        board.end_turn();
+
+        assert!(board.turn_report().is_empty());
+        assert!(board.end_turn().is_empty());
+    }
+
+    #[test]
+    fn turn_report_does_not_end_turn() {
+        let mut board = board_from_rows(&["aca", "bac", "aba"], &["aaa"]);
+        assert!(board.swap_pieces(Pos::new(1, 1), Pos::new(2, 1)));
+
+        assert_eq!(1, board.turn_report().swaps());
+        assert_eq!(1, board.end_turn().swaps());
+    }
+
+    #[test]
+    fn end_turn_rejected_swap_counted() {
+        let mut board = board_from_rows(&["aca", "bac", "aba"], &["aaa"]);
+        board.set_piece_silent(Pos::new(2, 1), Piece::Regular('c', EnumSet::new()));
+
+        assert!(!board.swap_pieces(Pos::new(1, 1), Pos::new(2, 1)));
+
+        let report = board.end_turn();
+        assert_eq!(0, report.swaps());
+        assert_eq!(1, report.rejected_swaps());
+    }
+
+    #[test]
+    fn end_turn_special_piece_recorded() {
+        let mut board = board_from_rows(&["aca", "bac", "aba"], &["aaa"]);
+        let mut rules = SpecialPieceRules::new();
+        rules.add_rule(SpecialCondition::MinSize(3), Box::new(|_| Piece::Regular('s', ALL_DIRECTIONS)));
+
+        assert!(board.swap_pieces(Pos::new(1, 0), Pos::new(1, 1)));
+        board.resolve_cascade_with(&mut spawn_b, &mut |_: &BoardEvent| {}, &mut rules);
+
+        let report = board.end_turn();
+        assert_eq!(Some(&(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS))), report.special_pieces().first());
+    }
+
+    #[test]
+    fn end_turn_tick_clears_counted() {
+        let mut board = board_from_rows(&["aca", "bac", "aba"], &["aaa"]);
+        board.add_tick_behavior(Box::new(|board: &mut Board, _: &[Pos], observer: &mut dyn BoardObserver| {
+            let piece = board.set_piece(Pos::new(2, 1), Piece::Empty);
+            observer.on_event(&BoardEvent::Cleared { pos: Pos::new(2, 1), piece });
+        }));
+
+        board.tick();
+
+        assert_eq!(1, board.end_turn().cleared('c'));
+    }
+
+    #[test]
+    fn end_turn_rolled_back_swap_not_counted() {
+        let mut board = board_from_rows(&["aca", "bac", "aba"], &["aaa"]);
+
+        board.begin_swap(Pos::new(1, 0), Pos::new(1, 1)).unwrap().rollback();
+        let _: Result<(), TransactionError> = board.transaction(|tx| {
+            tx.swap_pieces(Pos::new(1, 1), Pos::new(2, 1))?;
+            tx.swap_pieces(Pos::new(0, 0), Pos::new(0, 5))?;
+            Ok(())
+        });
+
+        assert!(board.end_turn().is_empty());
+    }
+}
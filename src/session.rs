@@ -0,0 +1,323 @@
+use crate::board::Board;
+use crate::cascade::{MatchAction, MatchInterceptor, Spawner};
+use crate::events::{BoardEvent, BoardObserver};
+use crate::goals::Goals;
+use crate::position::Pos;
+use crate::stats::MatchStats;
+
+/// Limits how long a player has to complete a level's goals.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SessionLimit {
+
+    /// Each successful swap uses one of a limited number of moves.
+    Moves(u32),
+
+    /// The game ends after a number of turns. Turns are advanced by
+    /// the game, such as once per second, rather than by swaps.
+    Turns(u32)
+
+}
+
+/// Whether a game has been won, lost, or is still being played.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SessionStatus {
+    InProgress,
+    Won,
+    Lost
+}
+
+/// Plays a level on a [Board] with a limited number of moves or turns.
+///
+/// Every successful swap resolves all the matches it causes, refilling the
+/// board with the session's [Spawner], and updates the progress toward the
+/// session's [Goals]. The game is won as soon as all goals are complete and
+/// lost once the limit runs out first. Swaps are rejected after the game ends.
+///
+/// A [MatchInterceptor], such as [SpecialPieceRules](crate::SpecialPieceRules),
+/// can be added to change matches before they are cleared.
+///
+/// The session also keeps [MatchStats] for every swap and match.
+pub struct GameSession {
+    board: Board,
+    spawner: Box<dyn Spawner>,
+    interceptor: Box<dyn MatchInterceptor>,
+    goals: Goals,
+    stats: MatchStats,
+    limit: SessionLimit,
+    used: u32
+}
+
+impl GameSession {
+
+    /// Creates a new session with no moves or turns used.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - the board to play on
+    /// * `spawner` - chooses the new pieces that fill the board after matches
+    /// * `goals` - the goals that must be completed to win
+    /// * `limit` - the number of moves or turns available to complete the goals
+    pub fn new(board: Board, spawner: Box<dyn Spawner>, goals: Goals, limit: SessionLimit) -> GameSession {
+        GameSession {
+            board,
+            spawner,
+            interceptor: Box::new(|_: &Board, _: &BoardEvent| MatchAction::Clear),
+            goals,
+            stats: MatchStats::new(),
+            limit,
+            used: 0
+        }
+    }
+
+    /// Gets the board being played on.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Gets the goals for this session and their progress.
+    pub fn goals(&self) -> &Goals {
+        &self.goals
+    }
+
+    /// Gets the goals for this session so that completion events can be read.
+    pub fn goals_mut(&mut self) -> &mut Goals {
+        &mut self.goals
+    }
+
+    /// Gets the statistics for every swap and match so far.
+    pub fn stats(&self) -> &MatchStats {
+        &self.stats
+    }
+
+    /// Sets the interceptor that decides what to do with each match before it
+    /// is cleared. By default, every match is cleared.
+    ///
+    /// # Arguments
+    ///
+    /// * `interceptor` - the new interceptor to use for all future matches
+    pub fn set_interceptor(&mut self, interceptor: Box<dyn MatchInterceptor>) {
+        self.interceptor = interceptor;
+    }
+
+    /// Gets the limit on moves or turns for this session.
+    pub fn limit(&self) -> SessionLimit {
+        self.limit
+    }
+
+    /// Gets the number of moves or turns that are left.
+    pub fn remaining(&self) -> u32 {
+        let total = match self.limit {
+            SessionLimit::Moves(moves) => moves,
+            SessionLimit::Turns(turns) => turns
+        };
+        total.saturating_sub(self.used)
+    }
+
+    /// Gets whether the game has been won, lost, or is still being played.
+    /// Completing the goals with the last move or turn wins the game.
+    pub fn status(&self) -> SessionStatus {
+        if self.goals.are_all_complete() {
+            SessionStatus::Won
+        } else if self.remaining() == 0 {
+            SessionStatus::Lost
+        } else {
+            SessionStatus::InProgress
+        }
+    }
+
+    /// Swaps two pieces and resolves all the matches that the swap causes.
+    /// Returns the number of matches that were cleared, or None if the swap
+    /// was rejected.
+    ///
+    /// The swap is rejected if the game is over or if the board's swap rules
+    /// do not allow it. A rejected swap does not use a move.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    ///
+    /// # Panics
+    ///
+    /// Panics if either position is outside the board.
+    pub fn swap_pieces(&mut self, first: Pos, second: Pos) -> Option<usize> {
+        if self.status() != SessionStatus::InProgress {
+            return None;
+        }
+
+        let is_swapped = self.board.swap_pieces(first, second);
+        self.stats.record_swap(is_swapped);
+        if !is_swapped {
+            return None;
+        }
+
+        if let SessionLimit::Moves(_) = self.limit {
+            self.used += 1;
+        }
+
+        let goals = &mut self.goals;
+        let stats = &mut self.stats;
+        Some(self.board.resolve_cascade_with(
+            self.spawner.as_mut(),
+            &mut |event: &BoardEvent| {
+                goals.on_event(event);
+                stats.on_event(event);
+            },
+            self.interceptor.as_mut()
+        ))
+    }
+
+    /// Uses one turn of a turn-limited session. Has no effect if the session
+    /// is limited by moves or if the game is over.
+    pub fn advance_turn(&mut self) {
+        if let SessionLimit::Turns(_) = self.limit {
+            if self.status() == SessionStatus::InProgress {
+                self.used += 1;
+            }
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, BoardState};
+    use crate::goals::{Goal, Goals};
+    use crate::matching::MatchPattern;
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::session::{GameSession, SessionLimit, SessionStatus};
+    use crate::special::{SpecialCondition, SpecialPieceRules};
+    use std::collections::HashSet;
+
+    fn session(goals: Vec<Goal>, limit: SessionLimit) -> GameSession {
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(0, 0));
+        spaces.insert(Pos::new(1, 0));
+        spaces.insert(Pos::new(2, 0));
+        let mut board = Board::new(BoardState::new(3, 2), vec![MatchPattern::new('f', spaces, 1)], Vec::new());
+
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 1), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 1), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 1), Piece::Regular('s', ALL_DIRECTIONS));
+        while board.next_match().is_some() {}
+
+        GameSession::new(
+            board,
+            Box::new(|_: &Board, _| Piece::Regular('g', ALL_DIRECTIONS)),
+            Goals::new(goals),
+            limit
+        )
+    }
+
+    #[test]
+    fn new_no_moves_used_in_progress() {
+        let session = session(vec![Goal::Score(1)], SessionLimit::Moves(2));
+        assert_eq!(2, session.remaining());
+        assert_eq!(SessionStatus::InProgress, session.status());
+    }
+
+    #[test]
+    fn swap_pieces_match_clears_and_uses_move() {
+        let mut session = session(vec![Goal::Clear { piece_type: 'f', count: 3 }], SessionLimit::Moves(2));
+
+        assert_eq!(Some(1), session.swap_pieces(Pos::new(1, 0), Pos::new(1, 1)));
+        assert_eq!(1, session.remaining());
+        assert_eq!(SessionStatus::Won, session.status());
+    }
+
+    #[test]
+    fn swap_pieces_no_match_uses_move() {
+        let mut session = session(vec![Goal::Score(1)], SessionLimit::Moves(2));
+
+        assert_eq!(Some(0), session.swap_pieces(Pos::new(0, 0), Pos::new(0, 1)));
+        assert_eq!(1, session.remaining());
+        assert_eq!(SessionStatus::InProgress, session.status());
+    }
+
+    #[test]
+    fn swap_pieces_last_move_without_goals_lost() {
+        let mut session = session(vec![Goal::Score(100)], SessionLimit::Moves(1));
+        session.swap_pieces(Pos::new(0, 0), Pos::new(0, 1));
+
+        assert_eq!(0, session.remaining());
+        assert_eq!(SessionStatus::Lost, session.status());
+    }
+
+    #[test]
+    fn swap_pieces_match_updates_stats() {
+        let mut session = session(vec![Goal::Score(100)], SessionLimit::Moves(2));
+
+        session.swap_pieces(Pos::new(1, 0), Pos::new(1, 1));
+
+        assert_eq!(1, session.stats().swaps_accepted());
+        assert_eq!(1, session.stats().matches_of_type('f'));
+        assert_eq!(3, session.stats().pieces_cleared());
+        assert_eq!(1, session.stats().longest_cascade());
+    }
+
+    #[test]
+    fn swap_pieces_after_game_over_rejected() {
+        let mut session = session(vec![Goal::Score(100)], SessionLimit::Moves(1));
+        session.swap_pieces(Pos::new(0, 0), Pos::new(0, 1));
+
+        assert_eq!(None, session.swap_pieces(Pos::new(1, 0), Pos::new(1, 1)));
+        assert_eq!(Piece::Regular('s', ALL_DIRECTIONS), session.board().piece(Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn swap_pieces_rejected_by_rules_no_move_used() {
+        let mut session = session(vec![Goal::Score(100)], SessionLimit::Moves(1));
+        session.board.set_piece(Pos::new(0, 0), Piece::Wall);
+
+        assert_eq!(None, session.swap_pieces(Pos::new(0, 0), Pos::new(0, 1)));
+        assert_eq!(1, session.remaining());
+    }
+
+    #[test]
+    fn swap_pieces_turn_limit_no_turn_used() {
+        let mut session = session(vec![Goal::Score(100)], SessionLimit::Turns(1));
+        session.swap_pieces(Pos::new(0, 0), Pos::new(0, 1));
+
+        assert_eq!(1, session.remaining());
+        assert_eq!(SessionStatus::InProgress, session.status());
+    }
+
+    #[test]
+    fn advance_turn_turn_limit_runs_out() {
+        let mut session = session(vec![Goal::Score(100)], SessionLimit::Turns(2));
+        session.advance_turn();
+        assert_eq!(SessionStatus::InProgress, session.status());
+
+        session.advance_turn();
+        assert_eq!(SessionStatus::Lost, session.status());
+
+        session.advance_turn();
+        assert_eq!(0, session.remaining());
+    }
+
+    #[test]
+    fn advance_turn_move_limit_no_effect() {
+        let mut session = session(vec![Goal::Score(100)], SessionLimit::Moves(2));
+        session.advance_turn();
+        assert_eq!(2, session.remaining());
+    }
+
+    #[test]
+    fn swap_pieces_interceptor_set_applied_to_matches() {
+        let mut session = session(vec![Goal::Clear { piece_type: 'f', count: 2 }], SessionLimit::Moves(2));
+        let mut rules = SpecialPieceRules::new();
+        rules.add_rule(SpecialCondition::MinSize(3), Box::new(|_| Piece::Wall));
+        session.set_interceptor(Box::new(rules));
+
+        session.swap_pieces(Pos::new(1, 0), Pos::new(1, 1));
+
+        assert_eq!(Piece::Wall, session.board().piece(Pos::new(1, 0)));
+        assert_eq!(2, session.goals().progress(0));
+    }
+}
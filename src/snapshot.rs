@@ -0,0 +1,796 @@
+use crate::bitboard::BitBoard;
+use crate::blocks::Block;
+use crate::board::{Board, BoardState, SwapRule};
+use crate::matching::{MatchPattern, MatchSource};
+use crate::position::Pos;
+use crate::random::Rng;
+use crate::types::TypeId;
+use crate::walls::{WallKind, WallType};
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+/// The bytes at the start of every snapshot.
+const MAGIC: &[u8; 4] = b"SMBS";
+
+/// The version of the snapshot format written by this version of the crate.
+/// Snapshots written with any other version cannot be read.
+const VERSION: u8 = 1;
+
+/// The reason that a snapshot could not be read.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SnapshotError {
+
+    /// The bytes do not start like a snapshot.
+    InvalidHeader,
+
+    /// The snapshot was written with a format version that cannot be read.
+    UnsupportedVersion(u8),
+
+    /// The snapshot ended before all the board's data was read.
+    UnexpectedEnd,
+
+    /// The snapshot contains data that cannot be on a board.
+    InvalidData
+
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::InvalidHeader => write!(f, "Not a board snapshot"),
+            SnapshotError::UnsupportedVersion(version) => write!(f, "Unsupported snapshot version: {}", version),
+            SnapshotError::UnexpectedEnd => write!(f, "Snapshot ended unexpectedly"),
+            SnapshotError::InvalidData => write!(f, "Snapshot contains invalid board data")
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// An in-memory checkpoint of a board's pieces that can be restored later,
+/// such as to try out moves during a search. Taking and restoring a
+/// checkpoint copies the board's [BoardState] but none of its rules,
+/// patterns, or settings, so it is much cheaper than copying the board.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct BoardSnapshot {
+    state: BoardState
+}
+
+impl BoardSnapshot {
+
+    /// Gets the board state saved in this checkpoint.
+    pub fn state(&self) -> &BoardState {
+        &self.state
+    }
+
+}
+
+impl BoardState {
+
+    /// Encodes the board state in a compact, versioned binary format that is
+    /// much smaller than general-purpose serialization formats, which makes it
+    /// suitable for network sync and save slots.
+    ///
+    /// The type of each space is packed into as few bits as the number of
+    /// piece types allows, and each bitboard uses one bit per space. Counters,
+    /// freeze timers, wall types, the positions marked for a match check and what
+    /// changed them, and the random number generator are included, so a decoded
+    /// state behaves exactly like the original.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.width);
+        bytes.push(self.height);
+
+        let types = self.pieces.types();
+        bytes.extend_from_slice(&(types.len() as u16).to_le_bytes());
+        for &piece_type in types.iter() {
+            bytes.extend_from_slice(&u32::from(piece_type).to_le_bytes());
+        }
+
+        let bits_per_space = bits_for(types.len());
+        write_bits(&mut bytes, self.type_grid.iter().map(|&index| u64::from(index)), bits_per_space);
+
+        write_bitboard(&mut bytes, &self.empties);
+        for movable in self.movable_directions.iter() {
+            write_bitboard(&mut bytes, movable);
+        }
+
+        // Gravity directions usually match the swap directions, so they are only stored if they differ
+        if self.gravity_directions == self.movable_directions {
+            bytes.push(0);
+        } else {
+            bytes.push(1);
+            for gravity in self.gravity_directions.iter() {
+                write_bitboard(&mut bytes, gravity);
+            }
+        }
+
+        bytes.extend_from_slice(&(self.last_changed.len() as u16).to_le_bytes());
+        for pos in self.last_changed.iter() {
+            bytes.push(pos.x());
+            bytes.push(pos.y());
+        }
+        for pos in self.last_changed.iter() {
+            bytes.push(match self.change_sources.get(pos) {
+                Some(MatchSource::PlayerSwap) => 0,
+                Some(MatchSource::Gravity) => 1,
+                Some(MatchSource::Scripted) | None => 2
+            });
+        }
+
+        let mut counters: Vec<(&Pos, &u32)> = self.counters.iter().collect();
+        counters.sort_by_key(|(pos, _)| (pos.y(), pos.x()));
+        bytes.extend_from_slice(&(counters.len() as u16).to_le_bytes());
+        for (pos, &value) in counters {
+            bytes.push(pos.x());
+            bytes.push(pos.y());
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        bytes.extend_from_slice(&(self.blocks.len() as u16).to_le_bytes());
+        for block in self.blocks.iter() {
+            bytes.extend_from_slice(&[block.anchor().x(), block.anchor().y(), block.width(), block.height()]);
+        }
+
+        let mut frozen: Vec<(&Pos, &u32)> = self.frozen.iter().collect();
+        frozen.sort_by_key(|(pos, _)| (pos.y(), pos.x()));
+        bytes.extend_from_slice(&(frozen.len() as u16).to_le_bytes());
+        for (pos, &turns) in frozen {
+            bytes.push(pos.x());
+            bytes.push(pos.y());
+            bytes.extend_from_slice(&turns.to_le_bytes());
+        }
+
+        let mut wall_kinds: Vec<(&WallType, &WallKind)> = self.wall_kinds.iter().collect();
+        wall_kinds.sort_by_key(|(&wall_type, _)| wall_type);
+        bytes.extend_from_slice(&(wall_kinds.len() as u16).to_le_bytes());
+        for (&wall_type, kind) in wall_kinds {
+            bytes.extend_from_slice(&u32::from(wall_type).to_le_bytes());
+            match kind {
+                WallKind::Decorative => bytes.push(0),
+                WallKind::Breakable { hit_points } => {
+                    bytes.push(1);
+                    bytes.extend_from_slice(&hit_points.to_le_bytes());
+                }
+            }
+        }
+
+        let mut walls: Vec<(&Pos, &(WallType, u32))> = self.walls.iter().collect();
+        walls.sort_by_key(|(pos, _)| (pos.y(), pos.x()));
+        bytes.extend_from_slice(&(walls.len() as u16).to_le_bytes());
+        for (pos, &(wall_type, hit_points)) in walls {
+            bytes.push(pos.x());
+            bytes.push(pos.y());
+            bytes.extend_from_slice(&u32::from(wall_type).to_le_bytes());
+            bytes.extend_from_slice(&hit_points.to_le_bytes());
+        }
+
+        bytes.extend_from_slice(&self.rng.state.to_le_bytes());
+        bytes
+    }
+
+    /// Decodes a board state from bytes created by [to_bytes()](BoardState::to_bytes).
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - the encoded board state
+    pub fn from_bytes(bytes: &[u8]) -> Result<BoardState, SnapshotError> {
+        let mut reader = Reader { bytes, index: 0 };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::InvalidHeader);
+        }
+
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let width = reader.u8()?;
+        let height = reader.u8()?;
+        let mut state = BoardState::new(width, height);
+
+        let type_count = usize::from(reader.u16()?);
+        for _ in 0..type_count {
+            let piece_type = char::from_u32(reader.u32()?).ok_or(SnapshotError::InvalidData)?;
+            if state.pieces.id(piece_type).is_some() {
+                return Err(SnapshotError::InvalidData);
+            }
+
+            state.pieces.insert(piece_type, BitBoard::new(width, height));
+        }
+
+        let bits_per_space = bits_for(type_count);
+        let type_grid = reader.bits(state.type_grid.len(), bits_per_space)?;
+        for (index, type_index) in type_grid.into_iter().enumerate() {
+            if type_index as usize > type_count {
+                return Err(SnapshotError::InvalidData);
+            }
+
+            state.type_grid[index] = type_index as TypeId;
+        }
+
+        state.empties = reader.bitboard(width, height)?;
+        for (index, &type_index) in state.type_grid.iter().enumerate() {
+            if type_index > 0 {
+                let pos = Pos::new((index % usize::from(width)) as u8, (index / usize::from(width)) as u8);
+                if state.empties.is_set(pos) {
+                    return Err(SnapshotError::InvalidData);
+                }
+
+                let piece_type = state.pieces.types()[usize::from(type_index) - 1];
+                state.pieces.get_mut(&piece_type).unwrap().set(pos);
+            }
+        }
+
+        for direction in 0..state.movable_directions.len() {
+            state.movable_directions[direction] = reader.bitboard(width, height)?;
+        }
+
+        let has_gravity_directions = match reader.u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err(SnapshotError::InvalidData)
+        };
+        if has_gravity_directions {
+            for direction in 0..state.gravity_directions.len() {
+                state.gravity_directions[direction] = reader.bitboard(width, height)?;
+            }
+        } else {
+            state.gravity_directions = state.movable_directions.clone();
+        }
+
+        let changed_count = reader.u16()?;
+        state.last_changed = VecDeque::with_capacity(usize::from(changed_count));
+        for _ in 0..changed_count {
+            let pos = reader.pos(width, height)?;
+            if state.changed.is_set(pos) {
+                return Err(SnapshotError::InvalidData);
+            }
+            state.mark_changed(pos, MatchSource::Scripted);
+        }
+        for &pos in state.last_changed.iter() {
+            let source = match reader.u8()? {
+                0 => MatchSource::PlayerSwap,
+                1 => MatchSource::Gravity,
+                2 => MatchSource::Scripted,
+                _ => return Err(SnapshotError::InvalidData)
+            };
+            state.change_sources.insert(pos, source);
+        }
+
+        let counter_count = reader.u16()?;
+        state.counters = HashMap::with_capacity(usize::from(counter_count));
+        for _ in 0..counter_count {
+            let pos = reader.pos(width, height)?;
+            state.counters.insert(pos, reader.u32()?);
+        }
+
+        let block_count = reader.u16()?;
+        let mut covered = BitBoard::new(width, height);
+        for _ in 0..block_count {
+            let anchor = reader.pos(width, height)?;
+            let block_width = reader.u8()?;
+            let block_height = reader.u8()?;
+            let fits_width = u16::from(anchor.x()) + u16::from(block_width) <= u16::from(width);
+            let fits_height = u16::from(anchor.y()) + u16::from(block_height) <= u16::from(height);
+            if block_width == 0 || block_height == 0 || !fits_width || !fits_height {
+                return Err(SnapshotError::InvalidData);
+            }
+
+            let block = Block::new(anchor, block_width, block_height);
+            for pos in block.positions() {
+                if covered.is_set(pos) {
+                    return Err(SnapshotError::InvalidData);
+                }
+                covered.set(pos);
+            }
+
+            state.blocks.push(block);
+        }
+
+        let frozen_count = reader.u16()?;
+        for _ in 0..frozen_count {
+            let pos = reader.pos(width, height)?;
+            let turns = reader.u32()?;
+            if turns == 0 {
+                return Err(SnapshotError::InvalidData);
+            }
+            state.frozen.insert(pos, turns);
+        }
+
+        let wall_kind_count = reader.u16()?;
+        for _ in 0..wall_kind_count {
+            let wall_type = char::from_u32(reader.u32()?).ok_or(SnapshotError::InvalidData)?;
+            let kind = match reader.u8()? {
+                0 => WallKind::Decorative,
+                1 => WallKind::Breakable { hit_points: reader.u32()? },
+                _ => return Err(SnapshotError::InvalidData)
+            };
+            state.wall_kinds.insert(wall_type, kind);
+        }
+
+        let wall_count = reader.u16()?;
+        for _ in 0..wall_count {
+            let pos = reader.pos(width, height)?;
+            let wall_type = char::from_u32(reader.u32()?).ok_or(SnapshotError::InvalidData)?;
+            let hit_points = reader.u32()?;
+            let is_wall = state.type_grid[usize::from(pos.y()) * usize::from(width) + usize::from(pos.x())] == 0
+                && !state.empties.is_set(pos);
+            if !is_wall || !state.wall_kinds.contains_key(&wall_type) {
+                return Err(SnapshotError::InvalidData);
+            }
+            state.walls.insert(pos, (wall_type, hit_points));
+        }
+
+        state.rng = Rng::new(reader.u64()?);
+
+        if reader.index != bytes.len() {
+            return Err(SnapshotError::InvalidData);
+        }
+
+        Ok(state)
+    }
+
+}
+
+impl Board {
+
+    /// Encodes the board's state in a compact binary format. Match patterns,
+    /// swap rules, and other configuration are not included. See
+    /// [BoardState::to_bytes()].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.state().to_bytes()
+    }
+
+    /// Saves the board's pieces, counters, frozen pieces, walls, blocks, positions
+    /// marked for a match check, and random number generator in a checkpoint that can be
+    /// restored with [restore()](Board::restore).
+    pub fn snapshot(&self) -> BoardSnapshot {
+        BoardSnapshot { state: self.state().clone() }
+    }
+
+    /// Returns the board to a checkpoint taken with [snapshot()](Board::snapshot).
+    /// The board's existing memory is reused where possible, so restoring
+    /// checkpoints repeatedly does not allocate. Queued effects are not
+    /// changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshot` - the checkpoint to return to
+    ///
+    /// # Panics
+    ///
+    /// Panics if the checkpoint was taken from a board with a different size.
+    pub fn restore(&mut self, snapshot: &BoardSnapshot) {
+        let state = self.state_mut();
+        if state.width != snapshot.state.width || state.height != snapshot.state.height {
+            panic!("Tried to restore {}x{} snapshot on {}x{} board", snapshot.state.width,
+                   snapshot.state.height, state.width, state.height);
+        }
+
+        state.clone_from(&snapshot.state);
+        self.refresh_zobrist();
+    }
+
+    /// Creates a board from a state encoded with [to_bytes()](Board::to_bytes).
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - the encoded board state
+    /// * `patterns` - the match patterns the board should use. See [Board::new()].
+    /// * `swap_rules` - the swap rules the board should use. See [Board::new()].
+    pub fn from_bytes(bytes: &[u8], patterns: Vec<MatchPattern>,
+                      swap_rules: Vec<SwapRule>) -> Result<Board, SnapshotError> {
+        Ok(Board::new(BoardState::from_bytes(bytes)?, patterns, swap_rules))
+    }
+
+}
+
+/// Gets the number of bits needed to store every value from zero to a maximum.
+///
+/// # Arguments
+///
+/// * `max` - the largest value to store
+fn bits_for(max: usize) -> u32 {
+    usize::BITS - max.leading_zeros()
+}
+
+/// Packs values into bytes, starting with the lowest bit of each value
+/// and the lowest bit of each byte.
+///
+/// # Arguments
+///
+/// * `bytes` - the bytes to add the packed values to
+/// * `values` - the values to pack
+/// * `bits_per_value` - the number of bits to store for each value
+fn write_bits(bytes: &mut Vec<u8>, values: impl Iterator<Item = u64>, bits_per_value: u32) {
+    let mut current = 0u8;
+    let mut used = 0;
+
+    for value in values {
+        for bit in 0..bits_per_value {
+            current |= (((value >> bit) & 1) as u8) << used;
+            used += 1;
+
+            if used == 8 {
+                bytes.push(current);
+                current = 0;
+                used = 0;
+            }
+        }
+    }
+
+    if used > 0 {
+        bytes.push(current);
+    }
+}
+
+/// Packs a bitboard into bytes, one bit per space, row by row from the bottom row.
+///
+/// # Arguments
+///
+/// * `bytes` - the bytes to add the packed bitboard to
+/// * `board` - the bitboard to pack
+fn write_bitboard(bytes: &mut Vec<u8>, board: &BitBoard) {
+    let positions = (0..board.height())
+        .flat_map(|y| (0..board.width()).map(move |x| Pos::new(x, y)));
+    write_bits(bytes, positions.map(|pos| u64::from(board.is_set(pos))), 1);
+}
+
+/// Reads values from encoded bytes in order.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    index: usize
+}
+
+impl Reader<'_> {
+
+    /// Reads a number of bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `length` - the number of bytes to read
+    fn take(&mut self, length: usize) -> Result<&[u8], SnapshotError> {
+        let end = self.index.checked_add(length).ok_or(SnapshotError::UnexpectedEnd)?;
+        let taken = self.bytes.get(self.index..end).ok_or(SnapshotError::UnexpectedEnd)?;
+        self.index = end;
+        Ok(taken)
+    }
+
+    /// Reads one byte.
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Reads a little-endian u16.
+    fn u16(&mut self) -> Result<u16, SnapshotError> {
+        Ok(u16::from_le_bytes(<[u8; 2]>::try_from(self.take(2)?).unwrap()))
+    }
+
+    /// Reads a little-endian u32.
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(<[u8; 4]>::try_from(self.take(4)?).unwrap()))
+    }
+
+    /// Reads a little-endian u64.
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(<[u8; 8]>::try_from(self.take(8)?).unwrap()))
+    }
+
+    /// Reads a position and checks that it is on a board.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - the width of the board
+    /// * `height` - the height of the board
+    fn pos(&mut self, width: u8, height: u8) -> Result<Pos, SnapshotError> {
+        let x = self.u8()?;
+        let y = self.u8()?;
+        if x >= width || y >= height {
+            return Err(SnapshotError::InvalidData);
+        }
+
+        Ok(Pos::new(x, y))
+    }
+
+    /// Reads values packed by [write_bits()].
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - the number of values to read
+    /// * `bits_per_value` - the number of bits stored for each value
+    fn bits(&mut self, count: usize, bits_per_value: u32) -> Result<Vec<u64>, SnapshotError> {
+        let total_bits = count * bits_per_value as usize;
+        let packed = self.take(total_bits.div_ceil(8))?;
+
+        Ok((0..count).map(|value_index| {
+            (0..bits_per_value).fold(0, |value, bit| {
+                let bit_index = value_index * bits_per_value as usize + bit as usize;
+                let bit_value = (packed[bit_index / 8] >> (bit_index % 8)) & 1;
+                value | (u64::from(bit_value) << bit)
+            })
+        }).collect())
+    }
+
+    /// Reads a bitboard packed by [write_bitboard()].
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - the width of the bitboard
+    /// * `height` - the height of the bitboard
+    fn bitboard(&mut self, width: u8, height: u8) -> Result<BitBoard, SnapshotError> {
+        let mut board = BitBoard::new(width, height);
+        let bits = self.bits(usize::from(width) * usize::from(height), 1)?;
+
+        for (index, bit) in bits.into_iter().enumerate() {
+            if bit == 1 {
+                board.set(Pos::new((index % usize::from(width)) as u8, (index / usize::from(width)) as u8));
+            }
+        }
+
+        Ok(board)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocks::Block;
+    use crate::board::{Board, BoardState};
+    use crate::matching::MatchSource;
+    use crate::piece::{Direction, Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::snapshot::SnapshotError;
+    use crate::walls::WallKind;
+    use enumset::{enum_set, EnumSet};
+
+    /// Creates a board with every kind of piece, pieces that only move in
+    /// some directions, counters, and a seed, so that every part of the
+    /// state is written to a snapshot.
+    fn mixed_board() -> Board {
+        let mut board = Board::new(BoardState::new(7, 5), Vec::new(), Vec::new());
+        for x in 0..7 {
+            for y in 0..5 {
+                let piece = match (x + 2 * y) % 5 {
+                    0 => Piece::Empty,
+                    1 => Piece::Regular('f', ALL_DIRECTIONS),
+                    2 => Piece::Regular('s', enum_set!(Direction::North | Direction::East)),
+                    3 => Piece::Regular('🍒', ALL_DIRECTIONS),
+                    _ => Piece::Wall
+                };
+                board.set_piece(Pos::new(x, y), piece);
+            }
+        }
+        board.set_counter(Pos::new(1, 0), 4);
+        board.set_counter(Pos::new(6, 4), 9);
+        board.set_seed(1234);
+        board
+    }
+
+    #[test]
+    fn from_bytes_to_bytes_same_state() {
+        let board = mixed_board();
+        let decoded = BoardState::from_bytes(&board.to_bytes()).unwrap();
+        assert_eq!(board.state(), &decoded);
+    }
+
+    #[test]
+    fn from_bytes_empty_board_same_state() {
+        let board = Board::new(BoardState::new(0, 0), Vec::new(), Vec::new());
+        let decoded = BoardState::from_bytes(&board.to_bytes()).unwrap();
+        assert_eq!(board.state(), &decoded);
+    }
+
+    #[test]
+    fn from_bytes_board_has_same_pieces() {
+        let board = mixed_board();
+        let decoded = Board::from_bytes(&board.to_bytes(), Vec::new(), Vec::new()).unwrap();
+
+        for x in 0..7 {
+            for y in 0..5 {
+                assert_eq!(board.piece(Pos::new(x, y)), decoded.piece(Pos::new(x, y)));
+            }
+        }
+        assert_eq!(Some(4), decoded.counter(Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn to_bytes_same_state_same_bytes() {
+        assert_eq!(mixed_board().to_bytes(), mixed_board().to_bytes());
+    }
+
+    #[test]
+    fn to_bytes_one_type_less_than_one_byte_per_space() {
+        let mut board = Board::new(BoardState::new(16, 16), Vec::new(), Vec::new());
+        for x in 0..16 {
+            for y in 0..16 {
+                board.set_piece(Pos::new(x, y), Piece::Regular('f', ALL_DIRECTIONS));
+            }
+        }
+        while board.next_match().is_some() {}
+
+        assert!(board.to_bytes().len() < 16 * 16);
+    }
+
+    #[test]
+    fn from_bytes_gravity_directions_differ_same_state() {
+        let mut board = mixed_board();
+        board.set_gravity_movable(Pos::new(1, 0), EnumSet::new());
+
+        let decoded = BoardState::from_bytes(&board.to_bytes()).unwrap();
+
+        assert_eq!(board.state(), &decoded);
+        assert!(!decoded.gravity_directions[Direction::South as usize].is_set(Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn from_bytes_blocks_same_state() {
+        let mut board = mixed_board();
+        board.place_block(Pos::new(2, 1), 2, 3, 'l');
+
+        let decoded = BoardState::from_bytes(&board.to_bytes()).unwrap();
+
+        assert_eq!(board.state(), &decoded);
+        assert_eq!(1, decoded.blocks.len());
+    }
+
+    #[test]
+    fn from_bytes_frozen_same_state() {
+        let mut board = mixed_board();
+        board.freeze(Pos::new(3, 2), 2);
+        board.freeze(Pos::new(0, 4), 5);
+
+        let decoded = BoardState::from_bytes(&board.to_bytes()).unwrap();
+
+        assert_eq!(board.state(), &decoded);
+        assert_eq!(2, decoded.frozen.len());
+    }
+
+    #[test]
+    fn from_bytes_many_types_same_state() {
+        let mut board = Board::new(BoardState::new(20, 20), Vec::new(), Vec::new());
+        for index in 0..400u32 {
+            let pos = Pos::new((index % 20) as u8, (index / 20) as u8);
+            board.set_piece(pos, Piece::Regular(std::char::from_u32(0x100 + index).unwrap(), ALL_DIRECTIONS));
+        }
+
+        assert_eq!(board.state(), &BoardState::from_bytes(&board.to_bytes()).unwrap());
+    }
+
+    #[test]
+    fn from_bytes_walls_same_state() {
+        let mut board = mixed_board();
+        board.register_wall_type('c', WallKind::Breakable { hit_points: 3 });
+        board.register_wall_type('d', WallKind::Decorative);
+        board.place_wall(Pos::new(2, 2), 'c');
+        board.place_wall(Pos::new(5, 0), 'd');
+
+        let decoded = BoardState::from_bytes(&board.to_bytes()).unwrap();
+
+        assert_eq!(board.state(), &decoded);
+        assert_eq!(2, decoded.walls.len());
+    }
+
+    #[test]
+    fn from_bytes_wall_on_regular_piece_error() {
+        let mut board = mixed_board();
+        board.register_wall_type('c', WallKind::Decorative);
+        board.state_mut().walls.insert(Pos::new(0, 0), ('c', 0));
+
+        assert_eq!(Err(SnapshotError::InvalidData), BoardState::from_bytes(&board.to_bytes()));
+    }
+
+    #[test]
+    fn from_bytes_overlapping_blocks_error() {
+        let mut board = mixed_board();
+        board.place_block(Pos::new(2, 1), 2, 3, 'l');
+        board.state_mut().blocks.push(Block::new(Pos::new(3, 3), 2, 2));
+
+        assert_eq!(Err(SnapshotError::InvalidData), BoardState::from_bytes(&board.to_bytes()));
+    }
+
+    #[test]
+    fn from_bytes_empty_space_with_type_error() {
+        let mut board = mixed_board();
+        board.state_mut().empties.set(Pos::new(1, 0));
+
+        assert_eq!(Err(SnapshotError::InvalidData), BoardState::from_bytes(&board.to_bytes()));
+    }
+
+    #[test]
+    fn from_bytes_older_version_error() {
+        let mut bytes = mixed_board().to_bytes();
+        bytes[4] = 0;
+        assert_eq!(Err(SnapshotError::UnsupportedVersion(0)), BoardState::from_bytes(&bytes));
+    }
+
+    #[test]
+    fn from_bytes_wrong_header_error() {
+        let mut bytes = mixed_board().to_bytes();
+        bytes[0] = b'X';
+        assert_eq!(Err(SnapshotError::InvalidHeader), BoardState::from_bytes(&bytes));
+    }
+
+    #[test]
+    fn from_bytes_wrong_version_error() {
+        let mut bytes = mixed_board().to_bytes();
+        bytes[4] = 99;
+        assert_eq!(Err(SnapshotError::UnsupportedVersion(99)), BoardState::from_bytes(&bytes));
+    }
+
+    #[test]
+    fn from_bytes_truncated_error() {
+        let bytes = mixed_board().to_bytes();
+        assert_eq!(Err(SnapshotError::UnexpectedEnd), BoardState::from_bytes(&bytes[..bytes.len() - 1]));
+    }
+
+    #[test]
+    fn from_bytes_match_sources_same_state() {
+        let mut board = mixed_board();
+        board.state_mut().mark_changed(Pos::new(0, 0), MatchSource::Gravity);
+        board.state_mut().mark_changed(Pos::new(1, 0), MatchSource::PlayerSwap);
+
+        let decoded = BoardState::from_bytes(&board.to_bytes()).unwrap();
+
+        assert_eq!(board.state(), &decoded);
+        assert_eq!(Some(&MatchSource::Gravity), decoded.change_sources.get(&Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn from_bytes_invalid_match_source_error() {
+        let board = mixed_board();
+        let mut bytes = board.to_bytes();
+        let last_source_index = bytes.len() - 8 - 8 - 2 - 6 * board.state().counters.len() - 1;
+        bytes[last_source_index] = 3;
+
+        assert_eq!(Err(SnapshotError::InvalidData), BoardState::from_bytes(&bytes));
+    }
+
+    #[test]
+    fn from_bytes_extra_bytes_error() {
+        let mut bytes = mixed_board().to_bytes();
+        bytes.push(0);
+        assert_eq!(Err(SnapshotError::InvalidData), BoardState::from_bytes(&bytes));
+    }
+
+    #[test]
+    fn restore_after_changes_returns_to_snapshot() {
+        let mut board = mixed_board();
+        let snapshot = board.snapshot();
+        board.set_piece(Pos::new(0, 0), Piece::Regular('z', ALL_DIRECTIONS));
+        board.set_counter(Pos::new(1, 0), 4);
+        board.rng_mut().next_below(10);
+        while board.next_match().is_some() {}
+
+        board.restore(&snapshot);
+
+        assert_eq!(snapshot.state(), board.state());
+    }
+
+    #[test]
+    fn restore_same_snapshot_twice_same_state() {
+        let mut board = mixed_board();
+        let snapshot = board.snapshot();
+
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        board.restore(&snapshot);
+        board.set_piece(Pos::new(3, 3), Piece::Empty);
+        board.restore(&snapshot);
+
+        assert_eq!(snapshot.state(), board.state());
+    }
+
+    #[test]
+    #[should_panic]
+    fn restore_different_size_panics() {
+        let mut board = mixed_board();
+        let other = Board::new(BoardState::new(3, 3), Vec::new(), Vec::new());
+
+        board.restore(&other.snapshot());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,647 @@
+use crate::board::Board;
+use crate::cascade::Spawner;
+use crate::events::BoardEvent;
+use crate::goals::{Goal, Goals};
+use crate::levels::{adjacent_pairs, best_swap};
+use crate::piece::{Piece, PieceType};
+use crate::position::Pos;
+use crate::random::Rng;
+#[cfg(feature = "parallel")]
+use crate::stats::MatchStats;
+
+/// How the simulated player in [estimate_difficulty()] chooses swaps.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlayoutStrategy {
+
+    /// Make any swap of adjacent pieces that causes a match, chosen randomly.
+    Random,
+
+    /// Make the swap of adjacent pieces that clears the most pieces right away,
+    /// choosing randomly between equally good swaps.
+    Greedy
+
+}
+
+/// Statistics about many simulated attempts to complete a board's goals.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DifficultyEstimate {
+    playouts: usize,
+    wins: usize,
+    winning_moves: u64
+}
+
+impl DifficultyEstimate {
+
+    /// Gets the number of simulated attempts.
+    pub fn playouts(&self) -> usize {
+        self.playouts
+    }
+
+    /// Gets the number of attempts that completed all the goals.
+    pub fn wins(&self) -> usize {
+        self.wins
+    }
+
+    /// Gets the fraction of attempts that did not complete all the goals.
+    /// Returns 0.0 if there were no attempts.
+    pub fn fail_rate(&self) -> f64 {
+        if self.playouts == 0 {
+            return 0.0;
+        }
+
+        (self.playouts - self.wins) as f64 / self.playouts as f64
+    }
+
+    /// Gets the average number of moves used by the attempts that completed
+    /// all the goals. Returns None if no attempt completed the goals.
+    pub fn average_moves_to_win(&self) -> Option<f64> {
+        if self.wins == 0 {
+            return None;
+        }
+
+        Some(self.winning_moves as f64 / self.wins as f64)
+    }
+
+}
+
+/// Estimates how hard it is to complete a board's goals by simulating many
+/// attempts. Higher fail rates and more moves to win mean a harder level.
+///
+/// Every attempt starts from the board's current state. The seed for each
+/// attempt's choices comes from the board's random number generator, so a
+/// board with the same seed and a spawner in the same state produce the same
+/// estimate. Matches are resolved and the board is refilled after each swap.
+/// An attempt fails if the moves run out or no swap causes a match.
+///
+/// The attempts are played on a copy of the board, so the board's state,
+/// turn report, effects, and analytics are not changed.
+///
+/// # Arguments
+///
+/// * `board` - the board to play on
+/// * `spawner` - chooses the new pieces that fill the board in every attempt
+/// * `goals` - the goals that must be completed
+/// * `moves` - the number of moves available in each attempt
+/// * `strategy` - how the simulated player chooses swaps
+/// * `n_playouts` - the number of attempts to simulate
+pub fn estimate_difficulty(board: &mut Board, spawner: &mut dyn Spawner, goals: &[Goal], moves: u32,
+                           strategy: PlayoutStrategy, n_playouts: usize) -> DifficultyEstimate {
+    let initial_state = board.state().clone();
+    let mut seeds = initial_state.rng;
+    let mut estimate = DifficultyEstimate { playouts: 0, wins: 0, winning_moves: 0 };
+
+    let mut copy = board.scratch_copy();
+    board.trade_rules(&mut copy);
+
+    for _ in 0..n_playouts {
+        let seed = seeds.next_u64();
+        copy.restore_state(initial_state.clone());
+        copy.set_seed(seed);
+        let mut rng = Rng::new(seed);
+        let mut progress = Goals::new(goals.to_vec());
+
+        let mut used = 0;
+        while !progress.are_all_complete() && used < moves {
+            let swap = match strategy {
+                PlayoutStrategy::Random => random_swap(&copy, &mut rng),
+                PlayoutStrategy::Greedy => best_swap(&copy, &mut rng)
+            };
+
+            match swap {
+                Some((first, second)) if copy.swap_pieces(first, second) => {
+                    used += 1;
+                    copy.resolve_cascade(spawner, &mut progress);
+                },
+                _ => break
+            }
+        }
+
+        estimate.playouts += 1;
+        if progress.are_all_complete() {
+            estimate.wins += 1;
+            estimate.winning_moves += u64::from(used);
+        }
+    }
+
+    board.trade_rules(&mut copy);
+    estimate
+}
+
+/// Chooses a random swap of adjacent pieces that causes a match. Returns None
+/// if no swap causes a match.
+///
+/// # Arguments
+///
+/// * `board` - the board to search for swaps
+/// * `rng` - chooses the swap
+fn random_swap(board: &Board, rng: &mut Rng) -> Option<(Pos, Pos)> {
+    let swaps: Vec<(Pos, Pos)> = adjacent_pairs(board).into_iter()
+        .filter(|&(first, second)| board.preview_swap(first, second).is_some_and(|matches| !matches.is_empty()))
+        .collect();
+
+    if swaps.is_empty() {
+        return None;
+    }
+
+    Some(swaps[rng.next_below(swaps.len())])
+}
+
+/// Finds the shortest sequence of swaps that clears every regular piece from
+/// a board in puzzle mode, where the board is never refilled. Returns None if
+/// the board cannot be cleared with at most the given number of swaps.
+///
+/// Only swaps of adjacent pieces that cause a match are considered, like in
+/// most games, and each swap must be allowed by the board's swap rules. After
+/// each swap, all matches are resolved and the remaining pieces trickle down.
+///
+/// The search is an iterative deepening depth-first search, so it always
+/// finds an optimal solution, but its running time grows exponentially with
+/// the length of the solution. It is only practical for small boards. Boards
+/// where some type has too few pieces left to match are skipped.
+///
+/// The search plays on a copy of the board, so the board's state, turn
+/// report, effects, and analytics are not changed.
+///
+/// # Arguments
+///
+/// * `board` - the board to solve
+/// * `max_swaps` - the largest number of swaps to search for
+pub fn solve(board: &mut Board, max_swaps: usize) -> Option<Vec<(Pos, Pos)>> {
+    let mut copy = board.scratch_copy();
+    board.trade_rules(&mut copy);
+
+    let mut swaps = Vec::new();
+    let is_solved = (0..=max_swaps).any(|depth| search(&mut copy, depth, &mut swaps));
+
+    board.trade_rules(&mut copy);
+    match is_solved {
+        true => Some(swaps),
+        false => None
+    }
+}
+
+/// Searches for a sequence of exactly a given number of swaps that clears the
+/// board. Returns true and leaves the swaps in the list if one is found.
+///
+/// # Arguments
+///
+/// * `board` - the board to search
+/// * `depth` - the number of swaps left to make
+/// * `swaps` - the swaps made so far
+fn search(board: &mut Board, depth: usize, swaps: &mut Vec<(Pos, Pos)>) -> bool {
+    if board.counts().is_empty() {
+        return depth == 0;
+    }
+
+    if depth == 0 || !can_be_cleared(board) {
+        return false;
+    }
+
+    for (first, second) in adjacent_pairs(board) {
+        if board.piece(first) == board.piece(second) {
+            continue;
+        }
+
+        match board.preview_swap(first, second) {
+            Some(matches) if !matches.is_empty() => {},
+            _ => continue
+        }
+
+        let state = board.state().clone();
+        if !board.swap_pieces(first, second) {
+            continue;
+        }
+        board.resolve_cascade(&mut |_: &Board, _| Piece::Empty, &mut |_: &BoardEvent| {});
+        swaps.push((first, second));
+
+        let is_solved = search(board, depth - 1, swaps);
+        board.restore_state(state);
+        if is_solved {
+            return true;
+        }
+
+        swaps.pop();
+    }
+
+    false
+}
+
+/// The result of simulating one swap with [simulate_many()].
+#[cfg(feature = "parallel")]
+#[derive(Clone, PartialEq, Debug)]
+pub struct SwapOutcome {
+    swap: (Pos, Pos),
+    is_accepted: bool,
+    stats: MatchStats
+}
+
+#[cfg(feature = "parallel")]
+impl SwapOutcome {
+
+    /// Gets the two positions that were swapped.
+    pub fn swap(&self) -> (Pos, Pos) {
+        self.swap
+    }
+
+    /// Checks whether the board allowed the swap. A rejected swap has no matches.
+    pub fn is_accepted(&self) -> bool {
+        self.is_accepted
+    }
+
+    /// Gets the statistics of the cascade that the swap caused.
+    pub fn stats(&self) -> &MatchStats {
+        &self.stats
+    }
+
+    /// Gets the score of the swap, which is the number of pieces that its
+    /// cascade cleared.
+    pub fn score(&self) -> u64 {
+        self.stats.pieces_cleared()
+    }
+
+}
+
+/// Simulates many possible swaps on copies of a board across a rayon thread
+/// pool, such as to find hints or choose moves for a computer player on a
+/// large board. The outcomes are in the same order as the swaps, regardless
+/// of the order in which the threads finish.
+///
+/// Each swap is checked against the board's swap rules and type swap rules,
+/// then made on its own copy of the board. All matches are then resolved
+/// without refilling the board, so the outcome only depends on the pieces
+/// already on the board. The board itself is not changed.
+///
+/// # Arguments
+///
+/// * `board` - the board to simulate swaps on
+/// * `swaps` - the pairs of positions to swap
+/// * `pool` - the thread pool to simulate the swaps in, which lets a game
+///   limit how many threads the simulation uses
+///
+/// # Panics
+///
+/// Panics if any position is outside the board.
+#[cfg(feature = "parallel")]
+pub fn simulate_many(board: &Board, swaps: &[(Pos, Pos)], pool: &rayon::ThreadPool) -> Vec<SwapOutcome> {
+    use rayon::prelude::*;
+
+    pool.install(|| swaps.par_iter().map(|&swap| simulate(board, swap)).collect())
+}
+
+/// Simulates one swap on a copy of a board and resolves its matches without
+/// refilling the board. The copy has none of the board's rules, so the board
+/// decides whether the swap is allowed.
+///
+/// # Arguments
+///
+/// * `board` - the board to copy
+/// * `swap` - the pair of positions to swap
+#[cfg(feature = "parallel")]
+fn simulate(board: &Board, (first, second): (Pos, Pos)) -> SwapOutcome {
+    let mut copy = board.scratch_copy();
+    let mut stats = MatchStats::new();
+
+    if !board.is_within_board(first) || !board.is_within_board(second) {
+        panic!("Tried to swap piece outside board: {} with {}", first, second);
+    }
+
+    let is_accepted = board.passes_swap_rules(first, second) && copy.swap_without_rules(first, second);
+    if is_accepted {
+        copy.resolve_cascade(&mut |_: &Board, _| Piece::Empty, &mut stats);
+    }
+
+    SwapOutcome { swap: (first, second), is_accepted, stats }
+}
+
+/// Checks whether every remaining type has enough pieces to fill at least one
+/// pattern. Patterns with a tag may match several types, so they are assumed
+/// to be able to match any type.
+///
+/// # Arguments
+///
+/// * `board` - the board to check
+fn can_be_cleared(board: &Board) -> bool {
+    board.counts().into_iter().all(|(piece_type, count): (PieceType, usize)| {
+        board.patterns().iter().any(|pattern| {
+            (pattern.piece_type() == piece_type || pattern.tag().is_some() || pattern.is_any_type())
+                && pattern.min_pieces() <= count
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, BoardState, TypeSwap};
+    use crate::events::BoardEvent;
+    use crate::matching::MatchPattern;
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::goals::Goal;
+    use crate::solver::{estimate_difficulty, solve, PlayoutStrategy};
+    #[cfg(feature = "parallel")]
+    use crate::solver::simulate_many;
+    use crate::spawners::WeightedSpawner;
+    use std::collections::HashSet;
+
+    fn horizontal_three(piece_type: char) -> MatchPattern {
+        let mut spaces = HashSet::new();
+        spaces.insert(Pos::new(0, 0));
+        spaces.insert(Pos::new(1, 0));
+        spaces.insert(Pos::new(2, 0));
+        MatchPattern::new(piece_type, spaces, 1)
+    }
+
+    fn puzzle(rows: &[&str]) -> Board {
+        let height = rows.len() as u8;
+        let width = rows[0].len() as u8;
+        let mut board = Board::new(
+            BoardState::new(width, height),
+            vec![horizontal_three('a'), horizontal_three('b')],
+            Vec::new()
+        );
+
+        for (row_index, row) in rows.iter().enumerate() {
+            for (x, symbol) in row.chars().enumerate() {
+                let piece = match symbol {
+                    '.' => Piece::Empty,
+                    piece_type => Piece::Regular(piece_type, ALL_DIRECTIONS)
+                };
+                board.set_piece(Pos::new(x as u8, height - 1 - row_index as u8), piece);
+            }
+        }
+        while board.next_match().is_some() {}
+
+        board
+    }
+
+    #[test]
+    fn solve_already_clear_no_swaps() {
+        let mut board = puzzle(&["...", "..."]);
+        assert_eq!(Some(Vec::new()), solve(&mut board, 3));
+    }
+
+    #[test]
+    fn solve_two_lines_one_swap_found() {
+        let mut board = puzzle(&["bab", "aba"]);
+        assert_eq!(Some(vec![(Pos::new(1, 0), Pos::new(1, 1))]), solve(&mut board, 3));
+    }
+
+    #[test]
+    fn solve_one_swap_found() {
+        let mut board = puzzle(&[".a.", "a.a"]);
+
+        assert_eq!(Some(vec![(Pos::new(1, 0), Pos::new(1, 1))]), solve(&mut board, 3));
+    }
+
+    #[test]
+    fn solve_two_swaps_shortest_found() {
+        let mut board = puzzle(&["b.b.", "aaba"]);
+
+        let swaps = solve(&mut board, 4).unwrap();
+
+        assert_eq!(2, swaps.len());
+        assert_eq!((Pos::new(2, 0), Pos::new(3, 0)), swaps[0]);
+    }
+
+    #[test]
+    fn solve_too_few_pieces_none() {
+        let mut board = puzzle(&[".a.", "aab"]);
+        assert_eq!(None, solve(&mut board, 5));
+    }
+
+    #[test]
+    fn solve_needs_more_swaps_than_max_none() {
+        let mut board = puzzle(&["b.b.", "aaba"]);
+
+        assert_eq!(None, solve(&mut board, 1));
+    }
+
+    #[test]
+    fn solve_board_unchanged() {
+        let mut board = puzzle(&["b.b.", "aaba"]);
+        let state = board.state().clone();
+
+        solve(&mut board, 4);
+
+        assert_eq!(&state, board.state());
+    }
+
+    #[test]
+    fn solve_turn_report_and_effects_unchanged() {
+        let mut board = puzzle(&["b.b.", "aaba"]);
+        board.set_effects_enabled(true);
+        let report = board.turn_report().clone();
+
+        solve(&mut board, 4);
+
+        assert_eq!(&report, board.turn_report());
+        assert!(board.effects().is_empty());
+    }
+
+    #[test]
+    fn solve_swap_rules_followed_and_kept() {
+        let mut board = puzzle(&["bab", "aba"]);
+        board.set_type_swap_rule('a', Box::new(|_, _, _, _| TypeSwap::Never));
+
+        assert_eq!(None, solve(&mut board, 3));
+        assert!(!board.swap_pieces(Pos::new(1, 0), Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn solve_swaps_clear_board() {
+        let mut board = puzzle(&["b.b.", "aaba"]);
+
+        for (first, second) in solve(&mut board, 4).unwrap() {
+            assert!(board.swap_pieces(first, second));
+            board.resolve_cascade(&mut |_: &Board, _| Piece::Empty, &mut |_: &BoardEvent| {});
+        }
+
+        assert!(board.counts().is_empty());
+    }
+
+    fn checkered(width: u8, height: u8) -> Board {
+        let mut board = Board::new(
+            BoardState::new(width, height),
+            vec![horizontal_three('a'), horizontal_three('b'), horizontal_three('c')],
+            Vec::new()
+        );
+        for x in 0..width {
+            for y in 0..height {
+                let piece_type = ['a', 'b', 'c'][usize::from(x + y) % 3];
+                board.set_piece(Pos::new(x, y), Piece::Regular(piece_type, ALL_DIRECTIONS));
+            }
+        }
+        while board.next_match().is_some() {}
+
+        board
+    }
+
+    #[test]
+    fn estimate_difficulty_easy_goal_no_fails() {
+        let mut board = puzzle(&["bab", "aba"]);
+        let mut spawner = |_: &Board, _| Piece::Empty;
+
+        let estimate = estimate_difficulty(
+            &mut board,
+            &mut spawner,
+            &[Goal::Clear { piece_type: 'a', count: 3 }],
+            3,
+            PlayoutStrategy::Greedy,
+            10
+        );
+
+        assert_eq!(10, estimate.playouts());
+        assert_eq!(10, estimate.wins());
+        assert_eq!(0.0, estimate.fail_rate());
+        assert_eq!(Some(1.0), estimate.average_moves_to_win());
+    }
+
+    #[test]
+    fn estimate_difficulty_impossible_goal_all_fail() {
+        let mut board = puzzle(&["bab", "aba"]);
+        let mut spawner = |_: &Board, _| Piece::Empty;
+
+        let estimate = estimate_difficulty(
+            &mut board,
+            &mut spawner,
+            &[Goal::Clear { piece_type: 'a', count: 30 }],
+            3,
+            PlayoutStrategy::Random,
+            5
+        );
+
+        assert_eq!(0, estimate.wins());
+        assert_eq!(1.0, estimate.fail_rate());
+        assert_eq!(None, estimate.average_moves_to_win());
+    }
+
+    #[test]
+    fn estimate_difficulty_no_playouts_no_fails() {
+        let mut board = puzzle(&["bab", "aba"]);
+        let mut spawner = |_: &Board, _| Piece::Empty;
+
+        let estimate = estimate_difficulty(&mut board, &mut spawner, &[], 3, PlayoutStrategy::Random, 0);
+
+        assert_eq!(0.0, estimate.fail_rate());
+    }
+
+    #[test]
+    fn estimate_difficulty_same_seed_same_estimate() {
+        let estimate = |strategy| {
+            let mut board = checkered(6, 6);
+            board.set_seed(11);
+            board.set_piece(Pos::new(2, 0), Piece::Regular('a', ALL_DIRECTIONS));
+            let mut spawner = WeightedSpawner::new(vec![('a', 1), ('b', 1), ('c', 1)], 3);
+            estimate_difficulty(
+                &mut board,
+                &mut spawner,
+                &[Goal::Clear { piece_type: 'a', count: 12 }],
+                10,
+                strategy,
+                20
+            )
+        };
+
+        assert_eq!(estimate(PlayoutStrategy::Random), estimate(PlayoutStrategy::Random));
+        assert_eq!(estimate(PlayoutStrategy::Greedy), estimate(PlayoutStrategy::Greedy));
+    }
+
+    #[test]
+    fn estimate_difficulty_board_unchanged() {
+        let mut board = checkered(6, 6);
+        let state = board.state().clone();
+        let mut spawner = WeightedSpawner::new(vec![('a', 1), ('b', 1), ('c', 1)], 3);
+
+        estimate_difficulty(&mut board, &mut spawner, &[Goal::Score(1000)], 5, PlayoutStrategy::Greedy, 5);
+
+        assert_eq!(&state, board.state());
+    }
+
+    #[test]
+    fn estimate_difficulty_turn_report_and_effects_unchanged() {
+        let mut board = puzzle(&["bab", "aba"]);
+        board.set_effects_enabled(true);
+        let report = board.turn_report().clone();
+        let mut spawner = |_: &Board, _| Piece::Empty;
+
+        estimate_difficulty(&mut board, &mut spawner, &[Goal::Clear { piece_type: 'a', count: 3 }], 3,
+                            PlayoutStrategy::Greedy, 2);
+
+        assert_eq!(&report, board.turn_report());
+        assert!(board.effects().is_empty());
+    }
+
+    #[cfg(feature = "parallel")]
+    fn pool() -> rayon::ThreadPool {
+        rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn simulate_many_outcomes_in_swap_order() {
+        let board = puzzle(&["bab", "aba"]);
+        let swaps = [(Pos::new(0, 0), Pos::new(1, 0)), (Pos::new(1, 0), Pos::new(1, 1)), (Pos::new(2, 0), Pos::new(2, 1))];
+
+        let outcomes = simulate_many(&board, &swaps, &pool());
+
+        let scores: Vec<u64> = outcomes.iter().map(|outcome| outcome.score()).collect();
+        assert_eq!(swaps.to_vec(), outcomes.iter().map(|outcome| outcome.swap()).collect::<Vec<_>>());
+        assert_eq!(vec![0, 6, 0], scores);
+        assert_eq!(2, outcomes[1].stats().total_matches());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn simulate_many_board_unchanged() {
+        let board = puzzle(&["bab", "aba"]);
+        let state = board.state().clone();
+
+        simulate_many(&board, &[(Pos::new(1, 0), Pos::new(1, 1))], &pool());
+
+        assert_eq!(&state, board.state());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn simulate_many_rejected_swap_not_accepted() {
+        let mut board = puzzle(&["bab", "aba"]);
+        board.set_piece(Pos::new(1, 1), Piece::Regular('b', enumset::EnumSet::new()));
+
+        let outcomes = simulate_many(&board, &[(Pos::new(1, 0), Pos::new(1, 1))], &pool());
+
+        assert!(!outcomes[0].is_accepted());
+        assert_eq!(0, outcomes[0].score());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn simulate_many_type_swap_rule_allows_unmovable_accepted() {
+        let mut board = puzzle(&["bab", "aba"]);
+        board.set_piece(Pos::new(1, 1), Piece::Regular('a', enumset::EnumSet::new()));
+        board.set_type_swap_rule('a', Box::new(|_, _, _, _| TypeSwap::Always));
+
+        let outcomes = simulate_many(&board, &[(Pos::new(1, 0), Pos::new(1, 1))], &pool());
+
+        assert!(outcomes[0].is_accepted());
+        assert_eq!(6, outcomes[0].score());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn simulate_many_type_swap_rule_forbids_not_accepted() {
+        let mut board = puzzle(&["bab", "aba"]);
+        board.set_type_swap_rule('b', Box::new(|_, _, _, _| TypeSwap::Never));
+
+        let outcomes = simulate_many(&board, &[(Pos::new(1, 0), Pos::new(1, 1))], &pool());
+
+        assert!(!outcomes[0].is_accepted());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn simulate_many_no_swaps_empty() {
+        let board = puzzle(&["bab", "aba"]);
+        assert!(simulate_many(&board, &[], &pool()).is_empty());
+    }
+}
@@ -0,0 +1,520 @@
+use crate::board::Board;
+use crate::cascade::Spawner;
+use crate::levels::has_matching_swap;
+use crate::piece::{Piece, PieceType, ALL_DIRECTIONS};
+use crate::position::Pos;
+use crate::random::Rng;
+
+/// Spawns regular pieces whose types are chosen randomly, with some types
+/// more likely than others. Spawned pieces are movable in all directions.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct WeightedSpawner {
+    weights: Vec<(PieceType, u32)>,
+    total_weight: u64,
+    rng: Rng
+}
+
+impl WeightedSpawner {
+
+    /// Creates a new weighted spawner.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights` - each type that can be spawned and its weight. A type
+    ///   with twice the weight of another is spawned twice as often.
+    /// * `seed` - the seed for the spawner's random number generator
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total of all the weights is zero.
+    pub fn new(weights: Vec<(PieceType, u32)>, seed: u64) -> WeightedSpawner {
+        let total_weight = weights.iter().map(|&(_, weight)| u64::from(weight)).sum();
+        if total_weight == 0 {
+            panic!("Tried to create weighted spawner with no weight");
+        }
+
+        WeightedSpawner { weights, total_weight, rng: Rng::new(seed) }
+    }
+
+    /// Gets each type that can be spawned and its weight.
+    pub fn weights(&self) -> &[(PieceType, u32)] {
+        &self.weights
+    }
+
+}
+
+impl Spawner for WeightedSpawner {
+    fn spawn(&mut self, _: &Board, _: Pos) -> Piece {
+        let mut remaining = self.rng.next_below_u64(self.total_weight);
+
+        for &(piece_type, weight) in self.weights.iter() {
+            if remaining < u64::from(weight) {
+                return Piece::Regular(piece_type, ALL_DIRECTIONS);
+            }
+            remaining -= u64::from(weight);
+        }
+
+        unreachable!("Random weight exceeded total weight")
+    }
+}
+
+/// Spawns regular pieces like a [WeightedSpawner], but makes a type less likely
+/// when the new piece would land next to pieces of the same type. This lowers
+/// the number of matches that happen by accident when the board is refilled.
+/// Spawned pieces are movable in all directions.
+///
+/// Each piece of a type that is next to the spawned piece reduces that type's
+/// weight by the streak penalty. The pieces checked are the pieces to the left
+/// and right of the spawn position and the first pieces below it, down to the
+/// lookdown depth, as long as they have the same type as the one being weighed.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct AntiClusterSpawner {
+    weights: Vec<(PieceType, u32)>,
+    streak_penalty: u8,
+    lookdown_depth: u8,
+    rng: Rng
+}
+
+impl AntiClusterSpawner {
+
+    /// Creates a new anti-clustering spawner with a streak penalty of 50% and
+    /// a lookdown depth of 2.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights` - each type that can be spawned and its weight before any
+    ///   penalty is applied
+    /// * `seed` - the seed for the spawner's random number generator
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total of all the weights is zero.
+    pub fn new(weights: Vec<(PieceType, u32)>, seed: u64) -> AntiClusterSpawner {
+        if weights.iter().all(|&(_, weight)| weight == 0) {
+            panic!("Tried to create anti-cluster spawner with no weight");
+        }
+
+        AntiClusterSpawner { weights, streak_penalty: 50, lookdown_depth: 2, rng: Rng::new(seed) }
+    }
+
+    /// Sets how much each neighboring piece of the same type reduces a type's weight.
+    ///
+    /// # Arguments
+    ///
+    /// * `percent` - the percentage of the weight removed for each neighbor.
+    ///   At 100, a type is never spawned next to its own type
+    ///   unless every type is next to the spawn position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the percentage is greater than 100.
+    pub fn with_streak_penalty(mut self, percent: u8) -> AntiClusterSpawner {
+        if percent > 100 {
+            panic!("Tried to set streak penalty above 100%: {}", percent);
+        }
+
+        self.streak_penalty = percent;
+        self
+    }
+
+    /// Sets how many pieces below the spawn position are checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - the most pieces below the spawn position that are checked
+    pub fn with_lookdown_depth(mut self, depth: u8) -> AntiClusterSpawner {
+        self.lookdown_depth = depth;
+        self
+    }
+
+    /// Gets each type that can be spawned and its weight before any penalty is applied.
+    pub fn weights(&self) -> &[(PieceType, u32)] {
+        &self.weights
+    }
+
+    /// Gets the percentage of a type's weight removed for each neighbor of the same type.
+    pub fn streak_penalty(&self) -> u8 {
+        self.streak_penalty
+    }
+
+    /// Gets the most pieces below the spawn position that are checked.
+    pub fn lookdown_depth(&self) -> u8 {
+        self.lookdown_depth
+    }
+
+    /// Counts the pieces of a type next to the position where a piece will spawn.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - the board that the piece will be placed on
+    /// * `pos` - the position where the piece will be placed
+    /// * `piece_type` - the type to count
+    fn count_neighbors(&self, board: &Board, pos: Pos, piece_type: PieceType) -> u32 {
+        let is_type = |pos: Pos| matches!(board.piece(pos), Piece::Regular(neighbor, _) if neighbor == piece_type);
+        let mut count = 0;
+
+        if pos.x() > 0 && is_type(Pos::new(pos.x() - 1, pos.y())) {
+            count += 1;
+        }
+        if pos.x() + 1 < board.state().width && is_type(Pos::new(pos.x() + 1, pos.y())) {
+            count += 1;
+        }
+
+        let below = (0..pos.y()).rev()
+            .map(|y| Pos::new(pos.x(), y))
+            .filter(|&below| board.piece(below) != Piece::Empty)
+            .take(usize::from(self.lookdown_depth))
+            .take_while(|&below| is_type(below))
+            .count();
+
+        count + below as u32
+    }
+
+}
+
+impl Spawner for AntiClusterSpawner {
+    fn spawn(&mut self, board: &Board, pos: Pos) -> Piece {
+        // Scale the weights up so that repeated penalties keep their precision
+        let mut weights: Vec<u64> = self.weights.iter().map(|&(piece_type, weight)| {
+            let neighbors = self.count_neighbors(board, pos, piece_type);
+            (0..neighbors).fold(u64::from(weight) << 20, |weight, _| {
+                weight * u64::from(100 - self.streak_penalty) / 100
+            })
+        }).collect();
+
+        if weights.iter().all(|&weight| weight == 0) {
+            weights = self.weights.iter().map(|&(_, weight)| u64::from(weight)).collect();
+        }
+
+        let total_weight: u64 = weights.iter().sum();
+        let mut remaining = self.rng.next_below_u64(total_weight);
+
+        for (&(piece_type, _), &weight) in self.weights.iter().zip(weights.iter()) {
+            if remaining < weight {
+                return Piece::Regular(piece_type, ALL_DIRECTIONS);
+            }
+            remaining -= weight;
+        }
+
+        unreachable!("Random weight exceeded total weight")
+    }
+}
+
+/// Spawns pieces with another spawner, but sometimes helps the player when no
+/// swap on the board causes a match. When it helps, the spawner picks a type
+/// that gives the board at least one swap that causes a match once the new
+/// piece lands. Helpful pieces are movable in all directions.
+///
+/// The mercy is the chance, as a percentage, that the spawner checks whether
+/// the board is stale and helps. Checking whether the board is stale tries
+/// every swap, so a lower mercy also makes spawning less expensive.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct HelpfulSpawner<S> {
+    base: S,
+    types: Vec<PieceType>,
+    mercy: u8,
+    rng: Rng
+}
+
+impl<S: Spawner> HelpfulSpawner<S> {
+
+    /// Creates a new helpful spawner with a mercy of 25%.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - spawns pieces when the spawner does not help
+    /// * `types` - the types that the spawner may choose from when it helps
+    /// * `seed` - the seed for the spawner's random number generator
+    pub fn new(base: S, types: Vec<PieceType>, seed: u64) -> HelpfulSpawner<S> {
+        HelpfulSpawner { base, types, mercy: 25, rng: Rng::new(seed) }
+    }
+
+    /// Sets the chance that the spawner helps when the board is stale.
+    ///
+    /// # Arguments
+    ///
+    /// * `percent` - the chance that the spawner helps, from 0 to 100
+    ///
+    /// # Panics
+    ///
+    /// Panics if the percentage is greater than 100.
+    pub fn with_mercy(mut self, percent: u8) -> HelpfulSpawner<S> {
+        if percent > 100 {
+            panic!("Tried to set mercy above 100%: {}", percent);
+        }
+
+        self.mercy = percent;
+        self
+    }
+
+    /// Gets the chance, as a percentage, that the spawner helps when the board is stale.
+    pub fn mercy(&self) -> u8 {
+        self.mercy
+    }
+
+    /// Gets the types that the spawner may choose from when it helps.
+    pub fn types(&self) -> &[PieceType] {
+        &self.types
+    }
+
+    /// Gets the spawner used when the spawner does not help.
+    pub fn base(&self) -> &S {
+        &self.base
+    }
+
+    /// Finds the types that would give the board a swap that causes a match
+    /// after a new piece lands below a position.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - the board that the piece will be placed on
+    /// * `pos` - the position where the piece will be placed
+    fn helpful_types(&self, board: &Board, pos: Pos) -> Vec<PieceType> {
+        let landing = (0..pos.y()).rev()
+            .map(|y| Pos::new(pos.x(), y))
+            .take_while(|&below| board.piece(below) == Piece::Empty)
+            .last()
+            .unwrap_or(pos);
+
+        let mut scratch = board.scratch_copy();
+        self.types.iter().copied().filter(|&piece_type| {
+            scratch.set_piece(landing, Piece::Regular(piece_type, ALL_DIRECTIONS));
+            has_matching_swap(&scratch)
+        }).collect()
+    }
+
+}
+
+impl<S: Spawner> Spawner for HelpfulSpawner<S> {
+    fn spawn(&mut self, board: &Board, pos: Pos) -> Piece {
+        let is_merciful = self.rng.next_below(100) < usize::from(self.mercy);
+        if !is_merciful || has_matching_swap(board) {
+            return self.base.spawn(board, pos);
+        }
+
+        let helpful_types = self.helpful_types(board, pos);
+        if helpful_types.is_empty() {
+            return self.base.spawn(board, pos);
+        }
+
+        let piece_type = helpful_types[self.rng.next_below(helpful_types.len())];
+        Piece::Regular(piece_type, ALL_DIRECTIONS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, BoardState};
+    use crate::cascade::Spawner;
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::matching::MatchPattern;
+    use crate::spawners::{AntiClusterSpawner, HelpfulSpawner, WeightedSpawner};
+
+    #[test]
+    fn spawn_one_type_always_spawned() {
+        let board = Board::new(BoardState::new(1, 1), Vec::new(), Vec::new());
+        let mut spawner = WeightedSpawner::new(vec![('f', 3)], 0);
+
+        for _ in 0..10 {
+            assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), spawner.spawn(&board, Pos::new(0, 0)));
+        }
+    }
+
+    #[test]
+    fn spawn_zero_weight_never_spawned() {
+        let board = Board::new(BoardState::new(1, 1), Vec::new(), Vec::new());
+        let mut spawner = WeightedSpawner::new(vec![('f', 0), ('s', 1)], 0);
+
+        for _ in 0..100 {
+            assert_eq!(Piece::Regular('s', ALL_DIRECTIONS), spawner.spawn(&board, Pos::new(0, 0)));
+        }
+    }
+
+    #[test]
+    fn spawn_heavier_type_spawned_more() {
+        let board = Board::new(BoardState::new(1, 1), Vec::new(), Vec::new());
+        let mut spawner = WeightedSpawner::new(vec![('f', 1), ('s', 9)], 5);
+
+        let heavier = (0..1000)
+            .filter(|_| spawner.spawn(&board, Pos::new(0, 0)) == Piece::Regular('s', ALL_DIRECTIONS))
+            .count();
+
+        assert!(heavier > 800);
+    }
+
+    #[test]
+    fn spawn_same_seed_same_pieces() {
+        let board = Board::new(BoardState::new(1, 1), Vec::new(), Vec::new());
+        let mut first = WeightedSpawner::new(vec![('f', 1), ('s', 1), ('t', 1)], 9);
+        let mut second = first.clone();
+
+        for _ in 0..20 {
+            assert_eq!(first.spawn(&board, Pos::new(0, 0)), second.spawn(&board, Pos::new(0, 0)));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_no_weight_panics() {
+        WeightedSpawner::new(vec![('f', 0)], 0);
+    }
+
+    #[test]
+    fn anti_cluster_spawn_full_penalty_avoids_neighbor_type() {
+        let mut board = Board::new(BoardState::new(3, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 1), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        let mut spawner = AntiClusterSpawner::new(vec![('f', 1), ('s', 1), ('t', 1)], 3)
+            .with_streak_penalty(100);
+
+        for _ in 0..100 {
+            assert_eq!(Piece::Regular('t', ALL_DIRECTIONS), spawner.spawn(&board, Pos::new(1, 1)));
+        }
+    }
+
+    #[test]
+    fn anti_cluster_spawn_no_penalty_spawns_all_types() {
+        let mut board = Board::new(BoardState::new(3, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 1), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        let mut spawner = AntiClusterSpawner::new(vec![('f', 1), ('s', 1)], 3)
+            .with_streak_penalty(0);
+
+        let neighbor_type = (0..1000)
+            .filter(|_| spawner.spawn(&board, Pos::new(1, 1)) == Piece::Regular('f', ALL_DIRECTIONS))
+            .count();
+
+        assert!(neighbor_type > 400 && neighbor_type < 600);
+    }
+
+    #[test]
+    fn anti_cluster_spawn_lookdown_skips_empty_spaces() {
+        let mut board = Board::new(BoardState::new(1, 4), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 1), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        board.set_piece(Pos::new(0, 3), Piece::Empty);
+        let mut spawner = AntiClusterSpawner::new(vec![('f', 1), ('s', 1)], 3)
+            .with_streak_penalty(100);
+
+        for _ in 0..100 {
+            assert_eq!(Piece::Regular('s', ALL_DIRECTIONS), spawner.spawn(&board, Pos::new(0, 3)));
+        }
+    }
+
+    #[test]
+    fn anti_cluster_spawn_beyond_lookdown_ignored() {
+        let mut board = Board::new(BoardState::new(1, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 1), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 2), Piece::Empty);
+        let mut spawner = AntiClusterSpawner::new(vec![('f', 1), ('s', 1)], 3)
+            .with_streak_penalty(100)
+            .with_lookdown_depth(2);
+
+        for _ in 0..100 {
+            assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), spawner.spawn(&board, Pos::new(0, 2)));
+        }
+    }
+
+    #[test]
+    fn anti_cluster_spawn_every_type_penalized_uses_base_weights() {
+        let mut board = Board::new(BoardState::new(3, 1), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(2, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        let mut spawner = AntiClusterSpawner::new(vec![('f', 1), ('s', 1)], 3)
+            .with_streak_penalty(100);
+
+        let spawned: Vec<Piece> = (0..100).map(|_| spawner.spawn(&board, Pos::new(1, 0))).collect();
+
+        assert!(spawned.contains(&Piece::Regular('f', ALL_DIRECTIONS)));
+        assert!(spawned.contains(&Piece::Regular('s', ALL_DIRECTIONS)));
+    }
+
+    #[test]
+    fn anti_cluster_spawn_same_seed_same_pieces() {
+        let mut board = Board::new(BoardState::new(2, 2), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        let mut first = AntiClusterSpawner::new(vec![('f', 1), ('s', 1), ('t', 1)], 9);
+        let mut second = first.clone();
+
+        for _ in 0..20 {
+            assert_eq!(first.spawn(&board, Pos::new(0, 1)), second.spawn(&board, Pos::new(0, 1)));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn anti_cluster_new_no_weight_panics() {
+        AntiClusterSpawner::new(vec![('f', 0)], 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn anti_cluster_with_streak_penalty_above_hundred_panics() {
+        AntiClusterSpawner::new(vec![('f', 1)], 0).with_streak_penalty(101);
+    }
+
+    fn stale_board() -> Board {
+        let pattern: MatchPattern = "fff".parse().unwrap();
+        let mut board = Board::new(BoardState::new(3, 2), vec![pattern], Vec::new());
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 1), Piece::Regular('s', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+        board.set_piece(Pos::new(2, 1), Piece::Regular('t', ALL_DIRECTIONS));
+        board
+    }
+
+    #[test]
+    fn helpful_spawn_full_mercy_stale_board_creates_move() {
+        let board = stale_board();
+        let mut spawner = HelpfulSpawner::new(|_: &Board, _| Piece::Regular('s', ALL_DIRECTIONS), vec!['s', 'f', 't'], 4)
+            .with_mercy(100);
+
+        for _ in 0..20 {
+            assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), spawner.spawn(&board, Pos::new(1, 1)));
+        }
+    }
+
+    #[test]
+    fn helpful_spawn_no_mercy_uses_base() {
+        let board = stale_board();
+        let mut spawner = HelpfulSpawner::new(|_: &Board, _| Piece::Regular('s', ALL_DIRECTIONS), vec!['f'], 4)
+            .with_mercy(0);
+
+        for _ in 0..20 {
+            assert_eq!(Piece::Regular('s', ALL_DIRECTIONS), spawner.spawn(&board, Pos::new(1, 1)));
+        }
+    }
+
+    #[test]
+    fn helpful_spawn_board_has_move_uses_base() {
+        let mut board = stale_board();
+        board.set_piece(Pos::new(1, 1), Piece::Regular('f', ALL_DIRECTIONS));
+        let mut spawner = HelpfulSpawner::new(|_: &Board, _| Piece::Regular('s', ALL_DIRECTIONS), vec!['f'], 4)
+            .with_mercy(100);
+
+        assert_eq!(Piece::Regular('s', ALL_DIRECTIONS), spawner.spawn(&board, Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn helpful_spawn_no_helpful_type_uses_base() {
+        let board = stale_board();
+        let mut spawner = HelpfulSpawner::new(|_: &Board, _| Piece::Regular('s', ALL_DIRECTIONS), vec!['t'], 4)
+            .with_mercy(100);
+
+        assert_eq!(Piece::Regular('s', ALL_DIRECTIONS), spawner.spawn(&board, Pos::new(1, 1)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn helpful_with_mercy_above_hundred_panics() {
+        HelpfulSpawner::new(|_: &Board, _| Piece::Empty, vec!['f'], 0).with_mercy(101);
+    }
+}
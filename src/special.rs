@@ -0,0 +1,262 @@
+use crate::board::{Board, PosSet};
+use crate::cascade::{MatchAction, MatchInterceptor};
+use crate::events::BoardEvent;
+use crate::piece::{Piece, PieceType};
+use crate::position::Pos;
+
+/// A condition that a match must meet to create a special piece.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum SpecialCondition {
+
+    /// The match contains at least this many pieces.
+    MinSize(usize),
+
+    /// The match was found with a pattern of exactly this rank.
+    Rank(u32),
+
+    /// The match has exactly this shape, regardless of where it is on the board.
+    /// The shape does not need to start at (0, 0).
+    Shape(PosSet)
+
+}
+
+impl SpecialCondition {
+
+    /// Checks whether a match meets this condition.
+    ///
+    /// # Arguments
+    ///
+    /// * `rank` - the rank of the pattern that found the match
+    /// * `positions` - the positions of the match on the board
+    fn is_met(&self, rank: u32, positions: &PosSet) -> bool {
+        match self {
+            SpecialCondition::MinSize(size) => positions.len() >= *size,
+            SpecialCondition::Rank(required_rank) => rank == *required_rank,
+            SpecialCondition::Shape(shape) => normalize(shape) == normalize(positions)
+        }
+    }
+
+}
+
+/// A function that creates a special piece from the type of the matched pieces.
+pub type SpecialPieceFn = Box<dyn Fn(PieceType) -> Piece>;
+
+/// Leaves a special piece behind when a large or unusually-shaped match
+/// is cleared. The special piece is placed at the position that triggered
+/// the match, such as the position of a swapped piece, and the rest of the
+/// match is cleared.
+///
+/// Rules are checked in the order they were added, and only the first rule
+/// whose condition is met is used. Matches that meet no condition are cleared
+/// normally.
+///
+/// Special piece rules are a [MatchInterceptor], so the cascade driver applies
+/// them automatically when they are passed to
+/// [resolve_cascade_with()](crate::Board::resolve_cascade_with).
+#[derive(Default)]
+pub struct SpecialPieceRules {
+    rules: Vec<(SpecialCondition, SpecialPieceFn)>
+}
+
+impl SpecialPieceRules {
+
+    /// Creates a new set of rules with no rules.
+    pub fn new() -> SpecialPieceRules {
+        SpecialPieceRules::default()
+    }
+
+    /// Adds a rule with lower priority than all the existing rules.
+    ///
+    /// # Arguments
+    ///
+    /// * `condition` - the condition that a match must meet
+    /// * `create` - creates the special piece from the type of the matched pieces
+    pub fn add_rule(&mut self, condition: SpecialCondition, create: SpecialPieceFn) {
+        self.rules.push((condition, create));
+    }
+
+}
+
+impl MatchInterceptor for SpecialPieceRules {
+    fn intercept(&mut self, _: &Board, matched: &BoardEvent) -> MatchAction {
+        let (piece_type, rank, changed_pos, positions) = match matched {
+            BoardEvent::Matched { piece_type, rank, changed_pos, positions, .. } =>
+                (*piece_type, *rank, *changed_pos, positions),
+            _ => return MatchAction::Clear
+        };
+
+        match self.rules.iter().find(|(condition, _)| condition.is_met(rank, positions)) {
+            Some((_, create)) => {
+                let mut cleared = positions.clone();
+                cleared.remove(&changed_pos);
+                MatchAction::Replace {
+                    cleared,
+                    placed: vec![(changed_pos, create(piece_type))]
+                }
+            },
+            None => MatchAction::Clear
+        }
+    }
+}
+
+/// Moves a group of positions so that its lowest x and lowest y are zero.
+///
+/// # Arguments
+///
+/// * `positions` - the positions to move
+fn normalize(positions: &PosSet) -> PosSet {
+    let min_x = positions.iter().map(|pos| pos.x()).min().unwrap_or(0);
+    let min_y = positions.iter().map(|pos| pos.y()).min().unwrap_or(0);
+    positions.iter().map(|&pos| pos - Pos::new(min_x, min_y)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, BoardState, PosSet};
+    use crate::cascade::{MatchAction, MatchInterceptor};
+    use crate::events::BoardEvent;
+    use crate::matching::{MatchPattern, MatchSource};
+    use crate::piece::{Piece, Direction, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::special::{SpecialCondition, SpecialPieceRules};
+    use enumset::EnumSet;
+
+    fn matched(rank: u32, changed_pos: Pos, positions: &[(u8, u8)]) -> BoardEvent {
+        BoardEvent::Matched {
+            piece_type: 'f',
+            rank,
+            pattern_id: 0,
+            changed_pos,
+            source: MatchSource::PlayerSwap,
+            positions: positions.iter().map(|&(x, y)| Pos::new(x, y)).collect(),
+            chain_index: 0,
+            simultaneous_index: 0
+        }
+    }
+
+    fn striped(piece_type: char) -> Piece {
+        Piece::Regular(piece_type.to_ascii_uppercase(), ALL_DIRECTIONS)
+    }
+
+    fn bomb(_: char) -> Piece {
+        Piece::Regular('b', EnumSet::<Direction>::new())
+    }
+
+    #[test]
+    fn intercept_no_rules_clear() {
+        let board = Board::new(BoardState::new(5, 5), Vec::new(), Vec::new());
+        let mut rules = SpecialPieceRules::new();
+
+        let action = rules.intercept(&board, &matched(1, Pos::new(0, 0), &[(0, 0), (1, 0), (2, 0)]));
+
+        assert_eq!(MatchAction::Clear, action);
+    }
+
+    #[test]
+    fn intercept_min_size_met_replaced_at_changed_pos() {
+        let board = Board::new(BoardState::new(5, 5), Vec::new(), Vec::new());
+        let mut rules = SpecialPieceRules::new();
+        rules.add_rule(SpecialCondition::MinSize(4), Box::new(striped));
+
+        let action = rules.intercept(&board, &matched(1, Pos::new(1, 0), &[(0, 0), (1, 0), (2, 0), (3, 0)]));
+
+        let cleared: PosSet = vec![Pos::new(0, 0), Pos::new(2, 0), Pos::new(3, 0)].into_iter().collect();
+        assert_eq!(MatchAction::Replace {
+            cleared,
+            placed: vec![(Pos::new(1, 0), Piece::Regular('F', ALL_DIRECTIONS))]
+        }, action);
+    }
+
+    #[test]
+    fn intercept_min_size_not_met_clear() {
+        let board = Board::new(BoardState::new(5, 5), Vec::new(), Vec::new());
+        let mut rules = SpecialPieceRules::new();
+        rules.add_rule(SpecialCondition::MinSize(4), Box::new(striped));
+
+        let action = rules.intercept(&board, &matched(1, Pos::new(1, 0), &[(0, 0), (1, 0), (2, 0)]));
+
+        assert_eq!(MatchAction::Clear, action);
+    }
+
+    #[test]
+    fn intercept_rank_met_replaced() {
+        let board = Board::new(BoardState::new(5, 5), Vec::new(), Vec::new());
+        let mut rules = SpecialPieceRules::new();
+        rules.add_rule(SpecialCondition::Rank(5), Box::new(bomb));
+
+        let action = rules.intercept(&board, &matched(5, Pos::new(0, 0), &[(0, 0), (1, 0), (2, 0)]));
+
+        assert!(matches!(action, MatchAction::Replace { .. }));
+    }
+
+    #[test]
+    fn intercept_shape_anywhere_on_board_replaced() {
+        let board = Board::new(BoardState::new(5, 5), Vec::new(), Vec::new());
+        let l_shape: PosSet = vec![
+            Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0), Pos::new(0, 1), Pos::new(0, 2)
+        ].into_iter().collect();
+        let mut rules = SpecialPieceRules::new();
+        rules.add_rule(SpecialCondition::Shape(l_shape), Box::new(bomb));
+
+        let action = rules.intercept(&board, &matched(
+            1,
+            Pos::new(2, 2),
+            &[(2, 2), (3, 2), (4, 2), (2, 3), (2, 4)]
+        ));
+
+        assert!(matches!(action, MatchAction::Replace { .. }));
+    }
+
+    #[test]
+    fn intercept_shape_different_clear() {
+        let board = Board::new(BoardState::new(5, 5), Vec::new(), Vec::new());
+        let l_shape: PosSet = vec![
+            Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0), Pos::new(0, 1), Pos::new(0, 2)
+        ].into_iter().collect();
+        let mut rules = SpecialPieceRules::new();
+        rules.add_rule(SpecialCondition::Shape(l_shape), Box::new(bomb));
+
+        let action = rules.intercept(&board, &matched(
+            1,
+            Pos::new(2, 2),
+            &[(2, 2), (3, 2), (4, 2), (3, 3), (3, 4)]
+        ));
+
+        assert_eq!(MatchAction::Clear, action);
+    }
+
+    #[test]
+    fn intercept_several_rules_met_first_used() {
+        let board = Board::new(BoardState::new(5, 5), Vec::new(), Vec::new());
+        let mut rules = SpecialPieceRules::new();
+        rules.add_rule(SpecialCondition::MinSize(5), Box::new(bomb));
+        rules.add_rule(SpecialCondition::MinSize(4), Box::new(striped));
+
+        let action = rules.intercept(&board, &matched(1, Pos::new(0, 0), &[(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]));
+
+        match action {
+            MatchAction::Replace { placed, .. } => assert_eq!(bomb('f'), placed[0].1),
+            _ => panic!("Expected special piece")
+        }
+    }
+
+    #[test]
+    fn resolve_cascade_with_rules_special_piece_left_behind() {
+        let mut spaces = PosSet::new();
+        for x in 0..4 {
+            spaces.insert(Pos::new(x, 0));
+        }
+        let mut board = Board::new(BoardState::new(4, 1), vec![MatchPattern::new('f', spaces, 1)], Vec::new());
+        for x in 0..4 {
+            board.set_piece(Pos::new(x, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        }
+        let mut rules = SpecialPieceRules::new();
+        rules.add_rule(SpecialCondition::MinSize(4), Box::new(striped));
+
+        board.resolve_cascade_with(&mut |_: &Board, _| Piece::Empty, &mut |_: &BoardEvent| {}, &mut rules);
+
+        assert_eq!(Piece::Regular('F', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert_eq!(0, board.count('f'));
+        assert_eq!(3, board.count_empty());
+    }
+}
@@ -0,0 +1,215 @@
+use crate::events::{BoardEvent, BoardObserver};
+use crate::piece::PieceType;
+
+use std::collections::HashMap;
+
+/// Tallies statistics about a game, such as for achievements and analytics.
+/// The statistics are updated automatically when the tracker is passed as
+/// the observer to [resolve_cascade()](crate::Board::resolve_cascade), and
+/// they can be read at any time.
+///
+/// Matches are counted by the pattern that found them as well as by the rank
+/// of that pattern. Swaps are not board events, so the game records them with
+/// [record_swap()](MatchStats::record_swap).
+/// A [GameSession](crate::GameSession) does both automatically.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchStats {
+    matches_by_rank: HashMap<u32, u64>,
+    matches_by_pattern: HashMap<usize, u64>,
+    matches_by_type: HashMap<PieceType, u64>,
+    total_matches: u64,
+    pieces_cleared: u64,
+    longest_cascade: usize,
+    swaps_accepted: u64,
+    swaps_rejected: u64
+}
+
+impl MatchStats {
+
+    /// Creates a new tracker with every statistic at zero.
+    pub fn new() -> MatchStats {
+        MatchStats::default()
+    }
+
+    /// Gets the number of matches found by patterns with a rank.
+    ///
+    /// # Arguments
+    ///
+    /// * `rank` - the rank of the patterns
+    pub fn matches_with_rank(&self, rank: u32) -> u64 {
+        self.matches_by_rank.get(&rank).copied().unwrap_or(0)
+    }
+
+    /// Gets the number of matches found by a pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern_id` - the [ID](crate::MatchPattern::id) of the pattern
+    pub fn matches_with_pattern(&self, pattern_id: usize) -> u64 {
+        self.matches_by_pattern.get(&pattern_id).copied().unwrap_or(0)
+    }
+
+    /// Gets the number of matches of a piece type.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of the matched pieces
+    pub fn matches_of_type(&self, piece_type: PieceType) -> u64 {
+        self.matches_by_type.get(&piece_type).copied().unwrap_or(0)
+    }
+
+    /// Gets the number of matches of every type.
+    pub fn total_matches(&self) -> u64 {
+        self.total_matches
+    }
+
+    /// Gets the number of pieces cleared by matches.
+    pub fn pieces_cleared(&self) -> u64 {
+        self.pieces_cleared
+    }
+
+    /// Gets the most rounds of matches in a single cascade. A cascade whose
+    /// matches are all found before any pieces fall has one round.
+    pub fn longest_cascade(&self) -> usize {
+        self.longest_cascade
+    }
+
+    /// Gets the number of swaps that the board allowed.
+    pub fn swaps_accepted(&self) -> u64 {
+        self.swaps_accepted
+    }
+
+    /// Gets the number of swaps that the board rejected.
+    pub fn swaps_rejected(&self) -> u64 {
+        self.swaps_rejected
+    }
+
+    /// Counts a swap that a player tried to make.
+    ///
+    /// # Arguments
+    ///
+    /// * `is_accepted` - whether the board allowed the swap
+    pub fn record_swap(&mut self, is_accepted: bool) {
+        match is_accepted {
+            true => self.swaps_accepted += 1,
+            false => self.swaps_rejected += 1
+        }
+    }
+
+}
+
+impl BoardObserver for MatchStats {
+    fn on_event(&mut self, event: &BoardEvent) {
+        match event {
+            BoardEvent::Matched { piece_type, rank, pattern_id, chain_index, .. } => {
+                *self.matches_by_rank.entry(*rank).or_insert(0) += 1;
+                *self.matches_by_pattern.entry(*pattern_id).or_insert(0) += 1;
+                *self.matches_by_type.entry(*piece_type).or_insert(0) += 1;
+                self.total_matches += 1;
+                self.longest_cascade = self.longest_cascade.max(chain_index + 1);
+            },
+            BoardEvent::Cleared { .. } => self.pieces_cleared += 1,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::{BoardEvent, BoardObserver};
+    use crate::matching::MatchSource;
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::stats::MatchStats;
+
+    fn matched(piece_type: char, rank: u32, chain_index: usize) -> BoardEvent {
+        with_pattern(piece_type, rank, 0, chain_index)
+    }
+
+    fn with_pattern(piece_type: char, rank: u32, pattern_id: usize, chain_index: usize) -> BoardEvent {
+        BoardEvent::Matched {
+            piece_type,
+            rank,
+            pattern_id,
+            changed_pos: Pos::new(0, 0),
+            source: MatchSource::PlayerSwap,
+            positions: (0..3).map(|x| Pos::new(x, 0)).collect(),
+            chain_index,
+            simultaneous_index: 0
+        }
+    }
+
+    #[test]
+    fn new_all_zero() {
+        let stats = MatchStats::new();
+
+        assert_eq!(0, stats.total_matches());
+        assert_eq!(0, stats.matches_with_rank(1));
+        assert_eq!(0, stats.matches_with_pattern(0));
+        assert_eq!(0, stats.matches_of_type('f'));
+        assert_eq!(0, stats.longest_cascade());
+    }
+
+    #[test]
+    fn on_event_matches_counted_by_rank_and_type() {
+        let mut stats = MatchStats::new();
+
+        stats.on_event(&matched('f', 1, 0));
+        stats.on_event(&matched('f', 2, 0));
+        stats.on_event(&matched('s', 1, 0));
+
+        assert_eq!(3, stats.total_matches());
+        assert_eq!(2, stats.matches_with_rank(1));
+        assert_eq!(1, stats.matches_with_rank(2));
+        assert_eq!(2, stats.matches_of_type('f'));
+        assert_eq!(1, stats.matches_of_type('s'));
+    }
+
+    #[test]
+    fn on_event_same_rank_counted_by_pattern() {
+        let mut stats = MatchStats::new();
+
+        stats.on_event(&with_pattern('f', 1, 0, 0));
+        stats.on_event(&with_pattern('f', 1, 2, 0));
+        stats.on_event(&with_pattern('s', 1, 2, 0));
+
+        assert_eq!(3, stats.matches_with_rank(1));
+        assert_eq!(1, stats.matches_with_pattern(0));
+        assert_eq!(0, stats.matches_with_pattern(1));
+        assert_eq!(2, stats.matches_with_pattern(2));
+    }
+
+    #[test]
+    fn on_event_longest_cascade_is_deepest_chain() {
+        let mut stats = MatchStats::new();
+
+        stats.on_event(&matched('f', 1, 0));
+        stats.on_event(&matched('f', 1, 2));
+        stats.on_event(&matched('f', 1, 1));
+
+        assert_eq!(3, stats.longest_cascade());
+    }
+
+    #[test]
+    fn on_event_cleared_pieces_counted() {
+        let mut stats = MatchStats::new();
+
+        stats.on_event(&BoardEvent::Cleared { pos: Pos::new(0, 0), piece: Piece::Regular('f', ALL_DIRECTIONS) });
+        stats.on_event(&BoardEvent::Moved { from: Pos::new(0, 1), to: Pos::new(0, 0) });
+
+        assert_eq!(1, stats.pieces_cleared());
+    }
+
+    #[test]
+    fn record_swap_counts_accepted_and_rejected() {
+        let mut stats = MatchStats::new();
+
+        stats.record_swap(true);
+        stats.record_swap(false);
+        stats.record_swap(false);
+
+        assert_eq!(1, stats.swaps_accepted());
+        assert_eq!(2, stats.swaps_rejected());
+    }
+}
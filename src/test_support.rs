@@ -0,0 +1,43 @@
+//! Helpers that set up boards for the tests in every module.
+
+use crate::board::{Board, BoardState};
+use crate::piece::{Piece, ALL_DIRECTIONS};
+use crate::position::Pos;
+
+/// Creates a board from rows of pieces drawn as text, with the top row first.
+/// Each character is a regular piece of that type that is movable in every
+/// direction, except that a space is an empty piece and `#` is a wall. No
+/// positions are left marked for a match check.
+///
+/// # Arguments
+///
+/// * `rows` - the pieces in each row, which must all be the same length
+/// * `patterns` - the board's match patterns, drawn as text like `"aaa"`
+///
+/// # Panics
+///
+/// Panics if the rows have different lengths or a pattern cannot be parsed.
+pub(crate) fn board_from_rows(rows: &[&str], patterns: &[&str]) -> Board {
+    let width = rows.first().map_or(0, |row| row.chars().count());
+    if rows.iter().any(|row| row.chars().count() != width) {
+        panic!("Tried to create test board with rows of different lengths: {:?}", rows);
+    }
+
+    let patterns = patterns.iter().map(|pattern| pattern.parse().unwrap()).collect();
+    let mut board = Board::new(BoardState::new(width as u8, rows.len() as u8), patterns, Vec::new());
+
+    for (row_index, row) in rows.iter().enumerate() {
+        let y = (rows.len() - 1 - row_index) as u8;
+        for (x, piece_type) in row.chars().enumerate() {
+            let piece = match piece_type {
+                ' ' => Piece::Empty,
+                '#' => Piece::Wall,
+                _ => Piece::Regular(piece_type, ALL_DIRECTIONS)
+            };
+            board.set_piece(Pos::new(x as u8, y), piece);
+        }
+    }
+
+    while board.next_match().is_some() {}
+    board
+}
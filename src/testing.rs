@@ -0,0 +1,167 @@
+//! [proptest](https://docs.rs/proptest) strategies for boards, pieces, and
+//! match patterns, so that downstream crates can fuzz their own rules
+//! against the engine's invariants.
+//!
+//! Pair the strategies with [Board::check_invariants()] to check that a board
+//! is still consistent after a game's rules change it:
+//!
+//! ```ignore
+//! proptest! {
+//!     #[test]
+//!     fn my_rule_keeps_board_consistent(mut board in testing::board(8, 8)) {
+//!         my_rule(&mut board);
+//!         prop_assert_eq!(Ok(()), board.check_invariants());
+//!     }
+//! }
+//! ```
+
+use crate::board::{Board, BoardState, PosSet};
+use crate::matching::MatchPattern;
+use crate::piece::{Direction, Piece, PieceType, ALL_DIRECTIONS};
+use crate::position::Pos;
+
+use enumset::EnumSet;
+use proptest::collection::{hash_set, vec};
+use proptest::prelude::*;
+
+/// The types chosen by [piece_type()]. The list is short so that generated
+/// boards have plenty of matches.
+pub const PIECE_TYPES: [PieceType; 5] = ['a', 'b', 'c', 'd', 'e'];
+
+/// Chooses one of the [PIECE_TYPES].
+pub fn piece_type() -> impl Strategy<Value = PieceType> {
+    proptest::sample::select(&PIECE_TYPES[..])
+}
+
+/// Chooses any set of movable directions. Every direction is included more
+/// often than not, because most pieces in real boards can move.
+pub fn directions() -> impl Strategy<Value = EnumSet<Direction>> {
+    prop_oneof![
+        3 => Just(ALL_DIRECTIONS),
+        1 => vec(any::<bool>(), 4).prop_map(|included| {
+            ALL_DIRECTIONS.iter().zip(included).filter(|&(_, is_included)| is_included).map(|(direction, _)| direction).collect()
+        })
+    ]
+}
+
+/// Chooses a piece, which is usually a regular piece of one of the
+/// [PIECE_TYPES] and sometimes an empty space or a wall.
+pub fn piece() -> impl Strategy<Value = Piece> {
+    prop_oneof![
+        8 => (piece_type(), directions()).prop_map(|(piece_type, directions)| Piece::Regular(piece_type, directions)),
+        1 => Just(Piece::Empty),
+        1 => Just(Piece::Wall)
+    ]
+}
+
+/// Chooses a pattern of one of the [PIECE_TYPES] with one to five spaces
+/// inside a 4x4 area and a rank from 1 to 4.
+pub fn match_pattern() -> impl Strategy<Value = MatchPattern> {
+    (piece_type(), hash_set((0u8..4, 0u8..4), 1..=5), 1u32..5).prop_map(|(piece_type, spaces, rank)| {
+        let spaces: PosSet = spaces.into_iter().map(|(x, y)| Pos::new(x, y)).collect();
+        MatchPattern::new(piece_type, spaces, rank)
+    })
+}
+
+/// Chooses a board of up to a given size, filled with [pieces](piece()) and
+/// given up to four [patterns](match_pattern()). Every space is marked for a
+/// match check, as if the pieces were just placed.
+///
+/// # Arguments
+///
+/// * `max_width` - the largest width of the board
+/// * `max_height` - the largest height of the board
+///
+/// # Panics
+///
+/// Panics if either maximum is zero.
+pub fn board(max_width: u8, max_height: u8) -> impl Strategy<Value = Board> {
+    if max_width == 0 || max_height == 0 {
+        panic!("Tried to generate boards with a maximum size of zero");
+    }
+
+    (1..=max_width, 1..=max_height).prop_flat_map(|(width, height)| {
+        let spaces = usize::from(width) * usize::from(height);
+        (Just(width), Just(height), vec(piece(), spaces), vec(match_pattern(), 0..=4))
+    }).prop_map(|(width, height, pieces, patterns)| {
+        let mut board = Board::new(BoardState::new(width, height), patterns, Vec::new());
+        for (index, piece) in pieces.into_iter().enumerate() {
+            let pos = Pos::new((index % usize::from(width)) as u8, (index / usize::from(width)) as u8);
+            board.set_piece(pos, piece);
+        }
+        board
+    })
+}
+
+/// Chooses two horizontally or vertically adjacent positions that are both
+/// inside a board of the given size.
+///
+/// # Arguments
+///
+/// * `width` - the width of the board
+/// * `height` - the height of the board
+///
+/// # Panics
+///
+/// Panics if the board has only one space, because no two positions are adjacent.
+pub fn adjacent_pair(width: u8, height: u8) -> impl Strategy<Value = (Pos, Pos)> {
+    if u16::from(width) * u16::from(height) < 2 {
+        panic!("Tried to choose adjacent positions on a board with fewer than two spaces");
+    }
+
+    let horizontal = (0..width.saturating_sub(1), 0..height)
+        .prop_map(|(x, y)| (Pos::new(x, y), Pos::new(x + 1, y)));
+    let vertical = (0..width, 0..height.saturating_sub(1))
+        .prop_map(|(x, y)| (Pos::new(x, y), Pos::new(x, y + 1)));
+
+    match (width > 1, height > 1) {
+        (true, true) => prop_oneof![horizontal, vertical].boxed(),
+        (true, false) => horizontal.boxed(),
+        _ => vertical.boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::piece::Piece;
+    use crate::position::Pos;
+    use crate::testing::{adjacent_pair, board, match_pattern};
+
+    use proptest::prelude::*;
+
+    fn sized_pair() -> impl Strategy<Value = (u8, u8, (Pos, Pos))> {
+        (1u8..6, 1u8..6).prop_filter("board must have two spaces", |&(width, height)| width > 1 || height > 1)
+            .prop_flat_map(|(width, height)| (Just(width), Just(height), adjacent_pair(width, height)))
+    }
+
+    proptest! {
+        #[test]
+        fn board_generated_board_consistent(board in board(8, 8)) {
+            prop_assert_eq!(Ok(()), board.check_invariants());
+        }
+
+        #[test]
+        fn match_pattern_generated_pattern_within_area(pattern in match_pattern()) {
+            prop_assert!(pattern.spaces().iter().all(|pos| pos.x() < 4 && pos.y() < 4));
+            prop_assert!(!pattern.spaces().is_empty());
+        }
+
+        #[test]
+        fn adjacent_pair_positions_adjacent_and_inside((width, height, (first, second)) in sized_pair()) {
+            prop_assert!(second.x() < width && second.y() < height);
+            let is_east = first.x() + 1 == second.x() && first.y() == second.y();
+            let is_north = first.x() == second.x() && first.y() + 1 == second.y();
+            prop_assert!(is_east || is_north);
+        }
+
+        #[test]
+        fn resolve_cascade_after_swap_board_consistent(mut board in board(6, 6),
+                                                       (first, second) in adjacent_pair(6, 6)) {
+            let _ = board.try_swap_pieces(first, second);
+            board.resolve_cascade(&mut |_: &Board, _| Piece::Empty, &mut |_: &_| {});
+
+            prop_assert_eq!(Ok(()), board.check_invariants());
+        }
+    }
+}
@@ -0,0 +1,494 @@
+use crate::board::Board;
+use crate::events::{BoardEvent, BoardObserver};
+use crate::piece::{Piece, PieceType};
+use crate::position::Pos;
+
+/// Changes the board once per turn when [tick()](Board::tick) is called.
+/// Any closure that accepts the board, the positions of the counters
+/// that reached zero, and an observer is a tick behavior.
+pub trait TickBehavior: Send + Sync {
+
+    /// Updates the board for a new turn.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - the board to update
+    /// * `expired` - the positions whose counters reached zero during this tick
+    /// * `observer` - receives an event for each change the behavior makes
+    fn on_tick(&mut self, board: &mut Board, expired: &[Pos], observer: &mut dyn BoardObserver);
+
+}
+
+impl<F: FnMut(&mut Board, &[Pos], &mut dyn BoardObserver) + Send + Sync> TickBehavior for F {
+    fn on_tick(&mut self, board: &mut Board, expired: &[Pos], observer: &mut dyn BoardObserver) {
+        self(board, expired, observer)
+    }
+}
+
+/// Clears the regular pieces around each piece whose counter reaches zero,
+/// including the piece itself, like a bomb exploding. Empty pieces and walls
+/// are not affected. Each cleared piece is reported with a
+/// [BoardEvent::Cleared] event.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Explode {
+    radius: u8
+}
+
+impl Explode {
+
+    /// Creates a new explosion behavior.
+    ///
+    /// # Arguments
+    ///
+    /// * `radius` - the maximum distance from the exploding piece to a
+    ///   cleared piece. See [Board::pieces_within()].
+    pub fn new(radius: u8) -> Explode {
+        Explode { radius }
+    }
+
+}
+
+impl TickBehavior for Explode {
+    fn on_tick(&mut self, board: &mut Board, expired: &[Pos], observer: &mut dyn BoardObserver) {
+        for &center in expired {
+            let cleared: Vec<Pos> = board.pieces_within(center, self.radius)
+                .filter(|(_, piece)| matches!(piece, Piece::Regular(..)))
+                .map(|(pos, _)| pos)
+                .collect();
+
+            for pos in cleared {
+                let piece = board.set_piece(pos, Piece::Empty);
+                observer.on_event(&BoardEvent::Cleared { pos, piece });
+            }
+        }
+    }
+}
+
+/// Makes every piece of a type copy itself into one adjacent empty space each
+/// tick, like a growing blocker. The north space is preferred, followed by
+/// the east, south, and west spaces. Pieces added during a tick do not grow
+/// until the next tick. Each new piece is reported with a
+/// [BoardEvent::Spawned] event.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct GrowIntoEmpty {
+    piece_type: PieceType
+}
+
+impl GrowIntoEmpty {
+
+    /// Creates a new growth behavior.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of the pieces that grow
+    pub fn new(piece_type: PieceType) -> GrowIntoEmpty {
+        GrowIntoEmpty { piece_type }
+    }
+
+}
+
+impl TickBehavior for GrowIntoEmpty {
+    fn on_tick(&mut self, board: &mut Board, _: &[Pos], observer: &mut dyn BoardObserver) {
+        let sources: Vec<Pos> = match board.type_bitboard(self.piece_type) {
+            Some(pieces) => pieces.iter_set().collect(),
+            None => return
+        };
+
+        for source in sources {
+            let target = adjacent(board, source).into_iter().find(
+                |&pos| board.piece(pos) == Piece::Empty
+            );
+
+            if let Some(target) = target {
+                let piece = board.piece(source);
+                board.set_piece(target, piece);
+                observer.on_event(&BoardEvent::Spawned { pos: target, piece });
+            }
+        }
+    }
+}
+
+/// Makes pieces of a type spread like chocolate or slime. On every tick where
+/// none of these pieces were destroyed since the last tick, one of them copies
+/// itself into an adjacent regular piece of another type. The piece to spread
+/// and the piece to replace are chosen randomly with the board's random number
+/// generator. See [Board::set_seed()].
+///
+/// A piece is considered destroyed when there are fewer pieces of the spreading
+/// type than there were at the end of the last tick. The replaced piece is
+/// reported with a [BoardEvent::Consumed] event.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct SpreadBlocker {
+    piece_type: PieceType,
+    last_count: Option<usize>
+}
+
+impl SpreadBlocker {
+
+    /// Creates a new spreading behavior.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type of the pieces that spread
+    pub fn new(piece_type: PieceType) -> SpreadBlocker {
+        SpreadBlocker { piece_type, last_count: None }
+    }
+
+}
+
+impl TickBehavior for SpreadBlocker {
+    fn on_tick(&mut self, board: &mut Board, _: &[Pos], observer: &mut dyn BoardObserver) {
+        let was_destroyed = self.last_count.is_some_and(|count| board.count(self.piece_type) < count);
+
+        if !was_destroyed {
+            let sources: Vec<Pos> = board.type_bitboard(self.piece_type)
+                .map(|pieces| pieces.iter_set().collect())
+                .unwrap_or_default();
+
+            let candidates: Vec<(Pos, Pos)> = sources.into_iter()
+                .flat_map(|source| adjacent(board, source).into_iter().map(move |target| (source, target)))
+                .filter(|&(_, target)| match board.piece(target) {
+                    Piece::Regular(piece_type, _) => piece_type != self.piece_type,
+                    _ => false
+                })
+                .collect();
+
+            if !candidates.is_empty() {
+                let (from, pos) = candidates[board.rng_mut().next_below(candidates.len())];
+                let piece = board.set_piece(pos, board.piece(from));
+                observer.on_event(&BoardEvent::Consumed { pos, piece, from });
+            }
+        }
+
+        self.last_count = Some(board.count(self.piece_type));
+    }
+}
+
+/// Gets the positions north, east, south, and west of a position that are
+/// on the board, in that order.
+///
+/// # Arguments
+///
+/// * `board` - the board that contains the position
+/// * `pos` - the position to get the adjacent positions of
+pub(crate) fn adjacent(board: &Board, pos: Pos) -> Vec<Pos> {
+    let width = board.state().width;
+    let height = board.state().height;
+    let mut positions = Vec::with_capacity(4);
+
+    if pos.y() + 1 < height {
+        positions.push(Pos::new(pos.x(), pos.y() + 1));
+    }
+    if pos.x() + 1 < width {
+        positions.push(Pos::new(pos.x() + 1, pos.y()));
+    }
+    if pos.y() > 0 {
+        positions.push(Pos::new(pos.x(), pos.y() - 1));
+    }
+    if pos.x() > 0 {
+        positions.push(Pos::new(pos.x() - 1, pos.y()));
+    }
+
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::events::{BoardEvent, BoardObserver};
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::test_support::board_from_rows;
+    use crate::tick::{Explode, GrowIntoEmpty, SpreadBlocker};
+
+    #[test]
+    fn tick_no_counters_nothing_expired() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        assert!(board.tick().is_empty());
+    }
+
+    #[test]
+    fn tick_counters_decremented() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_counter(Pos::new(1, 1), 3);
+
+        assert!(board.tick().is_empty());
+        assert_eq!(Some(2), board.counter(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn tick_counters_reach_zero_expired_in_order() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_counter(Pos::new(3, 1), 1);
+        board.set_counter(Pos::new(1, 2), 2);
+        board.set_counter(Pos::new(2, 1), 1);
+        board.set_counter(Pos::new(0, 0), 0);
+
+        assert_eq!(vec![Pos::new(0, 0), Pos::new(2, 1), Pos::new(3, 1)], board.tick());
+        assert_eq!(None, board.counter(Pos::new(2, 1)));
+        assert_eq!(vec![Pos::new(1, 2)], board.tick());
+    }
+
+    #[test]
+    fn set_piece_counter_removed() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_counter(Pos::new(1, 1), 3);
+
+        board.set_piece(Pos::new(1, 1), Piece::Regular('s', ALL_DIRECTIONS));
+
+        assert_eq!(None, board.counter(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn swap_pieces_counter_moves_with_piece() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_counter(Pos::new(1, 1), 3);
+
+        assert!(board.swap_pieces(Pos::new(1, 1), Pos::new(1, 2)));
+
+        assert_eq!(None, board.counter(Pos::new(1, 1)));
+        assert_eq!(Some(3), board.counter(Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn trickle_counter_moves_with_piece() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_counter(Pos::new(1, 2), 3);
+        board.set_piece(Pos::new(1, 0), Piece::Empty);
+        board.set_piece(Pos::new(1, 1), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(Some(3), board.counter(Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn shift_row_counter_moves_with_piece() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_counter(Pos::new(4, 1), 3);
+
+        board.shift_row(1, 1, true);
+
+        assert_eq!(Some(3), board.counter(Pos::new(0, 1)));
+        assert_eq!(None, board.counter(Pos::new(4, 1)));
+    }
+
+    #[test]
+    fn rotate_180_counter_moves_with_piece() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_counter(Pos::new(0, 1), 3);
+
+        board.rotate_180();
+
+        assert_eq!(Some(3), board.counter(Pos::new(4, 3)));
+    }
+
+    #[test]
+    fn tick_custom_behavior_receives_expired() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_counter(Pos::new(2, 2), 1);
+        board.add_tick_behavior(Box::new(|board: &mut Board, expired: &[Pos], _: &mut dyn BoardObserver| {
+            for &pos in expired {
+                board.set_piece(pos, Piece::Wall);
+            }
+        }));
+
+        board.tick();
+
+        assert_eq!(Piece::Wall, board.piece(Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn tick_explode_clears_radius() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_piece(Pos::new(2, 3), Piece::Wall);
+        board.set_counter(Pos::new(2, 2), 1);
+        board.add_tick_behavior(Box::new(Explode::new(1)));
+
+        board.tick();
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(2, 1)));
+        assert_eq!(Piece::Wall, board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), board.piece(Pos::new(1, 1)));
+        assert_eq!(4, board.count_empty());
+    }
+
+    #[test]
+    fn tick_frozen_decremented() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.freeze(Pos::new(1, 1), 3);
+
+        board.tick();
+
+        assert_eq!(Some(2), board.frozen_turns(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn tick_with_frozen_reach_zero_thawed_in_order() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.freeze(Pos::new(3, 1), 1);
+        board.freeze(Pos::new(1, 2), 2);
+        board.freeze(Pos::new(2, 1), 1);
+        let mut events = Vec::new();
+
+        board.tick_with(&mut |event: &BoardEvent| events.push(event.clone()));
+
+        assert_eq!(vec![
+            BoardEvent::Thawed { pos: Pos::new(2, 1) },
+            BoardEvent::Thawed { pos: Pos::new(3, 1) }
+        ], events);
+        assert_eq!(None, board.frozen_turns(Pos::new(2, 1)));
+        assert_eq!(Some(1), board.frozen_turns(Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn tick_frozen_thawed_piece_can_swap() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_piece(Pos::new(1, 1), Piece::Regular('s', ALL_DIRECTIONS));
+        board.freeze(Pos::new(1, 1), 1);
+        assert!(!board.swap_pieces(Pos::new(1, 1), Pos::new(1, 2)));
+
+        board.tick();
+
+        assert!(board.swap_pieces(Pos::new(1, 1), Pos::new(1, 2)));
+    }
+
+    #[test]
+    fn set_piece_frozen_removed() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.freeze(Pos::new(1, 1), 3);
+
+        board.set_piece(Pos::new(1, 1), Piece::Regular('s', ALL_DIRECTIONS));
+
+        assert_eq!(None, board.frozen_turns(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn trickle_frozen_moves_with_piece() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.freeze(Pos::new(2, 1), 3);
+        board.set_piece(Pos::new(2, 0), Piece::Empty);
+
+        board.trickle();
+
+        assert_eq!(None, board.frozen_turns(Pos::new(2, 1)));
+        assert_eq!(Some(3), board.frozen_turns(Pos::new(2, 0)));
+    }
+
+    #[test]
+    fn tick_with_explode_cleared_events() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_counter(Pos::new(0, 0), 1);
+        board.add_tick_behavior(Box::new(Explode::new(0)));
+        let mut events = Vec::new();
+
+        board.tick_with(&mut |event: &BoardEvent| events.push(event.clone()));
+
+        assert_eq!(vec![BoardEvent::Cleared {
+            pos: Pos::new(0, 0),
+            piece: Piece::Regular('f', ALL_DIRECTIONS)
+        }], events);
+    }
+
+    #[test]
+    fn tick_grow_into_empty_one_space_per_piece() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_piece(Pos::new(2, 2), Piece::Regular('b', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 3), Piece::Empty);
+        board.set_piece(Pos::new(3, 2), Piece::Empty);
+        board.add_tick_behavior(Box::new(GrowIntoEmpty::new('b')));
+
+        board.tick();
+
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(2, 3)));
+        assert_eq!(Piece::Empty, board.piece(Pos::new(3, 2)));
+        assert_eq!(2, board.count('b'));
+    }
+
+    #[test]
+    fn tick_grow_into_empty_no_empty_neighbors_no_growth() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_piece(Pos::new(2, 2), Piece::Regular('b', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(0, 0), Piece::Empty);
+        board.add_tick_behavior(Box::new(GrowIntoEmpty::new('b')));
+
+        board.tick();
+
+        assert_eq!(1, board.count('b'));
+    }
+
+    fn blocker() -> Piece {
+        Piece::Regular('c', ALL_DIRECTIONS)
+    }
+
+    #[test]
+    fn tick_spread_blocker_consumes_adjacent_piece() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_piece(Pos::new(2, 2), blocker());
+        board.add_tick_behavior(Box::new(SpreadBlocker::new('c')));
+        let mut events = Vec::new();
+
+        board.tick_with(&mut |event: &BoardEvent| events.push(event.clone()));
+
+        assert_eq!(2, board.count('c'));
+        assert_eq!(1, events.len());
+        match events[0] {
+            BoardEvent::Consumed { pos, piece, from } => {
+                assert_eq!(Pos::new(2, 2), from);
+                assert_eq!(1, (i16::from(pos.x()) - 2).abs() + (i16::from(pos.y()) - 2).abs());
+                assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), piece);
+                assert_eq!(blocker(), board.piece(pos));
+            },
+            _ => panic!("Expected consumed event")
+        }
+    }
+
+    #[test]
+    fn tick_spread_blocker_only_regular_pieces_consumed() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_piece(Pos::new(0, 0), blocker());
+        board.set_piece(Pos::new(1, 0), Piece::Wall);
+        board.set_piece(Pos::new(0, 1), Piece::Empty);
+        board.add_tick_behavior(Box::new(SpreadBlocker::new('c')));
+
+        board.tick();
+
+        assert_eq!(1, board.count('c'));
+    }
+
+    #[test]
+    fn tick_spread_blocker_destroyed_no_spread() {
+        let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+        board.set_piece(Pos::new(0, 0), blocker());
+        board.set_piece(Pos::new(4, 4), blocker());
+        board.add_tick_behavior(Box::new(SpreadBlocker::new('c')));
+        board.tick();
+        assert_eq!(3, board.count('c'));
+
+        board.set_piece(Pos::new(4, 4), Piece::Empty);
+        board.tick();
+        assert_eq!(2, board.count('c'));
+
+        board.tick();
+        assert_eq!(3, board.count('c'));
+    }
+
+    #[test]
+    fn tick_spread_blocker_same_seed_same_spread() {
+        let spread = |seed| {
+            let mut board = board_from_rows(&["fffff", "fffff", "fffff", "fffff", "fffff"], &[]);
+            board.set_seed(seed);
+            board.set_piece(Pos::new(2, 2), blocker());
+            board.add_tick_behavior(Box::new(SpreadBlocker::new('c')));
+            for _ in 0..5 {
+                board.tick();
+            }
+            board.type_bitboard('c').unwrap().clone()
+        };
+
+        assert_eq!(spread(3), spread(3));
+    }
+}
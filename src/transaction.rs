@@ -0,0 +1,540 @@
+#[cfg(feature = "analytics")]
+use crate::analytics::AnalyticsCheckpoint;
+use crate::board::{Board, BoardState, PosSet, SetPieceError, SwapError};
+use crate::matching::{Match, MatchSource};
+use crate::piece::{Piece, PieceType};
+use crate::position::Pos;
+use crate::report::TurnReport;
+
+use std::fmt::{Display, Formatter};
+use std::mem;
+
+/// A swap that has been applied to a board but can still be undone, created
+/// by [begin_swap()](Board::begin_swap). A game can inspect the matches the
+/// swap caused, play the swap animation, and then either keep the swap with
+/// [commit()](SwapTransaction::commit) or put the pieces back with
+/// [rollback()](SwapTransaction::rollback), such as when the swap did not
+/// cause a match.
+///
+/// A transaction that is dropped without being committed is rolled back, so
+/// a swap is never kept by accident.
+#[must_use = "a swap transaction is rolled back unless it is committed"]
+pub struct SwapTransaction<'a> {
+    board: &'a mut Board,
+    first: Pos,
+    second: Pos,
+    previous_state: Option<BoardState>,
+    previous_effect_count: Option<usize>,
+    previous_report: TurnReport,
+    #[cfg(feature = "analytics")]
+    previous_analytics: Option<AnalyticsCheckpoint>
+}
+
+impl SwapTransaction<'_> {
+
+    /// Gets the first position that was swapped.
+    pub fn first(&self) -> Pos {
+        self.first
+    }
+
+    /// Gets the second position that was swapped.
+    pub fn second(&self) -> Pos {
+        self.second
+    }
+
+    /// Gets the board with the pieces swapped.
+    pub fn board(&self) -> &Board {
+        self.board
+    }
+
+    /// Finds the matches that include either swapped position, highest-ranked
+    /// first for each position. The positions stay marked for a match check, so
+    /// the same matches are found by [next_match()](Board::next_match) after
+    /// the transaction is committed.
+    pub fn matches(&self) -> Vec<Match<'_>> {
+        let positions = match self.first == self.second {
+            true => vec![self.first],
+            false => vec![self.first, self.second]
+        };
+
+        positions.into_iter()
+            .filter_map(|pos| self.board.match_at(pos))
+            .map(|found| found.with_source(MatchSource::PlayerSwap))
+            .collect()
+    }
+
+    /// Checks whether the swap caused at least one match.
+    pub fn has_matches(&self) -> bool {
+        !self.matches().is_empty()
+    }
+
+    /// Keeps the swap. The swapped positions stay marked for a match check.
+    pub fn commit(mut self) {
+        self.previous_state = None;
+    }
+
+    /// Undoes the swap, returning the board to exactly how it was before
+    /// [begin_swap()](Board::begin_swap), including the positions marked for
+    /// a match check and the queued effects.
+    pub fn rollback(self) {}
+
+}
+
+impl Drop for SwapTransaction<'_> {
+    fn drop(&mut self) {
+        #[cfg(feature = "analytics")]
+        self.board.release_analytics(self.previous_analytics, self.previous_state.is_none());
+
+        if let Some(state) = self.previous_state.take() {
+            self.board.restore_state(state);
+            self.board.truncate_effects(self.previous_effect_count);
+            *self.board.turn_report_mut() = mem::take(&mut self.previous_report);
+        }
+    }
+}
+
+/// Why an operation inside a [transaction()](Board::transaction) failed. Any
+/// error type can be returned from a transaction, but this one can be built
+/// with `?` from the errors of every [Transaction] operation.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum TransactionError {
+
+    /// A piece could not be set.
+    SetPiece(SetPieceError),
+
+    /// Two pieces could not be swapped.
+    Swap(SwapError)
+
+}
+
+impl Display for TransactionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransactionError::SetPiece(err) => write!(f, "Could not set piece: {}", err),
+            TransactionError::Swap(err) => write!(f, "Could not swap pieces: {}", err)
+        }
+    }
+}
+
+impl std::error::Error for TransactionError {}
+
+impl From<SetPieceError> for TransactionError {
+    fn from(err: SetPieceError) -> Self {
+        TransactionError::SetPiece(err)
+    }
+}
+
+impl From<SwapError> for TransactionError {
+    fn from(err: SwapError) -> Self {
+        TransactionError::Swap(err)
+    }
+}
+
+/// Changes made to a board inside [transaction()](Board::transaction). Each
+/// operation behaves like the board method with the same name, but which
+/// positions are marked for a match check is only decided once the whole
+/// transaction succeeds.
+pub struct Transaction<'a> {
+    board: &'a mut Board
+}
+
+impl Transaction<'_> {
+
+    /// Gets the board with every change made so far in the transaction.
+    pub fn board(&self) -> &Board {
+        self.board
+    }
+
+    /// Replaces a piece like [try_set_piece()](Board::try_set_piece).
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the piece to replace
+    /// * `piece` - the piece to put at the given position
+    pub fn set_piece(&mut self, pos: Pos, piece: Piece) -> Result<Piece, SetPieceError> {
+        self.board.try_set_piece(pos, piece)
+    }
+
+    /// Swaps two pieces like [try_swap_pieces()](Board::try_swap_pieces),
+    /// following the board's swap rules.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    pub fn swap_pieces(&mut self, first: Pos, second: Pos) -> Result<(), SwapError> {
+        self.board.try_swap_pieces(first, second)
+    }
+
+    /// Changes every piece of one type into another type like
+    /// [convert_pieces()](Board::convert_pieces) and returns the number of
+    /// pieces that were converted.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_type` - the type of pieces to convert
+    /// * `to_type` - the type that the pieces become
+    /// * `region` - the only positions to convert, or None to convert pieces
+    ///   anywhere on the board
+    ///
+    /// # Panics
+    ///
+    /// Panics if the board already has the maximum number of piece types and
+    /// the new type is not one of them.
+    pub fn convert_pieces(&mut self, from_type: PieceType, to_type: PieceType, region: Option<&PosSet>) -> usize {
+        self.board.convert_pieces(from_type, to_type, region)
+    }
+
+}
+
+impl Board {
+
+    /// Swaps two pieces like [try_swap_pieces()](Board::try_swap_pieces), but
+    /// returns a [SwapTransaction] that must be committed to keep the swap. The
+    /// board cannot be changed in any other way until the transaction is
+    /// committed or rolled back. If the swap fails, the board is unchanged and
+    /// no transaction is started.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    pub fn begin_swap(&mut self, first: Pos, second: Pos) -> Result<SwapTransaction<'_>, SwapError> {
+        let previous_state = self.state().clone();
+        let previous_effect_count = self.effect_count();
+        let previous_report = self.turn_report().clone();
+        #[cfg(feature = "analytics")]
+        let previous_analytics = self.hold_analytics();
+        let result = self.try_swap_pieces(first, second);
+        #[cfg(feature = "analytics")]
+        if result.is_err() {
+            self.release_analytics(previous_analytics, true);
+        }
+        result?;
+
+        Ok(SwapTransaction {
+            board: self,
+            first,
+            second,
+            previous_state: Some(previous_state),
+            previous_effect_count,
+            previous_report,
+            #[cfg(feature = "analytics")]
+            previous_analytics
+        })
+    }
+
+    /// Applies several changes to the board at once, such as for a scripted
+    /// level event. If the closure returns an error, every change is undone,
+    /// including queued effects, and the error is returned. Otherwise, every
+    /// change is kept.
+    ///
+    /// Positions are marked for a match check in a single pass after the
+    /// closure succeeds instead of by each operation. Only positions whose
+    /// piece ended up different from before the transaction are marked, from
+    /// the bottom row up and west to east within a row, after any positions
+    /// that were already marked. A piece that was swapped away and back again
+    /// is not marked.
+    ///
+    /// # Arguments
+    ///
+    /// * `changes` - makes the changes through the given [Transaction]
+    pub fn transaction<T, E>(&mut self, changes: impl FnOnce(&mut Transaction<'_>) -> Result<T, E>) -> Result<T, E> {
+        let previous_state = self.state().clone();
+        let previous_effect_count = self.effect_count();
+        let previous_report = self.turn_report().clone();
+        #[cfg(feature = "analytics")]
+        let previous_analytics = self.hold_analytics();
+
+        let result = changes(&mut Transaction { board: self });
+        #[cfg(feature = "analytics")]
+        self.release_analytics(previous_analytics, result.is_ok());
+        if result.is_err() {
+            self.restore_state(previous_state);
+            self.truncate_effects(previous_effect_count);
+            *self.turn_report_mut() = previous_report;
+            return result;
+        }
+
+        let state = self.state_mut();
+        let changed: Vec<Pos> = (0..state.height)
+            .flat_map(|y| (0..state.width).map(move |x| Pos::new(x, y)))
+            .filter(|&pos| is_piece_changed(&previous_state, state, pos))
+            .collect();
+        state.last_changed = previous_state.last_changed;
+        state.changed = previous_state.changed;
+        state.change_sources = previous_state.change_sources;
+        state.change_order = previous_state.change_order;
+        for pos in changed {
+            state.mark_changed(pos, MatchSource::Scripted);
+        }
+
+        result
+    }
+
+}
+
+/// Checks whether the piece at a position differs between two states of the
+/// same board. Type IDs are never reused within a board, so they can be
+/// compared directly.
+///
+/// # Arguments
+///
+/// * `before` - the earlier state
+/// * `after` - the later state
+/// * `pos` - the position to compare
+fn is_piece_changed(before: &BoardState, after: &BoardState, pos: Pos) -> bool {
+    let grid_index = usize::from(pos.y()) * usize::from(before.width) + usize::from(pos.x());
+
+    before.type_grid[grid_index] != after.type_grid[grid_index]
+        || before.empties.is_set(pos) != after.empties.is_set(pos)
+        || before.movable_directions.iter().zip(after.movable_directions.iter())
+            .any(|(before, after)| before.is_set(pos) != after.is_set(pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{SetPieceError, SwapError};
+    use crate::events::Effect;
+    use crate::matching::MatchSource;
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::test_support::board_from_rows;
+    use crate::transaction::TransactionError;
+
+    use enumset::EnumSet;
+
+    #[test]
+    fn begin_swap_pieces_swapped_during_transaction() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+
+        let transaction = board.begin_swap(Pos::new(1, 0), Pos::new(1, 1)).unwrap();
+
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), transaction.board().piece(Pos::new(1, 0)));
+        assert_eq!(Pos::new(1, 0), transaction.first());
+        assert_eq!(Pos::new(1, 1), transaction.second());
+        transaction.rollback();
+    }
+
+    #[test]
+    fn matches_swap_causes_match_found() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+
+        let transaction = board.begin_swap(Pos::new(1, 0), Pos::new(1, 1)).unwrap();
+
+        let matches = transaction.matches();
+        assert_eq!(1, matches.len());
+        assert_eq!('a', matches[0].piece_type());
+        assert!(transaction.has_matches());
+        transaction.rollback();
+    }
+
+    #[test]
+    fn matches_swap_source_player_swap() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+
+        let transaction = board.begin_swap(Pos::new(1, 0), Pos::new(1, 1)).unwrap();
+
+        assert_eq!(MatchSource::PlayerSwap, transaction.matches()[0].source());
+        transaction.rollback();
+    }
+
+    #[test]
+    fn matches_swap_without_match_empty() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+
+        let transaction = board.begin_swap(Pos::new(1, 1), Pos::new(2, 1)).unwrap();
+
+        assert!(transaction.matches().is_empty());
+        assert!(!transaction.has_matches());
+        transaction.rollback();
+    }
+
+    #[test]
+    fn commit_swap_kept_and_marked() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+
+        board.begin_swap(Pos::new(1, 0), Pos::new(1, 1)).unwrap().commit();
+
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+        assert!(board.next_match().is_some());
+    }
+
+    #[test]
+    fn rollback_board_unchanged() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        let previous_state = board.state().clone();
+        let previous_zobrist = board.zobrist();
+
+        board.begin_swap(Pos::new(1, 0), Pos::new(1, 1)).unwrap().rollback();
+
+        assert_eq!(&previous_state, board.state());
+        assert_eq!(previous_zobrist, board.zobrist());
+        assert!(board.next_match().is_none());
+    }
+
+    #[test]
+    fn rollback_queued_effects_removed() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        board.set_effects_enabled(true);
+        board.set_piece(Pos::new(2, 1), Piece::Regular('d', ALL_DIRECTIONS));
+        let previous_effects: Vec<Effect> = board.effects().to_vec();
+
+        board.begin_swap(Pos::new(1, 0), Pos::new(1, 1)).unwrap().rollback();
+
+        assert_eq!(previous_effects, board.effects());
+    }
+
+    #[test]
+    fn drop_without_commit_rolled_back() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+
+        {
+            let _transaction = board.begin_swap(Pos::new(1, 0), Pos::new(1, 1)).unwrap();
+        }
+
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn begin_swap_rejected_error() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        board.set_piece(Pos::new(1, 1), Piece::Regular('a', EnumSet::new()));
+
+        assert_eq!(Some(SwapError::Rejected), board.begin_swap(Pos::new(1, 0), Pos::new(1, 1)).err());
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(1, 0)));
+    }
+
+    #[test]
+    fn begin_swap_out_of_bounds_error() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+
+        assert_eq!(
+            Some(SwapError::OutOfBounds(Pos::new(1, 5))),
+            board.begin_swap(Pos::new(1, 0), Pos::new(1, 5)).err()
+        );
+    }
+
+    #[test]
+    fn transaction_success_all_changes_kept() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+
+        let result = board.transaction(|tx| -> Result<usize, TransactionError> {
+            tx.set_piece(Pos::new(2, 1), Piece::Regular('b', ALL_DIRECTIONS))?;
+            tx.swap_pieces(Pos::new(0, 0), Pos::new(0, 1))?;
+            Ok(tx.convert_pieces('c', 'd', None))
+        });
+
+        assert_eq!(Ok(0), result);
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(0, 1)));
+        assert_eq!(Piece::Regular('b', ALL_DIRECTIONS), board.piece(Pos::new(2, 1)));
+    }
+
+    #[test]
+    fn transaction_error_all_changes_undone() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        let previous_state = board.state().clone();
+
+        let result = board.transaction(|tx| -> Result<(), TransactionError> {
+            tx.set_piece(Pos::new(2, 1), Piece::Regular('b', ALL_DIRECTIONS))?;
+            tx.convert_pieces('a', 'd', None);
+            tx.swap_pieces(Pos::new(0, 0), Pos::new(0, 5))?;
+            Ok(())
+        });
+
+        assert_eq!(Err(TransactionError::Swap(SwapError::OutOfBounds(Pos::new(0, 5)))), result);
+        assert_eq!(&previous_state, board.state());
+    }
+
+    #[test]
+    fn transaction_set_piece_error_converted() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+
+        let result = board.transaction(|tx| -> Result<(), TransactionError> {
+            tx.set_piece(Pos::new(7, 0), Piece::Empty)?;
+            Ok(())
+        });
+
+        assert_eq!(Err(TransactionError::SetPiece(SetPieceError::OutOfBounds(Pos::new(7, 0)))), result);
+    }
+
+    #[test]
+    fn transaction_custom_error_returned() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+
+        let result: Result<(), &str> = board.transaction(|tx| {
+            tx.set_piece(Pos::new(0, 0), Piece::Empty).map_err(|_| "could not set")?;
+            Err("level event cancelled")
+        });
+
+        assert_eq!(Err("level event cancelled"), result);
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn transaction_error_queued_effects_removed() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        board.set_effects_enabled(true);
+
+        let _: Result<(), ()> = board.transaction(|tx| {
+            tx.set_piece(Pos::new(0, 0), Piece::Empty).unwrap();
+            Err(())
+        });
+
+        assert!(board.effects().is_empty());
+    }
+
+    #[test]
+    fn transaction_success_changed_positions_marked_once_in_row_order() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+
+        let _ = board.transaction(|tx| -> Result<(), TransactionError> {
+            tx.set_piece(Pos::new(2, 1), Piece::Regular('b', ALL_DIRECTIONS))?;
+            tx.set_piece(Pos::new(0, 0), Piece::Regular('c', ALL_DIRECTIONS))?;
+            tx.set_piece(Pos::new(2, 1), Piece::Regular('a', ALL_DIRECTIONS))?;
+            Ok(())
+        });
+
+        let pending: Vec<Pos> = board.pending_match_checks().collect();
+        assert_eq!(vec![Pos::new(0, 0), Pos::new(2, 1)], pending);
+    }
+
+    #[test]
+    fn transaction_swapped_back_not_marked() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+
+        let _ = board.transaction(|tx| -> Result<(), TransactionError> {
+            tx.swap_pieces(Pos::new(1, 0), Pos::new(1, 1))?;
+            tx.swap_pieces(Pos::new(1, 0), Pos::new(1, 1))?;
+            Ok(())
+        });
+
+        assert_eq!(0, board.pending_match_checks().count());
+    }
+
+    #[test]
+    fn transaction_existing_marks_kept_first() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+        board.mark_for_match_check(Pos::new(2, 1));
+
+        let _ = board.transaction(|tx| -> Result<(), TransactionError> {
+            tx.set_piece(Pos::new(0, 0), Piece::Regular('c', ALL_DIRECTIONS))?;
+            Ok(())
+        });
+
+        let pending: Vec<Pos> = board.pending_match_checks().collect();
+        assert_eq!(vec![Pos::new(2, 1), Pos::new(0, 0)], pending);
+    }
+
+    #[test]
+    fn transaction_board_shows_changes_so_far() {
+        let mut board = board_from_rows(&["bac", "aba"], &["aaa"]);
+
+        let _ = board.transaction(|tx| -> Result<(), TransactionError> {
+            tx.set_piece(Pos::new(0, 0), Piece::Empty)?;
+            assert_eq!(Piece::Empty, tx.board().piece(Pos::new(0, 0)));
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,261 @@
+use crate::bitboard::BitBoard;
+use crate::piece::PieceType;
+
+use std::collections::HashMap;
+use std::ops::Index;
+
+/// A compact identifier for a piece type on one board. IDs start at one, in
+/// the order that the types were first placed on the board, and never change
+/// while the board exists. Zero is never a type's ID.
+pub type TypeId = u16;
+
+/// The bitboard of each piece type on a board, stored in a list indexed by the
+/// types' compact IDs. Looking up a type's ID is the only hashed operation, so
+/// iterating over every type's bitboard stays fast on boards with many types.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct TypeTable {
+    ids: HashMap<PieceType, TypeId>,
+    types: Vec<PieceType>,
+    boards: Vec<BitBoard>
+}
+
+impl TypeTable {
+
+    /// Creates a table without any types.
+    pub(crate) fn new() -> TypeTable {
+        TypeTable::default()
+    }
+
+    /// Gets the ID of a piece type, or None if the type is not in the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type to find the ID of
+    pub(crate) fn id(&self, piece_type: PieceType) -> Option<TypeId> {
+        self.ids.get(&piece_type).copied()
+    }
+
+    /// Gets the piece type with an ID, or None if no type has that ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - the ID of the type
+    pub(crate) fn piece_type(&self, id: TypeId) -> Option<PieceType> {
+        self.types.get(usize::from(id).checked_sub(1)?).copied()
+    }
+
+    /// Gets every type in the table, ordered by ID.
+    pub(crate) fn types(&self) -> &[PieceType] {
+        &self.types
+    }
+
+    /// Gets the number of types in the table.
+    pub(crate) fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    /// Gets the bitboard of a piece type, or None if the type is not in the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type whose bitboard to get
+    pub(crate) fn get(&self, piece_type: &PieceType) -> Option<&BitBoard> {
+        self.id(*piece_type).map(|id| &self.boards[usize::from(id) - 1])
+    }
+
+    /// Gets the bitboard of a piece type to change it, or None if the type is
+    /// not in the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type whose bitboard to get
+    pub(crate) fn get_mut(&mut self, piece_type: &PieceType) -> Option<&mut BitBoard> {
+        let id = self.id(*piece_type)?;
+        Some(&mut self.boards[usize::from(id) - 1])
+    }
+
+    /// Gets the bitboard of the type with an ID to change it.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - the ID of the type whose bitboard to get
+    ///
+    /// # Panics
+    ///
+    /// Panics if no type has the ID.
+    pub(crate) fn board_mut(&mut self, id: TypeId) -> &mut BitBoard {
+        usize::from(id).checked_sub(1)
+            .and_then(move |index| self.boards.get_mut(index))
+            .unwrap_or_else(|| panic!("No piece type has ID {}", id))
+    }
+
+    /// Gets the ID of a piece type, adding the type with an empty bitboard if
+    /// it is not in the table yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type to find the ID of
+    /// * `width` - the width of the bitboard for a new type
+    /// * `height` - the height of the bitboard for a new type
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type is new and the table already has the maximum number of types.
+    pub(crate) fn id_or_insert(&mut self, piece_type: PieceType, width: u8, height: u8) -> TypeId {
+        if let Some(id) = self.id(piece_type) {
+            return id;
+        }
+
+        if self.types.len() >= usize::from(TypeId::MAX) {
+            panic!("Tried to add more than {} piece types to the board", TypeId::MAX);
+        }
+
+        self.types.push(piece_type);
+        self.boards.push(BitBoard::new(width, height));
+        let id = self.types.len() as TypeId;
+        self.ids.insert(piece_type, id);
+        id
+    }
+
+    /// Replaces the bitboard of a piece type, adding the type if it is not in
+    /// the table yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `piece_type` - the type whose bitboard to replace
+    /// * `board` - the new bitboard of the type
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type is new and the table already has the maximum number of types.
+    pub(crate) fn insert(&mut self, piece_type: PieceType, board: BitBoard) {
+        let id = self.id_or_insert(piece_type, board.width(), board.height());
+        self.boards[usize::from(id) - 1] = board;
+    }
+
+    /// Gets an iterator over every type and its bitboard, ordered by ID.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&PieceType, &BitBoard)> {
+        self.types.iter().zip(self.boards.iter())
+    }
+
+    /// Gets an iterator over every type, ordered by ID.
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &PieceType> {
+        self.types.iter()
+    }
+
+    /// Gets an iterator over every type's bitboard, ordered by ID.
+    pub(crate) fn values(&self) -> impl Iterator<Item = &BitBoard> {
+        self.boards.iter()
+    }
+
+    /// Creates a table with the same types and IDs whose bitboards are
+    /// replaced by a function of the original bitboards.
+    ///
+    /// # Arguments
+    ///
+    /// * `map_board` - creates a new bitboard from a type's bitboard
+    pub(crate) fn map_boards(&self, map_board: impl FnMut(&BitBoard) -> BitBoard) -> TypeTable {
+        TypeTable {
+            ids: self.ids.clone(),
+            types: self.types.clone(),
+            boards: self.boards.iter().map(map_board).collect()
+        }
+    }
+
+}
+
+impl Index<&PieceType> for TypeTable {
+    type Output = BitBoard;
+
+    fn index(&self, piece_type: &PieceType) -> &BitBoard {
+        self.get(piece_type).expect("Piece type is not in the type table")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitboard::BitBoard;
+    use crate::position::Pos;
+    use crate::types::TypeTable;
+
+    #[test]
+    fn id_or_insert_ids_start_at_one_in_order() {
+        let mut table = TypeTable::new();
+
+        assert_eq!(1, table.id_or_insert('f', 4, 4));
+        assert_eq!(2, table.id_or_insert('s', 4, 4));
+        assert_eq!(1, table.id_or_insert('f', 4, 4));
+        assert_eq!(vec!['f', 's'], table.types().to_vec());
+    }
+
+    #[test]
+    fn piece_type_unknown_or_zero_id_none() {
+        let mut table = TypeTable::new();
+        table.id_or_insert('f', 4, 4);
+
+        assert_eq!(Some('f'), table.piece_type(1));
+        assert_eq!(None, table.piece_type(0));
+        assert_eq!(None, table.piece_type(2));
+    }
+
+    #[test]
+    fn insert_existing_type_board_replaced() {
+        let mut table = TypeTable::new();
+        table.id_or_insert('f', 4, 4);
+        let mut board = BitBoard::new(4, 4);
+        board.set(Pos::new(1, 2));
+
+        table.insert('f', board.clone());
+
+        assert_eq!(Some(&board), table.get(&'f'));
+        assert_eq!(1, table.len());
+    }
+
+    #[test]
+    fn board_mut_new_type_board_changed() {
+        let mut table = TypeTable::new();
+        let id = table.id_or_insert('f', 4, 4);
+
+        table.board_mut(id).set(Pos::new(1, 2));
+
+        assert!(table[&'f'].is_set(Pos::new(1, 2)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn board_mut_zero_id_panics() {
+        let mut table = TypeTable::new();
+        table.id_or_insert('f', 4, 4);
+
+        table.board_mut(0);
+    }
+
+    #[test]
+    fn get_unknown_type_none() {
+        assert_eq!(None, TypeTable::new().get(&'f'));
+    }
+
+    #[test]
+    fn map_boards_ids_kept() {
+        let mut table = TypeTable::new();
+        table.id_or_insert('f', 4, 4);
+        table.id_or_insert('s', 4, 4);
+
+        let mapped = table.map_boards(|_| BitBoard::new(2, 2));
+
+        assert_eq!(Some(2), mapped.id('s'));
+        assert_eq!(2, mapped.get(&'s').unwrap().width());
+    }
+
+    #[test]
+    fn id_or_insert_many_types_supported() {
+        let mut table = TypeTable::new();
+        for index in 0..1000u32 {
+            table.id_or_insert(std::char::from_u32(0x100 + index).unwrap(), 1, 1);
+        }
+
+        assert_eq!(1000, table.len());
+        assert_eq!(Some(1000), table.id('\u{4e7}'));
+    }
+}
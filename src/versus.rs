@@ -0,0 +1,376 @@
+use crate::board::Board;
+use crate::cascade::Spawner;
+use crate::events::BoardEvent;
+use crate::levels::has_matching_swap;
+use crate::piece::Piece;
+use crate::position::Pos;
+
+/// Whether a two-player game has a winner yet.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VersusStatus {
+
+    /// Neither player has won yet.
+    InProgress,
+
+    /// The player with this index (0 or 1) won.
+    Won(usize)
+
+}
+
+/// A competitive game where two players take turns swapping pieces.
+///
+/// Each player scores one point for every piece their matches clear,
+/// including pieces cleared by the cascades their swaps cause. The first
+/// player to reach the target score wins.
+///
+/// When each player has their own board, matches with at least a minimum
+/// number of pieces send garbage to the opponent: one garbage piece for a
+/// match of the minimum size, and one more for each additional piece. Before
+/// a player's turn, their pending garbage replaces randomly chosen regular
+/// pieces on their board. A player whose board has no swap that causes a
+/// match after the garbage arrives, or who receives more garbage than they
+/// have regular pieces, loses.
+///
+/// When both players share one board, no garbage is sent, and a player who
+/// has no swap that causes a match on their turn loses.
+pub struct Versus {
+    boards: Vec<Board>,
+    spawners: Vec<Box<dyn Spawner>>,
+    current_player: usize,
+    scores: [u64; 2],
+    pending_garbage: [usize; 2],
+    garbage_piece: Piece,
+    min_garbage_size: usize,
+    target_score: u64,
+    status: VersusStatus
+}
+
+impl Versus {
+
+    /// Creates a new game where each player has their own board. Player 0 goes
+    /// first. Matches with at least four pieces send walls as garbage by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `boards` - the board for each player
+    /// * `spawners` - chooses the new pieces that fill each player's board
+    /// * `target_score` - the score needed to win
+    pub fn new(boards: [Board; 2], spawners: [Box<dyn Spawner>; 2], target_score: u64) -> Versus {
+        let [first_board, second_board] = boards;
+        let [first_spawner, second_spawner] = spawners;
+        Versus::with_boards(vec![first_board, second_board], vec![first_spawner, second_spawner], target_score)
+    }
+
+    /// Creates a new game where both players take turns on the same board.
+    /// Player 0 goes first.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - the board both players play on
+    /// * `spawner` - chooses the new pieces that fill the board
+    /// * `target_score` - the score needed to win
+    pub fn shared(board: Board, spawner: Box<dyn Spawner>, target_score: u64) -> Versus {
+        Versus::with_boards(vec![board], vec![spawner], target_score)
+    }
+
+    /// Changes the garbage that big matches send to the opponent.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_size` - the number of pieces a match needs to send garbage
+    /// * `piece` - the piece that is placed on the opponent's board for each
+    ///   garbage piece
+    pub fn with_garbage(mut self, min_size: usize, piece: Piece) -> Versus {
+        self.min_garbage_size = min_size;
+        self.garbage_piece = piece;
+        self
+    }
+
+    /// Gets the index (0 or 1) of the player whose turn it is.
+    pub fn current_player(&self) -> usize {
+        self.current_player
+    }
+
+    /// Gets the board a player plays on.
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - the index of the player (0 or 1)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the player index is not 0 or 1.
+    pub fn board(&self, player: usize) -> &Board {
+        &self.boards[self.board_index(player)]
+    }
+
+    /// Gets a player's score.
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - the index of the player (0 or 1)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the player index is not 0 or 1.
+    pub fn score(&self, player: usize) -> u64 {
+        self.scores[player]
+    }
+
+    /// Gets the number of garbage pieces that will be placed on a player's
+    /// board before their next turn.
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - the index of the player (0 or 1)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the player index is not 0 or 1.
+    pub fn pending_garbage(&self, player: usize) -> usize {
+        self.pending_garbage[player]
+    }
+
+    /// Gets whether a player has won.
+    pub fn status(&self) -> VersusStatus {
+        self.status
+    }
+
+    /// Swaps two pieces on the current player's board, resolves the matches
+    /// that the swap causes, and ends the player's turn. Returns the number
+    /// of matches that were cleared, or None if the swap was rejected.
+    ///
+    /// The swap is rejected if the game is over or if the board's swap rules
+    /// do not allow it. A rejected swap does not end the player's turn.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - the first position of a piece to swap
+    /// * `second` - the second position of a piece to swap
+    ///
+    /// # Panics
+    ///
+    /// Panics if either position is outside the board.
+    pub fn swap_pieces(&mut self, first: Pos, second: Pos) -> Option<usize> {
+        let player = self.current_player;
+        let board_index = self.board_index(player);
+        if self.status != VersusStatus::InProgress || !self.boards[board_index].swap_pieces(first, second) {
+            return None;
+        }
+
+        let mut cleared = 0;
+        let mut garbage = 0;
+        let min_garbage_size = self.min_garbage_size;
+        let matches = self.boards[board_index].resolve_cascade(
+            self.spawners[board_index].as_mut(),
+            &mut |event: &BoardEvent| match event {
+                BoardEvent::Matched { positions, .. } if positions.len() >= min_garbage_size =>
+                    garbage += positions.len() - min_garbage_size + 1,
+                BoardEvent::Cleared { .. } => cleared += 1,
+                _ => {}
+            }
+        );
+
+        self.scores[player] += cleared;
+        if self.scores[player] >= self.target_score {
+            self.status = VersusStatus::Won(player);
+            return Some(matches);
+        }
+
+        let opponent = 1 - player;
+        if self.boards.len() > 1 {
+            self.pending_garbage[opponent] += garbage;
+        }
+        self.current_player = opponent;
+        self.start_turn();
+
+        Some(matches)
+    }
+
+    /// Creates a new game with one board or a board for each player.
+    ///
+    /// # Arguments
+    ///
+    /// * `boards` - one shared board or one board for each player
+    /// * `spawners` - one spawner for each board
+    /// * `target_score` - the score needed to win
+    fn with_boards(boards: Vec<Board>, spawners: Vec<Box<dyn Spawner>>, target_score: u64) -> Versus {
+        Versus {
+            boards,
+            spawners,
+            current_player: 0,
+            scores: [0; 2],
+            pending_garbage: [0; 2],
+            garbage_piece: Piece::Wall,
+            min_garbage_size: 4,
+            target_score,
+            status: VersusStatus::InProgress
+        }
+    }
+
+    /// Gets the index of a player's board.
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - the index of the player (0 or 1)
+    fn board_index(&self, player: usize) -> usize {
+        if player > 1 {
+            panic!("Tried to get board for player {} in a two-player game", player);
+        }
+
+        player.min(self.boards.len() - 1)
+    }
+
+    /// Places the current player's pending garbage on their board and checks
+    /// whether they can still make a match.
+    fn start_turn(&mut self) {
+        let player = self.current_player;
+        let board_index = self.board_index(player);
+        let board = &mut self.boards[board_index];
+
+        let mut targets: Vec<Pos> = (0..board.state().height)
+            .flat_map(|y| (0..board.state().width).map(move |x| Pos::new(x, y)))
+            .filter(|&pos| matches!(board.piece(pos), Piece::Regular(..)))
+            .collect();
+
+        let garbage = self.pending_garbage[player];
+        self.pending_garbage[player] = 0;
+        let overflowed = garbage > targets.len();
+
+        for placed in 0..garbage.min(targets.len()) {
+            let index = placed + board.rng_mut().next_below(targets.len() - placed);
+            targets.swap(placed, index);
+            board.set_piece(targets[placed], self.garbage_piece);
+        }
+
+        if overflowed || !has_matching_swap(board) {
+            self.status = VersusStatus::Won(1 - player);
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::test_support::board_from_rows;
+    use crate::versus::{Versus, VersusStatus};
+
+    /// Creates a game on two 5x3 boards where swapping (2, 0) and (2, 1)
+    /// makes five 'a' pieces in a row, and swapping (2, 2) and (3, 2) makes
+    /// three 'c' pieces in a row.
+    fn versus(target_score: u64) -> Versus {
+        Versus::new(
+            [
+                board_from_rows(&["ccdcd", "dbadb", "aabaa"], &["aaaaa\nr = 5", "aaa\nr = 3", "ccc\nr = 3"]),
+                board_from_rows(&["ccdcd", "dbadb", "aabaa"], &["aaaaa\nr = 5", "aaa\nr = 3", "ccc\nr = 3"])
+            ],
+            [
+                Box::new(|_: &Board, _| Piece::Regular('e', ALL_DIRECTIONS)),
+                Box::new(|_: &Board, _| Piece::Regular('e', ALL_DIRECTIONS))
+            ],
+            target_score
+        )
+    }
+
+    #[test]
+    fn new_first_player_starts() {
+        let versus = versus(10);
+
+        assert_eq!(0, versus.current_player());
+        assert_eq!(VersusStatus::InProgress, versus.status());
+    }
+
+    #[test]
+    fn swap_pieces_turn_passes_to_opponent() {
+        let mut versus = versus(10);
+
+        assert_eq!(Some(1), versus.swap_pieces(Pos::new(2, 2), Pos::new(3, 2)));
+
+        assert_eq!(1, versus.current_player());
+        assert_eq!(3, versus.score(0));
+        assert_eq!(0, versus.score(1));
+    }
+
+    #[test]
+    fn swap_pieces_rejected_turn_not_used() {
+        let mut versus = versus(10);
+        versus.boards[0] = board_from_rows(&["cc#cd", "dbadb", "aabaa"], &["aaaaa\nr = 5", "aaa\nr = 3", "ccc\nr = 3"]);
+
+        assert_eq!(None, versus.swap_pieces(Pos::new(2, 2), Pos::new(3, 2)));
+        assert_eq!(0, versus.current_player());
+    }
+
+    #[test]
+    fn swap_pieces_big_match_sends_garbage() {
+        let mut versus = versus(10).with_garbage(4, Piece::Wall);
+
+        versus.swap_pieces(Pos::new(2, 0), Pos::new(2, 1));
+
+        assert_eq!(0, versus.pending_garbage(1));
+        let walls = (0..5).flat_map(|x| (0..3).map(move |y| Pos::new(x, y)))
+            .filter(|&pos| versus.board(1).piece(pos) == Piece::Wall)
+            .count();
+        assert_eq!(2, walls);
+    }
+
+    #[test]
+    fn swap_pieces_small_match_no_garbage() {
+        let mut versus = versus(10).with_garbage(4, Piece::Wall);
+
+        versus.swap_pieces(Pos::new(2, 2), Pos::new(3, 2));
+
+        let walls = (0..5).flat_map(|x| (0..3).map(move |y| Pos::new(x, y)))
+            .filter(|&pos| versus.board(1).piece(pos) == Piece::Wall)
+            .count();
+        assert_eq!(0, walls);
+    }
+
+    #[test]
+    fn swap_pieces_target_score_reached_won() {
+        let mut versus = versus(3);
+
+        versus.swap_pieces(Pos::new(2, 2), Pos::new(3, 2));
+
+        assert_eq!(VersusStatus::Won(0), versus.status());
+        assert_eq!(None, versus.swap_pieces(Pos::new(2, 2), Pos::new(3, 2)));
+    }
+
+    #[test]
+    fn swap_pieces_garbage_overflow_opponent_loses() {
+        let mut versus = versus(100);
+        versus.pending_garbage[1] = 15;
+
+        versus.swap_pieces(Pos::new(2, 0), Pos::new(2, 1));
+
+        assert_eq!(VersusStatus::Won(0), versus.status());
+    }
+
+    #[test]
+    fn shared_board_players_alternate_on_same_board() {
+        let board = board_from_rows(&["ccdcd", "dbadb", "aabaa"], &["aaaaa\nr = 5", "aaa\nr = 3", "ccc\nr = 3"]);
+        let mut versus = Versus::shared(board, Box::new(|_: &Board, _| Piece::Regular('e', ALL_DIRECTIONS)), 10);
+
+        versus.swap_pieces(Pos::new(2, 2), Pos::new(3, 2));
+
+        assert_eq!(1, versus.current_player());
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), versus.board(1).piece(Pos::new(0, 0)));
+        assert_eq!(Some(1), versus.swap_pieces(Pos::new(2, 0), Pos::new(2, 1)));
+        assert_eq!(5, versus.score(1));
+        assert_eq!(0, versus.pending_garbage(0));
+    }
+
+    #[test]
+    fn shared_board_no_match_left_next_player_loses() {
+        let board = board_from_rows(&["ccdcd", "dbadb", "aabaa"], &["aaaaa\nr = 5", "aaa\nr = 3", "ccc\nr = 3"]);
+        let mut versus = Versus::shared(board, Box::new(|_: &Board, _| Piece::Regular('e', ALL_DIRECTIONS)), 100);
+
+        versus.swap_pieces(Pos::new(2, 2), Pos::new(3, 2));
+        versus.swap_pieces(Pos::new(2, 0), Pos::new(2, 1));
+
+        assert_eq!(VersusStatus::Won(1), versus.status());
+    }
+}
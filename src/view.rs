@@ -0,0 +1,206 @@
+use crate::bitboard::BitBoard;
+use crate::board::{Board, BoardState};
+use crate::matching::{Match, MatchOrder};
+use crate::piece::{Direction, Piece, PieceType};
+use crate::position::Pos;
+
+use std::collections::{HashMap, HashSet};
+
+/// A read-only view of a [Board]. Swap rules and other code that should only
+/// inspect the board receive a view, which makes it clear that they cannot
+/// change the board.
+///
+/// Views are cheap to copy. Each method behaves identically to the [Board]
+/// method with the same name.
+#[derive(Copy, Clone)]
+pub struct BoardView<'a> {
+    board: &'a Board
+}
+
+impl<'a> BoardView<'a> {
+
+    /// Creates a new view of a board.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - the board to view
+    pub fn new(board: &'a Board) -> BoardView<'a> {
+        BoardView { board }
+    }
+
+    /// Gets the width of the board.
+    pub fn width(&self) -> u8 {
+        self.board.state().width
+    }
+
+    /// Gets the height of the board.
+    pub fn height(&self) -> u8 {
+        self.board.state().height
+    }
+
+    /// See [Board::state()].
+    pub fn state(&self) -> &'a BoardState {
+        self.board.state()
+    }
+
+    /// See [Board::match_order()].
+    pub fn match_order(&self) -> MatchOrder {
+        self.board.match_order()
+    }
+
+    /// See [Board::piece()].
+    pub fn piece(&self, pos: Pos) -> Piece {
+        self.board.piece(pos)
+    }
+
+    /// See [Board::type_bitboard()].
+    pub fn type_bitboard(&self, piece_type: PieceType) -> Option<&'a BitBoard> {
+        self.board.type_bitboard(piece_type)
+    }
+
+    /// See [Board::empty_bitboard()].
+    pub fn empty_bitboard(&self) -> &'a BitBoard {
+        self.board.empty_bitboard()
+    }
+
+    /// See [Board::movable_in()].
+    pub fn movable_in(&self, direction: Direction) -> &'a BitBoard {
+        self.board.movable_in(direction)
+    }
+
+    /// See [Board::gravity_movable_in()].
+    pub fn gravity_movable_in(&self, direction: Direction) -> &'a BitBoard {
+        self.board.gravity_movable_in(direction)
+    }
+
+    /// See [Board::count()].
+    pub fn count(&self, piece_type: PieceType) -> usize {
+        self.board.count(piece_type)
+    }
+
+    /// See [Board::count_empty()].
+    pub fn count_empty(&self) -> usize {
+        self.board.count_empty()
+    }
+
+    /// See [Board::counts()].
+    pub fn counts(&self) -> HashMap<PieceType, usize> {
+        self.board.counts()
+    }
+
+    /// See [Board::pieces_in_rect()].
+    pub fn pieces_in_rect(&self, from: Pos, to: Pos) -> impl Iterator<Item = (Pos, Piece)> + 'a {
+        self.board.pieces_in_rect(from, to)
+    }
+
+    /// See [Board::pieces_within()].
+    pub fn pieces_within(&self, pos: Pos, radius: u8) -> impl Iterator<Item = (Pos, Piece)> + 'a {
+        self.board.pieces_within(pos, radius)
+    }
+
+    /// See [Board::has_tag()].
+    pub fn has_tag(&self, piece_type: PieceType, tag: &str) -> bool {
+        self.board.has_tag(piece_type, tag)
+    }
+
+    /// See [Board::pieces_with_tag()].
+    pub fn pieces_with_tag(&self, tag: &str) -> BitBoard {
+        self.board.pieces_with_tag(tag)
+    }
+
+    /// See [Board::is_ingredient()].
+    pub fn is_ingredient(&self, piece_type: PieceType) -> bool {
+        self.board.is_ingredient(piece_type)
+    }
+
+    /// See [Board::ingredients()].
+    pub fn ingredients(&self) -> &'a HashSet<PieceType> {
+        self.board.ingredients()
+    }
+
+    /// See [Board::exits()].
+    pub fn exits(&self) -> &'a BitBoard {
+        self.board.exits()
+    }
+
+    /// See [Board::is_locked()].
+    pub fn is_locked(&self, pos: Pos) -> bool {
+        self.board.is_locked(pos)
+    }
+
+    /// See [Board::is_staging()].
+    pub fn is_staging(&self, pos: Pos) -> bool {
+        self.board.is_staging(pos)
+    }
+
+    /// See [Board::visible_height()].
+    pub fn visible_height(&self) -> u8 {
+        self.board.visible_height()
+    }
+
+    /// See [Board::portal()].
+    pub fn portal(&self, exit: Pos) -> Option<Pos> {
+        self.board.portal(exit)
+    }
+
+    /// See [Board::preview_swap()].
+    pub fn preview_swap(&self, first: Pos, second: Pos) -> Option<Vec<Match<'a>>> {
+        self.board.preview_swap(first, second)
+    }
+
+    /// Gets the board that this view reads from.
+    pub(crate) fn board(&self) -> &'a Board {
+        self.board
+    }
+
+}
+
+impl<'a> From<&'a Board> for BoardView<'a> {
+    fn from(board: &'a Board) -> Self {
+        BoardView::new(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, BoardState};
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::view::BoardView;
+
+    #[test]
+    fn new_view_has_board_size() {
+        let board = Board::new(BoardState::new(4, 3), Vec::new(), Vec::new());
+        let view = BoardView::new(&board);
+
+        assert_eq!(4, view.width());
+        assert_eq!(3, view.height());
+    }
+
+    #[test]
+    fn view_reads_pieces_and_counts() {
+        let mut board = Board::new(BoardState::new(4, 3), Vec::new(), Vec::new());
+        board.set_piece(Pos::new(1, 2), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(2, 2), Piece::Empty);
+        let view = board.view();
+
+        assert_eq!(Piece::Regular('f', ALL_DIRECTIONS), view.piece(Pos::new(1, 2)));
+        assert_eq!(1, view.count('f'));
+        assert_eq!(1, view.count_empty());
+        assert_eq!(2, view.pieces_in_rect(Pos::new(0, 2), Pos::new(3, 2))
+            .filter(|&(_, piece)| piece != Piece::Wall)
+            .count());
+    }
+
+    #[test]
+    fn swap_rule_receives_view() {
+        let mut board = Board::new(BoardState::new(4, 3), Vec::new(), vec![
+            Box::new(|view: &BoardView, first, _| view.piece(first) != Piece::Regular('s', ALL_DIRECTIONS))
+        ]);
+        board.set_piece(Pos::new(0, 0), Piece::Regular('f', ALL_DIRECTIONS));
+        board.set_piece(Pos::new(1, 0), Piece::Regular('s', ALL_DIRECTIONS));
+
+        assert!(!board.swap_pieces(Pos::new(1, 0), Pos::new(0, 0)));
+        assert!(board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+    }
+}
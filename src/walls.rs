@@ -0,0 +1,313 @@
+use crate::board::{Board, PosSet};
+use crate::events::{BoardEvent, BoardObserver, ClearCause};
+use crate::piece::Piece;
+use crate::position::Pos;
+
+/// A unique category for walls, registered with
+/// [register_wall_type()](Board::register_wall_type).
+pub type WallType = char;
+
+/// How a registered type of wall behaves.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WallKind {
+
+    /// A wall that is never damaged, such as scenery. It only differs from an
+    /// ordinary wall in its type.
+    Decorative,
+
+    /// A wall, such as a crate, that loses one hit point each time a match
+    /// is cleared next to it and is removed when it has none left.
+    Breakable {
+        hit_points: u32
+    }
+
+}
+
+impl Board {
+
+    /// Registers a type of wall so that walls of that type can be placed with
+    /// [place_wall()](Board::place_wall). Registering a type again changes
+    /// the kind of walls placed afterward, but walls already on the board keep
+    /// their hit points.
+    ///
+    /// # Arguments
+    ///
+    /// * `wall_type` - the type of wall to register
+    /// * `kind` - how walls of the type behave
+    pub fn register_wall_type(&mut self, wall_type: WallType, kind: WallKind) {
+        self.state_mut().wall_kinds.insert(wall_type, kind);
+    }
+
+    /// Gets how walls of a registered type behave, if the type is registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `wall_type` - the type of wall to check
+    pub fn wall_kind(&self, wall_type: WallType) -> Option<WallKind> {
+        self.state().wall_kinds.get(&wall_type).copied()
+    }
+
+    /// Places a wall of a registered type. A breakable wall starts with the
+    /// hit points of its type. The wall acts like any other wall, and replacing
+    /// it with [set_piece()](Board::set_piece) removes its type.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the wall
+    /// * `wall_type` - the type of the wall
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided position is outside the board or the type is
+    /// not registered.
+    pub fn place_wall(&mut self, pos: Pos, wall_type: WallType) {
+        let hit_points = match self.wall_kind(wall_type) {
+            Some(WallKind::Breakable { hit_points }) => hit_points,
+            Some(WallKind::Decorative) => 0,
+            None => panic!("Tried to place unregistered wall type: {}", wall_type)
+        };
+
+        self.set_piece(pos, Piece::Wall);
+        self.state_mut().walls.insert(pos, (wall_type, hit_points));
+    }
+
+    /// Gets the type of the wall at a position, if it was placed with
+    /// [place_wall()](Board::place_wall).
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the wall
+    pub fn wall_type(&self, pos: Pos) -> Option<WallType> {
+        self.state().walls.get(&pos).map(|&(wall_type, _)| wall_type)
+    }
+
+    /// Gets the hit points left for the breakable wall at a position, if there
+    /// is one.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the wall
+    pub fn wall_hit_points(&self, pos: Pos) -> Option<u32> {
+        let &(wall_type, hit_points) = self.state().walls.get(&pos)?;
+        match self.wall_kind(wall_type)? {
+            WallKind::Breakable { .. } => Some(hit_points),
+            WallKind::Decorative => None
+        }
+    }
+
+    /// Damages every breakable wall and [obstacle](Board::set_obstacle)
+    /// orthogonally adjacent to a group of positions. Walls lose one hit point
+    /// and are removed when they have none left, leaving empty spaces. Each
+    /// wall or obstacle is damaged at most once, from the bottom row up.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - the positions that were cleared
+    /// * `observer` - receives an event for each damaged or destroyed wall or obstacle
+    pub(crate) fn damage_obstacles_next_to(&mut self, positions: &PosSet, observer: &mut dyn BoardObserver) {
+        if self.state().walls.is_empty() && self.obstacles().is_empty() {
+            return;
+        }
+
+        let is_obstacle = |board: &Board, pos: Pos| match board.piece(pos) {
+            Piece::Regular(piece_type, _) => board.obstacles().contains_key(&piece_type),
+            Piece::Wall => board.wall_hit_points(pos).is_some(),
+            Piece::Empty => false
+        };
+        let cleared = self.region_board(positions);
+        let mut next_to = cleared.clone();
+        next_to.dilate();
+        next_to.difference(&cleared);
+        let adjacent: Vec<Pos> = next_to.iter_set().filter(|&pos| is_obstacle(self, pos)).collect();
+
+        for pos in adjacent {
+            match self.piece(pos) {
+                Piece::Regular(piece_type, directions) => match self.obstacles()[&piece_type] {
+                    Some(damaged_type) => {
+                        let to = Piece::Regular(damaged_type, directions);
+                        let from = self.set_piece(pos, to);
+                        observer.on_event(&BoardEvent::ObstacleDamaged { pos, from, to });
+                    },
+                    None => {
+                        let previous_cause = self.set_clear_cause(ClearCause::AdjacentMatch);
+                        let piece = self.set_piece(pos, Piece::Empty);
+                        self.set_clear_cause(previous_cause);
+                        observer.on_event(&BoardEvent::ObstacleDestroyed { pos, piece });
+                    }
+                },
+                _ => self.damage_wall(pos, observer)
+            }
+        }
+    }
+
+    /// Takes one hit point from the breakable wall at a position and removes
+    /// the wall if it has none left.
+    ///
+    /// # Arguments
+    ///
+    /// * `pos` - the position of the wall
+    /// * `observer` - receives an event for the damaged or destroyed wall
+    fn damage_wall(&mut self, pos: Pos, observer: &mut dyn BoardObserver) {
+        let (wall_type, hit_points) = self.state().walls[&pos];
+        let hit_points = hit_points.saturating_sub(1);
+
+        if hit_points == 0 {
+            self.set_piece(pos, Piece::Empty);
+            observer.on_event(&BoardEvent::WallDestroyed { pos, wall_type });
+        } else {
+            self.state_mut().walls.insert(pos, (wall_type, hit_points));
+            observer.on_event(&BoardEvent::WallDamaged { pos, wall_type, hit_points });
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::PosSet;
+    use crate::events::{BoardEvent, ClearCause, Effect};
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::test_support::board_from_rows;
+    use crate::walls::WallKind;
+
+    fn positions(positions: &[(u8, u8)]) -> PosSet {
+        positions.iter().map(|&(x, y)| Pos::new(x, y)).collect()
+    }
+
+    #[test]
+    fn place_wall_breakable_starts_with_type_hit_points() {
+        let mut board = board_from_rows(&["aaa", "aaa", "aaa"], &[]);
+        board.register_wall_type('c', WallKind::Breakable { hit_points: 2 });
+
+        board.place_wall(Pos::new(1, 1), 'c');
+
+        assert_eq!(Piece::Wall, board.piece(Pos::new(1, 1)));
+        assert_eq!(Some('c'), board.wall_type(Pos::new(1, 1)));
+        assert_eq!(Some(2), board.wall_hit_points(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn place_wall_decorative_no_hit_points() {
+        let mut board = board_from_rows(&["aaa", "aaa", "aaa"], &[]);
+        board.register_wall_type('d', WallKind::Decorative);
+
+        board.place_wall(Pos::new(1, 1), 'd');
+
+        assert_eq!(Some('d'), board.wall_type(Pos::new(1, 1)));
+        assert_eq!(None, board.wall_hit_points(Pos::new(1, 1)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn place_wall_unregistered_type_panics() {
+        board_from_rows(&["aaa", "aaa", "aaa"], &[]).place_wall(Pos::new(1, 1), 'z');
+    }
+
+    #[test]
+    fn set_piece_wall_type_removed() {
+        let mut board = board_from_rows(&["aaa", "aaa", "aaa"], &[]);
+        board.register_wall_type('c', WallKind::Breakable { hit_points: 2 });
+        board.place_wall(Pos::new(1, 1), 'c');
+
+        board.set_piece(Pos::new(1, 1), Piece::Wall);
+
+        assert_eq!(None, board.wall_type(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn damage_obstacles_next_to_each_wall_damaged_once() {
+        let mut board = board_from_rows(&["aaa", "aaa", "aaa"], &[]);
+        board.register_wall_type('c', WallKind::Breakable { hit_points: 2 });
+        board.register_wall_type('d', WallKind::Decorative);
+        board.place_wall(Pos::new(1, 1), 'c');
+        board.place_wall(Pos::new(2, 2), 'd');
+        let mut events = Vec::new();
+
+        board.damage_obstacles_next_to(&positions(&[(0, 1), (1, 0), (1, 2)]), &mut |event: &BoardEvent| {
+            events.push(event.clone())
+        });
+
+        assert_eq!(vec![BoardEvent::WallDamaged { pos: Pos::new(1, 1), wall_type: 'c', hit_points: 1 }], events);
+        assert_eq!(Some(1), board.wall_hit_points(Pos::new(1, 1)));
+        assert_eq!(Some('d'), board.wall_type(Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn damage_obstacles_next_to_no_hit_points_left_destroyed() {
+        let mut board = board_from_rows(&["aaa", "aaa", "aaa"], &[]);
+        board.register_wall_type('c', WallKind::Breakable { hit_points: 2 });
+        board.place_wall(Pos::new(1, 1), 'c');
+        let mut events = Vec::new();
+
+        for _ in 0..2 {
+            board.damage_obstacles_next_to(&positions(&[(0, 1)]), &mut |event: &BoardEvent| events.push(event.clone()));
+        }
+
+        assert_eq!(Some(&BoardEvent::WallDestroyed { pos: Pos::new(1, 1), wall_type: 'c' }), events.last());
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(None, board.wall_type(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn damage_obstacles_next_to_diagonal_wall_not_damaged() {
+        let mut board = board_from_rows(&["aaa", "aaa", "aaa"], &[]);
+        board.register_wall_type('c', WallKind::Breakable { hit_points: 2 });
+        board.place_wall(Pos::new(1, 1), 'c');
+
+        board.damage_obstacles_next_to(&positions(&[(0, 0)]), &mut |_: &BoardEvent| {});
+
+        assert_eq!(Some(2), board.wall_hit_points(Pos::new(1, 1)));
+    }
+
+    #[test]
+    fn damage_obstacles_next_to_obstacle_becomes_damaged_type() {
+        let mut board = board_from_rows(&["aaa", "aaa", "aaa"], &[]);
+        board.set_piece(Pos::new(1, 1), Piece::Regular('C', enumset::EnumSet::new()));
+        board.set_obstacle('C', Some('c'));
+        board.set_obstacle('c', None);
+        let mut events = Vec::new();
+
+        board.damage_obstacles_next_to(&positions(&[(1, 0), (1, 2)]), &mut |event: &BoardEvent| {
+            events.push(event.clone())
+        });
+
+        let damaged = Piece::Regular('c', enumset::EnumSet::new());
+        assert_eq!(damaged, board.piece(Pos::new(1, 1)));
+        assert_eq!(vec![BoardEvent::ObstacleDamaged {
+            pos: Pos::new(1, 1),
+            from: Piece::Regular('C', enumset::EnumSet::new()),
+            to: damaged
+        }], events);
+    }
+
+    #[test]
+    fn damage_obstacles_next_to_last_hit_destroys_obstacle() {
+        let mut board = board_from_rows(&["aaa", "aaa", "aaa"], &[]);
+        board.set_piece(Pos::new(1, 1), Piece::Regular('c', ALL_DIRECTIONS));
+        board.set_obstacle('c', None);
+        board.set_effects_enabled(true);
+        let mut events = Vec::new();
+
+        board.damage_obstacles_next_to(&positions(&[(0, 1)]), &mut |event: &BoardEvent| events.push(event.clone()));
+
+        assert_eq!(Piece::Empty, board.piece(Pos::new(1, 1)));
+        assert_eq!(vec![BoardEvent::ObstacleDestroyed {
+            pos: Pos::new(1, 1),
+            piece: Piece::Regular('c', ALL_DIRECTIONS)
+        }], events);
+        assert_eq!(&[Effect::Cleared { pos: Pos::new(1, 1), cause: ClearCause::AdjacentMatch }], board.effects());
+    }
+
+    #[test]
+    fn damage_obstacles_next_to_removed_obstacle_not_damaged() {
+        let mut board = board_from_rows(&["aaa", "aaa", "aaa"], &[]);
+        board.set_obstacle('a', None);
+        assert_eq!(Some(None), board.remove_obstacle('a'));
+
+        board.damage_obstacles_next_to(&positions(&[(0, 1)]), &mut |_: &BoardEvent| {});
+
+        assert_eq!(Piece::Regular('a', ALL_DIRECTIONS), board.piece(Pos::new(1, 1)));
+    }
+}
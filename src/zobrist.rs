@@ -0,0 +1,120 @@
+use crate::board::BoardState;
+use crate::piece::Piece;
+use crate::position::Pos;
+use crate::random::Rng;
+
+use enumset::EnumSet;
+
+/// Computes the Zobrist hash of every piece in a board state.
+///
+/// # Arguments
+///
+/// * `state` - the state to hash
+pub(crate) fn compute_zobrist(state: &BoardState) -> u64 {
+    let mut hash = 0;
+
+    for y in 0..state.height {
+        for x in 0..state.width {
+            let pos = Pos::new(x, y);
+            let piece = match state.type_grid[usize::from(y) * usize::from(state.width) + usize::from(x)] {
+                0 if state.empties.is_set(pos) => Piece::Empty,
+                0 => Piece::Wall,
+                id => match state.pieces.piece_type(id) {
+                    Some(piece_type) => Piece::Regular(piece_type, EnumSet::new()),
+                    None => Piece::Wall
+                }
+            };
+            hash ^= zobrist_key(pos, piece);
+        }
+    }
+
+    hash
+}
+
+/// Gets the random value for a piece at a position that is combined into
+/// the Zobrist hash. Values are derived from the position and the piece's
+/// type, so they are the same on every board and platform without a table.
+/// Empty spaces have no value, and movable directions are ignored.
+///
+/// # Arguments
+///
+/// * `pos` - the position of the piece
+/// * `piece` - the piece at the position
+pub(crate) fn zobrist_key(pos: Pos, piece: Piece) -> u64 {
+    let code = match piece {
+        Piece::Empty => return 0,
+        Piece::Wall => 1,
+        Piece::Regular(piece_type, _) => 2 + u64::from(u32::from(piece_type))
+    };
+
+    Rng::new((u64::from(pos.x()) << 56) | (u64::from(pos.y()) << 48) | code).next_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, BoardState};
+    use crate::piece::{Piece, ALL_DIRECTIONS};
+    use crate::position::Pos;
+    use crate::test_support::board_from_rows;
+    use crate::zobrist::compute_zobrist;
+    use enumset::EnumSet;
+
+    #[test]
+    fn zobrist_after_changes_same_as_recomputed() {
+        let mut board = board_from_rows(&["ab a", " ab ", "b ab", "ab a"], &[]);
+
+        board.set_piece(Pos::new(0, 0), Piece::Wall);
+        assert!(board.swap_pieces(Pos::new(1, 1), Pos::new(1, 2)));
+        board.trickle();
+        board.convert_pieces('a', 'c', None);
+        board.rotate_90();
+
+        assert_eq!(compute_zobrist(board.state()), board.zobrist());
+    }
+
+    #[test]
+    fn zobrist_swap_and_swap_back_same_hash() {
+        let mut board = board_from_rows(&["ab a", " ab ", "b ab", "ab a"], &[]);
+        let original = board.zobrist();
+
+        assert!(board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+        assert_ne!(original, board.zobrist());
+        assert!(board.swap_pieces(Pos::new(0, 0), Pos::new(1, 0)));
+
+        assert_eq!(original, board.zobrist());
+    }
+
+    #[test]
+    fn zobrist_same_pieces_different_order_same_hash() {
+        let mut first = Board::new(BoardState::new(2, 1), Vec::new(), Vec::new());
+        first.set_piece(Pos::new(0, 0), Piece::Regular('a', ALL_DIRECTIONS));
+        first.set_piece(Pos::new(1, 0), Piece::Regular('b', ALL_DIRECTIONS));
+        let mut second = Board::new(BoardState::new(2, 1), Vec::new(), Vec::new());
+        second.set_piece(Pos::new(1, 0), Piece::Regular('b', EnumSet::new()));
+        second.set_piece(Pos::new(0, 0), Piece::Regular('a', ALL_DIRECTIONS));
+
+        assert_eq!(first.zobrist(), second.zobrist());
+    }
+
+    #[test]
+    fn zobrist_different_type_different_hash() {
+        let mut board = board_from_rows(&["ab a", " ab ", "b ab", "ab a"], &[]);
+        let original = board.zobrist();
+
+        board.set_piece(Pos::new(2, 0), Piece::Regular('z', ALL_DIRECTIONS));
+
+        assert_ne!(original, board.zobrist());
+    }
+
+    #[test]
+    fn zobrist_restore_snapshot_restores_hash() {
+        let mut board = board_from_rows(&["ab a", " ab ", "b ab", "ab a"], &[]);
+        let original = board.zobrist();
+        let snapshot = board.snapshot();
+
+        board.set_piece(Pos::new(2, 0), Piece::Regular('z', ALL_DIRECTIONS));
+        board.restore(&snapshot);
+
+        assert_eq!(original, board.zobrist());
+    }
+}